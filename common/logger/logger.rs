@@ -6,29 +6,129 @@
 
 #![allow(unexpected_cfgs)]
 
-use tracing::{self, dispatcher::DefaultGuard, metadata::LevelFilter, Level};
+use std::{error::Error, fmt, io, path::PathBuf, str::FromStr, sync::Mutex};
+
+use resource::constants::server::{DEFAULT_LOG_FILES, DEFAULT_LOG_FILE_SIZE, LOG_FILE_NAME};
+use tracing::{self, dispatcher::DefaultGuard, metadata::LevelFilter, Level, Subscriber};
 pub use tracing::{error, info, trace};
-use tracing_subscriber::{fmt::SubscriberBuilder, EnvFilter};
+use tracing_subscriber::{
+    filter::ParseError,
+    fmt::{MakeWriter, SubscriberBuilder},
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    EnvFilter, Layer,
+};
+
+use crate::rotating_file::RotatingFileWriter;
 
 pub mod result;
+pub mod rotating_file;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
 
-pub fn initialise_logging_global() {
-    let filter = EnvFilter::from_default_env()
-        .add_directive(LevelFilter::INFO.into())
-        // .add_directive("database=trace".parse().unwrap())
-        // .add_directive("server=trace".parse().unwrap())
-        // .add_directive("storage=trace".parse().unwrap())
-        // .add_directive("executor=trace".parse().unwrap())
-        // .add_directive("query=trace".parse().unwrap())
-        // useful for debugging what tonic is doing:
-        // .add_directive("tonic=trace".parse().unwrap());
-    ;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("Unrecognised log format '{s}', expected 'text' or 'json'")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    /// Per-module level directives on top of the default INFO level, eg. 'storage=debug' or 'server::service=trace'
+    pub directives: Vec<String>,
+    /// When set, logs are also written to a size-rotated file in this directory
+    pub directory: Option<PathBuf>,
+    pub max_file_size: u64,
+    pub max_files: usize,
+}
 
-    let subscriber = SubscriberBuilder::default().with_max_level(Level::TRACE).with_env_filter(filter).finish();
-    tracing::subscriber::set_global_default(subscriber).unwrap()
+impl LoggingConfig {
+    pub fn new(format: LogFormat, directives: Vec<String>, directory: Option<PathBuf>) -> Self {
+        Self { format, directives, directory, max_file_size: DEFAULT_LOG_FILE_SIZE, max_files: DEFAULT_LOG_FILES }
+    }
+}
+
+pub fn initialise_logging_global(config: &LoggingConfig) -> Result<(), LoggingError> {
+    let mut filter = EnvFilter::from_default_env().add_directive(LevelFilter::INFO.into());
+    // useful for debugging what tonic is doing: 'tonic=trace'
+    for directive in &config.directives {
+        let parsed = directive
+            .parse()
+            .map_err(|source| LoggingError::InvalidDirective { directive: directive.clone(), source })?;
+        filter = filter.add_directive(parsed);
+    }
+
+    let file_layer = match &config.directory {
+        None => None,
+        Some(directory) => {
+            let writer = RotatingFileWriter::open(directory, LOG_FILE_NAME, config.max_file_size, config.max_files)
+                .map_err(|source| LoggingError::CouldNotOpenLogFile { path: directory.join(LOG_FILE_NAME), source })?;
+            Some(format_layer(config.format, Mutex::new(writer), false))
+        }
+    };
+    let subscriber = tracing_subscriber::registry()
+        .with(filter)
+        .with(format_layer(config.format, io::stdout, true))
+        .with(file_layer);
+    tracing::subscriber::set_global_default(subscriber).map_err(|_| LoggingError::AlreadyInitialised {})
+}
+
+fn format_layer<S, W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer).with_ansi(ansi);
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer.json().with_current_span(true).with_span_list(true).boxed(),
+    }
 }
 
 pub fn initialise_logging() -> DefaultGuard {
     let subscriber = SubscriberBuilder::default().with_max_level(Level::TRACE).finish();
     tracing::subscriber::set_default(subscriber)
 }
+
+#[derive(Debug)]
+pub enum LoggingError {
+    InvalidDirective { directive: String, source: ParseError },
+    CouldNotOpenLogFile { path: PathBuf, source: io::Error },
+    AlreadyInitialised {},
+}
+
+impl fmt::Display for LoggingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidDirective { directive, source } => {
+                write!(f, "Invalid logging directive '{directive}': {source}")
+            }
+            Self::CouldNotOpenLogFile { path, source } => {
+                write!(f, "Could not open log file '{}': {source}", path.display())
+            }
+            Self::AlreadyInitialised {} => write!(f, "The global logger has already been initialised"),
+        }
+    }
+}
+
+impl Error for LoggingError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidDirective { source, .. } => Some(source),
+            Self::CouldNotOpenLogFile { source, .. } => Some(source),
+            Self::AlreadyInitialised {} => None,
+        }
+    }
+}
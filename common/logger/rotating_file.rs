@@ -0,0 +1,168 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// An append-only file that is rotated to 'name.1', 'name.2', ... once it would grow past `max_file_size`.
+/// At most `max_files` rotated files are kept. Rotation only happens between writes, so a single write is never split.
+#[derive(Debug)]
+pub struct RotatingFileWriter {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_file_size: u64,
+    max_files: usize,
+}
+
+impl RotatingFileWriter {
+    pub fn open(directory: &Path, file_name: &str, max_file_size: u64, max_files: usize) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+        let path = directory.join(file_name);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file, size, max_file_size, max_files })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // shift 'name.N-1' -> 'name.N', dropping the oldest, then move the active file to 'name.1'
+        for index in (1..self.max_files).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        if self.max_files > 0 {
+            fs::rename(&self.path, self.rotated_path(1))?;
+        } else {
+            fs::remove_file(&self.path)?;
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut file_name = self.path.file_name().unwrap().to_owned();
+        file_name.push(format!(".{index}"));
+        self.path.with_file_name(file_name)
+    }
+}
+
+impl Write for RotatingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_file_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::Write,
+        path::{Path, PathBuf},
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    use super::RotatingFileWriter;
+
+    const FILE_NAME: &str = "test.log";
+
+    struct TestDirectory(PathBuf);
+
+    impl TestDirectory {
+        fn new(name: &str) -> Self {
+            let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+            let path = std::env::temp_dir().join(format!("rotating_file_{name}_{}_{nanos}", std::process::id()));
+            Self(path)
+        }
+
+        fn read(&self, file_name: &str) -> Option<String> {
+            fs::read_to_string(self.0.join(file_name)).ok()
+        }
+    }
+
+    impl Drop for TestDirectory {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn open(directory: &Path, max_file_size: u64, max_files: usize) -> RotatingFileWriter {
+        RotatingFileWriter::open(directory, FILE_NAME, max_file_size, max_files).unwrap()
+    }
+
+    #[test]
+    fn writes_below_the_size_limit_stay_in_the_active_file() {
+        let directory = TestDirectory::new("below_limit");
+        let mut writer = open(&directory.0, 10, 2);
+        writer.write_all(b"hello").unwrap();
+        writer.write_all(b"world").unwrap();
+
+        assert_eq!(directory.read(FILE_NAME).as_deref(), Some("helloworld"));
+        assert_eq!(directory.read("test.log.1"), None);
+    }
+
+    #[test]
+    fn full_files_are_rotated_and_the_oldest_dropped() {
+        let directory = TestDirectory::new("rotation");
+        let mut writer = open(&directory.0, 8, 2);
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            writer.write_all(line.as_bytes()).unwrap();
+        }
+
+        assert_eq!(directory.read(FILE_NAME).as_deref(), Some("fourth\n"));
+        assert_eq!(directory.read("test.log.1").as_deref(), Some("third\n"));
+        assert_eq!(directory.read("test.log.2").as_deref(), Some("second\n"));
+        assert_eq!(directory.read("test.log.3"), None);
+    }
+
+    #[test]
+    fn writes_larger_than_the_limit_are_not_split() {
+        let directory = TestDirectory::new("oversized");
+        let mut writer = open(&directory.0, 4, 1);
+        writer.write_all(b"oversized\n").unwrap();
+        writer.write_all(b"next\n").unwrap();
+
+        assert_eq!(directory.read(FILE_NAME).as_deref(), Some("next\n"));
+        assert_eq!(directory.read("test.log.1").as_deref(), Some("oversized\n"));
+    }
+
+    #[test]
+    fn without_rotated_files_full_files_are_discarded() {
+        let directory = TestDirectory::new("no_rotated_files");
+        let mut writer = open(&directory.0, 6, 0);
+        writer.write_all(b"first\n").unwrap();
+        writer.write_all(b"second\n").unwrap();
+
+        assert_eq!(directory.read(FILE_NAME).as_deref(), Some("second\n"));
+        assert_eq!(directory.read("test.log.1"), None);
+    }
+
+    #[test]
+    fn reopening_appends_and_counts_the_existing_size() {
+        let directory = TestDirectory::new("reopen");
+        open(&directory.0, 10, 1).write_all(b"existing\n").unwrap();
+
+        let mut writer = open(&directory.0, 10, 1);
+        writer.write_all(b"new\n").unwrap();
+
+        assert_eq!(directory.read(FILE_NAME).as_deref(), Some("new\n"));
+        assert_eq!(directory.read("test.log.1").as_deref(), Some("existing\n"));
+    }
+}
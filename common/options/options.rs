@@ -4,12 +4,29 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use resource::constants::server::{DEFAULT_SCHEMA_LOCK_ACQUIRE_TIMEOUT_MILLIS, DEFAULT_TRANSACTION_PARALLEL};
+use std::path::PathBuf;
 
-#[derive(Debug)]
+use resource::constants::{
+    server::{
+        DEFAULT_SCHEMA_LOCK_ACQUIRE_TIMEOUT_MILLIS, DEFAULT_TRANSACTION_COMMIT_RETRY_ATTEMPTS,
+        DEFAULT_TRANSACTION_PARALLEL, DEFAULT_TRANSACTION_SERIALIZABLE,
+    },
+    snapshot::{DEFAULT_WRITE_BUFFER_LIMIT_BYTES, DEFAULT_WRITE_BUFFER_SPILL_BYTES},
+};
+
+#[derive(Debug, Clone)]
 pub struct TransactionOptions {
     pub parallel: bool,
     pub schema_lock_acquire_timeout_millis: u64,
+    /// Buffered writes beyond this size are spilled to disk
+    pub write_buffer_spill_bytes: u64,
+    /// Writes beyond this size fail the transaction, since its commit holds all of its writes in memory
+    pub write_buffer_limit_bytes: u64,
+    /// Write transactions fail to commit if a concurrent commit wrote data they read
+    pub serializable: bool,
+    /// Write transactions whose commit conflicts with a concurrent commit are replayed and committed again up to
+    /// this many times. Only the write queries are replayed, so their answers may differ from those first returned.
+    pub commit_retry_attempts: u32,
 }
 
 impl Default for TransactionOptions {
@@ -17,6 +34,30 @@ impl Default for TransactionOptions {
         Self {
             parallel: DEFAULT_TRANSACTION_PARALLEL,
             schema_lock_acquire_timeout_millis: DEFAULT_SCHEMA_LOCK_ACQUIRE_TIMEOUT_MILLIS,
+            write_buffer_spill_bytes: DEFAULT_WRITE_BUFFER_SPILL_BYTES,
+            write_buffer_limit_bytes: DEFAULT_WRITE_BUFFER_LIMIT_BYTES,
+            serializable: DEFAULT_TRANSACTION_SERIALIZABLE,
+            commit_retry_attempts: DEFAULT_TRANSACTION_COMMIT_RETRY_ATTEMPTS,
         }
     }
 }
+
+#[derive(Debug, Default, Clone)]
+pub struct QueryOptions {
+    pub profile: bool,
+    /// Queries are compiled and answer their plan, without being executed
+    pub explain: bool,
+    /// Queries executing for longer than this are interrupted
+    pub timeout_millis: Option<u64>,
+    /// Queries whose buffers hold more memory than this fail
+    pub memory_limit_bytes: Option<u64>,
+    /// Sorts spill the rows they buffer to disk as configured. Without it, sorts hold every row in memory
+    pub sort_spill: Option<SortSpillOptions>,
+}
+
+/// Where sorts spill their sorted runs, and the size of the runs they spill.
+#[derive(Debug, Clone)]
+pub struct SortSpillOptions {
+    pub directory: PathBuf,
+    pub run_bytes: u64,
+}
@@ -331,7 +331,7 @@ fn annotate_sub_fetch(
 ) -> Result<AnnotatedFetchListSubFetch, AnnotationError> {
     let FetchListSubFetch { context, input_variables, stages, fetch } = sub_fetch;
     let TranslationContext { mut variable_registry, .. } = context;
-    let (annotated_stages, annotated_fetch) = annotate_stages_and_fetch(
+    let (annotated_stages, annotated_fetch, _) = annotate_stages_and_fetch(
         snapshot,
         type_manager,
         annotated_function_signatures,
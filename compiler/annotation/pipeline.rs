@@ -52,6 +52,7 @@ pub struct AnnotatedPipeline {
     pub annotated_preamble: AnnotatedPreambleFunctions,
     pub annotated_stages: Vec<AnnotatedStage>,
     pub annotated_fetch: Option<AnnotatedFetch>,
+    pub annotated_value_types: BTreeMap<Variable, ExpressionValueType>,
 }
 
 #[derive(Debug, Clone)]
@@ -115,7 +116,7 @@ pub fn annotate_preamble_and_pipeline(
             .map_err(|typedb_source| AnnotationError::PreambleTypeInference { typedb_source })?;
     let combined_signature_annotations =
         AnnotatedFunctionSignaturesImpl::new(&schema_function_annotations, &annotated_preamble);
-    let (annotated_stages, annotated_fetch) = annotate_stages_and_fetch(
+    let (annotated_stages, annotated_fetch, annotated_value_types) = annotate_stages_and_fetch(
         snapshot,
         type_manager,
         &combined_signature_annotations,
@@ -126,7 +127,7 @@ pub fn annotate_preamble_and_pipeline(
         BTreeMap::new(),
         BTreeMap::new(),
     )?;
-    Ok(AnnotatedPipeline { annotated_stages, annotated_fetch, annotated_preamble })
+    Ok(AnnotatedPipeline { annotated_stages, annotated_fetch, annotated_preamble, annotated_value_types })
 }
 
 pub(crate) fn annotate_stages_and_fetch(
@@ -139,7 +140,7 @@ pub(crate) fn annotate_stages_and_fetch(
     translated_fetch: Option<FetchObject>,
     input_type_annotations: BTreeMap<Variable, Arc<BTreeSet<Type>>>,
     input_value_type_annotations: BTreeMap<Variable, ExpressionValueType>,
-) -> Result<(Vec<AnnotatedStage>, Option<AnnotatedFetch>), AnnotationError> {
+) -> Result<(Vec<AnnotatedStage>, Option<AnnotatedFetch>, BTreeMap<Variable, ExpressionValueType>), AnnotationError> {
     let (annotated_stages, running_variable_annotations, running_value_variable_types) = annotate_pipeline_stages(
         snapshot,
         type_manager,
@@ -166,7 +167,7 @@ pub(crate) fn annotate_stages_and_fetch(
             Some(annotated?)
         }
     };
-    Ok((annotated_stages, annotated_fetch))
+    Ok((annotated_stages, annotated_fetch, running_value_variable_types))
 }
 
 pub(crate) fn annotate_pipeline_stages(
@@ -0,0 +1,364 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+};
+
+use answer::variable::Variable;
+use ir::pipeline::function_signature::FunctionID;
+use itertools::Itertools;
+
+use crate::{
+    executable::{
+        function::{ExecutableFunction, FunctionTablingType},
+        match_::planner::{
+            function_plan::ExecutableFunctionRegistry,
+            match_executable::{ExecutionStep, MatchExecutable},
+        },
+        pipeline::{ExecutablePipeline, ExecutableStage},
+    },
+    VariablePosition,
+};
+
+/// The compiled plan of a pipeline, without executing it.
+/// All fields are plain data so the plan can be rendered as text or converted into other formats.
+#[derive(Debug, Clone)]
+pub struct PipelinePlan {
+    pub stages: Vec<StagePlan>,
+    pub has_fetch: bool,
+    pub functions: Vec<FunctionPlan>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StagePlan {
+    pub kind: &'static str,
+    pub executable_id: u64,
+    pub details: Vec<String>,
+    pub match_plan: Option<MatchPlan>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchPlan {
+    pub executable_id: u64,
+    pub variables: Vec<VariablePlan>,
+    pub steps: Vec<StepPlan>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VariablePlan {
+    pub position: VariablePosition,
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepPlan {
+    pub index: usize,
+    pub kind: &'static str,
+    pub sort_variable: Option<String>,
+    pub instructions: Vec<String>,
+    pub estimated_rows: Option<f64>,
+    pub estimated_cost: Option<f64>,
+    pub called_function: Option<String>,
+    /// Whether the step reads relations through the role player index rather than their role player edges
+    pub role_player_index: bool,
+    pub nested: Vec<MatchPlan>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionPlan {
+    pub function_id: String,
+    pub is_tabled: bool,
+    pub stages: Vec<StagePlan>,
+}
+
+impl PipelinePlan {
+    pub fn new(pipeline: &ExecutablePipeline, variable_names: &HashMap<Variable, String>) -> Self {
+        let ExecutablePipeline { executable_functions, executable_stages, executable_fetch, .. } = pipeline;
+        let stages = executable_stages.iter().map(|stage| StagePlan::new(stage, variable_names)).collect();
+        let functions = Self::called_functions(executable_stages, executable_functions)
+            .into_iter()
+            .map(|function_id| {
+                let function = executable_functions.get(function_id.clone());
+                FunctionPlan::new(function_id, function)
+            })
+            .collect();
+        Self { stages, has_fetch: executable_fetch.is_some(), functions }
+    }
+
+    fn called_functions(stages: &[ExecutableStage], functions: &ExecutableFunctionRegistry) -> Vec<FunctionID> {
+        let mut seen = HashSet::new();
+        let mut ordered = Vec::new();
+        let mut queue = VecDeque::new();
+        stages_called_functions(stages, &mut queue);
+        while let Some(function_id) = queue.pop_front() {
+            if seen.insert(function_id.clone()) {
+                stages_called_functions(&functions.get(function_id.clone()).executable_stages, &mut queue);
+                ordered.push(function_id);
+            }
+        }
+        ordered
+    }
+}
+
+fn stages_called_functions(stages: &[ExecutableStage], called: &mut VecDeque<FunctionID>) {
+    for stage in stages {
+        if let ExecutableStage::Match(match_executable) = stage {
+            match_called_functions(match_executable, called);
+        }
+    }
+}
+
+fn match_called_functions(match_executable: &MatchExecutable, called: &mut VecDeque<FunctionID>) {
+    for step in match_executable.steps() {
+        match step {
+            ExecutionStep::FunctionCall(step) => called.push_back(step.function_id.clone()),
+            ExecutionStep::Disjunction(step) => {
+                step.branches.iter().for_each(|branch| match_called_functions(branch, called))
+            }
+            ExecutionStep::Negation(step) => match_called_functions(&step.negation, called),
+            ExecutionStep::Optional(step) => match_called_functions(&step.optional, called),
+            ExecutionStep::Intersection(_)
+            | ExecutionStep::UnsortedJoin(_)
+            | ExecutionStep::Assignment(_)
+            | ExecutionStep::Check(_) => (),
+        }
+    }
+}
+
+impl StagePlan {
+    fn new(stage: &ExecutableStage, variable_names: &HashMap<Variable, String>) -> Self {
+        match stage {
+            ExecutableStage::Match(executable) => Self {
+                kind: "match",
+                executable_id: executable.executable_id(),
+                details: Vec::new(),
+                match_plan: Some(MatchPlan::new(executable, variable_names)),
+            },
+            ExecutableStage::Insert(executable) => Self::with_details(
+                "insert",
+                executable.executable_id,
+                executable
+                    .concept_instructions
+                    .iter()
+                    .map(|instruction| instruction.to_string())
+                    .chain(executable.connection_instructions.iter().map(|instruction| instruction.to_string()))
+                    .collect(),
+            ),
+            ExecutableStage::Delete(executable) => Self::with_details(
+                "delete",
+                executable.executable_id,
+                executable
+                    .connection_instructions
+                    .iter()
+                    .map(|instruction| instruction.to_string())
+                    .chain(executable.concept_instructions.iter().map(|instruction| instruction.to_string()))
+                    .collect(),
+            ),
+            ExecutableStage::Select(executable) => Self::with_details(
+                "select",
+                executable.executable_id,
+                vec![format!("retained: {:?}", executable.retained_positions.iter().sorted().collect_vec())],
+            ),
+            ExecutableStage::Sort(executable) => {
+                Self::with_details("sort", executable.executable_id, vec![format!("sort on: {:?}", executable.sort_on)])
+            }
+            ExecutableStage::Offset(executable) => {
+                Self::with_details("offset", executable.executable_id, vec![format!("offset: {}", executable.offset)])
+            }
+            ExecutableStage::Limit(executable) => {
+                Self::with_details("limit", executable.executable_id, vec![format!("limit: {}", executable.limit)])
+            }
+            ExecutableStage::Require(executable) => Self::with_details(
+                "require",
+                executable.executable_id,
+                vec![format!("required: {:?}", executable.required.iter().sorted().collect_vec())],
+            ),
+            ExecutableStage::Reduce(executable) => {
+                let rows_executable = &executable.reduce_rows_executable;
+                Self::with_details(
+                    "reduce",
+                    executable.executable_id,
+                    vec![
+                        format!("group by: {:?}", rows_executable.input_group_positions),
+                        format!("reductions: {:?}", rows_executable.reductions),
+                    ],
+                )
+            }
+        }
+    }
+
+    fn with_details(kind: &'static str, executable_id: u64, details: Vec<String>) -> Self {
+        Self { kind, executable_id, details, match_plan: None }
+    }
+}
+
+impl MatchPlan {
+    fn new(executable: &MatchExecutable, variable_names: &HashMap<Variable, String>) -> Self {
+        let variables = executable
+            .variable_positions()
+            .iter()
+            .map(|(variable, &position)| {
+                let name =
+                    variable_names.get(variable).map(|name| format!("${name}")).unwrap_or_else(|| variable.to_string());
+                VariablePlan { position, name }
+            })
+            .sorted_by_key(|variable| variable.position)
+            .collect();
+        let steps = executable
+            .steps()
+            .iter()
+            .enumerate()
+            .map(|(index, step)| StepPlan::new(index, step, executable, variable_names))
+            .collect();
+        Self { executable_id: executable.executable_id(), variables, steps }
+    }
+}
+
+impl StepPlan {
+    fn new(
+        index: usize,
+        step: &ExecutionStep,
+        executable: &MatchExecutable,
+        variable_names: &HashMap<Variable, String>,
+    ) -> Self {
+        let estimate = executable.step_estimate(index);
+        let mut plan = Self {
+            index,
+            kind: "",
+            sort_variable: None,
+            instructions: Vec::new(),
+            estimated_rows: estimate.map(|estimate| estimate.output_rows),
+            estimated_cost: estimate.map(|estimate| estimate.cost),
+            called_function: None,
+            // The role player index rewrite (`optimisation::apply_rp_index`) is not part of compilation yet, so steps
+            // only ever read the role player edges
+            role_player_index: false,
+            nested: Vec::new(),
+        };
+        match step {
+            ExecutionStep::Intersection(step) => {
+                plan.kind = "intersection";
+                plan.sort_variable = Some(step.sort_variable.to_string());
+                plan.instructions = step
+                    .instructions
+                    .iter()
+                    .map(|(instruction, modes)| format!("{instruction} with ({modes})"))
+                    .collect();
+            }
+            ExecutionStep::UnsortedJoin(step) => {
+                plan.kind = "unsorted_join";
+                plan.instructions = [step.iterate_instruction.to_string()]
+                    .into_iter()
+                    .chain(step.check_instructions.iter().map(|instruction| format!("check {instruction}")))
+                    .collect();
+            }
+            ExecutionStep::Assignment(step) => {
+                plan.kind = "assignment";
+                plan.instructions =
+                    vec![format!("{} = {:?} (inputs: {:?})", step.unbound, step.expression, step.input_positions)];
+            }
+            ExecutionStep::Check(step) => {
+                plan.kind = "check";
+                plan.instructions = step.check_instructions.iter().map(|instruction| instruction.to_string()).collect();
+            }
+            ExecutionStep::Disjunction(step) => {
+                plan.kind = "disjunction";
+                plan.nested = step.branches.iter().map(|branch| MatchPlan::new(branch, variable_names)).collect();
+            }
+            ExecutionStep::Negation(step) => {
+                plan.kind = "negation";
+                plan.nested = vec![MatchPlan::new(&step.negation, variable_names)];
+            }
+            ExecutionStep::Optional(step) => {
+                plan.kind = "optional";
+                plan.nested = vec![MatchPlan::new(&step.optional, variable_names)];
+            }
+            ExecutionStep::FunctionCall(step) => {
+                plan.kind = "function_call";
+                plan.called_function = Some(step.function_id.to_string());
+                plan.instructions = vec![format!("{:?} = call({:?})", step.assigned, step.arguments)];
+            }
+        }
+        plan
+    }
+}
+
+impl FunctionPlan {
+    fn new(function_id: FunctionID, function: &ExecutableFunction) -> Self {
+        Self {
+            function_id: function_id.to_string(),
+            is_tabled: function.is_tabled == FunctionTablingType::Tabled,
+            stages: function
+                .executable_stages
+                .iter()
+                .map(|stage| StagePlan::new(stage, &function.variable_names))
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for PipelinePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Pipeline plan:")?;
+        for (index, stage) in self.stages.iter().enumerate() {
+            fmt_stage(f, index, stage, 1)?;
+        }
+        if self.has_fetch {
+            write!(f, "\n  Fetch")?;
+        }
+        for function in &self.functions {
+            let tabling = if function.is_tabled { "tabled" } else { "untabled" };
+            write!(f, "\nFunction {} ({tabling}):", function.function_id)?;
+            for (index, stage) in function.stages.iter().enumerate() {
+                fmt_stage(f, index, stage, 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn fmt_stage(f: &mut fmt::Formatter<'_>, index: usize, stage: &StagePlan, depth: usize) -> fmt::Result {
+    let indent = "  ".repeat(depth);
+    write!(f, "\n{indent}Stage {index}: {} [id={}]", stage.kind, stage.executable_id)?;
+    for detail in &stage.details {
+        write!(f, "\n{indent}  {detail}")?;
+    }
+    if let Some(match_plan) = &stage.match_plan {
+        fmt_match(f, match_plan, depth + 1)?;
+    }
+    Ok(())
+}
+
+fn fmt_match(f: &mut fmt::Formatter<'_>, match_plan: &MatchPlan, depth: usize) -> fmt::Result {
+    let indent = "  ".repeat(depth);
+    let variables = match_plan.variables.iter().map(|variable| format!("{}={}", variable.position, variable.name));
+    write!(f, "\n{indent}Variables: {}", variables.format(", "))?;
+    for step in &match_plan.steps {
+        write!(f, "\n{indent}Step {}: {}", step.index, step.kind)?;
+        if let Some(sort_variable) = &step.sort_variable {
+            write!(f, " [sort_by={sort_variable}]")?;
+        }
+        if let Some(function_id) = &step.called_function {
+            write!(f, " [fn_id={function_id}]")?;
+        }
+        if step.role_player_index {
+            write!(f, " [rp_index]")?;
+        }
+        if let (Some(rows), Some(cost)) = (step.estimated_rows, step.estimated_cost) {
+            write!(f, " (estimated rows: {rows:.1}, estimated cost: {cost:.3})")?;
+        }
+        for instruction in &step.instructions {
+            write!(f, "\n{indent}    {instruction}")?;
+        }
+        for (branch, nested) in step.nested.iter().enumerate() {
+            write!(f, "\n{indent}  Branch {branch}:")?;
+            fmt_match(f, nested, depth + 2)?;
+        }
+    }
+    Ok(())
+}
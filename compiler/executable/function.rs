@@ -40,6 +40,7 @@ pub struct ExecutableFunction {
     pub returns: ExecutableReturn,
     pub is_tabled: FunctionTablingType,
     pub parameter_registry: Arc<ParameterRegistry>,
+    pub variable_names: Arc<HashMap<Variable, String>>,
     // pub plan_cost: f64, // TODO: Where do we fit this in?
 }
 
@@ -80,6 +81,7 @@ pub(crate) fn compile_function(
         argument_positions,
         returns,
         parameter_registry: Arc::new(parameter_registry),
+        variable_names: Arc::new(variable_registry.variable_names().clone()),
         is_tabled,
     })
 }
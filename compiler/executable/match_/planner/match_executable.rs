@@ -27,6 +27,7 @@ pub struct MatchExecutable {
     pub(crate) steps: Vec<ExecutionStep>,
     variable_positions: HashMap<Variable, VariablePosition>,
     variable_positions_index: Vec<Variable>,
+    step_estimates: Vec<Option<StepEstimate>>,
 }
 
 impl MatchExecutable {
@@ -36,7 +37,14 @@ impl MatchExecutable {
         variable_positions: HashMap<Variable, VariablePosition>,
         variable_positions_index: Vec<Variable>,
     ) -> Self {
-        Self { executable_id, steps, variable_positions, variable_positions_index }
+        let step_estimates = vec![None; steps.len()];
+        Self { executable_id, steps, variable_positions, variable_positions_index, step_estimates }
+    }
+
+    pub(crate) fn with_step_estimates(mut self, step_estimates: Vec<Option<StepEstimate>>) -> Self {
+        debug_assert_eq!(step_estimates.len(), self.steps.len());
+        self.step_estimates = step_estimates;
+        self
     }
 
     pub fn executable_id(&self) -> u64 {
@@ -47,6 +55,15 @@ impl MatchExecutable {
         &self.steps
     }
 
+    /// The planner's estimates for the step, if it was planned from statistics
+    pub fn step_estimate(&self, step_index: usize) -> Option<StepEstimate> {
+        self.step_estimates.get(step_index).copied().flatten()
+    }
+
+    pub fn estimated_output_rows(&self, step_index: usize) -> Option<f64> {
+        self.step_estimate(step_index).map(|estimate| estimate.output_rows)
+    }
+
     pub fn outputs(&self) -> &[VariablePosition] {
         self.steps.last().unwrap().selected_variables()
     }
@@ -70,6 +87,12 @@ impl fmt::Display for MatchExecutable {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct StepEstimate {
+    pub output_rows: f64,
+    pub cost: f64,
+}
+
 #[derive(Clone, Debug)]
 pub enum ExecutionStep {
     Intersection(IntersectionStep),
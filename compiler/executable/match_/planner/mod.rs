@@ -19,7 +19,7 @@ use crate::{
             planner::{
                 match_executable::{
                     AssignmentStep, CheckStep, DisjunctionStep, ExecutionStep, FunctionCallStep, IntersectionStep,
-                    MatchExecutable, NegationStep,
+                    MatchExecutable, NegationStep, StepEstimate,
                 },
                 plan::plan_conjunction,
             },
@@ -168,7 +168,7 @@ impl StepInstructionsBuilder {
 
 impl From<StepInstructionsBuilder> for StepBuilder {
     fn from(instructions_builder: StepInstructionsBuilder) -> Self {
-        StepBuilder { selected_variables: Vec::new(), builder: instructions_builder }
+        StepBuilder { selected_variables: Vec::new(), builder: instructions_builder, estimate: None }
     }
 }
 
@@ -176,6 +176,7 @@ impl From<StepInstructionsBuilder> for StepBuilder {
 struct StepBuilder {
     selected_variables: Vec<Variable>,
     builder: StepInstructionsBuilder,
+    estimate: Option<StepEstimate>,
 }
 
 impl StepBuilder {
@@ -299,6 +300,7 @@ impl MatchExecutableBuilder {
             self.current = Some(Box::new(StepBuilder {
                 selected_variables: Vec::from_iter(self.current_outputs.iter().copied()),
                 builder: StepInstructionsBuilder::Intersection(IntersectionBuilder::new()),
+                estimate: None,
             }));
         }
 
@@ -325,6 +327,7 @@ impl MatchExecutableBuilder {
             self.current = Some(Box::new(StepBuilder {
                 selected_variables: Vec::from_iter(self.current_outputs.iter().copied()),
                 builder: StepInstructionsBuilder::Check(CheckBuilder::default()),
+                estimate: None,
             }))
         }
         let current = self.current.as_mut().unwrap().builder.as_check_mut().unwrap();
@@ -383,6 +386,15 @@ impl MatchExecutableBuilder {
         self.steps.push(step);
     }
 
+    /// Attribute the planner's running estimate to the step currently being built, or the last finished step
+    fn record_estimate(&mut self, estimate: StepEstimate) {
+        if let Some(current) = self.current.as_mut() {
+            current.estimate = Some(estimate);
+        } else if let Some(last) = self.steps.last_mut() {
+            last.estimate = Some(estimate);
+        }
+    }
+
     fn position_mapping(&self) -> &HashMap<Variable, ExecutorVariable> {
         &self.index
     }
@@ -427,6 +439,7 @@ impl MatchExecutableBuilder {
             .iter()
             .filter_map(|(var, &pos)| variable_registry.variable_names().get(var).and(Some(pos)))
             .collect();
+        let step_estimates = self.steps.iter().map(|step| step.estimate).collect();
         let steps = self
             .steps
             .into_iter()
@@ -445,5 +458,6 @@ impl MatchExecutableBuilder {
             self.index.into_iter().filter_map(|(var, id)| Some((var, id.as_position()?))).collect(),
             variable_positions_index,
         )
+        .with_step_estimates(step_estimates)
     }
 }
@@ -42,6 +42,7 @@ use crate::{
             CheckInstruction, CheckVertex, ConstraintInstruction, Inputs, IsInstruction,
         },
         planner::{
+            match_executable::StepEstimate,
             vertex::{
                 constraint::{
                     ConstraintVertex, HasPlanner, IidPlanner, IsaPlanner, LinksPlanner, OwnsPlanner, PlaysPlanner,
@@ -651,18 +652,23 @@ impl<'a> ConjunctionPlanBuilder<'a> {
         let ordering = self.initialise_greedy_ordering();
         let element_to_order = ordering.iter().copied().enumerate().map(|(order, index)| (index, order)).collect();
 
-        let cost = ordering
+        let element_costs = ordering
             .iter()
             .enumerate()
             .map(|(i, idx)| {
                 let sort_variable = ordering.get(i + 1).and_then(|vertex| vertex.as_variable_id());
                 self.graph.elements[idx].cost(&ordering[..i], sort_variable, &self.graph)
             })
-            .fold(ElementCost::MEM_SIMPLE_BRANCH_1, |acc, e| acc.chain(e));
+            .scan(ElementCost::MEM_SIMPLE_BRANCH_1, |acc, e| {
+                *acc = acc.chain(e);
+                Some(*acc)
+            })
+            .collect_vec();
+        let cost = element_costs.last().copied().unwrap_or(ElementCost::MEM_SIMPLE_BRANCH_1);
 
         let Self { shared_variables, graph, type_annotations, statistics: _ } = self;
 
-        ConjunctionPlan { shared_variables, graph, type_annotations, ordering, element_to_order, cost }
+        ConjunctionPlan { shared_variables, graph, type_annotations, ordering, element_to_order, element_costs, cost }
     }
 }
 
@@ -673,6 +679,8 @@ pub(super) struct ConjunctionPlan<'a> {
     type_annotations: &'a TypeAnnotations,
     ordering: Vec<VertexId>,
     element_to_order: HashMap<VertexId, usize>,
+    // cost of the plan up to and including each element in `ordering`
+    element_costs: Vec<ElementCost>,
     cost: ElementCost,
 }
 
@@ -701,7 +709,7 @@ impl ConjunctionPlan<'_> {
             input_variables.into_iter().collect(),
         );
 
-        for &index in &self.ordering {
+        for (order, &index) in self.ordering.iter().enumerate() {
             match index {
                 VertexId::Variable(var) => {
                     self.may_make_variable_producing_step(&mut match_builder, var, variable_registry);
@@ -733,6 +741,8 @@ impl ConjunctionPlan<'_> {
                     }
                 }
             }
+            let ElementCost { per_input, branching_factor, .. } = self.element_costs[order];
+            match_builder.record_estimate(StepEstimate { output_rows: branching_factor, cost: per_input });
         }
 
         match_builder
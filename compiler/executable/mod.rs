@@ -11,6 +11,7 @@ use error::typedb_error;
 use crate::executable::{fetch::executable::FetchCompilationError, insert::WriteCompilationError};
 
 pub mod delete;
+pub mod explain;
 pub mod fetch;
 pub mod function;
 pub mod insert;
@@ -31,12 +31,18 @@ impl SelectExecutable {
 pub struct SortExecutable {
     pub executable_id: u64,
     pub sort_on: Vec<SortVariable>,
+    // set when the sort is directly followed by a limit, so only the first rows must be kept
+    pub limit: Option<u64>,
     pub output_row_mapping: HashMap<Variable, VariablePosition>,
 }
 
 impl SortExecutable {
-    pub(crate) fn new(sort_on: Vec<SortVariable>, output_row_mapping: HashMap<Variable, VariablePosition>) -> Self {
-        Self { executable_id: next_executable_id(), sort_on, output_row_mapping }
+    pub(crate) fn new(
+        sort_on: Vec<SortVariable>,
+        limit: Option<u64>,
+        output_row_mapping: HashMap<Variable, VariablePosition>,
+    ) -> Self {
+        Self { executable_id: next_executable_id(), sort_on, limit, output_row_mapping }
     }
 }
 
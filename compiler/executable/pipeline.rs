@@ -5,7 +5,7 @@
  */
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     iter::zip,
     sync::Arc,
 };
@@ -17,6 +17,7 @@ use itertools::Itertools;
 
 use crate::{
     annotation::{
+        expression::compiled_expression::ExpressionValueType,
         fetch::AnnotatedFetch,
         function::{AnnotatedPreambleFunctions, AnnotatedSchemaFunctions},
         pipeline::AnnotatedStage,
@@ -39,6 +40,8 @@ pub struct ExecutablePipeline {
     pub executable_functions: ExecutableFunctionRegistry,
     pub executable_stages: Vec<ExecutableStage>,
     pub executable_fetch: Option<Arc<ExecutableFetch>>,
+    // The value types assigned to the output variables of the last stage, where they are values
+    pub output_value_types: HashMap<Variable, ExpressionValueType>,
 }
 
 #[derive(Debug, Clone)]
@@ -89,6 +92,7 @@ pub fn compile_pipeline(
     annotated_preamble: AnnotatedPreambleFunctions,
     annotated_stages: Vec<AnnotatedStage>,
     annotated_fetch: Option<AnnotatedFetch>,
+    annotated_value_types: BTreeMap<Variable, ExpressionValueType>,
     input_variables: &HashSet<Variable>,
 ) -> Result<ExecutablePipeline, ExecutableCompilationError> {
     // TODO: Cache compiled schema functions?
@@ -134,7 +138,15 @@ pub fn compile_pipeline(
         annotated_fetch,
         input_variables,
     )?;
-    Ok(ExecutablePipeline { executable_functions: schema_and_preamble_functions, executable_stages, executable_fetch })
+    let output_variables = executable_stages.last().map(ExecutableStage::output_row_mapping).unwrap_or_default();
+    let output_value_types =
+        annotated_value_types.into_iter().filter(|(variable, _)| output_variables.contains_key(variable)).collect();
+    Ok(ExecutablePipeline {
+        executable_functions: schema_and_preamble_functions,
+        executable_stages,
+        executable_fetch,
+        output_value_types,
+    })
 }
 
 pub fn compile_stages_and_fetch(
@@ -182,7 +194,13 @@ pub(crate) fn compile_pipeline_stages(
     let input_variable_positions =
         input_variables.enumerate().map(|(i, var)| (var, VariablePosition::new(i as u32))).collect();
 
-    for stage in annotated_stages {
+    let mut annotated_stages = annotated_stages.into_iter().peekable();
+    while let Some(stage) = annotated_stages.next() {
+        // a sort directly followed by a limit only needs to keep the first rows
+        let next_limit = match annotated_stages.peek() {
+            Some(AnnotatedStage::Limit(limit)) => Some(limit.limit()),
+            _ => None,
+        };
         // TODO: We can filter out the variables that are no longer needed in the future stages, but are carried as selected variables from the previous one
         let selected_variables = selected_variables
             .iter()
@@ -191,9 +209,15 @@ pub(crate) fn compile_pipeline_stages(
             .unique()
             .collect_vec();
         let executable_stage = match executable_stages.last().map(|stage| stage.output_row_mapping()) {
-            Some(row_mapping) => {
-                compile_stage(statistics, variable_registry, functions, &row_mapping, &selected_variables, stage)?
-            }
+            Some(row_mapping) => compile_stage(
+                statistics,
+                variable_registry,
+                functions,
+                &row_mapping,
+                &selected_variables,
+                stage,
+                next_limit,
+            )?,
             None => compile_stage(
                 statistics,
                 variable_registry,
@@ -201,6 +225,7 @@ pub(crate) fn compile_pipeline_stages(
                 &input_variable_positions,
                 &selected_variables,
                 stage,
+                next_limit,
             )?,
         };
         executable_stages.push(executable_stage);
@@ -215,6 +240,7 @@ fn compile_stage(
     input_variables: &HashMap<Variable, VariablePosition>,
     selected_variables: &[Variable],
     annotated_stage: AnnotatedStage,
+    next_limit: Option<u64>,
 ) -> Result<ExecutableStage, ExecutableCompilationError> {
     match &annotated_stage {
         AnnotatedStage::Match { block, block_annotations, executable_expressions } => {
@@ -258,9 +284,11 @@ fn compile_stage(
             }
             Ok(ExecutableStage::Select(Arc::new(SelectExecutable::new(retained_positions, output_row_mapping))))
         }
-        AnnotatedStage::Sort(sort) => {
-            Ok(ExecutableStage::Sort(Arc::new(SortExecutable::new(sort.variables.clone(), input_variables.clone()))))
-        }
+        AnnotatedStage::Sort(sort) => Ok(ExecutableStage::Sort(Arc::new(SortExecutable::new(
+            sort.variables.clone(),
+            next_limit,
+            input_variables.clone(),
+        )))),
         AnnotatedStage::Offset(offset) => {
             Ok(ExecutableStage::Offset(Arc::new(OffsetExecutable::new(offset.offset(), input_variables.clone()))))
         }
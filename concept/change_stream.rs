@@ -0,0 +1,192 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::BTreeMap;
+
+use bytes::Bytes;
+use encoding::graph::{
+    thing::{
+        edge::{ThingEdgeHas, ThingEdgeLinks},
+        vertex_attribute::AttributeVertex,
+        vertex_object::ObjectVertex,
+        ThingVertex,
+    },
+    type_::vertex::PrefixedTypeVertexEncoding,
+};
+use error::typedb_error;
+use storage::{
+    durability_client::DurabilityClient,
+    isolation_manager::CommitType,
+    iterator::MVCCReadError,
+    key_value::StorageKeyReference,
+    recovery::commit_recovery::{load_commit_data_between, RecoveryCommitStatus, StorageRecoveryError},
+    sequence_number::SequenceNumber,
+    MVCCStorage,
+};
+
+use crate::{
+    thing::{
+        attribute::Attribute,
+        entity::Entity,
+        object::Object,
+        relation::Relation,
+        statistics::{write_to_delta, CommittedWrites},
+        ThingAPI,
+    },
+    type_::role_type::RoleType,
+};
+
+/// A concept-level change made by a committed transaction. A bulk load only reports `BulkLoaded`, since its
+/// individual changes are not in the WAL, so consumers must re-read the data.
+#[derive(Debug, Clone)]
+pub enum ChangeEvent {
+    EntityCreated { entity: Entity },
+    EntityDeleted { entity: Entity },
+    RelationCreated { relation: Relation },
+    RelationDeleted { relation: Relation },
+    AttributeCreated { attribute: Attribute },
+    AttributeDeleted { attribute: Attribute },
+    HasAdded { owner: Object, attribute: Attribute },
+    HasRemoved { owner: Object, attribute: Attribute },
+    LinksAdded { relation: Relation, player: Object, role_type: RoleType },
+    LinksRemoved { relation: Relation, player: Object, role_type: RoleType },
+    SchemaChanged,
+    BulkLoaded,
+}
+
+/// All the changes made by one committed transaction. Consumers resume a stream from `sequence_number.next()`.
+#[derive(Debug, Clone)]
+pub struct CommittedChanges {
+    pub sequence_number: SequenceNumber,
+    pub commit_type: CommitType,
+    pub events: Vec<ChangeEvent>,
+}
+
+/// Decode the changes of every commit from `start` up to and including `end`, which must not exceed the storage watermark.
+/// Rejected commits are skipped, so the returned sequence numbers are not contiguous.
+/// A commit may be applied before its status is written to the WAL: such a commit fails the read with
+/// `CommitStatusPending`, and callers retry from it later.
+pub fn read_changes<D: DurabilityClient>(
+    storage: &MVCCStorage<D>,
+    start: SequenceNumber,
+    end: SequenceNumber,
+) -> Result<Vec<CommittedChanges>, ChangeStreamError> {
+    use ChangeStreamError::{CommitStatusPending, DataRead, ReadCommitData};
+
+    if start > end {
+        return Ok(Vec::new());
+    }
+    let mut commits = BTreeMap::new();
+    let mut commit_types = BTreeMap::new();
+    for (sequence_number, status) in load_commit_data_between(start, end, storage.durability())
+        .map_err(|err| ReadCommitData { typedb_source: err })?
+    {
+        match status {
+            RecoveryCommitStatus::Validated(record) => {
+                commit_types.insert(sequence_number, record.commit_type());
+                let writes = CommittedWrites {
+                    open_sequence_number: record.open_sequence_number(),
+                    operations: record.into_operations(),
+                };
+                commits.insert(sequence_number, writes);
+            }
+            RecoveryCommitStatus::Pending(_) => return Err(CommitStatusPending { sequence_number }),
+            RecoveryCommitStatus::Rejected => (),
+        }
+    }
+
+    let mut changes = Vec::with_capacity(commits.len());
+    for (&sequence_number, writes) in &commits {
+        let commit_type = commit_types[&sequence_number];
+        let events = decode_commit(sequence_number, commit_type, writes, &commits, storage)
+            .map_err(|err| DataRead { source: err })?;
+        changes.push(CommittedChanges { sequence_number, commit_type, events });
+    }
+    Ok(changes)
+}
+
+fn decode_commit<D>(
+    commit_sequence_number: SequenceNumber,
+    commit_type: CommitType,
+    writes: &CommittedWrites,
+    commits: &BTreeMap<SequenceNumber, CommittedWrites>,
+    storage: &MVCCStorage<D>,
+) -> Result<Vec<ChangeEvent>, MVCCReadError> {
+    let mut events = Vec::new();
+    match commit_type {
+        CommitType::Data => (),
+        CommitType::Schema => events.push(ChangeEvent::SchemaChanged),
+        CommitType::BulkLoad => events.push(ChangeEvent::BulkLoaded),
+    }
+    for (key, write) in writes.operations.iterate_writes() {
+        // Puts and concurrent deletes may turn out not to change anything, exactly as for statistics.
+        // Deletes racing a commit from an earlier window are resolved against storage, so resuming does not repeat them
+        let delta =
+            write_to_delta(&key, &write, writes.open_sequence_number, commit_sequence_number, commits, storage)?;
+        if delta == 0 {
+            continue;
+        }
+        let added = delta > 0;
+        let event = if ObjectVertex::is_entity_vertex(StorageKeyReference::from(&key)) {
+            let entity = Entity::new(ObjectVertex::decode(key.bytes()));
+            if added {
+                ChangeEvent::EntityCreated { entity }
+            } else {
+                ChangeEvent::EntityDeleted { entity }
+            }
+        } else if ObjectVertex::is_relation_vertex(StorageKeyReference::from(&key)) {
+            let relation = Relation::new(ObjectVertex::decode(key.bytes()));
+            if added {
+                ChangeEvent::RelationCreated { relation }
+            } else {
+                ChangeEvent::RelationDeleted { relation }
+            }
+        } else if AttributeVertex::is_attribute_vertex(StorageKeyReference::from(&key)) {
+            let attribute = Attribute::new(AttributeVertex::decode(key.bytes()));
+            if added {
+                ChangeEvent::AttributeCreated { attribute }
+            } else {
+                ChangeEvent::AttributeDeleted { attribute }
+            }
+        } else if ThingEdgeHas::is_has(&key) {
+            let edge = ThingEdgeHas::decode(Bytes::Reference(key.bytes()));
+            let owner = Object::new(edge.from());
+            let attribute = Attribute::new(edge.to());
+            if added {
+                ChangeEvent::HasAdded { owner, attribute }
+            } else {
+                ChangeEvent::HasRemoved { owner, attribute }
+            }
+        } else if ThingEdgeLinks::is_links(&key) {
+            let edge = ThingEdgeLinks::new(Bytes::Reference(key.bytes()));
+            let relation = Relation::new(edge.from());
+            let player = Object::new(edge.to());
+            let role_type = RoleType::build_from_type_id(edge.role_id());
+            if added {
+                ChangeEvent::LinksAdded { relation, player, role_type }
+            } else {
+                ChangeEvent::LinksRemoved { relation, player, role_type }
+            }
+        } else {
+            // reverse edges, indexes and schema keys are either derived or covered by SchemaChanged
+            continue;
+        };
+        events.push(event);
+    }
+    Ok(events)
+}
+
+typedb_error!(
+    pub ChangeStreamError(component = "Change stream", prefix = "CHS") {
+        ReadCommitData(1, "Failed to read commit records from the WAL.", ( typedb_source: StorageRecoveryError )),
+        DataRead(2, "Error decoding changes due to an error reading MVCC storage layer.", ( source: MVCCReadError )),
+        CommitStatusPending(
+            3,
+            "The status of commit '{sequence_number}' has not been written to the WAL yet.",
+            sequence_number: SequenceNumber
+        ),
+    }
+);
@@ -0,0 +1,304 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::ops::ControlFlow;
+
+use bytes::{byte_array::ByteArray, util::HexBytesFormatter, Bytes};
+use encoding::{
+    graph::{
+        definition::definition_key::DefinitionKey,
+        thing::{
+            edge::{ThingEdgeHas, ThingEdgeHasReverse, ThingEdgeLinks, ThingEdgeLinksIndex},
+            property::ObjectVertexProperty,
+            vertex_attribute::AttributeVertex,
+            vertex_object::ObjectVertex,
+            ThingVertex,
+        },
+        type_::{
+            edge::TypeEdge,
+            property::{TypeEdgeProperty, TypeVertexProperty},
+            vertex::{PrefixedTypeVertexEncoding, TypeID, TypeVertex, TypeVertexEncoding},
+        },
+        Typed,
+    },
+    layout::prefix::{Prefix, PrefixID},
+    value::{label::Label, primitive_encoding::decode_u64},
+    EncodingKeyspace, Prefixed,
+};
+use lending_iterator::LendingIterator;
+use resource::constants::{encoding::DefinitionIDUInt, snapshot::BUFFER_KEY_INLINE};
+use storage::{
+    key_range::KeyRange,
+    key_value::{StorageKey, StorageKeyArray, StorageKeyReference},
+    snapshot::ReadableSnapshot,
+};
+
+use crate::{
+    error::ConceptReadError,
+    thing::{
+        attribute::Attribute,
+        conflict::{
+            describe_attribute, describe_has, describe_links, describe_links_index, describe_object, describe_type,
+        },
+        object::Object,
+        thing_manager::ThingManager,
+        ThingAPI,
+    },
+    type_::{
+        attribute_type::AttributeType, entity_type::EntityType, relation_type::RelationType, role_type::RoleType,
+        type_manager::TypeManager, TypeAPI,
+    },
+};
+
+const TYPE_EDGE_LENGTH: usize = PrefixID::LENGTH + 2 * TypeVertex::LENGTH;
+const TYPE_VERTEX_PROPERTY_MIN_LENGTH: usize = PrefixID::LENGTH + TypeVertex::LENGTH + 1;
+const TYPE_EDGE_PROPERTY_MIN_LENGTH: usize = PrefixID::LENGTH + TYPE_EDGE_LENGTH + 1;
+const OBJECT_PROPERTY_MIN_LENGTH: usize = PrefixID::LENGTH + ObjectVertex::LENGTH + 1;
+
+/// A storage key and its value decoded for humans, such as when dumping keyspaces to debug them
+#[derive(Debug)]
+pub struct InspectedKey {
+    /// `None` if the first byte of the key is not a known prefix
+    pub prefix: Option<Prefix>,
+    /// The types this key is about: for example the owner and attribute types of a `has` edge
+    pub types: Vec<TypeVertex>,
+    pub description: String,
+    /// `None` if the value is empty
+    pub value: Option<String>,
+}
+
+/// Decode a key from any keyspace into the vertex, edge, property or index it encodes.
+/// Keys that do not have the expected layout for their prefix are described by their bytes.
+pub fn inspect_key(
+    snapshot: &impl ReadableSnapshot,
+    thing_manager: &ThingManager,
+    key: &StorageKeyArray<BUFFER_KEY_INLINE>,
+    value: &[u8],
+) -> InspectedKey {
+    let bytes = key.bytes();
+    let prefix = bytes.first().and_then(|&byte| Prefix::try_from_prefix_id(PrefixID::new(byte)));
+    let undecoded = || InspectedKey {
+        prefix,
+        types: Vec::new(),
+        description: format!("key {} in keyspace {}", HexBytesFormatter::borrowed(bytes), key.keyspace_id()),
+        value: describe_bytes(value),
+    };
+    let Some(prefix) = prefix else { return undecoded() };
+
+    let reference = StorageKeyReference::from(key);
+    let (types, description, value) = match prefix {
+        Prefix::VertexEntityType
+        | Prefix::VertexRelationType
+        | Prefix::VertexAttributeType
+        | Prefix::VertexRoleType
+            if bytes.len() == TypeVertex::LENGTH =>
+        {
+            let vertex = TypeVertex::decode(Bytes::Reference(bytes));
+            (vec![vertex], format!("type {}", describe_type_vertex(snapshot, thing_manager, vertex)), None)
+        }
+        Prefix::DefinitionStruct | Prefix::DefinitionFunction
+            if bytes.len() == PrefixID::LENGTH + size_of::<DefinitionIDUInt>() =>
+        {
+            let definition_key = DefinitionKey::new(Bytes::Reference(bytes));
+            (Vec::new(), format!("{prefix:?} {}", definition_key.definition_id().as_uint()), describe_bytes(value))
+        }
+        Prefix::VertexEntity | Prefix::VertexRelation
+            if ObjectVertex::is_entity_vertex(reference) || ObjectVertex::is_relation_vertex(reference) =>
+        {
+            let vertex = ObjectVertex::decode(bytes);
+            (vec![object_type(vertex)], describe_object(snapshot, thing_manager, vertex), None)
+        }
+        Prefix::VertexAttribute if AttributeVertex::is_attribute_vertex(reference) => {
+            let vertex = AttributeVertex::decode(bytes);
+            (vec![attribute_type(vertex)], describe_attribute(snapshot, thing_manager, vertex), None)
+        }
+        Prefix::EdgeSub
+        | Prefix::EdgeSubReverse
+        | Prefix::EdgeOwns
+        | Prefix::EdgeOwnsReverse
+        | Prefix::EdgePlays
+        | Prefix::EdgePlaysReverse
+        | Prefix::EdgeRelates
+        | Prefix::EdgeRelatesReverse
+            if bytes.len() == TYPE_EDGE_LENGTH =>
+        {
+            let edge = TypeEdge::decode(Bytes::Reference(bytes));
+            let description = format!(
+                "{prefix:?} {} -> {}",
+                describe_type_vertex(snapshot, thing_manager, edge.from()),
+                describe_type_vertex(snapshot, thing_manager, edge.to())
+            );
+            (vec![edge.from(), edge.to()], description, describe_bytes(value))
+        }
+        Prefix::EdgeHas if ThingEdgeHas::is_has(key) => {
+            let edge = ThingEdgeHas::decode(Bytes::Reference(bytes));
+            let types = vec![object_type(edge.from()), attribute_type(edge.to())];
+            (types, describe_has(snapshot, thing_manager, edge.from(), edge.to()), describe_count(value))
+        }
+        Prefix::EdgeHasReverse if ThingEdgeHasReverse::is_has_reverse(reference) => {
+            let edge = ThingEdgeHasReverse::decode(Bytes::Reference(bytes));
+            let types = vec![object_type(edge.to()), attribute_type(edge.from())];
+            let description = format!("reverse {}", describe_has(snapshot, thing_manager, edge.to(), edge.from()));
+            (types, description, describe_count(value))
+        }
+        Prefix::EdgeLinks | Prefix::EdgeLinksReverse
+            if ThingEdgeLinks::is_links(key) || ThingEdgeLinks::is_links_reverse(reference) =>
+        {
+            let edge = ThingEdgeLinks::new(Bytes::Reference(bytes));
+            let types = vec![object_type(edge.relation()), object_type(edge.player()), role_type(edge.role_id())];
+            let description = describe_links(snapshot, thing_manager, edge.relation(), edge.player(), edge.role_id());
+            let description =
+                if prefix == Prefix::EdgeLinksReverse { format!("reverse {description}") } else { description };
+            (types, description, describe_count(value))
+        }
+        Prefix::EdgeLinksIndex if ThingEdgeLinksIndex::is_index(key) => {
+            let index = ThingEdgeLinksIndex::decode(Bytes::Reference(bytes));
+            let types = vec![
+                object_type(index.relation()),
+                object_type(index.from()),
+                object_type(index.to()),
+                role_type(index.from_role_id()),
+                role_type(index.to_role_id()),
+            ];
+            (types, describe_links_index(snapshot, thing_manager, index), describe_count(value))
+        }
+        Prefix::PropertyTypeVertex if bytes.len() >= TYPE_VERTEX_PROPERTY_MIN_LENGTH => {
+            let property = TypeVertexProperty::decode(Bytes::Reference(bytes));
+            let description = format!(
+                "{:?} of type {}{}",
+                property.infix(),
+                describe_type_vertex(snapshot, thing_manager, property.type_vertex()),
+                describe_suffix(property.suffix())
+            );
+            (vec![property.type_vertex()], description, describe_bytes(value))
+        }
+        Prefix::PropertyTypeEdge if bytes.len() >= TYPE_EDGE_PROPERTY_MIN_LENGTH => {
+            let property = TypeEdgeProperty::decode(Bytes::Reference(bytes));
+            let edge = property.type_edge();
+            let description = format!(
+                "{:?} of {:?} {} -> {}{}",
+                property.infix(),
+                edge.prefix(),
+                describe_type_vertex(snapshot, thing_manager, edge.from()),
+                describe_type_vertex(snapshot, thing_manager, edge.to()),
+                describe_suffix(property.suffix())
+            );
+            (vec![edge.from(), edge.to()], description, describe_bytes(value))
+        }
+        Prefix::PropertyObjectVertex
+            if bytes.len() >= OBJECT_PROPERTY_MIN_LENGTH
+                && ObjectVertex::try_from_bytes(&bytes[PrefixID::LENGTH..OBJECT_PROPERTY_MIN_LENGTH - 1]).is_some() =>
+        {
+            let property = ObjectVertexProperty::decode(Bytes::Reference(bytes));
+            let description = format!(
+                "{:?} of {}{}",
+                property.infix(),
+                describe_object(snapshot, thing_manager, property.object_vertex()),
+                describe_suffix(property.suffix())
+            );
+            (vec![object_type(property.object_vertex())], description, describe_bytes(value))
+        }
+        Prefix::IndexLabelToType => {
+            let label = String::from_utf8_lossy(&bytes[PrefixID::LENGTH..]);
+            match <&[u8; TypeVertex::LENGTH]>::try_from(value) {
+                Ok(value) if Prefix::try_from_prefix_id(PrefixID::new(value[0])).is_some() => {
+                    let vertex = TypeVertex::decode(Bytes::Reference(value));
+                    let type_ = describe_type_vertex(snapshot, thing_manager, vertex);
+                    (vec![vertex], format!("label index '{label}'"), Some(format!("type {type_}")))
+                }
+                _ => (Vec::new(), format!("label index '{label}'"), describe_bytes(value)),
+            }
+        }
+        Prefix::IndexNameToDefinitionStruct | Prefix::IndexNameToDefinitionFunction => {
+            let name = String::from_utf8_lossy(&bytes[PrefixID::LENGTH..]);
+            (Vec::new(), format!("{prefix:?} '{name}'"), describe_bytes(value))
+        }
+        _ => return undecoded(),
+    };
+    InspectedKey { prefix: Some(prefix), types, description, value }
+}
+
+/// Decode every key under `prefix` in `keyspace`, in order, until `visit` breaks
+pub fn inspect_keyspace(
+    snapshot: &impl ReadableSnapshot,
+    thing_manager: &ThingManager,
+    keyspace: EncodingKeyspace,
+    prefix: Prefix,
+    mut visit: impl FnMut(InspectedKey) -> ControlFlow<()>,
+) -> Result<ControlFlow<()>, Box<ConceptReadError>> {
+    let prefix_key =
+        StorageKey::<{ PrefixID::LENGTH }>::new_owned(keyspace, ByteArray::copy(&prefix.prefix_id().to_bytes()));
+    let mut iterator = snapshot.iterate_range(&KeyRange::new_within(prefix_key, prefix.fixed_width_keys()));
+    while let Some(result) = iterator.next() {
+        let (key, value) = result.map_err(|source| Box::new(ConceptReadError::SnapshotIterate { source }))?;
+        let key = StorageKeyArray::from(key.as_reference());
+        if visit(inspect_key(snapshot, thing_manager, &key, &value)).is_break() {
+            return Ok(ControlFlow::Break(()));
+        }
+    }
+    Ok(ControlFlow::Continue(()))
+}
+
+/// Find the type with the given label, of any kind
+pub fn resolve_type_label(
+    snapshot: &impl ReadableSnapshot,
+    type_manager: &TypeManager,
+    label: &Label,
+) -> Result<Option<TypeVertex>, Box<ConceptReadError>> {
+    if let Some(entity_type) = type_manager.get_entity_type(snapshot, label)? {
+        Ok(Some(entity_type.vertex()))
+    } else if let Some(relation_type) = type_manager.get_relation_type(snapshot, label)? {
+        Ok(Some(relation_type.vertex()))
+    } else if let Some(attribute_type) = type_manager.get_attribute_type(snapshot, label)? {
+        Ok(Some(attribute_type.vertex()))
+    } else {
+        Ok(type_manager.get_role_type(snapshot, label)?.map(|role_type| role_type.vertex()))
+    }
+}
+
+fn describe_type_vertex(snapshot: &impl ReadableSnapshot, thing_manager: &ThingManager, vertex: TypeVertex) -> String {
+    match vertex.prefix() {
+        Prefix::VertexEntityType => describe_type(snapshot, thing_manager, EntityType::new(vertex)),
+        Prefix::VertexRelationType => describe_type(snapshot, thing_manager, RelationType::new(vertex)),
+        Prefix::VertexAttributeType => describe_type(snapshot, thing_manager, AttributeType::new(vertex)),
+        Prefix::VertexRoleType => describe_type(snapshot, thing_manager, RoleType::new(vertex)),
+        _ => format!("type {}", vertex.type_id_()),
+    }
+}
+
+fn describe_suffix(suffix: Option<&[u8]>) -> String {
+    suffix.map(|suffix| format!(" with suffix {}", HexBytesFormatter::borrowed(suffix))).unwrap_or_default()
+}
+
+fn describe_count(value: &[u8]) -> Option<String> {
+    match <[u8; 8]>::try_from(value) {
+        Ok(bytes) => Some(format!("count {}", decode_u64(bytes))),
+        Err(_) => describe_bytes(value),
+    }
+}
+
+/// Values are shown as text if they are printable, otherwise by their bytes
+fn describe_bytes(value: &[u8]) -> Option<String> {
+    if value.is_empty() {
+        return None;
+    }
+    match std::str::from_utf8(value) {
+        Ok(string) if !string.chars().any(char::is_control) => Some(format!("'{string}'")),
+        _ => Some(HexBytesFormatter::borrowed(value).to_string()),
+    }
+}
+
+fn object_type(vertex: ObjectVertex) -> TypeVertex {
+    Object::new(vertex).type_().vertex()
+}
+
+fn attribute_type(vertex: AttributeVertex) -> TypeVertex {
+    Attribute::new(vertex).type_().vertex()
+}
+
+fn role_type(role_id: TypeID) -> TypeVertex {
+    RoleType::build_from_type_id(role_id).vertex()
+}
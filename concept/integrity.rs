@@ -0,0 +1,585 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    hash::Hash,
+};
+
+use bytes::{byte_array::ByteArray, util::HexBytesFormatter, Bytes};
+use encoding::{
+    graph::{
+        thing::{
+            edge::{ThingEdgeHas, ThingEdgeHasReverse, ThingEdgeLinks, ThingEdgeLinksIndex},
+            vertex_attribute::AttributeVertex,
+            vertex_object::ObjectVertex,
+        },
+        type_::{
+            index::LabelToTypeVertexIndex,
+            vertex::{PrefixedTypeVertexEncoding, TypeID, TypeVertex, TypeVertexEncoding},
+        },
+    },
+    layout::prefix::Prefix,
+    value::{
+        label::Label,
+        primitive_encoding::{decode_u64, encode_u64},
+    },
+    AsBytes, Keyable,
+};
+use lending_iterator::LendingIterator;
+use resource::constants::snapshot::{BUFFER_KEY_INLINE, BUFFER_VALUE_INLINE};
+use storage::{
+    key_range::KeyRange,
+    key_value::{StorageKey, StorageKeyArray},
+    snapshot::{iterator::SnapshotRangeIterator, ReadableSnapshot, WritableSnapshot},
+};
+
+use crate::{
+    error::ConceptReadError,
+    thing::{
+        attribute::Attribute,
+        conflict::{describe_attribute, describe_has, describe_links, describe_links_index, describe_type},
+        relation::Relation,
+        statistics::Statistics,
+        thing_manager::ThingManager,
+        ThingAPI,
+    },
+    type_::{
+        attribute_type::AttributeType, entity_type::EntityType, relation_type::RelationType, role_type::RoleType,
+        type_manager::type_reader::TypeReader, TypeAPI,
+    },
+};
+
+/// The result of checking the invariants between stored data and the indexes derived from it
+#[derive(Debug, Default)]
+pub struct IntegrityReport {
+    pub problems: Vec<IntegrityProblem>,
+    /// Statistics are only compared when they are as of the checked snapshot
+    pub statistics_checked: bool,
+}
+
+impl IntegrityReport {
+    pub fn is_consistent(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    pub fn repairable_count(&self) -> usize {
+        self.problems.iter().filter(|problem| problem.is_repairable()).count()
+    }
+
+    /// Rewrite or delete the derived index entries of every repairable problem, returning how many were repaired.
+    /// The snapshot must be at the same sequence number as the one checked.
+    pub fn repair(&self, snapshot: &mut impl WritableSnapshot) -> usize {
+        let mut repaired = 0;
+        for repair in self.problems.iter().filter_map(|problem| problem.repair.as_ref()) {
+            match repair {
+                IntegrityRepair::Put { key, value } => snapshot.put_val(key.clone(), value.clone()),
+                IntegrityRepair::Delete { key } => snapshot.delete(key.clone()),
+            }
+            repaired += 1;
+        }
+        repaired
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IntegrityProblem {
+    pub kind: IntegrityProblemKind,
+    pub description: String,
+    repair: Option<IntegrityRepair>,
+}
+
+impl IntegrityProblem {
+    /// Only indexes derivable from canonical data are repaired: canonical data itself is never modified
+    pub fn is_repairable(&self) -> bool {
+        self.repair.is_some()
+    }
+}
+
+impl fmt::Display for IntegrityProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.description)?;
+        if self.is_repairable() {
+            write!(f, " (repairable)")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntegrityProblemKind {
+    EdgeCountMalformed,
+    HasReverseMissing,
+    HasReverseCountMismatch,
+    HasReverseOrphaned,
+    LinksReverseMissing,
+    LinksReverseCountMismatch,
+    LinksReverseOrphaned,
+    LinksIndexMissing,
+    LinksIndexCountMismatch,
+    LinksIndexStale,
+    DependentAttributeWithoutOwners,
+    StatisticsCountMismatch,
+    TypeLabelMissing,
+    TypeLabelIndexMissing,
+    TypeLabelIndexMismatch,
+    TypeLabelIndexStale,
+}
+
+impl fmt::Display for IntegrityProblemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Self::EdgeCountMalformed => "edge count is not a valid count",
+            Self::HasReverseMissing => "has edge without its reverse edge",
+            Self::HasReverseCountMismatch => "has edge count differs from its reverse edge",
+            Self::HasReverseOrphaned => "reverse has edge without its canonical edge",
+            Self::LinksReverseMissing => "links edge without its reverse edge",
+            Self::LinksReverseCountMismatch => "links edge count differs from its reverse edge",
+            Self::LinksReverseOrphaned => "reverse links edge without its canonical edge",
+            Self::LinksIndexMissing => "relation index entry missing",
+            Self::LinksIndexCountMismatch => "relation index entry count differs from the role players",
+            Self::LinksIndexStale => "relation index entry without matching role players",
+            Self::DependentAttributeWithoutOwners => "attribute without owners whose type is not @independent",
+            Self::StatisticsCountMismatch => "statistics count differs from the data",
+            Self::TypeLabelMissing => "type without a label",
+            Self::TypeLabelIndexMissing => "type label index entry missing",
+            Self::TypeLabelIndexMismatch => "type label index entry points to another type",
+            Self::TypeLabelIndexStale => "type label index entry without a type with that label",
+        };
+        f.write_str(message)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum IntegrityRepair {
+    Put { key: StorageKeyArray<BUFFER_KEY_INLINE>, value: ByteArray<BUFFER_VALUE_INLINE> },
+    Delete { key: StorageKeyArray<BUFFER_KEY_INLINE> },
+}
+
+/// Check, without modifying anything, that:
+/// - every has and links edge has its reverse edge with the same count, and vice versa
+/// - relation index entries match the role players of relations whose type is indexed
+/// - attributes without owners are of @independent types
+/// - `statistics`, if they are as of the snapshot, count the instances and edges that exist
+/// - every type has a label, indexed to that type
+pub fn check_integrity(
+    snapshot: &impl ReadableSnapshot,
+    thing_manager: &ThingManager,
+    statistics: &Statistics,
+) -> Result<IntegrityReport, Box<ConceptReadError>> {
+    let mut checker = IntegrityChecker {
+        snapshot,
+        thing_manager,
+        relation_index_available: HashMap::new(),
+        counts: DataCounts::default(),
+        problems: Vec::new(),
+    };
+    checker.check_has()?;
+    checker.check_links()?;
+    checker.check_links_index()?;
+    checker.check_attributes()?;
+    checker.count_objects()?;
+    let statistics_checked = statistics.sequence_number == snapshot.open_sequence_number();
+    if statistics_checked {
+        checker.check_statistics(statistics);
+    }
+    checker.check_type_labels()?;
+    Ok(IntegrityReport { problems: checker.problems, statistics_checked })
+}
+
+#[derive(Debug, Default)]
+struct DataCounts {
+    entity_counts: HashMap<EntityType, u64>,
+    relation_counts: HashMap<RelationType, u64>,
+    attribute_counts: HashMap<AttributeType, u64>,
+    has_count: u64,
+    role_count: u64,
+}
+
+struct IntegrityChecker<'a, Snapshot> {
+    snapshot: &'a Snapshot,
+    thing_manager: &'a ThingManager,
+    relation_index_available: HashMap<RelationType, bool>,
+    counts: DataCounts,
+    problems: Vec<IntegrityProblem>,
+}
+
+impl<Snapshot: ReadableSnapshot> IntegrityChecker<'_, Snapshot> {
+    fn check_has(&mut self) -> Result<(), Box<ConceptReadError>> {
+        let mut iterator = self.iterate_prefix(ThingEdgeHas::prefix(), ThingEdgeHas::FIXED_WIDTH_ENCODING);
+        while let Some(result) = iterator.next() {
+            let (key, value) = result.map_err(|source| Box::new(ConceptReadError::SnapshotIterate { source }))?;
+            let has = ThingEdgeHas::decode(Bytes::Reference(key.bytes()));
+            self.counts.has_count += 1;
+            let describe = || describe_has(self.snapshot, self.thing_manager, has.from(), has.to());
+            let Some(count) = self.decode_count(&value, describe) else { continue };
+            let reverse = ThingEdgeHasReverse::new(has.to(), has.from()).into_storage_key().into_owned_array();
+            self.check_reverse(
+                reverse,
+                count,
+                (IntegrityProblemKind::HasReverseMissing, IntegrityProblemKind::HasReverseCountMismatch),
+                describe,
+            )?;
+        }
+
+        for keyspace_is_short in [true, false] {
+            let prefix = if keyspace_is_short {
+                ThingEdgeHasReverse::prefix_from_prefix_short(Prefix::VertexAttribute)
+            } else {
+                ThingEdgeHasReverse::prefix_from_prefix_long(Prefix::VertexAttribute)
+            };
+            let mut iterator = self.iterate_prefix(prefix, ThingEdgeHasReverse::FIXED_WIDTH_ENCODING);
+            while let Some(result) = iterator.next() {
+                let (key, _) = result.map_err(|source| Box::new(ConceptReadError::SnapshotIterate { source }))?;
+                let reverse = ThingEdgeHasReverse::decode(Bytes::Reference(key.bytes()));
+                let has = ThingEdgeHas::new(reverse.to(), reverse.from());
+                if !self.contains(has.into_storage_key())? {
+                    let description = describe_has(self.snapshot, self.thing_manager, reverse.to(), reverse.from());
+                    let key = StorageKeyArray::from(key.as_reference());
+                    self.report(IntegrityProblemKind::HasReverseOrphaned, description, IntegrityRepair::Delete { key });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Canonical links edges are sorted by relation, so the role players of each relation are checked against the
+    /// relation index together
+    fn check_links(&mut self) -> Result<(), Box<ConceptReadError>> {
+        let mut role_players: Vec<(ObjectVertex, TypeID, u64)> = Vec::new();
+        let mut current_relation = None;
+        let mut iterator = self.iterate_prefix(ThingEdgeLinks::prefix(), ThingEdgeLinks::FIXED_WIDTH_ENCODING);
+        while let Some(result) = iterator.next() {
+            let (key, value) = result.map_err(|source| Box::new(ConceptReadError::SnapshotIterate { source }))?;
+            let links = ThingEdgeLinks::new(Bytes::Reference(key.bytes()));
+            self.counts.role_count += 1;
+            if current_relation != Some(links.relation()) {
+                if let Some(relation) = current_relation {
+                    self.check_relation_index(relation, &role_players)?;
+                }
+                current_relation = Some(links.relation());
+                role_players.clear();
+            }
+
+            let describe =
+                || describe_links(self.snapshot, self.thing_manager, links.relation(), links.player(), links.role_id());
+            let Some(count) = self.decode_count(&value, describe) else { continue };
+            role_players.push((links.player(), links.role_id(), count));
+            let role_type = RoleType::build_from_type_id(links.role_id());
+            let reverse = ThingEdgeLinks::build_links_reverse(links.player(), links.relation(), role_type.vertex());
+            self.check_reverse(
+                reverse.into_storage_key().into_owned_array(),
+                count,
+                (IntegrityProblemKind::LinksReverseMissing, IntegrityProblemKind::LinksReverseCountMismatch),
+                describe,
+            )?;
+        }
+        if let Some(relation) = current_relation {
+            self.check_relation_index(relation, &role_players)?;
+        }
+
+        let mut iterator =
+            self.iterate_prefix(ThingEdgeLinks::prefix_reverse(), ThingEdgeLinks::FIXED_WIDTH_ENCODING_REVERSE);
+        while let Some(result) = iterator.next() {
+            let (key, _) = result.map_err(|source| Box::new(ConceptReadError::SnapshotIterate { source }))?;
+            let reverse = ThingEdgeLinks::new(Bytes::Reference(key.bytes()));
+            let role_type = RoleType::build_from_type_id(reverse.role_id());
+            let links = ThingEdgeLinks::build_links(reverse.relation(), reverse.player(), role_type.vertex());
+            if !self.contains(links.into_storage_key())? {
+                let description = describe_links(
+                    self.snapshot,
+                    self.thing_manager,
+                    reverse.relation(),
+                    reverse.player(),
+                    reverse.role_id(),
+                );
+                let key = StorageKeyArray::from(key.as_reference());
+                self.report(IntegrityProblemKind::LinksReverseOrphaned, description, IntegrityRepair::Delete { key });
+            }
+        }
+        Ok(())
+    }
+
+    /// For N repetitions of a role player, the self index entry has N-1 repetitions. Between different role
+    /// players, the index entry to a player has that player's repetitions.
+    fn check_relation_index(
+        &mut self,
+        relation: ObjectVertex,
+        role_players: &[(ObjectVertex, TypeID, u64)],
+    ) -> Result<(), Box<ConceptReadError>> {
+        if !self.is_relation_index_available(Relation::new(relation).type_())? {
+            return Ok(());
+        }
+        for &(from, from_role_id, from_count) in role_players {
+            for &(to, to_role_id, to_count) in role_players {
+                let expected = if from == to && from_role_id == to_role_id { from_count - 1 } else { to_count };
+                if expected == 0 {
+                    continue;
+                }
+                let index = ThingEdgeLinksIndex::new(from, to, relation, from_role_id, to_role_id);
+                let key = index.into_storage_key().into_owned_array();
+                let actual = self.get_count(&key)?;
+                if actual == Some(expected) {
+                    continue;
+                }
+                let description = format!(
+                    "{} (expected count {expected}, found {})",
+                    describe_links_index(self.snapshot, self.thing_manager, index),
+                    actual.map_or_else(|| "none".to_owned(), |count| count.to_string())
+                );
+                let kind = match actual {
+                    None => IntegrityProblemKind::LinksIndexMissing,
+                    Some(_) => IntegrityProblemKind::LinksIndexCountMismatch,
+                };
+                let value = ByteArray::copy(&encode_u64(expected));
+                self.report(kind, description, IntegrityRepair::Put { key, value });
+            }
+        }
+        Ok(())
+    }
+
+    /// Index entries that should exist are checked from the role players, so only entries that should not exist at
+    /// all are reported here
+    fn check_links_index(&mut self) -> Result<(), Box<ConceptReadError>> {
+        let mut iterator =
+            self.iterate_prefix(ThingEdgeLinksIndex::prefix(), ThingEdgeLinksIndex::FIXED_WIDTH_ENCODING);
+        while let Some(result) = iterator.next() {
+            let (key, _) = result.map_err(|source| Box::new(ConceptReadError::SnapshotIterate { source }))?;
+            let index = ThingEdgeLinksIndex::decode(Bytes::Reference(key.bytes()));
+            let from_role = RoleType::build_from_type_id(index.from_role_id());
+            let to_role = RoleType::build_from_type_id(index.to_role_id());
+            let from_links = ThingEdgeLinks::build_links(index.relation(), index.from(), from_role.vertex());
+            let to_links = ThingEdgeLinks::build_links(index.relation(), index.to(), to_role.vertex());
+            let from_count = self.get_count(&from_links.into_storage_key().into_owned_array())?;
+            let to_count = self.get_count(&to_links.into_storage_key().into_owned_array())?;
+            let is_self_index = index.from() == index.to() && index.from_role_id() == index.to_role_id();
+            let is_expected = match (from_count, to_count) {
+                (Some(from_count), Some(_)) if is_self_index => from_count > 1,
+                (Some(_), Some(_)) => true,
+                _ => false,
+            };
+            if !is_expected || !self.is_relation_index_available(Relation::new(index.relation()).type_())? {
+                let description = describe_links_index(self.snapshot, self.thing_manager, index);
+                let key = StorageKeyArray::from(key.as_reference());
+                self.report(IntegrityProblemKind::LinksIndexStale, description, IntegrityRepair::Delete { key });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_attributes(&mut self) -> Result<(), Box<ConceptReadError>> {
+        let type_manager = self.thing_manager.type_manager();
+        let independent_types = type_manager.get_independent_attribute_types(self.snapshot)?;
+        for is_short in [true, false] {
+            let keyspace = AttributeVertex::keyspace_for_is_short(is_short);
+            for attribute in self.thing_manager.get_instances::<Attribute>(keyspace, self.snapshot) {
+                let attribute = attribute?;
+                *self.counts.attribute_counts.entry(attribute.type_()).or_default() += 1;
+                if !independent_types.contains(&attribute.type_())
+                    && !attribute.has_owners(self.snapshot, self.thing_manager)
+                {
+                    let description = describe_attribute(self.snapshot, self.thing_manager, attribute.vertex());
+                    self.problems.push(IntegrityProblem {
+                        kind: IntegrityProblemKind::DependentAttributeWithoutOwners,
+                        description,
+                        repair: None,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn count_objects(&mut self) -> Result<(), Box<ConceptReadError>> {
+        for entity in self.thing_manager.get_entities(self.snapshot) {
+            *self.counts.entity_counts.entry(entity?.type_()).or_default() += 1;
+        }
+        for relation in self.thing_manager.get_relations(self.snapshot) {
+            *self.counts.relation_counts.entry(relation?.type_()).or_default() += 1;
+        }
+        Ok(())
+    }
+
+    fn check_statistics(&mut self, statistics: &Statistics) {
+        let counts = std::mem::take(&mut self.counts);
+        let totals = [
+            ("entity", statistics.total_entity_count, counts.entity_counts.values().sum()),
+            ("relation", statistics.total_relation_count, counts.relation_counts.values().sum()),
+            ("attribute", statistics.total_attribute_count, counts.attribute_counts.values().sum()),
+            ("has", statistics.total_has_count, counts.has_count),
+            ("role player", statistics.total_role_count, counts.role_count),
+        ];
+        for (name, recorded, actual) in totals {
+            self.check_count(format!("total {name} count"), recorded, actual);
+        }
+        self.check_type_counts("entity", &statistics.entity_counts, &counts.entity_counts);
+        self.check_type_counts("relation", &statistics.relation_counts, &counts.relation_counts);
+        self.check_type_counts("attribute", &statistics.attribute_counts, &counts.attribute_counts);
+    }
+
+    fn check_type_counts<T: TypeAPI + Hash>(
+        &mut self,
+        name: &str,
+        recorded: &HashMap<T, u64>,
+        actual: &HashMap<T, u64>,
+    ) {
+        let types: HashSet<&T> = recorded.keys().chain(actual.keys()).collect();
+        for type_ in types {
+            let recorded = recorded.get(type_).copied().unwrap_or(0);
+            let actual = actual.get(type_).copied().unwrap_or(0);
+            let label = describe_type(self.snapshot, self.thing_manager, type_.clone());
+            self.check_count(format!("{name} count of {label}"), recorded, actual);
+        }
+    }
+
+    fn check_count(&mut self, what: String, recorded: u64, actual: u64) {
+        if recorded != actual {
+            self.problems.push(IntegrityProblem {
+                kind: IntegrityProblemKind::StatisticsCountMismatch,
+                description: format!("{what} is {recorded}, but {actual} exist"),
+                repair: None,
+            });
+        }
+    }
+
+    fn check_type_labels(&mut self) -> Result<(), Box<ConceptReadError>> {
+        let mut labels = HashMap::new();
+        let snapshot = self.snapshot;
+        let mut types = Vec::new();
+        types.extend(labelled_types(snapshot, TypeReader::get_entity_types(snapshot)?)?);
+        types.extend(labelled_types(snapshot, TypeReader::get_relation_types(snapshot)?)?);
+        types.extend(labelled_types(snapshot, TypeReader::get_attribute_types(snapshot)?)?);
+        types.extend(labelled_types(snapshot, TypeReader::get_role_types(snapshot)?)?);
+        for (vertex, label) in types {
+            let Some(label) = label else {
+                self.problems.push(IntegrityProblem {
+                    kind: IntegrityProblemKind::TypeLabelMissing,
+                    description: format!("type {}", HexBytesFormatter::borrowed(&vertex.to_bytes())),
+                    repair: None,
+                });
+                continue;
+            };
+            let key = LabelToTypeVertexIndex::build(&label).into_storage_key().into_owned_array();
+            let indexed = snapshot
+                .get::<BUFFER_VALUE_INLINE>((&key).into())
+                .map_err(|source| Box::new(ConceptReadError::SnapshotGet { source }))?;
+            let value = ByteArray::copy(&vertex.to_bytes());
+            let kind = match indexed {
+                Some(indexed) if indexed == value => None,
+                Some(_) => Some(IntegrityProblemKind::TypeLabelIndexMismatch),
+                None => Some(IntegrityProblemKind::TypeLabelIndexMissing),
+            };
+            if let Some(kind) = kind {
+                let description = format!(
+                    "{} for type {}",
+                    label.scoped_name().as_str(),
+                    HexBytesFormatter::borrowed(&vertex.to_bytes())
+                );
+                self.report(kind, description, IntegrityRepair::Put { key, value });
+            }
+            labels.insert(vertex, label);
+        }
+
+        let mut iterator = self.iterate_prefix(
+            LabelToTypeVertexIndex::build_prefix().into_storage_key(),
+            LabelToTypeVertexIndex::FIXED_WIDTH_ENCODING,
+        );
+        while let Some(result) = iterator.next() {
+            let (key, value) = result.map_err(|source| Box::new(ConceptReadError::SnapshotIterate { source }))?;
+            let vertex = TypeVertex::decode(Bytes::Reference(&value));
+            let is_expected = labels.get(&vertex).is_some_and(|label| {
+                LabelToTypeVertexIndex::build(label).into_storage_key().as_reference() == key.as_reference()
+            });
+            if !is_expected {
+                let index = LabelToTypeVertexIndex::new(Bytes::Reference(key.bytes()));
+                let description = format!(
+                    "{} for type {}",
+                    index.identifier().as_str(),
+                    HexBytesFormatter::borrowed(&vertex.to_bytes())
+                );
+                let key = StorageKeyArray::from(key.as_reference());
+                self.report(IntegrityProblemKind::TypeLabelIndexStale, description, IntegrityRepair::Delete { key });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_reverse(
+        &mut self,
+        reverse: StorageKeyArray<BUFFER_KEY_INLINE>,
+        count: u64,
+        (missing, mismatch): (IntegrityProblemKind, IntegrityProblemKind),
+        describe: impl FnOnce() -> String,
+    ) -> Result<(), Box<ConceptReadError>> {
+        let reverse_count = self.get_count(&reverse)?;
+        let kind = match reverse_count {
+            Some(reverse_count) if reverse_count == count => return Ok(()),
+            Some(_) => mismatch,
+            None => missing,
+        };
+        let description = match reverse_count {
+            Some(reverse_count) => format!("{} (count {count}, reverse count {reverse_count})", describe()),
+            None => describe(),
+        };
+        let value = ByteArray::copy(&encode_u64(count));
+        self.report(kind, description, IntegrityRepair::Put { key: reverse, value });
+        Ok(())
+    }
+
+    fn is_relation_index_available(&mut self, relation_type: RelationType) -> Result<bool, Box<ConceptReadError>> {
+        if let Some(&available) = self.relation_index_available.get(&relation_type) {
+            return Ok(available);
+        }
+        let available = self.thing_manager.type_manager().relation_index_available(self.snapshot, relation_type)?;
+        self.relation_index_available.insert(relation_type, available);
+        Ok(available)
+    }
+
+    fn decode_count(&mut self, value: &[u8], describe: impl FnOnce() -> String) -> Option<u64> {
+        match <[u8; 8]>::try_from(value) {
+            Ok(bytes) => Some(decode_u64(bytes)),
+            Err(_) => {
+                self.problems.push(IntegrityProblem {
+                    kind: IntegrityProblemKind::EdgeCountMalformed,
+                    description: format!("{} has count {}", describe(), HexBytesFormatter::borrowed(value)),
+                    repair: None,
+                });
+                None
+            }
+        }
+    }
+
+    fn get_count(&self, key: &StorageKeyArray<BUFFER_KEY_INLINE>) -> Result<Option<u64>, Box<ConceptReadError>> {
+        self.snapshot
+            .get_mapped(key.into(), |value| <[u8; 8]>::try_from(value).map(decode_u64).ok())
+            .map(Option::flatten)
+            .map_err(|source| Box::new(ConceptReadError::SnapshotGet { source }))
+    }
+
+    fn contains<const INLINE: usize>(&self, key: StorageKey<'_, INLINE>) -> Result<bool, Box<ConceptReadError>> {
+        self.snapshot.contains(key.as_reference()).map_err(|source| Box::new(ConceptReadError::SnapshotGet { source }))
+    }
+
+    fn iterate_prefix<const INLINE: usize>(
+        &self,
+        prefix: StorageKey<'static, INLINE>,
+        fixed_width: bool,
+    ) -> SnapshotRangeIterator {
+        self.snapshot.iterate_range(&KeyRange::new_within(prefix, fixed_width))
+    }
+
+    fn report(&mut self, kind: IntegrityProblemKind, description: String, repair: IntegrityRepair) {
+        self.problems.push(IntegrityProblem { kind, description, repair: Some(repair) });
+    }
+}
+
+fn labelled_types<T: TypeAPI>(
+    snapshot: &impl ReadableSnapshot,
+    types: Vec<T>,
+) -> Result<Vec<(TypeVertex, Option<Label>)>, Box<ConceptReadError>> {
+    types.into_iter().map(|type_| Ok((type_.vertex(), TypeReader::get_label(snapshot, type_)?))).collect()
+}
@@ -7,7 +7,10 @@
 #![deny(unused_must_use)]
 #![deny(elided_lifetimes_in_paths)]
 
+pub mod change_stream;
 pub mod error;
+pub mod inspect;
+pub mod integrity;
 pub mod iterator;
 pub mod thing;
 pub mod type_;
@@ -0,0 +1,173 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+#![deny(unused_must_use)]
+
+use concept::{
+    change_stream::{read_changes, ChangeEvent},
+    thing::{object::ObjectAPI, ThingAPI},
+    type_::{Ordering, OwnerAPI},
+};
+use encoding::value::{label::Label, value::Value, value_type::ValueType};
+use storage::{isolation_manager::CommitType, sequence_number::SequenceNumber, snapshot::CommittableSnapshot};
+use test_utils_concept::{load_managers, setup_concept_storage};
+use test_utils_encoding::create_core_storage;
+
+#[test]
+fn schema_and_data_changes() {
+    let (_tmp_dir, mut storage) = create_core_storage();
+    setup_concept_storage(&mut storage);
+    let (type_manager, thing_manager) = load_managers(storage.clone(), None);
+    let start = storage.snapshot_watermark().next();
+
+    let mut snapshot = storage.clone().open_snapshot_schema();
+    let person_type = type_manager.create_entity_type(&mut snapshot, &Label::build("person")).unwrap();
+    let name_type = type_manager.create_attribute_type(&mut snapshot, &Label::build("name")).unwrap();
+    name_type.set_value_type(&mut snapshot, &type_manager, &thing_manager, ValueType::String).unwrap();
+    person_type.set_owns(&mut snapshot, &type_manager, &thing_manager, name_type, Ordering::Unordered).unwrap();
+    thing_manager.finalise(&mut snapshot).unwrap();
+    let schema_commit = snapshot.commit().unwrap().unwrap();
+
+    let mut snapshot = storage.clone().open_snapshot_write();
+    let person = thing_manager.create_entity(&mut snapshot, person_type).unwrap();
+    let name = thing_manager.create_attribute(&mut snapshot, name_type, Value::String("alice".into())).unwrap();
+    person.set_has_unordered(&mut snapshot, &thing_manager, &name).unwrap();
+    thing_manager.finalise(&mut snapshot).unwrap();
+    let data_commit = snapshot.commit().unwrap().unwrap();
+
+    let changes = read_changes(&storage, start, storage.snapshot_watermark()).unwrap();
+    assert_eq!(changes.len(), 2);
+
+    assert_eq!(changes[0].sequence_number, schema_commit);
+    assert!(matches!(changes[0].commit_type, CommitType::Schema));
+    assert!(matches!(changes[0].events.as_slice(), [ChangeEvent::SchemaChanged]));
+
+    assert_eq!(changes[1].sequence_number, data_commit);
+    assert!(matches!(changes[1].commit_type, CommitType::Data));
+    let events = &changes[1].events;
+    assert_eq!(events.len(), 3);
+    assert!(events.iter().any(|event| matches!(event, ChangeEvent::EntityCreated { entity } if *entity == person)));
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, ChangeEvent::AttributeCreated { attribute } if attribute.type_() == name_type)));
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, ChangeEvent::HasAdded { attribute, .. } if attribute.type_() == name_type)));
+
+    // resuming after the schema commit only yields the data commit
+    let resumed = read_changes(&storage, schema_commit.next(), storage.snapshot_watermark()).unwrap();
+    assert_eq!(resumed.len(), 1);
+    assert_eq!(resumed[0].sequence_number, data_commit);
+}
+
+#[test]
+fn concurrent_deletes_produce_one_event() {
+    let (_tmp_dir, mut storage) = create_core_storage();
+    setup_concept_storage(&mut storage);
+    let (type_manager, thing_manager) = load_managers(storage.clone(), None);
+
+    let mut snapshot = storage.clone().open_snapshot_schema();
+    let person_type = type_manager.create_entity_type(&mut snapshot, &Label::build("person")).unwrap();
+    let person = thing_manager.create_entity(&mut snapshot, person_type).unwrap();
+    thing_manager.finalise(&mut snapshot).unwrap();
+    let create_commit = snapshot.commit().unwrap().unwrap();
+
+    for _ in 0..2 {
+        let mut snapshot = storage.clone().open_snapshot_write_at(create_commit);
+        person.delete(&mut snapshot, &thing_manager).unwrap();
+        thing_manager.finalise(&mut snapshot).unwrap();
+        snapshot.commit().unwrap().unwrap();
+    }
+
+    let changes = read_changes(&storage, create_commit.next(), storage.snapshot_watermark()).unwrap();
+    let deletes = changes
+        .iter()
+        .flat_map(|changes| changes.events.iter())
+        .filter(|event| matches!(event, ChangeEvent::EntityDeleted { entity } if *entity == person))
+        .count();
+    assert_eq!(deletes, 1);
+}
+
+#[test]
+fn concurrent_deletes_in_separate_windows_produce_one_event() {
+    let (_tmp_dir, mut storage) = create_core_storage();
+    setup_concept_storage(&mut storage);
+    let (type_manager, thing_manager) = load_managers(storage.clone(), None);
+
+    let mut snapshot = storage.clone().open_snapshot_schema();
+    let person_type = type_manager.create_entity_type(&mut snapshot, &Label::build("person")).unwrap();
+    let person = thing_manager.create_entity(&mut snapshot, person_type).unwrap();
+    thing_manager.finalise(&mut snapshot).unwrap();
+    let create_commit = snapshot.commit().unwrap().unwrap();
+
+    let mut delete_commits = Vec::new();
+    for _ in 0..2 {
+        let mut snapshot = storage.clone().open_snapshot_write_at(create_commit);
+        person.delete(&mut snapshot, &thing_manager).unwrap();
+        thing_manager.finalise(&mut snapshot).unwrap();
+        delete_commits.push(snapshot.commit().unwrap().unwrap());
+    }
+
+    // each window only sees one of the deletes, as when a consumer resumes between them
+    let deletes = delete_commits
+        .iter()
+        .flat_map(|&commit| read_changes(&storage, commit, commit).unwrap())
+        .flat_map(|changes| changes.events)
+        .filter(|event| matches!(event, ChangeEvent::EntityDeleted { entity } if *entity == person))
+        .count();
+    assert_eq!(deletes, 1);
+}
+
+#[test]
+fn resumed_windows_match_a_single_read() {
+    let (_tmp_dir, mut storage) = create_core_storage();
+    setup_concept_storage(&mut storage);
+    let (type_manager, thing_manager) = load_managers(storage.clone(), None);
+    let start = storage.snapshot_watermark().next();
+
+    let mut snapshot = storage.clone().open_snapshot_schema();
+    let person_type = type_manager.create_entity_type(&mut snapshot, &Label::build("person")).unwrap();
+    thing_manager.finalise(&mut snapshot).unwrap();
+    snapshot.commit().unwrap().unwrap();
+
+    let mut people = Vec::new();
+    for _ in 0..3 {
+        let mut snapshot = storage.clone().open_snapshot_write();
+        people.push(thing_manager.create_entity(&mut snapshot, person_type).unwrap());
+        thing_manager.finalise(&mut snapshot).unwrap();
+        snapshot.commit().unwrap().unwrap();
+    }
+    let mut snapshot = storage.clone().open_snapshot_write();
+    people[0].delete(&mut snapshot, &thing_manager).unwrap();
+    thing_manager.finalise(&mut snapshot).unwrap();
+    snapshot.commit().unwrap().unwrap();
+
+    let end = storage.snapshot_watermark();
+    let all = read_changes(&storage, start, end).unwrap();
+    assert_eq!(all.len(), 5);
+
+    let mut resumed = Vec::new();
+    let mut next = start;
+    while next <= end {
+        let window = read_changes(&storage, next, next).unwrap();
+        next = window.last().map(|changes| changes.sequence_number).unwrap_or(next).next();
+        resumed.extend(window);
+    }
+    assert_eq!(resumed.len(), all.len());
+    for (resumed, all) in resumed.iter().zip(&all) {
+        assert_eq!(resumed.sequence_number, all.sequence_number);
+        assert_eq!(format!("{:?}", resumed.events), format!("{:?}", all.events));
+    }
+}
+
+#[test]
+fn empty_range() {
+    let (_tmp_dir, mut storage) = create_core_storage();
+    setup_concept_storage(&mut storage);
+    let watermark = storage.snapshot_watermark();
+    assert!(read_changes(&storage, watermark.next(), watermark).unwrap().is_empty());
+    assert!(read_changes(&storage, SequenceNumber::MIN.next(), SequenceNumber::MIN).unwrap().is_empty());
+}
@@ -0,0 +1,110 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+#![deny(unused_must_use)]
+
+use concept::{
+    integrity::{check_integrity, IntegrityProblemKind},
+    thing::{object::ObjectAPI, statistics::Statistics, ThingAPI},
+    type_::{annotation::AnnotationCardinality, relates::RelatesAnnotation, Ordering, OwnerAPI, PlayerAPI},
+};
+use encoding::{
+    graph::{
+        thing::edge::{ThingEdgeHasReverse, ThingEdgeLinks, ThingEdgeLinksIndex},
+        type_::vertex::TypeVertexEncoding,
+        Typed,
+    },
+    value::{label::Label, value::Value, value_type::ValueType},
+    Keyable,
+};
+use storage::{
+    sequence_number::SequenceNumber,
+    snapshot::{CommittableSnapshot, WritableSnapshot},
+};
+use test_utils_concept::{load_managers, setup_concept_storage};
+use test_utils_encoding::create_core_storage;
+
+#[test]
+fn damaged_indexes_are_reported_and_repaired() {
+    let (_tmp_dir, mut storage) = create_core_storage();
+    setup_concept_storage(&mut storage);
+    let (type_manager, thing_manager) = load_managers(storage.clone(), None);
+
+    let mut snapshot = storage.clone().open_snapshot_schema();
+    let person_type = type_manager.create_entity_type(&mut snapshot, &Label::build("person")).unwrap();
+    let name_type = type_manager.create_attribute_type(&mut snapshot, &Label::build("name")).unwrap();
+    name_type.set_value_type(&mut snapshot, &type_manager, &thing_manager, ValueType::String).unwrap();
+    person_type.set_owns(&mut snapshot, &type_manager, &thing_manager, name_type, Ordering::Unordered).unwrap();
+    let friendship_type = type_manager.create_relation_type(&mut snapshot, &Label::build("friendship")).unwrap();
+    let friend_relates = friendship_type
+        .create_relates(&mut snapshot, &type_manager, &thing_manager, "friend", Ordering::Unordered)
+        .unwrap();
+    let friend_role = friend_relates.role();
+    person_type.set_plays(&mut snapshot, &type_manager, &thing_manager, friend_role).unwrap();
+    friend_relates
+        .set_annotation(
+            &mut snapshot,
+            &type_manager,
+            &thing_manager,
+            RelatesAnnotation::Cardinality(AnnotationCardinality::new(1, Some(4))),
+        )
+        .unwrap();
+
+    let alice = thing_manager.create_entity(&mut snapshot, person_type).unwrap();
+    let bob = thing_manager.create_entity(&mut snapshot, person_type).unwrap();
+    let name = thing_manager.create_attribute(&mut snapshot, name_type, Value::String("alice".into())).unwrap();
+    alice.set_has_unordered(&mut snapshot, &thing_manager, &name).unwrap();
+    let friendship = thing_manager.create_relation(&mut snapshot, friendship_type).unwrap();
+    friendship.add_player(&mut snapshot, &thing_manager, friend_role, alice.into_object()).unwrap();
+    friendship.add_player(&mut snapshot, &thing_manager, friend_role, bob.into_object()).unwrap();
+    thing_manager.finalise(&mut snapshot).unwrap();
+    snapshot.commit().unwrap().unwrap();
+
+    let mut statistics = Statistics::new(SequenceNumber::MIN);
+    statistics.may_synchronise(&storage).unwrap();
+    let snapshot = storage.clone().open_snapshot_read();
+    let report = check_integrity(&snapshot, &thing_manager, &statistics).unwrap();
+    assert!(report.statistics_checked);
+    assert!(report.is_consistent(), "{:?}", report.problems);
+    drop(snapshot);
+
+    // damage the derived indexes directly, bypassing the thing manager
+    let mut snapshot = storage.clone().open_snapshot_write();
+    let has_reverse = ThingEdgeHasReverse::new(name.vertex(), alice.vertex());
+    snapshot.delete(has_reverse.into_storage_key().into_owned_array());
+    let links_reverse = ThingEdgeLinks::build_links_reverse(bob.vertex(), friendship.vertex(), friend_role.vertex());
+    snapshot.delete(links_reverse.into_storage_key().into_owned_array());
+    let index = ThingEdgeLinksIndex::new(
+        alice.vertex(),
+        bob.vertex(),
+        friendship.vertex(),
+        friend_role.vertex().type_id_(),
+        friend_role.vertex().type_id_(),
+    );
+    snapshot.delete(index.into_storage_key().into_owned_array());
+    snapshot.commit().unwrap().unwrap();
+
+    let statistics = Statistics::new(SequenceNumber::MIN);
+    let mut snapshot = storage.clone().open_snapshot_write();
+    let report = check_integrity(&snapshot, &thing_manager, &statistics).unwrap();
+    assert!(!report.statistics_checked);
+    let mut kinds: Vec<_> = report.problems.iter().map(|problem| problem.kind).collect();
+    kinds.sort_by_key(|kind| format!("{kind:?}"));
+    assert_eq!(
+        kinds,
+        vec![
+            IntegrityProblemKind::HasReverseMissing,
+            IntegrityProblemKind::LinksIndexMissing,
+            IntegrityProblemKind::LinksReverseMissing,
+        ]
+    );
+    assert_eq!(report.repair(&mut snapshot), 3);
+    snapshot.commit().unwrap().unwrap();
+
+    let snapshot = storage.clone().open_snapshot_read();
+    let report = check_integrity(&snapshot, &thing_manager, &statistics).unwrap();
+    assert!(report.is_consistent(), "{:?}", report.problems);
+}
@@ -8,10 +8,12 @@
 
 use std::{borrow::Cow, collections::HashMap, ops::Bound};
 
+use bytes::util::HexBytesFormatter;
 use concept::{
     error::ConceptReadError,
     thing::{
         attribute::Attribute,
+        conflict::describe_conflict,
         entity::Entity,
         object::{Object, ObjectAPI},
         relation::Relation,
@@ -40,7 +42,8 @@ use encoding::{
 use itertools::Itertools;
 use storage::{
     durability_client::WALClient,
-    snapshot::{CommittableSnapshot, ReadSnapshot, WritableSnapshot, WriteSnapshot},
+    snapshot::{CommittableSnapshot, ReadSnapshot, SnapshotError, WritableSnapshot, WriteSnapshot},
+    StorageCommitError,
 };
 use test_utils_concept::{load_managers, setup_concept_storage};
 use test_utils_encoding::create_core_storage;
@@ -1556,3 +1559,52 @@ pub fn define_struct(
     }
     struct_key
 }
+
+#[test]
+fn conflicting_commit_is_described_by_label_and_iid() {
+    let (_tmp_dir, mut storage) = create_core_storage();
+    setup_concept_storage(&mut storage);
+
+    let person_label = Label::build("person");
+    let name_label = Label::build("name");
+
+    let mut snapshot: WriteSnapshot<WALClient> = storage.clone().open_snapshot_write();
+    let person = {
+        let (type_manager, thing_manager) = load_managers(storage.clone(), None);
+        let name_type = type_manager.create_attribute_type(&mut snapshot, &name_label).unwrap();
+        name_type.set_value_type(&mut snapshot, &type_manager, &thing_manager, ValueType::String).unwrap();
+        let person_type = type_manager.create_entity_type(&mut snapshot, &person_label).unwrap();
+        person_type.set_owns(&mut snapshot, &type_manager, &thing_manager, name_type, Ordering::Unordered).unwrap();
+        let person = thing_manager.create_entity(&mut snapshot, person_type).unwrap();
+        assert!(thing_manager.finalise(&mut snapshot).is_ok());
+        person
+    };
+    snapshot.commit().unwrap();
+
+    // one snapshot deletes the person while a concurrent one gives it a name, which requires it to exist
+    let mut snapshot_1: WriteSnapshot<WALClient> = storage.clone().open_snapshot_write();
+    let mut snapshot_2: WriteSnapshot<WALClient> = storage.clone().open_snapshot_write();
+    {
+        let (_, thing_manager) = load_managers(storage.clone(), None);
+        person.delete(&mut snapshot_1, &thing_manager).unwrap();
+        assert!(thing_manager.finalise(&mut snapshot_1).is_ok());
+    }
+    {
+        let (type_manager, thing_manager) = load_managers(storage.clone(), None);
+        let name_type = type_manager.get_attribute_type(&snapshot_2, &name_label).unwrap().unwrap();
+        let name =
+            thing_manager.create_attribute(&mut snapshot_2, name_type, Value::String(Cow::Borrowed("Alice"))).unwrap();
+        person.set_has_unordered(&mut snapshot_2, &thing_manager, &name).unwrap();
+        assert!(thing_manager.finalise(&mut snapshot_2).is_ok());
+    }
+    snapshot_1.commit().unwrap();
+    let result = snapshot_2.commit();
+
+    let Err(SnapshotError::Commit { typedb_source: StorageCommitError::Isolation { conflict, .. } }) = &result else {
+        panic!("expected an isolation conflict, found {result:?}");
+    };
+    let snapshot: ReadSnapshot<WALClient> = storage.clone().open_snapshot_read();
+    let (_, thing_manager) = load_managers(storage.clone(), None);
+    let description = describe_conflict(&snapshot, &thing_manager, conflict);
+    assert_eq!(description, format!("person:{}", HexBytesFormatter::borrowed(&person.iid())));
+}
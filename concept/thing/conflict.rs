@@ -0,0 +1,218 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use bytes::{util::HexBytesFormatter, Bytes};
+use encoding::{
+    graph::{
+        thing::{
+            edge::{ThingEdgeHas, ThingEdgeHasReverse, ThingEdgeLinks, ThingEdgeLinksIndex},
+            vertex_attribute::{AttributeID, AttributeVertex},
+            vertex_object::ObjectVertex,
+            ThingVertex,
+        },
+        type_::vertex::{PrefixedTypeVertexEncoding, TypeID, TypeVertex},
+        Typed,
+    },
+    layout::{infix::Infix, prefix::Prefix},
+    value::value_type::ValueTypeCategory,
+};
+use resource::constants::snapshot::BUFFER_KEY_INLINE;
+use storage::{
+    isolation_manager::IsolationConflict,
+    key_value::{StorageKeyArray, StorageKeyReference},
+    snapshot::ReadableSnapshot,
+};
+
+use crate::{
+    thing::{attribute::Attribute, object::Object, thing_manager::ThingManager, ThingAPI},
+    type_::{attribute_type::AttributeType, role_type::RoleType, TypeAPI},
+};
+
+/// Describe what an isolation conflict was about in terms of concepts, such as
+/// `has edge person:[1E00 0000 0000 0000 0000 01] -> name "Bob"` or `key lock on email "a@b"`.
+/// Anything that cannot be decoded, for example because the schema changed since, is described by its bytes.
+pub fn describe_conflict(
+    snapshot: &impl ReadableSnapshot,
+    thing_manager: &ThingManager,
+    conflict: &IsolationConflict,
+) -> String {
+    match conflict {
+        IsolationConflict::DeletingRequiredKey { key, .. }
+        | IsolationConflict::RequireDeletedKey { key, .. }
+        | IsolationConflict::ReadSetWritten { key, .. } => describe_key(snapshot, thing_manager, key),
+        IsolationConflict::ExclusiveLock { lock, .. } => describe_lock(snapshot, thing_manager, lock),
+    }
+}
+
+fn describe_key(
+    snapshot: &impl ReadableSnapshot,
+    thing_manager: &ThingManager,
+    key: &StorageKeyArray<BUFFER_KEY_INLINE>,
+) -> String {
+    let reference = StorageKeyReference::from(key);
+    if ObjectVertex::is_entity_vertex(reference) || ObjectVertex::is_relation_vertex(reference) {
+        describe_object(snapshot, thing_manager, ObjectVertex::decode(key.bytes()))
+    } else if AttributeVertex::is_attribute_vertex(reference) {
+        describe_attribute(snapshot, thing_manager, AttributeVertex::decode(key.bytes()))
+    } else if ThingEdgeHas::is_has(key) {
+        let edge = ThingEdgeHas::decode(Bytes::Reference(key.bytes()));
+        describe_has(snapshot, thing_manager, edge.from(), edge.to())
+    } else if ThingEdgeHasReverse::is_has_reverse(reference) {
+        let edge = ThingEdgeHasReverse::decode(Bytes::Reference(key.bytes()));
+        describe_has(snapshot, thing_manager, edge.to(), edge.from())
+    } else if ThingEdgeLinks::is_links(key) || ThingEdgeLinks::is_links_reverse(reference) {
+        let edge = ThingEdgeLinks::new(Bytes::Reference(key.bytes()));
+        describe_links(snapshot, thing_manager, edge.relation(), edge.player(), edge.role_id())
+    } else {
+        format!("key {} in keyspace {}", HexBytesFormatter::borrowed(key.bytes()), key.keyspace_id())
+    }
+}
+
+fn describe_lock(snapshot: &impl ReadableSnapshot, thing_manager: &ThingManager, lock: &[u8]) -> String {
+    let unique = Infix::PropertyAnnotationUnique.infix_id().bytes();
+    let cardinality = Infix::PropertyAnnotationCardinality.infix_id().bytes();
+    if let Some(rest) = lock.strip_prefix(unique.as_slice()) {
+        // attribute type, attribute id, attribute value, owner, owner type
+        if let Some(attribute) = rest.get(TypeVertex::LENGTH..).and_then(|rest| decode_attribute_id(rest)) {
+            let type_id = TypeID::decode([rest[1], rest[2]]);
+            return format!(
+                "key lock on {}",
+                describe_attribute(snapshot, thing_manager, AttributeVertex::new(type_id, attribute))
+            );
+        }
+    } else if let Some(rest) = lock.strip_prefix(cardinality.as_slice()) {
+        // object, capability edge prefix, interface type
+        if let (Some(object), Some(&[edge_prefix, _, high, low])) = (
+            rest.get(..ObjectVertex::LENGTH).and_then(ObjectVertex::try_from_bytes),
+            rest.get(ObjectVertex::LENGTH..).and_then(|rest| <&[u8; 4]>::try_from(rest).ok()),
+        ) {
+            let interface_id = TypeID::decode([high, low]);
+            let (capability, interface) = if [edge_prefix] == Prefix::EdgeOwns.prefix_id().to_bytes() {
+                ("owns", describe_type(snapshot, thing_manager, AttributeType::build_from_type_id(interface_id)))
+            } else if [edge_prefix] == Prefix::EdgePlays.prefix_id().to_bytes() {
+                ("plays", describe_type(snapshot, thing_manager, RoleType::build_from_type_id(interface_id)))
+            } else {
+                ("relates", describe_type(snapshot, thing_manager, RoleType::build_from_type_id(interface_id)))
+            };
+            return format!(
+                "cardinality lock on {} {capability} {interface}",
+                describe_object(snapshot, thing_manager, object)
+            );
+        }
+    } else if lock.starts_with(&Prefix::VertexAttribute.prefix_id().to_bytes()) && lock.len() > TypeVertex::LENGTH {
+        let attribute_type = AttributeType::build_from_type_id(TypeID::decode([lock[1], lock[2]]));
+        return format!(
+            "attribute id lock on {} with hash {}",
+            describe_type(snapshot, thing_manager, attribute_type),
+            HexBytesFormatter::borrowed(&lock[TypeVertex::LENGTH..])
+        );
+    } else if lock.starts_with(&Prefix::PropertyObjectVertex.prefix_id().to_bytes()) {
+        if let Some(object) = lock.get(1..1 + ObjectVertex::LENGTH).and_then(ObjectVertex::try_from_bytes) {
+            return format!("ordering lock on {}", describe_object(snapshot, thing_manager, object));
+        }
+    }
+    format!("lock {}", HexBytesFormatter::borrowed(lock))
+}
+
+fn decode_attribute_id(bytes: &[u8]) -> Option<AttributeID> {
+    let &category = bytes.first()?;
+    let is_known_category = [
+        ValueTypeCategory::Boolean,
+        ValueTypeCategory::Long,
+        ValueTypeCategory::Double,
+        ValueTypeCategory::Decimal,
+        ValueTypeCategory::Date,
+        ValueTypeCategory::DateTime,
+        ValueTypeCategory::DateTimeTZ,
+        ValueTypeCategory::Duration,
+        ValueTypeCategory::String,
+        ValueTypeCategory::Struct,
+    ]
+    .iter()
+    .any(|known| known.to_bytes() == [category]);
+    if !is_known_category {
+        return None;
+    }
+    let length = AttributeID::value_type_encoding_length(ValueTypeCategory::from_bytes([category]));
+    bytes.get(..length).map(AttributeID::new)
+}
+
+pub(crate) fn describe_has(
+    snapshot: &impl ReadableSnapshot,
+    thing_manager: &ThingManager,
+    owner: ObjectVertex,
+    attribute: AttributeVertex,
+) -> String {
+    format!(
+        "has edge {} -> {}",
+        describe_object(snapshot, thing_manager, owner),
+        describe_attribute(snapshot, thing_manager, attribute)
+    )
+}
+
+pub(crate) fn describe_links(
+    snapshot: &impl ReadableSnapshot,
+    thing_manager: &ThingManager,
+    relation: ObjectVertex,
+    player: ObjectVertex,
+    role_id: TypeID,
+) -> String {
+    format!(
+        "links edge {} -[{}]-> {}",
+        describe_object(snapshot, thing_manager, relation),
+        describe_type(snapshot, thing_manager, RoleType::build_from_type_id(role_id)),
+        describe_object(snapshot, thing_manager, player),
+    )
+}
+
+pub(crate) fn describe_links_index(
+    snapshot: &impl ReadableSnapshot,
+    thing_manager: &ThingManager,
+    index: ThingEdgeLinksIndex,
+) -> String {
+    format!(
+        "relation index {}: {} -[{}]- {} -[{}]- {}",
+        describe_object(snapshot, thing_manager, index.relation()),
+        describe_object(snapshot, thing_manager, index.from()),
+        describe_type(snapshot, thing_manager, RoleType::build_from_type_id(index.from_role_id())),
+        describe_object(snapshot, thing_manager, index.relation()),
+        describe_type(snapshot, thing_manager, RoleType::build_from_type_id(index.to_role_id())),
+        describe_object(snapshot, thing_manager, index.to()),
+    )
+}
+
+pub(crate) fn describe_object(
+    snapshot: &impl ReadableSnapshot,
+    thing_manager: &ThingManager,
+    vertex: ObjectVertex,
+) -> String {
+    let object = Object::new(vertex);
+    format!("{}:{}", describe_type(snapshot, thing_manager, object.type_()), HexBytesFormatter::borrowed(&object.iid()))
+}
+
+pub(crate) fn describe_attribute(
+    snapshot: &impl ReadableSnapshot,
+    thing_manager: &ThingManager,
+    vertex: AttributeVertex,
+) -> String {
+    let attribute = Attribute::new(vertex);
+    let label = describe_type(snapshot, thing_manager, attribute.type_());
+    match attribute.get_value(snapshot, thing_manager) {
+        Ok(value) => format!("{label} {value}"),
+        Err(_) => format!("{label}:{}", HexBytesFormatter::borrowed(&attribute.iid())),
+    }
+}
+
+pub(crate) fn describe_type(
+    snapshot: &impl ReadableSnapshot,
+    thing_manager: &ThingManager,
+    type_: impl TypeAPI,
+) -> String {
+    match type_.get_label(snapshot, thing_manager.type_manager()) {
+        Ok(label) => label.scoped_name().as_str().to_owned(),
+        Err(_) => format!("type {}", type_.vertex().type_id_()),
+    }
+}
@@ -22,6 +22,7 @@ use crate::{
 };
 
 pub mod attribute;
+pub mod conflict;
 pub mod entity;
 pub mod has;
 pub mod object;
@@ -26,7 +26,7 @@ use resource::constants::{database::STATISTICS_DURABLE_WRITE_CHANGE_PERCENT, sna
 use serde::{Deserialize, Serialize};
 use storage::{
     durability_client::{DurabilityClient, DurabilityClientError, DurabilityRecord, UnsequencedDurabilityRecord},
-    isolation_manager::CommitType,
+    isolation_manager::{CommitRecord, CommitType},
     iterator::MVCCReadError,
     key_value::{StorageKeyArray, StorageKeyReference},
     keyspace::IteratorPool,
@@ -147,6 +147,9 @@ impl Statistics {
                         commits.insert(seq, writes);
                         self.update_writes(&commits, storage).map_err(|err| DataRead { source: err })?;
                     }
+                    CommitType::BulkLoad => {
+                        // the marker carries no operations, and the ingested data is counted by `update_ingested`
+                    }
                 }
             }
         }
@@ -163,6 +166,30 @@ impl Statistics {
         Ok(())
     }
 
+    /// Account for a commit ingested directly into storage, whose operations are not in the WAL, then durably write
+    /// the result so recovery does not rely on the WAL for it either.
+    pub fn update_ingested(
+        &mut self,
+        sequence_number: SequenceNumber,
+        commit_record: CommitRecord,
+        storage: &MVCCStorage<impl DurabilityClient>,
+    ) -> Result<(), StatisticsError> {
+        use StatisticsError::DataRead;
+
+        // catch up to the ingested commit, which appears empty in the WAL
+        self.may_synchronise(storage)?;
+        debug_assert!(self.sequence_number >= sequence_number);
+        let writes = CommittedWrites {
+            open_sequence_number: commit_record.open_sequence_number(),
+            operations: commit_record.into_operations(),
+        };
+        let commits = BTreeMap::from([(sequence_number, writes)]);
+        let sequence_number = self.sequence_number;
+        self.update_writes(&commits, storage).map_err(|err| DataRead { source: err })?;
+        self.sequence_number = sequence_number;
+        self.durably_write(storage)
+    }
+
     pub fn durably_write(&mut self, storage: &MVCCStorage<impl DurabilityClient>) -> Result<(), StatisticsError> {
         use StatisticsError::DurablyWrite;
         storage.durability().unsequenced_write(self).map_err(|err| DurablyWrite { typedb_source: err })?;
@@ -385,7 +412,7 @@ impl Statistics {
     }
 }
 
-fn write_to_delta<D>(
+pub(crate) fn write_to_delta<D>(
     write_key: &StorageKeyArray<{ BUFFER_KEY_INLINE }>,
     write: &Write,
     open_sequence_number: SequenceNumber,
@@ -399,11 +426,18 @@ fn write_to_delta<D>(
         Write::Delete => {
             if commits.range(concurrent_commit_range).any(|(_, writes)| {
                 matches!(
-                    writes.operations.writes_in(write_key.keyspace_id()).get_write(write_key.bytes()),
+                    writes.operations.writes_in(write_key.keyspace_id()).get_write(write_key.bytes()).as_deref(),
                     Some(Write::Delete)
                 )
             }) {
                 Ok(0)
+            } else if open_sequence_number < *commits.first_key_value().unwrap().0 {
+                // a concurrent delete may have been committed before the current set of commits, so check storage
+                if storage.get::<0>(&IteratorPool::new(), write_key, commit_sequence_number.previous())?.is_some() {
+                    Ok(-1)
+                } else {
+                    Ok(0)
+                }
             } else {
                 Ok(-1)
             }
@@ -444,9 +478,9 @@ fn write_to_delta<D>(
     }
 }
 
-struct CommittedWrites {
-    open_sequence_number: SequenceNumber,
-    operations: OperationsBuffer,
+pub(crate) struct CommittedWrites {
+    pub(crate) open_sequence_number: SequenceNumber,
+    pub(crate) operations: OperationsBuffer,
 }
 
 typedb_error!(
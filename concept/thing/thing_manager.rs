@@ -142,7 +142,7 @@ impl ThingManager {
         InstanceIterator::new(snapshot_iterator)
     }
 
-    fn get_instances<T: ThingAPI>(
+    pub(crate) fn get_instances<T: ThingAPI>(
         &self,
         keyspace: EncodingKeyspace,
         snapshot: &impl ReadableSnapshot,
@@ -1288,7 +1288,7 @@ impl ThingManager {
     ) -> ConceptStatus {
         snapshot
             .get_write(key.as_reference())
-            .map(|write| match write {
+            .map(|write| match &*write {
                 Write::Insert { .. } => ConceptStatus::Inserted,
                 Write::Put { .. } => ConceptStatus::Put,
                 Write::Delete => ConceptStatus::Deleted,
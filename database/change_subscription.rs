@@ -0,0 +1,65 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{collections::VecDeque, sync::Arc};
+
+use concept::change_stream::{read_changes, ChangeStreamError, CommittedChanges};
+use storage::{durability_client::DurabilityClient, sequence_number::SequenceNumber};
+
+use crate::Database;
+
+/// Follows the committed changes of a database from a resumable position.
+/// Only commits at or below the storage watermark are delivered, so every delivered commit is durable and applied.
+#[derive(Debug)]
+pub struct ChangeSubscription<D> {
+    database: Arc<Database<D>>,
+    next_sequence_number: SequenceNumber,
+    pending: VecDeque<CommittedChanges>,
+}
+
+impl<D: DurabilityClient> ChangeSubscription<D> {
+    pub fn new(database: Arc<Database<D>>, start: SequenceNumber) -> Self {
+        Self { database, next_sequence_number: start, pending: VecDeque::new() }
+    }
+
+    /// Subscribes to the commits made after the current watermark
+    pub fn from_latest(database: Arc<Database<D>>) -> Self {
+        let start = database.storage.snapshot_watermark().next();
+        Self::new(database, start)
+    }
+
+    pub fn database(&self) -> &Database<D> {
+        &self.database
+    }
+
+    /// The position a new subscription should start from to receive everything not yet returned by `poll`
+    pub fn resume_from(&self) -> SequenceNumber {
+        self.pending.front().map(|changes| changes.sequence_number).unwrap_or(self.next_sequence_number)
+    }
+
+    /// Returns the next committed changes, or None if the subscription has caught up with the watermark
+    pub fn poll(&mut self) -> Result<Option<CommittedChanges>, ChangeStreamError> {
+        if self.pending.is_empty() {
+            let watermark = self.database.storage.snapshot_watermark();
+            if watermark >= self.next_sequence_number {
+                match read_changes(&self.database.storage, self.next_sequence_number, watermark) {
+                    Ok(changes) => {
+                        self.pending.extend(changes);
+                        self.next_sequence_number = watermark.next();
+                    }
+                    Err(ChangeStreamError::CommitStatusPending { sequence_number }) => {
+                        // deliver everything before the commit whose status is not yet durable, and retry it next poll
+                        let end = sequence_number.previous();
+                        self.pending.extend(read_changes(&self.database.storage, self.next_sequence_number, end)?);
+                        self.next_sequence_number = sequence_number;
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+        Ok(self.pending.pop_front())
+    }
+}
@@ -11,7 +11,7 @@ use std::{
     path::{Path, PathBuf},
     sync::{
         mpsc::{sync_channel, SyncSender},
-        Arc, Mutex, MutexGuard, RwLock, TryLockError,
+        Arc, Mutex, MutexGuard, OnceLock, RwLock, TryLockError,
     },
     time::{Duration, Instant},
 };
@@ -37,7 +37,7 @@ use error::typedb_error;
 use function::{function_cache::FunctionCache, FunctionError};
 use query::query_cache::QueryCache;
 use storage::{
-    durability_client::{DurabilityClient, DurabilityClientError, WALClient},
+    durability_client::{DurabilityClient, DurabilityClientError, NoDurabilityClient, WALClient},
     recovery::checkpoint::{Checkpoint, CheckpointCreateError, CheckpointLoadError},
     sequence_number::SequenceNumber,
     MVCCStorage, StorageDeleteError, StorageOpenError, StorageResetError,
@@ -45,6 +45,7 @@ use storage::{
 use tracing::{event, Level};
 
 use crate::{
+    replication::ReplicaState,
     transaction::TransactionError,
     DatabaseOpenError::FunctionCacheInitialise,
     DatabaseResetError::{
@@ -64,7 +65,7 @@ type SchemaWriteTransactionState = (bool, usize, VecDeque<TransactionReservation
 
 pub struct Database<D> {
     name: String,
-    path: PathBuf,
+    path: Option<PathBuf>,
     pub(super) storage: Arc<MVCCStorage<D>>,
     pub(super) definition_key_generator: Arc<DefinitionKeyGenerator>,
     pub(super) type_vertex_generator: Arc<TypeVertexGenerator>,
@@ -73,6 +74,7 @@ pub struct Database<D> {
     pub(super) schema: Arc<RwLock<Schema>>,
     pub(super) query_cache: Arc<QueryCache>,
     schema_write_transaction_exclusivity: Mutex<SchemaWriteTransactionState>,
+    pub(super) replica: OnceLock<ReplicaState>,
     _statistics_updater: IntervalRunner,
 }
 
@@ -94,7 +96,42 @@ impl<D> Database<D> {
         &self.name
     }
 
+    pub fn storage(&self) -> &Arc<MVCCStorage<D>> {
+        &self.storage
+    }
+
+    pub fn thing_statistics(&self) -> Arc<Statistics> {
+        self.schema.read().unwrap().thing_statistics.clone()
+    }
+
+    /// Replicas only serve read transactions, applying the commits of a primary instead
+    pub fn is_replica(&self) -> bool {
+        self.replica.get().is_some()
+    }
+
+    /// How many sequence numbers this replica's watermark is behind the primary, as of the last message received
+    pub fn replication_lag(&self) -> Option<u64> {
+        self.replica.get().map(|replica| replica.lag(self.storage.snapshot_watermark()))
+    }
+
+    /// Whether the database only lives in memory, and is lost when dropped
+    pub fn is_ephemeral(&self) -> bool {
+        self.path.is_none()
+    }
+
+    pub(super) fn checkpoint(&self) -> Result<(), CheckpointCreateError> {
+        // ephemeral databases are never recovered
+        let Some(path) = &self.path else { return Ok(()) };
+        let checkpoint = Checkpoint::new(path)?;
+        self.storage.checkpoint(&checkpoint)?;
+        checkpoint.finish()?;
+        Ok(())
+    }
+
     pub(super) fn reserve_write_transaction(&self, timeout_millis: u64) -> Result<(), TransactionError> {
+        if self.is_replica() {
+            return Err(TransactionError::ReadOnlyReplica { name: self.name.clone() });
+        }
         let (mut guard, timeout_left) =
             self.try_acquire_schema_write_transaction_lock(Duration::from_millis(timeout_millis))?;
         let (has_schema_transaction, running_write_transactions, ref mut notify_queue) = *guard;
@@ -112,6 +149,9 @@ impl<D> Database<D> {
     }
 
     pub(super) fn reserve_schema_transaction(&self, timeout_millis: u64) -> Result<(), TransactionError> {
+        if self.is_replica() {
+            return Err(TransactionError::ReadOnlyReplica { name: self.name.clone() });
+        }
         let (mut guard, timeout_left) =
             self.try_acquire_schema_write_transaction_lock(Duration::from_millis(timeout_millis))?;
         let (has_schema_transaction, running_write_transactions, ref mut notify_queue) = *guard;
@@ -199,8 +239,6 @@ impl<D> Database<D> {
 }
 
 impl Database<WALClient> {
-    const STATISTICS_UPDATE_INTERVAL: Duration = Duration::from_millis(100);
-
     pub fn open(path: &Path) -> Result<Database<WALClient>, DatabaseOpenError> {
         use DatabaseOpenError::InvalidUnicodeName;
 
@@ -215,9 +253,7 @@ impl Database<WALClient> {
     }
 
     fn create(path: &Path, name: impl AsRef<str>) -> Result<Database<WALClient>, DatabaseOpenError> {
-        use DatabaseOpenError::{
-            DirectoryCreate, Encoding, FunctionCacheInitialise, StorageOpen, TypeCacheInitialise, WALOpen,
-        };
+        use DatabaseOpenError::{DirectoryCreate, StorageOpen, WALOpen};
 
         let name = name.as_ref();
 
@@ -231,45 +267,7 @@ impl Database<WALClient> {
             MVCCStorage::create::<EncodingKeyspace>(name, path, wal_client)
                 .map_err(|error| StorageOpen { typedb_source: error })?,
         );
-        let definition_key_generator = Arc::new(DefinitionKeyGenerator::new());
-        let type_vertex_generator = Arc::new(TypeVertexGenerator::new());
-        let thing_vertex_generator =
-            Arc::new(ThingVertexGenerator::load(storage.clone()).map_err(|err| Encoding { source: err })?);
-        let thing_statistics = Arc::new(Statistics::new(storage.snapshot_watermark()));
-
-        let type_cache = Arc::new(
-            TypeCache::new(storage.clone(), SequenceNumber::MIN)
-                .map_err(|error| TypeCacheInitialise { typedb_source: error })?,
-        );
-
-        let function_cache = Arc::new(
-            FunctionCache::new(
-                storage.clone(),
-                &TypeManager::new(definition_key_generator.clone(), type_vertex_generator.clone(), None),
-                SequenceNumber::MIN,
-            )
-            .map_err(|error| FunctionCacheInitialise { typedb_source: error })?,
-        );
-
-        let schema = Arc::new(RwLock::new(Schema { thing_statistics, type_cache, function_cache }));
-        let schema_txn_lock = Arc::new(RwLock::default());
-
-        let query_cache = Arc::new(QueryCache::new(0));
-        let update_statistics =
-            make_update_statistics_fn(storage.clone(), schema.clone(), schema_txn_lock.clone(), query_cache.clone());
-
-        Ok(Database::<WALClient> {
-            name: name.to_owned(),
-            path: path.to_owned(),
-            storage,
-            definition_key_generator,
-            type_vertex_generator,
-            thing_vertex_generator,
-            schema,
-            query_cache,
-            schema_write_transaction_exclusivity: Mutex::new((false, 0, VecDeque::with_capacity(100))),
-            _statistics_updater: IntervalRunner::new(update_statistics, Self::STATISTICS_UPDATE_INTERVAL),
-        })
+        Self::new(name, Some(path.to_owned()), storage)
     }
 
     fn load(path: &Path, name: impl AsRef<str>) -> Result<Database<WALClient>, DatabaseOpenError> {
@@ -335,7 +333,7 @@ impl Database<WALClient> {
 
         let database = Database::<WALClient> {
             name: name.to_owned(),
-            path: path.to_owned(),
+            path: Some(path.to_owned()),
             storage,
             definition_key_generator,
             type_vertex_generator,
@@ -343,6 +341,7 @@ impl Database<WALClient> {
             schema,
             query_cache,
             schema_write_transaction_exclusivity: Mutex::new((false, 0, VecDeque::with_capacity(100))),
+            replica: OnceLock::new(),
             _statistics_updater: IntervalRunner::new(update_statistics, Self::STATISTICS_UPDATE_INTERVAL),
         };
 
@@ -358,12 +357,69 @@ impl Database<WALClient> {
 
         Ok(database)
     }
+}
 
-    fn checkpoint(&self) -> Result<(), CheckpointCreateError> {
-        let checkpoint = Checkpoint::new(&self.path)?;
-        self.storage.checkpoint(&checkpoint)?;
-        checkpoint.finish()?;
-        Ok(())
+impl Database<NoDurabilityClient> {
+    /// Create a database that only lives in memory. Nothing is written to disk, so it is lost when dropped, and its
+    /// statistics are not maintained from its commits.
+    pub fn create_ephemeral(name: impl AsRef<str>) -> Result<Database<NoDurabilityClient>, DatabaseOpenError> {
+        use DatabaseOpenError::StorageOpen;
+
+        let name = name.as_ref();
+        let storage = Arc::new(
+            MVCCStorage::create_in_memory::<EncodingKeyspace>(name, NoDurabilityClient::new())
+                .map_err(|error| StorageOpen { typedb_source: error })?,
+        );
+        Self::new(name, None, storage)
+    }
+}
+
+impl<D: DurabilityClient + Send + Sync + 'static> Database<D> {
+    const STATISTICS_UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+    fn new(name: &str, path: Option<PathBuf>, storage: Arc<MVCCStorage<D>>) -> Result<Database<D>, DatabaseOpenError> {
+        use DatabaseOpenError::{Encoding, FunctionCacheInitialise, TypeCacheInitialise};
+
+        let definition_key_generator = Arc::new(DefinitionKeyGenerator::new());
+        let type_vertex_generator = Arc::new(TypeVertexGenerator::new());
+        let thing_vertex_generator =
+            Arc::new(ThingVertexGenerator::load(storage.clone()).map_err(|err| Encoding { source: err })?);
+        let thing_statistics = Arc::new(Statistics::new(storage.snapshot_watermark()));
+
+        let type_cache = Arc::new(
+            TypeCache::new(storage.clone(), SequenceNumber::MIN)
+                .map_err(|error| TypeCacheInitialise { typedb_source: error })?,
+        );
+
+        let function_cache = Arc::new(
+            FunctionCache::new(
+                storage.clone(),
+                &TypeManager::new(definition_key_generator.clone(), type_vertex_generator.clone(), None),
+                SequenceNumber::MIN,
+            )
+            .map_err(|error| FunctionCacheInitialise { typedb_source: error })?,
+        );
+
+        let schema = Arc::new(RwLock::new(Schema { thing_statistics, type_cache, function_cache }));
+        let schema_txn_lock = Arc::new(RwLock::default());
+
+        let query_cache = Arc::new(QueryCache::new(0));
+        let update_statistics =
+            make_update_statistics_fn(storage.clone(), schema.clone(), schema_txn_lock.clone(), query_cache.clone());
+
+        Ok(Database::<D> {
+            name: name.to_owned(),
+            path,
+            storage,
+            definition_key_generator,
+            type_vertex_generator,
+            thing_vertex_generator,
+            schema,
+            query_cache,
+            schema_write_transaction_exclusivity: Mutex::new((false, 0, VecDeque::with_capacity(100))),
+            replica: OnceLock::new(),
+            _statistics_updater: IntervalRunner::new(update_statistics, Self::STATISTICS_UPDATE_INTERVAL),
+        })
     }
 
     #[allow(clippy::drop_non_drop)]
@@ -387,8 +443,9 @@ impl Database<WALClient> {
             .expect("Cannot get exclusive ownership of inner of Arc<MVCCStorage>.")
             .delete_storage()
             .map_err(|err| DatabaseDeleteError::StorageDelete { typedb_source: err })?;
-        let path = self.path;
-        fs::remove_dir_all(path).map_err(|err| DatabaseDeleteError::DirectoryDelete { source: Arc::new(err) })?;
+        if let Some(path) = self.path {
+            fs::remove_dir_all(path).map_err(|err| DatabaseDeleteError::DirectoryDelete { source: Arc::new(err) })?;
+        }
         Ok(())
     }
 
@@ -428,8 +485,8 @@ impl Database<WALClient> {
     }
 }
 
-fn make_update_statistics_fn(
-    storage: Arc<MVCCStorage<WALClient>>,
+fn make_update_statistics_fn<D: DurabilityClient + Send + Sync + 'static>(
+    storage: Arc<MVCCStorage<D>>,
     schema: Arc<RwLock<Schema>>,
     schema_txn_lock: Arc<RwLock<()>>,
     query_cache: Arc<QueryCache>,
@@ -439,9 +496,12 @@ fn make_update_statistics_fn(
             let _schema_txn_guard = schema_txn_lock.read().unwrap(); // prevent Schema txns from opening during statistics update
             let mut thing_statistics = (*schema.read().unwrap().thing_statistics).clone();
             thing_statistics.may_synchronise(&storage).ok();
-            let total_count = thing_statistics.total_count;
-            query_cache.may_reset(total_count);
-            schema.write().unwrap().thing_statistics = Arc::new(thing_statistics);
+            let mut schema = schema.write().unwrap();
+            // a concurrent commit may have replaced the statistics with ones this update can not reproduce from the WAL
+            if thing_statistics.sequence_number > schema.thing_statistics.sequence_number {
+                query_cache.may_reset(thing_statistics.total_count);
+                schema.thing_statistics = Arc::new(thing_statistics);
+            }
         }
     }
 }
@@ -468,6 +528,8 @@ typedb_error!(
     pub DatabaseCreateError(component = "Database create", prefix = "DBC") {
         InvalidName(1, "Cannot create database since '{name}' is not a valid database name.", name: String),
         InternalDatabaseCreationProhibited(2, "Creating an internal database is prohibited"),
+        AlreadyExists(3, "Cannot create database since a database named '{name}' already exists.", name: String),
+        EphemeralCreate(4, "Error creating ephemeral database.", ( typedb_source: DatabaseOpenError )),
     }
 );
 
@@ -12,7 +12,8 @@ use std::{
 };
 
 use itertools::Itertools;
-use storage::durability_client::WALClient;
+use resource::constants::executor::SORT_SPILL_DIRECTORY_NAME;
+use storage::durability_client::{DurabilityClient, NoDurabilityClient, WALClient};
 
 use crate::{database::DatabaseCreateError, Database, DatabaseDeleteError, DatabaseOpenError, DatabaseResetError};
 
@@ -27,6 +28,7 @@ macro_rules! internal_database_prefix {
 pub struct DatabaseManager {
     data_directory: PathBuf,
     databases: RwLock<HashMap<String, Arc<Database<WALClient>>>>,
+    ephemeral_databases: RwLock<HashMap<String, Arc<Database<NoDurabilityClient>>>>,
 }
 
 impl DatabaseManager {
@@ -36,6 +38,8 @@ impl DatabaseManager {
                 path: data_directory.to_owned(),
                 source: Arc::new(error),
             })?
+            // sorts spill to a directory alongside the databases by default
+            .filter(|entry| !entry.as_ref().is_ok_and(|entry| entry.file_name() == SORT_SPILL_DIRECTORY_NAME))
             .map(|entry| {
                 let entry = entry.map_err(|error| DatabaseOpenError::CouldNotReadDataDirectory {
                     path: data_directory.to_owned(),
@@ -46,22 +50,24 @@ impl DatabaseManager {
             })
             .try_collect()?;
 
-        Ok(Self { data_directory: data_directory.to_owned(), databases: RwLock::new(databases) })
+        Ok(Self {
+            data_directory: data_directory.to_owned(),
+            databases: RwLock::new(databases),
+            ephemeral_databases: RwLock::new(HashMap::new()),
+        })
     }
 
     pub fn create_database(&self, name: impl AsRef<str>) -> Result<(), DatabaseCreateError> {
         let name = name.as_ref();
-        if Self::is_internal_database(name) {
-            return Err(DatabaseCreateError::InternalDatabaseCreationProhibited {});
-        }
-        if !typeql::common::identifier::is_valid_identifier(name) {
-            return Err(DatabaseCreateError::InvalidName { name: name.to_owned() });
-        }
+        Self::validate_new_name(name)?;
         self.create_database_unrestricted(name)
     }
 
     pub fn create_database_unrestricted(&self, name: impl AsRef<str>) -> Result<(), DatabaseCreateError> {
         let name = name.as_ref();
+        if self.ephemeral_databases.read().unwrap().contains_key(name) {
+            return Err(DatabaseCreateError::AlreadyExists { name: name.to_owned() });
+        }
         self.databases
             .write()
             .unwrap()
@@ -70,15 +76,52 @@ impl DatabaseManager {
         Ok(())
     }
 
+    /// Create a database that only lives in memory, for tests, scratch analysis and caches. It is never written to the
+    /// data directory, so it is lost when the manager is dropped, and it is only available as an ephemeral database.
+    pub fn create_ephemeral_database(&self, name: impl AsRef<str>) -> Result<(), DatabaseCreateError> {
+        let name = name.as_ref();
+        Self::validate_new_name(name)?;
+        if self.databases.read().unwrap().contains_key(name) {
+            return Err(DatabaseCreateError::AlreadyExists { name: name.to_owned() });
+        }
+        let mut ephemeral_databases = self.ephemeral_databases.write().unwrap();
+        if !ephemeral_databases.contains_key(name) {
+            let database = Database::<NoDurabilityClient>::create_ephemeral(name)
+                .map_err(|typedb_source| DatabaseCreateError::EphemeralCreate { typedb_source })?;
+            ephemeral_databases.insert(name.to_owned(), Arc::new(database));
+        }
+        Ok(())
+    }
+
+    fn validate_new_name(name: &str) -> Result<(), DatabaseCreateError> {
+        if Self::is_internal_database(name) {
+            return Err(DatabaseCreateError::InternalDatabaseCreationProhibited {});
+        }
+        if !typeql::common::identifier::is_valid_identifier(name) {
+            return Err(DatabaseCreateError::InvalidName { name: name.to_owned() });
+        }
+        Ok(())
+    }
+
     pub fn delete_database(&self, name: impl AsRef<str>) -> Result<(), DatabaseDeleteError> {
         let name = name.as_ref();
         if Self::is_internal_database(name) {
             return Err(DatabaseDeleteError::InternalDatabaseDeletionProhibited {});
         }
+        if self.is_ephemeral_database(name) {
+            Self::delete_from(&self.ephemeral_databases, name)
+        } else {
+            Self::delete_from(&self.databases, name)
+        }
+    }
 
+    fn delete_from<D: DurabilityClient + Send + Sync + 'static>(
+        databases: &RwLock<HashMap<String, Arc<Database<D>>>>,
+        name: &str,
+    ) -> Result<(), DatabaseDeleteError> {
         // TODO: this is a partial implementation, only single threaded and without cooperative transaction shutdown
         // remove from map to make DB unavailable
-        let mut databases = self.databases.write().unwrap();
+        let mut databases = databases.write().unwrap();
         let db = databases.remove(name);
         match db {
             None => return Err(DatabaseDeleteError::DoesNotExist {}),
@@ -97,42 +140,52 @@ impl DatabaseManager {
     }
 
     pub fn reset_else_recreate_database(&self, name: impl AsRef<str>) -> Result<(), DatabaseResetError> {
-        // TODO: this is a partial implementation, only single threaded and without cooperative transaction shutdown
-        // remove from map to make DB unavailable
-        let mut databases = self.databases.write().unwrap();
-        let db = databases.remove(name.as_ref());
-        let result = if let Some(db) = db {
-            match Arc::try_unwrap(db) {
-                Ok(mut unwrapped) => {
-                    let reset_result = unwrapped.reset();
-                    databases.insert(name.as_ref().to_owned(), Arc::new(unwrapped));
-                    reset_result
-                }
-                Err(arc) => {
-                    // failed to reset since it's in use - let's re-insert for now instead of losing the reference
-                    databases.insert(name.as_ref().to_owned(), arc);
-                    Err(DatabaseResetError::InUse {})
-                }
-            }
+        let name = name.as_ref();
+        let is_ephemeral = self.is_ephemeral_database(name);
+        let result = if is_ephemeral {
+            Self::reset_in(&self.ephemeral_databases, name)
         } else {
-            drop(databases);
-            self.create_database(name).map_err(|typedb_source| DatabaseResetError::DatabaseCreate { typedb_source })?;
-            return Ok(());
+            Self::reset_in(&self.databases, name)
+        };
+        let create = |name: &str| match is_ephemeral {
+            true => self.create_ephemeral_database(name),
+            false => self.create_database(name),
         };
 
-        drop(databases);
         match result {
-            Ok(_) => (),
-            Err(_) => {
-                self.delete_database(name.as_ref())
+            None => create(name).map_err(|typedb_source| DatabaseResetError::DatabaseCreate { typedb_source })?,
+            Some(Ok(_)) => (),
+            Some(Err(_)) => {
+                self.delete_database(name)
                     .map_err(|typedb_source| DatabaseResetError::DatabaseDelete { typedb_source })?;
-                self.create_database(name)
-                    .map_err(|typedb_source| DatabaseResetError::DatabaseCreate { typedb_source })?
+                create(name).map_err(|typedb_source| DatabaseResetError::DatabaseCreate { typedb_source })?
             }
         };
         Ok(())
     }
 
+    fn reset_in<D: DurabilityClient + Send + Sync + 'static>(
+        databases: &RwLock<HashMap<String, Arc<Database<D>>>>,
+        name: &str,
+    ) -> Option<Result<(), DatabaseResetError>> {
+        // TODO: this is a partial implementation, only single threaded and without cooperative transaction shutdown
+        // remove from map to make DB unavailable
+        let mut databases = databases.write().unwrap();
+        let db = databases.remove(name)?;
+        match Arc::try_unwrap(db) {
+            Ok(mut unwrapped) => {
+                let reset_result = unwrapped.reset();
+                databases.insert(name.to_owned(), Arc::new(unwrapped));
+                Some(reset_result)
+            }
+            Err(arc) => {
+                // failed to reset since it's in use - let's re-insert for now instead of losing the reference
+                databases.insert(name.to_owned(), arc);
+                Some(Err(DatabaseResetError::InUse {}))
+            }
+        }
+    }
+
     pub fn database(&self, name: &str) -> Option<Arc<Database<WALClient>>> {
         if Self::is_internal_database(name) {
             return None;
@@ -144,10 +197,22 @@ impl DatabaseManager {
         self.databases.read().unwrap().get(name).cloned()
     }
 
+    pub fn ephemeral_database(&self, name: &str) -> Option<Arc<Database<NoDurabilityClient>>> {
+        self.ephemeral_databases.read().unwrap().get(name).cloned()
+    }
+
     pub fn database_names(&self) -> Vec<String> {
         self.databases.read().unwrap().keys().cloned().filter(|db| Self::is_user_database(db)).collect()
     }
 
+    pub fn ephemeral_database_names(&self) -> Vec<String> {
+        self.ephemeral_databases.read().unwrap().keys().cloned().collect()
+    }
+
+    pub fn is_ephemeral_database(&self, name: &str) -> bool {
+        self.ephemeral_databases.read().unwrap().contains_key(name)
+    }
+
     pub fn is_user_database(name: &str) -> bool {
         !Self::is_internal_database(name)
     }
@@ -0,0 +1,79 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{ops::ControlFlow, sync::Arc};
+
+use concept::{
+    error::ConceptReadError,
+    inspect::{inspect_key, inspect_keyspace, resolve_type_label, InspectedKey},
+    thing::thing_manager::ThingManager,
+    type_::type_manager::TypeManager,
+};
+use encoding::{layout::prefix::Prefix, value::label::Label, EncodingKeyspace};
+use error::typedb_error;
+use resource::constants::snapshot::BUFFER_KEY_INLINE;
+use storage::{durability_client::DurabilityClient, key_value::StorageKeyArray, keyspace::KeyspaceSet};
+
+use crate::Database;
+
+impl<D: DurabilityClient> Database<D> {
+    /// Decode the keys under each of `prefixes` in every keyspace as of the latest commit, optionally only those about
+    /// the type with `type_label`, until `visit` breaks.
+    pub fn inspect_keyspaces(
+        &self,
+        prefixes: &[Prefix],
+        type_label: Option<&Label>,
+        mut visit: impl FnMut(EncodingKeyspace, InspectedKey) -> ControlFlow<()>,
+    ) -> Result<(), InspectError> {
+        let snapshot = self.storage.clone().open_snapshot_read();
+        let thing_manager = self.read_thing_manager();
+        let type_ = match type_label {
+            None => None,
+            Some(label) => Some(
+                resolve_type_label(&snapshot, thing_manager.type_manager(), label)
+                    .map_err(|source| InspectError::ConceptRead { source })?
+                    .ok_or_else(|| InspectError::TypeNotFound { label: label.clone() })?,
+            ),
+        };
+        for keyspace in EncodingKeyspace::iter() {
+            for &prefix in prefixes {
+                let flow = inspect_keyspace(&snapshot, &thing_manager, keyspace, prefix, |inspected| match type_ {
+                    Some(type_) if !inspected.types.contains(&type_) => ControlFlow::Continue(()),
+                    _ => visit(keyspace, inspected),
+                })
+                .map_err(|source| InspectError::ConceptRead { source })?;
+                if flow.is_break() {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode a key and its value against the schema and data as of the latest commit, such as a key written to the
+    /// write-ahead log
+    pub fn inspect_key(&self, key: &StorageKeyArray<BUFFER_KEY_INLINE>, value: &[u8]) -> InspectedKey {
+        let snapshot = self.storage.clone().open_snapshot_read();
+        inspect_key(&snapshot, &self.read_thing_manager(), key, value)
+    }
+
+    pub(crate) fn read_thing_manager(&self) -> ThingManager {
+        let schema = self.schema.read().unwrap();
+        let type_manager = Arc::new(TypeManager::new(
+            self.definition_key_generator.clone(),
+            self.type_vertex_generator.clone(),
+            Some(schema.type_cache.clone()),
+        ));
+        ThingManager::new(self.thing_vertex_generator.clone(), type_manager, schema.thing_statistics.clone())
+    }
+}
+
+typedb_error!(
+    pub InspectError(component = "Storage inspection", prefix = "DIN") {
+        ConceptRead(1, "Error reading data while inspecting storage.", ( source: Box<ConceptReadError> )),
+        TypeNotFound(2, "No type found with label '{label}'.", label: Label),
+    }
+);
@@ -0,0 +1,62 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use concept::{
+    error::ConceptReadError,
+    integrity::{check_integrity, IntegrityReport},
+};
+use error::typedb_error;
+use options::TransactionOptions;
+use storage::{
+    durability_client::DurabilityClient,
+    snapshot::{CommittableSnapshot, ReadableSnapshot, SnapshotError},
+};
+
+use crate::{transaction::TransactionError, Database};
+
+impl<D: DurabilityClient> Database<D> {
+    /// Check the invariants between stored data and its derived indexes as of the latest commit.
+    ///
+    /// With `repair`, the check runs in a write snapshot and the derived index entries of repairable problems are
+    /// rewritten and committed. The returned report lists the problems found before repairing.
+    pub fn check_integrity(&self, repair: bool) -> Result<IntegrityReport, IntegrityCheckError> {
+        if !repair {
+            let snapshot = self.storage.clone().open_snapshot_read();
+            return self.check_integrity_in(&snapshot);
+        }
+
+        self.reserve_write_transaction(TransactionOptions::default().schema_lock_acquire_timeout_millis)
+            .map_err(|typedb_source| IntegrityCheckError::Transaction { typedb_source })?;
+        let result = self.check_integrity_and_repair();
+        self.release_write_transaction();
+        result
+    }
+
+    fn check_integrity_and_repair(&self) -> Result<IntegrityReport, IntegrityCheckError> {
+        let mut snapshot = self.storage.clone().open_snapshot_write();
+        let report = self.check_integrity_in(&snapshot)?;
+        if report.repairable_count() > 0 {
+            report.repair(&mut snapshot);
+            // repairs only rewrite index entries, so bypass the thing manager's validation and statistics
+            snapshot.commit().map_err(|typedb_source| IntegrityCheckError::Commit { typedb_source })?;
+        }
+        Ok(report)
+    }
+
+    fn check_integrity_in(&self, snapshot: &impl ReadableSnapshot) -> Result<IntegrityReport, IntegrityCheckError> {
+        let thing_manager = self.read_thing_manager();
+        check_integrity(snapshot, &thing_manager, &self.thing_statistics())
+            .map_err(|source| IntegrityCheckError::ConceptRead { source })
+    }
+}
+
+typedb_error!(
+    pub IntegrityCheckError(component = "Integrity check", prefix = "DIC") {
+        ConceptRead(1, "Error reading data while checking integrity.", ( source: Box<ConceptReadError> )),
+        Transaction(2, "Could not reserve a write transaction to repair with.", ( typedb_source: TransactionError )),
+        Commit(3, "Error committing integrity repairs.", ( typedb_source: SnapshotError )),
+    }
+);
@@ -9,6 +9,10 @@
 
 pub use self::database::{Database, DatabaseDeleteError, DatabaseOpenError, DatabaseResetError};
 
+pub mod change_subscription;
 pub mod database;
 pub mod database_manager;
+pub mod inspect;
+pub mod integrity;
+pub mod replication;
 pub mod transaction;
@@ -0,0 +1,474 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! WAL-shipping replication between a primary and read-only followers.
+//!
+//! A follower connects to the primary's replication address once per database and asks for WAL records from its
+//! resume position. The primary tails its WAL and ships every record, followed by a heartbeat carrying its latest
+//! sequence number. The follower applies the records through its storage's `ReplicationApplier` and serves read
+//! transactions at its own watermark.
+//!
+//! The wire format is a minimal length-prefixed binary framing, intended for a trusted local network. Lengths are
+//! checked against a limit before anything is allocated, so a corrupt or hostile peer cannot exhaust memory.
+
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    io::{self, BufReader, BufWriter, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use concept::{
+    thing::statistics::StatisticsError,
+    type_::type_manager::{
+        type_cache::{TypeCache, TypeCacheCreateError},
+        TypeManager,
+    },
+};
+use durability::RawRecord;
+use error::typedb_error;
+use function::{function_cache::FunctionCache, FunctionError};
+use resource::constants::database::{
+    REPLICATION_BATCH_RECORDS, REPLICATION_MAX_NAME_BYTES, REPLICATION_MAX_RECORD_BYTES,
+    REPLICATION_POLL_INTERVAL_MILLIS, REPLICATION_RECONNECT_INTERVAL_MILLIS,
+};
+use storage::{
+    durability_client::{DurabilityClient, DurabilityClientError},
+    recovery::replication::{ReplicationApplier, StorageReplicationError},
+    sequence_number::SequenceNumber,
+    MVCCStorage,
+};
+use tracing::{event, Level};
+
+use crate::{database_manager::DatabaseManager, Database};
+
+const REQUEST_LIST_DATABASES: u8 = 0;
+const REQUEST_FOLLOW: u8 = 1;
+const FRAME_RECORD: u8 = 0;
+const FRAME_HEARTBEAT: u8 = 1;
+
+/// Replication state of a follower database, shared with the database for reporting
+#[derive(Debug, Default)]
+pub struct ReplicaState {
+    primary_sequence_number: AtomicU64,
+    connected: AtomicBool,
+}
+
+impl ReplicaState {
+    pub fn primary_sequence_number(&self) -> SequenceNumber {
+        SequenceNumber::new(self.primary_sequence_number.load(Ordering::Relaxed))
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn lag(&self, watermark: SequenceNumber) -> u64 {
+        self.primary_sequence_number().number().saturating_sub(watermark.number())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplicationRequest {
+    ListDatabases,
+    Follow { database: String, start: SequenceNumber },
+}
+
+impl ReplicationRequest {
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        match self {
+            Self::ListDatabases => writer.write_all(&[REQUEST_LIST_DATABASES])?,
+            Self::Follow { database, start } => {
+                writer.write_all(&[REQUEST_FOLLOW])?;
+                writer.write_all(&start.number().to_le_bytes())?;
+                write_bytes(writer, database.as_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        match read_u8(reader)? {
+            REQUEST_LIST_DATABASES => Ok(Self::ListDatabases),
+            REQUEST_FOLLOW => {
+                let start = SequenceNumber::new(read_u64(reader)?);
+                let database = String::from_utf8(read_bytes(reader, REPLICATION_MAX_NAME_BYTES)?)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                Ok(Self::Follow { database, start })
+            }
+            kind => Err(io::Error::new(io::ErrorKind::InvalidData, format!("unknown replication request {kind}"))),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplicationFrame {
+    Record(RawRecord<'static>),
+    Heartbeat { primary_sequence_number: SequenceNumber },
+}
+
+impl ReplicationFrame {
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        match self {
+            Self::Record(record) => {
+                writer.write_all(&[FRAME_RECORD, record.record_type])?;
+                writer.write_all(&record.sequence_number.number().to_le_bytes())?;
+                write_bytes(writer, &record.bytes)
+            }
+            Self::Heartbeat { primary_sequence_number } => {
+                writer.write_all(&[FRAME_HEARTBEAT])?;
+                writer.write_all(&primary_sequence_number.number().to_le_bytes())
+            }
+        }
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        match read_u8(reader)? {
+            FRAME_RECORD => {
+                let record_type = read_u8(reader)?;
+                let sequence_number = SequenceNumber::new(read_u64(reader)?);
+                let bytes = Cow::Owned(read_bytes(reader, REPLICATION_MAX_RECORD_BYTES)?);
+                Ok(Self::Record(RawRecord { sequence_number, record_type, bytes }))
+            }
+            FRAME_HEARTBEAT => Ok(Self::Heartbeat { primary_sequence_number: SequenceNumber::new(read_u64(reader)?) }),
+            kind => Err(io::Error::new(io::ErrorKind::InvalidData, format!("unknown replication frame {kind}"))),
+        }
+    }
+}
+
+/// Tails the WAL of a primary database. Records are shipped at most once per cursor,
+/// including unsequenced records that share the sequence number of the commit before them.
+#[derive(Debug)]
+pub struct WALCursor {
+    position: SequenceNumber,
+    shipped_at_position: usize,
+}
+
+impl WALCursor {
+    pub fn new(start: SequenceNumber) -> Self {
+        Self { position: start, shipped_at_position: 0 }
+    }
+
+    /// Collect the next batch of unshipped records. The WAL is not held while the records are sent.
+    pub fn next_batch<D: DurabilityClient>(
+        &mut self,
+        storage: &MVCCStorage<D>,
+    ) -> Result<Vec<RawRecord<'static>>, DurabilityClientError> {
+        let mut batch = Vec::new();
+        let mut skip = self.shipped_at_position;
+        for record in storage.durability().iter_from(self.position)? {
+            let record = record?;
+            if record.sequence_number == self.position && skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            if record.sequence_number > self.position {
+                self.position = record.sequence_number;
+                self.shipped_at_position = 0;
+            }
+            self.shipped_at_position += 1;
+            batch.push(record);
+            if batch.len() >= REPLICATION_BATCH_RECORDS {
+                break;
+            }
+        }
+        Ok(batch)
+    }
+}
+
+/// Applies replication frames to a follower database
+#[derive(Debug)]
+pub struct DatabaseReplica<D> {
+    database: Arc<Database<D>>,
+    applier: ReplicationApplier,
+}
+
+impl<D: DurabilityClient> DatabaseReplica<D> {
+    /// Turns the database into a read-only replica
+    pub fn new(database: Arc<Database<D>>) -> Self {
+        database.replica.get_or_init(ReplicaState::default);
+        Self { database, applier: ReplicationApplier::new() }
+    }
+
+    pub fn database(&self) -> &Arc<Database<D>> {
+        &self.database
+    }
+
+    pub fn resume_from(&self) -> SequenceNumber {
+        self.applier.resume_from(&self.database.storage)
+    }
+
+    fn state(&self) -> &ReplicaState {
+        self.database.replica.get().unwrap()
+    }
+
+    pub fn apply(&mut self, frame: ReplicationFrame) -> Result<(), DatabaseReplicationError> {
+        use DatabaseReplicationError::{Apply, FunctionCacheUpdate, StatisticsUpdate, TypeCacheUpdate};
+
+        let record = match frame {
+            ReplicationFrame::Heartbeat { primary_sequence_number } => {
+                self.state().primary_sequence_number.fetch_max(primary_sequence_number.number(), Ordering::Relaxed);
+                return Ok(());
+            }
+            ReplicationFrame::Record(record) => record,
+        };
+        self.state().primary_sequence_number.fetch_max(record.sequence_number.number(), Ordering::Relaxed);
+
+        let database = &*self.database;
+        if !self.applier.resolves_schema_commit(&record) {
+            self.applier.apply(&database.storage, record).map_err(|typedb_source| Apply { typedb_source })?;
+            return Ok(());
+        }
+
+        // No transaction may open while the schema changes underneath the caches, as for a local schema commit
+        let mut schema_guard = database.schema.write().unwrap();
+        let sequence_number = self
+            .applier
+            .apply(&database.storage, record)
+            .map_err(|typedb_source| Apply { typedb_source })?
+            .expect("a resolved schema commit must be applied");
+        let mut schema = (*schema_guard).clone();
+        schema.type_cache = Arc::new(
+            TypeCache::new(database.storage.clone(), sequence_number)
+                .map_err(|typedb_source| TypeCacheUpdate { typedb_source })?,
+        );
+        let type_manager = TypeManager::new(
+            database.definition_key_generator.clone(),
+            database.type_vertex_generator.clone(),
+            Some(schema.type_cache.clone()),
+        );
+        schema.function_cache = Arc::new(
+            FunctionCache::new(database.storage.clone(), &type_manager, sequence_number)
+                .map_err(|typedb_source| FunctionCacheUpdate { typedb_source })?,
+        );
+        let mut thing_statistics = (*schema.thing_statistics).clone();
+        thing_statistics
+            .may_synchronise(&database.storage)
+            .map_err(|typedb_source| StatisticsUpdate { typedb_source })?;
+        database.query_cache.force_reset(thing_statistics.total_count);
+        schema.thing_statistics = Arc::new(thing_statistics);
+        *schema_guard = schema;
+        Ok(())
+    }
+
+    /// Apply frames until the stream fails or is closed
+    pub fn follow(&mut self, reader: &mut impl Read) -> Result<(), DatabaseReplicationError> {
+        loop {
+            let frame = ReplicationFrame::read_from(reader)
+                .map_err(|source| DatabaseReplicationError::Connection { source: Arc::new(source) })?;
+            self.apply(frame)?;
+        }
+    }
+}
+
+/// Serves the WAL of every database on this server to followers
+#[derive(Debug)]
+pub struct ReplicationServer {
+    database_manager: Arc<DatabaseManager>,
+}
+
+impl ReplicationServer {
+    pub fn new(database_manager: Arc<DatabaseManager>) -> Self {
+        Self { database_manager }
+    }
+
+    pub fn serve(self, address: SocketAddr) -> io::Result<()> {
+        let listener = TcpListener::bind(address)?;
+        let server = Arc::new(self);
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let server = server.clone();
+            thread::spawn(move || {
+                let peer = stream.peer_addr().ok();
+                if let Err(error) = server.serve_connection(stream) {
+                    event!(Level::DEBUG, ?peer, ?error, "Replication connection closed");
+                }
+            });
+        }
+        Ok(())
+    }
+
+    pub fn serve_connection(&self, stream: TcpStream) -> Result<(), DatabaseReplicationError> {
+        use DatabaseReplicationError::{Connection, DatabaseNotFound, DurabilityRead};
+
+        let mut reader = BufReader::new(stream.try_clone().map_err(|source| Connection { source: Arc::new(source) })?);
+        let mut writer = BufWriter::new(stream);
+        let request =
+            ReplicationRequest::read_from(&mut reader).map_err(|source| Connection { source: Arc::new(source) })?;
+        match request {
+            ReplicationRequest::ListDatabases => {
+                let names = self.database_manager.database_names();
+                let result = (|| {
+                    writer.write_all(&(names.len() as u64).to_le_bytes())?;
+                    for name in &names {
+                        write_bytes(&mut writer, name.as_bytes())?;
+                    }
+                    writer.flush()
+                })();
+                result.map_err(|source| Connection { source: Arc::new(source) })
+            }
+            ReplicationRequest::Follow { database: name, start } => {
+                event!(Level::INFO, "Shipping WAL of database '{}' from {} to a follower", name, start);
+                let mut cursor = WALCursor::new(start);
+                loop {
+                    // look the database up each round, so followers do not prevent it from being deleted
+                    let database =
+                        self.database_manager.database(&name).ok_or_else(|| DatabaseNotFound { name: name.clone() })?;
+                    let batch = cursor
+                        .next_batch(&database.storage)
+                        .map_err(|typedb_source| DurabilityRead { typedb_source })?;
+                    let primary_sequence_number = database.storage.durability().previous();
+                    drop(database);
+
+                    let caught_up = batch.len() < REPLICATION_BATCH_RECORDS;
+                    let result = (|| {
+                        for record in batch {
+                            ReplicationFrame::Record(record).write_to(&mut writer)?;
+                        }
+                        ReplicationFrame::Heartbeat { primary_sequence_number }.write_to(&mut writer)?;
+                        writer.flush()
+                    })();
+                    result.map_err(|source| Connection { source: Arc::new(source) })?;
+                    if caught_up {
+                        thread::sleep(Duration::from_millis(REPLICATION_POLL_INTERVAL_MILLIS));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Follows every user database of a primary, creating local replicas as databases appear on the primary.
+/// Database deletion is not replicated.
+#[derive(Debug)]
+pub struct ReplicationFollower {
+    database_manager: Arc<DatabaseManager>,
+    primary_address: SocketAddr,
+}
+
+impl ReplicationFollower {
+    /// Existing user databases become replicas immediately, so they reject writes before replication resumes
+    pub fn new(database_manager: Arc<DatabaseManager>, primary_address: SocketAddr) -> Self {
+        for name in database_manager.database_names() {
+            if let Some(database) = database_manager.database(&name) {
+                database.replica.get_or_init(ReplicaState::default);
+            }
+        }
+        Self { database_manager, primary_address }
+    }
+
+    pub fn run(self) {
+        let follower = Arc::new(self);
+        let mut followed = HashSet::new();
+        loop {
+            match follower.list_primary_databases() {
+                Ok(names) => {
+                    for name in names {
+                        if followed.insert(name.clone()) {
+                            let follower = follower.clone();
+                            thread::spawn(move || follower.follow_database(name));
+                        }
+                    }
+                }
+                Err(error) => {
+                    event!(Level::WARN, ?error, "Could not list databases of primary at '{}'", follower.primary_address)
+                }
+            }
+            thread::sleep(Duration::from_millis(REPLICATION_RECONNECT_INTERVAL_MILLIS));
+        }
+    }
+
+    fn list_primary_databases(&self) -> io::Result<Vec<String>> {
+        let mut stream = TcpStream::connect(self.primary_address)?;
+        ReplicationRequest::ListDatabases.write_to(&mut stream)?;
+        let mut reader = BufReader::new(stream);
+        let count = read_u64(&mut reader)?;
+        (0..count)
+            .map(|_| {
+                String::from_utf8(read_bytes(&mut reader, REPLICATION_MAX_NAME_BYTES)?)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+            })
+            .collect()
+    }
+
+    fn follow_database(&self, name: String) {
+        if let Err(error) = self.database_manager.create_database_unrestricted(&name) {
+            event!(Level::ERROR, ?error, "Could not create replica of database '{}'", name);
+            return;
+        }
+        let database = self.database_manager.database_unrestricted(&name).unwrap();
+        let mut replica = DatabaseReplica::new(database);
+        loop {
+            let result = self.follow_once(&name, &mut replica);
+            replica.state().connected.store(false, Ordering::Relaxed);
+            if let Err(error) = result {
+                event!(Level::WARN, ?error, "Replication of database '{}' interrupted, reconnecting", name);
+            }
+            thread::sleep(Duration::from_millis(REPLICATION_RECONNECT_INTERVAL_MILLIS));
+        }
+    }
+
+    fn follow_once<D: DurabilityClient>(
+        &self,
+        name: &str,
+        replica: &mut DatabaseReplica<D>,
+    ) -> Result<(), DatabaseReplicationError> {
+        use DatabaseReplicationError::Connection;
+
+        let mut stream =
+            TcpStream::connect(self.primary_address).map_err(|source| Connection { source: Arc::new(source) })?;
+        let request = ReplicationRequest::Follow { database: name.to_owned(), start: replica.resume_from() };
+        request.write_to(&mut stream).map_err(|source| Connection { source: Arc::new(source) })?;
+        replica.state().connected.store(true, Ordering::Relaxed);
+        replica.follow(&mut BufReader::new(stream))
+    }
+}
+
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+    writer.write_all(bytes)
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_bytes(reader: &mut impl Read, limit: u64) -> io::Result<Vec<u8>> {
+    let length = read_u64(reader)?;
+    if length > limit {
+        let message = format!("replication frame of {length} bytes exceeds the limit of {limit} bytes");
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+    let mut bytes = vec![0; length as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+typedb_error!(
+    pub DatabaseReplicationError(component = "Database replication", prefix = "DRP") {
+        Apply(1, "Error applying replicated WAL record.", ( typedb_source: StorageReplicationError )),
+        TypeCacheUpdate(2, "Error updating the type cache after a replicated schema commit.", ( typedb_source: TypeCacheCreateError )),
+        FunctionCacheUpdate(3, "Error updating the function cache after a replicated schema commit.", ( typedb_source: FunctionError )),
+        StatisticsUpdate(4, "Error updating statistics after a replicated schema commit.", ( typedb_source: StatisticsError )),
+        DurabilityRead(5, "Error reading the WAL to replicate.", ( typedb_source: DurabilityClientError )),
+        Connection(6, "Replication connection error.", ( source: Arc<io::Error> )),
+        DatabaseNotFound(7, "Database '{name}' to replicate does not exist on the primary.", name: String),
+    }
+);
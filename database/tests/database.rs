@@ -4,7 +4,22 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use database::Database;
+use std::{
+    io::{self, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+    thread,
+};
+
+use database::{
+    database::DatabaseCreateError,
+    database_manager::DatabaseManager,
+    replication::{DatabaseReplica, ReplicationFrame, ReplicationRequest, ReplicationServer},
+    transaction::{TransactionBulkLoad, TransactionError, TransactionRead, TransactionSchema, TransactionWrite},
+    Database,
+};
+use encoding::value::label::Label;
+use options::TransactionOptions;
 use storage::durability_client::WALClient;
 use test_utils::{create_tmp_dir, init_logging};
 
@@ -18,3 +33,190 @@ fn create_delete_database() {
     let delete_result = db.delete();
     assert!(delete_result.is_ok());
 }
+
+#[test]
+fn bulk_load_is_visible_and_recovered() {
+    init_logging();
+    let database_path = create_tmp_dir().join("bulk_load");
+    {
+        let database = Arc::new(Database::<WALClient>::open(&database_path).unwrap());
+        let mut transaction = TransactionSchema::open(database.clone(), TransactionOptions::default()).unwrap();
+        let snapshot = Arc::get_mut(&mut transaction.snapshot).unwrap();
+        transaction.type_manager.create_entity_type(snapshot, &Label::build("person")).unwrap();
+        transaction.commit().unwrap();
+
+        let mut transaction = TransactionBulkLoad::open(database.clone(), TransactionOptions::default()).unwrap();
+        let person = transaction.type_manager.get_entity_type(&*transaction.snapshot, &Label::build("person")).unwrap();
+        let snapshot = Arc::get_mut(&mut transaction.snapshot).unwrap();
+        for _ in 0..100 {
+            transaction.thing_manager.create_entity(snapshot, person.unwrap()).unwrap();
+        }
+        transaction.commit().unwrap();
+
+        let transaction = TransactionRead::open(database.clone(), TransactionOptions::default()).unwrap();
+        let person = transaction.type_manager.get_entity_type(&*transaction.snapshot, &Label::build("person")).unwrap();
+        assert_eq!(transaction.thing_manager.get_entities_in(&*transaction.snapshot, person.unwrap()).count(), 100);
+        assert_eq!(database.thing_statistics().entity_counts.get(&person.unwrap()), Some(&100));
+    }
+
+    let database = Arc::new(Database::<WALClient>::open(&database_path).unwrap());
+    let transaction = TransactionRead::open(database.clone(), TransactionOptions::default()).unwrap();
+    let person = transaction.type_manager.get_entity_type(&*transaction.snapshot, &Label::build("person")).unwrap();
+    assert_eq!(transaction.thing_manager.get_entities_in(&*transaction.snapshot, person.unwrap()).count(), 100);
+    assert_eq!(database.thing_statistics().total_entity_count, 100);
+}
+
+#[test]
+fn bulk_load_ingests_full_batches() {
+    init_logging();
+    let database = Arc::new(Database::<WALClient>::open(&create_tmp_dir().join("bulk_load_batches")).unwrap());
+    let mut transaction = TransactionSchema::open(database.clone(), TransactionOptions::default()).unwrap();
+    let snapshot = Arc::get_mut(&mut transaction.snapshot).unwrap();
+    transaction.type_manager.create_entity_type(snapshot, &Label::build("person")).unwrap();
+    transaction.commit().unwrap();
+
+    // the whole load would exceed the write buffer limit, so it only succeeds if it is ingested in batches
+    let options =
+        TransactionOptions { write_buffer_spill_bytes: 1024, write_buffer_limit_bytes: 8192, ..Default::default() };
+    let mut transaction = TransactionBulkLoad::open(database.clone(), options).unwrap();
+    let person = transaction.type_manager.get_entity_type(&*transaction.snapshot, &Label::build("person")).unwrap();
+    for _ in 0..1000 {
+        let snapshot = Arc::get_mut(&mut transaction.snapshot).unwrap();
+        transaction.thing_manager.create_entity(snapshot, person.unwrap()).unwrap();
+        transaction.may_ingest_batch().unwrap();
+    }
+    transaction.commit().unwrap();
+
+    let transaction = TransactionRead::open(database.clone(), TransactionOptions::default()).unwrap();
+    let person = transaction.type_manager.get_entity_type(&*transaction.snapshot, &Label::build("person")).unwrap();
+    assert_eq!(transaction.thing_manager.get_entities_in(&*transaction.snapshot, person.unwrap()).count(), 1000);
+    assert_eq!(database.thing_statistics().entity_counts.get(&person.unwrap()), Some(&1000));
+}
+
+#[test]
+fn bulk_load_into_ephemeral_database_is_not_checkpointed() {
+    init_logging();
+    let data_path = create_tmp_dir();
+    let manager = DatabaseManager::new(&data_path).unwrap();
+    manager.create_ephemeral_database("scratch").unwrap();
+    let database = manager.ephemeral_database("scratch").unwrap();
+    let mut transaction = TransactionSchema::open(database.clone(), TransactionOptions::default()).unwrap();
+    let snapshot = Arc::get_mut(&mut transaction.snapshot).unwrap();
+    transaction.type_manager.create_entity_type(snapshot, &Label::build("person")).unwrap();
+    transaction.commit().unwrap();
+
+    let mut transaction = TransactionBulkLoad::open(database.clone(), TransactionOptions::default()).unwrap();
+    let person = transaction.type_manager.get_entity_type(&*transaction.snapshot, &Label::build("person")).unwrap();
+    let snapshot = Arc::get_mut(&mut transaction.snapshot).unwrap();
+    for _ in 0..10 {
+        transaction.thing_manager.create_entity(snapshot, person.unwrap()).unwrap();
+    }
+    transaction.commit().unwrap();
+
+    let transaction = TransactionRead::open(database.clone(), TransactionOptions::default()).unwrap();
+    let person = transaction.type_manager.get_entity_type(&*transaction.snapshot, &Label::build("person")).unwrap();
+    assert_eq!(transaction.thing_manager.get_entities_in(&*transaction.snapshot, person.unwrap()).count(), 10);
+    assert!(!data_path.join("scratch").exists());
+}
+
+#[test]
+fn ephemeral_database_is_kept_in_memory() {
+    init_logging();
+    let data_path = create_tmp_dir();
+    let manager = DatabaseManager::new(&data_path).unwrap();
+    manager.create_ephemeral_database("scratch").unwrap();
+    assert!(manager.database("scratch").is_none());
+    assert!(!data_path.join("scratch").exists());
+    let database = manager.ephemeral_database("scratch").unwrap();
+    assert!(database.is_ephemeral());
+
+    let mut transaction = TransactionSchema::open(database.clone(), TransactionOptions::default()).unwrap();
+    let snapshot = Arc::get_mut(&mut transaction.snapshot).unwrap();
+    transaction.type_manager.create_entity_type(snapshot, &Label::build("person")).unwrap();
+    transaction.commit().unwrap();
+
+    let mut transaction = TransactionWrite::open(database.clone(), TransactionOptions::default()).unwrap();
+    let person = transaction.type_manager.get_entity_type(&*transaction.snapshot, &Label::build("person")).unwrap();
+    let snapshot = Arc::get_mut(&mut transaction.snapshot).unwrap();
+    for _ in 0..10 {
+        transaction.thing_manager.create_entity(snapshot, person.unwrap()).unwrap();
+    }
+    transaction.commit().unwrap();
+
+    let transaction = TransactionRead::open(database.clone(), TransactionOptions::default()).unwrap();
+    let person = transaction.type_manager.get_entity_type(&*transaction.snapshot, &Label::build("person")).unwrap();
+    assert_eq!(transaction.thing_manager.get_entities_in(&*transaction.snapshot, person.unwrap()).count(), 10);
+    drop(transaction);
+    assert!(!data_path.join("scratch").exists());
+
+    assert!(matches!(manager.create_database("scratch"), Err(DatabaseCreateError::AlreadyExists { .. })));
+    drop(database);
+    manager.delete_database("scratch").unwrap();
+    assert!(manager.ephemeral_database("scratch").is_none());
+}
+
+#[test]
+fn replica_follows_primary_over_tcp() {
+    init_logging();
+    let primary_path = create_tmp_dir();
+    let follower_path = create_tmp_dir();
+    let primary_manager = Arc::new(DatabaseManager::new(&primary_path).unwrap());
+    primary_manager.create_database("replicated").unwrap();
+    let primary = primary_manager.database("replicated").unwrap();
+
+    let mut transaction = TransactionSchema::open(primary.clone(), TransactionOptions::default()).unwrap();
+    let snapshot = Arc::get_mut(&mut transaction.snapshot).unwrap();
+    transaction.type_manager.create_entity_type(snapshot, &Label::build("person")).unwrap();
+    transaction.commit().unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let replication_server = ReplicationServer::new(primary_manager.clone());
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let _closed_by_follower = replication_server.serve_connection(stream);
+    });
+
+    let follower_manager = DatabaseManager::new(&follower_path).unwrap();
+    follower_manager.create_database_unrestricted("replicated").unwrap();
+    let mut replica = DatabaseReplica::new(follower_manager.database("replicated").unwrap());
+    let mut stream = TcpStream::connect(address).unwrap();
+    ReplicationRequest::Follow { database: "replicated".to_owned(), start: replica.resume_from() }
+        .write_to(&mut stream)
+        .unwrap();
+    let mut reader = BufReader::new(stream);
+    while replica.database().storage().snapshot_watermark() < primary.storage().snapshot_watermark() {
+        replica.apply(ReplicationFrame::read_from(&mut reader).unwrap()).unwrap();
+    }
+
+    let follower = replica.database().clone();
+    let transaction = TransactionRead::open(follower.clone(), TransactionOptions::default()).unwrap();
+    let person = transaction.type_manager.get_entity_type(&*transaction.snapshot, &Label::build("person")).unwrap();
+    assert!(person.is_some());
+    drop(transaction);
+
+    assert!(matches!(
+        TransactionWrite::open(follower.clone(), TransactionOptions::default()),
+        Err(TransactionError::ReadOnlyReplica { .. })
+    ));
+    assert!(matches!(
+        TransactionSchema::open(follower.clone(), TransactionOptions::default()),
+        Err(TransactionError::ReadOnlyReplica { .. })
+    ));
+    assert!(follower.replication_lag().is_some());
+}
+
+#[test]
+fn oversized_replication_frames_are_rejected() {
+    let mut record_frame = vec![0u8, 0u8];
+    record_frame.extend_from_slice(&1u64.to_le_bytes());
+    record_frame.extend_from_slice(&u64::MAX.to_le_bytes());
+    let error = ReplicationFrame::read_from(&mut record_frame.as_slice()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+    let mut follow_request = vec![1u8];
+    follow_request.extend_from_slice(&1u64.to_le_bytes());
+    follow_request.extend_from_slice(&(1024u64 * 1024).to_le_bytes());
+    let error = ReplicationRequest::read_from(&mut follow_request.as_slice()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
@@ -4,11 +4,14 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use std::sync::{mpsc::RecvTimeoutError, Arc};
+use std::{
+    sync::{mpsc::RecvTimeoutError, Arc},
+    time::Instant,
+};
 
 use concept::{
     error::ConceptWriteError,
-    thing::{statistics::StatisticsError, thing_manager::ThingManager},
+    thing::{conflict::describe_conflict, statistics::StatisticsError, thing_manager::ThingManager},
     type_::type_manager::{
         type_cache::{TypeCache, TypeCacheCreateError},
         TypeManager,
@@ -18,13 +21,29 @@ use error::typedb_error;
 use function::{function_cache::FunctionCache, function_manager::FunctionManager, FunctionError};
 use options::TransactionOptions;
 use query::query_manager::QueryManager;
+use resource::perf_counters::{
+    TRANSACTION_COMMITTED_SCHEMA, TRANSACTION_COMMITTED_WRITE, TRANSACTION_COMMIT_FAILED_SCHEMA,
+    TRANSACTION_COMMIT_FAILED_WRITE, TRANSACTION_COMMIT_LATENCY_SCHEMA, TRANSACTION_COMMIT_LATENCY_WRITE,
+    TRANSACTION_OPENED_BULK_LOAD, TRANSACTION_OPENED_READ, TRANSACTION_OPENED_SCHEMA, TRANSACTION_OPENED_WRITE,
+};
 use storage::{
     durability_client::DurabilityClient,
-    snapshot::{CommittableSnapshot, ReadSnapshot, SchemaSnapshot, SnapshotError, WritableSnapshot, WriteSnapshot},
+    isolation_manager::CommitRecord,
+    recovery::checkpoint::CheckpointCreateError,
+    sequence_number::SequenceNumber,
+    snapshot::{
+        buffer::WriteBufferLimits, CommittableSnapshot, ReadSnapshot, SchemaSnapshot, SnapshotError, WritableSnapshot,
+        WriteSnapshot,
+    },
+    StorageCommitError,
 };
 
 use crate::Database;
 
+fn write_buffer_limits(options: &TransactionOptions) -> WriteBufferLimits {
+    WriteBufferLimits { spill_bytes: options.write_buffer_spill_bytes, limit_bytes: options.write_buffer_limit_bytes }
+}
+
 #[derive(Debug)]
 pub struct TransactionRead<D> {
     pub snapshot: Arc<ReadSnapshot<D>>,
@@ -32,7 +51,7 @@ pub struct TransactionRead<D> {
     pub thing_manager: Arc<ThingManager>,
     pub function_manager: Arc<FunctionManager>,
     pub query_manager: Arc<QueryManager>,
-    _database: Arc<Database<D>>,
+    pub database: Arc<Database<D>>,
     transaction_options: TransactionOptions,
 }
 
@@ -64,13 +83,14 @@ impl<D: DurabilityClient> TransactionRead<D> {
 
         drop(schema);
 
+        TRANSACTION_OPENED_READ.increment();
         Ok(Self {
             snapshot: Arc::new(snapshot),
             type_manager,
             thing_manager,
             function_manager,
             query_manager,
-            _database: database,
+            database,
             transaction_options,
         })
     }
@@ -102,7 +122,11 @@ impl<D: DurabilityClient> TransactionWrite<D> {
         database.reserve_write_transaction(transaction_options.schema_lock_acquire_timeout_millis)?;
 
         let schema = database.schema.read().unwrap();
-        let snapshot: WriteSnapshot<D> = database.storage.clone().open_snapshot_write();
+        let mut snapshot: WriteSnapshot<D> = database.storage.clone().open_snapshot_write();
+        snapshot.set_write_buffer_limits(write_buffer_limits(&transaction_options));
+        if transaction_options.serializable {
+            snapshot.set_serializable();
+        }
         let type_manager = Arc::new(TypeManager::new(
             database.definition_key_generator.clone(),
             database.type_vertex_generator.clone(),
@@ -120,6 +144,7 @@ impl<D: DurabilityClient> TransactionWrite<D> {
         let query_manager = Arc::new(QueryManager::new(Some(database.query_cache.clone())));
         drop(schema);
 
+        TRANSACTION_OPENED_WRITE.increment();
         Ok(Self {
             snapshot: Arc::new(snapshot),
             type_manager,
@@ -145,7 +170,13 @@ impl<D: DurabilityClient> TransactionWrite<D> {
 
     pub fn commit(self) -> Result<(), DataCommitError> {
         let database = self.database.clone(); // TODO: can we get away without cloning the database before?
+        let start = Instant::now();
         let result = self.try_commit();
+        TRANSACTION_COMMIT_LATENCY_WRITE.record(start.elapsed());
+        match &result {
+            Ok(_) => TRANSACTION_COMMITTED_WRITE.increment(),
+            Err(_) => TRANSACTION_COMMIT_FAILED_WRITE.increment(),
+        }
         database.release_write_transaction();
         result
     }
@@ -159,7 +190,19 @@ impl<D: DurabilityClient> TransactionWrite<D> {
             DataCommitError::ConceptWriteErrorsFirst { typedb_source: Box::new(error) }
         })?;
         drop(self.type_manager);
-        snapshot.commit().map_err(|err| DataCommitError::SnapshotError { typedb_source: err })?;
+        snapshot.commit().map_err(|error| match &error {
+            SnapshotError::Commit { typedb_source: StorageCommitError::Isolation { conflict, .. } } => {
+                // describe the conflict as of the latest commit, which includes the commit we conflicted with
+                let snapshot = self.database.storage.clone().open_snapshot_read();
+                let description = describe_conflict(&snapshot, &self.thing_manager, conflict);
+                DataCommitError::IsolationConflict {
+                    predecessor: conflict.predecessor(),
+                    description,
+                    typedb_source: error,
+                }
+            }
+            _ => DataCommitError::SnapshotError { typedb_source: error },
+        })?;
         Ok(())
     }
 
@@ -181,6 +224,13 @@ typedb_error!(
         ConceptWriteErrors(2, "Data commit error.", source: Vec<ConceptWriteError> ),
         ConceptWriteErrorsFirst(3, "Data commit error.", ( typedb_source : Box<ConceptWriteError> )),
         SnapshotError(4, "Snapshot error.", ( typedb_source: SnapshotError )),
+        IsolationConflict(
+            5,
+            "Commit conflicts with concurrent commit '{predecessor}' on {description}.",
+            predecessor: SequenceNumber,
+            description: String,
+            ( typedb_source: SnapshotError )
+        ),
     }
 );
 
@@ -214,7 +264,8 @@ impl<D: DurabilityClient> TransactionSchema<D> {
     pub fn open(database: Arc<Database<D>>, transaction_options: TransactionOptions) -> Result<Self, TransactionError> {
         database.reserve_schema_transaction(transaction_options.schema_lock_acquire_timeout_millis)?;
 
-        let snapshot: SchemaSnapshot<D> = database.storage.clone().open_snapshot_schema();
+        let mut snapshot: SchemaSnapshot<D> = database.storage.clone().open_snapshot_schema();
+        snapshot.set_write_buffer_limits(write_buffer_limits(&transaction_options));
         let type_manager = Arc::new(TypeManager::new(
             database.definition_key_generator.clone(),
             database.type_vertex_generator.clone(),
@@ -231,6 +282,7 @@ impl<D: DurabilityClient> TransactionSchema<D> {
         let function_manager = Arc::new(FunctionManager::new(database.definition_key_generator.clone(), None));
         let query_manager = Arc::new(QueryManager::new(None));
 
+        TRANSACTION_OPENED_SCHEMA.increment();
         Ok(Self {
             snapshot: Arc::new(snapshot),
             type_manager,
@@ -264,7 +316,13 @@ impl<D: DurabilityClient> TransactionSchema<D> {
 
     pub fn commit(self) -> Result<(), SchemaCommitError> {
         let database = self.database.clone(); // TODO: can we get away without cloning the database before?
+        let start = Instant::now();
         let result = self.try_commit();
+        TRANSACTION_COMMIT_LATENCY_SCHEMA.record(start.elapsed());
+        match &result {
+            Ok(_) => TRANSACTION_COMMITTED_SCHEMA.increment(),
+            Err(_) => TRANSACTION_COMMIT_FAILED_SCHEMA.increment(),
+        }
         database.release_schema_transaction();
         result
     }
@@ -368,9 +426,171 @@ typedb_error!(
     }
 );
 
+/// An exclusive transaction for loading large amounts of data into an existing schema. It holds the schema lock
+/// for its lifetime, so no other write or schema transaction runs concurrently. Its writes are ingested into storage
+/// as sorted files instead of being logged and validated, one batch at a time: whenever the buffered writes reach
+/// the spill size, or the caller ends a batch with `ingest_batch`, they are validated, ingested and checkpointed, and
+/// later writes see them. A bulk load is therefore not atomic - batches ingested before a failure remain loaded, and
+/// each batch must be valid on its own. Ingested batches are marked in the WAL as bulk loads, which change
+/// subscriptions report without their contents and which replicas refuse.
+#[derive(Debug)]
+pub struct TransactionBulkLoad<D> {
+    pub snapshot: Arc<SchemaSnapshot<D>>,
+    pub type_manager: Arc<TypeManager>,
+    pub thing_manager: Arc<ThingManager>,
+    pub function_manager: Arc<FunctionManager>,
+    pub query_manager: Arc<QueryManager>,
+    pub database: Arc<Database<D>>,
+    pub transaction_options: TransactionOptions,
+}
+
+impl<D: DurabilityClient> TransactionBulkLoad<D> {
+    pub fn open(database: Arc<Database<D>>, transaction_options: TransactionOptions) -> Result<Self, TransactionError> {
+        database.reserve_schema_transaction(transaction_options.schema_lock_acquire_timeout_millis)?;
+
+        let schema = database.schema.read().unwrap();
+        let type_manager = Arc::new(TypeManager::new(
+            database.definition_key_generator.clone(),
+            database.type_vertex_generator.clone(),
+            Some(schema.type_cache.clone()),
+        ));
+        let thing_manager = Arc::new(ThingManager::new(
+            database.thing_vertex_generator.clone(),
+            type_manager.clone(),
+            schema.thing_statistics.clone(),
+        ));
+        let function_manager = Arc::new(FunctionManager::new(
+            database.definition_key_generator.clone(),
+            Some(schema.function_cache.clone()),
+        ));
+        let query_manager = Arc::new(QueryManager::new(Some(database.query_cache.clone())));
+        drop(schema);
+
+        let mut snapshot = database.storage.clone().open_snapshot_schema();
+        snapshot.set_write_buffer_limits(write_buffer_limits(&transaction_options));
+        TRANSACTION_OPENED_BULK_LOAD.increment();
+        Ok(Self {
+            snapshot: Arc::new(snapshot),
+            type_manager,
+            thing_manager,
+            function_manager,
+            query_manager,
+            database,
+            transaction_options,
+        })
+    }
+
+    pub fn from(
+        snapshot: SchemaSnapshot<D>,
+        type_manager: Arc<TypeManager>,
+        thing_manager: Arc<ThingManager>,
+        function_manager: Arc<FunctionManager>,
+        query_manager: Arc<QueryManager>,
+        database: Arc<Database<D>>,
+        transaction_options: TransactionOptions,
+    ) -> Self {
+        Self {
+            snapshot: Arc::new(snapshot),
+            type_manager,
+            thing_manager,
+            function_manager,
+            query_manager,
+            database,
+            transaction_options,
+        }
+    }
+
+    /// Ingest the buffered writes if they have reached the spill size. Callers loading data call this between
+    /// writes, so memory use stays bounded by the spill size rather than by the size of the load.
+    pub fn may_ingest_batch(&mut self) -> Result<(), BulkLoadCommitError> {
+        if self.snapshot.is_write_buffer_full() {
+            self.ingest_batch()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Validate and ingest the writes buffered since the previous batch, then checkpoint so they are recoverable.
+    /// Ephemeral databases are never recovered, so they are not checkpointed. If validation fails, the batch stays
+    /// buffered, to be rolled back or corrected by the caller.
+    pub fn ingest_batch(&mut self) -> Result<(), BulkLoadCommitError> {
+        use BulkLoadCommitError::{ConceptWriteErrorsFirst, SnapshotError, SnapshotInUse};
+
+        let snapshot = Arc::get_mut(&mut self.snapshot).ok_or_else(|| SnapshotInUse {})?;
+        self.thing_manager.finalise(snapshot).map_err(|errs| {
+            // TODO: send all the errors, not just the first,
+            // when we can print the stacktraces of multiple errors, not just a single one
+            ConceptWriteErrorsFirst { typedb_source: Box::new(errs.into_iter().next().unwrap()) }
+        })?;
+
+        // the snapshot moves past the ingested batch, so the next batch reads what this one loaded
+        let Some((sequence_number, commit_record)) =
+            snapshot.commit_ingest().map_err(|typedb_source| SnapshotError { typedb_source })?
+        else {
+            return Ok(());
+        };
+        self.ingested(sequence_number, commit_record)?;
+        self.thing_manager = Arc::new(ThingManager::new(
+            self.database.thing_vertex_generator.clone(),
+            self.type_manager.clone(),
+            self.database.schema.read().unwrap().thing_statistics.clone(),
+        ));
+        Ok(())
+    }
+
+    pub fn commit(mut self) -> Result<(), BulkLoadCommitError> {
+        let result = self.ingest_batch();
+        self.close();
+        result
+    }
+
+    fn ingested(
+        &self,
+        sequence_number: SequenceNumber,
+        commit_record: CommitRecord,
+    ) -> Result<(), BulkLoadCommitError> {
+        use BulkLoadCommitError::{Checkpoint, StatisticsError};
+
+        // statistics are normally synchronised from the WAL, which does not contain the ingested writes
+        let mut schema = self.database.schema.write().unwrap();
+        let mut thing_statistics = (*schema.thing_statistics).clone();
+        thing_statistics
+            .update_ingested(sequence_number, commit_record, &self.database.storage)
+            .map_err(|typedb_source| StatisticsError { typedb_source })?;
+        self.database.query_cache.force_reset(thing_statistics.total_count);
+        schema.thing_statistics = Arc::new(thing_statistics);
+        drop(schema);
+
+        self.database.checkpoint().map_err(|source| Checkpoint { source })
+    }
+
+    /// Discard the writes buffered since the previous batch. Batches already ingested remain loaded.
+    pub fn rollback(&mut self) {
+        Arc::get_mut(&mut self.snapshot).unwrap().clear()
+    }
+
+    pub fn close(self) {
+        self.database.release_schema_transaction();
+        drop(self.thing_manager);
+        drop(self.type_manager);
+        Arc::into_inner(self.snapshot).unwrap().close_resources();
+    }
+}
+
+typedb_error!(
+    pub BulkLoadCommitError(component = "Bulk load commit", prefix = "BCT") {
+        SnapshotInUse(1, "Failed to commit since the transaction snapshot is still in use."),
+        ConceptWriteErrorsFirst(2, "Bulk load commit error.", ( typedb_source : Box<ConceptWriteError> )),
+        SnapshotError(3, "Snapshot error.", ( typedb_source: SnapshotError )),
+        StatisticsError(4, "Statistics error.", ( typedb_source: StatisticsError )),
+        Checkpoint(5, "Failed to checkpoint the database after ingesting the loaded data.", ( source: CheckpointCreateError )),
+    }
+);
+
 typedb_error!(
     pub TransactionError(component = "Transaction", prefix = "TXN") {
         Timeout(1, "Transaction timeout.", source: RecvTimeoutError),
         WriteExclusivityTimeout(2, "Transaction timeout due to an exclusive write access requested by this or a concurrent transaction."),
+        ReadOnlyReplica(3, "Database '{name}' is a read-only replica and only accepts read transactions.", name: String),
     }
 );
@@ -26,7 +26,10 @@ use std::{
 
 use itertools::Itertools;
 use logger::result::ResultExt;
-use resource::constants::storage::WAL_SYNC_INTERVAL_MICROSECONDS;
+use resource::{
+    constants::storage::WAL_SYNC_INTERVAL_MICROSECONDS,
+    perf_counters::{WAL_BYTES_WRITTEN, WAL_FSYNC_LATENCY},
+};
 
 use crate::{DurabilityRecordType, DurabilitySequenceNumber, DurabilityService, DurabilityServiceError, RawRecord};
 
@@ -203,6 +206,46 @@ impl DurabilityService for WAL {
     }
 }
 
+/// Read-only access to the files and records of a WAL, for inspection. Nothing is written, so the WAL may be read
+/// while it is in use, although records being written concurrently may be seen partially.
+#[derive(Debug)]
+pub struct WALReader {
+    files: Vec<File>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WALFileInfo {
+    pub path: PathBuf,
+    /// The sequence number of the first record in the file
+    pub start: DurabilitySequenceNumber,
+    pub len: u64,
+}
+
+impl WALReader {
+    pub fn open(directory: impl AsRef<Path>) -> Result<Self, WALError> {
+        let wal_dir = directory.as_ref().join(WAL::WAL_DIR_NAME);
+        if !wal_dir.exists() {
+            Err(WALError::LoadErrorDirectoryMissing { directory: wal_dir.clone() })?
+        }
+        let files = Files::list_files(&wal_dir).map_err(|err| WALError::LoadError { source: Arc::new(err) })?;
+        Ok(Self { files })
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = WALFileInfo> + '_ {
+        self.files.iter().map(|file| WALFileInfo { path: file.path.clone(), start: file.start, len: file.len })
+    }
+
+    /// Iterate the records of the file at `index` in `files()`
+    pub fn file_records(
+        &self,
+        index: usize,
+    ) -> Result<impl Iterator<Item = Result<RawRecord<'static>, DurabilityServiceError>> + '_, DurabilityServiceError>
+    {
+        FileRecordIterator::new(&self.files[index], DurabilitySequenceNumber::MIN)
+            .map_err(|err| DurabilityServiceError::IO { source: Arc::new(err) })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum WALError {
     CreateError { source: Arc<io::Error> },
@@ -242,6 +285,12 @@ impl Files {
     }
 
     fn init_files_writer(directory: &Path) -> io::Result<(Vec<File>, Option<BufWriter<StdFile>>)> {
+        let files = Self::list_files(directory)?;
+        let writer = files.last().map(File::writer).transpose()?;
+        Ok((files, writer))
+    }
+
+    fn list_files(directory: &Path) -> io::Result<Vec<File>> {
         let mut files: Vec<File> = directory
             .read_dir()?
             .map_ok(|entry| entry.path())
@@ -251,9 +300,7 @@ impl Files {
             .map(|path| File::open(path?))
             .try_collect()?;
         files.sort_unstable_by(|lhs, rhs| lhs.path.cmp(&rhs.path));
-
-        let writer = files.last().map(File::writer).transpose()?;
-        Ok((files, writer))
+        Ok(files)
     }
 
     fn open_new_file_at(&mut self, start: DurabilitySequenceNumber) -> io::Result<()> {
@@ -280,7 +327,10 @@ impl Files {
         writer.write_all(&record.bytes)?;
         writer.flush()?;
 
-        self.files.last_mut().unwrap().len = writer.stream_position()?;
+        let end_position = writer.stream_position()?;
+        let file = self.files.last_mut().unwrap();
+        WAL_BYTES_WRITTEN.add(end_position.saturating_sub(file.len));
+        file.len = end_position;
         Ok(())
     }
 
@@ -584,7 +634,9 @@ impl FsyncThread {
         let vec_lock = context.signalling.get(current_signal as usize).unwrap().lock();
         let mut vec = vec_lock.unwrap();
         if !vec.is_empty() {
+            let sync_start = Instant::now();
             context.files.write().unwrap().sync_all();
+            WAL_FSYNC_LATENCY.record(sync_start.elapsed());
             while let Some(sender_opt) = vec.pop() {
                 if let Some(sender) = sender_opt {
                     sender.send(()).unwrap();
@@ -610,7 +662,7 @@ mod test {
     use itertools::Itertools;
     use tempdir::TempDir;
 
-    use super::WAL;
+    use super::{WALReader, WAL};
     use crate::{DurabilityRecordType, DurabilitySequenceNumber, DurabilityService, RawRecord};
 
     #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -826,4 +878,27 @@ mod test {
             matches!(found, RawRecord { bytes: Cow::Owned(bytes), record_type: UnsequencedTestRecord::RECORD_TYPE, .. } if bytes == unsequenced_2.bytes())
         );
     }
+
+    #[test]
+    fn test_wal_reader_reads_files_without_writing() {
+        let directory = TempDir::new("wal-test").unwrap();
+        let records = [TestRecord { bytes: *b"test" }, TestRecord { bytes: *b"abcd" }];
+
+        let wal = create_wal(&directory);
+        records
+            .iter()
+            .try_for_each(|record| wal.sequenced_write(TestRecord::RECORD_TYPE, record.bytes()).map(|_| ()))
+            .unwrap();
+
+        let reader = WALReader::open(&directory).unwrap();
+        let files = reader.files().collect_vec();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].start, DurabilitySequenceNumber::MIN.next());
+        let read_records =
+            reader.file_records(0).unwrap().map(|res| TestRecord::new(&res.unwrap().bytes)).collect_vec();
+        assert_eq!(records, &*read_records);
+
+        drop(wal);
+        assert_eq!(std::fs::metadata(&files[0].path).unwrap().len(), files[0].len);
+    }
 }
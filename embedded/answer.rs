@@ -0,0 +1,223 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{collections::HashMap, sync::Arc, vec};
+
+use answer::{variable_value::VariableValue, Thing, Type};
+use encoding::{graph::type_::Kind, value::value::Value};
+use error::typedb_error;
+
+/// The answer to a query. Schema queries answer `Ok`, queries ending in `fetch` answer documents, and all other
+/// queries answer rows. Answers are read in full when the query runs, so they may be kept after the transaction
+/// is closed.
+#[derive(Debug)]
+pub enum QueryAnswer {
+    Ok,
+    Rows(Rows),
+    Documents(Documents),
+}
+
+impl QueryAnswer {
+    pub fn into_rows(self) -> Result<Rows, AnswerError> {
+        match self {
+            Self::Rows(rows) => Ok(rows),
+            _ => Err(AnswerError::NotRows {}),
+        }
+    }
+
+    pub fn into_documents(self) -> Result<Documents, AnswerError> {
+        match self {
+            Self::Documents(documents) => Ok(documents),
+            _ => Err(AnswerError::NotDocuments {}),
+        }
+    }
+}
+
+/// The rows answering a query, each holding a value for every column
+#[derive(Debug)]
+pub struct Rows {
+    columns: Arc<[String]>,
+    rows: vec::IntoIter<Row>,
+}
+
+impl Rows {
+    pub(crate) fn new(columns: Arc<[String]>, rows: Vec<Row>) -> Self {
+        Self { columns, rows: rows.into_iter() }
+    }
+
+    /// The names of the variables returned by the query, in the order of each row's values
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+}
+
+impl Iterator for Rows {
+    type Item = Row;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rows.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Rows {}
+
+/// A row of an answer. Types and instances are returned as handles, whose labels and attribute values are read
+/// through the transaction that ran the query.
+#[derive(Debug, Clone)]
+pub struct Row {
+    columns: Arc<[String]>,
+    values: Vec<VariableValue<'static>>,
+}
+
+impl Row {
+    pub(crate) fn new(columns: Arc<[String]>, values: Vec<VariableValue<'static>>) -> Self {
+        Self { columns, values }
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn values(&self) -> &[VariableValue<'static>] {
+        &self.values
+    }
+
+    /// The value of `column`, which is `VariableValue::Empty` for optional variables without an answer
+    pub fn get(&self, column: &str) -> Result<&VariableValue<'static>, AnswerError> {
+        let index = self
+            .columns
+            .iter()
+            .position(|name| name == column)
+            .ok_or_else(|| AnswerError::UnknownColumn { column: column.to_owned() })?;
+        Ok(&self.values[index])
+    }
+
+    pub fn get_type(&self, column: &str) -> Result<&Type, AnswerError> {
+        match self.get(column)? {
+            VariableValue::Type(type_) => Ok(type_),
+            other => Err(Self::unexpected(column, "a type", other)),
+        }
+    }
+
+    pub fn get_thing(&self, column: &str) -> Result<&Thing, AnswerError> {
+        match self.get(column)? {
+            VariableValue::Thing(thing) => Ok(thing),
+            other => Err(Self::unexpected(column, "an instance", other)),
+        }
+    }
+
+    pub fn get_value(&self, column: &str) -> Result<&Value<'static>, AnswerError> {
+        match self.get(column)? {
+            VariableValue::Value(value) => Ok(value),
+            other => Err(Self::unexpected(column, "a value", other)),
+        }
+    }
+
+    pub fn get_thing_list(&self, column: &str) -> Result<&[Thing], AnswerError> {
+        match self.get(column)? {
+            VariableValue::ThingList(things) => Ok(things),
+            other => Err(Self::unexpected(column, "a list of instances", other)),
+        }
+    }
+
+    pub fn get_value_list(&self, column: &str) -> Result<&[Value<'static>], AnswerError> {
+        match self.get(column)? {
+            VariableValue::ValueList(values) => Ok(values),
+            other => Err(Self::unexpected(column, "a list of values", other)),
+        }
+    }
+
+    fn unexpected(column: &str, expected: &'static str, found: &VariableValue<'_>) -> AnswerError {
+        let found = match found {
+            VariableValue::Empty => "empty",
+            VariableValue::Type(_) => "a type",
+            VariableValue::Thing(_) => "an instance",
+            VariableValue::Value(_) => "a value",
+            VariableValue::ThingList(_) => "a list of instances",
+            VariableValue::ValueList(_) => "a list of values",
+        };
+        AnswerError::UnexpectedValue { column: column.to_owned(), expected, found }
+    }
+}
+
+/// The documents answering a query ending in `fetch`
+#[derive(Debug)]
+pub struct Documents {
+    documents: vec::IntoIter<Document>,
+}
+
+impl Documents {
+    pub(crate) fn new(documents: Vec<Document>) -> Self {
+        Self { documents: documents.into_iter() }
+    }
+}
+
+impl Iterator for Documents {
+    type Item = Document;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.documents.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.documents.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Documents {}
+
+/// A fetched document. Unlike rows, types are given by their labels and attributes by their values.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Document {
+    List(Vec<Document>),
+    Map(HashMap<String, Document>),
+    Empty,
+    Type { kind: Kind, label: String },
+    Kind(Kind),
+    Value(Value<'static>),
+}
+
+impl Document {
+    pub fn get(&self, key: &str) -> Option<&Document> {
+        match self {
+            Self::Map(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Document]> {
+        match self {
+            Self::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn as_value(&self) -> Option<&Value<'static>> {
+        match self {
+            Self::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+typedb_error!(
+    pub AnswerError(component = "Answer", prefix = "ANS") {
+        NotRows(1, "The query did not answer rows."),
+        NotDocuments(2, "The query did not answer documents."),
+        UnknownColumn(3, "The answer has no column '{column}'.", column: String),
+        UnexpectedValue(
+            4,
+            "Column '{column}' holds {found} rather than {expected}.",
+            column: String,
+            expected: &'static str,
+            found: &'static str
+        ),
+    }
+);
@@ -0,0 +1,87 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{path::Path, sync::Arc};
+
+use options::TransactionOptions;
+use storage::durability_client::{DurabilityClient, NoDurabilityClient, WALClient};
+
+use crate::{
+    transaction::{ReadTransaction, SchemaTransaction, WriteTransaction},
+    EmbeddedError,
+};
+
+/// A database opened in this process. Cloning it shares the same open database.
+#[derive(Debug)]
+pub struct Database<D> {
+    database: Arc<database::Database<D>>,
+}
+
+impl Database<WALClient> {
+    /// Open the database stored in the directory at `path`, creating it if it does not exist. The database is named
+    /// after the directory.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, EmbeddedError> {
+        let database = database::Database::<WALClient>::open(path.as_ref())
+            .map_err(|typedb_source| EmbeddedError::DatabaseOpen { typedb_source })?;
+        Ok(Self { database: Arc::new(database) })
+    }
+}
+
+impl Database<NoDurabilityClient> {
+    /// Create a database that is only held in memory and is lost when the last clone of it is dropped
+    pub fn open_in_memory(name: impl AsRef<str>) -> Result<Self, EmbeddedError> {
+        let database = database::Database::<NoDurabilityClient>::create_ephemeral(name)
+            .map_err(|typedb_source| EmbeddedError::DatabaseOpen { typedb_source })?;
+        Ok(Self { database: Arc::new(database) })
+    }
+}
+
+impl<D: DurabilityClient + 'static> Database<D> {
+    pub fn name(&self) -> &str {
+        self.database.name()
+    }
+
+    pub fn read_transaction(&self) -> Result<ReadTransaction<D>, EmbeddedError> {
+        self.read_transaction_with_options(TransactionOptions::default())
+    }
+
+    pub fn read_transaction_with_options(
+        &self,
+        options: TransactionOptions,
+    ) -> Result<ReadTransaction<D>, EmbeddedError> {
+        ReadTransaction::open(self.database.clone(), options)
+    }
+
+    /// Open a write transaction, waiting for any open schema transaction to close
+    pub fn write_transaction(&self) -> Result<WriteTransaction<D>, EmbeddedError> {
+        self.write_transaction_with_options(TransactionOptions::default())
+    }
+
+    pub fn write_transaction_with_options(
+        &self,
+        options: TransactionOptions,
+    ) -> Result<WriteTransaction<D>, EmbeddedError> {
+        WriteTransaction::open(self.database.clone(), options)
+    }
+
+    /// Open a schema transaction, waiting for all open write and schema transactions to close
+    pub fn schema_transaction(&self) -> Result<SchemaTransaction<D>, EmbeddedError> {
+        self.schema_transaction_with_options(TransactionOptions::default())
+    }
+
+    pub fn schema_transaction_with_options(
+        &self,
+        options: TransactionOptions,
+    ) -> Result<SchemaTransaction<D>, EmbeddedError> {
+        SchemaTransaction::open(self.database.clone(), options)
+    }
+}
+
+impl<D> Clone for Database<D> {
+    fn clone(&self) -> Self {
+        Self { database: self.database.clone() }
+    }
+}
@@ -0,0 +1,26 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use concept::error::ConceptReadError;
+use database::{
+    transaction::{DataCommitError, SchemaCommitError, TransactionError},
+    DatabaseOpenError,
+};
+use error::typedb_error;
+use query::error::QueryError;
+
+typedb_error!(
+    pub EmbeddedError(component = "Embedded", prefix = "EMB") {
+        DatabaseOpen(1, "Failed to open database.", ( typedb_source: DatabaseOpenError )),
+        TransactionOpen(2, "Failed to open transaction.", ( typedb_source: TransactionError )),
+        Query(3, "Query failed.", ( typedb_source: QueryError )),
+        ConceptRead(4, "Failed to read concept.", ( source: Box<ConceptReadError> )),
+        DataCommit(5, "Failed to commit write transaction.", ( typedb_source: DataCommitError )),
+        SchemaCommit(6, "Failed to commit schema transaction.", ( typedb_source: SchemaCommitError )),
+        SchemaQueryRequiresSchemaTransaction(7, "Schema queries may only be run in schema transactions."),
+        WriteQueryInReadTransaction(8, "Write queries may not be run in read transactions."),
+    }
+);
@@ -0,0 +1,42 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Run TypeDB in-process, without a server. A [`Database`] is opened from its directory, or created in memory, and
+//! queries are run as TypeQL strings in read, write or schema transactions:
+//!
+//! ```ignore
+//! let database = embedded::Database::open("data/social")?;
+//! let mut transaction = database.schema_transaction()?;
+//! transaction.query("define entity person, owns name; attribute name, value string;")?;
+//! transaction.query("insert $p isa person, has name 'Alice';")?;
+//! transaction.commit()?;
+//!
+//! let transaction = database.read_transaction()?;
+//! for row in transaction.query("match $p isa person, has name $n;")?.into_rows()? {
+//!     println!("{}", transaction.attribute_value(row.get_thing("n")?.as_attribute())?);
+//! }
+//! ```
+
+#![deny(unused_must_use)]
+#![deny(elided_lifetimes_in_paths)]
+
+pub use ::answer::{variable_value::VariableValue, Thing, Type};
+pub use encoding::{graph::type_::Kind, value::value::Value};
+pub use options::TransactionOptions;
+pub use storage::durability_client::{NoDurabilityClient, WALClient};
+
+pub use crate::{
+    answer::{AnswerError, Document, Documents, QueryAnswer, Row, Rows},
+    database::Database,
+    error::EmbeddedError,
+    transaction::{ReadTransaction, SchemaTransaction, WriteTransaction},
+};
+
+pub mod answer;
+pub mod database;
+pub mod error;
+mod query;
+pub mod transaction;
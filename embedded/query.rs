@@ -0,0 +1,213 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{collections::HashMap, sync::Arc};
+
+use answer::{Concept, Thing, Type};
+use compiler::VariablePosition;
+use concept::{error::ConceptReadError, thing::thing_manager::ThingManager, type_::type_manager::TypeManager};
+use executor::{
+    batch::Batch,
+    document::{ConceptDocument, DocumentLeaf, DocumentMap, DocumentNode},
+    pipeline::{
+        stage::{ExecutionContext, StageIterator},
+        PipelineExecutionError,
+    },
+    ExecutionInterrupt,
+};
+use function::function_manager::FunctionManager;
+use ir::pipeline::ParameterRegistry;
+use itertools::Itertools;
+use lending_iterator::LendingIterator;
+use options::QueryOptions;
+use query::{error::QueryError, query_manager::QueryManager};
+use storage::snapshot::{ReadableSnapshot, WritableSnapshot};
+use typeql::query::{stage::Stage, Pipeline};
+
+use crate::{
+    answer::{Document, Documents, QueryAnswer, Row, Rows},
+    EmbeddedError,
+};
+
+pub(crate) fn parse(query: &str) -> Result<typeql::Query, EmbeddedError> {
+    typeql::parse_query(query).map_err(|typedb_source| EmbeddedError::Query {
+        typedb_source: QueryError::ParseError { query: query.to_owned(), typedb_source },
+    })
+}
+
+pub(crate) fn is_write_pipeline(pipeline: &Pipeline) -> bool {
+    pipeline
+        .stages
+        .iter()
+        .any(|stage| matches!(stage, Stage::Insert(_) | Stage::Put(_) | Stage::Delete(_) | Stage::Update(_)))
+}
+
+pub(crate) fn execute_read_pipeline<Snapshot: ReadableSnapshot + 'static>(
+    snapshot: Arc<Snapshot>,
+    type_manager: &TypeManager,
+    thing_manager: Arc<ThingManager>,
+    function_manager: &FunctionManager,
+    query_manager: &QueryManager,
+    pipeline: &Pipeline,
+) -> Result<QueryAnswer, EmbeddedError> {
+    let to_error = |typedb_source| EmbeddedError::Query { typedb_source };
+    let read_error = |typedb_source| to_error(QueryError::ReadPipelineExecution { typedb_source });
+    let pipeline = query_manager
+        .prepare_read_pipeline(
+            snapshot.clone(),
+            type_manager,
+            thing_manager.clone(),
+            function_manager,
+            pipeline,
+            QueryOptions::default(),
+        )
+        .map_err(to_error)?;
+    if pipeline.has_fetch() {
+        let (iterator, ExecutionContext { parameters, .. }) = pipeline
+            .into_documents_iterator(ExecutionInterrupt::new_uninterruptible())
+            .map_err(|(err, _)| read_error(err))?;
+        let documents: Vec<ConceptDocument> = iterator.collect::<Result<_, _>>().map_err(read_error)?;
+        Ok(QueryAnswer::Documents(to_documents(documents, &*snapshot, type_manager, &thing_manager, &parameters)?))
+    } else {
+        let positions = pipeline.rows_positions().expect("Expected unfetched result").clone();
+        let (iterator, _) = pipeline
+            .into_rows_iterator(ExecutionInterrupt::new_uninterruptible())
+            .map_err(|(err, _)| read_error(err))?;
+        Ok(QueryAnswer::Rows(to_rows(iterator.collect_owned().map_err(read_error)?, positions)))
+    }
+}
+
+/// Runs a pipeline that writes, returning the snapshot it wrote to even if it failed.
+pub(crate) fn execute_write_pipeline<Snapshot: WritableSnapshot + 'static>(
+    snapshot: Snapshot,
+    type_manager: &TypeManager,
+    thing_manager: Arc<ThingManager>,
+    function_manager: &FunctionManager,
+    query_manager: &QueryManager,
+    pipeline: &Pipeline,
+) -> (Snapshot, Result<QueryAnswer, EmbeddedError>) {
+    let to_error = |typedb_source| EmbeddedError::Query { typedb_source };
+    let write_error = |typedb_source| to_error(QueryError::WritePipelineExecution { typedb_source });
+    let pipeline = match query_manager.prepare_write_pipeline(
+        snapshot,
+        type_manager,
+        thing_manager.clone(),
+        function_manager,
+        pipeline,
+        QueryOptions::default(),
+    ) {
+        Ok(pipeline) => pipeline,
+        Err((snapshot, err)) => return (snapshot, Err(to_error(err))),
+    };
+    let (snapshot, result) = if pipeline.has_fetch() {
+        match pipeline.into_documents_iterator(ExecutionInterrupt::new_uninterruptible()) {
+            Ok((iterator, ExecutionContext { snapshot, parameters, .. })) => {
+                let documents: Result<Vec<ConceptDocument>, Box<PipelineExecutionError>> = iterator.collect();
+                let result = documents.map_err(write_error).and_then(|documents| {
+                    to_documents(documents, &*snapshot, type_manager, &thing_manager, &parameters)
+                        .map(QueryAnswer::Documents)
+                });
+                (snapshot, result)
+            }
+            Err((err, ExecutionContext { snapshot, .. })) => (snapshot, Err(write_error(err))),
+        }
+    } else {
+        let positions = pipeline.rows_positions().expect("Expected unfetched result").clone();
+        match pipeline.into_rows_iterator(ExecutionInterrupt::new_uninterruptible()) {
+            Ok((iterator, ExecutionContext { snapshot, .. })) => {
+                let result = iterator.collect_owned().map(|batch| QueryAnswer::Rows(to_rows(batch, positions)));
+                (snapshot, result.map_err(write_error))
+            }
+            Err((err, ExecutionContext { snapshot, .. })) => (snapshot, Err(write_error(err))),
+        }
+    };
+    // answers are collected in full, so nothing else holds the snapshot
+    (Arc::into_inner(snapshot).unwrap(), result)
+}
+
+fn to_rows(batch: Batch, positions: HashMap<String, VariablePosition>) -> Rows {
+    let (columns, positions): (Vec<String>, Vec<VariablePosition>) = positions.into_iter().sorted().unzip();
+    let columns: Arc<[String]> = columns.into();
+    let row_columns = columns.clone();
+    let rows = batch
+        .into_iterator_mut()
+        .map_static(move |row| {
+            let values = positions.iter().map(|position| row.get(*position).clone().into_owned()).collect();
+            Row::new(row_columns.clone(), values)
+        })
+        .collect();
+    Rows::new(columns, rows)
+}
+
+fn to_documents(
+    documents: Vec<ConceptDocument>,
+    snapshot: &impl ReadableSnapshot,
+    type_manager: &TypeManager,
+    thing_manager: &ThingManager,
+    parameters: &ParameterRegistry,
+) -> Result<Documents, EmbeddedError> {
+    let documents = documents
+        .into_iter()
+        .map(|document| to_document(document.root, snapshot, type_manager, thing_manager, parameters))
+        .collect::<Result<_, _>>()
+        .map_err(|source| EmbeddedError::ConceptRead { source })?;
+    Ok(Documents::new(documents))
+}
+
+fn to_document(
+    node: DocumentNode,
+    snapshot: &impl ReadableSnapshot,
+    type_manager: &TypeManager,
+    thing_manager: &ThingManager,
+    parameters: &ParameterRegistry,
+) -> Result<Document, Box<ConceptReadError>> {
+    match node {
+        DocumentNode::List(list) => Ok(Document::List(
+            list.list
+                .into_iter()
+                .map(|node| to_document(node, snapshot, type_manager, thing_manager, parameters))
+                .collect::<Result<_, _>>()?,
+        )),
+        DocumentNode::Map(DocumentMap::UserKeys(map)) => Ok(Document::Map(
+            map.into_iter()
+                .map(|(key, node)| -> Result<_, Box<ConceptReadError>> {
+                    let key = parameters.fetch_key(key).expect("Expected key in parameters to get its name");
+                    Ok((key.to_owned(), to_document(node, snapshot, type_manager, thing_manager, parameters)?))
+                })
+                .collect::<Result<_, _>>()?,
+        )),
+        DocumentNode::Map(DocumentMap::GeneratedKeys(map)) => Ok(Document::Map(
+            map.into_iter()
+                .map(|(label, node)| -> Result<_, Box<ConceptReadError>> {
+                    let key = label.scoped_name().as_str().to_owned();
+                    Ok((key, to_document(node, snapshot, type_manager, thing_manager, parameters)?))
+                })
+                .collect::<Result<_, _>>()?,
+        )),
+        DocumentNode::Leaf(DocumentLeaf::Empty) => Ok(Document::Empty),
+        DocumentNode::Leaf(DocumentLeaf::Kind(kind)) => Ok(Document::Kind(kind)),
+        DocumentNode::Leaf(DocumentLeaf::Concept(concept)) => match concept {
+            Concept::Type(type_) => {
+                Ok(Document::Type { kind: type_.kind(), label: type_label(&type_, snapshot, type_manager)? })
+            }
+            Concept::Thing(Thing::Attribute(attribute)) => {
+                Ok(Document::Value(attribute.get_value(snapshot, thing_manager)?.into_owned()))
+            }
+            Concept::Thing(Thing::Entity(_) | Thing::Relation(_)) => {
+                unreachable!("Fetched documents only contain attributes of entities and relations")
+            }
+            Concept::Value(value) => Ok(Document::Value(value.into_owned())),
+        },
+    }
+}
+
+pub(crate) fn type_label(
+    type_: &Type,
+    snapshot: &impl ReadableSnapshot,
+    type_manager: &TypeManager,
+) -> Result<String, Box<ConceptReadError>> {
+    Ok(type_.get_label(snapshot, type_manager)?.scoped_name().as_str().to_owned())
+}
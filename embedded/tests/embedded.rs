@@ -0,0 +1,91 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use embedded::{Database, Document, EmbeddedError, Value};
+use test_utils::{create_tmp_dir, init_logging};
+
+const SCHEMA: &str = r#"
+define
+    entity person, owns name, owns age;
+    attribute name, value string;
+    attribute age, value long;
+"#;
+
+#[test]
+fn query_rows_and_documents() {
+    init_logging();
+    let database_path = create_tmp_dir().join("embedded");
+    {
+        let database = Database::open(&database_path).unwrap();
+        let mut transaction = database.schema_transaction().unwrap();
+        transaction.query(SCHEMA).unwrap();
+        transaction.commit().unwrap();
+
+        let mut transaction = database.write_transaction().unwrap();
+        let inserted = transaction.query("insert $p isa person, has name 'Alice', has age 30;").unwrap();
+        assert_eq!(inserted.into_rows().unwrap().len(), 1);
+        transaction.query("insert $p isa person, has name 'Bob', has age 40;").unwrap();
+        transaction.commit().unwrap();
+    }
+
+    let database = Database::open(&database_path).unwrap();
+    let transaction = database.read_transaction().unwrap();
+    let rows =
+        transaction.query("match $p isa $t, has age $a; $t label person; sort $a;").unwrap().into_rows().unwrap();
+    assert_eq!(rows.columns(), ["a", "p", "t"]);
+    let ages: Vec<Value<'static>> =
+        rows.map(|row| transaction.attribute_value(row.get_thing("a").unwrap().as_attribute()).unwrap()).collect();
+    assert_eq!(ages, [Value::Long(30), Value::Long(40)]);
+
+    let row = transaction.query("match $p isa $t, has name 'Alice';").unwrap().into_rows().unwrap().next().unwrap();
+    assert_eq!(transaction.type_label(row.get_type("t").unwrap()).unwrap(), "person");
+    assert!(row.get_value("p").is_err());
+    assert!(row.get("missing").is_err());
+
+    let documents: Vec<Document> = transaction
+        .query("match $p isa person, has name 'Bob'; fetch { 'name': $p.name, 'age': $p.age };")
+        .unwrap()
+        .into_documents()
+        .unwrap()
+        .collect();
+    let [document] = documents.as_slice() else { panic!("Expected one document, found {documents:?}") };
+    assert_eq!(document.get("name").and_then(Document::as_value), Some(&Value::String("Bob".into())));
+    assert_eq!(document.get("age").and_then(Document::as_value), Some(&Value::Long(40)));
+
+    assert!(matches!(
+        transaction.query("insert $p isa person;"),
+        Err(EmbeddedError::WriteQueryInReadTransaction { .. })
+    ));
+    transaction.close();
+}
+
+#[test]
+fn in_memory_database_rolls_back_and_rejects_schema_writes() {
+    init_logging();
+    let database = Database::open_in_memory("embedded").unwrap();
+    let mut transaction = database.schema_transaction().unwrap();
+    transaction.query(SCHEMA).unwrap();
+    transaction.commit().unwrap();
+
+    let mut transaction = database.write_transaction().unwrap();
+    assert!(matches!(
+        transaction.query("define entity company;"),
+        Err(EmbeddedError::SchemaQueryRequiresSchemaTransaction { .. })
+    ));
+    transaction.query("insert $p isa person, has name 'Alice';").unwrap();
+    let count = |transaction: &mut embedded::WriteTransaction<_>| {
+        transaction.query("match $p isa person;").unwrap().into_rows().unwrap().len()
+    };
+    assert_eq!(count(&mut transaction), 1);
+    transaction.rollback();
+    assert_eq!(count(&mut transaction), 0);
+    // dropping the transaction releases it, so the next one opens without waiting
+    drop(transaction);
+
+    let mut transaction = database.write_transaction().unwrap();
+    assert_eq!(count(&mut transaction), 0);
+    transaction.close();
+}
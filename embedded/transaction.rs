@@ -0,0 +1,295 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::Arc;
+
+use answer::Type;
+use concept::thing::attribute::Attribute;
+use database::{
+    transaction::{TransactionRead, TransactionSchema, TransactionWrite},
+    Database,
+};
+use encoding::value::value::Value;
+use options::TransactionOptions;
+use storage::durability_client::DurabilityClient;
+use typeql::Query;
+
+use crate::{
+    answer::QueryAnswer,
+    query::{execute_read_pipeline, execute_write_pipeline, is_write_pipeline, parse, type_label},
+    EmbeddedError,
+};
+
+/// A transaction that reads the database as of when it was opened
+#[derive(Debug)]
+pub struct ReadTransaction<D> {
+    transaction: TransactionRead<D>,
+}
+
+impl<D: DurabilityClient + 'static> ReadTransaction<D> {
+    pub(crate) fn open(database: Arc<Database<D>>, options: TransactionOptions) -> Result<Self, EmbeddedError> {
+        let transaction = TransactionRead::open(database, options)
+            .map_err(|typedb_source| EmbeddedError::TransactionOpen { typedb_source })?;
+        Ok(Self { transaction })
+    }
+
+    /// Run a read query. Schema and write queries are rejected.
+    pub fn query(&self, query: &str) -> Result<QueryAnswer, EmbeddedError> {
+        let pipeline = match parse(query)? {
+            Query::Schema(_) => return Err(EmbeddedError::SchemaQueryRequiresSchemaTransaction {}),
+            Query::Pipeline(pipeline) if is_write_pipeline(&pipeline) => {
+                return Err(EmbeddedError::WriteQueryInReadTransaction {})
+            }
+            Query::Pipeline(pipeline) => pipeline,
+        };
+        let transaction = &self.transaction;
+        execute_read_pipeline(
+            transaction.snapshot.clone(),
+            &transaction.type_manager,
+            transaction.thing_manager.clone(),
+            &transaction.function_manager,
+            &transaction.query_manager,
+            &pipeline,
+        )
+    }
+
+    pub fn type_label(&self, type_: &Type) -> Result<String, EmbeddedError> {
+        type_label(type_, &*self.transaction.snapshot, &self.transaction.type_manager)
+            .map_err(|source| EmbeddedError::ConceptRead { source })
+    }
+
+    pub fn attribute_value(&self, attribute: &Attribute) -> Result<Value<'static>, EmbeddedError> {
+        attribute
+            .get_value(&*self.transaction.snapshot, &self.transaction.thing_manager)
+            .map(Value::into_owned)
+            .map_err(|source| EmbeddedError::ConceptRead { source })
+    }
+
+    pub fn close(self) {
+        self.transaction.close()
+    }
+}
+
+/// A transaction that reads and writes data. It sees its own writes, which become visible to others on commit.
+/// Queries that fail part way may leave some of their writes behind, so the transaction should then be rolled back
+/// or closed. Dropping the transaction closes it without committing.
+#[derive(Debug)]
+pub struct WriteTransaction<D: DurabilityClient> {
+    // NOTE: only `None` once committed or closed, or while a write query holds the snapshot
+    transaction: Option<TransactionWrite<D>>,
+}
+
+impl<D: DurabilityClient + 'static> WriteTransaction<D> {
+    pub(crate) fn open(database: Arc<Database<D>>, options: TransactionOptions) -> Result<Self, EmbeddedError> {
+        let transaction = TransactionWrite::open(database, options)
+            .map_err(|typedb_source| EmbeddedError::TransactionOpen { typedb_source })?;
+        Ok(Self { transaction: Some(transaction) })
+    }
+
+    /// Run a read or write query. Schema queries are rejected.
+    pub fn query(&mut self, query: &str) -> Result<QueryAnswer, EmbeddedError> {
+        let pipeline = match parse(query)? {
+            Query::Schema(_) => return Err(EmbeddedError::SchemaQueryRequiresSchemaTransaction {}),
+            Query::Pipeline(pipeline) => pipeline,
+        };
+        if !is_write_pipeline(&pipeline) {
+            let transaction = self.transaction();
+            return execute_read_pipeline(
+                transaction.snapshot.clone(),
+                &transaction.type_manager,
+                transaction.thing_manager.clone(),
+                &transaction.function_manager,
+                &transaction.query_manager,
+                &pipeline,
+            );
+        }
+
+        let TransactionWrite {
+            snapshot,
+            type_manager,
+            thing_manager,
+            function_manager,
+            query_manager,
+            database,
+            transaction_options,
+        } = self.transaction.take().unwrap();
+        let (snapshot, result) = execute_write_pipeline(
+            Arc::into_inner(snapshot).unwrap(),
+            &type_manager,
+            thing_manager.clone(),
+            &function_manager,
+            &query_manager,
+            &pipeline,
+        );
+        self.transaction = Some(TransactionWrite::from(
+            Arc::new(snapshot),
+            type_manager,
+            thing_manager,
+            function_manager,
+            query_manager,
+            database,
+            transaction_options,
+        ));
+        result
+    }
+
+    pub fn type_label(&self, type_: &Type) -> Result<String, EmbeddedError> {
+        let transaction = self.transaction();
+        type_label(type_, &*transaction.snapshot, &transaction.type_manager)
+            .map_err(|source| EmbeddedError::ConceptRead { source })
+    }
+
+    pub fn attribute_value(&self, attribute: &Attribute) -> Result<Value<'static>, EmbeddedError> {
+        let transaction = self.transaction();
+        attribute
+            .get_value(&*transaction.snapshot, &transaction.thing_manager)
+            .map(Value::into_owned)
+            .map_err(|source| EmbeddedError::ConceptRead { source })
+    }
+
+    pub fn commit(mut self) -> Result<(), EmbeddedError> {
+        let transaction = self.transaction.take().unwrap();
+        transaction.commit().map_err(|typedb_source| EmbeddedError::DataCommit { typedb_source })
+    }
+
+    /// Discard the writes made so far, leaving the transaction open
+    pub fn rollback(&mut self) {
+        self.transaction.as_mut().unwrap().rollback()
+    }
+
+    pub fn close(self) {
+        drop(self)
+    }
+
+    fn transaction(&self) -> &TransactionWrite<D> {
+        self.transaction.as_ref().unwrap()
+    }
+}
+
+impl<D: DurabilityClient> Drop for WriteTransaction<D> {
+    fn drop(&mut self) {
+        if let Some(transaction) = self.transaction.take() {
+            transaction.close()
+        }
+    }
+}
+
+/// A transaction that may change the schema as well as data, excluding all other writers while it is open.
+/// Queries that fail part way may leave some of their writes behind, so the transaction should then be rolled back
+/// or closed. Dropping the transaction closes it without committing.
+#[derive(Debug)]
+pub struct SchemaTransaction<D: DurabilityClient> {
+    // NOTE: only `None` once committed or closed, or while a write query holds the snapshot
+    transaction: Option<TransactionSchema<D>>,
+}
+
+impl<D: DurabilityClient + 'static> SchemaTransaction<D> {
+    pub(crate) fn open(database: Arc<Database<D>>, options: TransactionOptions) -> Result<Self, EmbeddedError> {
+        let transaction = TransactionSchema::open(database, options)
+            .map_err(|typedb_source| EmbeddedError::TransactionOpen { typedb_source })?;
+        Ok(Self { transaction: Some(transaction) })
+    }
+
+    /// Run a schema, read or write query
+    pub fn query(&mut self, query: &str) -> Result<QueryAnswer, EmbeddedError> {
+        let pipeline = match parse(query)? {
+            Query::Schema(schema_query) => {
+                let transaction = self.transaction.as_mut().unwrap();
+                transaction
+                    .query_manager
+                    .execute_schema(
+                        Arc::get_mut(&mut transaction.snapshot).unwrap(),
+                        &transaction.type_manager,
+                        &transaction.thing_manager,
+                        &transaction.function_manager,
+                        schema_query,
+                    )
+                    .map_err(|typedb_source| EmbeddedError::Query { typedb_source })?;
+                return Ok(QueryAnswer::Ok);
+            }
+            Query::Pipeline(pipeline) => pipeline,
+        };
+        if !is_write_pipeline(&pipeline) {
+            let transaction = self.transaction();
+            return execute_read_pipeline(
+                transaction.snapshot.clone(),
+                &transaction.type_manager,
+                transaction.thing_manager.clone(),
+                &transaction.function_manager,
+                &transaction.query_manager,
+                &pipeline,
+            );
+        }
+
+        let TransactionSchema {
+            snapshot,
+            type_manager,
+            thing_manager,
+            function_manager,
+            query_manager,
+            database,
+            transaction_options,
+        } = self.transaction.take().unwrap();
+        let (snapshot, result) = execute_write_pipeline(
+            Arc::into_inner(snapshot).unwrap(),
+            &type_manager,
+            thing_manager.clone(),
+            &function_manager,
+            &query_manager,
+            &pipeline,
+        );
+        self.transaction = Some(TransactionSchema::from(
+            snapshot,
+            type_manager,
+            thing_manager,
+            function_manager,
+            query_manager,
+            database,
+            transaction_options,
+        ));
+        result
+    }
+
+    pub fn type_label(&self, type_: &Type) -> Result<String, EmbeddedError> {
+        let transaction = self.transaction();
+        type_label(type_, &*transaction.snapshot, &transaction.type_manager)
+            .map_err(|source| EmbeddedError::ConceptRead { source })
+    }
+
+    pub fn attribute_value(&self, attribute: &Attribute) -> Result<Value<'static>, EmbeddedError> {
+        let transaction = self.transaction();
+        attribute
+            .get_value(&*transaction.snapshot, &transaction.thing_manager)
+            .map(Value::into_owned)
+            .map_err(|source| EmbeddedError::ConceptRead { source })
+    }
+
+    pub fn commit(mut self) -> Result<(), EmbeddedError> {
+        let transaction = self.transaction.take().unwrap();
+        transaction.commit().map_err(|typedb_source| EmbeddedError::SchemaCommit { typedb_source })
+    }
+
+    /// Discard the changes made so far, leaving the transaction open
+    pub fn rollback(&mut self) {
+        self.transaction.as_mut().unwrap().rollback()
+    }
+
+    pub fn close(self) {
+        drop(self)
+    }
+
+    fn transaction(&self) -> &TransactionSchema<D> {
+        self.transaction.as_ref().unwrap()
+    }
+}
+
+impl<D: DurabilityClient> Drop for SchemaTransaction<D> {
+    fn drop(&mut self) {
+        if let Some(transaction) = self.transaction.take() {
+            transaction.close()
+        }
+    }
+}
@@ -695,6 +695,10 @@ impl ThingEdgeLinksIndex {
         StorageKey::new_owned(Self::KEYSPACE, bytes)
     }
 
+    pub fn prefix() -> StorageKey<'static, { PrefixID::LENGTH }> {
+        StorageKey::new_owned(Self::KEYSPACE, ByteArray::copy(&Self::PREFIX.prefix_id().to_bytes()))
+    }
+
     pub fn is_index(key: &StorageKeyArray<BUFFER_KEY_INLINE>) -> bool {
         key.keyspace_id() == Self::KEYSPACE.id()
             && key.bytes().len() == Self::LENGTH
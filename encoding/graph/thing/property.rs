@@ -11,7 +11,11 @@ use resource::constants::snapshot::BUFFER_KEY_INLINE;
 use storage::key_value::StorageKey;
 
 use crate::{
-    graph::{thing::vertex_object::ObjectVertex, type_::vertex::TypeVertex, Typed},
+    graph::{
+        thing::{vertex_object::ObjectVertex, ThingVertex},
+        type_::vertex::TypeVertex,
+        Typed,
+    },
     layout::{
         infix::{Infix, InfixID},
         prefix::{Prefix, PrefixID},
@@ -65,6 +69,17 @@ impl ObjectVertexProperty {
         Self { object, infix, suffix: Some(ByteArray::copy(&suffix)) }
     }
 
+    pub fn decode(bytes: Bytes<'_, BUFFER_KEY_INLINE>) -> Self {
+        debug_assert!(bytes.length() >= Self::LENGTH_NO_SUFFIX);
+        debug_assert_eq!(bytes[Self::INDEX_PREFIX], Self::PREFIX.prefix_id().byte);
+
+        let object = ObjectVertex::decode(&bytes[Self::range_object_vertex()]);
+        let infix = Infix::from_infix_id(InfixID::new((&bytes[Self::range_infix()]).try_into().unwrap()));
+        let suffix =
+            (bytes.length() > Self::LENGTH_NO_SUFFIX).then(|| ByteArray::copy(&bytes[Self::LENGTH_NO_SUFFIX..]));
+        Self { object, infix, suffix }
+    }
+
     pub fn build_prefix() -> StorageKey<'static, { ObjectVertexProperty::LENGTH_PREFIX }> {
         // TODO: is it better to have a const fn that is a reference to owned memory, or
         //       to always induce a tiny copy have a non-const function?
@@ -27,6 +27,8 @@ macro_rules! make_prefix_enum {
         }
 
         impl Prefix {
+            pub const ALL: &'static [Prefix] = &[$(Self::$name,)*];
+
             pub const fn prefix_id(&self) -> PrefixID {
                 match self {
                     $(Self::$name => PrefixID::new($byte),)*
@@ -46,6 +48,13 @@ macro_rules! make_prefix_enum {
                 }
            }
 
+            pub fn try_from_prefix_id(prefix: PrefixID) -> Option<Self> {
+                match prefix.byte {
+                    $($byte => Some(Self::$name),)*
+                    _ => None,
+                }
+            }
+
             ///
             /// Return true if we expect all keys within this exact prefix to have the same width.
             /// Note: two different prefixes with fixed width are not necessarily the same fixed widths!
@@ -19,7 +19,7 @@ use crate::{
     row::{MaybeOwnedRow, Row},
 };
 
-const FIXED_BATCH_ROWS_MAX: u32 = 64;
+pub const FIXED_BATCH_ROWS_MAX: u32 = 64;
 
 #[derive(Debug)]
 pub struct FixedBatch {
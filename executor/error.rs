@@ -8,7 +8,7 @@ use compiler::annotation::expression::instructions::ExpressionEvaluationError;
 use concept::error::ConceptReadError;
 use error::typedb_error;
 
-use crate::InterruptType;
+use crate::{memory::MemoryLimitError, InterruptType};
 
 typedb_error!(
     pub ReadExecutionError(component = "Read execution", prefix = "REX") {
@@ -17,5 +17,6 @@ typedb_error!(
         CreatingIterator(3, "Error creating iterator from {instruction_name} instruction.", instruction_name: String, ( source: Box<ConceptReadError> )),
         AdvancingIteratorTo(4, "Error moving iterator (by steps or seek) to target value.", ( source: Box<ConceptReadError> )),
         ExpressionEvaluate(5, "Error evaluating expression", ( source: ExpressionEvaluationError )),
+        MemoryLimit(6, "Query memory limit exceeded.", ( typedb_source: MemoryLimitError )),
     }
 );
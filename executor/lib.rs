@@ -17,6 +17,7 @@ pub mod document;
 pub mod error;
 pub(crate) mod instruction;
 pub mod match_executor;
+pub mod memory;
 pub mod pipeline;
 pub mod profile;
 pub mod read;
@@ -54,6 +55,10 @@ pub enum InterruptType {
     TransactionRolledback,
     WriteQueryExecution,
     SchemaQueryExecution,
+    KillRequest,
+    TransactionTimedOut,
+    QueryCancelled,
+    QueryTimedOut,
 }
 
 impl fmt::Display for InterruptType {
@@ -64,6 +69,10 @@ impl fmt::Display for InterruptType {
             InterruptType::TransactionRolledback => write!(f, "transaction rollback"),
             InterruptType::WriteQueryExecution => write!(f, "write query"),
             InterruptType::SchemaQueryExecution => write!(f, "schema query"),
+            InterruptType::KillRequest => write!(f, "kill request"),
+            InterruptType::TransactionTimedOut => write!(f, "transaction timeout"),
+            InterruptType::QueryCancelled => write!(f, "query cancel request"),
+            InterruptType::QueryTimedOut => write!(f, "query timeout"),
         }
     }
 }
@@ -71,15 +80,21 @@ impl fmt::Display for InterruptType {
 #[derive(Debug)]
 pub struct ExecutionInterrupt {
     signal: Option<tokio::sync::broadcast::Receiver<InterruptType>>,
+    query_signal: Option<tokio::sync::broadcast::Receiver<InterruptType>>,
 }
 
 impl ExecutionInterrupt {
     pub fn new(signal: tokio::sync::broadcast::Receiver<InterruptType>) -> Self {
-        Self { signal: Some(signal) }
+        Self { signal: Some(signal), query_signal: None }
     }
 
     pub fn new_uninterruptible() -> Self {
-        Self { signal: None }
+        Self { signal: None, query_signal: None }
+    }
+
+    // Also interrupted by a signal for this query alone, while the shared signal interrupts every query it is cloned to
+    pub fn with_query_signal(self, query_signal: tokio::sync::broadcast::Receiver<InterruptType>) -> Self {
+        Self { signal: self.signal, query_signal: Some(query_signal) }
     }
 
     pub fn check(&mut self) -> Option<InterruptType> {
@@ -87,7 +102,7 @@ impl ExecutionInterrupt {
         //       optimise it by caching the last time it was checked, and only actually check
         //       the signal once T micros/millis are elapsed... if this is really really cheap we can
         //       check the optimised interrupt in really hot loops as well.
-        match &mut self.signal {
+        let interrupt = match &mut self.signal {
             None => None,
             Some(signal) => match signal.try_recv() {
                 Ok(type_) => Some(type_),
@@ -96,13 +111,18 @@ impl ExecutionInterrupt {
                     unreachable!("Unexpected interrupt signal state. They should never be lagged or closed before cleaning up the receivers.")
                 }
             },
-        }
+        };
+        // the query signal is closed once the query is no longer tracked, which may be before it stops checking
+        interrupt.or_else(|| self.query_signal.as_mut()?.try_recv().ok())
     }
 }
 
 impl Clone for ExecutionInterrupt {
     // Note: going against tokio's broadcast signal convention, which explicitly isn't `clone()`
     fn clone(&self) -> Self {
-        Self { signal: self.signal.as_ref().map(|signal| signal.resubscribe()) }
+        Self {
+            signal: self.signal.as_ref().map(|signal| signal.resubscribe()),
+            query_signal: self.query_signal.as_ref().map(|signal| signal.resubscribe()),
+        }
     }
 }
@@ -0,0 +1,157 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    mem::{size_of, size_of_val},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use answer::{variable_value::VariableValue, Concept, Thing};
+use encoding::value::value::Value;
+use error::typedb_error;
+
+use crate::{
+    document::{DocumentLeaf, DocumentMap, DocumentNode},
+    pipeline::external_sort::SortSpill,
+};
+
+// Memory held by the queries of every database, since they share the server's memory
+static SERVER_MEMORY_USED: AtomicU64 = AtomicU64::new(0);
+static SERVER_MEMORY_LIMIT: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Limits the memory the buffers of all running queries may hold together. Unlimited by default.
+pub fn set_server_memory_limit(limit_bytes: Option<u64>) {
+    SERVER_MEMORY_LIMIT.store(limit_bytes.unwrap_or(u64::MAX), Ordering::Relaxed);
+}
+
+pub fn server_memory_used() -> u64 {
+    SERVER_MEMORY_USED.load(Ordering::Relaxed)
+}
+
+/// The memory held by the buffers of one query: the answer tables of tabled functions, sort buffers, reduce groups
+/// and fetched documents. It is counted against the query's own limit and the limit shared by all queries of the
+/// server, and the query fails as soon as it would exceed either. Given a spill, sorts write their rows to disk
+/// instead.
+#[derive(Debug, Default)]
+pub struct QueryMemory {
+    used: AtomicU64,
+    limit: Option<u64>,
+    sort_spill: Option<SortSpill>,
+}
+
+impl QueryMemory {
+    pub fn new(limit_bytes: Option<u64>, sort_spill: Option<SortSpill>) -> Self {
+        Self { used: AtomicU64::new(0), limit: limit_bytes, sort_spill }
+    }
+
+    pub fn used(&self) -> u64 {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn sort_spill(&self) -> Option<&SortSpill> {
+        self.sort_spill.as_ref()
+    }
+
+    fn reserve(&self, bytes: u64) -> Result<(), MemoryLimitError> {
+        let used = self.used.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        if let Some(limit) = self.limit.filter(|limit| used > *limit) {
+            self.used.fetch_sub(bytes, Ordering::Relaxed);
+            return Err(MemoryLimitError::QueryLimitExceeded { limit });
+        }
+        let server_used = SERVER_MEMORY_USED.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        let server_limit = SERVER_MEMORY_LIMIT.load(Ordering::Relaxed);
+        if server_used > server_limit {
+            self.release(bytes);
+            return Err(MemoryLimitError::ServerLimitExceeded { limit: server_limit });
+        }
+        Ok(())
+    }
+
+    fn release(&self, bytes: u64) {
+        self.used.fetch_sub(bytes, Ordering::Relaxed);
+        SERVER_MEMORY_USED.fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+/// Memory reserved by one buffer of a query as it grows, released when the buffer is dropped.
+#[derive(Debug)]
+pub(crate) struct MemoryReservation {
+    memory: Arc<QueryMemory>,
+    bytes: u64,
+}
+
+impl MemoryReservation {
+    pub(crate) fn new(memory: Arc<QueryMemory>) -> Self {
+        Self { memory, bytes: 0 }
+    }
+
+    pub(crate) fn grow(&mut self, bytes: u64) -> Result<(), MemoryLimitError> {
+        self.memory.reserve(bytes)?;
+        self.bytes += bytes;
+        Ok(())
+    }
+
+    pub(crate) fn shrink(&mut self, bytes: u64) {
+        debug_assert!(bytes <= self.bytes);
+        self.memory.release(bytes);
+        self.bytes -= bytes;
+    }
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        self.memory.release(self.bytes)
+    }
+}
+
+// Sizes are estimates: the inline size of the values plus the heap allocations that grow with the data
+pub(crate) fn row_size(row: &[VariableValue<'_>]) -> u64 {
+    let heap_size: usize = row
+        .iter()
+        .map(|value| match value {
+            VariableValue::Value(value) => value_heap_size(value),
+            VariableValue::ThingList(things) => things.len() * size_of::<Thing>(),
+            VariableValue::ValueList(values) => {
+                values.iter().map(|value| size_of_val(value) + value_heap_size(value)).sum()
+            }
+            VariableValue::Empty | VariableValue::Type(_) | VariableValue::Thing(_) => 0,
+        })
+        .sum();
+    (size_of_val(row) + size_of::<u64>() + heap_size) as u64
+}
+
+pub(crate) fn document_size(node: &DocumentNode) -> u64 {
+    let heap_size = match node {
+        DocumentNode::List(list) => list.list.iter().map(document_size).sum(),
+        DocumentNode::Map(DocumentMap::UserKeys(map)) => map.values().map(document_size).sum(),
+        DocumentNode::Map(DocumentMap::GeneratedKeys(map)) => map.values().map(document_size).sum(),
+        DocumentNode::Leaf(DocumentLeaf::Concept(Concept::Value(value))) => value_heap_size(value) as u64,
+        DocumentNode::Leaf(_) => 0,
+    };
+    size_of::<DocumentNode>() as u64 + heap_size
+}
+
+fn value_heap_size(value: &Value<'_>) -> usize {
+    match value {
+        Value::String(string) => string.len(),
+        Value::Struct(struct_) => size_of_val(struct_.as_ref()),
+        _ => 0,
+    }
+}
+
+typedb_error!(
+    pub MemoryLimitError(component = "Query memory", prefix = "QME") {
+        QueryLimitExceeded(1, "The query needs more than its memory limit of {limit} bytes.", limit: u64),
+        ServerLimitExceeded(
+            2,
+            "The query needs more memory than is left of the {limit} bytes shared by the queries of the server.",
+            limit: u64
+        ),
+    }
+);
@@ -10,6 +10,7 @@ use compiler::executable::delete::{executable::DeleteExecutable, instructions::C
 use concept::thing::thing_manager::ThingManager;
 use ir::pipeline::ParameterRegistry;
 use storage::snapshot::WritableSnapshot;
+use tracing::{span, Level};
 
 use crate::{
     pipeline::{
@@ -48,6 +49,7 @@ where
         (Box<PipelineExecutionError>, ExecutionContext<Snapshot>),
     > {
         let (previous_iterator, mut context) = self.previous.into_iterator(interrupt.clone())?;
+        let _span = span!(Level::DEBUG, "delete_stage", executable_id = self.executable.executable_id).entered();
         // accumulate once, then we will operate in-place
         let mut batch = match previous_iterator.collect_owned() {
             Ok(batch) => batch,
@@ -115,5 +117,5 @@ pub fn execute_delete(
         index += 1;
     }
 
-    Ok(())
+    snapshot.check_write_buffer().map_err(|typedb_source| Box::new(WriteError::WriteBuffer { typedb_source }))
 }
@@ -0,0 +1,761 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::BinaryHeap,
+    fs,
+    fs::File,
+    io,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{atomic, atomic::AtomicU64, Arc},
+    vec,
+};
+
+use answer::{variable_value::VariableValue, Thing, Type};
+use bytes::{byte_array::ByteArray, Bytes};
+use compiler::executable::modifiers::SortExecutable;
+use concept::{
+    thing::{attribute::Attribute, object::Object, ThingAPI},
+    type_::{
+        attribute_type::AttributeType, entity_type::EntityType, relation_type::RelationType, role_type::RoleType,
+        TypeAPI,
+    },
+};
+use encoding::{
+    graph::{
+        thing::{vertex_attribute::AttributeVertex, vertex_object::ObjectVertex},
+        type_::vertex::{TypeVertex, TypeVertexEncoding},
+    },
+    value::{
+        boolean_bytes::BooleanBytes, date_bytes::DateBytes, date_time_bytes::DateTimeBytes,
+        date_time_tz_bytes::DateTimeTZBytes, decimal_bytes::DecimalBytes, double_bytes::DoubleBytes,
+        duration_bytes::DurationBytes, long_bytes::LongBytes, string_bytes::StringBytes, struct_bytes::StructBytes,
+        value::Value, value_type::ValueTypeCategory, ValueEncodable,
+    },
+    AsBytes,
+};
+use error::typedb_error;
+use ir::pipeline::modifier::SortVariable;
+use lending_iterator::{kmerge::KMergeBy, LendingIterator, Peekable};
+use resource::constants::encoding::AD_HOC_BYTES_INLINE;
+
+use crate::{
+    memory::{row_size, MemoryReservation},
+    pipeline::{modifiers::SortStageIterator, PipelineExecutionError},
+    row::MaybeOwnedRow,
+};
+
+static SORT_RUN_ID: AtomicU64 = AtomicU64::new(0);
+const SORT_RUN_FILE_PREFIX: &str = "typedb-sort-";
+
+/// Where sorts of more rows than fit in memory spill their sorted runs, and the size of the runs they spill. The
+/// server spills to `<storage>/.sort-spill` unless configured otherwise, and empties the directory when it starts.
+#[derive(Debug, Clone)]
+pub struct SortSpill {
+    pub directory: PathBuf,
+    pub run_bytes: u64,
+}
+
+/// The order of a sort stage's rows: the positions sorted on, each ascending or descending.
+#[derive(Debug, Clone)]
+pub(crate) struct SortOrder {
+    positions: Arc<[(usize, bool)]>,
+}
+
+impl SortOrder {
+    pub(crate) fn new(executable: &SortExecutable) -> Self {
+        let positions = executable
+            .sort_on
+            .iter()
+            .map(|sort_variable| match sort_variable {
+                SortVariable::Ascending(v) => (executable.output_row_mapping.get(v).unwrap().as_usize(), true),
+                SortVariable::Descending(v) => (executable.output_row_mapping.get(v).unwrap().as_usize(), false),
+            })
+            .collect();
+        Self { positions }
+    }
+
+    fn compare(&self, left: &[VariableValue<'_>], right: &[VariableValue<'_>]) -> Ordering {
+        for &(position, ascending) in self.positions.iter() {
+            let ordering = left[position]
+                .partial_cmp(&right[position])
+                .expect("Sort on variable with uncomparable values should have been caught at query-compile time");
+            if ordering != Ordering::Equal {
+                return if ascending { ordering } else { ordering.reverse() };
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+/// Keeps the first `limit` rows in sort order, for a sort followed by a limit. Rows sorting equal keep the order
+/// they arrived in.
+pub(crate) struct TopK {
+    order: SortOrder,
+    limit: usize,
+    heap: BinaryHeap<HeapRow>,
+    next_sequence: u64,
+    reservation: MemoryReservation,
+}
+
+impl TopK {
+    pub(crate) fn new(order: SortOrder, limit: u64, reservation: MemoryReservation) -> Self {
+        Self { order, limit: limit as usize, heap: BinaryHeap::new(), next_sequence: 0, reservation }
+    }
+
+    pub(crate) fn push(&mut self, row: MaybeOwnedRow<'_>) -> Result<(), Box<PipelineExecutionError>> {
+        let is_kept = self.heap.len() < self.limit
+            || self.heap.peek().is_some_and(|last| self.order.compare(row.row(), last.row.row()) == Ordering::Less);
+        if !is_kept {
+            return Ok(());
+        }
+        self.reservation
+            .grow(row_size(row.row()))
+            .map_err(|typedb_source| Box::new(PipelineExecutionError::MemoryLimit { typedb_source }))?;
+        let row = HeapRow { row: row.into_owned(), sequence: self.next_sequence, order: self.order.clone() };
+        self.next_sequence += 1;
+        self.heap.push(row);
+        if self.heap.len() > self.limit {
+            let dropped = self.heap.pop().unwrap();
+            self.reservation.shrink(row_size(dropped.row.row()));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> SortedRows {
+        let rows = self.heap.into_sorted_vec().into_iter().map(|heap_row| heap_row.row).collect();
+        SortedRows { rows, next_index: 0, _reservation: self.reservation }
+    }
+}
+
+// The heap's greatest row is the last of the rows kept, and is replaced first
+struct HeapRow {
+    row: MaybeOwnedRow<'static>,
+    sequence: u64,
+    order: SortOrder,
+}
+
+impl Ord for HeapRow {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order.compare(self.row.row(), other.row.row()).then(self.sequence.cmp(&other.sequence))
+    }
+}
+
+impl PartialOrd for HeapRow {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for HeapRow {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for HeapRow {}
+
+/// Sorts rows in memory while they fit, in runs of at most the spill's `run_bytes` counted against the query's
+/// memory. Each full run is sorted and spilled to a file in the spill's directory, and the runs are merged once every
+/// row arrived. Without a spill, every row is held in memory.
+pub(crate) struct ExternalSorter {
+    order: SortOrder,
+    spill: Option<SortSpill>,
+    run: Vec<MaybeOwnedRow<'static>>,
+    run_bytes: u64,
+    reservation: MemoryReservation,
+    spilled: Vec<SpilledSortRun>,
+}
+
+impl ExternalSorter {
+    pub(crate) fn new(order: SortOrder, spill: Option<SortSpill>, reservation: MemoryReservation) -> Self {
+        Self { order, spill, run: Vec::new(), run_bytes: 0, reservation, spilled: Vec::new() }
+    }
+
+    pub(crate) fn push(&mut self, row: MaybeOwnedRow<'_>) -> Result<(), Box<PipelineExecutionError>> {
+        let size = row_size(row.row());
+        let run_full = self.spill.as_ref().is_some_and(|spill| self.run_bytes + size > spill.run_bytes);
+        if !self.run.is_empty() && run_full {
+            self.spill_run()?;
+        }
+        if let Err(typedb_source) = self.reservation.grow(size) {
+            if self.run.is_empty() || self.spill.is_none() {
+                return Err(Box::new(PipelineExecutionError::MemoryLimit { typedb_source }));
+            }
+            // other buffers of the query may hold the memory, so spilling only helps if the row then fits
+            self.spill_run()?;
+            self.reservation
+                .grow(size)
+                .map_err(|typedb_source| Box::new(PipelineExecutionError::MemoryLimit { typedb_source }))?;
+        }
+        self.run.push(row.into_owned());
+        self.run_bytes += size;
+        Ok(())
+    }
+
+    fn spill_run(&mut self) -> Result<(), Box<PipelineExecutionError>> {
+        self.sort_run();
+        let spill = self.spill.as_ref().expect("only sorters with a spill spill their runs");
+        let run = SpilledSortRun::write(&spill.directory, &self.run)
+            .map_err(|typedb_source| Box::new(PipelineExecutionError::SortSpill { typedb_source }))?;
+        self.spilled.push(run);
+        self.run.clear();
+        self.reservation.shrink(self.run_bytes);
+        self.run_bytes = 0;
+        Ok(())
+    }
+
+    fn sort_run(&mut self) {
+        let order = &self.order;
+        self.run.sort_by(|left, right| order.compare(left.row(), right.row()));
+    }
+
+    pub(crate) fn finish(mut self) -> Result<SortStageIterator, Box<PipelineExecutionError>> {
+        self.sort_run();
+        let Self { order, run, reservation, spilled, .. } = self;
+        if spilled.is_empty() {
+            return Ok(SortStageIterator::InMemory(SortedRows { rows: run, next_index: 0, _reservation: reservation }));
+        }
+        let memory_run_index = spilled.len();
+        let mut runs = Vec::with_capacity(spilled.len() + 1);
+        for (index, spilled_run) in spilled.into_iter().enumerate() {
+            let reader = spilled_run
+                .read()
+                .map_err(|typedb_source| Box::new(PipelineExecutionError::SortSpill { typedb_source }))?;
+            runs.push(Peekable::new(SortedRun { index, order: order.clone(), source: RunSource::Spilled(reader) }));
+        }
+        let in_memory = RunSource::InMemory(run.into_iter());
+        runs.push(Peekable::new(SortedRun { index: memory_run_index, order, source: in_memory }));
+        let merge = KMergeBy::new(runs, compare_run_rows as RunRowComparator);
+        Ok(SortStageIterator::Merged(MergedRuns { merge, _reservation: reservation }))
+    }
+}
+
+/// Rows sorted in memory.
+pub struct SortedRows {
+    rows: Vec<MaybeOwnedRow<'static>>,
+    next_index: usize,
+    // released once the sorted rows are dropped
+    _reservation: MemoryReservation,
+}
+
+impl LendingIterator for SortedRows {
+    type Item<'a> = Result<MaybeOwnedRow<'a>, Box<PipelineExecutionError>>;
+
+    fn next(&mut self) -> Option<Self::Item<'_>> {
+        let row = self.rows.get(self.next_index)?;
+        self.next_index += 1;
+        Some(Ok(row.as_reference()))
+    }
+}
+
+/// The k-way merge of sorted runs spilled to disk and the last run, kept in memory.
+pub struct MergedRuns {
+    merge: KMergeBy<SortedRun, RunRowComparator>,
+    // held by the run kept in memory
+    _reservation: MemoryReservation,
+}
+
+impl LendingIterator for MergedRuns {
+    type Item<'a> = Result<MaybeOwnedRow<'a>, Box<PipelineExecutionError>>;
+
+    fn next(&mut self) -> Option<Self::Item<'_>> {
+        self.merge.next().map(|run_row| run_row.map(|run_row| run_row.row))
+    }
+}
+
+type RunRowResult = Result<RunRow, Box<PipelineExecutionError>>;
+
+type RunRowComparator = fn((&RunRowResult, &RunRowResult)) -> Ordering;
+
+struct RunRow {
+    row: MaybeOwnedRow<'static>,
+    run_index: usize,
+    order: SortOrder,
+}
+
+// Errors are merged first, so they surface as soon as they occur. Rows sorting equal come from earlier runs first,
+// which keeps the sort stable.
+fn compare_run_rows((left, right): (&RunRowResult, &RunRowResult)) -> Ordering {
+    match (left, right) {
+        (Ok(left), Ok(right)) => {
+            left.order.compare(left.row.row(), right.row.row()).then(left.run_index.cmp(&right.run_index))
+        }
+        (Err(_), Ok(_)) => Ordering::Less,
+        (Ok(_), Err(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => Ordering::Equal,
+    }
+}
+
+struct SortedRun {
+    index: usize,
+    order: SortOrder,
+    source: RunSource,
+}
+
+enum RunSource {
+    InMemory(vec::IntoIter<MaybeOwnedRow<'static>>),
+    Spilled(SpilledRunReader),
+}
+
+impl LendingIterator for SortedRun {
+    type Item<'a> = RunRowResult;
+
+    fn next(&mut self) -> Option<Self::Item<'_>> {
+        let row = match &mut self.source {
+            RunSource::InMemory(rows) => Ok(rows.next()?),
+            RunSource::Spilled(reader) => reader.next()?,
+        };
+        Some(
+            row.map(|row| RunRow { row, run_index: self.index, order: self.order.clone() })
+                .map_err(|typedb_source| Box::new(PipelineExecutionError::SortSpill { typedb_source })),
+        )
+    }
+}
+
+/// A sorted run of rows, spilled to a file which is deleted when the run is dropped.
+#[derive(Debug)]
+struct SpilledSortRun {
+    path: PathBuf,
+    rows: usize,
+    width: usize,
+}
+
+impl SpilledSortRun {
+    fn write(directory: &Path, rows: &[MaybeOwnedRow<'static>]) -> Result<Self, SortSpillError> {
+        let id = SORT_RUN_ID.fetch_add(1, atomic::Ordering::Relaxed);
+        let path = directory.join(format!("{SORT_RUN_FILE_PREFIX}{}-{}", process::id(), id));
+        let width = rows.first().map_or(0, |row| row.len());
+        let run = Self { path, rows: rows.len(), width };
+        run.write_rows(directory, rows)
+            .map_err(|source| SortSpillError::WriteRun { path: run.path.clone(), source: Arc::new(source) })?;
+        Ok(run)
+    }
+
+    fn write_rows(&self, directory: &Path, rows: &[MaybeOwnedRow<'static>]) -> io::Result<()> {
+        fs::create_dir_all(directory)?;
+        let mut writer = BufWriter::new(File::options().write(true).create_new(true).open(&self.path)?);
+        for row in rows {
+            debug_assert_eq!(row.len(), self.width);
+            writer.write_all(&row.multiplicity().to_le_bytes())?;
+            for value in row.row() {
+                write_variable_value(&mut writer, value)?;
+            }
+        }
+        writer.flush()
+    }
+
+    fn read(self) -> Result<SpilledRunReader, SortSpillError> {
+        match File::open(&self.path) {
+            Ok(file) => Ok(SpilledRunReader { reader: BufReader::new(file), remaining: self.rows, run: self }),
+            Err(source) => Err(SortSpillError::ReadRun { path: self.path.clone(), source: Arc::new(source) }),
+        }
+    }
+}
+
+impl Drop for SpilledSortRun {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+struct SpilledRunReader {
+    run: SpilledSortRun,
+    reader: BufReader<File>,
+    remaining: usize,
+}
+
+impl SpilledRunReader {
+    fn next(&mut self) -> Option<Result<MaybeOwnedRow<'static>, SortSpillError>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        match self.read_row() {
+            Ok(row) => {
+                self.remaining -= 1;
+                Some(Ok(row))
+            }
+            Err(source) => {
+                // the rest of the run cannot be read reliably
+                self.remaining = 0;
+                Some(Err(SortSpillError::ReadRun { path: self.run.path.clone(), source: Arc::new(source) }))
+            }
+        }
+    }
+
+    fn read_row(&mut self) -> io::Result<MaybeOwnedRow<'static>> {
+        let multiplicity = u64::from_le_bytes(read_array(&mut self.reader)?);
+        let row = (0..self.run.width).map(|_| read_variable_value(&mut self.reader)).collect::<io::Result<_>>()?;
+        Ok(MaybeOwnedRow::new_owned(row, multiplicity))
+    }
+}
+
+// Spilled rows are written as their multiplicity, then each value as a tag and its encoding
+const TAG_EMPTY: u8 = 0;
+const TAG_TYPE: u8 = 1;
+const TAG_THING: u8 = 2;
+const TAG_VALUE: u8 = 3;
+const TAG_THING_LIST: u8 = 4;
+const TAG_VALUE_LIST: u8 = 5;
+
+const TAG_ENTITY_TYPE: u8 = 0;
+const TAG_RELATION_TYPE: u8 = 1;
+const TAG_ATTRIBUTE_TYPE: u8 = 2;
+const TAG_ROLE_TYPE: u8 = 3;
+
+fn write_variable_value(writer: &mut impl Write, value: &VariableValue<'_>) -> io::Result<()> {
+    match value {
+        VariableValue::Empty => writer.write_all(&[TAG_EMPTY]),
+        VariableValue::Type(type_) => {
+            let (tag, vertex) = match type_ {
+                Type::Entity(entity_type) => (TAG_ENTITY_TYPE, entity_type.vertex()),
+                Type::Relation(relation_type) => (TAG_RELATION_TYPE, relation_type.vertex()),
+                Type::Attribute(attribute_type) => (TAG_ATTRIBUTE_TYPE, attribute_type.vertex()),
+                Type::RoleType(role_type) => (TAG_ROLE_TYPE, role_type.vertex()),
+            };
+            writer.write_all(&[TAG_TYPE, tag])?;
+            writer.write_all(&*vertex.to_bytes())
+        }
+        VariableValue::Thing(thing) => {
+            writer.write_all(&[TAG_THING])?;
+            write_thing(writer, thing)
+        }
+        VariableValue::Value(value) => {
+            writer.write_all(&[TAG_VALUE])?;
+            write_value(writer, value)
+        }
+        VariableValue::ThingList(things) => {
+            writer.write_all(&[TAG_THING_LIST])?;
+            write_length(writer, things.len())?;
+            things.iter().try_for_each(|thing| write_thing(writer, thing))
+        }
+        VariableValue::ValueList(values) => {
+            writer.write_all(&[TAG_VALUE_LIST])?;
+            write_length(writer, values.len())?;
+            values.iter().try_for_each(|value| write_value(writer, value))
+        }
+    }
+}
+
+fn read_variable_value(reader: &mut impl Read) -> io::Result<VariableValue<'static>> {
+    let [tag] = read_array(reader)?;
+    match tag {
+        TAG_EMPTY => Ok(VariableValue::Empty),
+        TAG_TYPE => {
+            let [type_tag] = read_array(reader)?;
+            let vertex =
+                TypeVertex::decode(Bytes::Array(ByteArray::copy(&read_array::<{ TypeVertex::LENGTH }>(reader)?)));
+            let type_ = match type_tag {
+                TAG_ENTITY_TYPE => Type::Entity(EntityType::new(vertex)),
+                TAG_RELATION_TYPE => Type::Relation(RelationType::new(vertex)),
+                TAG_ATTRIBUTE_TYPE => Type::Attribute(AttributeType::new(vertex)),
+                TAG_ROLE_TYPE => Type::RoleType(RoleType::new(vertex)),
+                _ => return Err(invalid_data("unrecognised type tag")),
+            };
+            Ok(VariableValue::Type(type_))
+        }
+        TAG_THING => Ok(VariableValue::Thing(read_thing(reader)?)),
+        TAG_VALUE => Ok(VariableValue::Value(read_value(reader)?)),
+        TAG_THING_LIST => {
+            let length = read_length(reader)?;
+            Ok(VariableValue::ThingList((0..length).map(|_| read_thing(reader)).collect::<io::Result<_>>()?))
+        }
+        TAG_VALUE_LIST => {
+            let length = read_length(reader)?;
+            Ok(VariableValue::ValueList((0..length).map(|_| read_value(reader)).collect::<io::Result<_>>()?))
+        }
+        _ => Err(invalid_data("unrecognised value tag")),
+    }
+}
+
+fn write_thing(writer: &mut impl Write, thing: &Thing) -> io::Result<()> {
+    match thing {
+        Thing::Entity(entity) => write_bytes(writer, &entity.iid()),
+        Thing::Relation(relation) => write_bytes(writer, &relation.iid()),
+        Thing::Attribute(attribute) => write_bytes(writer, &attribute.iid()),
+    }
+}
+
+fn read_thing(reader: &mut impl Read) -> io::Result<Thing> {
+    let iid = read_bytes(reader)?;
+    if let Some(object) = ObjectVertex::try_from_bytes(&iid) {
+        Ok(Thing::from(Object::new(object)))
+    } else if let Some(attribute) = AttributeVertex::try_from_bytes(&iid) {
+        Ok(Thing::Attribute(Attribute::new(attribute)))
+    } else {
+        Err(invalid_data("unrecognised instance IID"))
+    }
+}
+
+fn write_value(writer: &mut impl Write, value: &Value<'_>) -> io::Result<()> {
+    writer.write_all(&value.value_type().category().to_bytes())?;
+    write_bytes(writer, &value.encode_bytes::<AD_HOC_BYTES_INLINE>())
+}
+
+fn read_value(reader: &mut impl Read) -> io::Result<Value<'static>> {
+    let category = ValueTypeCategory::from_bytes(read_array(reader)?);
+    let bytes = read_bytes(reader)?;
+    let value = match category {
+        ValueTypeCategory::Boolean => Value::Boolean(BooleanBytes::new(fixed_bytes(&bytes)?).as_bool()),
+        ValueTypeCategory::Long => Value::Long(LongBytes::new(fixed_bytes(&bytes)?).as_i64()),
+        ValueTypeCategory::Double => Value::Double(DoubleBytes::new(fixed_bytes(&bytes)?).as_f64()),
+        ValueTypeCategory::Decimal => Value::Decimal(DecimalBytes::new(fixed_bytes(&bytes)?).as_decimal()),
+        ValueTypeCategory::Date => Value::Date(DateBytes::new(fixed_bytes(&bytes)?).as_naive_date()),
+        ValueTypeCategory::DateTime => Value::DateTime(DateTimeBytes::new(fixed_bytes(&bytes)?).as_naive_date_time()),
+        ValueTypeCategory::DateTimeTZ => Value::DateTimeTZ(DateTimeTZBytes::new(fixed_bytes(&bytes)?).as_date_time()),
+        ValueTypeCategory::Duration => Value::Duration(DurationBytes::new(fixed_bytes(&bytes)?).as_duration()),
+        ValueTypeCategory::String => {
+            Value::String(Cow::Owned(StringBytes::new(Bytes::<AD_HOC_BYTES_INLINE>::copy(&bytes)).as_str().to_owned()))
+        }
+        ValueTypeCategory::Struct => {
+            Value::Struct(Cow::Owned(StructBytes::new(Bytes::<AD_HOC_BYTES_INLINE>::copy(&bytes)).as_struct()))
+        }
+    };
+    Ok(value)
+}
+
+fn write_length(writer: &mut impl Write, length: usize) -> io::Result<()> {
+    writer.write_all(&(length as u32).to_le_bytes())
+}
+
+fn read_length(reader: &mut impl Read) -> io::Result<usize> {
+    Ok(u32::from_le_bytes(read_array(reader)?) as usize)
+}
+
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    write_length(writer, bytes.len())?;
+    writer.write_all(bytes)
+}
+
+fn read_bytes(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0; read_length(reader)?];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut array = [0; N];
+    reader.read_exact(&mut array)?;
+    Ok(array)
+}
+
+fn fixed_bytes<const N: usize>(bytes: &[u8]) -> io::Result<[u8; N]> {
+    bytes.try_into().map_err(|_| invalid_data("unexpected value length"))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+typedb_error!(
+    pub SortSpillError(component = "Sort spill", prefix = "SSP") {
+        WriteRun(1, "Failed to spill sorted rows to '{path:?}'.", path: PathBuf, ( source: Arc<io::Error> )),
+        ReadRun(2, "Failed to read sorted rows spilled to '{path:?}'.", path: PathBuf, ( source: Arc<io::Error> )),
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, collections::HashMap, fs, path::Path, sync::Arc};
+
+    use answer::{variable_value::VariableValue, Thing, Type};
+    use chrono::{FixedOffset, NaiveDate};
+    use concept::{
+        thing::{attribute::Attribute, object::Object, ThingAPI},
+        type_::{
+            attribute_type::AttributeType, entity_type::EntityType, relation_type::RelationType, role_type::RoleType,
+        },
+    };
+    use encoding::{
+        graph::{
+            definition::{
+                definition_key::{DefinitionID, DefinitionKey},
+                r#struct::StructDefinition,
+            },
+            thing::{
+                vertex_attribute::{AttributeID, AttributeVertex},
+                vertex_object::{ObjectID, ObjectVertex},
+            },
+            type_::vertex::{PrefixedTypeVertexEncoding, TypeID},
+        },
+        value::{
+            decimal_value::Decimal, duration_value::Duration, timezone::TimeZone, value::Value,
+            value_struct::StructValue,
+        },
+    };
+    use lending_iterator::LendingIterator;
+    use test_utils::create_tmp_dir;
+
+    use super::{ExternalSorter, SortOrder, SortSpill};
+    use crate::{
+        memory::{MemoryReservation, QueryMemory},
+        row::MaybeOwnedRow,
+    };
+
+    // small enough that a few rows fill a run
+    const RUN_BYTES: u64 = 2048;
+    const ROWS: i64 = 300;
+
+    fn sort(directory: &Path, order: SortOrder, rows: &[MaybeOwnedRow<'static>]) -> Vec<MaybeOwnedRow<'static>> {
+        let spill = SortSpill { directory: directory.to_owned(), run_bytes: RUN_BYTES };
+        let reservation = MemoryReservation::new(Arc::new(QueryMemory::default()));
+        let mut sorter = ExternalSorter::new(order, Some(spill), reservation);
+        for row in rows {
+            sorter.push(row.as_reference()).unwrap();
+        }
+        assert!(sorter.spilled.len() > 2, "expected several spilled runs, found {}", sorter.spilled.len());
+        assert_eq!(fs::read_dir(directory).unwrap().count(), sorter.spilled.len());
+
+        let mut sorted = sorter.finish().unwrap();
+        let mut sorted_rows = Vec::new();
+        while let Some(row) = sorted.next() {
+            sorted_rows.push(row.unwrap().into_owned());
+        }
+        drop(sorted);
+        assert_eq!(fs::read_dir(directory).unwrap().count(), 0, "spilled runs should be deleted once merged");
+        sorted_rows
+    }
+
+    // the rows sorted in memory, keeping rows sorting equal in the order they arrived
+    fn sort_in_memory(order: &SortOrder, rows: &[MaybeOwnedRow<'static>]) -> Vec<MaybeOwnedRow<'static>> {
+        let mut sorted = rows.to_vec();
+        sorted.sort_by(|left, right| order.compare(left.row(), right.row()));
+        sorted
+    }
+
+    fn assert_rows_eq(actual: &[MaybeOwnedRow<'static>], expected: &[MaybeOwnedRow<'static>]) {
+        assert_eq!(actual.len(), expected.len());
+        for (index, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+            assert_eq!(actual.row(), expected.row(), "row {index} differs");
+            assert_eq!(actual.multiplicity(), expected.multiplicity(), "multiplicity of row {index} differs");
+        }
+    }
+
+    fn entity(id: u64) -> Thing {
+        Thing::from(Object::new(ObjectVertex::build_entity(TypeID::new(10), ObjectID::new(id))))
+    }
+
+    fn relation(id: u64) -> Thing {
+        Thing::from(Object::new(ObjectVertex::build_relation(TypeID::new(11), ObjectID::new(id))))
+    }
+
+    fn attribute(value: i64) -> Thing {
+        let vertex = AttributeVertex::new(TypeID::new(12), AttributeID::build_inline(Value::Long(value)));
+        Thing::Attribute(Attribute::new(vertex))
+    }
+
+    fn string(i: i64) -> Value<'static> {
+        // longer strings than are inlined in attribute IDs
+        Value::String(Cow::Owned(format!("{i:04}-{}", "x".repeat(i as usize % 40))))
+    }
+
+    fn date(i: i64) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2000, 1, 1).unwrap() + chrono::Duration::days(i)
+    }
+
+    fn date_time_tz(i: i64) -> Value<'static> {
+        let time_zone = TimeZone::Fixed(FixedOffset::east_opt((i % 24 - 12) as i32 * 3600).unwrap());
+        Value::DateTimeTZ(date(i).and_hms_opt(12, 30, 0).unwrap().and_local_timezone(time_zone).unwrap())
+    }
+
+    fn struct_value(i: i64) -> Value<'static> {
+        let definition_key = DefinitionKey::build(StructDefinition::PREFIX, DefinitionID::build(0));
+        let fields = HashMap::from([(0, Value::Long(i)), (1, string(i))]);
+        Value::Struct(Cow::Owned(StructValue::new(definition_key, fields)))
+    }
+
+    // every kind of value a row can hold, so each is spilled and read back
+    fn value_of_kind(kind: i64, i: i64) -> VariableValue<'static> {
+        match kind {
+            0 => VariableValue::Empty,
+            1 => VariableValue::Type(Type::Entity(EntityType::build_from_type_id(TypeID::new(i as u16)))),
+            2 => VariableValue::Type(Type::Relation(RelationType::build_from_type_id(TypeID::new(i as u16)))),
+            3 => VariableValue::Type(Type::Attribute(AttributeType::build_from_type_id(TypeID::new(i as u16)))),
+            4 => VariableValue::Type(Type::RoleType(RoleType::build_from_type_id(TypeID::new(i as u16)))),
+            5 => VariableValue::Thing(entity(i as u64)),
+            6 => VariableValue::Thing(relation(i as u64)),
+            7 => VariableValue::Thing(attribute(i)),
+            8 => VariableValue::Value(Value::Boolean(i % 2 == 0)),
+            9 => VariableValue::Value(Value::Long(i)),
+            10 => VariableValue::Value(Value::Double(i as f64 / 3.0)),
+            11 => VariableValue::Value(Value::Decimal(Decimal::new(i, i as u64 * 1_000_000))),
+            12 => VariableValue::Value(Value::Date(date(i))),
+            13 => VariableValue::Value(Value::DateTime(date(i).and_hms_opt(1, 2, 3).unwrap())),
+            14 => VariableValue::Value(date_time_tz(i)),
+            15 => VariableValue::Value(Value::Duration(Duration::new(i as u32, i as u32 * 2, i as u64 * 1000))),
+            16 => VariableValue::Value(string(i)),
+            17 => VariableValue::Value(struct_value(i)),
+            18 => VariableValue::ThingList(Arc::new([entity(i as u64), relation(i as u64), attribute(i)])),
+            19 => VariableValue::ValueList(Arc::new([Value::Long(i), string(i), struct_value(i)])),
+            _ => unreachable!(),
+        }
+    }
+
+    const KINDS: i64 = 20;
+
+    // the kinds that sort, and that a sort can be on
+    const SORTABLE_KINDS: [i64; 15] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 16];
+
+    #[test]
+    fn spilled_runs_of_every_kind_of_value_merge_in_order() {
+        let directory = create_tmp_dir();
+        // a key with many rows sorting equal, a value of every kind in turn, and the row's position
+        let rows: Vec<_> = (0..ROWS)
+            .map(|i| {
+                let key = VariableValue::Value(Value::Long(i * 7919 % 50));
+                let row = vec![key, value_of_kind(i % KINDS, i), VariableValue::Value(Value::Long(i))];
+                MaybeOwnedRow::new_owned(row, i as u64 % 3 + 1)
+            })
+            .collect();
+        for ascending in [true, false] {
+            let order = SortOrder { positions: Arc::new([(0, ascending)]) };
+            let sorted = sort(&directory, order.clone(), &rows);
+            assert_rows_eq(&sorted, &sort_in_memory(&order, &rows));
+        }
+    }
+
+    #[test]
+    fn spilled_runs_sorted_on_each_kind_of_value_merge_in_order() {
+        let directory = create_tmp_dir();
+        for kind in SORTABLE_KINDS {
+            let rows: Vec<_> = (0..ROWS)
+                .map(|i| {
+                    // out of order, with duplicates
+                    let value = value_of_kind(kind, i * 37 % (ROWS / 2) + 1);
+                    MaybeOwnedRow::new_owned(vec![value, VariableValue::Value(Value::Long(i))], i as u64 % 4 + 1)
+                })
+                .collect();
+            let order = SortOrder { positions: Arc::new([(0, false), (1, true)]) };
+            let sorted = sort(&directory, order.clone(), &rows);
+            assert_rows_eq(&sorted, &sort_in_memory(&order, &rows));
+        }
+    }
+
+    #[test]
+    fn sorts_without_a_spill_hold_every_row_in_memory() {
+        let rows: Vec<_> = (0..ROWS)
+            .map(|i| MaybeOwnedRow::new_owned(vec![VariableValue::Value(Value::Long(i * 7919 % ROWS))], 1))
+            .collect();
+        let order = SortOrder { positions: Arc::new([(0, true)]) };
+        let reservation = MemoryReservation::new(Arc::new(QueryMemory::default()));
+        let mut sorter = ExternalSorter::new(order.clone(), None, reservation);
+        for row in &rows {
+            sorter.push(row.as_reference()).unwrap();
+        }
+        assert!(sorter.spilled.is_empty());
+
+        let mut sorted = sorter.finish().unwrap();
+        let mut sorted_rows = Vec::new();
+        while let Some(row) = sorted.next() {
+            sorted_rows.push(row.unwrap().into_owned());
+        }
+        assert_rows_eq(&sorted_rows, &sort_in_memory(&order, &rows));
+    }
+}
@@ -37,6 +37,7 @@ use crate::{
     batch::FixedBatch,
     document::{ConceptDocument, DocumentLeaf, DocumentList, DocumentMap, DocumentNode},
     error::ReadExecutionError,
+    memory::{document_size, MemoryLimitError, MemoryReservation, QueryMemory},
     pipeline::{
         pipeline::{Pipeline, PipelineError},
         stage::{ExecutionContext, StageAPI},
@@ -78,7 +79,7 @@ impl<Snapshot: ReadableSnapshot + 'static> FetchStageExecutor<Snapshot> {
         context: ExecutionContext<Snapshot>,
         interrupt: ExecutionInterrupt,
     ) -> (impl Iterator<Item = Result<ConceptDocument, Box<PipelineExecutionError>>>, ExecutionContext<Snapshot>) {
-        let ExecutionContext { snapshot, thing_manager, parameters, profile } = context.clone();
+        let ExecutionContext { snapshot, thing_manager, parameters, profile, memory } = context.clone();
         let executable = self.executable;
         let functions = self.functions;
         let stage_profile = profile.profile_stage(|| String::from("Fetch"), executable.executable_id);
@@ -91,6 +92,7 @@ impl<Snapshot: ReadableSnapshot + 'static> FetchStageExecutor<Snapshot> {
                     parameters.clone(),
                     functions.clone(),
                     profile.clone(),
+                    memory.clone(),
                     stage_profile.clone(),
                     row.as_reference(),
                     interrupt.clone(),
@@ -110,6 +112,7 @@ fn execute_fetch(
     parameters: Arc<ParameterRegistry>,
     functions: Arc<ExecutableFunctionRegistry>,
     query_profile: Arc<QueryProfile>,
+    memory: Arc<QueryMemory>,
     stage_profile: Arc<StageProfile>,
     row: MaybeOwnedRow<'_>,
     interrupt: ExecutionInterrupt,
@@ -123,9 +126,14 @@ fn execute_fetch(
         parameters,
         functions,
         query_profile,
+        memory.clone(),
         row,
         interrupt,
     )?;
+    // documents are streamed one by one, so each is checked against the memory left once it is built
+    MemoryReservation::new(memory)
+        .grow(document_size(&node))
+        .map_err(|typedb_source| FetchExecutionError::MemoryLimit { typedb_source })?;
     measurement.end(&step, 1, 1);
     Ok(ConceptDocument { root: node })
 }
@@ -137,6 +145,7 @@ fn execute_fetch_some(
     parameters: Arc<ParameterRegistry>,
     functions_registry: Arc<ExecutableFunctionRegistry>,
     query_profile: Arc<QueryProfile>,
+    memory: Arc<QueryMemory>,
     row: MaybeOwnedRow<'_>,
     interrupt: ExecutionInterrupt,
 ) -> Result<DocumentNode, FetchExecutionError> {
@@ -151,6 +160,7 @@ fn execute_fetch_some(
             parameters,
             functions_registry,
             query_profile,
+            memory,
             row,
             interrupt,
             variable_positions,
@@ -163,6 +173,7 @@ fn execute_fetch_some(
             parameters,
             functions_registry,
             query_profile.clone(),
+            memory.clone(),
             row,
             interrupt,
         ),
@@ -172,6 +183,7 @@ fn execute_fetch_some(
             parameters,
             functions_registry,
             query_profile,
+            memory,
             row,
             interrupt,
             variable_positions,
@@ -183,6 +195,7 @@ fn execute_fetch_some(
             parameters,
             functions_registry,
             query_profile,
+            memory,
             row,
             interrupt,
             subfetch,
@@ -225,6 +238,7 @@ fn execute_single_function(
     parameters: Arc<ParameterRegistry>,
     functions_registry: Arc<ExecutableFunctionRegistry>,
     query_profile: Arc<QueryProfile>,
+    memory: Arc<QueryMemory>,
     row: MaybeOwnedRow<'_>,
     mut interrupt: ExecutionInterrupt,
     variable_positions: &HashMap<Variable, VariablePosition>,
@@ -236,6 +250,7 @@ fn execute_single_function(
         parameters,
         functions_registry.clone(),
         query_profile,
+        memory,
         variable_positions,
         row,
         function,
@@ -285,6 +300,7 @@ fn execute_object(
     parameters: Arc<ParameterRegistry>,
     functions: Arc<ExecutableFunctionRegistry>,
     query_profile: Arc<QueryProfile>,
+    memory: Arc<QueryMemory>,
     row: MaybeOwnedRow<'_>,
     interrupt: ExecutionInterrupt,
 ) -> Result<DocumentNode, FetchExecutionError> {
@@ -297,6 +313,7 @@ fn execute_object(
                 parameters,
                 functions,
                 query_profile,
+                memory,
                 row,
                 interrupt,
             )?;
@@ -314,17 +331,20 @@ fn execute_list_function(
     parameters: Arc<ParameterRegistry>,
     functions_registry: Arc<ExecutableFunctionRegistry>,
     query_profile: Arc<QueryProfile>,
+    memory: Arc<QueryMemory>,
     row: MaybeOwnedRow<'_>,
     mut interrupt: ExecutionInterrupt,
     variable_positions: &HashMap<Variable, VariablePosition>,
     function: &ExecutableFunction,
 ) -> Result<DocumentNode, FetchExecutionError> {
+    let mut reservation = MemoryReservation::new(memory.clone());
     let (mut pattern_executor, execution_context) = prepare_single_function_execution(
         snapshot,
         thing_manager,
         parameters,
         functions_registry.clone(),
         query_profile,
+        memory,
         variable_positions,
         row,
         function,
@@ -340,7 +360,11 @@ fn execute_list_function(
     {
         for row in batch {
             for value in row {
-                nodes.push(variable_value_to_document(value.clone())?);
+                let node = variable_value_to_document(value.clone())?;
+                reservation
+                    .grow(document_size(&node))
+                    .map_err(|typedb_source| FetchExecutionError::MemoryLimit { typedb_source })?;
+                nodes.push(node);
             }
         }
     }
@@ -354,6 +378,7 @@ fn execute_list_subfetch(
     parameters: Arc<ParameterRegistry>,
     functions_registry: Arc<ExecutableFunctionRegistry>,
     query_profile: Arc<QueryProfile>,
+    memory: Arc<QueryMemory>,
     row: MaybeOwnedRow<'_>,
     interrupt: ExecutionInterrupt,
     executable_subfetch: &ExecutableFetchListSubFetch,
@@ -364,11 +389,14 @@ fn execute_list_subfetch(
         Pipeline::build_read_pipeline(
             snapshot,
             thing_manager,
-            variable_registry.variable_names(),
+            variable_registry,
+            &HashMap::new(),
             functions_registry,
             stages,
             Some(fetch.clone()),
             parameters,
+            query_profile,
+            memory.clone(),
             None,
         )
     } else {
@@ -382,11 +410,14 @@ fn execute_list_subfetch(
         Pipeline::build_read_pipeline(
             snapshot,
             thing_manager,
-            variable_registry.variable_names(),
+            variable_registry,
+            &HashMap::new(),
             functions_registry,
             stages,
             Some(fetch.clone()),
             parameters,
+            query_profile,
+            memory.clone(),
             Some(initial_row),
         )
     }
@@ -396,9 +427,14 @@ fn execute_list_subfetch(
         .into_documents_iterator(interrupt)
         .map_err(|(err, _context)| FetchExecutionError::SubFetch { typedb_source: err })?;
 
+    let mut reservation = MemoryReservation::new(memory);
     let mut nodes = Vec::new();
     for result in iterator {
-        nodes.push(result.map_err(|err| FetchExecutionError::SubFetch { typedb_source: err })?.root);
+        let node = result.map_err(|err| FetchExecutionError::SubFetch { typedb_source: err })?.root;
+        reservation
+            .grow(document_size(&node))
+            .map_err(|typedb_source| FetchExecutionError::MemoryLimit { typedb_source })?;
+        nodes.push(node);
     }
     Ok(DocumentNode::List(DocumentList::new_from(nodes)))
 }
@@ -551,6 +587,7 @@ fn prepare_single_function_execution<Snapshot: ReadableSnapshot + 'static>(
     parameters: Arc<ParameterRegistry>,
     functions_registry: Arc<ExecutableFunctionRegistry>,
     query_profile: Arc<QueryProfile>,
+    memory: Arc<QueryMemory>,
     variable_positions: &HashMap<Variable, VariablePosition>,
     row: MaybeOwnedRow<'_>,
     function: &ExecutableFunction,
@@ -567,7 +604,10 @@ fn prepare_single_function_execution<Snapshot: ReadableSnapshot + 'static>(
             .map_err(|err| FetchExecutionError::ConceptRead { source: err })?;
     let mut pattern_executor = PatternExecutor::new(next_executable_id(), step_executors);
     pattern_executor.prepare(FixedBatch::from(args));
-    Ok((pattern_executor, Arc::new(ExecutionContext::new(snapshot, thing_manager, parameters))))
+    Ok((
+        pattern_executor,
+        Arc::new(ExecutionContext::new_with_profile(snapshot, thing_manager, parameters, query_profile, memory)),
+    ))
 }
 
 fn execute_object_entries(
@@ -577,6 +617,7 @@ fn execute_object_entries(
     parameters: Arc<ParameterRegistry>,
     functions: Arc<ExecutableFunctionRegistry>,
     query_profile: Arc<QueryProfile>,
+    memory: Arc<QueryMemory>,
     row: MaybeOwnedRow<'_>,
     interrupt: ExecutionInterrupt,
 ) -> Result<DocumentMap, FetchExecutionError> {
@@ -591,6 +632,7 @@ fn execute_object_entries(
                 parameters.clone(),
                 functions.clone(),
                 query_profile.clone(),
+                memory.clone(),
                 row.as_reference(),
                 interrupt.clone(),
             )?,
@@ -634,6 +676,7 @@ typedb_error!(
         FetchSingleFunctionNotSingle(8, "Fetching results of a function call '{func_name}()' expected a single return, got a stream instead. It must be wrapped in `[]` to collect into a list.", func_name: String),
 
         SubFetch(10, "Error executing sub fetch.", ( typedb_source : Box<PipelineExecutionError>)),
+        MemoryLimit(11, "Query memory limit exceeded.", ( typedb_source: MemoryLimitError )),
 
         ConceptRead(30, "Unexpected failed to read concept.", ( source: Box<ConceptReadError>)),
         ReadExecution(31, "Unexpected failed to execute read.", ( typedb_source: Box<ReadExecutionError> )),
@@ -14,6 +14,7 @@ use concept::thing::thing_manager::ThingManager;
 use ir::pipeline::ParameterRegistry;
 use lending_iterator::LendingIterator;
 use storage::snapshot::WritableSnapshot;
+use tracing::{span, Level};
 
 use crate::{
     batch::Batch,
@@ -58,6 +59,7 @@ where
     > {
         let Self { executable, previous } = self;
         let (previous_iterator, mut context) = previous.into_iterator(interrupt.clone())?;
+        let _span = span!(Level::DEBUG, "insert_stage", executable_id = executable.executable_id).entered();
 
         let profile = context.profile.profile_stage(|| String::from("Insert"), executable.executable_id);
 
@@ -154,5 +156,5 @@ fn execute_insert(
         measurement.end(&step_profile, 1, 1);
         index += 1;
     }
-    Ok(())
+    snapshot.check_write_buffer().map_err(|typedb_source| Box::new(WriteError::WriteBuffer { typedb_source }))
 }
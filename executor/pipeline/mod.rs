@@ -11,13 +11,15 @@ use lending_iterator::LendingIterator;
 use crate::{
     batch::Batch,
     error::ReadExecutionError,
-    pipeline::{fetch::FetchExecutionError, stage::StageIterator},
+    memory::MemoryLimitError,
+    pipeline::{external_sort::SortSpillError, fetch::FetchExecutionError, stage::StageIterator},
     row::MaybeOwnedRow,
     write::WriteError,
     InterruptType,
 };
 
 pub mod delete;
+pub mod external_sort;
 pub mod fetch;
 pub mod initial;
 pub mod insert;
@@ -71,5 +73,7 @@ typedb_error!(
         WriteError(6, "Error executing write operation.", ( typedb_source: Box<WriteError> )),
         ReadPatternExecution(7, "Error executing a read pattern.", ( typedb_source : ReadExecutionError )),
         FetchError(8, "Error executing fetch operation.", ( typedb_source: FetchExecutionError )),
+        MemoryLimit(9, "Query memory limit exceeded.", ( typedb_source: MemoryLimitError )),
+        SortSpill(10, "Error spilling sorted rows to disk.", ( typedb_source: SortSpillError )),
     }
 );
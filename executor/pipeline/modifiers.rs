@@ -3,18 +3,18 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
-use std::{cmp::Ordering, sync::Arc};
+use std::sync::Arc;
 
 use compiler::executable::modifiers::{
     LimitExecutable, OffsetExecutable, RequireExecutable, SelectExecutable, SortExecutable,
 };
-use ir::pipeline::modifier::SortVariable;
 use lending_iterator::{LendingIterator, Peekable};
 use storage::snapshot::ReadableSnapshot;
 
 use crate::{
-    batch::Batch,
+    memory::MemoryReservation,
     pipeline::{
+        external_sort::{ExternalSorter, MergedRuns, SortOrder, SortSpill, SortedRows, TopK},
         stage::{ExecutionContext, StageAPI},
         PipelineExecutionError, StageIterator,
     },
@@ -50,71 +50,61 @@ where
     > {
         let Self { previous, executable, .. } = self;
         let (previous_iterator, context) = previous.into_iterator(interrupt)?;
-        // accumulate once, then we will operate in-place
-        let batch = match previous_iterator.collect_owned() {
-            Ok(batch) => batch,
-            Err(err) => return Err((err, context)),
-        };
-        let batch_len = batch.len();
         let profile = context.profile.profile_stage(|| String::from("Sort"), executable.executable_id);
         let step_profile = profile.extend_or_get(1, || String::from("Sort execution"));
         let measurement = step_profile.start_measurement();
-        let sorted_iterator = SortStageIterator::from_unsorted(batch, &executable);
-        measurement.end(&step_profile, 1, batch_len as u64);
+        let spill = context.memory.sort_spill().cloned();
+        let reservation = MemoryReservation::new(context.memory.clone());
+        let (sorted_iterator, input_rows) = match sort_rows(previous_iterator, &executable, spill, reservation) {
+            Ok(sorted) => sorted,
+            Err(err) => return Err((err, context)),
+        };
+        measurement.end(&step_profile, 1, input_rows);
         Ok((sorted_iterator, context))
     }
 }
 
-pub struct SortStageIterator {
-    unsorted: Batch,
-    sorted_indices: Vec<usize>,
-    next_index_index: usize,
-}
-
-impl SortStageIterator {
-    fn from_unsorted(unsorted: Batch, sort_executable: &SortExecutable) -> Self {
-        let mut indices: Vec<usize> = (0..unsorted.len()).collect();
-        let sort_by: Vec<(usize, bool)> = sort_executable
-            .sort_on
-            .iter()
-            .map(|sort_variable| match sort_variable {
-                SortVariable::Ascending(v) => (sort_executable.output_row_mapping.get(v).unwrap().as_usize(), true),
-                SortVariable::Descending(v) => (sort_executable.output_row_mapping.get(v).unwrap().as_usize(), false),
-            })
-            .collect();
-        indices.sort_by(|x, y| {
-            let x_row_as_row = unsorted.get_row(*x);
-            let y_row_as_row = unsorted.get_row(*y);
-            let x_row = x_row_as_row.row();
-            let y_row = y_row_as_row.row();
-            for &(idx, asc) in &sort_by {
-                let ord = x_row[idx]
-                    .partial_cmp(&y_row[idx])
-                    .expect("Sort on variable with uncomparable values should have been caught at query-compile time");
-                if ord != Ordering::Equal {
-                    if asc {
-                        return ord;
-                    } else {
-                        return ord.reverse();
-                    }
-                };
+// A sort followed by a limit only keeps the first rows, while a sort of every row spills to disk once it grows large
+fn sort_rows(
+    mut iterator: impl StageIterator,
+    executable: &SortExecutable,
+    spill: Option<SortSpill>,
+    reservation: MemoryReservation,
+) -> Result<(SortStageIterator, u64), Box<PipelineExecutionError>> {
+    let order = SortOrder::new(executable);
+    let mut input_rows = 0;
+    match executable.limit {
+        Some(limit) => {
+            let mut top_k = TopK::new(order, limit, reservation);
+            while let Some(row) = iterator.next() {
+                top_k.push(row?)?;
+                input_rows += 1;
             }
-            Ordering::Equal
-        });
-        Self { unsorted, sorted_indices: indices, next_index_index: 0 }
+            Ok((SortStageIterator::InMemory(top_k.finish()), input_rows))
+        }
+        None => {
+            let mut sorter = ExternalSorter::new(order, spill, reservation);
+            while let Some(row) = iterator.next() {
+                sorter.push(row?)?;
+                input_rows += 1;
+            }
+            Ok((sorter.finish()?, input_rows))
+        }
     }
 }
 
+pub enum SortStageIterator {
+    InMemory(SortedRows),
+    Merged(MergedRuns),
+}
+
 impl LendingIterator for SortStageIterator {
     type Item<'a> = Result<MaybeOwnedRow<'a>, Box<PipelineExecutionError>>;
 
     fn next(&mut self) -> Option<Self::Item<'_>> {
-        if self.next_index_index < self.unsorted.len() {
-            let row = self.unsorted.get_row(self.sorted_indices[self.next_index_index]);
-            self.next_index_index += 1;
-            Some(Ok(row))
-        } else {
-            None
+        match self {
+            SortStageIterator::InMemory(rows) => rows.next(),
+            SortStageIterator::Merged(runs) => runs.next(),
         }
     }
 }
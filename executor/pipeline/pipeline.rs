@@ -8,6 +8,7 @@ use std::{collections::HashMap, sync::Arc};
 
 use answer::variable::Variable;
 use compiler::{
+    annotation::expression::compiled_expression::ExpressionValueType,
     executable::{
         fetch::executable::ExecutableFetch, match_::planner::function_plan::ExecutableFunctionRegistry,
         pipeline::ExecutableStage,
@@ -15,12 +16,17 @@ use compiler::{
     VariablePosition,
 };
 use concept::thing::thing_manager::ThingManager;
+use encoding::value::value_type::ValueType;
 use error::typedb_error;
-use ir::pipeline::ParameterRegistry;
+use ir::{
+    pattern::variable_category::VariableCategory,
+    pipeline::{ParameterRegistry, VariableRegistry},
+};
 use storage::snapshot::{ReadableSnapshot, WritableSnapshot};
 
 use crate::{
     document::ConceptDocument,
+    memory::QueryMemory,
     pipeline::{
         delete::DeleteStageExecutor,
         fetch::FetchStageExecutor,
@@ -34,30 +40,83 @@ use crate::{
         stage::{ExecutionContext, ReadPipelineStage, StageAPI, WritePipelineStage},
         PipelineExecutionError,
     },
+    profile::QueryProfile,
     row::MaybeOwnedRow,
     ExecutionInterrupt,
 };
 
 pub enum Pipeline<Snapshot: ReadableSnapshot, Nonterminals: StageAPI<Snapshot>> {
-    Unfetched(Nonterminals, HashMap<String, VariablePosition>),
+    Unfetched(Nonterminals, HashMap<String, VariablePosition>, HashMap<String, ColumnType>),
     Fetched(Nonterminals, FetchStageExecutor<Snapshot>),
 }
 
+/// What an output column of a rows pipeline holds, as far as it is known before the pipeline runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnType {
+    Concept,
+    Value(ValueType),
+    List,
+    Unknown,
+}
+
+impl ColumnType {
+    fn of(category: Option<VariableCategory>, value_type: Option<&ExpressionValueType>) -> Self {
+        match (category, value_type) {
+            (_, Some(ExpressionValueType::Single(value_type))) => Self::Value(value_type.clone()),
+            (_, Some(ExpressionValueType::List(_))) => Self::List,
+            (
+                Some(
+                    VariableCategory::Type
+                    | VariableCategory::ThingType
+                    | VariableCategory::AttributeType
+                    | VariableCategory::RoleType
+                    | VariableCategory::Thing
+                    | VariableCategory::Object
+                    | VariableCategory::Attribute,
+                ),
+                None,
+            ) => Self::Concept,
+            (
+                Some(
+                    VariableCategory::ThingList
+                    | VariableCategory::ObjectList
+                    | VariableCategory::AttributeList
+                    | VariableCategory::ValueList,
+                ),
+                None,
+            ) => Self::List,
+            (Some(VariableCategory::AttributeOrValue | VariableCategory::Value) | None, None) => Self::Unknown,
+        }
+    }
+}
+
 impl<Snapshot: ReadableSnapshot + 'static, Nonterminals: StageAPI<Snapshot>> Pipeline<Snapshot, Nonterminals> {
     fn build_with_fetch(
-        variable_names: &HashMap<Variable, String>,
+        variable_registry: &VariableRegistry,
+        output_value_types: &HashMap<Variable, ExpressionValueType>,
         executable_functions: Arc<ExecutableFunctionRegistry>,
         last_stage: Nonterminals,
         last_stage_output_positions: HashMap<Variable, VariablePosition>,
         executable_fetch: Option<Arc<ExecutableFetch>>,
     ) -> Self {
+        let variable_names = variable_registry.variable_names();
         let named_outputs = last_stage_output_positions
             .iter()
             .filter_map(|(variable, &position)| variable_names.get(variable).map(|name| (name.clone(), position)))
             .collect::<HashMap<_, _>>();
+        let column_types = last_stage_output_positions
+            .keys()
+            .filter_map(|variable| {
+                let column_type = ColumnType::of(
+                    variable_registry.get_variable_category(*variable),
+                    output_value_types.get(variable),
+                );
+                variable_names.get(variable).map(|name| (name.clone(), column_type))
+            })
+            .collect::<HashMap<_, _>>();
 
         match executable_fetch {
-            None => Pipeline::Unfetched(last_stage, named_outputs),
+            None => Pipeline::Unfetched(last_stage, named_outputs, column_types),
             Some(executable) => {
                 let fetch = FetchStageExecutor::new(executable, executable_functions);
                 Pipeline::Fetched(last_stage, fetch)
@@ -71,7 +130,14 @@ impl<Snapshot: ReadableSnapshot + 'static, Nonterminals: StageAPI<Snapshot>> Pip
 
     pub fn rows_positions(&self) -> Option<&HashMap<String, VariablePosition>> {
         match self {
-            Self::Unfetched(_, positions) => Some(positions),
+            Self::Unfetched(_, positions, _) => Some(positions),
+            Self::Fetched(_, _) => None,
+        }
+    }
+
+    pub fn rows_column_types(&self) -> Option<&HashMap<String, ColumnType>> {
+        match self {
+            Self::Unfetched(_, _, column_types) => Some(column_types),
             Self::Fetched(_, _) => None,
         }
     }
@@ -84,7 +150,7 @@ impl<Snapshot: ReadableSnapshot + 'static, Nonterminals: StageAPI<Snapshot>> Pip
         (Box<PipelineExecutionError>, ExecutionContext<Snapshot>),
     > {
         match self {
-            Self::Unfetched(nonterminals, _) => nonterminals.into_iterator(execution_interrupt),
+            Self::Unfetched(nonterminals, _, _) => nonterminals.into_iterator(execution_interrupt),
             Self::Fetched(nonterminals, _) => {
                 let (_, context) = nonterminals.into_iterator(execution_interrupt)?;
                 Err((Box::new(PipelineExecutionError::FetchUsedAsRows {}), context))
@@ -100,7 +166,7 @@ impl<Snapshot: ReadableSnapshot + 'static, Nonterminals: StageAPI<Snapshot>> Pip
         (Box<PipelineExecutionError>, ExecutionContext<Snapshot>),
     > {
         match self {
-            Self::Unfetched(nonterminals, _) => {
+            Self::Unfetched(nonterminals, _, _) => {
                 let (_, context) = nonterminals.into_iterator(execution_interrupt)?;
                 Err((Box::new(PipelineExecutionError::FetchUsedAsRows {}), context))
             }
@@ -116,15 +182,19 @@ impl<Snapshot: ReadableSnapshot + 'static> Pipeline<Snapshot, ReadPipelineStage<
     pub fn build_read_pipeline(
         snapshot: Arc<Snapshot>,
         thing_manager: Arc<ThingManager>,
-        variable_names: &HashMap<Variable, String>,
+        variable_registry: &VariableRegistry,
+        output_value_types: &HashMap<Variable, ExpressionValueType>,
         executable_functions: Arc<ExecutableFunctionRegistry>,
         executable_stages: &[ExecutableStage],
         executable_fetch: Option<Arc<ExecutableFetch>>,
         parameters: Arc<ParameterRegistry>,
+        query_profile: Arc<QueryProfile>,
+        query_memory: Arc<QueryMemory>,
         input: Option<MaybeOwnedRow<'_>>,
     ) -> Result<Self, Box<PipelineError>> {
         let output_variable_positions = executable_stages.last().unwrap().output_row_mapping();
-        let context = ExecutionContext::new(snapshot, thing_manager, parameters);
+        let context =
+            ExecutionContext::new_with_profile(snapshot, thing_manager, parameters, query_profile, query_memory);
         let mut last_stage = ReadPipelineStage::Initial(Box::new(
             input
                 .map(|row| InitialStage::new_with(context.clone(), row))
@@ -171,7 +241,8 @@ impl<Snapshot: ReadableSnapshot + 'static> Pipeline<Snapshot, ReadPipelineStage<
             }
         }
         Ok(Pipeline::build_with_fetch(
-            variable_names,
+            variable_registry,
+            output_value_types,
             executable_functions.clone(),
             last_stage,
             output_variable_positions,
@@ -183,14 +254,23 @@ impl<Snapshot: ReadableSnapshot + 'static> Pipeline<Snapshot, ReadPipelineStage<
 impl<Snapshot: WritableSnapshot + 'static> Pipeline<Snapshot, WritePipelineStage<Snapshot>> {
     pub fn build_write_pipeline(
         snapshot: Snapshot,
-        variable_names: &HashMap<Variable, String>,
+        variable_registry: &VariableRegistry,
+        output_value_types: &HashMap<Variable, ExpressionValueType>,
         thing_manager: Arc<ThingManager>,
         executable_stages: Vec<ExecutableStage>,
         executable_fetch: Option<Arc<ExecutableFetch>>,
         parameters: Arc<ParameterRegistry>,
+        query_profile: Arc<QueryProfile>,
+        query_memory: Arc<QueryMemory>,
     ) -> Self {
         let output_variable_positions = executable_stages.last().unwrap().output_row_mapping();
-        let context = ExecutionContext::new(Arc::new(snapshot), thing_manager, parameters);
+        let context = ExecutionContext::new_with_profile(
+            Arc::new(snapshot),
+            thing_manager,
+            parameters,
+            query_profile,
+            query_memory,
+        );
         let mut last_stage = WritePipelineStage::Initial(Box::new(InitialStage::new_empty(context)));
         // TODO: Receive as an argument from schema
         let executable_functions = Arc::new(ExecutableFunctionRegistry::empty());
@@ -237,7 +317,8 @@ impl<Snapshot: WritableSnapshot + 'static> Pipeline<Snapshot, WritePipelineStage
             }
         }
         Pipeline::build_with_fetch(
-            variable_names,
+            variable_registry,
+            output_value_types,
             executable_functions,
             last_stage,
             output_variable_positions,
@@ -65,9 +65,11 @@ fn reduce_iterator<Snapshot: ReadableSnapshot>(
     iterator: impl StageIterator,
 ) -> Result<Batch, Box<PipelineExecutionError>> {
     let mut iterator = iterator;
-    let mut grouped_reducer = GroupedReducer::new(executable.reduce_rows_executable.clone());
+    let mut grouped_reducer = GroupedReducer::new(executable.reduce_rows_executable.clone(), context.memory.clone());
     while let Some(result) = iterator.next() {
-        grouped_reducer.accept(&result?, context)?;
+        grouped_reducer
+            .accept(&result?, context)
+            .map_err(|typedb_source| Box::new(PipelineExecutionError::MemoryLimit { typedb_source }))?;
     }
     Ok(grouped_reducer.finalise())
 }
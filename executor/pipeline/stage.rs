@@ -14,6 +14,7 @@ use tracing::Level;
 
 use crate::{
     batch::Batch,
+    memory::QueryMemory,
     pipeline::{
         delete::DeleteStageExecutor,
         initial::{InitialIterator, InitialStage},
@@ -37,13 +38,29 @@ pub struct ExecutionContext<Snapshot> {
     pub thing_manager: Arc<ThingManager>,
     pub parameters: Arc<ParameterRegistry>,
     pub profile: Arc<QueryProfile>,
+    pub memory: Arc<QueryMemory>,
 }
 
 impl<Snapshot> ExecutionContext<Snapshot> {
     pub fn new(snapshot: Arc<Snapshot>, thing_manager: Arc<ThingManager>, parameters: Arc<ParameterRegistry>) -> Self {
-        // TODO: in the future, we should use a parameter passed either at Query or Transaction time
         let is_tracing = tracing::enabled!(Level::TRACE);
-        Self { snapshot, thing_manager, parameters, profile: Arc::new(QueryProfile::new(is_tracing)) }
+        Self::new_with_profile(
+            snapshot,
+            thing_manager,
+            parameters,
+            Arc::new(QueryProfile::new(is_tracing)),
+            Arc::new(QueryMemory::default()),
+        )
+    }
+
+    pub fn new_with_profile(
+        snapshot: Arc<Snapshot>,
+        thing_manager: Arc<ThingManager>,
+        parameters: Arc<ParameterRegistry>,
+        profile: Arc<QueryProfile>,
+        memory: Arc<QueryMemory>,
+    ) -> Self {
+        Self { snapshot, thing_manager, parameters, profile, memory }
     }
 
     pub(crate) fn clone_with_replaced_parameters(&self, parameters: Arc<ParameterRegistry>) -> Self {
@@ -52,6 +69,7 @@ impl<Snapshot> ExecutionContext<Snapshot> {
             thing_manager: self.thing_manager.clone(),
             parameters,
             profile: self.profile.clone(),
+            memory: self.memory.clone(),
         }
     }
 
@@ -74,12 +92,13 @@ impl<Snapshot> ExecutionContext<Snapshot> {
 
 impl<Snapshot> Clone for ExecutionContext<Snapshot> {
     fn clone(&self) -> Self {
-        let Self { snapshot, thing_manager, parameters, profile } = self;
+        let Self { snapshot, thing_manager, parameters, profile, memory } = self;
         Self {
             snapshot: snapshot.clone(),
             thing_manager: thing_manager.clone(),
             parameters: parameters.clone(),
             profile: profile.clone(),
+            memory: memory.clone(),
         }
     }
 }
@@ -46,6 +46,20 @@ impl QueryProfile {
             Arc::new(StageProfile::new(String::new(), false))
         }
     }
+
+    pub fn summary(&self) -> QueryProfileSummary {
+        let profiles = self.stage_profiles.read().unwrap();
+        let stages = profiles
+            .iter()
+            .sorted_by_key(|(id, _)| **id)
+            .map(|(id, stage_profile)| StageProfileSummary {
+                id: *id,
+                description: stage_profile.description.clone(),
+                steps: stage_profile.step_summaries(),
+            })
+            .collect();
+        QueryProfileSummary { stages }
+    }
 }
 
 impl fmt::Display for QueryProfile {
@@ -74,13 +88,22 @@ impl StageProfile {
     }
 
     pub(crate) fn extend_or_get(&self, index: usize, description_getter: impl Fn() -> String) -> Arc<StepProfile> {
+        self.extend_or_get_estimated(index, None, description_getter)
+    }
+
+    pub(crate) fn extend_or_get_estimated(
+        &self,
+        index: usize,
+        estimated_rows: Option<f64>,
+        description_getter: impl Fn() -> String,
+    ) -> Arc<StepProfile> {
         if self.enabled {
             let profiles = self.step_profiles.read().unwrap();
             if index < profiles.len() {
                 profiles[index].clone()
             } else {
                 debug_assert!(index == profiles.len(), "Can only extend step profiles sequentially");
-                let profile = Arc::new(StepProfile::new_enabled(description_getter()));
+                let profile = Arc::new(StepProfile::new_enabled(description_getter(), estimated_rows));
                 drop(profiles);
                 let mut profiles_mut = self.step_profiles.write().unwrap();
                 profiles_mut.push(profile.clone());
@@ -90,6 +113,25 @@ impl StageProfile {
             Arc::new(StepProfile::new_disabled())
         }
     }
+
+    fn step_summaries(&self) -> Vec<StepProfileSummary> {
+        let profiles = self.step_profiles.read().unwrap();
+        profiles
+            .iter()
+            .enumerate()
+            .filter_map(|(index, step_profile)| {
+                let data = step_profile.data.as_ref()?;
+                Some(StepProfileSummary {
+                    index,
+                    description: data.description.clone(),
+                    estimated_rows: data.estimated_rows,
+                    batches: data.batches.load(Ordering::Relaxed),
+                    rows: data.rows.load(Ordering::Relaxed),
+                    micros: Duration::from_nanos(data.nanos.load(Ordering::Relaxed)).as_micros() as u64,
+                })
+            })
+            .collect()
+    }
 }
 
 impl fmt::Display for StageProfile {
@@ -112,16 +154,18 @@ pub struct StepProfile {
 #[derive(Debug)]
 struct StepProfileData {
     description: String,
+    estimated_rows: Option<f64>,
     batches: AtomicU64,
     rows: AtomicU64,
     nanos: AtomicU64,
 }
 
 impl StepProfile {
-    fn new_enabled(description: String) -> Self {
+    fn new_enabled(description: String, estimated_rows: Option<f64>) -> Self {
         Self {
             data: Some(StepProfileData {
                 description,
+                estimated_rows,
                 batches: AtomicU64::new(0),
                 rows: AtomicU64::new(0),
                 nanos: AtomicU64::new(0),
@@ -151,7 +195,11 @@ impl fmt::Display for StepProfileData {
             self.batches.load(Ordering::Relaxed),
             self.rows.load(Ordering::Relaxed),
             Duration::from_nanos(self.nanos.load(Ordering::Relaxed)).as_micros(),
-        )
+        )?;
+        if let Some(estimated_rows) = self.estimated_rows {
+            write!(f, ", estimated rows: {:.1}", estimated_rows)?;
+        }
+        Ok(())
     }
 }
 
@@ -179,3 +227,26 @@ impl StepProfileMeasurement {
         }
     }
 }
+
+/// Snapshot of a query profile's measurements, in stage and step order
+#[derive(Debug, Clone)]
+pub struct QueryProfileSummary {
+    pub stages: Vec<StageProfileSummary>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StageProfileSummary {
+    pub id: u64,
+    pub description: String,
+    pub steps: Vec<StepProfileSummary>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepProfileSummary {
+    pub index: usize,
+    pub description: String,
+    pub estimated_rows: Option<f64>,
+    pub batches: u64,
+    pub rows: u64,
+    pub micros: u64,
+}
@@ -14,6 +14,7 @@ use storage::snapshot::ReadableSnapshot;
 use crate::{
     batch::{Batch, BatchRowIterator, FixedBatch},
     error::ReadExecutionError,
+    memory::{row_size, MemoryReservation},
     pipeline::stage::ExecutionContext,
     read::pattern_executor::PatternExecutor,
     reduce_executor::GroupedReducer,
@@ -30,7 +31,11 @@ pub(super) enum CollectorEnum {
 }
 
 impl CollectorEnum {
-    pub(crate) fn accept(&mut self, context: &ExecutionContext<impl ReadableSnapshot>, batch: FixedBatch) {
+    pub(crate) fn accept(
+        &mut self,
+        context: &ExecutionContext<impl ReadableSnapshot>,
+        batch: FixedBatch,
+    ) -> Result<(), ReadExecutionError> {
         match self {
             CollectorEnum::Reduce(collector) => collector.accept(context, batch),
             CollectorEnum::Sort(collector) => collector.accept(context, batch),
@@ -84,7 +89,7 @@ impl CollectingStageExecutor {
         }
     }
 
-    pub(crate) fn prepare(&mut self, batch: FixedBatch) {
+    pub(crate) fn prepare(&mut self, batch: FixedBatch, context: &ExecutionContext<impl ReadableSnapshot>) {
         debug_assert!({
             match &self.collector {
                 CollectorEnum::Reduce(_) => batch.len() == 1,
@@ -93,16 +98,20 @@ impl CollectingStageExecutor {
         });
         self.pattern.prepare(batch);
         match &mut self.collector {
-            CollectorEnum::Reduce(collector) => collector.prepare(),
-            CollectorEnum::Sort(collector) => collector.prepare(),
+            CollectorEnum::Reduce(collector) => collector.prepare(context),
+            CollectorEnum::Sort(collector) => collector.prepare(context),
         }
     }
 }
 
 pub(super) trait CollectorTrait {
-    fn prepare(&mut self);
+    fn prepare(&mut self, context: &ExecutionContext<impl ReadableSnapshot>);
     fn reset(&mut self);
-    fn accept(&mut self, context: &ExecutionContext<impl ReadableSnapshot>, batch: FixedBatch);
+    fn accept(
+        &mut self,
+        context: &ExecutionContext<impl ReadableSnapshot>,
+        batch: FixedBatch,
+    ) -> Result<(), ReadExecutionError>;
     fn collected_to_iterator(&mut self) -> CollectedStageIterator;
 }
 
@@ -126,19 +135,26 @@ impl ReduceCollector {
 }
 
 impl CollectorTrait for ReduceCollector {
-    fn prepare(&mut self) {
-        self.active_reducer = Some(GroupedReducer::new(self.reduce_executable.clone()));
+    fn prepare(&mut self, context: &ExecutionContext<impl ReadableSnapshot>) {
+        self.active_reducer = Some(GroupedReducer::new(self.reduce_executable.clone(), context.memory.clone()));
     }
 
     fn reset(&mut self) {
         self.active_reducer = None;
     }
 
-    fn accept(&mut self, context: &ExecutionContext<impl ReadableSnapshot>, batch: FixedBatch) {
+    fn accept(
+        &mut self,
+        context: &ExecutionContext<impl ReadableSnapshot>,
+        batch: FixedBatch,
+    ) -> Result<(), ReadExecutionError> {
         let active_reducer = self.active_reducer.as_mut().unwrap();
         for row in batch {
-            active_reducer.accept(&row, context).unwrap(); // TODO: potentially unsafe unwrap
+            active_reducer
+                .accept(&row, context)
+                .map_err(|typedb_source| ReadExecutionError::MemoryLimit { typedb_source })?;
         }
+        Ok(())
     }
 
     fn collected_to_iterator(&mut self) -> CollectedStageIterator {
@@ -181,7 +197,7 @@ impl CollectedStageIteratorTrait for ReduceStageIterator {
 // Sort
 pub(super) struct SortCollector {
     sort_on: Vec<(usize, bool)>,
-    collector: Option<Batch>,
+    collector: Option<(Batch, MemoryReservation)>,
 }
 
 impl SortCollector {
@@ -200,7 +216,7 @@ impl SortCollector {
 }
 
 impl CollectorTrait for SortCollector {
-    fn prepare(&mut self) {
+    fn prepare(&mut self, _context: &ExecutionContext<impl ReadableSnapshot>) {
         // self.collector = Some(Batch::new(self.output_width));
     }
 
@@ -208,17 +224,27 @@ impl CollectorTrait for SortCollector {
         self.collector = None;
     }
 
-    fn accept(&mut self, _context: &ExecutionContext<impl ReadableSnapshot>, batch: FixedBatch) {
+    fn accept(
+        &mut self,
+        context: &ExecutionContext<impl ReadableSnapshot>,
+        batch: FixedBatch,
+    ) -> Result<(), ReadExecutionError> {
         for row in batch {
             if self.collector.is_none() {
-                self.collector = Some(Batch::new(row.len() as u32, 0usize))
+                let reservation = MemoryReservation::new(context.memory.clone());
+                self.collector = Some((Batch::new(row.len() as u32, 0usize), reservation))
             }
-            self.collector.as_mut().unwrap().append(row);
+            let (collector, reservation) = self.collector.as_mut().unwrap();
+            reservation
+                .grow(row_size(row.row()))
+                .map_err(|typedb_source| ReadExecutionError::MemoryLimit { typedb_source })?;
+            collector.append(row);
         }
+        Ok(())
     }
 
     fn collected_to_iterator(&mut self) -> CollectedStageIterator {
-        let unsorted = self.collector.take().unwrap();
+        let (unsorted, reservation) = self.collector.take().unwrap();
         let mut indices: Vec<usize> = (0..unsorted.len()).collect();
         indices.sort_by(|x, y| {
             let x_row_as_row = unsorted.get_row(*x);
@@ -238,18 +264,20 @@ impl CollectorTrait for SortCollector {
             Ordering::Equal
         });
         let sorted_indices = indices.into_iter().peekable();
-        CollectedStageIterator::Sort(SortStageIterator { unsorted, sorted_indices })
+        CollectedStageIterator::Sort(SortStageIterator { unsorted, sorted_indices, _reservation: reservation })
     }
 }
 
 pub struct SortStageIterator {
     unsorted: Batch,
     sorted_indices: Peekable<std::vec::IntoIter<usize>>,
+    // released once the sorted rows are dropped
+    _reservation: MemoryReservation,
 }
 
 impl CollectedStageIteratorTrait for SortStageIterator {
     fn batch_continue(&mut self) -> Result<Option<FixedBatch>, ReadExecutionError> {
-        let Self { unsorted, sorted_indices } = self;
+        let Self { unsorted, sorted_indices, .. } = self;
         if sorted_indices.peek().is_some() {
             let width = unsorted.get_row(0).len();
             let mut next_batch = FixedBatch::new(width as u32);
@@ -302,7 +302,7 @@ impl PatternExecutor {
                     let (pattern, collector) = executors[index.0].unwrap_collecting_stage().to_parts_mut();
                     match pattern.batch_continue(context, interrupt, tabled_functions, suspensions)? {
                         Some(batch) => {
-                            collector.accept(context, batch);
+                            collector.accept(context, batch)?;
                             self.control_stack.push(ControlInstruction::CollectingStage(CollectingStage { index }))
                         }
                         None => {
@@ -368,7 +368,7 @@ impl PatternExecutor {
                     }))
                 }
                 StepExecutors::CollectingStage(collecting_stage) => {
-                    collecting_stage.prepare(batch);
+                    collecting_stage.prepare(batch, context);
                     self.control_stack
                         .push(ControlInstruction::CollectingStage(CollectingStage { index: next_executor_index }));
                 }
@@ -485,8 +485,7 @@ impl PatternExecutor {
                     suspensions: function_suspensions,
                     parameters,
                 } = pattern_state_mutex_guard.deref_mut();
-                let context_with_function_parameters =
-                    ExecutionContext::new(context.snapshot.clone(), context.thing_manager.clone(), parameters.clone());
+                let context_with_function_parameters = context.clone_with_replaced_parameters(parameters.clone());
                 let _suspension_count_before = function_suspensions.record_nested_pattern_entry();
                 let batch_opt = pattern_executor.batch_continue(
                     &context_with_function_parameters,
@@ -496,7 +495,9 @@ impl PatternExecutor {
                 )?;
                 let suspension_count_after = function_suspensions.record_nested_pattern_exit();
                 if let Some(batch) = batch_opt {
-                    let deduplicated_batch = executor.add_batch_to_table(&function_state, batch);
+                    let deduplicated_batch = executor
+                        .add_batch_to_table(&function_state, batch)
+                        .map_err(|typedb_source| ReadExecutionError::MemoryLimit { typedb_source })?;
                     Some(deduplicated_batch)
                 } else {
                     if suspension_count_after.0 > 0 {
@@ -95,30 +95,36 @@ pub(crate) fn create_executors_for_match(
     let stage_profile = query_profile.profile_stage(|| String::from("Match"), match_executable.executable_id());
     let mut steps = Vec::with_capacity(match_executable.steps().len());
     for (index, step) in match_executable.steps().iter().enumerate() {
+        let estimated_rows = match_executable.estimated_output_rows(index);
         match step {
             ExecutionStep::Intersection(inner) => {
-                let step_profile = stage_profile.extend_or_get(index, || format!("{}", inner));
+                let step_profile =
+                    stage_profile.extend_or_get_estimated(index, estimated_rows, || format!("{}", inner));
                 let step = ImmediateExecutor::new_intersection(inner, snapshot, thing_manager, step_profile)?;
                 steps.push(step.into());
             }
             ExecutionStep::UnsortedJoin(inner) => {
-                let step_profile = stage_profile.extend_or_get(index, || format!("{}", inner));
+                let step_profile =
+                    stage_profile.extend_or_get_estimated(index, estimated_rows, || format!("{}", inner));
                 let step = ImmediateExecutor::new_unsorted_join(inner, step_profile)?;
                 steps.push(step.into());
             }
             ExecutionStep::Assignment(inner) => {
-                let step_profile = stage_profile.extend_or_get(index, || format!("{}", inner));
+                let step_profile =
+                    stage_profile.extend_or_get_estimated(index, estimated_rows, || format!("{}", inner));
                 let step = ImmediateExecutor::new_assignment(inner, step_profile)?;
                 steps.push(step.into());
             }
             ExecutionStep::Check(inner) => {
-                let step_profile = stage_profile.extend_or_get(index, || format!("{}", inner));
+                let step_profile =
+                    stage_profile.extend_or_get_estimated(index, estimated_rows, || format!("{}", inner));
                 let step = ImmediateExecutor::new_check(inner, step_profile)?;
                 steps.push(step.into());
             }
             ExecutionStep::Negation(negation_step) => {
                 // NOTE: still create the profile so each step has an entry in the profile, even if unused
-                let _step_profile = stage_profile.extend_or_get(index, || format!("{}", negation_step));
+                let _step_profile =
+                    stage_profile.extend_or_get_estimated(index, estimated_rows, || format!("{}", negation_step));
                 let inner = create_executors_for_match(
                     snapshot,
                     thing_manager,
@@ -137,7 +143,8 @@ pub(crate) fn create_executors_for_match(
             }
             ExecutionStep::FunctionCall(function_call) => {
                 // NOTE: still create the profile so each step has an entry in the profile, even if unused
-                let _step_profile = stage_profile.extend_or_get(index, || format!("{}", function_call));
+                let _step_profile =
+                    stage_profile.extend_or_get_estimated(index, estimated_rows, || format!("{}", function_call));
 
                 let function = function_registry.get(function_call.function_id.clone());
                 if function.is_tabled == FunctionTablingType::Tabled {
@@ -167,7 +174,8 @@ pub(crate) fn create_executors_for_match(
             }
             ExecutionStep::Disjunction(step) => {
                 // NOTE: still create the profile so each step has an entry in the profile, even if unused
-                let _step_profile = stage_profile.extend_or_get(index, || format!("{}", step));
+                let _step_profile =
+                    stage_profile.extend_or_get_estimated(index, estimated_rows, || format!("{}", step));
 
                 // I shouldn't need to pass recursive here since it's stratified
                 let branches = step
@@ -11,6 +11,7 @@ use ir::pipeline::function_signature::FunctionID;
 
 use crate::{
     batch::FixedBatch,
+    memory::MemoryLimitError,
     read::{
         pattern_executor::ExecutorIndex,
         tabled_call_executor::TabledCallResult::Suspend,
@@ -117,9 +118,13 @@ impl TabledCallExecutor {
         }
     }
 
-    pub(crate) fn add_batch_to_table(&mut self, state: &TabledFunctionState, batch: FixedBatch) -> FixedBatch {
-        let deduplicated_batch = state.add_batch_to_table(batch);
+    pub(crate) fn add_batch_to_table(
+        &mut self,
+        state: &TabledFunctionState,
+        batch: FixedBatch,
+    ) -> Result<FixedBatch, MemoryLimitError> {
+        let deduplicated_batch = state.add_batch_to_table(batch)?;
         *self.active_executor.as_mut().unwrap().next_table_row += deduplicated_batch.len() as usize;
-        deduplicated_batch
+        Ok(deduplicated_batch)
     }
 }
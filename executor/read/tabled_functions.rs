@@ -16,6 +16,7 @@ use storage::snapshot::ReadableSnapshot;
 use crate::{
     batch::FixedBatch,
     error::ReadExecutionError,
+    memory::{row_size, MemoryLimitError, MemoryReservation, QueryMemory},
     pipeline::stage::ExecutionContext,
     read::{pattern_executor::PatternExecutor, step_executor::create_executors_for_function, QueryPatternSuspensions},
     row::MaybeOwnedRow,
@@ -58,6 +59,7 @@ impl TabledFunctions {
                     &call_key.arguments,
                     width,
                     function.parameter_registry.clone(),
+                    context.memory.clone(),
                 )),
             );
         }
@@ -102,10 +104,15 @@ impl TabledFunctionState {
         args: &MaybeOwnedRow<'_>,
         answer_width: u32,
         parameters: Arc<ParameterRegistry>,
+        memory: Arc<QueryMemory>,
     ) -> Self {
         pattern_executor.prepare(FixedBatch::from(args.as_reference()));
         Self {
-            table: RwLock::new(AnswerTable { answers: Vec::new(), width: answer_width }),
+            table: RwLock::new(AnswerTable {
+                answers: Vec::new(),
+                width: answer_width,
+                reservation: MemoryReservation::new(memory),
+            }),
             executor_state: Mutex::new(TabledFunctionPatternExecutorState {
                 pattern_executor,
                 suspensions: QueryPatternSuspensions::new(),
@@ -114,18 +121,18 @@ impl TabledFunctionState {
         }
     }
 
-    pub(crate) fn add_batch_to_table(&self, batch: FixedBatch) -> FixedBatch {
+    pub(crate) fn add_batch_to_table(&self, batch: FixedBatch) -> Result<FixedBatch, MemoryLimitError> {
         if !batch.is_empty() {
             let mut deduplicated_batch = FixedBatch::new(batch.get_row(0).len() as u32);
             let mut table = self.table.write().unwrap();
             for row in batch {
-                if table.try_add_row(row.as_reference()) {
+                if table.try_add_row(row.as_reference())? {
                     deduplicated_batch.append(|mut write_to| write_to.copy_from_row(row))
                 }
             }
-            deduplicated_batch
+            Ok(deduplicated_batch)
         } else {
-            batch
+            Ok(batch)
         }
     }
 }
@@ -134,6 +141,7 @@ pub(crate) struct AnswerTable {
     // TODO: use a better data-structure. XSB has an "answer-trie" though a LinkedHashSet might do.
     answers: Vec<MaybeOwnedRow<'static>>,
     width: u32,
+    reservation: MemoryReservation,
     // TODO: We need to be able to record the fact that a table is DONE
 }
 
@@ -155,12 +163,13 @@ impl AnswerTable {
         batch
     }
 
-    fn try_add_row(&mut self, row: MaybeOwnedRow<'_>) -> bool {
+    fn try_add_row(&mut self, row: MaybeOwnedRow<'_>) -> Result<bool, MemoryLimitError> {
         if !self.answers.contains(&row) {
+            self.reservation.grow(row_size(row.row()))?;
             self.answers.push(row.clone().into_owned());
-            true
+            Ok(true)
         } else {
-            false
+            Ok(false)
         }
     }
 }
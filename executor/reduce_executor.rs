@@ -4,7 +4,7 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, mem::size_of, sync::Arc};
 
 use answer::{variable_value::VariableValue, Thing};
 use compiler::{
@@ -16,7 +16,8 @@ use storage::snapshot::ReadableSnapshot;
 
 use crate::{
     batch::Batch,
-    pipeline::{stage::ExecutionContext, PipelineExecutionError},
+    memory::{row_size, MemoryLimitError, MemoryReservation, QueryMemory},
+    pipeline::stage::ExecutionContext,
     row::MaybeOwnedRow,
 };
 
@@ -26,10 +27,11 @@ pub(crate) struct GroupedReducer {
     reused_group: Vec<VariableValue<'static>>,
     // Clone for efficient instantiation of reducers for a new group
     uninitialised_reducer_executors: Vec<ReducerExecutor>,
+    reservation: MemoryReservation,
 }
 
 impl GroupedReducer {
-    pub(crate) fn new(executable: Arc<ReduceRowsExecutable>) -> Self {
+    pub(crate) fn new(executable: Arc<ReduceRowsExecutable>, memory: Arc<QueryMemory>) -> Self {
         let reducers: Vec<ReducerExecutor> = executable.reductions.iter().map(ReducerExecutor::build).collect();
         let reused_group = Vec::with_capacity(executable.input_group_positions.len());
         let mut grouped_reductions = HashMap::new();
@@ -42,6 +44,7 @@ impl GroupedReducer {
             grouped_reductions,
             reused_group,
             uninitialised_reducer_executors: reducers,
+            reservation: MemoryReservation::new(memory),
         }
     }
 
@@ -49,12 +52,14 @@ impl GroupedReducer {
         &mut self,
         row: &MaybeOwnedRow<'_>,
         context: &ExecutionContext<Snapshot>,
-    ) -> Result<(), Box<PipelineExecutionError>> {
+    ) -> Result<(), MemoryLimitError> {
         self.reused_group.clear();
         for &pos in &self.rows_executable.input_group_positions {
             self.reused_group.push(row.get(pos).to_owned());
         }
         if !self.grouped_reductions.contains_key(&self.reused_group) {
+            let reducers_size = size_of::<ReducerExecutor>() * self.uninitialised_reducer_executors.len();
+            self.reservation.grow(row_size(&self.reused_group) + reducers_size as u64)?;
             self.grouped_reductions.insert(self.reused_group.clone(), self.uninitialised_reducer_executors.clone());
         }
         let reducers = self.grouped_reductions.get_mut(&self.reused_group).unwrap();
@@ -32,6 +32,7 @@ use ir::{
 };
 use itertools::Itertools;
 use lending_iterator::LendingIterator;
+use options::QueryOptions;
 use query::query_manager::QueryManager;
 use storage::{
     durability_client::WALClient, sequence_number::SequenceNumber, snapshot::CommittableSnapshot, MVCCStorage,
@@ -57,7 +58,14 @@ fn setup(
     let snapshot = storage.clone().open_snapshot_write();
     let query = typeql::parse_query(data).unwrap().into_pipeline();
     let pipeline = query_manager
-        .prepare_write_pipeline(snapshot, &type_manager, thing_manager.clone(), &FunctionManager::default(), &query)
+        .prepare_write_pipeline(
+            snapshot,
+            &type_manager,
+            thing_manager.clone(),
+            &FunctionManager::default(),
+            &query,
+            QueryOptions::default(),
+        )
         .unwrap();
     let (mut iterator, ExecutionContext { snapshot, .. }) =
         pipeline.into_rows_iterator(ExecutionInterrupt::new_uninterruptible()).unwrap();
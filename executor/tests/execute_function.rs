@@ -16,6 +16,7 @@ use executor::{
 };
 use function::function_manager::FunctionManager;
 use lending_iterator::LendingIterator;
+use options::QueryOptions;
 use query::{query_cache::QueryCache, query_manager::QueryManager};
 use storage::{durability_client::WALClient, snapshot::CommittableSnapshot, MVCCStorage};
 use test_utils::TempDir;
@@ -120,6 +121,7 @@ fn run_read_query(
             context.thing_manager.clone(),
             &context.function_manager,
             &match_,
+            QueryOptions::default(),
         )
         .unwrap();
     let rows_positions = pipeline.rows_positions().unwrap().clone();
@@ -145,6 +147,7 @@ fn run_write_query(
             context.thing_manager.clone(),
             &context.function_manager,
             &query_as_pipeline,
+            QueryOptions::default(),
         )
         .unwrap();
     let rows_positions = pipeline.rows_positions().unwrap().clone();
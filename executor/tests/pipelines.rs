@@ -12,11 +12,15 @@ use encoding::{
     value::{label::Label, value::Value},
 };
 use executor::{
-    pipeline::stage::{ExecutionContext, StageIterator},
+    pipeline::{
+        stage::{ExecutionContext, StageIterator},
+        PipelineExecutionError,
+    },
     ExecutionInterrupt,
 };
 use function::function_manager::FunctionManager;
 use lending_iterator::LendingIterator;
+use options::QueryOptions;
 use query::{query_cache::QueryCache, query_manager::QueryManager};
 use storage::{durability_client::WALClient, snapshot::CommittableSnapshot, MVCCStorage};
 use test_utils::{assert_matches, TempDir};
@@ -75,6 +79,7 @@ fn test_insert() {
             context.thing_manager.clone(),
             &context.function_manager,
             &query,
+            QueryOptions::default(),
         )
         .unwrap();
 
@@ -113,6 +118,7 @@ fn test_insert_insert() {
             context.thing_manager.clone(),
             &context.function_manager,
             &query,
+            QueryOptions::default(),
         )
         .unwrap();
 
@@ -147,6 +153,7 @@ fn test_match() {
             context.thing_manager.clone(),
             &context.function_manager,
             &query,
+            QueryOptions::default(),
         )
         .unwrap();
     let (iterator, ExecutionContext { snapshot, .. }) =
@@ -167,6 +174,7 @@ fn test_match() {
             context.thing_manager.clone(),
             &context.function_manager,
             &match_,
+            QueryOptions::default(),
         )
         .unwrap();
     let (iterator, ExecutionContext { snapshot, .. }) =
@@ -184,6 +192,7 @@ fn test_match() {
             context.thing_manager.clone(),
             &context.function_manager,
             &match_,
+            QueryOptions::default(),
         )
         .unwrap();
     let (iterator, ExecutionContext { .. }) =
@@ -211,6 +220,7 @@ fn test_match_match() {
             context.thing_manager.clone(),
             &context.function_manager,
             &query,
+            QueryOptions::default(),
         )
         .unwrap();
     let (iterator, ExecutionContext { snapshot, .. }) =
@@ -234,6 +244,7 @@ fn test_match_match() {
             context.thing_manager.clone(),
             &context.function_manager,
             &match_,
+            QueryOptions::default(),
         )
         .unwrap();
     let (iterator, ExecutionContext { snapshot, .. }) =
@@ -251,6 +262,7 @@ fn test_match_match() {
             context.thing_manager.clone(),
             &context.function_manager,
             &match_,
+            QueryOptions::default(),
         )
         .unwrap();
     let (iterator, ExecutionContext { .. }) =
@@ -273,6 +285,7 @@ fn test_match_delete_has() {
             context.thing_manager.clone(),
             &context.function_manager,
             &insert_query,
+            QueryOptions::default(),
         )
         .unwrap();
     let (mut iterator, ExecutionContext { snapshot, .. }) =
@@ -307,6 +320,7 @@ fn test_match_delete_has() {
             context.thing_manager.clone(),
             &context.function_manager,
             &delete_query,
+            QueryOptions::default(),
         )
         .unwrap();
 
@@ -346,6 +360,7 @@ fn test_insert_match_insert() {
             context.thing_manager.clone(),
             &context.function_manager,
             &query,
+            QueryOptions::default(),
         )
         .unwrap();
     let (iterator, ExecutionContext { snapshot, .. }) =
@@ -374,6 +389,7 @@ fn test_insert_match_insert() {
             context.thing_manager.clone(),
             &context.function_manager,
             &query,
+            QueryOptions::default(),
         )
         .unwrap();
 
@@ -403,6 +419,7 @@ fn test_match_sort() {
             context.thing_manager.clone(),
             &context.function_manager,
             &insert_query,
+            QueryOptions::default(),
         )
         .unwrap();
     let (mut iterator, ExecutionContext { snapshot, .. }) =
@@ -424,6 +441,7 @@ fn test_match_sort() {
             context.thing_manager.clone(),
             &context.function_manager,
             &match_,
+            QueryOptions::default(),
         )
         .unwrap();
     let named_outputs = pipeline.rows_positions().unwrap().clone();
@@ -448,6 +466,64 @@ fn test_match_sort() {
     assert_eq!([4, 3, 2, 1], values.as_slice());
 }
 
+#[test]
+fn test_match_sort_limit() {
+    let context = setup_common();
+    let snapshot = context.storage.clone().open_snapshot_write();
+    let insert_query_str = "insert $p isa person, has age 3, has age 1, has age 5, has age 2, has age 4;";
+    let insert_query = typeql::parse_query(insert_query_str).unwrap().into_pipeline();
+    let pipeline = context
+        .query_manager
+        .prepare_write_pipeline(
+            snapshot,
+            &context.type_manager,
+            context.thing_manager.clone(),
+            &context.function_manager,
+            &insert_query,
+            QueryOptions::default(),
+        )
+        .unwrap();
+    let (iterator, ExecutionContext { snapshot, .. }) =
+        pipeline.into_rows_iterator(ExecutionInterrupt::new_uninterruptible()).unwrap();
+    let _ = iterator.count();
+    let snapshot = Arc::into_inner(snapshot).unwrap();
+    snapshot.commit().unwrap();
+
+    let snapshot = Arc::new(context.storage.open_snapshot_read());
+    let query = "match $age isa age; sort $age desc; limit 2;";
+    let match_ = typeql::parse_query(query).unwrap().into_pipeline();
+    let pipeline = context
+        .query_manager
+        .prepare_read_pipeline(
+            snapshot,
+            &context.type_manager,
+            context.thing_manager.clone(),
+            &context.function_manager,
+            &match_,
+            QueryOptions::default(),
+        )
+        .unwrap();
+    let named_outputs = pipeline.rows_positions().unwrap().clone();
+    let (iterator, ExecutionContext { snapshot, .. }) =
+        pipeline.into_rows_iterator(ExecutionInterrupt::new_uninterruptible()).unwrap();
+
+    let batch = iterator.collect_owned().unwrap();
+    let pos = named_outputs["age"];
+    let values = batch
+        .into_iterator_mut()
+        .map_static(move |res| {
+            res.get(pos)
+                .as_thing()
+                .as_attribute()
+                .get_value(&*snapshot, &context.thing_manager)
+                .clone()
+                .unwrap()
+                .unwrap_long()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!([5, 4], values.as_slice());
+}
+
 #[test]
 fn test_select() {
     let context = setup_common();
@@ -464,6 +540,7 @@ fn test_select() {
             context.thing_manager.clone(),
             &context.function_manager,
             &insert_query,
+            QueryOptions::default(),
         )
         .unwrap();
     let (mut iterator, ExecutionContext { snapshot, .. }) =
@@ -486,6 +563,7 @@ fn test_select() {
                 context.thing_manager.clone(),
                 &context.function_manager,
                 &match_,
+                QueryOptions::default(),
             )
             .unwrap();
         let named_outputs = pipeline.rows_positions().unwrap();
@@ -504,6 +582,7 @@ fn test_select() {
                 context.thing_manager.clone(),
                 &context.function_manager,
                 &match_,
+                QueryOptions::default(),
             )
             .unwrap();
         let named_outputs = pipeline.rows_positions().unwrap();
@@ -528,6 +607,7 @@ fn test_require() {
             context.thing_manager.clone(),
             &context.function_manager,
             &insert_query,
+            QueryOptions::default(),
         )
         .unwrap();
     let (mut iterator, ExecutionContext { snapshot, .. }) =
@@ -550,6 +630,7 @@ fn test_require() {
                 context.thing_manager.clone(),
                 &context.function_manager,
                 &match_,
+                QueryOptions::default(),
             )
             .unwrap();
         let named_outputs = pipeline.rows_positions().unwrap();
@@ -557,3 +638,273 @@ fn test_require() {
         assert!(named_outputs.contains_key("p"));
     }
 }
+
+#[test]
+fn test_profile() {
+    let context = setup_common();
+    let snapshot = context.storage.clone().open_snapshot_write();
+    let query_str = r#"
+       insert
+       $p isa person, has age 10, has name 'John';
+       $q isa person, has age 20, has name 'Alice';
+   "#;
+    let query = typeql::parse_query(query_str).unwrap().into_pipeline();
+    let pipeline = context
+        .query_manager
+        .prepare_write_pipeline(
+            snapshot,
+            &context.type_manager,
+            context.thing_manager.clone(),
+            &context.function_manager,
+            &query,
+            QueryOptions::default(),
+        )
+        .unwrap();
+    let (iterator, ExecutionContext { snapshot, .. }) =
+        pipeline.into_rows_iterator(ExecutionInterrupt::new_uninterruptible()).unwrap();
+    let _ = iterator.count();
+    let snapshot = Arc::into_inner(snapshot).unwrap();
+    snapshot.commit().unwrap();
+
+    let snapshot = Arc::new(context.storage.open_snapshot_read());
+    let query = "match $person isa person, has age $age;";
+    let match_ = typeql::parse_query(query).unwrap().into_pipeline();
+    let pipeline = context
+        .query_manager
+        .prepare_read_pipeline(
+            snapshot,
+            &context.type_manager,
+            context.thing_manager.clone(),
+            &context.function_manager,
+            &match_,
+            QueryOptions { profile: true, ..QueryOptions::default() },
+        )
+        .unwrap();
+    let (iterator, ExecutionContext { profile, .. }) =
+        pipeline.into_rows_iterator(ExecutionInterrupt::new_uninterruptible()).unwrap();
+    let batch = iterator.collect_owned().unwrap();
+    assert_eq!(batch.len(), 2);
+
+    assert!(profile.is_enabled());
+    let summary = profile.summary();
+    let steps = summary.stages.iter().flat_map(|stage| stage.steps.iter()).collect::<Vec<_>>();
+    assert!(!steps.is_empty());
+    assert!(steps.iter().any(|step| step.estimated_rows.is_some()));
+    assert!(steps.iter().any(|step| step.rows > 0));
+}
+
+#[test]
+fn test_query_memory_limit() {
+    let context = setup_common();
+    let snapshot = context.storage.clone().open_snapshot_write();
+    let query_str = r#"
+       insert
+       $p isa person, has age 10, has name 'John';
+       $q isa person, has age 20, has name 'Alice';
+   "#;
+    let query = typeql::parse_query(query_str).unwrap().into_pipeline();
+    let pipeline = context
+        .query_manager
+        .prepare_write_pipeline(
+            snapshot,
+            &context.type_manager,
+            context.thing_manager.clone(),
+            &context.function_manager,
+            &query,
+            QueryOptions::default(),
+        )
+        .unwrap();
+    let (iterator, ExecutionContext { snapshot, .. }) =
+        pipeline.into_rows_iterator(ExecutionInterrupt::new_uninterruptible()).unwrap();
+    let _ = iterator.count();
+    let snapshot = Arc::into_inner(snapshot).unwrap();
+    snapshot.commit().unwrap();
+
+    let query = typeql::parse_query("match $person isa person, has age $age; sort $age;").unwrap().into_pipeline();
+    for (memory_limit_bytes, exceeds_limit) in [(None, false), (Some(1), true)] {
+        let snapshot = Arc::new(context.storage.open_snapshot_read());
+        let pipeline = context
+            .query_manager
+            .prepare_read_pipeline(
+                snapshot,
+                &context.type_manager,
+                context.thing_manager.clone(),
+                &context.function_manager,
+                &query,
+                QueryOptions { memory_limit_bytes, ..QueryOptions::default() },
+            )
+            .unwrap();
+        let result = match pipeline.into_rows_iterator(ExecutionInterrupt::new_uninterruptible()) {
+            Ok((iterator, _)) => iterator.collect_owned(),
+            Err((err, _)) => Err(err),
+        };
+        if exceeds_limit {
+            assert_matches!(result.map_err(|err| *err), Err(PipelineExecutionError::MemoryLimit { .. }));
+        } else {
+            assert_eq!(result.unwrap().len(), 2);
+        }
+    }
+}
+
+#[test]
+fn test_explain() {
+    let context = setup_common();
+    let snapshot = context.storage.clone().open_snapshot_read();
+    let query = "match $person isa person, has age $age; limit 10;";
+    let match_ = typeql::parse_query(query).unwrap().into_pipeline();
+    let plan = context
+        .query_manager
+        .explain_pipeline(&snapshot, &context.type_manager, &context.thing_manager, &context.function_manager, &match_)
+        .unwrap();
+
+    assert_eq!(plan.stages.len(), 2);
+    assert_eq!(plan.stages[0].kind, "match");
+    assert_eq!(plan.stages[1].kind, "limit");
+    let match_plan = plan.stages[0].match_plan.as_ref().unwrap();
+    assert!(match_plan.variables.iter().any(|variable| variable.name == "$person"));
+    assert!(match_plan.variables.iter().any(|variable| variable.name == "$age"));
+    assert!(!match_plan.steps.is_empty());
+    assert!(plan.functions.is_empty());
+    assert!(plan.to_string().starts_with("Pipeline plan:"));
+    snapshot.close_resources()
+}
+
+#[test]
+fn test_explain_names_function_variables() {
+    let context = setup_common();
+    let snapshot = context.storage.clone().open_snapshot_read();
+    let query = r#"
+        with
+        fun get_ages($p_arg: person) -> { age }:
+        match
+            $p_arg has age $age_return;
+        return { $age_return };
+
+        match
+            $p isa person;
+            $z in get_ages($p);
+    "#;
+    let pipeline = typeql::parse_query(query).unwrap().into_pipeline();
+    let plan = context
+        .query_manager
+        .explain_pipeline(
+            &snapshot,
+            &context.type_manager,
+            &context.thing_manager,
+            &context.function_manager,
+            &pipeline,
+        )
+        .unwrap();
+
+    let match_plan = plan.stages[0].match_plan.as_ref().unwrap();
+    let call = match_plan.steps.iter().find(|step| step.kind == "function_call").unwrap();
+    assert_eq!(plan.functions.len(), 1);
+    assert_eq!(call.called_function.as_ref(), Some(&plan.functions[0].function_id));
+
+    let function_match = plan.functions[0].stages[0].match_plan.as_ref().unwrap();
+    assert!(function_match.variables.iter().any(|variable| variable.name == "$p_arg"));
+    assert!(function_match.variables.iter().any(|variable| variable.name == "$age_return"));
+    assert!(plan.to_string().contains(&format!("Function {}", plan.functions[0].function_id)));
+    snapshot.close_resources()
+}
+
+#[test]
+fn test_explain_nested_patterns() {
+    let context = setup_common();
+    let snapshot = context.storage.clone().open_snapshot_read();
+    let query = r#"
+        match
+            $p isa person;
+            { $p has age 10; } or { $p has name "Alice"; };
+            not { $p has age 5; };
+    "#;
+    let pipeline = typeql::parse_query(query).unwrap().into_pipeline();
+    let plan = context
+        .query_manager
+        .explain_pipeline(
+            &snapshot,
+            &context.type_manager,
+            &context.thing_manager,
+            &context.function_manager,
+            &pipeline,
+        )
+        .unwrap();
+
+    let match_plan = plan.stages[0].match_plan.as_ref().unwrap();
+    let disjunction = match_plan.steps.iter().find(|step| step.kind == "disjunction").unwrap();
+    assert_eq!(disjunction.nested.len(), 2);
+    assert!(disjunction.nested.iter().all(|branch| !branch.steps.is_empty()));
+    let negation = match_plan.steps.iter().find(|step| step.kind == "negation").unwrap();
+    assert_eq!(negation.nested.len(), 1);
+
+    let rendered = plan.to_string();
+    assert!(rendered.contains("Branch 0:"));
+    assert!(rendered.contains("Branch 1:"));
+    snapshot.close_resources()
+}
+
+#[test]
+fn test_explain_write_pipeline_is_not_executed() {
+    let context = setup_common();
+    let snapshot = context.storage.clone().open_snapshot_write();
+    let query = "insert $p isa person, has age 10;";
+    let pipeline = typeql::parse_query(query).unwrap().into_pipeline();
+    let plan = context
+        .query_manager
+        .explain_pipeline(
+            &snapshot,
+            &context.type_manager,
+            &context.thing_manager,
+            &context.function_manager,
+            &pipeline,
+        )
+        .unwrap();
+
+    assert_eq!(plan.stages.len(), 1);
+    assert_eq!(plan.stages[0].kind, "insert");
+    assert!(!plan.stages[0].details.is_empty());
+    let age_type = context.type_manager.get_attribute_type(&snapshot, &AGE_LABEL).unwrap().unwrap();
+    assert!(context.thing_manager.get_attribute_with_value(&snapshot, age_type, Value::Long(10)).unwrap().is_none());
+    snapshot.close_resources()
+}
+
+#[test]
+fn test_explain_reports_role_player_index() {
+    let context = setup_common();
+    let snapshot = context.storage.clone().open_snapshot_read();
+    let query = "match $m (member: $p, group: $o) isa membership;";
+    let pipeline = typeql::parse_query(query).unwrap().into_pipeline();
+    let plan = context
+        .query_manager
+        .explain_pipeline(
+            &snapshot,
+            &context.type_manager,
+            &context.thing_manager,
+            &context.function_manager,
+            &pipeline,
+        )
+        .unwrap();
+
+    // the relation is returned, so its role players are read through the links edges rather than the index
+    let match_plan = plan.stages[0].match_plan.as_ref().unwrap();
+    assert!(!match_plan.steps.is_empty());
+    assert!(match_plan.steps.iter().all(|step| !step.role_player_index));
+    assert!(!plan.to_string().contains("[rp_index]"));
+    snapshot.close_resources()
+}
+
+#[test]
+fn test_explain_unknown_type_fails() {
+    let context = setup_common();
+    let snapshot = context.storage.clone().open_snapshot_read();
+    let pipeline = typeql::parse_query("match $x isa spaceship;").unwrap().into_pipeline();
+    let result = context.query_manager.explain_pipeline(
+        &snapshot,
+        &context.type_manager,
+        &context.thing_manager,
+        &context.function_manager,
+        &pipeline,
+    );
+    assert!(result.is_err());
+    snapshot.close_resources()
+}
@@ -22,6 +22,7 @@ use concept::{
 };
 use encoding::value::{label::Label, value::Value, value_type::ValueType};
 use executor::{
+    memory::QueryMemory,
     pipeline::{
         delete::DeleteStageExecutor,
         insert::InsertStageExecutor,
@@ -192,6 +193,7 @@ fn execute_insert<Snapshot: WritableSnapshot + 'static>(
             thing_manager,
             parameters: Arc::new(value_parameters),
             profile: Arc::new(QueryProfile::new(false)),
+            memory: Arc::new(QueryMemory::default()),
         },
     );
     let insert_executor = InsertStageExecutor::new(Arc::new(insert_plan), initial);
@@ -278,6 +280,7 @@ fn execute_delete<Snapshot: WritableSnapshot + 'static>(
             thing_manager,
             parameters: Arc::new(value_parameters),
             profile: Arc::new(QueryProfile::new(false)),
+            memory: Arc::new(QueryMemory::default()),
         },
     );
     let delete_executor = DeleteStageExecutor::new(Arc::new(delete_plan), initial);
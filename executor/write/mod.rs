@@ -6,11 +6,13 @@
 
 use concept::error::ConceptWriteError;
 use error::typedb_error;
+use storage::snapshot::SnapshotError;
 
 pub(crate) mod write_instruction;
 
 typedb_error!(
     pub WriteError(component = "Write execution", prefix = "WEX") {
         ConceptWrite(1, "Write execution failed due to a concept write error.", (typedb_source : Box<ConceptWriteError>)),
+        WriteBuffer(2, "Write execution failed as the transaction can buffer no more writes.", (typedb_source : SnapshotError)),
     }
 );
@@ -0,0 +1,94 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{io, sync::Arc};
+
+use concept::error::{ConceptReadError, ConceptWriteError};
+use database::transaction::{BulkLoadCommitError, DataCommitError, TransactionError};
+use encoding::value::value_type::ValueType;
+use error::typedb_error;
+
+typedb_error!(
+    pub ImportError(component = "Import", prefix = "IMP") {
+        MappingRead(1, "Failed to read the mapping file '{path}'.", path: String, ( source: Arc<io::Error> )),
+        MappingParse(
+            2,
+            "The mapping file '{path}' is not a valid mapping.",
+            path: String,
+            ( source: Arc<serde_json::Error> )
+        ),
+        SourceThingType(3, "The mapping of '{file}' must name exactly one of 'entity' or 'relation'.", file: String),
+        UnrecognisedSourceFormat(
+            4,
+            "The format of '{file}' is not given, and its extension is not one of '.csv', '.ndjson' or '.jsonl'.",
+            file: String
+        ),
+        InvalidDelimiter(5, "The delimiter of '{file}' must be a single ASCII character.", file: String),
+        PlayersRequireRelation(
+            6,
+            "The mapping of '{file}' has role players, which only relations can have.",
+            file: String
+        ),
+        TypeNotFound(7, "Type '{label}' not found.", label: String),
+        RoleNotFound(8, "Relation type '{relation}' does not relate a role '{role}'.", relation: String, role: String),
+        AttributeTypeWithoutValueType(9, "Attribute type '{label}' has no value type.", label: String),
+        StructAttributeType(
+            10,
+            "Attribute type '{label}' holds struct values, which cannot be imported from a column.",
+            label: String
+        ),
+        ColumnNotFound(11, "Column '{column}' is not in the header of '{file}'.", file: String, column: String),
+        SourceRead(12, "Failed to read '{file}'.", file: String, ( source: Arc<io::Error> )),
+        CsvRead(13, "Failed to read the CSV file '{file}'.", file: String, ( source: Arc<csv::Error> )),
+        ProgressRead(14, "Failed to read the progress file '{path}'.", path: String, ( source: Arc<io::Error> )),
+        ProgressParse(15, "The progress file '{path}' is not valid.", path: String, ( source: Arc<serde_json::Error> )),
+        ProgressBatchSizeMismatch(
+            16,
+            "The progress file was written with a batch size of {recorded}, but the batch size is {batch_size}. Resuming requires the same batch size.",
+            recorded: usize,
+            batch_size: usize
+        ),
+        ProgressWrite(17, "Failed to write the progress file '{path}'.", path: String, ( source: Arc<io::Error> )),
+        RejectedWrite(18, "Failed to write the rejected rows file '{path}'.", path: String, ( source: Arc<io::Error> )),
+        TransactionOpen(19, "Failed to open a transaction.", ( typedb_source: TransactionError )),
+        ConceptRead(20, "Failed to read the schema.", ( source: Box<ConceptReadError> )),
+        BulkLoad(21, "Failed to ingest a bulk-loaded batch.", ( typedb_source: BulkLoadCommitError )),
+    }
+);
+
+typedb_error!(
+    pub RowError(component = "Import row", prefix = "IMR") {
+        InvalidJsonRecord(1, "The record is not a JSON object.", ( source: Arc<serde_json::Error> )),
+        InvalidCsvRecord(2, "The record is not valid CSV.", ( source: Arc<csv::Error> )),
+        InvalidValue(
+            3,
+            "Column '{column}' holds '{value}', which is not a valid {value_type} value.",
+            column: String,
+            value: String,
+            value_type: ValueType
+        ),
+        PlayerNotFound(
+            4,
+            "No '{player_type}' has the '{key}' '{value}' given in column '{column}'.",
+            player_type: String,
+            key: String,
+            value: String,
+            column: String
+        ),
+        PlayerNotUnique(
+            5,
+            "More than one '{player_type}' has the '{key}' '{value}' given in column '{column}'.",
+            player_type: String,
+            key: String,
+            value: String,
+            column: String
+        ),
+        ConceptRead(6, "Failed to read the data the record refers to.", ( source: Box<ConceptReadError> )),
+        ConceptWrite(7, "Failed to write the record.", ( typedb_source: Box<ConceptWriteError> )),
+        Commit(8, "Failed to commit the record.", ( typedb_source: DataCommitError )),
+        BulkLoad(9, "Failed to validate the record for bulk loading.", ( typedb_source: BulkLoadCommitError )),
+    }
+);
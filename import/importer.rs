@@ -0,0 +1,409 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use concept::thing::thing_manager::ThingManager;
+use database::{
+    transaction::{BulkLoadCommitError, DataCommitError, TransactionBulkLoad, TransactionRead, TransactionWrite},
+    Database,
+};
+use options::TransactionOptions;
+use rand::Rng;
+use resource::constants::{
+    import::{DEFAULT_IMPORT_BATCH_SIZE, DEFAULT_IMPORT_WORKERS, IMPORT_COMMIT_CONFLICT_RETRIES},
+    server::{COMMIT_RETRY_BACKOFF_BASE_MILLIS, COMMIT_RETRY_BACKOFF_MAX_MILLIS},
+};
+use storage::{durability_client::DurabilityClient, snapshot::WritableSnapshot};
+use tracing::{event, Level};
+
+use crate::{
+    error::{ImportError, RowError},
+    insert::insert_record,
+    mapping::{Mapping, ResolvedSource},
+    progress::{Progress, RejectedLog},
+    source::{SourceReader, SourceRecord},
+};
+
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// Records committed together in one write transaction
+    pub batch_size: usize,
+    /// Write transactions committing batches in parallel. Bulk loads ingest their batches one after another.
+    pub workers: usize,
+    /// Load through a bulk-load transaction, ingesting each batch into storage without logging its writes, instead
+    /// of committing write transactions. This holds the schema lock for the whole import, and is not atomic per
+    /// source: batches ingested before a failure remain loaded, as progress files record.
+    pub bulk_load: bool,
+    /// Committed batches are recorded in this file, and batches it already records are skipped, so an interrupted
+    /// import can be resumed with the same mapping and batch size
+    pub progress_file: Option<PathBuf>,
+    /// Rejected rows are appended to this file as JSON lines
+    pub rejected_file: Option<PathBuf>,
+    pub transaction_options: TransactionOptions,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: DEFAULT_IMPORT_BATCH_SIZE,
+            workers: DEFAULT_IMPORT_WORKERS,
+            bulk_load: false,
+            progress_file: None,
+            rejected_file: None,
+            transaction_options: TransactionOptions::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub imported_rows: u64,
+    /// Rows of batches committed by an earlier, interrupted import
+    pub skipped_rows: u64,
+    pub rejected: Vec<RejectedRow>,
+}
+
+#[derive(Debug)]
+pub struct RejectedRow {
+    pub file: String,
+    pub line: u64,
+    pub error: RowError,
+}
+
+/// Imports records into a database through batched write transactions, committed in parallel.
+///
+/// A record that fails to be written is rejected and the rest of its batch is written again without it. When a batch
+/// fails to commit, as commit-time validation cannot tell which record is at fault, its records are committed one at
+/// a time so only the invalid ones are rejected. Bulk loads reject records the same way, validating and ingesting each
+/// batch in turn.
+#[derive(Debug)]
+pub struct Importer<D> {
+    database: Arc<Database<D>>,
+    options: ImportOptions,
+}
+
+struct Batch {
+    index: usize,
+    records: Vec<SourceRecord>,
+}
+
+struct ImportState {
+    progress: Progress,
+    rejected_log: RejectedLog,
+    report: ImportReport,
+}
+
+impl<D: DurabilityClient + Send + Sync + 'static> Importer<D> {
+    pub fn new(database: Arc<Database<D>>, options: ImportOptions) -> Self {
+        Self { database, options }
+    }
+
+    pub fn import(&self, mapping: &Mapping) -> Result<ImportReport, ImportError> {
+        let sources = self.resolve(mapping)?;
+        let state = Mutex::new(ImportState {
+            progress: Progress::load(self.options.progress_file.as_deref(), self.options.batch_size)?,
+            rejected_log: RejectedLog::open(self.options.rejected_file.as_deref())?,
+            report: ImportReport::default(),
+        });
+        // sources are imported one after another, so relations find the role players of earlier sources committed
+        if self.options.bulk_load {
+            let mut transaction =
+                TransactionBulkLoad::open(self.database.clone(), self.options.transaction_options.clone())
+                    .map_err(|typedb_source| ImportError::TransactionOpen { typedb_source })?;
+            let result = sources.iter().try_for_each(|source| self.bulk_load_source(source, &mut transaction, &state));
+            // every batch is ingested or rolled back by now, so closing loses no writes
+            transaction.close();
+            result?;
+        } else {
+            for source in &sources {
+                self.import_source(source, &state)?;
+            }
+        }
+        Ok(state.into_inner().unwrap().report)
+    }
+
+    fn resolve(&self, mapping: &Mapping) -> Result<Vec<ResolvedSource>, ImportError> {
+        let transaction = TransactionRead::open(self.database.clone(), self.options.transaction_options.clone())
+            .map_err(|typedb_source| ImportError::TransactionOpen { typedb_source })?;
+        let sources = mapping
+            .sources
+            .iter()
+            .map(|source| ResolvedSource::resolve(source, transaction.snapshot(), &transaction.type_manager))
+            .collect();
+        transaction.close();
+        sources
+    }
+
+    fn import_source(&self, source: &ResolvedSource, state: &Mutex<ImportState>) -> Result<(), ImportError> {
+        let file = source.mapping.file_name();
+        let mut reader = SourceReader::open(source)?;
+        event!(Level::INFO, "Importing '{file}'.");
+
+        let (sender, receiver) = mpsc::sync_channel::<Batch>(self.options.workers);
+        let receiver = Mutex::new(receiver);
+        let failed = AtomicBool::new(false);
+        thread::scope(|scope| {
+            let workers = (0..self.options.workers.max(1))
+                .map(|_| {
+                    scope.spawn(|| -> Result<(), ImportError> {
+                        let mut result = Ok(());
+                        loop {
+                            let received = receiver.lock().unwrap().recv();
+                            let Ok(batch) = received else { break };
+                            // after a failure, batches are still drained so the reader is never blocked
+                            if result.is_ok() {
+                                result = self.import_batch(source, &file, batch, state);
+                                failed.fetch_or(result.is_err(), Ordering::Relaxed);
+                            }
+                        }
+                        result
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let read_result = (|| -> Result<(), ImportError> {
+                let batch_size = self.options.batch_size.max(1);
+                let mut batch = Batch { index: 0, records: Vec::with_capacity(batch_size) };
+                while !failed.load(Ordering::Relaxed) {
+                    match reader.next().transpose()? {
+                        Some(record) => batch.records.push(record),
+                        None if batch.records.is_empty() => break,
+                        None => {
+                            self.send_batch(&file, batch, &sender, state);
+                            break;
+                        }
+                    }
+                    if batch.records.len() == batch_size {
+                        let next = Batch { index: batch.index + 1, records: Vec::with_capacity(batch_size) };
+                        self.send_batch(&file, std::mem::replace(&mut batch, next), &sender, state);
+                    }
+                }
+                Ok(())
+            })();
+            drop(sender);
+
+            let worker_results = workers.into_iter().map(|worker| worker.join().unwrap()).collect::<Vec<_>>();
+            read_result.and(worker_results.into_iter().collect())
+        })
+    }
+
+    fn bulk_load_source(
+        &self,
+        source: &ResolvedSource,
+        transaction: &mut TransactionBulkLoad<D>,
+        state: &Mutex<ImportState>,
+    ) -> Result<(), ImportError> {
+        let file = source.mapping.file_name();
+        let mut reader = SourceReader::open(source)?;
+        event!(Level::INFO, "Bulk loading '{file}'.");
+
+        let batch_size = self.options.batch_size.max(1);
+        let mut batch = Batch { index: 0, records: Vec::with_capacity(batch_size) };
+        loop {
+            let record = reader.next().transpose()?;
+            let is_last = record.is_none();
+            batch.records.extend(record);
+            if batch.records.len() == batch_size || (is_last && !batch.records.is_empty()) {
+                let next = Batch { index: batch.index + 1, records: Vec::with_capacity(batch_size) };
+                let batch = std::mem::replace(&mut batch, next);
+                let mut locked_state = state.lock().unwrap();
+                if locked_state.progress.is_completed(&file, batch.index) {
+                    locked_state.report.skipped_rows += batch.records.len() as u64;
+                } else {
+                    drop(locked_state);
+                    self.bulk_load_batch(source, &file, transaction, batch, state)?;
+                }
+            }
+            if is_last {
+                return Ok(());
+            }
+        }
+    }
+
+    fn send_batch(&self, file: &str, batch: Batch, sender: &mpsc::SyncSender<Batch>, state: &Mutex<ImportState>) {
+        let mut state = state.lock().unwrap();
+        if state.progress.is_completed(file, batch.index) {
+            state.report.skipped_rows += batch.records.len() as u64;
+        } else {
+            drop(state);
+            // the receiver is only dropped once all batches are sent
+            sender.send(batch).unwrap();
+        }
+    }
+
+    fn import_batch(
+        &self,
+        source: &ResolvedSource,
+        file: &str,
+        batch: Batch,
+        state: &Mutex<ImportState>,
+    ) -> Result<(), ImportError> {
+        let mut rejected = Vec::new();
+        let mut records = Vec::with_capacity(batch.records.len());
+        for record in batch.records {
+            match record.fields {
+                Ok(fields) => records.push((record.line, fields)),
+                Err(error) => rejected.push(RejectedRow { file: file.to_owned(), line: record.line, error }),
+            }
+        }
+        let imported = self.commit_records(source, file, records, &mut rejected)?;
+        Self::complete_batch(file, batch.index, imported, rejected, state)
+    }
+
+    fn bulk_load_batch(
+        &self,
+        source: &ResolvedSource,
+        file: &str,
+        transaction: &mut TransactionBulkLoad<D>,
+        batch: Batch,
+        state: &Mutex<ImportState>,
+    ) -> Result<(), ImportError> {
+        let mut rejected = Vec::new();
+        let mut records = Vec::with_capacity(batch.records.len());
+        for record in batch.records {
+            match record.fields {
+                Ok(fields) => records.push((record.line, fields)),
+                Err(error) => rejected.push(RejectedRow { file: file.to_owned(), line: record.line, error }),
+            }
+        }
+        let imported = Self::ingest_records(transaction, source, file, records, &mut rejected)?;
+        Self::complete_batch(file, batch.index, imported, rejected, state)
+    }
+
+    fn complete_batch(
+        file: &str,
+        index: usize,
+        imported: u64,
+        rejected: Vec<RejectedRow>,
+        state: &Mutex<ImportState>,
+    ) -> Result<(), ImportError> {
+        let mut state = state.lock().unwrap();
+        // rejections are recorded before the batch is completed, so resuming may repeat but never lose them
+        state.rejected_log.append(&rejected)?;
+        state.progress.complete(file, index)?;
+        state.report.imported_rows += imported;
+        state.report.rejected.extend(rejected);
+        Ok(())
+    }
+
+    fn commit_records(
+        &self,
+        source: &ResolvedSource,
+        file: &str,
+        mut records: Vec<(u64, HashMap<String, String>)>,
+        rejected: &mut Vec<RejectedRow>,
+    ) -> Result<u64, ImportError> {
+        let mut conflicts = 0;
+        loop {
+            let mut transaction =
+                TransactionWrite::open(self.database.clone(), self.options.transaction_options.clone())
+                    .map_err(|typedb_source| ImportError::TransactionOpen { typedb_source })?;
+            while let Err((index, error)) =
+                Self::insert_records(&mut transaction.snapshot, &transaction.thing_manager, source, &records)
+            {
+                transaction.rollback();
+                let (line, _) = records.remove(index);
+                rejected.push(RejectedRow { file: file.to_owned(), line, error });
+            }
+            if records.is_empty() {
+                transaction.close();
+                return Ok(0);
+            }
+
+            match transaction.commit() {
+                Ok(()) => return Ok(records.len() as u64),
+                Err(DataCommitError::IsolationConflict { .. }) if conflicts < IMPORT_COMMIT_CONFLICT_RETRIES => {
+                    conflicts += 1;
+                    thread::sleep(commit_retry_backoff(conflicts));
+                }
+                Err(typedb_source) if records.len() == 1 => {
+                    let (line, _) = records.pop().unwrap();
+                    rejected.push(RejectedRow {
+                        file: file.to_owned(),
+                        line,
+                        error: RowError::Commit { typedb_source },
+                    });
+                    return Ok(0);
+                }
+                Err(_) => {
+                    let mut imported = 0;
+                    for record in records {
+                        imported += self.commit_records(source, file, vec![record], rejected)?;
+                    }
+                    return Ok(imported);
+                }
+            }
+        }
+    }
+
+    // As for committed batches, a batch failing validation is ingested again one record at a time
+    fn ingest_records(
+        transaction: &mut TransactionBulkLoad<D>,
+        source: &ResolvedSource,
+        file: &str,
+        mut records: Vec<(u64, HashMap<String, String>)>,
+        rejected: &mut Vec<RejectedRow>,
+    ) -> Result<u64, ImportError> {
+        while let Err((index, error)) =
+            Self::insert_records(&mut transaction.snapshot, &transaction.thing_manager, source, &records)
+        {
+            transaction.rollback();
+            let (line, _) = records.remove(index);
+            rejected.push(RejectedRow { file: file.to_owned(), line, error });
+        }
+        if records.is_empty() {
+            return Ok(0);
+        }
+
+        match transaction.ingest_batch() {
+            Ok(()) => Ok(records.len() as u64),
+            Err(typedb_source @ BulkLoadCommitError::ConceptWriteErrorsFirst { .. }) if records.len() == 1 => {
+                transaction.rollback();
+                let (line, _) = records.pop().unwrap();
+                rejected.push(RejectedRow { file: file.to_owned(), line, error: RowError::BulkLoad { typedb_source } });
+                Ok(0)
+            }
+            Err(BulkLoadCommitError::ConceptWriteErrorsFirst { .. }) => {
+                transaction.rollback();
+                let mut imported = 0;
+                for record in records {
+                    imported += Self::ingest_records(transaction, source, file, vec![record], rejected)?;
+                }
+                Ok(imported)
+            }
+            Err(typedb_source) => Err(ImportError::BulkLoad { typedb_source }),
+        }
+    }
+
+    fn insert_records(
+        snapshot: &mut Arc<impl WritableSnapshot>,
+        thing_manager: &ThingManager,
+        source: &ResolvedSource,
+        records: &[(u64, HashMap<String, String>)],
+    ) -> Result<(), (usize, RowError)> {
+        let snapshot = Arc::get_mut(snapshot).unwrap();
+        for (index, (_, fields)) in records.iter().enumerate() {
+            insert_record(snapshot, thing_manager, source, fields).map_err(|error| (index, error))?;
+        }
+        Ok(())
+    }
+}
+
+// Full jitter, as for retried transaction commits
+fn commit_retry_backoff(attempt: u32) -> Duration {
+    let ceiling = (COMMIT_RETRY_BACKOFF_BASE_MILLIS << attempt.min(16)).min(COMMIT_RETRY_BACKOFF_MAX_MILLIS);
+    Duration::from_millis(rand::thread_rng().gen_range(0..=ceiling))
+}
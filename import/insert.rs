@@ -0,0 +1,135 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{borrow::Cow, collections::HashMap, str::FromStr};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
+use concept::thing::{
+    object::{Object, ObjectAPI},
+    thing_manager::ThingManager,
+};
+use encoding::value::{
+    decimal_value::Decimal, duration_value::Duration, timezone::TimeZone, value::Value, value_type::ValueType,
+};
+use storage::snapshot::WritableSnapshot;
+
+use crate::{
+    error::RowError,
+    mapping::{ResolvedPlayer, ResolvedSource, ResolvedThingType},
+};
+
+/// Writes the instance a record describes. Empty and missing columns are skipped, so a record missing a role player
+/// or an attribute its type requires is rejected by the cardinality validation on commit.
+pub(crate) fn insert_record(
+    snapshot: &mut impl WritableSnapshot,
+    thing_manager: &ThingManager,
+    source: &ResolvedSource,
+    fields: &HashMap<String, String>,
+) -> Result<(), RowError> {
+    let field = |column: &str| fields.get(column).filter(|text| !text.is_empty());
+
+    let object = match source.thing_type {
+        ResolvedThingType::Entity(entity_type) => Object::Entity(
+            thing_manager
+                .create_entity(snapshot, entity_type)
+                .map_err(|typedb_source| RowError::ConceptWrite { typedb_source })?,
+        ),
+        ResolvedThingType::Relation(relation_type) => Object::Relation(
+            thing_manager
+                .create_relation(snapshot, relation_type)
+                .map_err(|typedb_source| RowError::ConceptWrite { typedb_source })?,
+        ),
+    };
+
+    for attribute in &source.attributes {
+        let Some(text) = field(&attribute.column) else { continue };
+        let value = parse_value(&attribute.column, text, &attribute.value_type)?;
+        let inserted = thing_manager
+            .create_attribute(snapshot, attribute.attribute_type, value)
+            .map_err(|typedb_source| RowError::ConceptWrite { typedb_source })?;
+        object
+            .set_has_unordered(snapshot, thing_manager, &inserted)
+            .map_err(|typedb_source| RowError::ConceptWrite { typedb_source })?;
+    }
+
+    if let Object::Relation(relation) = object {
+        for player in &source.players {
+            let Some(text) = field(&player.column) else { continue };
+            let player_object = find_player(snapshot, thing_manager, player, text)?;
+            relation
+                .add_player(snapshot, thing_manager, player.role_type, player_object)
+                .map_err(|typedb_source| RowError::ConceptWrite { typedb_source })?;
+        }
+    }
+    Ok(())
+}
+
+fn find_player(
+    snapshot: &impl WritableSnapshot,
+    thing_manager: &ThingManager,
+    player: &ResolvedPlayer,
+    text: &str,
+) -> Result<Object, RowError> {
+    let not_found = || RowError::PlayerNotFound {
+        player_type: player.player_label.clone(),
+        key: player.key_label.clone(),
+        value: text.to_owned(),
+        column: player.column.clone(),
+    };
+    let key = parse_value(&player.column, text, &player.key_value_type)?;
+    let attribute = thing_manager
+        .get_attribute_with_value(snapshot, player.key_type, key)
+        .map_err(|source| RowError::ConceptRead { source })?
+        .ok_or_else(not_found)?;
+    let mut owners = attribute.get_owners_by_type(snapshot, thing_manager, player.player_type);
+    let (owner, _) =
+        owners.next().transpose().map_err(|source| RowError::ConceptRead { source })?.ok_or_else(not_found)?;
+    match owners.next().transpose().map_err(|source| RowError::ConceptRead { source })? {
+        None => Ok(owner),
+        Some(_) => Err(RowError::PlayerNotUnique {
+            player_type: player.player_label.clone(),
+            key: player.key_label.clone(),
+            value: text.to_owned(),
+            column: player.column.clone(),
+        }),
+    }
+}
+
+/// Fields are read in the syntax of TypeQL literals, except that datetimes with a fixed offset are also read in
+/// RFC 3339 syntax
+fn parse_value(column: &str, text: &str, value_type: &ValueType) -> Result<Value<'static>, RowError> {
+    let value = match value_type {
+        ValueType::Boolean => text.parse().ok().map(Value::Boolean),
+        ValueType::Long => text.parse().ok().map(Value::Long),
+        ValueType::Double => text.parse().ok().map(Value::Double),
+        ValueType::Decimal => Decimal::from_str(text.trim_end_matches("dec")).ok().map(Value::Decimal),
+        ValueType::Date => NaiveDate::parse_from_str(text, "%Y-%m-%d").ok().map(Value::Date),
+        ValueType::DateTime => NaiveDateTime::from_str(text).ok().map(Value::DateTime),
+        ValueType::DateTimeTZ => parse_datetime_tz(text).map(Value::DateTimeTZ),
+        ValueType::Duration => Duration::from_str(text).ok().map(Value::Duration),
+        ValueType::String => Some(Value::String(Cow::Owned(text.to_owned()))),
+        ValueType::Struct(_) => None,
+    };
+    value.ok_or_else(|| RowError::InvalidValue {
+        column: column.to_owned(),
+        value: text.to_owned(),
+        value_type: value_type.clone(),
+    })
+}
+
+fn parse_datetime_tz(text: &str) -> Option<DateTime<TimeZone>> {
+    match text.split_once(' ') {
+        Some((datetime, zone)) => {
+            let timezone = TimeZone::IANA(Tz::from_str_insensitive(zone).ok()?);
+            NaiveDateTime::from_str(datetime).ok()?.and_local_timezone(timezone).single()
+        }
+        None => {
+            let datetime = DateTime::parse_from_rfc3339(text).ok()?;
+            Some(datetime.with_timezone(&TimeZone::Fixed(*datetime.offset())))
+        }
+    }
+}
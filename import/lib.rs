@@ -0,0 +1,48 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Import CSV and newline-delimited JSON files into a database, as described by a [`Mapping`]:
+//!
+//! ```json
+//! {
+//!   "sources": [
+//!     {
+//!       "file": "people.csv",
+//!       "entity": "person",
+//!       "attributes": [{ "column": "email", "attribute": "email" }, { "column": "name", "attribute": "name" }]
+//!     },
+//!     {
+//!       "file": "employments.ndjson",
+//!       "relation": "employment",
+//!       "attributes": [{ "column": "since", "attribute": "start-date" }],
+//!       "players": [
+//!         { "role": "employee", "column": "employee", "player": "person", "key": "email" },
+//!         { "role": "employer", "column": "company", "player": "company", "key": "name" }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Records are written through the `ThingManager` in batched write transactions, so they are validated as inserted
+//! data is. Records failing validation are rejected and reported with the error, and the rest are committed. Large
+//! loads can instead be ingested through a bulk-load transaction, which skips the write-ahead log.
+
+#![deny(unused_must_use)]
+#![deny(elided_lifetimes_in_paths)]
+
+pub use crate::{
+    error::{ImportError, RowError},
+    importer::{ImportOptions, ImportReport, Importer, RejectedRow},
+    mapping::{AttributeMapping, Mapping, PlayerMapping, SourceFormat, SourceMapping},
+};
+
+pub mod error;
+mod importer;
+mod insert;
+pub mod mapping;
+mod progress;
+mod source;
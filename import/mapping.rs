@@ -0,0 +1,257 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use concept::type_::{
+    attribute_type::AttributeType, entity_type::EntityType, object_type::ObjectType, relation_type::RelationType,
+    role_type::RoleType, type_manager::TypeManager,
+};
+use encoding::value::{label::Label, value_type::ValueType};
+use serde::Deserialize;
+use storage::snapshot::ReadableSnapshot;
+
+use crate::error::ImportError;
+
+/// Describes how the records of each source file become instances. Sources are imported in order, so relations can
+/// look up role players imported from earlier sources.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mapping {
+    pub sources: Vec<SourceMapping>,
+}
+
+/// Each record of the file creates one instance of `entity` or `relation`, owning an attribute for each non-empty
+/// mapped column. Relations get a role player for each non-empty player column, found by a key attribute.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceMapping {
+    pub file: PathBuf,
+    /// Inferred from the file extension when not given
+    pub format: Option<SourceFormat>,
+    /// Field delimiter of CSV files, which defaults to a comma
+    pub delimiter: Option<char>,
+    pub entity: Option<String>,
+    pub relation: Option<String>,
+    #[serde(default)]
+    pub attributes: Vec<AttributeMapping>,
+    #[serde(default)]
+    pub players: Vec<PlayerMapping>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceFormat {
+    /// Comma separated values, with a header naming the columns
+    Csv,
+    /// One JSON object per line, whose fields are the columns
+    Ndjson,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributeMapping {
+    pub column: String,
+    pub attribute: String,
+}
+
+/// The player of `role` is the unique instance of `player` owning the `key` attribute with the column's value
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerMapping {
+    pub role: String,
+    pub column: String,
+    pub player: String,
+    pub key: String,
+}
+
+impl Mapping {
+    /// Read a mapping from a JSON file. Relative source paths are resolved against the directory of the mapping.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ImportError> {
+        let path = path.as_ref();
+        let contents = fs::read(path).map_err(|source| ImportError::MappingRead {
+            path: path.display().to_string(),
+            source: Arc::new(source),
+        })?;
+        let mut mapping: Mapping = serde_json::from_slice(&contents).map_err(|source| ImportError::MappingParse {
+            path: path.display().to_string(),
+            source: Arc::new(source),
+        })?;
+        if let Some(directory) = path.parent() {
+            for source in &mut mapping.sources {
+                source.file = directory.join(&source.file);
+            }
+        }
+        Ok(mapping)
+    }
+}
+
+impl SourceMapping {
+    pub(crate) fn file_name(&self) -> String {
+        self.file.display().to_string()
+    }
+
+    pub(crate) fn source_format(&self) -> Result<SourceFormat, ImportError> {
+        if let Some(format) = self.format {
+            return Ok(format);
+        }
+        match self.file.extension().and_then(|extension| extension.to_str()) {
+            Some("csv") => Ok(SourceFormat::Csv),
+            Some("ndjson" | "jsonl") => Ok(SourceFormat::Ndjson),
+            _ => Err(ImportError::UnrecognisedSourceFormat { file: self.file_name() }),
+        }
+    }
+
+    pub(crate) fn csv_delimiter(&self) -> Result<u8, ImportError> {
+        match self.delimiter {
+            None => Ok(b','),
+            Some(delimiter) if delimiter.is_ascii() => Ok(delimiter as u8),
+            Some(_) => Err(ImportError::InvalidDelimiter { file: self.file_name() }),
+        }
+    }
+
+    /// The columns records must have a header for
+    pub(crate) fn columns(&self) -> impl Iterator<Item = &str> {
+        let attribute_columns = self.attributes.iter().map(|attribute| attribute.column.as_str());
+        attribute_columns.chain(self.players.iter().map(|player| player.column.as_str()))
+    }
+}
+
+/// A source mapping with its labels resolved against the schema
+#[derive(Debug)]
+pub(crate) struct ResolvedSource {
+    pub(crate) mapping: SourceMapping,
+    pub(crate) format: SourceFormat,
+    pub(crate) thing_type: ResolvedThingType,
+    pub(crate) attributes: Vec<ResolvedAttribute>,
+    pub(crate) players: Vec<ResolvedPlayer>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ResolvedThingType {
+    Entity(EntityType),
+    Relation(RelationType),
+}
+
+#[derive(Debug)]
+pub(crate) struct ResolvedAttribute {
+    pub(crate) column: String,
+    pub(crate) attribute_type: AttributeType,
+    pub(crate) value_type: ValueType,
+}
+
+#[derive(Debug)]
+pub(crate) struct ResolvedPlayer {
+    pub(crate) column: String,
+    pub(crate) role_type: RoleType,
+    pub(crate) player_label: String,
+    pub(crate) player_type: ObjectType,
+    pub(crate) key_label: String,
+    pub(crate) key_type: AttributeType,
+    pub(crate) key_value_type: ValueType,
+}
+
+impl ResolvedSource {
+    pub(crate) fn resolve(
+        mapping: &SourceMapping,
+        snapshot: &impl ReadableSnapshot,
+        type_manager: &TypeManager,
+    ) -> Result<Self, ImportError> {
+        let format = mapping.source_format()?;
+        let thing_type = match (&mapping.entity, &mapping.relation) {
+            (Some(entity), None) => ResolvedThingType::Entity(
+                type_manager
+                    .get_entity_type(snapshot, &Label::build(entity))
+                    .map_err(|source| ImportError::ConceptRead { source })?
+                    .ok_or_else(|| ImportError::TypeNotFound { label: entity.clone() })?,
+            ),
+            (None, Some(relation)) => ResolvedThingType::Relation(
+                type_manager
+                    .get_relation_type(snapshot, &Label::build(relation))
+                    .map_err(|source| ImportError::ConceptRead { source })?
+                    .ok_or_else(|| ImportError::TypeNotFound { label: relation.clone() })?,
+            ),
+            _ => return Err(ImportError::SourceThingType { file: mapping.file_name() }),
+        };
+
+        let attributes = mapping
+            .attributes
+            .iter()
+            .map(|attribute| {
+                let (attribute_type, value_type) =
+                    resolve_attribute_type(&attribute.attribute, snapshot, type_manager)?;
+                Ok(ResolvedAttribute { column: attribute.column.clone(), attribute_type, value_type })
+            })
+            .collect::<Result<Vec<_>, ImportError>>()?;
+
+        let players = match thing_type {
+            ResolvedThingType::Relation(relation_type) => mapping
+                .players
+                .iter()
+                .map(|player| resolve_player(player, relation_type, mapping, snapshot, type_manager))
+                .collect::<Result<Vec<_>, ImportError>>()?,
+            ResolvedThingType::Entity(_) if mapping.players.is_empty() => Vec::new(),
+            ResolvedThingType::Entity(_) => {
+                return Err(ImportError::PlayersRequireRelation { file: mapping.file_name() });
+            }
+        };
+
+        Ok(Self { mapping: mapping.clone(), format, thing_type, attributes, players })
+    }
+}
+
+fn resolve_player(
+    player: &PlayerMapping,
+    relation_type: RelationType,
+    mapping: &SourceMapping,
+    snapshot: &impl ReadableSnapshot,
+    type_manager: &TypeManager,
+) -> Result<ResolvedPlayer, ImportError> {
+    let relates = relation_type
+        .get_relates_role_name(snapshot, type_manager, &player.role)
+        .map_err(|source| ImportError::ConceptRead { source })?
+        .ok_or_else(|| ImportError::RoleNotFound {
+            relation: mapping.relation.clone().unwrap_or_default(),
+            role: player.role.clone(),
+        })?;
+    let player_type = type_manager
+        .get_object_type(snapshot, &Label::build(&player.player))
+        .map_err(|source| ImportError::ConceptRead { source })?
+        .ok_or_else(|| ImportError::TypeNotFound { label: player.player.clone() })?;
+    let (key_type, key_value_type) = resolve_attribute_type(&player.key, snapshot, type_manager)?;
+    Ok(ResolvedPlayer {
+        column: player.column.clone(),
+        role_type: relates.role(),
+        player_label: player.player.clone(),
+        player_type,
+        key_label: player.key.clone(),
+        key_type,
+        key_value_type,
+    })
+}
+
+fn resolve_attribute_type(
+    label: &str,
+    snapshot: &impl ReadableSnapshot,
+    type_manager: &TypeManager,
+) -> Result<(AttributeType, ValueType), ImportError> {
+    let attribute_type = type_manager
+        .get_attribute_type(snapshot, &Label::build(label))
+        .map_err(|source| ImportError::ConceptRead { source })?
+        .ok_or_else(|| ImportError::TypeNotFound { label: label.to_owned() })?;
+    let value_type = attribute_type
+        .get_value_type_without_source(snapshot, type_manager)
+        .map_err(|source| ImportError::ConceptRead { source })?
+        .ok_or_else(|| ImportError::AttributeTypeWithoutValueType { label: label.to_owned() })?;
+    match value_type {
+        ValueType::Struct(_) => Err(ImportError::StructAttributeType { label: label.to_owned() }),
+        value_type => Ok((attribute_type, value_type)),
+    }
+}
@@ -0,0 +1,108 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use error::TypeDBError;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{error::ImportError, RejectedRow};
+
+/// The batches of each source that have been committed. Batches are committed out of order by parallel workers, so
+/// each one is recorded rather than a position in the file.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Progress {
+    batch_size: usize,
+    completed_batches: BTreeMap<String, BTreeSet<usize>>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Progress {
+    pub(crate) fn load(path: Option<&Path>, batch_size: usize) -> Result<Self, ImportError> {
+        let mut progress = Self { batch_size, completed_batches: BTreeMap::new(), path: None };
+        if let Some(path) = path {
+            let path_name = || path.display().to_string();
+            match fs::read(path) {
+                Ok(contents) => {
+                    progress = serde_json::from_slice(&contents)
+                        .map_err(|source| ImportError::ProgressParse { path: path_name(), source: Arc::new(source) })?;
+                    if progress.batch_size != batch_size {
+                        return Err(ImportError::ProgressBatchSizeMismatch {
+                            recorded: progress.batch_size,
+                            batch_size,
+                        });
+                    }
+                }
+                Err(error) if error.kind() == io::ErrorKind::NotFound => (),
+                Err(error) => {
+                    return Err(ImportError::ProgressRead { path: path_name(), source: Arc::new(error) });
+                }
+            }
+            progress.path = Some(path.to_owned());
+        }
+        Ok(progress)
+    }
+
+    pub(crate) fn is_completed(&self, file: &str, batch: usize) -> bool {
+        self.completed_batches.get(file).is_some_and(|batches| batches.contains(&batch))
+    }
+
+    pub(crate) fn complete(&mut self, file: &str, batch: usize) -> Result<(), ImportError> {
+        self.completed_batches.entry(file.to_owned()).or_default().insert(batch);
+        let Some(path) = &self.path else { return Ok(()) };
+        // replace the file atomically, so an interrupted import never leaves it truncated
+        let write = || {
+            let temporary = path.with_extension("tmp");
+            fs::write(&temporary, serde_json::to_vec(self)?)?;
+            fs::rename(&temporary, path)
+        };
+        write()
+            .map_err(|source| ImportError::ProgressWrite { path: path.display().to_string(), source: Arc::new(source) })
+    }
+}
+
+/// Appends rejected rows to a file as JSON lines, naming the file and line of the row and the error rejecting it
+#[derive(Debug)]
+pub(crate) struct RejectedLog {
+    writer: Option<(PathBuf, BufWriter<File>)>,
+}
+
+impl RejectedLog {
+    pub(crate) fn open(path: Option<&Path>) -> Result<Self, ImportError> {
+        let Some(path) = path else { return Ok(Self { writer: None }) };
+        let file = OpenOptions::new().create(true).append(true).open(path).map_err(|source| {
+            ImportError::RejectedWrite { path: path.display().to_string(), source: Arc::new(source) }
+        })?;
+        Ok(Self { writer: Some((path.to_owned(), BufWriter::new(file))) })
+    }
+
+    pub(crate) fn append(&mut self, rows: &[RejectedRow]) -> Result<(), ImportError> {
+        let Some((path, writer)) = &mut self.writer else { return Ok(()) };
+        let mut write = || {
+            for row in rows {
+                let line = json!({
+                    "file": row.file,
+                    "line": row.line,
+                    "code": row.error.root_source_typedb_error().code(),
+                    "error": format!("{:?}", row.error),
+                });
+                writeln!(writer, "{line}")?;
+            }
+            writer.flush()
+        };
+        write()
+            .map_err(|source| ImportError::RejectedWrite { path: path.display().to_string(), source: Arc::new(source) })
+    }
+}
@@ -0,0 +1,112 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Lines},
+    sync::Arc,
+};
+
+use serde_json::{Map, Value as JSON};
+
+use crate::{
+    error::{ImportError, RowError},
+    mapping::{ResolvedSource, SourceFormat},
+};
+
+/// One record of a source file, keyed by column. Records that cannot be read are kept so they can be reported.
+#[derive(Debug)]
+pub(crate) struct SourceRecord {
+    pub(crate) line: u64,
+    pub(crate) fields: Result<HashMap<String, String>, RowError>,
+}
+
+pub(crate) enum SourceReader {
+    Csv { reader: csv::Reader<File>, headers: csv::StringRecord, file: String },
+    Ndjson { lines: Lines<BufReader<File>>, line: u64, file: String },
+}
+
+impl SourceReader {
+    pub(crate) fn open(source: &ResolvedSource) -> Result<Self, ImportError> {
+        let file = source.mapping.file_name();
+        let opened = File::open(&source.mapping.file)
+            .map_err(|error| ImportError::SourceRead { file: file.clone(), source: Arc::new(error) })?;
+        match source.format {
+            SourceFormat::Csv => {
+                let mut reader =
+                    csv::ReaderBuilder::new().delimiter(source.mapping.csv_delimiter()?).from_reader(opened);
+                let headers = reader
+                    .headers()
+                    .map_err(|error| ImportError::CsvRead { file: file.clone(), source: Arc::new(error) })?
+                    .clone();
+                if let Some(column) = source.mapping.columns().find(|column| !headers.iter().any(|h| h == *column)) {
+                    return Err(ImportError::ColumnNotFound { file, column: column.to_owned() });
+                }
+                Ok(Self::Csv { reader, headers, file })
+            }
+            SourceFormat::Ndjson => Ok(Self::Ndjson { lines: BufReader::new(opened).lines(), line: 0, file }),
+        }
+    }
+}
+
+impl Iterator for SourceReader {
+    type Item = Result<SourceRecord, ImportError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Csv { reader, headers, file } => {
+                let mut record = csv::StringRecord::new();
+                match reader.read_record(&mut record) {
+                    Ok(false) => None,
+                    Ok(true) => {
+                        let line = record.position().map_or(0, |position| position.line());
+                        let fields = headers.iter().zip(record.iter());
+                        let fields = fields.map(|(column, field)| (column.to_owned(), field.to_owned())).collect();
+                        Some(Ok(SourceRecord { line, fields: Ok(fields) }))
+                    }
+                    Err(error) if error.is_io_error() => {
+                        Some(Err(ImportError::CsvRead { file: file.clone(), source: Arc::new(error) }))
+                    }
+                    Err(error) => {
+                        let line = error.position().map_or(0, |position| position.line());
+                        Some(Ok(SourceRecord {
+                            line,
+                            fields: Err(RowError::InvalidCsvRecord { source: Arc::new(error) }),
+                        }))
+                    }
+                }
+            }
+            Self::Ndjson { lines, line, file } => loop {
+                let text = match lines.next()? {
+                    Ok(text) => text,
+                    Err(error) => {
+                        return Some(Err(ImportError::SourceRead { file: file.clone(), source: Arc::new(error) }))
+                    }
+                };
+                *line += 1;
+                if text.trim().is_empty() {
+                    continue;
+                }
+                let fields = serde_json::from_str::<Map<String, JSON>>(&text)
+                    .map(|object| {
+                        object.into_iter().filter_map(|(column, value)| Some((column, field_text(value)?))).collect()
+                    })
+                    .map_err(|error| RowError::InvalidJsonRecord { source: Arc::new(error) });
+                return Some(Ok(SourceRecord { line: *line, fields }));
+            },
+        }
+    }
+}
+
+// JSON values are read back from their text, as CSV fields are, and nulls are empty
+fn field_text(value: JSON) -> Option<String> {
+    match value {
+        JSON::Null => None,
+        JSON::String(string) => Some(string),
+        value => Some(value.to_string()),
+    }
+}
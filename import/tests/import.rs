@@ -0,0 +1,179 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{fs, path::Path, sync::Arc};
+
+use database::{
+    transaction::{TransactionRead, TransactionSchema},
+    Database,
+};
+use encoding::value::label::Label;
+use import::{ImportOptions, Importer, Mapping, RowError};
+use options::TransactionOptions;
+use storage::durability_client::NoDurabilityClient;
+use test_utils::{create_tmp_dir, init_logging};
+
+const SCHEMA: &str = r#"
+define
+    entity person, owns email @key, owns name, plays employment:employee;
+    entity company, owns name @key, plays employment:employer;
+    relation employment, relates employee, relates employer, owns start-date;
+    attribute email, value string;
+    attribute name, value string;
+    attribute start-date, value date;
+"#;
+
+const MAPPING: &str = r#"
+{
+    "sources": [
+        {
+            "file": "people.csv",
+            "entity": "person",
+            "attributes": [{ "column": "email", "attribute": "email" }, { "column": "name", "attribute": "name" }]
+        },
+        {
+            "file": "companies.ndjson",
+            "entity": "company",
+            "attributes": [{ "column": "name", "attribute": "name" }]
+        },
+        {
+            "file": "employments.csv",
+            "relation": "employment",
+            "attributes": [{ "column": "since", "attribute": "start-date" }],
+            "players": [
+                { "role": "employee", "column": "employee", "player": "person", "key": "email" },
+                { "role": "employer", "column": "company", "player": "company", "key": "name" }
+            ]
+        }
+    ]
+}
+"#;
+
+fn setup_database() -> Arc<Database<NoDurabilityClient>> {
+    let database = Arc::new(Database::<NoDurabilityClient>::create_ephemeral("import").unwrap());
+    let mut transaction = TransactionSchema::open(database.clone(), TransactionOptions::default()).unwrap();
+    let query = typeql::parse_query(SCHEMA).unwrap().into_schema();
+    transaction
+        .query_manager
+        .execute_schema(
+            Arc::get_mut(&mut transaction.snapshot).unwrap(),
+            &transaction.type_manager,
+            &transaction.thing_manager,
+            &transaction.function_manager,
+            query,
+        )
+        .unwrap();
+    transaction.commit().unwrap();
+    database
+}
+
+fn write_sources(directory: &Path) -> Mapping {
+    fs::write(
+        directory.join("people.csv"),
+        "email,name\nalice@example.com,Alice\nbob@example.com,Bob\ncarol@example.com,\n",
+    )
+    .unwrap();
+    fs::write(directory.join("companies.ndjson"), "{\"name\": \"TypeDB\"}\n\n{\"name\": \"Vaticle\"}\n").unwrap();
+    fs::write(
+        directory.join("employments.csv"),
+        "employee,company,since\n\
+         alice@example.com,TypeDB,2020-01-01\n\
+         bob@example.com,TypeDB,not-a-date\n\
+         dave@example.com,TypeDB,2021-01-01\n\
+         carol@example.com,Vaticle,2022-06-30\n",
+    )
+    .unwrap();
+    fs::write(directory.join("mapping.json"), MAPPING).unwrap();
+    Mapping::from_file(directory.join("mapping.json")).unwrap()
+}
+
+fn count_instances(database: &Arc<Database<NoDurabilityClient>>) -> (usize, usize, usize) {
+    let transaction = TransactionRead::open(database.clone(), TransactionOptions::default()).unwrap();
+    let snapshot = transaction.snapshot();
+    let type_manager = &transaction.type_manager;
+    let person = type_manager.get_entity_type(snapshot, &Label::build("person")).unwrap().unwrap();
+    let company = type_manager.get_entity_type(snapshot, &Label::build("company")).unwrap().unwrap();
+    let employment = type_manager.get_relation_type(snapshot, &Label::build("employment")).unwrap().unwrap();
+    let counts = (
+        transaction.thing_manager.get_entities_in(snapshot, person).count(),
+        transaction.thing_manager.get_entities_in(snapshot, company).count(),
+        transaction.thing_manager.get_relations_in(snapshot, employment).count(),
+    );
+    transaction.close();
+    counts
+}
+
+#[test]
+fn import_rejects_invalid_rows() {
+    init_logging();
+    let directory = create_tmp_dir();
+    let mapping = write_sources(&directory);
+    let database = setup_database();
+
+    let options = ImportOptions { batch_size: 2, workers: 2, ..ImportOptions::default() };
+    let report = Importer::new(database.clone(), options).import(&mapping).unwrap();
+
+    assert_eq!(report.imported_rows, 7);
+    assert_eq!(report.skipped_rows, 0);
+    let mut rejected = report.rejected.iter().map(|row| (row.line, &row.error)).collect::<Vec<_>>();
+    rejected.sort_by_key(|(line, _)| *line);
+    assert!(matches!(rejected.as_slice(), [(3, RowError::InvalidValue { .. }), (4, RowError::PlayerNotFound { .. }),]));
+    assert_eq!(count_instances(&database), (3, 2, 2));
+}
+
+#[test]
+fn import_resumes_from_progress() {
+    init_logging();
+    let directory = create_tmp_dir();
+    let mapping = write_sources(&directory);
+    let database = setup_database();
+
+    let options = ImportOptions {
+        batch_size: 2,
+        progress_file: Some(directory.join("progress.json")),
+        rejected_file: Some(directory.join("rejected.ndjson")),
+        ..ImportOptions::default()
+    };
+    let report = Importer::new(database.clone(), options.clone()).import(&mapping).unwrap();
+    assert_eq!(report.imported_rows, 7);
+    assert_eq!(fs::read_to_string(directory.join("rejected.ndjson")).unwrap().lines().count(), 2);
+
+    let report = Importer::new(database.clone(), options.clone()).import(&mapping).unwrap();
+    assert_eq!(report.imported_rows, 0);
+    assert_eq!(report.skipped_rows, 9);
+    assert!(report.rejected.is_empty());
+    assert_eq!(count_instances(&database), (3, 2, 2));
+
+    let mismatched = ImportOptions { batch_size: 3, ..options };
+    assert!(Importer::new(database.clone(), mismatched).import(&mapping).is_err());
+}
+
+#[test]
+fn bulk_load_import_rejects_invalid_rows() {
+    init_logging();
+    let directory = create_tmp_dir();
+    let mapping = write_sources(&directory);
+    // an ephemeral database, which bulk loads must not try to checkpoint
+    let database = setup_database();
+
+    let options = ImportOptions {
+        batch_size: 2,
+        bulk_load: true,
+        progress_file: Some(directory.join("progress.json")),
+        ..ImportOptions::default()
+    };
+    let report = Importer::new(database.clone(), options.clone()).import(&mapping).unwrap();
+
+    assert_eq!(report.imported_rows, 7);
+    let mut rejected = report.rejected.iter().map(|row| (row.line, &row.error)).collect::<Vec<_>>();
+    rejected.sort_by_key(|(line, _)| *line);
+    assert!(matches!(rejected.as_slice(), [(3, RowError::InvalidValue { .. }), (4, RowError::PlayerNotFound { .. }),]));
+    assert_eq!(count_instances(&database), (3, 2, 2));
+
+    let report = Importer::new(database.clone(), options).import(&mapping).unwrap();
+    assert_eq!(report.skipped_rows, 9);
+    assert_eq!(count_instances(&database), (3, 2, 2));
+}
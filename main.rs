@@ -10,11 +10,14 @@
 use std::{path::PathBuf, str::FromStr};
 
 use clap::Parser;
-use logger::initialise_logging_global;
+use logger::{initialise_logging_global, LoggingConfig};
 use resource::constants::server::ASCII_LOGO;
 use server::parameters::{
     cli::CLIArgs,
-    config::{Config, EncryptionConfig},
+    config::{
+        Config, EncryptionConfig, HttpConfig, MetricsConfig, QueryMemoryConfig, ReplicationConfig, SlowQueryLogConfig,
+        TransactionConfig,
+    },
 };
 
 #[tokio::main]
@@ -23,7 +26,7 @@ async fn main() {
     let cli_args = server::parameters::cli::CLIArgs::parse();
 
     print_ascii_logo(); // very important
-    initialise_logging_global();
+    initialise_logging_global(&get_logging_configuration(&cli_args)).unwrap();
 
     let config = get_configuration(cli_args);
 
@@ -36,6 +39,19 @@ async fn main() {
     }
 }
 
+fn get_logging_configuration(cli_args: &CLIArgs) -> LoggingConfig {
+    let format = cli_args.logging_format.unwrap_or_default();
+    let directives = cli_args
+        .logging_filter
+        .as_ref()
+        .map(|filter| {
+            filter.split(',').map(str::trim).filter(|directive| !directive.is_empty()).map(str::to_owned).collect()
+        })
+        .unwrap_or_default();
+    let directory = cli_args.logging_directory.as_ref().map(|dir| PathBuf::from_str(dir.as_str()).unwrap());
+    LoggingConfig::new(format, directives, directory)
+}
+
 fn get_configuration(cli_args: CLIArgs) -> Config {
     let encryption_config = EncryptionConfig::new(
         cli_args.server_encryption_enabled,
@@ -43,8 +59,37 @@ fn get_configuration(cli_args: CLIArgs) -> Config {
         cli_args.server_encryption_cert_key.map(|path| PathBuf::from_str(path.as_str()).unwrap()),
         cli_args.server_encryption_root_ca.map(|path| PathBuf::from_str(path.as_str()).unwrap()),
     );
+    let metrics_config = MetricsConfig::new(cli_args.server_metrics_enabled, cli_args.server_metrics_address);
+    let http_config = HttpConfig::new(cli_args.server_http_enabled, cli_args.server_http_address);
+    let slow_query_log_config = SlowQueryLogConfig::new(
+        cli_args.server_slow_query_log_enabled,
+        cli_args.server_slow_query_log_threshold_millis,
+        cli_args.server_slow_query_log_directory.map(|dir| PathBuf::from_str(dir.as_str()).unwrap()),
+        cli_args.server_slow_query_log_profile,
+    );
+    let replication_config = ReplicationConfig::new(
+        cli_args.server_replication_enabled,
+        cli_args.server_replication_address,
+        cli_args.server_replication_primary,
+    );
+    let query_memory_config = QueryMemoryConfig::new(
+        cli_args.server_query_memory_query_limit_bytes,
+        cli_args.server_query_memory_server_limit_bytes,
+        cli_args.server_query_memory_sort_spill_bytes,
+        cli_args.server_query_memory_sort_spill_directory.map(|dir| PathBuf::from_str(dir.as_str()).unwrap()),
+    );
+    let transaction_config = TransactionConfig::new(cli_args.server_transaction_max_commit_retry_attempts);
     let data_dir = cli_args.storage_data.map(|dir| PathBuf::from_str(dir.as_str()).unwrap());
-    Config::customised(Some(encryption_config), data_dir)
+    Config::customised(
+        Some(encryption_config),
+        Some(metrics_config),
+        Some(http_config),
+        Some(slow_query_log_config),
+        Some(replication_config),
+        Some(query_memory_config),
+        Some(transaction_config),
+        data_dir,
+    )
 }
 
 fn print_ascii_logo() {
@@ -27,6 +27,7 @@ use encoding::{
 use executor::{pipeline::stage::StageIterator, ExecutionInterrupt};
 use function::function_manager::FunctionManager;
 use lending_iterator::LendingIterator;
+use options::QueryOptions;
 use pprof::ProfilerGuard;
 use query::{error::QueryError, query_cache::QueryCache, query_manager::QueryManager};
 use storage::{
@@ -100,7 +101,14 @@ fn execute_insert<Snapshot: WritableSnapshot + 'static>(
     let function_manager = FunctionManager::new(Arc::new(DefinitionKeyGenerator::new()), None);
 
     let pipeline = query_manager
-        .prepare_write_pipeline(snapshot, type_manager, thing_manager, &function_manager, &typeql_insert)
+        .prepare_write_pipeline(
+            snapshot,
+            type_manager,
+            thing_manager,
+            &function_manager,
+            &typeql_insert,
+            QueryOptions::default(),
+        )
         .map_err(|(snapshot, err)| (err, snapshot))?;
     let outputs = pipeline.rows_positions().unwrap().clone();
     let (iter, ctx) =
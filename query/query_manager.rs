@@ -8,21 +8,36 @@ use std::{collections::HashSet, sync::Arc};
 
 use compiler::{
     annotation::pipeline::{annotate_preamble_and_pipeline, AnnotatedPipeline},
-    executable::pipeline::{compile_pipeline, ExecutablePipeline},
+    executable::{
+        explain::PipelinePlan,
+        pipeline::{compile_pipeline, ExecutablePipeline},
+    },
 };
-use concept::{thing::thing_manager::ThingManager, type_::type_manager::TypeManager};
-use executor::pipeline::{
-    pipeline::Pipeline,
-    stage::{ReadPipelineStage, WritePipelineStage},
+use concept::{
+    thing::{statistics::Statistics, thing_manager::ThingManager},
+    type_::type_manager::TypeManager,
+};
+use executor::{
+    memory::QueryMemory,
+    pipeline::{
+        external_sort::SortSpill,
+        pipeline::Pipeline,
+        stage::{ReadPipelineStage, WritePipelineStage},
+    },
+    profile::QueryProfile,
 };
 use function::function_manager::{validate_no_cycles, FunctionManager, ReadThroughFunctionSignatureIndex};
 use ir::{
-    pipeline::function_signature::{FunctionID, HashMapFunctionSignatureIndex},
+    pipeline::{
+        function_signature::{FunctionID, HashMapFunctionSignatureIndex},
+        ParameterRegistry, VariableRegistry,
+    },
     translation::pipeline::{translate_pipeline, TranslatedPipeline},
 };
+use options::QueryOptions;
 use resource::perf_counters::{QUERY_CACHE_HITS, QUERY_CACHE_MISSES};
 use storage::snapshot::{ReadableSnapshot, WritableSnapshot};
-use tracing::{event, Level};
+use tracing::{event, span, Level};
 use typeql::query::SchemaQuery;
 
 use crate::{define, error::QueryError, query_cache::QueryCache, redefine, undefine};
@@ -45,6 +60,7 @@ impl QueryManager {
         function_manager: &FunctionManager,
         query: SchemaQuery,
     ) -> Result<(), QueryError> {
+        let _span = span!(Level::DEBUG, "schema_query").entered();
         event!(Level::TRACE, "Running schema query:\n{}", query);
         match query {
             SchemaQuery::Define(define) => {
@@ -69,8 +85,61 @@ impl QueryManager {
         thing_manager: Arc<ThingManager>,
         function_manager: &FunctionManager,
         query: &typeql::query::Pipeline,
+        query_options: QueryOptions,
     ) -> Result<Pipeline<Snapshot, ReadPipelineStage<Snapshot>>, QueryError> {
+        let _span = span!(Level::DEBUG, "prepare_read_pipeline").entered();
         event!(Level::TRACE, "Running read query:\n{}", query);
+        let (executable_pipeline, variable_registry, parameters) = self.translate_and_compile(
+            snapshot.as_ref(),
+            type_manager,
+            thing_manager.statistics(),
+            function_manager,
+            query,
+        )?;
+        let ExecutablePipeline { executable_functions, executable_stages, executable_fetch, output_value_types } =
+            executable_pipeline;
+
+        // 4: Executor
+        Pipeline::build_read_pipeline(
+            snapshot,
+            thing_manager,
+            &variable_registry,
+            &output_value_types,
+            Arc::new(executable_functions),
+            &executable_stages,
+            executable_fetch,
+            Arc::new(parameters),
+            Self::new_query_profile(&query_options),
+            Self::new_query_memory(&query_options),
+            None,
+        )
+        .map_err(|typedb_source| QueryError::Pipeline { typedb_source })
+    }
+
+    /// Translate, annotate and compile a pipeline, returning the plan it would be executed with
+    pub fn explain_pipeline(
+        &self,
+        snapshot: &impl ReadableSnapshot,
+        type_manager: &TypeManager,
+        thing_manager: &ThingManager,
+        function_manager: &FunctionManager,
+        query: &typeql::query::Pipeline,
+    ) -> Result<PipelinePlan, QueryError> {
+        let _span = span!(Level::DEBUG, "explain_pipeline").entered();
+        event!(Level::TRACE, "Explaining query:\n{}", query);
+        let (executable_pipeline, variable_registry, _) =
+            self.translate_and_compile(snapshot, type_manager, thing_manager.statistics(), function_manager, query)?;
+        Ok(PipelinePlan::new(&executable_pipeline, variable_registry.variable_names()))
+    }
+
+    fn translate_and_compile(
+        &self,
+        snapshot: &impl ReadableSnapshot,
+        type_manager: &TypeManager,
+        statistics: &Statistics,
+        function_manager: &FunctionManager,
+        query: &typeql::query::Pipeline,
+    ) -> Result<(ExecutablePipeline, VariableRegistry, ParameterRegistry), QueryError> {
         // 1: Translate
         let TranslatedPipeline {
             translated_preamble,
@@ -78,7 +147,7 @@ impl QueryManager {
             translated_fetch,
             mut variable_registry,
             value_parameters: parameters,
-        } = self.translate_pipeline(snapshot.as_ref(), function_manager, query)?;
+        } = self.translate_pipeline(snapshot, function_manager, query)?;
         let arced_premable = Arc::new(translated_preamble);
         let arced_stages = Arc::new(translated_stages);
         let arced_fetch = Arc::new(translated_fetch);
@@ -99,12 +168,12 @@ impl QueryManager {
             None => {
                 // 2: Annotate
                 let annotated_schema_functions = function_manager
-                    .get_annotated_functions(snapshot.as_ref(), type_manager)
+                    .get_annotated_functions(snapshot, type_manager)
                     .map_err(|err| QueryError::FunctionDefinition { typedb_source: err })?;
 
-                let AnnotatedPipeline { annotated_preamble, annotated_stages, annotated_fetch } =
+                let AnnotatedPipeline { annotated_preamble, annotated_stages, annotated_fetch, annotated_value_types } =
                     annotate_preamble_and_pipeline(
-                        snapshot.as_ref(),
+                        snapshot,
                         type_manager,
                         annotated_schema_functions.clone(),
                         &mut variable_registry,
@@ -117,12 +186,13 @@ impl QueryManager {
 
                 // 3: Compile
                 let executable_pipeline = compile_pipeline(
-                    thing_manager.statistics(),
+                    statistics,
                     &variable_registry,
                     &annotated_schema_functions,
                     annotated_preamble,
                     annotated_stages,
                     annotated_fetch,
+                    annotated_value_types,
                     &HashSet::with_capacity(0),
                 )
                 .map_err(|err| QueryError::ExecutableCompilation { typedb_source: err })?;
@@ -133,21 +203,7 @@ impl QueryManager {
                 executable_pipeline
             }
         };
-
-        let ExecutablePipeline { executable_functions, executable_stages, executable_fetch } = executable_pipeline;
-
-        // 4: Executor
-        Pipeline::build_read_pipeline(
-            snapshot,
-            thing_manager,
-            variable_registry.variable_names(),
-            Arc::new(executable_functions),
-            &executable_stages,
-            executable_fetch,
-            Arc::new(parameters),
-            None,
-        )
-        .map_err(|typedb_source| QueryError::Pipeline { typedb_source })
+        Ok((executable_pipeline, variable_registry, parameters))
     }
 
     pub fn prepare_write_pipeline<Snapshot: WritableSnapshot>(
@@ -157,7 +213,9 @@ impl QueryManager {
         thing_manager: Arc<ThingManager>,
         function_manager: &FunctionManager,
         query: &typeql::query::Pipeline,
+        query_options: QueryOptions,
     ) -> Result<Pipeline<Snapshot, WritePipelineStage<Snapshot>>, (Snapshot, QueryError)> {
+        let _span = span!(Level::DEBUG, "prepare_write_pipeline").entered();
         event!(Level::TRACE, "Running write query:\n{}", query);
         // 1: Translate
         let TranslatedPipeline {
@@ -209,7 +267,7 @@ impl QueryManager {
                     (*arced_fetch).clone(),
                 );
 
-                let AnnotatedPipeline { annotated_preamble, annotated_stages, annotated_fetch } =
+                let AnnotatedPipeline { annotated_preamble, annotated_stages, annotated_fetch, annotated_value_types } =
                     match annotated_pipeline {
                         Ok(annotated_pipeline) => annotated_pipeline,
                         Err(err) => return Err((snapshot, QueryError::Annotation { typedb_source: err })),
@@ -223,6 +281,7 @@ impl QueryManager {
                     annotated_preamble,
                     annotated_stages,
                     annotated_fetch,
+                    annotated_value_types,
                     &HashSet::with_capacity(0),
                 ) {
                     Ok(executable) => executable,
@@ -236,19 +295,35 @@ impl QueryManager {
             }
         };
 
-        let ExecutablePipeline { executable_functions, executable_stages, executable_fetch } = executable_pipeline;
+        let ExecutablePipeline { executable_functions, executable_stages, executable_fetch, output_value_types } =
+            executable_pipeline;
 
         // 4: Executor
         Ok(Pipeline::build_write_pipeline(
             snapshot,
-            variable_registry.variable_names(),
+            &variable_registry,
+            &output_value_types,
             thing_manager,
             executable_stages,
             executable_fetch,
             Arc::new(value_parameters),
+            Self::new_query_profile(&query_options),
+            Self::new_query_memory(&query_options),
         ))
     }
 
+    fn new_query_profile(query_options: &QueryOptions) -> Arc<QueryProfile> {
+        Arc::new(QueryProfile::new(query_options.profile || tracing::enabled!(Level::TRACE)))
+    }
+
+    fn new_query_memory(query_options: &QueryOptions) -> Arc<QueryMemory> {
+        let sort_spill = query_options
+            .sort_spill
+            .as_ref()
+            .map(|spill| SortSpill { directory: spill.directory.clone(), run_bytes: spill.run_bytes });
+        Arc::new(QueryMemory::new(query_options.memory_limit_bytes, sort_spill))
+    }
+
     fn translate_pipeline<Snapshot: ReadableSnapshot>(
         &self,
         snapshot: &Snapshot,
@@ -15,13 +15,43 @@ pub mod server {
     pub const DEFAULT_PREFETCH_SIZE: u64 = 32;
     pub const DEFAULT_SCHEMA_LOCK_ACQUIRE_TIMEOUT_MILLIS: u64 = Duration::from_secs(10).as_millis() as u64;
     pub const DEFAULT_TRANSACTION_PARALLEL: bool = true;
+    pub const DEFAULT_TRANSACTION_SERIALIZABLE: bool = false;
+    pub const DEFAULT_TRANSACTION_COMMIT_RETRY_ATTEMPTS: u32 = 0;
+    pub const DEFAULT_MAX_COMMIT_RETRY_ATTEMPTS: u32 = 10;
+    pub const COMMIT_RETRY_BACKOFF_BASE_MILLIS: u64 = 10;
+    pub const COMMIT_RETRY_BACKOFF_MAX_MILLIS: u64 = 1000;
 
     pub const PERF_COUNTERS_ENABLED: bool = true;
 
     pub const AUTHENTICATOR_USERNAME_FIELD: &str = "username";
     pub const AUTHENTICATOR_PASSWORD_FIELD: &str = "password";
+    pub const CONNECTION_ID_METADATA_FIELD: &str = "connection_id";
+    pub const QUERY_BATCH_METADATA_FIELD: &str = "batch";
+    pub const QUERY_PARAMETERS_METADATA_FIELD: &str = "parameters";
+    pub const QUERY_TIMEOUT_METADATA_FIELD: &str = "timeout_millis";
+    pub const QUERY_CANCEL_METADATA_FIELD: &str = "cancel";
+    pub const QUERY_PROFILE_METADATA_FIELD: &str = "profile";
+    pub const QUERY_PROFILE_COLUMN: &str = "profile";
+    pub const QUERY_EXPLAIN_METADATA_FIELD: &str = "explain";
+    pub const QUERY_EXPLAIN_PLAN_COLUMN: &str = "plan";
+    pub const COMMIT_RETRY_ATTEMPTS_METADATA_FIELD: &str = "commit_retry_attempts";
+    pub const COMMIT_ATTEMPTS_METADATA_FIELD: &str = "commit_attempts";
 
     pub const DEFAULT_ADDRESS: &str = "0.0.0.0:1729";
+    pub const DEFAULT_METRICS_ADDRESS: &str = "127.0.0.1:4104";
+    pub const DEFAULT_HTTP_ADDRESS: &str = "127.0.0.1:8000";
+    pub const HTTP_TRANSACTION_IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+    pub const CHANGE_STREAM_POLL_INTERVAL: Duration = Duration::from_millis(100);
+    pub const CHANGE_STREAM_BATCH_COMMITS: usize = 100;
+    pub const HTTP_ARROW_STREAM_BUFFERED_BATCHES: usize = 16;
+    pub const DEFAULT_REPLICATION_ADDRESS: &str = "127.0.0.1:1730";
+    pub const DEFAULT_SLOW_QUERY_THRESHOLD_MILLIS: u64 = Duration::from_secs(1).as_millis() as u64;
+    pub const DEFAULT_SLOW_QUERY_LOG_FILE_SIZE: u64 = 64 * 1024 * 1024;
+    pub const DEFAULT_SLOW_QUERY_LOG_FILES: usize = 5;
+    pub const SLOW_QUERY_LOG_FILE_NAME: &str = "slow-queries.log";
+    pub const DEFAULT_LOG_FILE_SIZE: u64 = 64 * 1024 * 1024;
+    pub const DEFAULT_LOG_FILES: usize = 10;
+    pub const LOG_FILE_NAME: &str = "typedb.log";
     pub const DEFAULT_USER_NAME: &str = "admin";
     pub const DEFAULT_USER_PASSWORD: &str = "password";
 }
@@ -30,15 +60,28 @@ pub mod database {
     pub const QUERY_PLAN_CACHE_FLUSH_STATISTICS_CHANGE_PERCENT: f64 = 0.05;
     pub const QUERY_PLAN_CACHE_SIZE: u64 = 100;
     pub const STATISTICS_DURABLE_WRITE_CHANGE_PERCENT: f64 = 0.05;
+    pub const REPLICATION_POLL_INTERVAL_MILLIS: u64 = 5;
+    pub const REPLICATION_RECONNECT_INTERVAL_MILLIS: u64 = 1000;
+    pub const REPLICATION_BATCH_RECORDS: usize = 1000;
+    pub const REPLICATION_MAX_RECORD_BYTES: u64 = 1024 * 1024 * 1024;
+    pub const REPLICATION_MAX_NAME_BYTES: u64 = 1024;
 }
 
 pub mod traversal {
     pub const CONSTANT_CONCEPT_LIMIT: usize = 10;
 }
 
+pub mod executor {
+    pub const DEFAULT_SORT_RUN_SPILL_BYTES: u64 = 64 * 1024 * 1024;
+    pub const SORT_SPILL_DIRECTORY_NAME: &str = ".sort-spill";
+}
+
 pub mod snapshot {
     pub const BUFFER_KEY_INLINE: usize = 40;
     pub const BUFFER_VALUE_INLINE: usize = 64;
+    pub const DEFAULT_WRITE_BUFFER_SPILL_BYTES: u64 = 256 * 1024 * 1024;
+    pub const DEFAULT_WRITE_BUFFER_LIMIT_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+    pub const SPILLED_RUN_INDEX_INTERVAL: usize = 64;
 }
 
 pub mod storage {
@@ -64,3 +107,9 @@ pub mod encoding {
 
     pub type StructFieldIDUInt = u16;
 }
+
+pub mod import {
+    pub const DEFAULT_IMPORT_BATCH_SIZE: usize = 1000;
+    pub const DEFAULT_IMPORT_WORKERS: usize = 4;
+    pub const IMPORT_COMMIT_CONFLICT_RETRIES: u32 = 5;
+}
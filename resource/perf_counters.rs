@@ -6,7 +6,10 @@
 
 // let's start with a simple and fast global list of NAME = COUNTER for now.
 
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
 
 use crate::constants::server::PERF_COUNTERS_ENABLED;
 
@@ -16,17 +19,140 @@ pub struct Counter {
 }
 
 impl Counter {
-    const fn new(enabled: bool) -> Self {
+    pub const fn new(enabled: bool) -> Self {
         Self { counter: AtomicU64::new(0), enabled }
     }
 
     pub fn increment(&self) {
+        self.add(1)
+    }
+
+    pub fn add(&self, value: u64) {
         if self.enabled {
-            self.counter.fetch_add(1, Ordering::Relaxed);
+            self.counter.fetch_add(value, Ordering::Relaxed);
         }
     }
+
+    pub fn get(&self) -> u64 {
+        self.counter.load(Ordering::Relaxed)
+    }
+}
+
+// Upper bounds, in microseconds, of the latency histogram buckets. The final +Inf bucket is implicit.
+pub const LATENCY_BUCKETS_MICROS: [u64; 12] =
+    [100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 1_000_000];
+
+pub struct Histogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS_MICROS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+    enabled: bool,
+}
+
+impl Histogram {
+    pub const fn new(enabled: bool) -> Self {
+        Self {
+            buckets: [const { AtomicU64::new(0) }; LATENCY_BUCKETS_MICROS.len()],
+            count: AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+            enabled,
+        }
+    }
+
+    pub fn record(&self, duration: Duration) {
+        if self.enabled {
+            let micros = duration.as_micros() as u64;
+            if let Some(index) = LATENCY_BUCKETS_MICROS.iter().position(|bound| micros <= *bound) {
+                self.buckets[index].fetch_add(1, Ordering::Relaxed);
+            }
+            self.count.fetch_add(1, Ordering::Relaxed);
+            self.sum_micros.fetch_add(micros, Ordering::Relaxed);
+        }
+    }
+
+    /// Cumulative counts per bucket upper bound, as required by the Prometheus exposition format
+    pub fn cumulative_buckets(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        LATENCY_BUCKETS_MICROS.iter().zip(self.buckets.iter()).scan(0, |cumulative, (bound, count)| {
+            *cumulative += count.load(Ordering::Relaxed);
+            Some((*bound, *cumulative))
+        })
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn sum_micros(&self) -> u64 {
+        self.sum_micros.load(Ordering::Relaxed)
+    }
 }
 
 pub static QUERY_CACHE_HITS: Counter = Counter::new(PERF_COUNTERS_ENABLED);
 pub static QUERY_CACHE_MISSES: Counter = Counter::new(PERF_COUNTERS_ENABLED);
 pub static QUERY_CACHE_FLUSH: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+
+pub static TRANSACTION_OPENED_READ: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+pub static TRANSACTION_OPENED_WRITE: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+pub static TRANSACTION_OPENED_SCHEMA: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+pub static TRANSACTION_OPENED_BULK_LOAD: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+pub static TRANSACTION_COMMITTED_WRITE: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+pub static TRANSACTION_COMMITTED_SCHEMA: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+pub static TRANSACTION_COMMIT_FAILED_WRITE: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+pub static TRANSACTION_COMMIT_FAILED_SCHEMA: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+pub static TRANSACTION_COMMIT_RETRIED_WRITE: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+pub static TRANSACTION_COMMIT_LATENCY_WRITE: Histogram = Histogram::new(PERF_COUNTERS_ENABLED);
+pub static TRANSACTION_COMMIT_LATENCY_SCHEMA: Histogram = Histogram::new(PERF_COUNTERS_ENABLED);
+
+pub static ISOLATION_CONFLICT_DELETING_REQUIRED_KEY: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+pub static ISOLATION_CONFLICT_REQUIRE_DELETED_KEY: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+pub static ISOLATION_CONFLICT_EXCLUSIVE_LOCK: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+pub static ISOLATION_CONFLICT_READ_SET_WRITTEN: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+
+pub static WAL_BYTES_WRITTEN: Counter = Counter::new(PERF_COUNTERS_ENABLED);
+pub static WAL_FSYNC_LATENCY: Histogram = Histogram::new(PERF_COUNTERS_ENABLED);
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Counter, Histogram, LATENCY_BUCKETS_MICROS};
+
+    #[test]
+    fn disabled_counters_stay_at_zero() {
+        let enabled = Counter::new(true);
+        let disabled = Counter::new(false);
+        enabled.add(2);
+        enabled.increment();
+        disabled.add(2);
+        assert_eq!(enabled.get(), 3);
+        assert_eq!(disabled.get(), 0);
+    }
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let histogram = Histogram::new(true);
+        for micros in [100, 101, 250, 3_000, 2_000_000] {
+            histogram.record(Duration::from_micros(micros));
+        }
+        let buckets = histogram.cumulative_buckets().collect::<Vec<_>>();
+        assert_eq!(buckets.len(), LATENCY_BUCKETS_MICROS.len());
+        // a bucket counts the durations up to and including its bound
+        assert_eq!(buckets[0], (100, 1));
+        assert_eq!(buckets[1], (250, 3));
+        assert_eq!(buckets[2], (500, 3));
+        assert_eq!(buckets[5], (5_000, 4));
+        // durations beyond the last bound only count towards the implicit +Inf bucket
+        assert_eq!(*buckets.last().unwrap(), (1_000_000, 4));
+        assert_eq!(histogram.count(), 5);
+        assert_eq!(histogram.sum_micros(), 2_003_451);
+        assert!(buckets.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    }
+
+    #[test]
+    fn disabled_histograms_record_nothing() {
+        let histogram = Histogram::new(false);
+        histogram.record(Duration::from_micros(10));
+        assert_eq!(histogram.count(), 0);
+        assert!(histogram.cumulative_buckets().all(|(_, count)| count == 0));
+    }
+}
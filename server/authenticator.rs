@@ -30,20 +30,15 @@ impl Authenticator {
         let username_metadata = metadata.get(AUTHENTICATOR_USERNAME_FIELD).map(|u| u.to_str());
         let password_metadata = metadata.get(AUTHENTICATOR_PASSWORD_FIELD).map(|u| u.to_str());
         match (username_metadata, password_metadata) {
-            (Some(Ok(username)), Some(Ok(password))) => match self.user_manager.get(username) {
-                Ok(get_result) => match get_result {
-                    Some((_, Credential::PasswordType { password_hash })) => {
-                        if password_hash.matches(password) {
-                            Ok(req)
-                        } else {
-                            Err(Status::unauthenticated(ERROR_INVALID_CREDENTIAL))
-                        }
-                    }
-                    None => Err(Status::unauthenticated(ERROR_INVALID_CREDENTIAL)),
-                },
-                Err(_) => Err(Status::unauthenticated(ERROR_INVALID_CREDENTIAL)),
-            },
+            (Some(Ok(username)), Some(Ok(password))) if self.is_valid_credential(username, password) => Ok(req),
             _ => Err(Status::unauthenticated(ERROR_INVALID_CREDENTIAL)),
         }
     }
+
+    pub(crate) fn is_valid_credential(&self, username: &str, password: &str) -> bool {
+        match self.user_manager.get(username) {
+            Ok(Some((_, Credential::PasswordType { password_hash }))) => password_hash.matches(password),
+            Ok(None) | Err(_) => false,
+        }
+    }
 }
@@ -4,7 +4,10 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+use std::{net::SocketAddr, str::FromStr};
+
 use clap::Parser;
+use logger::LogFormat;
 
 /// TypeDB Core usage
 #[derive(Parser, Debug)]
@@ -26,7 +29,125 @@ pub struct CLIArgs {
     #[arg(long = "server.encryption.root-ca", value_name = "FILE")]
     pub server_encryption_root_ca: Option<String>,
 
+    /// Enable/disable the Prometheus metrics endpoint. Specify to enable, or leave out to disable
+    #[arg(long = "server.metrics.enabled")]
+    pub server_metrics_enabled: bool,
+
+    /// Address of the Prometheus metrics endpoint, served at '/metrics'
+    #[arg(long = "server.metrics.address", value_name = "ADDRESS")]
+    pub server_metrics_address: Option<SocketAddr>,
+
+    /// Enable/disable the HTTP/JSON API. Specify to enable, or leave out to disable
+    #[arg(long = "server.http.enabled")]
+    pub server_http_enabled: bool,
+
+    /// Address of the HTTP/JSON API, served under '/v1'
+    #[arg(long = "server.http.address", value_name = "ADDRESS")]
+    pub server_http_address: Option<SocketAddr>,
+
+    /// Enable/disable the slow query log. Specify to enable, or leave out to disable
+    #[arg(long = "server.slow-query-log.enabled")]
+    pub server_slow_query_log_enabled: bool,
+
+    /// Queries taking longer than this many milliseconds are recorded in the slow query log
+    #[arg(long = "server.slow-query-log.threshold-millis", value_name = "MILLIS")]
+    pub server_slow_query_log_threshold_millis: Option<u64>,
+
+    /// Directory the slow query log is written to. Defaults to 'logs' next to the data directory
+    #[arg(long = "server.slow-query-log.directory", value_name = "DIR")]
+    pub server_slow_query_log_directory: Option<String>,
+
+    /// Profile every query, so slow queries are logged with per-step row counts and timings. Whether a query is slow
+    /// is only known once it finishes, so this times every step of every query, not only the slow ones
+    #[arg(long = "server.slow-query-log.profile")]
+    pub server_slow_query_log_profile: bool,
+
+    /// Enable/disable shipping the WAL to read replicas. Specify to enable, or leave out to disable
+    #[arg(long = "server.replication.enabled")]
+    pub server_replication_enabled: bool,
+
+    /// Address read replicas connect to for WAL shipping
+    #[arg(long = "server.replication.address", value_name = "ADDRESS")]
+    pub server_replication_address: Option<SocketAddr>,
+
+    /// Run as a read replica following the primary's replication address. Write and schema transactions are rejected
+    #[arg(long = "server.replication.primary", value_name = "ADDRESS")]
+    pub server_replication_primary: Option<SocketAddr>,
+
+    /// Queries holding more than this many bytes in answer tables, sort buffers, reduce groups or fetched documents fail
+    #[arg(long = "server.query-memory.query-limit-bytes", value_name = "BYTES")]
+    pub server_query_memory_query_limit_bytes: Option<u64>,
+
+    /// Queries fail when all running queries together would hold more than this many bytes
+    #[arg(long = "server.query-memory.server-limit-bytes", value_name = "BYTES")]
+    pub server_query_memory_server_limit_bytes: Option<u64>,
+
+    /// Sorts holding more than this many bytes of rows spill them to disk in sorted runs of this size
+    #[arg(long = "server.query-memory.sort-spill-bytes", value_name = "BYTES")]
+    pub server_query_memory_sort_spill_bytes: Option<u64>,
+
+    /// Directory sorts spill their rows to, emptied when the server starts. Defaults to `<storage>/.sort-spill`
+    #[arg(long = "server.query-memory.sort-spill-directory", value_name = "DIR")]
+    pub server_query_memory_sort_spill_directory: Option<String>,
+
+    /// Requests for more commit retries than this are rejected
+    #[arg(long = "server.transaction.max-commit-retry-attempts", value_name = "ATTEMPTS")]
+    pub server_transaction_max_commit_retry_attempts: Option<u32>,
+
+    /// Log output format: 'text' or 'json'
+    #[arg(long = "logging.format", value_name = "FORMAT", value_parser = LogFormat::from_str)]
+    pub logging_format: Option<LogFormat>,
+
+    /// Comma-separated per-module log levels, eg. 'storage=debug,server=trace'
+    #[arg(long = "logging.filter", value_name = "DIRECTIVES")]
+    pub logging_filter: Option<String>,
+
+    /// Directory to additionally write size-rotated log files to
+    #[arg(long = "logging.directory", value_name = "DIR")]
+    pub logging_directory: Option<String>,
+
     /// Path to the data directory
     #[arg(long = "storage.data", value_name = "DIR")]
     pub storage_data: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use logger::LogFormat;
+
+    use super::CLIArgs;
+
+    #[test]
+    fn addresses_are_validated_when_parsed() {
+        let args = CLIArgs::try_parse_from([
+            "typedb",
+            "--server.metrics.address",
+            "127.0.0.1:9090",
+            "--server.replication.primary",
+            "[::1]:1730",
+        ])
+        .unwrap();
+        assert_eq!(args.server_metrics_address, Some("127.0.0.1:9090".parse().unwrap()));
+        assert_eq!(args.server_replication_primary, Some("[::1]:1730".parse().unwrap()));
+
+        for flag in [
+            "--server.metrics.address",
+            "--server.http.address",
+            "--server.replication.address",
+            "--server.replication.primary",
+        ] {
+            assert!(CLIArgs::try_parse_from(["typedb", flag, "localhost"]).is_err());
+            assert!(CLIArgs::try_parse_from(["typedb", flag, "127.0.0.1:port"]).is_err());
+        }
+    }
+
+    #[test]
+    fn log_format_is_validated_when_parsed() {
+        let args = CLIArgs::try_parse_from(["typedb", "--logging.format", "JSON"]).unwrap();
+        assert_eq!(args.logging_format, Some(LogFormat::Json));
+        assert_eq!(CLIArgs::try_parse_from(["typedb"]).unwrap().logging_format, None);
+        assert!(CLIArgs::try_parse_from(["typedb", "--logging.format", "xml"]).is_err());
+    }
+}
@@ -8,9 +8,17 @@ use std::{
     net::SocketAddr,
     path::{Path, PathBuf},
     str::FromStr,
+    time::Duration,
 };
 
-use resource::constants::server::DEFAULT_ADDRESS;
+use resource::constants::{
+    executor::DEFAULT_SORT_RUN_SPILL_BYTES,
+    server::{
+        DEFAULT_ADDRESS, DEFAULT_HTTP_ADDRESS, DEFAULT_MAX_COMMIT_RETRY_ATTEMPTS, DEFAULT_METRICS_ADDRESS,
+        DEFAULT_REPLICATION_ADDRESS, DEFAULT_SLOW_QUERY_LOG_FILES, DEFAULT_SLOW_QUERY_LOG_FILE_SIZE,
+        DEFAULT_SLOW_QUERY_THRESHOLD_MILLIS,
+    },
+};
 
 #[derive(Debug)]
 pub struct Config {
@@ -33,21 +41,42 @@ impl Config {
             server: ServerConfig {
                 address: SocketAddr::from_str(DEFAULT_ADDRESS).unwrap(),
                 encryption: EncryptionConfig::disabled(),
+                metrics: MetricsConfig::disabled(),
+                http: HttpConfig::disabled(),
+                slow_query_log: SlowQueryLogConfig::disabled(),
+                replication: ReplicationConfig::disabled(),
+                query_memory: QueryMemoryConfig::unlimited(),
+                transaction: TransactionConfig::default(),
             },
             storage: StorageConfig { data: typedb_dir_or_current.join(PathBuf::from_str("server/data").unwrap()) },
         }
     }
 
     pub fn new_with_encryption_config(encryption_config: EncryptionConfig) -> Self {
-        Self::customised(Some(encryption_config), None)
+        Self::customised(Some(encryption_config), None, None, None, None, None, None, None)
     }
 
     pub fn new_with_data_directory(data_directory: &Path) -> Self {
-        Self::customised(None, Some(data_directory.to_path_buf()))
+        Self::customised(None, None, None, None, None, None, None, Some(data_directory.to_path_buf()))
     }
 
-    pub fn customised(encryption_config: Option<EncryptionConfig>, data_directory: Option<PathBuf>) -> Self {
+    pub fn customised(
+        encryption_config: Option<EncryptionConfig>,
+        metrics_config: Option<MetricsConfig>,
+        http_config: Option<HttpConfig>,
+        slow_query_log_config: Option<SlowQueryLogConfig>,
+        replication_config: Option<ReplicationConfig>,
+        query_memory_config: Option<QueryMemoryConfig>,
+        transaction_config: Option<TransactionConfig>,
+        data_directory: Option<PathBuf>,
+    ) -> Self {
         let encryption_config = encryption_config.unwrap_or_else(|| EncryptionConfig::disabled());
+        let metrics_config = metrics_config.unwrap_or_else(|| MetricsConfig::disabled());
+        let http_config = http_config.unwrap_or_else(|| HttpConfig::disabled());
+        let slow_query_log_config = slow_query_log_config.unwrap_or_else(|| SlowQueryLogConfig::disabled());
+        let replication_config = replication_config.unwrap_or_else(|| ReplicationConfig::disabled());
+        let query_memory_config = query_memory_config.unwrap_or_else(|| QueryMemoryConfig::unlimited());
+        let transaction_config = transaction_config.unwrap_or_default();
         let data_directory = data_directory.map(|dir| dir.to_path_buf()).unwrap_or_else(|| {
             let typedb_dir_or_current = std::env::current_exe()
                 .map(|path| path.parent().unwrap().to_path_buf())
@@ -58,6 +87,12 @@ impl Config {
             server: ServerConfig {
                 address: SocketAddr::from_str("0.0.0.0:1729").unwrap(),
                 encryption: encryption_config,
+                metrics: metrics_config,
+                http: http_config,
+                slow_query_log: slow_query_log_config,
+                replication: replication_config,
+                query_memory: query_memory_config,
+                transaction: transaction_config,
             },
             storage: StorageConfig { data: data_directory.to_owned() },
         }
@@ -68,6 +103,12 @@ impl Config {
 pub(crate) struct ServerConfig {
     pub(crate) address: SocketAddr,
     pub(crate) encryption: EncryptionConfig,
+    pub(crate) metrics: MetricsConfig,
+    pub(crate) http: HttpConfig,
+    pub(crate) slow_query_log: SlowQueryLogConfig,
+    pub(crate) replication: ReplicationConfig,
+    pub(crate) query_memory: QueryMemoryConfig,
+    pub(crate) transaction: TransactionConfig,
 }
 
 #[derive(Debug)]
@@ -88,6 +129,141 @@ impl EncryptionConfig {
     }
 }
 
+#[derive(Debug)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub address: SocketAddr,
+}
+
+impl MetricsConfig {
+    pub fn disabled() -> Self {
+        Self::new(false, None)
+    }
+
+    pub fn new(enabled: bool, address: Option<SocketAddr>) -> Self {
+        Self { enabled, address: address.unwrap_or_else(|| SocketAddr::from_str(DEFAULT_METRICS_ADDRESS).unwrap()) }
+    }
+}
+
+/// The HTTP/JSON API, served alongside gRPC for clients that cannot use the gRPC protocol
+#[derive(Debug)]
+pub struct HttpConfig {
+    pub enabled: bool,
+    pub address: SocketAddr,
+}
+
+impl HttpConfig {
+    pub fn disabled() -> Self {
+        Self::new(false, None)
+    }
+
+    pub fn new(enabled: bool, address: Option<SocketAddr>) -> Self {
+        Self { enabled, address: address.unwrap_or_else(|| SocketAddr::from_str(DEFAULT_HTTP_ADDRESS).unwrap()) }
+    }
+}
+
+#[derive(Debug)]
+pub struct SlowQueryLogConfig {
+    pub enabled: bool,
+    pub threshold: Duration,
+    pub directory: Option<PathBuf>,
+    /// Profile every query, so slow queries are logged with the measurements of their steps
+    pub profile: bool,
+    pub max_file_size: u64,
+    pub max_files: usize,
+}
+
+impl SlowQueryLogConfig {
+    pub fn disabled() -> Self {
+        Self::new(false, None, None, false)
+    }
+
+    pub fn new(enabled: bool, threshold_millis: Option<u64>, directory: Option<PathBuf>, profile: bool) -> Self {
+        Self {
+            enabled,
+            threshold: Duration::from_millis(threshold_millis.unwrap_or(DEFAULT_SLOW_QUERY_THRESHOLD_MILLIS)),
+            directory,
+            profile,
+            max_file_size: DEFAULT_SLOW_QUERY_LOG_FILE_SIZE,
+            max_files: DEFAULT_SLOW_QUERY_LOG_FILES,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ReplicationConfig {
+    pub enabled: bool,
+    pub address: SocketAddr,
+    /// When set, this server is a read replica of the primary serving replication at this address
+    pub primary: Option<SocketAddr>,
+}
+
+impl ReplicationConfig {
+    pub fn disabled() -> Self {
+        Self::new(false, None, None)
+    }
+
+    pub fn new(enabled: bool, address: Option<SocketAddr>, primary: Option<SocketAddr>) -> Self {
+        Self {
+            enabled,
+            address: address.unwrap_or_else(|| SocketAddr::from_str(DEFAULT_REPLICATION_ADDRESS).unwrap()),
+            primary,
+        }
+    }
+}
+
+/// Bounds on the memory held by the answer tables, sort buffers, reduce groups and fetched documents of queries
+#[derive(Debug)]
+pub struct QueryMemoryConfig {
+    /// The memory each query may hold
+    pub query_limit_bytes: Option<u64>,
+    /// The memory all running queries may hold together
+    pub server_limit_bytes: Option<u64>,
+    /// Sorts holding more rows than fit in this size spill them to disk
+    pub sort_spill_bytes: u64,
+    /// The directory sorts spill to, which the server empties when it starts. Defaults to `<storage>/.sort-spill`
+    pub sort_spill_directory: Option<PathBuf>,
+}
+
+impl QueryMemoryConfig {
+    pub fn unlimited() -> Self {
+        Self::new(None, None, None, None)
+    }
+
+    pub fn new(
+        query_limit_bytes: Option<u64>,
+        server_limit_bytes: Option<u64>,
+        sort_spill_bytes: Option<u64>,
+        sort_spill_directory: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            query_limit_bytes,
+            server_limit_bytes,
+            sort_spill_bytes: sort_spill_bytes.unwrap_or(DEFAULT_SORT_RUN_SPILL_BYTES),
+            sort_spill_directory,
+        }
+    }
+}
+
+/// Bounds on what clients may request of the transactions they open
+#[derive(Debug)]
+pub struct TransactionConfig {
+    /// The most times a client may ask for a conflicting commit to be retried
+    pub max_commit_retry_attempts: u32,
+}
+
+impl Default for TransactionConfig {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl TransactionConfig {
+    pub fn new(max_commit_retry_attempts: Option<u32>) -> Self {
+        Self { max_commit_retry_attempts: max_commit_retry_attempts.unwrap_or(DEFAULT_MAX_COMMIT_RETRY_ATTEMPTS) }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct StorageConfig {
     pub(crate) data: PathBuf,
@@ -0,0 +1,294 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+    time::UNIX_EPOCH,
+};
+
+use error::typedb_error;
+use executor::InterruptType;
+use tonic::{
+    body::BoxBody,
+    codec::ProstCodec,
+    codegen::{http, Body, BoxFuture, Service, StdError},
+    server::{Grpc, NamedService},
+    Request, Response, Status,
+};
+use user::permission_manager::PermissionManager;
+use uuid::Uuid;
+
+use crate::service::{
+    error::{IntoGRPCStatus, IntoProtocolErrorMessage},
+    grpc::{unimplemented_response, UnaryMethod},
+    transaction_registry::{QueryInterrupt, TransactionInfo, TransactionRegistry},
+    typedb_service::extract_username_field,
+};
+
+const LIST_TRANSACTIONS_PATH: &str = "/typedb.admin.Admin/ListTransactions";
+const KILL_TRANSACTION_PATH: &str = "/typedb.admin.Admin/KillTransaction";
+const KILL_QUERY_PATH: &str = "/typedb.admin.Admin/KillQuery";
+
+/// The `typedb.admin.Admin` gRPC service, which lists the transactions open on the server and the queries they are
+/// running, and kills transactions or single read queries. Only the admin user may call it.
+#[derive(Debug, Clone)]
+pub(crate) struct AdminService {
+    transaction_registry: Arc<TransactionRegistry>,
+}
+
+impl AdminService {
+    pub(crate) fn new(transaction_registry: Arc<TransactionRegistry>) -> Self {
+        Self { transaction_registry }
+    }
+
+    async fn list_transactions(
+        &self,
+        request: Request<ListTransactionsReq>,
+    ) -> Result<Response<ListTransactionsRes>, Status> {
+        check_admin(&request)?;
+        let transactions = self.transaction_registry.transactions().iter().map(transaction_info_message).collect();
+        Ok(Response::new(ListTransactionsRes { transactions }))
+    }
+
+    async fn kill_transaction(
+        &self,
+        request: Request<KillTransactionReq>,
+    ) -> Result<Response<KillTransactionRes>, Status> {
+        check_admin(&request)?;
+        let transaction_id = parse_transaction_id(&request.get_ref().transaction_id)?;
+        match self.transaction_registry.kill_transaction(transaction_id) {
+            true => Ok(Response::new(KillTransactionRes {})),
+            false => Err(into_status(AdminServiceError::TransactionNotFound { id: transaction_id.to_string() })),
+        }
+    }
+
+    async fn kill_query(&self, request: Request<KillQueryReq>) -> Result<Response<KillQueryRes>, Status> {
+        check_admin(&request)?;
+        let transaction_id = parse_transaction_id(&request.get_ref().transaction_id)?;
+        let query_id = Uuid::from_slice(&request.get_ref().query_id).map_err(|_| {
+            into_status(AdminServiceError::QueryNotFound { id: format!("{:?}", request.get_ref().query_id) })
+        })?;
+        match self.transaction_registry.interrupt_query(transaction_id, query_id, InterruptType::KillRequest) {
+            QueryInterrupt::Sent => Ok(Response::new(KillQueryRes {})),
+            QueryInterrupt::QueryNotFound => {
+                Err(into_status(AdminServiceError::QueryNotFound { id: query_id.to_string() }))
+            }
+            QueryInterrupt::QueryNotInterruptible => {
+                Err(into_status(AdminServiceError::WriteQueryCannotBeKilled { id: query_id.to_string() }))
+            }
+        }
+    }
+}
+
+impl<B> Service<http::Request<B>> for AdminService
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let service = self.clone();
+        match request.uri().path() {
+            LIST_TRANSACTIONS_PATH => Box::pin(async move {
+                let method = UnaryMethod(|request| {
+                    let service = service.clone();
+                    async move { service.list_transactions(request).await }
+                });
+                Ok(Grpc::new(ProstCodec::default()).unary(method, request).await)
+            }),
+            KILL_TRANSACTION_PATH => Box::pin(async move {
+                let method = UnaryMethod(|request| {
+                    let service = service.clone();
+                    async move { service.kill_transaction(request).await }
+                });
+                Ok(Grpc::new(ProstCodec::default()).unary(method, request).await)
+            }),
+            KILL_QUERY_PATH => Box::pin(async move {
+                let method = UnaryMethod(|request| {
+                    let service = service.clone();
+                    async move { service.kill_query(request).await }
+                });
+                Ok(Grpc::new(ProstCodec::default()).unary(method, request).await)
+            }),
+            _ => Box::pin(async { Ok(unimplemented_response()) }),
+        }
+    }
+}
+
+impl NamedService for AdminService {
+    const NAME: &'static str = "typedb.admin.Admin";
+}
+
+fn check_admin<T>(request: &Request<T>) -> Result<(), Status> {
+    match PermissionManager::exec_transactions_manage_permitted(&extract_username_field(request.metadata())) {
+        true => Ok(()),
+        false => Err(into_status(AdminServiceError::OperationNotPermitted {})),
+    }
+}
+
+fn parse_transaction_id(id: &[u8]) -> Result<Uuid, Status> {
+    Uuid::from_slice(id).map_err(|_| into_status(AdminServiceError::TransactionNotFound { id: format!("{id:?}") }))
+}
+
+fn into_status(error: AdminServiceError) -> Status {
+    error.into_error_message().into_status()
+}
+
+fn transaction_info_message(transaction: &TransactionInfo) -> TransactionInfoMessage {
+    let queries = transaction
+        .queries
+        .iter()
+        .map(|query| QueryInfoMessage {
+            id: query.id.as_bytes().to_vec(),
+            query: query.query.clone(),
+            elapsed_millis: query.elapsed.as_millis() as u64,
+        })
+        .collect();
+    TransactionInfoMessage {
+        id: transaction.id.as_bytes().to_vec(),
+        database: transaction.database.clone(),
+        user: transaction.user.clone(),
+        transaction_type: transaction.transaction_type.to_owned(),
+        opened_at_millis: transaction.opened_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+        open_sequence_number: transaction.open_sequence_number.number(),
+        queries,
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ListTransactionsReq {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ListTransactionsRes {
+    #[prost(message, repeated, tag = "1")]
+    pub(crate) transactions: Vec<TransactionInfoMessage>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct TransactionInfoMessage {
+    #[prost(bytes = "vec", tag = "1")]
+    pub(crate) id: Vec<u8>,
+    #[prost(string, tag = "2")]
+    pub(crate) database: String,
+    #[prost(string, tag = "3")]
+    pub(crate) user: String,
+    #[prost(string, tag = "4")]
+    pub(crate) transaction_type: String,
+    #[prost(uint64, tag = "5")]
+    pub(crate) opened_at_millis: u64,
+    #[prost(uint64, tag = "6")]
+    pub(crate) open_sequence_number: u64,
+    #[prost(message, repeated, tag = "7")]
+    pub(crate) queries: Vec<QueryInfoMessage>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct QueryInfoMessage {
+    #[prost(bytes = "vec", tag = "1")]
+    pub(crate) id: Vec<u8>,
+    #[prost(string, tag = "2")]
+    pub(crate) query: String,
+    #[prost(uint64, tag = "3")]
+    pub(crate) elapsed_millis: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct KillTransactionReq {
+    #[prost(bytes = "vec", tag = "1")]
+    pub(crate) transaction_id: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct KillTransactionRes {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct KillQueryReq {
+    #[prost(bytes = "vec", tag = "1")]
+    pub(crate) transaction_id: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub(crate) query_id: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct KillQueryRes {}
+
+typedb_error!(
+    AdminServiceError(component = "Admin service", prefix = "ADM") {
+        OperationNotPermitted(1, "The user is not permitted to execute the operation."),
+        TransactionNotFound(2, "Transaction '{id}' is not open on the server.", id: String),
+        QueryNotFound(3, "Query '{id}' is not running in the transaction.", id: String),
+        WriteQueryCannotBeKilled(
+            4,
+            "Query '{id}' is a write query, which cannot be killed alone. Kill its transaction instead.",
+            id: String
+        ),
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use resource::constants::server::{AUTHENTICATOR_USERNAME_FIELD, DEFAULT_USER_NAME};
+    use tonic_types::StatusExt;
+    use typedb_protocol::transaction::Type;
+
+    use super::*;
+    use crate::service::test_client::TestServer;
+
+    fn admin_request<T>(message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.metadata_mut().insert(AUTHENTICATOR_USERNAME_FIELD, DEFAULT_USER_NAME.parse().unwrap());
+        request
+    }
+
+    #[tokio::test]
+    async fn admins_list_and_kill_transactions_over_grpc() {
+        let server = TestServer::start().await;
+        server.database_manager.create_database("admin_grpc").unwrap();
+        let service = AdminService::new(server.transaction_registry.clone());
+        let mut transaction = server.open_transaction("admin_grpc", Type::Read).await;
+
+        let listed = service.list_transactions(admin_request(ListTransactionsReq {})).await.unwrap().into_inner();
+        assert_eq!(listed.transactions.len(), 1);
+        assert_eq!(listed.transactions[0].database, "admin_grpc");
+        assert_eq!(listed.transactions[0].user, DEFAULT_USER_NAME);
+        assert_eq!(listed.transactions[0].transaction_type, "read");
+        let id = listed.transactions[0].id.clone();
+
+        let unknown = KillTransactionReq { transaction_id: Uuid::new_v4().as_bytes().to_vec() };
+        let status = service.kill_transaction(admin_request(unknown)).await.unwrap_err();
+        assert_eq!(status.get_error_details().error_info().unwrap().reason, "ADM2");
+        let unknown_query = KillQueryReq { transaction_id: id.clone(), query_id: Uuid::new_v4().as_bytes().to_vec() };
+        let status = service.kill_query(admin_request(unknown_query)).await.unwrap_err();
+        assert_eq!(status.get_error_details().error_info().unwrap().reason, "ADM3");
+
+        service.kill_transaction(admin_request(KillTransactionReq { transaction_id: id })).await.unwrap();
+        let Some(Err(status)) = transaction.next_for(Uuid::new_v4()).await else {
+            panic!("expected the killed transaction's stream to fail");
+        };
+        assert_eq!(status.get_error_details().error_info().unwrap().reason, "TSV17");
+        let listed = service.list_transactions(admin_request(ListTransactionsReq {})).await.unwrap().into_inner();
+        assert!(listed.transactions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_admins_may_call_the_admin_service() {
+        let server = TestServer::start().await;
+        let service = AdminService::new(server.transaction_registry.clone());
+        let mut request = Request::new(ListTransactionsReq {});
+        request.metadata_mut().insert(AUTHENTICATOR_USERNAME_FIELD, "someone".parse().unwrap());
+        let status = service.list_transactions(request).await.unwrap_err();
+        assert_eq!(status.get_error_details().error_info().unwrap().reason, "ADM1");
+    }
+}
@@ -0,0 +1,509 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{iter, mem, sync::Arc};
+
+use answer::{variable_value::VariableValue, Thing};
+use arrow_array::{
+    types::IntervalMonthDayNano, ArrayRef, BinaryArray, BooleanArray, Date32Array, Decimal128Array, Float64Array,
+    Int64Array, IntervalMonthDayNanoArray, RecordBatch, RecordBatchOptions, StringArray, StructArray,
+    TimestampNanosecondArray, UnionArray,
+};
+use arrow_buffer::ScalarBuffer;
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{ArrowError, DataType, Field, Fields, IntervalUnit, Schema, TimeUnit, UnionFields, UnionMode};
+use chrono::{NaiveDate, NaiveDateTime};
+use compiler::VariablePosition;
+use concept::{error::ConceptReadError, thing::ThingAPI, type_::type_manager::TypeManager};
+use encoding::value::{
+    decimal_value::FRACTIONAL_PART_DENOMINATOR_LOG10, value::Value, value_type::ValueType, ValueEncodable,
+};
+use error::typedb_error;
+use executor::{batch::FIXED_BATCH_ROWS_MAX, pipeline::pipeline::ColumnType, row::MaybeOwnedRow};
+use storage::snapshot::ReadableSnapshot;
+
+use crate::service::transaction_service::StreamQueryOutputDescriptor;
+
+pub(crate) const ARROW_STREAM_CONTENT_TYPE: &str = "application/vnd.apache.arrow.stream";
+
+const DECIMAL_PRECISION: u8 = 38;
+const CONCEPT_IID_FIELD: &str = "iid";
+const CONCEPT_LABEL_FIELD: &str = "label";
+const CONCEPT_UNION_FIELD: &str = "concept";
+const UTC: &str = "UTC";
+const UNION_VALUE_TYPES: [ValueType; 9] = [
+    ValueType::Boolean,
+    ValueType::Long,
+    ValueType::Double,
+    ValueType::Decimal,
+    ValueType::Date,
+    ValueType::DateTime,
+    ValueType::DateTimeTZ,
+    ValueType::Duration,
+    ValueType::String,
+];
+
+/// Encodes concept rows as an Arrow IPC stream, in record batches of the executor's fixed batch size.
+///
+/// Each variable is a column, typed by what the query is known to answer there before it runs, so the schema is
+/// written ahead of the first row. Types and instances become a struct of their IID, which is null for types, and the
+/// label of the type. Values become the Arrow type of their value type: datetimes with time zones are normalised to
+/// UTC, and durations become month-day-nano intervals. A column that may hold more than one of these, such as a
+/// variable bound to either an attribute or a value, becomes a dense union of them all, in which empty entries are
+/// null concepts.
+pub(crate) struct ArrowRowsEncoder {
+    columns: Vec<(String, VariablePosition, DataType)>,
+    schema: Arc<Schema>,
+    writer: StreamWriter<Vec<u8>>,
+    cells: Vec<Vec<Cell>>,
+    rows: usize,
+}
+
+enum Cell {
+    Empty,
+    Concept { iid: Option<Vec<u8>>, label: String },
+    Value(Value<'static>),
+}
+
+impl ArrowRowsEncoder {
+    pub(crate) fn new(descriptor: &StreamQueryOutputDescriptor) -> Result<Self, ArrowEncodeError> {
+        let columns = descriptor
+            .iter()
+            .map(|(column, position, column_type)| {
+                Ok((column.clone(), *position, column_data_type(column, column_type)?))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let fields = columns
+            .iter()
+            .map(|(column, _, data_type)| Field::new(column, data_type.clone(), true))
+            .collect::<Vec<_>>();
+        let schema = Arc::new(Schema::new(fields));
+        let writer = StreamWriter::try_new(Vec::new(), &schema).map_err(arrow_error)?;
+        let cells = columns.iter().map(|_| Vec::new()).collect();
+        Ok(Self { columns, schema, writer, cells, rows: 0 })
+    }
+
+    pub(crate) fn push_row(
+        &mut self,
+        row: &MaybeOwnedRow<'_>,
+        snapshot: &impl ReadableSnapshot,
+        type_manager: &TypeManager,
+    ) -> Result<(), ArrowEncodeError> {
+        for ((column, position, data_type), cells) in self.columns.iter().zip(&mut self.cells) {
+            let cell = match row.get(*position) {
+                VariableValue::Empty => Cell::Empty,
+                VariableValue::Type(type_) => Cell::Concept {
+                    iid: None,
+                    label: type_
+                        .get_label(snapshot, type_manager)
+                        .map_err(|source| ArrowEncodeError::ConceptRead { source })?
+                        .scoped_name()
+                        .as_str()
+                        .to_owned(),
+                },
+                VariableValue::Thing(thing) => {
+                    let iid = match thing {
+                        Thing::Entity(entity) => Vec::from(entity.iid()),
+                        Thing::Relation(relation) => Vec::from(relation.iid()),
+                        Thing::Attribute(attribute) => Vec::from(attribute.iid()),
+                    };
+                    let label = thing
+                        .type_()
+                        .get_label(snapshot, type_manager)
+                        .map_err(|source| ArrowEncodeError::ConceptRead { source })?
+                        .scoped_name()
+                        .as_str()
+                        .to_owned();
+                    Cell::Concept { iid: Some(iid), label }
+                }
+                VariableValue::Value(value) => Cell::Value(value.clone().into_owned()),
+                VariableValue::ThingList(_) | VariableValue::ValueList(_) => {
+                    return Err(ArrowEncodeError::ListColumn { column: column.clone() })
+                }
+            };
+            check_cell(column, data_type, &cell)?;
+            cells.push(cell);
+        }
+        self.rows += 1;
+        if self.rows == FIXED_BATCH_ROWS_MAX as usize {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    /// The part of the stream encoded since the last call: the schema, then each full batch of rows.
+    pub(crate) fn take_encoded(&mut self) -> Vec<u8> {
+        mem::take(self.writer.get_mut())
+    }
+
+    /// Encodes the rows of the last, partial batch and the end of the stream, and returns what was not yet taken.
+    pub(crate) fn finish(mut self) -> Result<Vec<u8>, ArrowEncodeError> {
+        if self.rows > 0 {
+            self.write_batch()?;
+        }
+        self.writer.finish().map_err(arrow_error)?;
+        self.writer.into_inner().map_err(arrow_error)
+    }
+
+    fn write_batch(&mut self) -> Result<(), ArrowEncodeError> {
+        let arrays = self
+            .columns
+            .iter()
+            .zip(&mut self.cells)
+            .map(|((column, _, data_type), cells)| {
+                let cells = mem::take(cells);
+                column_array(column, &cells.iter().collect::<Vec<_>>(), data_type)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let options = RecordBatchOptions::new().with_row_count(Some(self.rows));
+        let batch = RecordBatch::try_new_with_options(self.schema.clone(), arrays, &options).map_err(arrow_error)?;
+        self.rows = 0;
+        self.writer.write(&batch).map_err(arrow_error)
+    }
+}
+
+fn column_data_type(column: &str, column_type: &ColumnType) -> Result<DataType, ArrowEncodeError> {
+    match column_type {
+        ColumnType::Concept => Ok(concept_data_type()),
+        ColumnType::Value(value_type) => {
+            value_data_type(value_type).ok_or_else(|| ArrowEncodeError::StructColumn { column: column.to_owned() })
+        }
+        ColumnType::List => Err(ArrowEncodeError::ListColumn { column: column.to_owned() }),
+        ColumnType::Unknown => Ok(DataType::Union(union_fields(), UnionMode::Dense)),
+    }
+}
+
+fn concept_data_type() -> DataType {
+    DataType::Struct(concept_fields())
+}
+
+fn concept_fields() -> Fields {
+    Fields::from(vec![
+        Field::new(CONCEPT_IID_FIELD, DataType::Binary, true),
+        Field::new(CONCEPT_LABEL_FIELD, DataType::Utf8, true),
+    ])
+}
+
+// The first member of a union is the concept struct, followed by each value type in the order of UNION_VALUE_TYPES
+fn union_fields() -> UnionFields {
+    let fields = iter::once(Field::new(CONCEPT_UNION_FIELD, concept_data_type(), true)).chain(
+        UNION_VALUE_TYPES
+            .iter()
+            .map(|value_type| Field::new(value_type.to_string(), value_data_type(value_type).unwrap(), true)),
+    );
+    UnionFields::new(0..=UNION_VALUE_TYPES.len() as i8, fields)
+}
+
+fn union_type_id(cell: &Cell) -> i8 {
+    match cell {
+        Cell::Empty | Cell::Concept { .. } => 0,
+        Cell::Value(value) => {
+            let value_type = value.value_type();
+            1 + UNION_VALUE_TYPES.iter().position(|union_value_type| *union_value_type == value_type).unwrap() as i8
+        }
+    }
+}
+
+fn value_data_type(value_type: &ValueType) -> Option<DataType> {
+    let data_type = match value_type {
+        ValueType::Boolean => DataType::Boolean,
+        ValueType::Long => DataType::Int64,
+        ValueType::Double => DataType::Float64,
+        ValueType::Decimal => DataType::Decimal128(DECIMAL_PRECISION, FRACTIONAL_PART_DENOMINATOR_LOG10 as i8),
+        ValueType::Date => DataType::Date32,
+        ValueType::DateTime => DataType::Timestamp(TimeUnit::Nanosecond, None),
+        ValueType::DateTimeTZ => DataType::Timestamp(TimeUnit::Nanosecond, Some(UTC.into())),
+        ValueType::Duration => DataType::Interval(IntervalUnit::MonthDayNano),
+        ValueType::String => DataType::Utf8,
+        ValueType::Struct(_) => return None,
+    };
+    Some(data_type)
+}
+
+fn check_cell(column: &str, data_type: &DataType, cell: &Cell) -> Result<(), ArrowEncodeError> {
+    let entry_data_type = match cell {
+        Cell::Empty => return Ok(()),
+        Cell::Concept { .. } if matches!(data_type, DataType::Struct(_) | DataType::Union(_, _)) => return Ok(()),
+        Cell::Concept { .. } => concept_data_type(),
+        Cell::Value(value) => value_data_type(&value.value_type())
+            .ok_or_else(|| ArrowEncodeError::StructColumn { column: column.to_owned() })?,
+    };
+    match data_type {
+        DataType::Union(_, _) => Ok(()),
+        _ if *data_type == entry_data_type => Ok(()),
+        _ => Err(ArrowEncodeError::UnexpectedEntry {
+            column: column.to_owned(),
+            data_type: data_type.to_string(),
+            entry_data_type: entry_data_type.to_string(),
+        }),
+    }
+}
+
+fn column_array(column: &str, cells: &[&Cell], data_type: &DataType) -> Result<ArrayRef, ArrowEncodeError> {
+    let values = || {
+        cells.iter().map(|cell| match cell {
+            Cell::Value(value) => Some(value),
+            Cell::Empty | Cell::Concept { .. } => None,
+        })
+    };
+    let out_of_range = || ArrowEncodeError::ValueOutOfRange { column: column.to_owned() };
+    let array: ArrayRef = match data_type {
+        DataType::Union(fields, _) => {
+            let type_ids = cells.iter().map(|cell| union_type_id(cell)).collect::<Vec<_>>();
+            let mut members: Vec<Vec<&Cell>> = fields.iter().map(|_| Vec::new()).collect();
+            let offsets = cells
+                .iter()
+                .zip(&type_ids)
+                .map(|(cell, type_id)| {
+                    let member = &mut members[*type_id as usize];
+                    member.push(*cell);
+                    member.len() as i32 - 1
+                })
+                .collect::<Vec<_>>();
+            let arrays = fields
+                .iter()
+                .zip(&members)
+                .map(|((_, field), cells)| column_array(column, cells, field.data_type()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let union = UnionArray::try_new(
+                fields.clone(),
+                ScalarBuffer::from(type_ids),
+                Some(ScalarBuffer::from(offsets)),
+                arrays,
+            )
+            .map_err(arrow_error)?;
+            Arc::new(union)
+        }
+        DataType::Struct(_) => {
+            let iids = cells.iter().map(|cell| match cell {
+                Cell::Concept { iid, .. } => iid.as_deref(),
+                Cell::Empty | Cell::Value(_) => None,
+            });
+            let labels = cells.iter().map(|cell| match cell {
+                Cell::Concept { label, .. } => Some(label.as_str()),
+                Cell::Empty | Cell::Value(_) => None,
+            });
+            let nulls = cells.iter().map(|cell| matches!(cell, Cell::Concept { .. })).collect::<Vec<_>>();
+            let arrays: Vec<ArrayRef> =
+                vec![Arc::new(iids.collect::<BinaryArray>()), Arc::new(labels.collect::<StringArray>())];
+            Arc::new(StructArray::try_new(concept_fields(), arrays, Some(nulls.into())).map_err(arrow_error)?)
+        }
+        DataType::Boolean => {
+            Arc::new(values().map(|value| value.map(|value| value.clone().unwrap_boolean())).collect::<BooleanArray>())
+        }
+        DataType::Int64 => {
+            Arc::new(values().map(|value| value.map(|value| value.clone().unwrap_long())).collect::<Int64Array>())
+        }
+        DataType::Float64 => {
+            Arc::new(values().map(|value| value.map(|value| value.clone().unwrap_double())).collect::<Float64Array>())
+        }
+        DataType::Decimal128(precision, scale) => {
+            let scale_factor = 10i128.pow(FRACTIONAL_PART_DENOMINATOR_LOG10);
+            let decimals = values()
+                .map(|value| {
+                    value.map(|value| {
+                        let decimal = value.clone().unwrap_decimal();
+                        decimal.integer_part() as i128 * scale_factor + decimal.fractional_part() as i128
+                    })
+                })
+                .collect::<Decimal128Array>();
+            Arc::new(decimals.with_precision_and_scale(*precision, *scale).map_err(arrow_error)?)
+        }
+        DataType::Date32 => {
+            let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+            let dates = values()
+                .map(|value| {
+                    value
+                        .map(|value| {
+                            let days = value.clone().unwrap_date().signed_duration_since(epoch).num_days();
+                            i32::try_from(days).map_err(|_| out_of_range())
+                        })
+                        .transpose()
+                })
+                .collect::<Result<Date32Array, _>>()?;
+            Arc::new(dates)
+        }
+        DataType::Timestamp(_, timezone) => {
+            let nanos = values()
+                .map(|value| {
+                    value
+                        .map(|value| {
+                            let datetime: NaiveDateTime = match value {
+                                Value::DateTimeTZ(datetime) => datetime.naive_utc(),
+                                _ => value.clone().unwrap_date_time(),
+                            };
+                            datetime.and_utc().timestamp_nanos_opt().ok_or_else(out_of_range)
+                        })
+                        .transpose()
+                })
+                .collect::<Result<TimestampNanosecondArray, _>>()?;
+            Arc::new(nanos.with_timezone_opt(timezone.clone()))
+        }
+        DataType::Interval(_) => {
+            let intervals = values()
+                .map(|value| {
+                    value
+                        .map(|value| {
+                            let duration = value.clone().unwrap_duration();
+                            Ok(IntervalMonthDayNano::new(
+                                i32::try_from(duration.months).map_err(|_| out_of_range())?,
+                                i32::try_from(duration.days).map_err(|_| out_of_range())?,
+                                i64::try_from(duration.nanos).map_err(|_| out_of_range())?,
+                            ))
+                        })
+                        .transpose()
+                })
+                .collect::<Result<IntervalMonthDayNanoArray, _>>()?;
+            Arc::new(intervals)
+        }
+        DataType::Utf8 => Arc::new(
+            values()
+                .map(|value| value.map(|value| value.clone().unwrap_string().into_owned()))
+                .collect::<StringArray>(),
+        ),
+        _ => unreachable!("Column data types are only created from value types, concepts and their union."),
+    };
+    Ok(array)
+}
+
+fn arrow_error(source: ArrowError) -> ArrowEncodeError {
+    ArrowEncodeError::Arrow { source: Arc::new(source) }
+}
+
+typedb_error!(
+    pub(crate) ArrowEncodeError(component = "Arrow encoding", prefix = "ARW") {
+        Arrow(1, "Failed to write the Arrow stream.", ( source: Arc<ArrowError> )),
+        ConceptRead(2, "Failed to read a concept of the answer.", ( source: Box<ConceptReadError> )),
+        UnexpectedEntry(
+            3,
+            "Column '{column}' of Arrow type {data_type} holds an entry of Arrow type {entry_data_type}.",
+            column: String,
+            data_type: String,
+            entry_data_type: String
+        ),
+        ListColumn(4, "Column '{column}' holds a list, which the Arrow format does not support yet.", column: String),
+        StructColumn(
+            5,
+            "Column '{column}' holds a struct value, which the Arrow format does not support yet.",
+            column: String
+        ),
+        ValueOutOfRange(6, "Column '{column}' holds a value outside the range of its Arrow type.", column: String),
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, io::Cursor, sync::Arc};
+
+    use answer::{variable_value::VariableValue, Type};
+    use arrow_array::{
+        cast::AsArray,
+        types::{Float64Type, Int64Type},
+        Array,
+    };
+    use arrow_ipc::reader::StreamReader;
+    use arrow_schema::{DataType, UnionMode};
+    use compiler::VariablePosition;
+    use database::{database_manager::DatabaseManager, transaction::TransactionSchema};
+    use encoding::value::{label::Label, value::Value, value_type::ValueType};
+    use executor::{batch::FIXED_BATCH_ROWS_MAX, pipeline::pipeline::ColumnType, row::MaybeOwnedRow};
+    use options::TransactionOptions;
+    use test_utils::create_tmp_dir;
+
+    use super::{ArrowEncodeError, ArrowRowsEncoder};
+
+    #[test]
+    fn rows_round_trip_through_the_arrow_stream() {
+        let data_directory = create_tmp_dir();
+        let database_manager = DatabaseManager::new(&data_directory).unwrap();
+        database_manager.create_database("arrow").unwrap();
+        let database = database_manager.database("arrow").unwrap();
+        let mut transaction = TransactionSchema::open(database, TransactionOptions::default()).unwrap();
+        let snapshot = Arc::get_mut(&mut transaction.snapshot).unwrap();
+        let person = transaction.type_manager.create_entity_type(snapshot, &Label::build("person")).unwrap();
+
+        let descriptor = vec![
+            ("age".to_owned(), VariablePosition::new(0), ColumnType::Value(ValueType::Long)),
+            ("name".to_owned(), VariablePosition::new(1), ColumnType::Value(ValueType::String)),
+            ("number".to_owned(), VariablePosition::new(2), ColumnType::Unknown),
+            ("type".to_owned(), VariablePosition::new(3), ColumnType::Concept),
+        ];
+        let batch_rows = FIXED_BATCH_ROWS_MAX as usize;
+        let row_count = 2 * batch_rows + 1;
+        let mut encoder = ArrowRowsEncoder::new(&descriptor).unwrap();
+        let mut encoded = encoder.take_encoded();
+        assert!(!encoded.is_empty(), "the schema is written before any row");
+        for i in 0..row_count {
+            let number = match i % 3 {
+                0 => VariableValue::Value(Value::Long(i as i64)),
+                1 => VariableValue::Value(Value::Double(i as f64 + 0.5)),
+                _ => VariableValue::Empty,
+            };
+            let row = vec![
+                VariableValue::Value(Value::Long(i as i64)),
+                VariableValue::Value(Value::String(Cow::Owned(format!("person {i}")))),
+                number,
+                VariableValue::Type(Type::Entity(person.clone())),
+            ];
+            let row = MaybeOwnedRow::new_owned(row, 1);
+            encoder.push_row(&row, transaction.snapshot.as_ref(), &transaction.type_manager).unwrap();
+            let taken = encoder.take_encoded();
+            assert_eq!(!taken.is_empty(), (i + 1) % batch_rows == 0, "a batch is encoded once it is full");
+            encoded.extend(taken);
+        }
+        encoded.extend(encoder.finish().unwrap());
+
+        let reader = StreamReader::try_new(Cursor::new(encoded), None).unwrap();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(batches.iter().map(|batch| batch.num_rows()).collect::<Vec<_>>(), vec![batch_rows, batch_rows, 1]);
+        let schema = batches[0].schema();
+        assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+        assert_eq!(schema.field(1).data_type(), &DataType::Utf8);
+        assert!(matches!(schema.field(2).data_type(), DataType::Union(_, UnionMode::Dense)));
+        assert!(matches!(schema.field(3).data_type(), DataType::Struct(_)));
+
+        let mut i = 0;
+        for batch in &batches {
+            let ages = batch.column(0).as_primitive::<Int64Type>();
+            let names = batch.column(1).as_string::<i32>();
+            let numbers = batch.column(2).as_union();
+            let types = batch.column(3).as_struct();
+            for row in 0..batch.num_rows() {
+                assert_eq!(ages.value(row), i as i64);
+                assert_eq!(names.value(row), format!("person {i}"));
+                let number = numbers.value(row);
+                match i % 3 {
+                    0 => assert_eq!(number.as_primitive::<Int64Type>().value(0), i as i64),
+                    1 => assert_eq!(number.as_primitive::<Float64Type>().value(0), i as f64 + 0.5),
+                    _ => assert!(number.is_null(0)),
+                }
+                assert!(types.column(0).is_null(row));
+                assert_eq!(types.column(1).as_string::<i32>().value(row), "person");
+                i += 1;
+            }
+        }
+        assert_eq!(i, row_count);
+    }
+
+    #[test]
+    fn entries_must_match_the_column_type() {
+        let data_directory = create_tmp_dir();
+        let database_manager = DatabaseManager::new(&data_directory).unwrap();
+        database_manager.create_database("arrow").unwrap();
+        let transaction =
+            TransactionSchema::open(database_manager.database("arrow").unwrap(), TransactionOptions::default())
+                .unwrap();
+
+        let descriptor = vec![("age".to_owned(), VariablePosition::new(0), ColumnType::Value(ValueType::Long))];
+        let mut encoder = ArrowRowsEncoder::new(&descriptor).unwrap();
+        let row = MaybeOwnedRow::new_owned(vec![VariableValue::Value(Value::Double(1.5))], 1);
+        let result = encoder.push_row(&row, transaction.snapshot.as_ref(), &transaction.type_manager);
+        assert!(matches!(result, Err(ArrowEncodeError::UnexpectedEntry { .. })));
+
+        let lists = vec![("ages".to_owned(), VariablePosition::new(0), ColumnType::List)];
+        assert!(matches!(ArrowRowsEncoder::new(&lists), Err(ArrowEncodeError::ListColumn { .. })));
+    }
+}
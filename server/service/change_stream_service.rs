@@ -0,0 +1,347 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use concept::{
+    change_stream::{ChangeEvent, ChangeStreamError, CommittedChanges},
+    thing::ThingAPI,
+};
+use database::{change_subscription::ChangeSubscription, database_manager::DatabaseManager};
+use encoding::graph::type_::vertex::TypeVertexEncoding;
+use error::typedb_error;
+use resource::constants::server::{CHANGE_STREAM_BATCH_COMMITS, CHANGE_STREAM_POLL_INTERVAL};
+use storage::{durability_client::WALClient, isolation_manager::CommitType, sequence_number::SequenceNumber};
+use tokio::{sync::mpsc, task::spawn_blocking};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{
+    body::BoxBody,
+    codec::ProstCodec,
+    codegen::{http, Body, BoxFuture, Service, StdError},
+    server::{Grpc, NamedService},
+    Request, Response, Status,
+};
+
+use crate::service::{
+    error::{IntoGRPCStatus, IntoProtocolErrorMessage},
+    grpc::{unimplemented_response, ServerStreamingMethod},
+};
+
+const SUBSCRIBE_PATH: &str = "/typedb.changes.Changes/Subscribe";
+
+/// The `typedb.changes.Changes` gRPC service, which streams the changes committed to a database, one message per
+/// commit, until the client cancels the call. Without `from`, only new commits are streamed. A client resumes after
+/// the `sequence_number` of the last message it received by subscribing again from the next sequence number.
+#[derive(Debug, Clone)]
+pub(crate) struct ChangeStreamService {
+    database_manager: Arc<DatabaseManager>,
+}
+
+impl ChangeStreamService {
+    pub(crate) fn new(database_manager: Arc<DatabaseManager>) -> Self {
+        Self { database_manager }
+    }
+
+    async fn subscribe(
+        &self,
+        request: Request<SubscribeReq>,
+    ) -> Result<Response<ReceiverStream<Result<CommittedChangesRes, Status>>>, Status> {
+        let SubscribeReq { database, from } = request.into_inner();
+        let database = self
+            .database_manager
+            .database(&database)
+            .ok_or_else(|| into_status(ChangeStreamServiceError::DatabaseNotFound { name: database }))?;
+        let mut subscription = match from {
+            None => ChangeSubscription::from_latest(database),
+            Some(from) => ChangeSubscription::new(database, SequenceNumber::new(from)),
+        };
+        let (sender, receiver) = mpsc::channel(CHANGE_STREAM_BATCH_COMMITS);
+        tokio::spawn(async move {
+            loop {
+                let (returned, polled) = poll_changes(subscription).await;
+                subscription = returned;
+                match polled {
+                    Ok(changes) if changes.is_empty() => {
+                        tokio::select! {
+                            _ = tokio::time::sleep(CHANGE_STREAM_POLL_INTERVAL) => (),
+                            _ = sender.closed() => return,
+                        }
+                    }
+                    Ok(changes) => {
+                        for committed in &changes {
+                            if sender.send(Ok(committed_changes_message(committed))).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Err(typedb_source) => {
+                        let error = ChangeStreamServiceError::ChangeStreamFailed { typedb_source };
+                        let _ = sender.send(Err(into_status(error))).await;
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+impl<B> Service<http::Request<B>> for ChangeStreamService
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let service = self.clone();
+        match request.uri().path() {
+            SUBSCRIBE_PATH => Box::pin(async move {
+                let method = ServerStreamingMethod(|request| {
+                    let service = service.clone();
+                    async move { service.subscribe(request).await }
+                });
+                Ok(Grpc::new(ProstCodec::default()).server_streaming(method, request).await)
+            }),
+            _ => Box::pin(async { Ok(unimplemented_response()) }),
+        }
+    }
+}
+
+impl NamedService for ChangeStreamService {
+    const NAME: &'static str = "typedb.changes.Changes";
+}
+
+/// Polls up to `CHANGE_STREAM_BATCH_COMMITS` commits on the blocking pool, handing the subscription back with them
+pub(crate) async fn poll_changes(
+    mut subscription: ChangeSubscription<WALClient>,
+) -> (ChangeSubscription<WALClient>, Result<Vec<CommittedChanges>, ChangeStreamError>) {
+    spawn_blocking(move || {
+        let mut changes = Vec::new();
+        while changes.len() < CHANGE_STREAM_BATCH_COMMITS {
+            match subscription.poll() {
+                Ok(Some(committed)) => changes.push(committed),
+                Ok(None) => break,
+                Err(err) => return (subscription, Err(err)),
+            }
+        }
+        (subscription, Ok(changes))
+    })
+    .await
+    .unwrap()
+}
+
+fn into_status(error: ChangeStreamServiceError) -> Status {
+    error.into_error_message().into_status()
+}
+
+fn committed_changes_message(changes: &CommittedChanges) -> CommittedChangesRes {
+    let events = changes
+        .events
+        .iter()
+        .map(|event| {
+            let (kind, event) = match event {
+                ChangeEvent::EntityCreated { entity } => (
+                    ChangeEventKind::EntityCreated,
+                    ChangeEventMessage { iid: entity.iid().to_vec(), ..Default::default() },
+                ),
+                ChangeEvent::EntityDeleted { entity } => (
+                    ChangeEventKind::EntityDeleted,
+                    ChangeEventMessage { iid: entity.iid().to_vec(), ..Default::default() },
+                ),
+                ChangeEvent::RelationCreated { relation } => (
+                    ChangeEventKind::RelationCreated,
+                    ChangeEventMessage { iid: relation.iid().to_vec(), ..Default::default() },
+                ),
+                ChangeEvent::RelationDeleted { relation } => (
+                    ChangeEventKind::RelationDeleted,
+                    ChangeEventMessage { iid: relation.iid().to_vec(), ..Default::default() },
+                ),
+                ChangeEvent::AttributeCreated { attribute } => (
+                    ChangeEventKind::AttributeCreated,
+                    ChangeEventMessage { iid: attribute.iid().to_vec(), ..Default::default() },
+                ),
+                ChangeEvent::AttributeDeleted { attribute } => (
+                    ChangeEventKind::AttributeDeleted,
+                    ChangeEventMessage { iid: attribute.iid().to_vec(), ..Default::default() },
+                ),
+                ChangeEvent::HasAdded { owner, attribute } => (
+                    ChangeEventKind::HasAdded,
+                    ChangeEventMessage {
+                        owner: owner.iid().to_vec(),
+                        attribute: attribute.iid().to_vec(),
+                        ..Default::default()
+                    },
+                ),
+                ChangeEvent::HasRemoved { owner, attribute } => (
+                    ChangeEventKind::HasRemoved,
+                    ChangeEventMessage {
+                        owner: owner.iid().to_vec(),
+                        attribute: attribute.iid().to_vec(),
+                        ..Default::default()
+                    },
+                ),
+                ChangeEvent::LinksAdded { relation, player, role_type } => (
+                    ChangeEventKind::LinksAdded,
+                    ChangeEventMessage {
+                        relation: relation.iid().to_vec(),
+                        player: player.iid().to_vec(),
+                        role_type_id: role_type.vertex().type_id_().as_u16() as u32,
+                        ..Default::default()
+                    },
+                ),
+                ChangeEvent::LinksRemoved { relation, player, role_type } => (
+                    ChangeEventKind::LinksRemoved,
+                    ChangeEventMessage {
+                        relation: relation.iid().to_vec(),
+                        player: player.iid().to_vec(),
+                        role_type_id: role_type.vertex().type_id_().as_u16() as u32,
+                        ..Default::default()
+                    },
+                ),
+                ChangeEvent::SchemaChanged => (ChangeEventKind::SchemaChanged, ChangeEventMessage::default()),
+                ChangeEvent::BulkLoaded => (ChangeEventKind::BulkLoaded, ChangeEventMessage::default()),
+            };
+            ChangeEventMessage { kind: kind as i32, ..event }
+        })
+        .collect();
+    let commit_type = match changes.commit_type {
+        CommitType::Data => CommitTypeMessage::Data,
+        CommitType::Schema => CommitTypeMessage::Schema,
+        CommitType::BulkLoad => CommitTypeMessage::BulkLoad,
+    };
+    CommittedChangesRes { sequence_number: changes.sequence_number.number(), commit_type: commit_type as i32, events }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct SubscribeReq {
+    #[prost(string, tag = "1")]
+    pub(crate) database: String,
+    #[prost(uint64, optional, tag = "2")]
+    pub(crate) from: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct CommittedChangesRes {
+    #[prost(uint64, tag = "1")]
+    pub(crate) sequence_number: u64,
+    #[prost(enumeration = "CommitTypeMessage", tag = "2")]
+    pub(crate) commit_type: i32,
+    #[prost(message, repeated, tag = "3")]
+    pub(crate) events: Vec<ChangeEventMessage>,
+}
+
+/// One change. Created and deleted instances set `iid`, ownerships set `owner` and `attribute`, and role players set
+/// `relation`, `player` and `role_type_id`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ChangeEventMessage {
+    #[prost(enumeration = "ChangeEventKind", tag = "1")]
+    pub(crate) kind: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub(crate) iid: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub(crate) owner: Vec<u8>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub(crate) attribute: Vec<u8>,
+    #[prost(bytes = "vec", tag = "5")]
+    pub(crate) relation: Vec<u8>,
+    #[prost(bytes = "vec", tag = "6")]
+    pub(crate) player: Vec<u8>,
+    #[prost(uint32, tag = "7")]
+    pub(crate) role_type_id: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub(crate) enum CommitTypeMessage {
+    Data = 0,
+    Schema = 1,
+    BulkLoad = 2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub(crate) enum ChangeEventKind {
+    EntityCreated = 0,
+    EntityDeleted = 1,
+    RelationCreated = 2,
+    RelationDeleted = 3,
+    AttributeCreated = 4,
+    AttributeDeleted = 5,
+    HasAdded = 6,
+    HasRemoved = 7,
+    LinksAdded = 8,
+    LinksRemoved = 9,
+    SchemaChanged = 10,
+    BulkLoaded = 11,
+}
+
+typedb_error!(
+    ChangeStreamServiceError(component = "Change stream service", prefix = "CSS") {
+        DatabaseNotFound(1, "Database '{name}' not found.", name: String),
+        ChangeStreamFailed(2, "Reading the database's committed changes failed.", ( typedb_source: ChangeStreamError )),
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::StreamExt;
+    use tonic_types::StatusExt;
+    use typedb_protocol::transaction::Type;
+
+    use super::*;
+    use crate::service::test_client::TestServer;
+
+    fn subscribe_request(database: &str, from: Option<u64>) -> Request<SubscribeReq> {
+        Request::new(SubscribeReq { database: database.to_owned(), from })
+    }
+
+    #[tokio::test]
+    async fn streams_commits_and_resumes_from_a_sequence_number() {
+        let server = TestServer::start().await;
+        server.database_manager.create_database("changes_grpc").unwrap();
+        let service = ChangeStreamService::new(server.database_manager.clone());
+        let mut stream = service.subscribe(subscribe_request("changes_grpc", None)).await.unwrap().into_inner();
+
+        let mut schema = server.open_transaction("changes_grpc", Type::Schema).await;
+        schema.query("define entity person;").await;
+        schema.commit(&[]).await.unwrap();
+        let schema_changes = stream.next().await.unwrap().unwrap();
+        assert_eq!(schema_changes.commit_type, CommitTypeMessage::Schema as i32);
+
+        let mut write = server.open_transaction("changes_grpc", Type::Write).await;
+        write.query("insert $p isa person;").await;
+        write.commit(&[]).await.unwrap();
+        let data_changes = stream.next().await.unwrap().unwrap();
+        assert_eq!(data_changes.commit_type, CommitTypeMessage::Data as i32);
+        assert_eq!(data_changes.events.len(), 1);
+        assert_eq!(data_changes.events[0].kind, ChangeEventKind::EntityCreated as i32);
+        assert!(!data_changes.events[0].iid.is_empty());
+        drop(stream);
+
+        let from = schema_changes.sequence_number + 1;
+        let mut resumed = service.subscribe(subscribe_request("changes_grpc", Some(from))).await.unwrap().into_inner();
+        assert_eq!(resumed.next().await.unwrap().unwrap(), data_changes);
+    }
+
+    #[tokio::test]
+    async fn subscribing_to_a_missing_database_fails() {
+        let server = TestServer::start().await;
+        let service = ChangeStreamService::new(server.database_manager.clone());
+        let status = service.subscribe(subscribe_request("missing", None)).await.unwrap_err();
+        assert_eq!(status.get_error_details().error_info().unwrap().reason, "CSS1");
+    }
+}
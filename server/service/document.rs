@@ -11,6 +11,7 @@ use concept::{error::ConceptReadError, thing::thing_manager::ThingManager, type_
 use executor::document::{ConceptDocument, DocumentLeaf, DocumentList, DocumentMap, DocumentNode};
 use ir::pipeline::ParameterRegistry;
 use itertools::Itertools;
+use resource::constants::server::QUERY_PROFILE_COLUMN;
 use storage::snapshot::ReadableSnapshot;
 
 use crate::service::concept::{
@@ -29,6 +30,26 @@ pub(crate) fn encode_document(
     })
 }
 
+/// The final document of a profiled fetch query, holding the profile under a `profile` key
+pub(crate) fn encode_profile_document(profile: String) -> typedb_protocol::ConceptDocument {
+    let profile = typedb_protocol::Value { value: Some(typedb_protocol::value::Value::String(profile)) };
+    let leaf = typedb_protocol::concept_document::Node {
+        node: Some(typedb_protocol::concept_document::node::Node::Leaf(
+            typedb_protocol::concept_document::node::Leaf {
+                leaf: Some(typedb_protocol::concept_document::node::leaf::Leaf::Value(profile)),
+            },
+        )),
+    };
+    let map = HashMap::from([(QUERY_PROFILE_COLUMN.to_owned(), leaf)]);
+    typedb_protocol::ConceptDocument {
+        root: Some(typedb_protocol::concept_document::Node {
+            node: Some(typedb_protocol::concept_document::node::Node::Map(
+                typedb_protocol::concept_document::node::Map { map },
+            )),
+        }),
+    }
+}
+
 fn encode_node(
     node: DocumentNode,
     snapshot: &impl ReadableSnapshot,
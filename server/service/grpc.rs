@@ -0,0 +1,57 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+// Plumbing for the gRPC services the server defines itself, rather than generating from typedb-protocol. Each
+// service routes a request by its path to a method adapted here, and answers unknown paths as unimplemented.
+
+use std::future::Future;
+
+use tonic::{
+    body::BoxBody,
+    codegen::{empty_body, http},
+    server::{ServerStreamingService, UnaryService},
+    Code, Request, Response, Status,
+};
+
+pub(super) struct UnaryMethod<F>(pub(super) F);
+
+impl<F, Req, Res, Fut> UnaryService<Req> for UnaryMethod<F>
+where
+    F: FnMut(Request<Req>) -> Fut,
+    Fut: Future<Output = Result<Response<Res>, Status>>,
+{
+    type Response = Res;
+    type Future = Fut;
+
+    fn call(&mut self, request: Request<Req>) -> Self::Future {
+        (self.0)(request)
+    }
+}
+
+pub(super) struct ServerStreamingMethod<F>(pub(super) F);
+
+impl<F, Req, Res, Stream, Fut> ServerStreamingService<Req> for ServerStreamingMethod<F>
+where
+    F: FnMut(Request<Req>) -> Fut,
+    Stream: tokio_stream::Stream<Item = Result<Res, Status>>,
+    Fut: Future<Output = Result<Response<Stream>, Status>>,
+{
+    type Response = Res;
+    type ResponseStream = Stream;
+    type Future = Fut;
+
+    fn call(&mut self, request: Request<Req>) -> Self::Future {
+        (self.0)(request)
+    }
+}
+
+pub(super) fn unimplemented_response() -> http::Response<BoxBody> {
+    let mut response = http::Response::new(empty_body());
+    let headers = response.headers_mut();
+    headers.insert(Status::GRPC_STATUS, (Code::Unimplemented as i32).into());
+    headers.insert(http::header::CONTENT_TYPE, tonic::metadata::GRPC_CONTENT_TYPE);
+    response
+}
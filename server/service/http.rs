@@ -0,0 +1,88 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{collections::HashMap, io};
+
+use tokio::{io::AsyncReadExt, net::TcpStream};
+
+pub(crate) const MAX_REQUEST_HEAD_BYTES: usize = 8 * 1024;
+
+/// One HTTP/1.1 request, as read by `read_request`. Header names are lower-cased.
+#[derive(Debug)]
+pub(crate) struct HttpRequest {
+    pub(crate) method: String,
+    pub(crate) path: String,
+    pub(crate) headers: HashMap<String, String>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// The request path without its query string
+    pub(crate) fn route(&self) -> &str {
+        self.path.split('?').next().unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RequestTooLarge {
+    pub(crate) limit: usize,
+}
+
+/// Read one request from a connection, shared by the HTTP endpoints of the server. Answers `None` if the client
+/// disconnects or sends a malformed or oversized request head, which is dropped without a response.
+pub(crate) async fn read_request(
+    stream: &mut TcpStream,
+    max_body_bytes: usize,
+) -> io::Result<Option<Result<HttpRequest, RequestTooLarge>>> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 4096];
+    let head_end = loop {
+        if let Some(position) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position;
+        }
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_HEAD_BYTES {
+            return Ok(None);
+        }
+        request.extend_from_slice(&buffer[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&request[..head_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(path)) = (request_line.next(), request_line.next()) else {
+        return Ok(None);
+    };
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_owned()))
+        .collect();
+
+    let content_length = headers.get("content-length").and_then(|length| length.parse().ok()).unwrap_or(0);
+    if content_length > max_body_bytes {
+        return Ok(Some(Err(RequestTooLarge { limit: max_body_bytes })));
+    }
+    let mut body = request.split_off(head_end + 4);
+    while body.len() < content_length {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        body.extend_from_slice(&buffer[..read]);
+    }
+    body.truncate(content_length);
+    Ok(Some(Ok(HttpRequest { method: method.to_owned(), path: path.to_owned(), headers, body })))
+}
+
+pub(crate) fn http_response(status: &str, content_type: &str, body: &[u8]) -> Vec<u8> {
+    let mut response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )
+    .into_bytes();
+    response.extend_from_slice(body);
+    response
+}
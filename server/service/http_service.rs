@@ -0,0 +1,1546 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use concept::{
+    change_stream::ChangeStreamError, error::ConceptReadError, thing::thing_manager::ThingManager,
+    type_::type_manager::TypeManager,
+};
+use database::{
+    change_subscription::ChangeSubscription,
+    database::DatabaseCreateError,
+    database_manager::DatabaseManager,
+    transaction::{
+        BulkLoadCommitError, DataCommitError, SchemaCommitError, TransactionBulkLoad, TransactionError,
+        TransactionRead, TransactionSchema, TransactionWrite,
+    },
+    Database, DatabaseDeleteError,
+};
+use error::typedb_error;
+use executor::{
+    batch::Batch, pipeline::PipelineExecutionError, profile::QueryProfile, row::MaybeOwnedRow, ExecutionInterrupt,
+    InterruptType,
+};
+use function::function_manager::FunctionManager;
+use lending_iterator::LendingIterator;
+use options::{QueryOptions, SortSpillOptions, TransactionOptions};
+use query::{error::QueryError, query_manager::QueryManager};
+use resource::constants::server::{
+    AUTHENTICATOR_PASSWORD_FIELD, AUTHENTICATOR_USERNAME_FIELD, CHANGE_STREAM_POLL_INTERVAL,
+    HTTP_ARROW_STREAM_BUFFERED_BATCHES, HTTP_TRANSACTION_IDLE_TIMEOUT,
+};
+use serde_json::{json, Value as JSON};
+use storage::{
+    durability_client::WALClient,
+    sequence_number::SequenceNumber,
+    snapshot::{ReadableSnapshot, WritableSnapshot},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, Notify},
+    task::{spawn_blocking, JoinHandle},
+};
+use tracing::{event, Level};
+use typeql::{parse_query, query::SchemaQuery, Query};
+use user::permission_manager::PermissionManager;
+use uuid::Uuid;
+
+use crate::{
+    authenticator::Authenticator,
+    service::{
+        arrow::{ArrowEncodeError, ArrowRowsEncoder, ARROW_STREAM_CONTENT_TYPE},
+        change_stream_service::poll_changes,
+        document::encode_document,
+        error::IntoProtocolErrorMessage,
+        http::{http_response, read_request, HttpRequest, RequestTooLarge},
+        json::{encode_changes_json, encode_document_json, encode_profile_json, encode_row_json},
+        row::encode_row,
+        transaction_registry::{QueryInterrupt, TransactionInfo, TransactionRegistry},
+        transaction_service::{output_descriptor, StreamQueryOutputDescriptor, Transaction, TransactionService},
+    },
+};
+
+const MAX_REQUEST_BODY_BYTES: usize = 16 * 1024 * 1024;
+const JSON_CONTENT_TYPE: &str = "application/json";
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Serves a JSON API over plain HTTP for clients that cannot speak gRPC. Requests are authenticated with the same
+/// `username` and `password` the gRPC service reads from its metadata, passed here as headers.
+///
+/// - `GET /v1/databases` lists the databases, and `GET`, `POST` and `DELETE` on `/v1/databases/{name}` check for,
+///   create and delete one.
+/// - `POST /v1/query` runs one query in a new transaction, given `database`, `transactionType` and `query`. Queries in
+///   write and schema transactions are committed, unless `commit` is `false`.
+/// - `POST /v1/transactions/open`, given `database` and `transactionType`, opens a transaction and answers its
+///   `transactionId`. `POST /v1/transactions/{id}/query` runs a `query` in it, and `commit`, `rollback` and `close`
+///   end it. Transactions left idle are closed after `HTTP_TRANSACTION_IDLE_TIMEOUT`.
+/// - `GET /v1/admin/transactions` lists the transactions opened through gRPC or `POST /v1/transactions/open`, and the
+///   queries they are running. `POST /v1/admin/transactions/{id}/kill` closes a transaction, and
+///   `POST /v1/admin/transactions/{id}/queries/{queryId}/kill` interrupts one read query. Only the admin user may use
+///   these.
+/// - `POST /v1/databases/{name}/bulk-load`, given a list of write `queries`, runs them in a bulk-load transaction,
+///   which ingests their writes in batches without logging them. Batches ingested before a failed query remain loaded.
+/// - `GET /v1/databases/{name}/changes?from={sequenceNumber}` streams the changes committed to a database as a chunked
+///   response of newline-delimited JSON, one commit per line, until the client disconnects. Without `from`, only new
+///   commits are streamed. A client resumes after the `sequenceNumber` of the last line it received.
+///
+/// Queries answer JSON by default. Given `"format": "arrow"`, concept rows are instead answered as an Arrow IPC stream,
+/// sent as a chunked response one record batch at a time while the query runs. A query that fails after its first
+/// batch was sent ends the response without its last chunk. Given `"profile": true`, a query is profiled and its JSON
+/// answer carries the measurements under `profile`. Given `"explain": true`, a pipeline is compiled but not executed,
+/// and answers its `plan` instead.
+#[derive(Debug)]
+pub(crate) struct HttpService {
+    database_manager: Arc<DatabaseManager>,
+    authenticator: Arc<Authenticator>,
+    transaction_registry: Arc<TransactionRegistry>,
+    query_memory_limit_bytes: Option<u64>,
+    query_sort_spill: Option<SortSpillOptions>,
+    transactions: Arc<Mutex<HashMap<Uuid, OpenTransaction>>>,
+}
+
+// The transaction is taken out while a request uses it, so concurrent requests to one transaction are rejected
+#[derive(Debug)]
+struct OpenTransaction {
+    transaction: Option<Transaction>,
+    last_used: Instant,
+    // notified when the transaction is killed through the registry, and when it ends in any other way
+    kill: Arc<Notify>,
+}
+
+// A query of a transaction opened through the API, listed in the registry while it runs
+#[derive(Debug)]
+struct QueryRegistration {
+    registry: Arc<TransactionRegistry>,
+    transaction_id: Uuid,
+}
+
+#[derive(Debug, Clone)]
+enum AnswerFormat {
+    Json,
+    // Each full batch of rows is sent on as soon as it is encoded
+    Arrow(mpsc::Sender<Vec<u8>>),
+}
+
+#[derive(Debug)]
+enum HttpAnswer {
+    Json(JSON),
+    Arrow(Vec<u8>),
+    ArrowStream(ArrowStream),
+    Changes(ChangeSubscription<WALClient>),
+}
+
+// An Arrow answer whose first batch was encoded while its query was still running. The query answers the rest of the
+// stream once it finishes.
+#[derive(Debug)]
+struct ArrowStream {
+    first: Vec<u8>,
+    batches: mpsc::Receiver<Vec<u8>>,
+    query: JoinHandle<Result<HttpAnswer, HttpServiceError>>,
+}
+
+impl HttpService {
+    pub(crate) fn new(
+        database_manager: Arc<DatabaseManager>,
+        authenticator: Arc<Authenticator>,
+        transaction_registry: Arc<TransactionRegistry>,
+        query_memory_limit_bytes: Option<u64>,
+        query_sort_spill: Option<SortSpillOptions>,
+    ) -> Self {
+        Self {
+            database_manager,
+            authenticator,
+            transaction_registry,
+            query_memory_limit_bytes,
+            query_sort_spill,
+            transactions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub(crate) async fn serve(self, address: SocketAddr) -> io::Result<()> {
+        let listener = TcpListener::bind(address).await?;
+        let service = Arc::new(self);
+        let reaper = service.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(HTTP_TRANSACTION_IDLE_TIMEOUT / 10);
+            loop {
+                interval.tick().await;
+                reaper.close_idle_transactions();
+            }
+        });
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let service = service.clone();
+            tokio::spawn(async move {
+                if let Err(error) = service.respond(stream).await {
+                    event!(Level::DEBUG, ?error, "Failed to respond to HTTP request");
+                }
+            });
+        }
+    }
+
+    async fn respond(&self, mut stream: TcpStream) -> io::Result<()> {
+        let response = match read_request(&mut stream, MAX_REQUEST_BODY_BYTES).await? {
+            None => return Ok(()),
+            Some(Err(RequestTooLarge { limit })) => error_response(HttpServiceError::RequestTooLarge { limit }),
+            Some(Ok(request)) => match self.handle(request).await {
+                Ok(HttpAnswer::Json(body)) => http_response("200 OK", JSON_CONTENT_TYPE, body.to_string().as_bytes()),
+                Ok(HttpAnswer::Arrow(body)) => http_response("200 OK", ARROW_STREAM_CONTENT_TYPE, &body),
+                Ok(HttpAnswer::ArrowStream(arrow)) => return Self::stream_arrow(stream, arrow).await,
+                Ok(HttpAnswer::Changes(subscription)) => return Self::stream_changes(stream, subscription).await,
+                Err(error) => error_response(error),
+            },
+        };
+        stream.write_all(&response).await?;
+        stream.shutdown().await
+    }
+
+    async fn stream_arrow(mut stream: TcpStream, arrow: ArrowStream) -> io::Result<()> {
+        let ArrowStream { first, mut batches, query } = arrow;
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: {ARROW_STREAM_CONTENT_TYPE}\r\nTransfer-Encoding: chunked\r\n\
+             Connection: close\r\n\r\n"
+        );
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(&http_bytes_chunk(&first)).await?;
+        while let Some(batch) = batches.recv().await {
+            stream.write_all(&http_bytes_chunk(&batch)).await?;
+        }
+        match query.await.unwrap() {
+            Ok(HttpAnswer::Arrow(rest)) => {
+                if !rest.is_empty() {
+                    stream.write_all(&http_bytes_chunk(&rest)).await?;
+                }
+                stream.write_all(b"0\r\n\r\n").await?;
+            }
+            Ok(_) => unreachable!("A query that streams Arrow batches answers the rest of the Arrow stream."),
+            // the response ends without its last chunk, so the client can tell the stream is incomplete
+            Err(error) => event!(Level::DEBUG, ?error, "Query failed after its Arrow answer started streaming"),
+        }
+        stream.shutdown().await
+    }
+
+    async fn stream_changes(mut stream: TcpStream, mut subscription: ChangeSubscription<WALClient>) -> io::Result<()> {
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: {NDJSON_CONTENT_TYPE}\r\nTransfer-Encoding: chunked\r\n\
+             Connection: close\r\n\r\n"
+        );
+        stream.write_all(head.as_bytes()).await?;
+        let mut client_bytes = [0u8; 64];
+        loop {
+            let (returned, polled) = poll_changes(subscription).await;
+            subscription = returned;
+            match polled {
+                Ok(changes) if changes.is_empty() => {
+                    // the client sends nothing after its request, so a read only completes once it disconnects
+                    tokio::select! {
+                        _ = tokio::time::sleep(CHANGE_STREAM_POLL_INTERVAL) => (),
+                        _ = stream.read(&mut client_bytes) => return Ok(()),
+                    }
+                }
+                Ok(changes) => {
+                    let lines = changes.iter().map(encode_changes_json).collect::<Vec<_>>();
+                    stream.write_all(&http_chunk(&lines)).await?
+                }
+                Err(typedb_source) => {
+                    let message = HttpServiceError::ChangeStreamFailed { typedb_source }.into_error_message();
+                    let line = json!({ "error": { "code": message.error_code, "stackTrace": message.stack_trace } });
+                    stream.write_all(&http_chunk(&[line])).await?;
+                    break;
+                }
+            }
+        }
+        stream.write_all(b"0\r\n\r\n").await?;
+        stream.shutdown().await
+    }
+
+    async fn handle(&self, request: HttpRequest) -> Result<HttpAnswer, HttpServiceError> {
+        let username = self.authenticate(&request)?;
+        let path = request.route();
+        let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+        let answer = match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["v1", "databases"]) => Ok(json!({ "databases": self.database_manager.database_names() })),
+            ("GET", ["v1", "databases", name]) => match self.database_manager.database(name) {
+                Some(_) => Ok(json!({ "name": name })),
+                None => Err(HttpServiceError::DatabaseNotFound { name: name.to_string() }),
+            },
+            ("POST", ["v1", "databases", name]) => self
+                .database_manager
+                .create_database(name)
+                .map(|()| json!({ "name": name }))
+                .map_err(|typedb_source| HttpServiceError::DatabaseCreateFailed { typedb_source }),
+            ("GET", ["v1", "databases", name, "changes"]) => {
+                let database = self
+                    .database_manager
+                    .database(name)
+                    .ok_or_else(|| HttpServiceError::DatabaseNotFound { name: name.to_string() })?;
+                let subscription = match query_parameter(&request.path, "from") {
+                    None => ChangeSubscription::from_latest(database),
+                    Some(from) => match from.parse() {
+                        Ok(start) => ChangeSubscription::new(database, SequenceNumber::new(start)),
+                        Err(_) => {
+                            return Err(HttpServiceError::InvalidQueryParameter {
+                                name: "from",
+                                value: from.to_owned(),
+                            })
+                        }
+                    },
+                };
+                return Ok(HttpAnswer::Changes(subscription));
+            }
+            ("POST", ["v1", "databases", name, "bulk-load"]) => {
+                let database = self
+                    .database_manager
+                    .database(name)
+                    .ok_or_else(|| HttpServiceError::DatabaseNotFound { name: name.to_string() })?;
+                let queries = string_list_field(&parse_body(&request.body)?, "queries")?;
+                let query_options = QueryOptions {
+                    memory_limit_bytes: self.query_memory_limit_bytes,
+                    sort_spill: self.query_sort_spill.clone(),
+                    ..QueryOptions::default()
+                };
+                spawn_blocking(move || bulk_load(database, &queries, query_options)).await.unwrap()
+            }
+            ("DELETE", ["v1", "databases", name]) => self
+                .database_manager
+                .delete_database(name)
+                .map(|()| json!({}))
+                .map_err(|typedb_source| HttpServiceError::DatabaseDeleteFailed { typedb_source }),
+            ("GET", ["v1", "admin", "transactions"]) => {
+                Self::check_admin(username)?;
+                let transactions = self.transaction_registry.transactions();
+                Ok(json!({ "transactions": transactions.iter().map(transaction_info_json).collect::<Vec<_>>() }))
+            }
+            ("POST", ["v1", "admin", "transactions", id, "kill"]) => {
+                Self::check_admin(username)?;
+                let transaction_id = parse_transaction_id(id)?;
+                match self.transaction_registry.kill_transaction(transaction_id) {
+                    true => Ok(json!({})),
+                    false => Err(HttpServiceError::TransactionNotFound { id: id.to_string() }),
+                }
+            }
+            ("POST", ["v1", "admin", "transactions", id, "queries", query_id, "kill"]) => {
+                Self::check_admin(username)?;
+                let transaction_id = parse_transaction_id(id)?;
+                let query_id = Uuid::parse_str(query_id)
+                    .map_err(|_| HttpServiceError::QueryNotFound { id: query_id.to_string() })?;
+                match self.transaction_registry.interrupt_query(transaction_id, query_id, InterruptType::KillRequest) {
+                    QueryInterrupt::Sent => Ok(json!({})),
+                    QueryInterrupt::QueryNotFound => Err(HttpServiceError::QueryNotFound { id: query_id.to_string() }),
+                    QueryInterrupt::QueryNotInterruptible => {
+                        Err(HttpServiceError::WriteQueryCannotBeKilled { id: query_id.to_string() })
+                    }
+                }
+            }
+            ("POST", ["v1", "query"]) => return self.one_shot_query(parse_body(&request.body)?).await,
+            ("POST", ["v1", "transactions", "open"]) => {
+                let transaction = self.open_transaction(&parse_body(&request.body)?).await?;
+                let id = Uuid::new_v4();
+                let kill = self.transaction_registry.register(id, username.to_owned(), &transaction);
+                self.close_when_killed(id, kill.clone());
+                let open_transaction =
+                    OpenTransaction { transaction: Some(transaction), last_used: Instant::now(), kill };
+                self.transactions.lock().unwrap().insert(id, open_transaction);
+                Ok(json!({ "transactionId": id.to_string() }))
+            }
+            ("POST", ["v1", "transactions", id, action]) => {
+                let id = parse_transaction_id(id)?;
+                match *action {
+                    "query" => return self.transaction_query(id, parse_body(&request.body)?).await,
+                    "commit" => {
+                        let transaction = self.remove_transaction(id)?;
+                        spawn_blocking(move || commit_transaction(transaction)).await.unwrap().map(|()| json!({}))
+                    }
+                    "rollback" => {
+                        let transaction = self.take_transaction(id)?;
+                        let (transaction, result) = rollback_transaction(transaction);
+                        return_transaction(&self.transactions, id, transaction);
+                        result.map(|()| json!({}))
+                    }
+                    "close" => {
+                        let transaction = self.remove_transaction(id)?;
+                        spawn_blocking(move || close_transaction(transaction)).await.unwrap();
+                        Ok(json!({}))
+                    }
+                    _ => Err(HttpServiceError::EndpointNotFound {
+                        method: request.method.clone(),
+                        path: path.to_owned(),
+                    }),
+                }
+            }
+            _ => Err(HttpServiceError::EndpointNotFound { method: request.method.clone(), path: path.to_owned() }),
+        };
+        answer.map(HttpAnswer::Json)
+    }
+
+    fn authenticate<'a>(&self, request: &'a HttpRequest) -> Result<&'a str, HttpServiceError> {
+        let username = request.headers.get(AUTHENTICATOR_USERNAME_FIELD);
+        let password = request.headers.get(AUTHENTICATOR_PASSWORD_FIELD);
+        match (username, password) {
+            (Some(username), Some(password)) if self.authenticator.is_valid_credential(username, password) => {
+                Ok(username)
+            }
+            _ => Err(HttpServiceError::InvalidCredential {}),
+        }
+    }
+
+    fn check_admin(username: &str) -> Result<(), HttpServiceError> {
+        match PermissionManager::exec_transactions_manage_permitted(username) {
+            true => Ok(()),
+            false => Err(HttpServiceError::OperationNotPermitted {}),
+        }
+    }
+
+    async fn open_transaction(&self, body: &JSON) -> Result<Transaction, HttpServiceError> {
+        let database_name = string_field(body, "database")?;
+        let transaction_type = string_field(body, "transactionType")?;
+        let open: fn(Arc<Database<WALClient>>, TransactionOptions) -> Result<Transaction, TransactionError> =
+            match transaction_type.as_str() {
+                "read" => |database, options| TransactionRead::open(database, options).map(Transaction::Read),
+                "write" => |database, options| TransactionWrite::open(database, options).map(Transaction::Write),
+                "schema" => |database, options| TransactionSchema::open(database, options).map(Transaction::Schema),
+                _ => return Err(HttpServiceError::UnrecognisedTransactionType { transaction_type }),
+            };
+        let database = self
+            .database_manager
+            .database(&database_name)
+            .ok_or(HttpServiceError::DatabaseNotFound { name: database_name })?;
+        spawn_blocking(move || open(database, TransactionOptions::default()))
+            .await
+            .unwrap()
+            .map_err(|typedb_source| HttpServiceError::TransactionOpenFailed { typedb_source })
+    }
+
+    async fn one_shot_query(&self, body: JSON) -> Result<HttpAnswer, HttpServiceError> {
+        let query = string_field(&body, "query")?;
+        let (format, batches) = answer_format(&body)?;
+        let commit = body.get("commit").and_then(JSON::as_bool).unwrap_or(true);
+        let transaction = self.open_transaction(&body).await?;
+        let query_options = self.query_options(&body);
+        let commit = commit && !query_options.explain;
+        let query = spawn_blocking(move || {
+            let (transaction, result) = execute_query(transaction, &query, query_options, format, None);
+            match result {
+                Ok(answer) if commit && !matches!(transaction, Transaction::Read(_)) => {
+                    commit_transaction(transaction).map(|()| answer)
+                }
+                result => {
+                    close_transaction(transaction);
+                    result
+                }
+            }
+        });
+        answer_query(batches, query).await
+    }
+
+    // The transaction is returned by the query's blocking task, so it stays in use until an Arrow stream is written
+    async fn transaction_query(&self, id: Uuid, body: JSON) -> Result<HttpAnswer, HttpServiceError> {
+        let query = string_field(&body, "query")?;
+        let (format, batches) = answer_format(&body)?;
+        let transaction = self.take_transaction(id)?;
+        let query_options = self.query_options(&body);
+        let transactions = self.transactions.clone();
+        let registration = QueryRegistration { registry: self.transaction_registry.clone(), transaction_id: id };
+        let query = spawn_blocking(move || {
+            let (transaction, result) = execute_query(transaction, &query, query_options, format, Some(registration));
+            return_transaction(&transactions, id, transaction);
+            result
+        });
+        answer_query(batches, query).await
+    }
+
+    fn query_options(&self, body: &JSON) -> QueryOptions {
+        QueryOptions {
+            memory_limit_bytes: self.query_memory_limit_bytes,
+            sort_spill: self.query_sort_spill.clone(),
+            profile: body.get("profile").and_then(JSON::as_bool).unwrap_or(false),
+            explain: body.get("explain").and_then(JSON::as_bool).unwrap_or(false),
+            ..QueryOptions::default()
+        }
+    }
+
+    fn take_transaction(&self, id: Uuid) -> Result<Transaction, HttpServiceError> {
+        let mut transactions = self.transactions.lock().unwrap();
+        let open_transaction =
+            transactions.get_mut(&id).ok_or_else(|| HttpServiceError::TransactionNotFound { id: id.to_string() })?;
+        open_transaction.transaction.take().ok_or_else(|| HttpServiceError::TransactionBusy { id: id.to_string() })
+    }
+
+    fn remove_transaction(&self, id: Uuid) -> Result<Transaction, HttpServiceError> {
+        let mut transactions = self.transactions.lock().unwrap();
+        match transactions.get(&id) {
+            None => Err(HttpServiceError::TransactionNotFound { id: id.to_string() }),
+            Some(OpenTransaction { transaction: None, .. }) => {
+                Err(HttpServiceError::TransactionBusy { id: id.to_string() })
+            }
+            Some(_) => {
+                let open_transaction = transactions.remove(&id).unwrap();
+                open_transaction.kill.notify_one();
+                Ok(open_transaction.transaction.unwrap())
+            }
+        }
+    }
+
+    // Transactions opened through the API are listed in the registry while they are open. A kill closes the
+    // transaction, or, while a query uses it, interrupts the query and leaves the transaction to be closed once the
+    // query returns it. Every other way the transaction ends also notifies, so the task always finishes.
+    fn close_when_killed(&self, id: Uuid, kill: Arc<Notify>) {
+        let transactions = self.transactions.clone();
+        let registry = self.transaction_registry.clone();
+        tokio::spawn(async move {
+            kill.notified().await;
+            registry.interrupt_queries(id, InterruptType::KillRequest);
+            registry.unregister(id);
+            let removed = transactions.lock().unwrap().remove(&id);
+            if let Some(OpenTransaction { transaction: Some(transaction), .. }) = removed {
+                spawn_blocking(move || close_transaction(transaction));
+            }
+        });
+    }
+
+    fn close_idle_transactions(&self) {
+        let idle = {
+            let mut transactions = self.transactions.lock().unwrap();
+            let idle_ids = transactions
+                .iter()
+                .filter(|(_, open)| {
+                    open.transaction.is_some() && open.last_used.elapsed() > HTTP_TRANSACTION_IDLE_TIMEOUT
+                })
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>();
+            idle_ids
+                .into_iter()
+                .filter_map(|id| {
+                    let open_transaction = transactions.remove(&id)?;
+                    open_transaction.kill.notify_one();
+                    open_transaction.transaction
+                })
+                .collect::<Vec<_>>()
+        };
+        if !idle.is_empty() {
+            event!(Level::DEBUG, "Closing {} idle HTTP transactions.", idle.len());
+            idle.into_iter().for_each(close_transaction);
+        }
+    }
+}
+
+// A transaction killed while a query used it is no longer open, so it is closed instead
+fn return_transaction(transactions: &Mutex<HashMap<Uuid, OpenTransaction>>, id: Uuid, transaction: Transaction) {
+    let mut transactions = transactions.lock().unwrap();
+    match transactions.get_mut(&id) {
+        Some(open_transaction) => {
+            open_transaction.transaction = Some(transaction);
+            open_transaction.last_used = Instant::now();
+        }
+        None => {
+            drop(transactions);
+            close_transaction(transaction);
+        }
+    }
+}
+
+// Without Arrow batches, or when the query finishes before its first batch is full, the query's answer is the
+// response, so its errors are answered with an error status. Otherwise the Arrow answer is streamed as it is encoded.
+async fn answer_query(
+    batches: Option<mpsc::Receiver<Vec<u8>>>,
+    mut query: JoinHandle<Result<HttpAnswer, HttpServiceError>>,
+) -> Result<HttpAnswer, HttpServiceError> {
+    let Some(mut batches) = batches else {
+        return query.await.unwrap();
+    };
+    tokio::select! {
+        biased;
+        Some(first) = batches.recv() => Ok(HttpAnswer::ArrowStream(ArrowStream { first, batches, query })),
+        answer = &mut query => answer.unwrap(),
+    }
+}
+
+// Read queries of a registered transaction can be killed alone, but its write queries run to the end
+fn execute_query(
+    transaction: Transaction,
+    query: &str,
+    query_options: QueryOptions,
+    format: AnswerFormat,
+    registration: Option<QueryRegistration>,
+) -> (Transaction, Result<HttpAnswer, HttpServiceError>) {
+    let parsed = match parse_query(query) {
+        Ok(parsed) => parsed,
+        Err(typedb_source) => return (transaction, Err(HttpServiceError::QueryParseFailed { typedb_source })),
+    };
+    match parsed {
+        Query::Schema(_) if query_options.explain => {
+            (transaction, Err(HttpServiceError::SchemaQueryCannotBeExplained {}))
+        }
+        Query::Schema(schema_query) => execute_schema_query(transaction, schema_query),
+        Query::Pipeline(pipeline) if query_options.explain => {
+            let result = explain_query(&transaction, &pipeline);
+            (transaction, result)
+        }
+        Query::Pipeline(pipeline) if TransactionService::is_write_pipeline(&pipeline) => {
+            let _running_query = registration.map(|QueryRegistration { registry, transaction_id }| {
+                registry.start_write_query(transaction_id, Uuid::new_v4(), query.to_owned())
+            });
+            execute_write_query(transaction, &pipeline, query_options, format)
+        }
+        Query::Pipeline(pipeline) => {
+            let (_running_query, interrupt) = match registration {
+                Some(QueryRegistration { registry, transaction_id }) => {
+                    let (running_query, query_signal) =
+                        registry.start_query(transaction_id, Uuid::new_v4(), query.to_owned(), None);
+                    (Some(running_query), ExecutionInterrupt::new_uninterruptible().with_query_signal(query_signal))
+                }
+                None => (None, ExecutionInterrupt::new_uninterruptible()),
+            };
+            let result = match &transaction {
+                Transaction::Read(transaction) => execute_read_query_in(
+                    transaction.snapshot.clone(),
+                    &transaction.type_manager,
+                    transaction.thing_manager.clone(),
+                    &transaction.function_manager,
+                    &transaction.query_manager,
+                    &pipeline,
+                    query_options,
+                    format,
+                    interrupt,
+                ),
+                Transaction::Write(transaction) => execute_read_query_in(
+                    transaction.snapshot.clone(),
+                    &transaction.type_manager,
+                    transaction.thing_manager.clone(),
+                    &transaction.function_manager,
+                    &transaction.query_manager,
+                    &pipeline,
+                    query_options,
+                    format,
+                    interrupt,
+                ),
+                Transaction::Schema(transaction) => execute_read_query_in(
+                    transaction.snapshot.clone(),
+                    &transaction.type_manager,
+                    transaction.thing_manager.clone(),
+                    &transaction.function_manager,
+                    &transaction.query_manager,
+                    &pipeline,
+                    query_options,
+                    format,
+                    interrupt,
+                ),
+            };
+            (transaction, result)
+        }
+    }
+}
+
+// The writes are ingested whenever the write buffer fills, and once more after the last query
+fn bulk_load(
+    database: Arc<Database<WALClient>>,
+    queries: &[String],
+    query_options: QueryOptions,
+) -> Result<JSON, HttpServiceError> {
+    let mut transaction = TransactionBulkLoad::open(database, TransactionOptions::default())
+        .map_err(|typedb_source| HttpServiceError::TransactionOpenFailed { typedb_source })?;
+    for query in queries {
+        let result;
+        (transaction, result) = execute_bulk_load_query(transaction, query, query_options.clone());
+        let result = result.and_then(|()| transaction.may_ingest_batch().map_err(bulk_load_error));
+        if let Err(error) = result {
+            transaction.close();
+            return Err(error);
+        }
+    }
+    transaction.commit().map_err(bulk_load_error)?;
+    Ok(json!({ "loadedQueries": queries.len() }))
+}
+
+fn execute_bulk_load_query(
+    transaction: TransactionBulkLoad<WALClient>,
+    query: &str,
+    query_options: QueryOptions,
+) -> (TransactionBulkLoad<WALClient>, Result<(), HttpServiceError>) {
+    let pipeline = match parse_query(query) {
+        Ok(Query::Pipeline(pipeline)) if TransactionService::is_write_pipeline(&pipeline) => pipeline,
+        Ok(_) => return (transaction, Err(HttpServiceError::BulkLoadRequiresWriteQueries {})),
+        Err(typedb_source) => return (transaction, Err(HttpServiceError::QueryParseFailed { typedb_source })),
+    };
+    let TransactionBulkLoad {
+        snapshot,
+        type_manager,
+        thing_manager,
+        function_manager,
+        query_manager,
+        database,
+        transaction_options,
+    } = transaction;
+    let (snapshot, result) = TransactionService::execute_write_query_in(
+        Arc::into_inner(snapshot).expect("Cannot unwrap Arc<Snapshot>, still in use."),
+        &type_manager,
+        thing_manager.clone(),
+        &function_manager,
+        &query_manager,
+        &pipeline,
+        query_options,
+        false,
+        None,
+        ExecutionInterrupt::new_uninterruptible(),
+    );
+    let transaction = TransactionBulkLoad::from(
+        snapshot,
+        type_manager,
+        thing_manager,
+        function_manager,
+        query_manager,
+        database,
+        transaction_options,
+    );
+    (transaction, result.map(|_| ()).map_err(|typedb_source| HttpServiceError::QueryFailed { typedb_source }))
+}
+
+fn explain_query(
+    transaction: &Transaction,
+    pipeline: &typeql::query::Pipeline,
+) -> Result<HttpAnswer, HttpServiceError> {
+    let plan = match transaction {
+        Transaction::Read(transaction) => transaction.query_manager.explain_pipeline(
+            transaction.snapshot.as_ref(),
+            &transaction.type_manager,
+            &transaction.thing_manager,
+            &transaction.function_manager,
+            pipeline,
+        ),
+        Transaction::Write(transaction) => transaction.query_manager.explain_pipeline(
+            transaction.snapshot.as_ref(),
+            &transaction.type_manager,
+            &transaction.thing_manager,
+            &transaction.function_manager,
+            pipeline,
+        ),
+        Transaction::Schema(transaction) => transaction.query_manager.explain_pipeline(
+            transaction.snapshot.as_ref(),
+            &transaction.type_manager,
+            &transaction.thing_manager,
+            &transaction.function_manager,
+            pipeline,
+        ),
+    };
+    plan.map(|plan| HttpAnswer::Json(json!({ "answerType": "explain", "plan": plan.to_string() })))
+        .map_err(|typedb_source| HttpServiceError::QueryFailed { typedb_source })
+}
+
+fn execute_schema_query(
+    transaction: Transaction,
+    query: SchemaQuery,
+) -> (Transaction, Result<HttpAnswer, HttpServiceError>) {
+    match transaction {
+        Transaction::Schema(transaction) => {
+            let TransactionSchema {
+                snapshot,
+                type_manager,
+                thing_manager,
+                function_manager,
+                query_manager,
+                database,
+                transaction_options,
+            } = transaction;
+            let mut snapshot = Arc::into_inner(snapshot).expect("Cannot unwrap Arc<Snapshot>, still in use.");
+            let result =
+                query_manager.execute_schema(&mut snapshot, &type_manager, &thing_manager, &function_manager, query);
+            let transaction = TransactionSchema::from(
+                snapshot,
+                type_manager,
+                thing_manager,
+                function_manager,
+                query_manager,
+                database,
+                transaction_options,
+            );
+            let result = result
+                .map(|()| HttpAnswer::Json(ok_answer()))
+                .map_err(|typedb_source| HttpServiceError::QueryFailed { typedb_source });
+            (Transaction::Schema(transaction), result)
+        }
+        transaction => (transaction, Err(HttpServiceError::SchemaQueryRequiresSchemaTransaction {})),
+    }
+}
+
+fn execute_write_query(
+    transaction: Transaction,
+    pipeline: &typeql::query::Pipeline,
+    query_options: QueryOptions,
+    format: AnswerFormat,
+) -> (Transaction, Result<HttpAnswer, HttpServiceError>) {
+    match transaction {
+        Transaction::Read(transaction) => {
+            (Transaction::Read(transaction), Err(HttpServiceError::WriteQueryRequiresSchemaOrWriteTransaction {}))
+        }
+        Transaction::Write(transaction) => {
+            let TransactionWrite {
+                snapshot,
+                type_manager,
+                thing_manager,
+                function_manager,
+                query_manager,
+                database,
+                transaction_options,
+            } = transaction;
+            let (snapshot, result) = execute_write_query_in(
+                Arc::into_inner(snapshot).expect("Cannot unwrap Arc<Snapshot>, still in use."),
+                &type_manager,
+                &thing_manager,
+                &function_manager,
+                &query_manager,
+                pipeline,
+                query_options,
+                format,
+            );
+            let transaction = TransactionWrite::from(
+                Arc::new(snapshot),
+                type_manager,
+                thing_manager,
+                function_manager,
+                query_manager,
+                database,
+                transaction_options,
+            );
+            (Transaction::Write(transaction), result)
+        }
+        Transaction::Schema(transaction) => {
+            let TransactionSchema {
+                snapshot,
+                type_manager,
+                thing_manager,
+                function_manager,
+                query_manager,
+                database,
+                transaction_options,
+            } = transaction;
+            let (snapshot, result) = execute_write_query_in(
+                Arc::into_inner(snapshot).expect("Cannot unwrap Arc<Snapshot>, still in use."),
+                &type_manager,
+                &thing_manager,
+                &function_manager,
+                &query_manager,
+                pipeline,
+                query_options,
+                format,
+            );
+            let transaction = TransactionSchema::from(
+                snapshot,
+                type_manager,
+                thing_manager,
+                function_manager,
+                query_manager,
+                database,
+                transaction_options,
+            );
+            (Transaction::Schema(transaction), result)
+        }
+    }
+}
+
+fn execute_write_query_in<Snapshot: WritableSnapshot + 'static>(
+    snapshot: Snapshot,
+    type_manager: &TypeManager,
+    thing_manager: &Arc<ThingManager>,
+    function_manager: &FunctionManager,
+    query_manager: &QueryManager,
+    pipeline: &typeql::query::Pipeline,
+    query_options: QueryOptions,
+    format: AnswerFormat,
+) -> (Snapshot, Result<HttpAnswer, HttpServiceError>) {
+    let (snapshot, result) = TransactionService::execute_write_query_in(
+        snapshot,
+        type_manager,
+        thing_manager.clone(),
+        function_manager,
+        query_manager,
+        pipeline,
+        query_options,
+        false,
+        None,
+        ExecutionInterrupt::new_uninterruptible(),
+    );
+    let result = match result {
+        Ok((descriptor, batch, profile)) => {
+            encode_batch(&descriptor, batch, &snapshot, type_manager, thing_manager, format)
+                .map(|answer| with_profile(answer, &profile))
+        }
+        Err(typedb_source) => Err(HttpServiceError::QueryFailed { typedb_source }),
+    };
+    (snapshot, result)
+}
+
+fn encode_batch(
+    descriptor: &StreamQueryOutputDescriptor,
+    batch: Batch,
+    snapshot: &impl ReadableSnapshot,
+    type_manager: &TypeManager,
+    thing_manager: &ThingManager,
+    format: AnswerFormat,
+) -> Result<HttpAnswer, HttpServiceError> {
+    let mut encoder = RowsEncoder::new(descriptor, format)?;
+    let mut iterator = batch.into_iterator();
+    while let Some(row) = iterator.next() {
+        encoder.push(row, snapshot, type_manager, thing_manager)?;
+    }
+    encoder.finish()
+}
+
+fn execute_read_query_in<Snapshot: ReadableSnapshot + 'static>(
+    snapshot: Arc<Snapshot>,
+    type_manager: &TypeManager,
+    thing_manager: Arc<ThingManager>,
+    function_manager: &FunctionManager,
+    query_manager: &QueryManager,
+    pipeline: &typeql::query::Pipeline,
+    query_options: QueryOptions,
+    format: AnswerFormat,
+    interrupt: ExecutionInterrupt,
+) -> Result<HttpAnswer, HttpServiceError> {
+    let prepared_pipeline = TransactionService::prepare_read_query_in(
+        snapshot.clone(),
+        type_manager,
+        thing_manager.clone(),
+        function_manager,
+        query_manager,
+        pipeline,
+        query_options,
+    )
+    .map_err(|typedb_source| HttpServiceError::QueryFailed { typedb_source })?;
+
+    if prepared_pipeline.has_fetch() {
+        if matches!(format, AnswerFormat::Arrow(_)) {
+            return Err(HttpServiceError::ArrowRequiresConceptRows {});
+        }
+        let (iterator, context) =
+            prepared_pipeline.into_documents_iterator(interrupt).map_err(|(err, _)| read_pipeline_error(err))?;
+        let documents = iterator
+            .map(|document| {
+                let document = document.map_err(read_pipeline_error)?;
+                encode_document(document, snapshot.as_ref(), type_manager, &thing_manager, &context.parameters)
+                    .map(|document| encode_document_json(&document))
+                    .map_err(concept_read_error)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let answer = HttpAnswer::Json(json!({ "answerType": "conceptDocuments", "answers": documents }));
+        Ok(with_profile(answer, &context.profile))
+    } else {
+        let descriptor = output_descriptor(&prepared_pipeline);
+        let (mut iterator, context) =
+            prepared_pipeline.into_rows_iterator(interrupt).map_err(|(err, _)| read_pipeline_error(err))?;
+        let mut encoder = RowsEncoder::new(&descriptor, format)?;
+        while let Some(row) = iterator.next() {
+            encoder.push(row.map_err(read_pipeline_error)?, snapshot.as_ref(), type_manager, &thing_manager)?;
+        }
+        encoder.finish().map(|answer| with_profile(answer, &context.profile))
+    }
+}
+
+enum RowsEncoder<'a> {
+    Json { descriptor: &'a StreamQueryOutputDescriptor, columns: Vec<String>, rows: Vec<JSON> },
+    Arrow { encoder: ArrowRowsEncoder, batches: mpsc::Sender<Vec<u8>> },
+}
+
+impl<'a> RowsEncoder<'a> {
+    fn new(descriptor: &'a StreamQueryOutputDescriptor, format: AnswerFormat) -> Result<Self, HttpServiceError> {
+        match format {
+            AnswerFormat::Json => Ok(Self::Json {
+                descriptor,
+                columns: descriptor.iter().map(|(name, _, _)| name.clone()).collect(),
+                rows: Vec::new(),
+            }),
+            AnswerFormat::Arrow(batches) => {
+                let encoder = ArrowRowsEncoder::new(descriptor).map_err(arrow_encoding_error)?;
+                Ok(Self::Arrow { encoder, batches })
+            }
+        }
+    }
+
+    fn push(
+        &mut self,
+        row: MaybeOwnedRow<'_>,
+        snapshot: &impl ReadableSnapshot,
+        type_manager: &TypeManager,
+        thing_manager: &ThingManager,
+    ) -> Result<(), HttpServiceError> {
+        match self {
+            Self::Json { descriptor, columns, rows } => {
+                let row =
+                    encode_row(row, descriptor, snapshot, type_manager, thing_manager).map_err(concept_read_error)?;
+                rows.push(encode_row_json(&row, columns));
+                Ok(())
+            }
+            Self::Arrow { encoder, batches } => {
+                encoder.push_row(&row, snapshot, type_manager).map_err(arrow_encoding_error)?;
+                let encoded = encoder.take_encoded();
+                match encoded.is_empty() {
+                    true => Ok(()),
+                    false => batches.blocking_send(encoded).map_err(|_| HttpServiceError::AnswerStreamClosed {}),
+                }
+            }
+        }
+    }
+
+    // The Arrow answer is what remains of the stream after the batches already sent
+    fn finish(self) -> Result<HttpAnswer, HttpServiceError> {
+        match self {
+            Self::Json { columns, rows, .. } => Ok(HttpAnswer::Json(rows_answer(columns, rows))),
+            Self::Arrow { encoder, .. } => encoder.finish().map(HttpAnswer::Arrow).map_err(arrow_encoding_error),
+        }
+    }
+}
+
+fn commit_transaction(transaction: Transaction) -> Result<(), HttpServiceError> {
+    match transaction {
+        Transaction::Read(transaction) => {
+            transaction.close();
+            Err(HttpServiceError::CannotCommitReadTransaction {})
+        }
+        Transaction::Write(transaction) => {
+            transaction.commit().map_err(|typedb_source| HttpServiceError::DataCommitFailed { typedb_source })
+        }
+        Transaction::Schema(transaction) => {
+            transaction.commit().map_err(|typedb_source| HttpServiceError::SchemaCommitFailed { typedb_source })
+        }
+    }
+}
+
+fn rollback_transaction(transaction: Transaction) -> (Transaction, Result<(), HttpServiceError>) {
+    match transaction {
+        Transaction::Read(transaction) => {
+            (Transaction::Read(transaction), Err(HttpServiceError::CannotRollbackReadTransaction {}))
+        }
+        Transaction::Write(mut transaction) => {
+            transaction.rollback();
+            (Transaction::Write(transaction), Ok(()))
+        }
+        Transaction::Schema(mut transaction) => {
+            transaction.rollback();
+            (Transaction::Schema(transaction), Ok(()))
+        }
+    }
+}
+
+fn close_transaction(transaction: Transaction) {
+    match transaction {
+        Transaction::Read(transaction) => transaction.close(),
+        Transaction::Write(transaction) => transaction.close(),
+        Transaction::Schema(transaction) => transaction.close(),
+    }
+}
+
+fn ok_answer() -> JSON {
+    json!({ "answerType": "ok" })
+}
+
+fn rows_answer(columns: Vec<String>, rows: Vec<JSON>) -> JSON {
+    json!({ "answerType": "conceptRows", "columns": columns, "answers": rows })
+}
+
+// Arrow streams have nowhere to carry a profile, so only JSON answers include one
+fn with_profile(answer: HttpAnswer, profile: &QueryProfile) -> HttpAnswer {
+    match answer {
+        HttpAnswer::Json(JSON::Object(mut object)) if profile.is_enabled() => {
+            object.insert("profile".to_owned(), encode_profile_json(&profile.summary()));
+            HttpAnswer::Json(JSON::Object(object))
+        }
+        answer => answer,
+    }
+}
+
+fn read_pipeline_error(typedb_source: Box<PipelineExecutionError>) -> HttpServiceError {
+    HttpServiceError::QueryFailed { typedb_source: QueryError::ReadPipelineExecution { typedb_source } }
+}
+
+fn concept_read_error(source: Box<ConceptReadError>) -> HttpServiceError {
+    HttpServiceError::ConceptReadFailed { source }
+}
+
+fn bulk_load_error(typedb_source: BulkLoadCommitError) -> HttpServiceError {
+    HttpServiceError::BulkLoadFailed { typedb_source }
+}
+
+fn arrow_encoding_error(typedb_source: ArrowEncodeError) -> HttpServiceError {
+    HttpServiceError::ArrowEncodingFailed { typedb_source }
+}
+
+fn transaction_info_json(transaction: &TransactionInfo) -> JSON {
+    let queries = transaction
+        .queries
+        .iter()
+        .map(|query| {
+            json!({
+                "id": query.id.to_string(),
+                "query": query.query,
+                "elapsedMillis": query.elapsed.as_millis() as u64,
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "id": transaction.id.to_string(),
+        "database": transaction.database,
+        "user": transaction.user,
+        "transactionType": transaction.transaction_type,
+        "openedAt": DateTime::<Utc>::from(transaction.opened_at).to_rfc3339_opts(SecondsFormat::Millis, true),
+        "openSequenceNumber": transaction.open_sequence_number.number(),
+        "queries": queries,
+    })
+}
+
+fn parse_transaction_id(id: &str) -> Result<Uuid, HttpServiceError> {
+    Uuid::parse_str(id).map_err(|_| HttpServiceError::TransactionNotFound { id: id.to_string() })
+}
+
+fn parse_body(body: &[u8]) -> Result<JSON, HttpServiceError> {
+    serde_json::from_slice(body).map_err(|source| HttpServiceError::InvalidRequestBody { source: Arc::new(source) })
+}
+
+fn string_field(body: &JSON, field: &'static str) -> Result<String, HttpServiceError> {
+    body.get(field).and_then(JSON::as_str).map(str::to_owned).ok_or(HttpServiceError::MissingField { field })
+}
+
+fn string_list_field(body: &JSON, field: &'static str) -> Result<Vec<String>, HttpServiceError> {
+    body.get(field)
+        .and_then(JSON::as_array)
+        .and_then(|values| values.iter().map(|value| value.as_str().map(str::to_owned)).collect())
+        .ok_or(HttpServiceError::MissingListField { field })
+}
+
+// An Arrow answer comes with the receiver of the batches its query encodes
+fn answer_format(body: &JSON) -> Result<(AnswerFormat, Option<mpsc::Receiver<Vec<u8>>>), HttpServiceError> {
+    match body.get("format").and_then(JSON::as_str) {
+        None | Some("json") => Ok((AnswerFormat::Json, None)),
+        Some("arrow") => {
+            let (sender, receiver) = mpsc::channel(HTTP_ARROW_STREAM_BUFFERED_BATCHES);
+            Ok((AnswerFormat::Arrow(sender), Some(receiver)))
+        }
+        Some(format) => Err(HttpServiceError::UnrecognisedAnswerFormat { format: format.to_owned() }),
+    }
+}
+
+fn query_parameter<'a>(path: &'a str, name: &str) -> Option<&'a str> {
+    let (_, query) = path.split_once('?')?;
+    query
+        .split('&')
+        .filter_map(|parameter| parameter.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+fn http_chunk(lines: &[JSON]) -> Vec<u8> {
+    let body = lines.iter().map(|line| format!("{line}\n")).collect::<String>();
+    http_bytes_chunk(body.as_bytes())
+}
+
+fn http_bytes_chunk(body: &[u8]) -> Vec<u8> {
+    let mut chunk = format!("{:x}\r\n", body.len()).into_bytes();
+    chunk.extend_from_slice(body);
+    chunk.extend_from_slice(b"\r\n");
+    chunk
+}
+
+fn error_response(error: HttpServiceError) -> Vec<u8> {
+    let status = error.http_status();
+    let message = error.into_error_message();
+    let body = json!({ "code": message.error_code, "domain": message.domain, "stackTrace": message.stack_trace });
+    http_response(status, JSON_CONTENT_TYPE, body.to_string().as_bytes())
+}
+
+impl HttpServiceError {
+    fn http_status(&self) -> &'static str {
+        match self {
+            Self::InvalidCredential { .. } => "401 Unauthorized",
+            Self::OperationNotPermitted { .. } => "403 Forbidden",
+            Self::EndpointNotFound { .. }
+            | Self::DatabaseNotFound { .. }
+            | Self::TransactionNotFound { .. }
+            | Self::QueryNotFound { .. } => "404 Not Found",
+            Self::TransactionBusy { .. } | Self::DataCommitFailed { .. } => "409 Conflict",
+            Self::RequestTooLarge { .. } => "413 Payload Too Large",
+            Self::TransactionOpenFailed { .. } => "503 Service Unavailable",
+            _ => "400 Bad Request",
+        }
+    }
+}
+
+typedb_error!(
+    pub(crate) HttpServiceError(component = "HTTP service", prefix = "HSV") {
+        InvalidCredential(1, "Invalid credential supplied."),
+        EndpointNotFound(2, "There is no endpoint '{method} {path}'.", method: String, path: String),
+        RequestTooLarge(3, "The request body is larger than the limit of {limit} bytes.", limit: usize),
+        InvalidRequestBody(4, "The request body is not valid JSON.", ( source: Arc<serde_json::Error> )),
+        MissingField(5, "The request body has no string field '{field}'.", field: &'static str),
+        UnrecognisedTransactionType(
+            6,
+            "Transaction type '{transaction_type}' is not one of 'read', 'write' or 'schema'.",
+            transaction_type: String
+        ),
+        DatabaseNotFound(7, "Database '{name}' not found.", name: String),
+        DatabaseCreateFailed(8, "Database creation failed.", ( typedb_source: DatabaseCreateError )),
+        DatabaseDeleteFailed(9, "Database deletion failed.", ( typedb_source: DatabaseDeleteError )),
+        TransactionOpenFailed(10, "Transaction failed to open.", ( typedb_source: TransactionError )),
+        TransactionNotFound(
+            11,
+            "Transaction '{id}' not found. It may have been closed after being left idle.",
+            id: String
+        ),
+        TransactionBusy(12, "Transaction '{id}' is in use by another request.", id: String),
+        QueryParseFailed(13, "Query parsing failed.", ( typedb_source: typeql::Error )),
+        QueryFailed(14, "Query failed.", ( typedb_source: QueryError )),
+        ConceptReadFailed(15, "Failed to read a concept of the answer.", ( source: Box<ConceptReadError> )),
+        SchemaQueryRequiresSchemaTransaction(16, "Schema modification queries require schema transactions."),
+        WriteQueryRequiresSchemaOrWriteTransaction(
+            17,
+            "Data modification queries require either write or schema transactions."
+        ),
+        CannotCommitReadTransaction(18, "Read transactions cannot be committed."),
+        CannotRollbackReadTransaction(19, "Read transactions cannot be rolled back, since they never contain writes."),
+        DataCommitFailed(20, "Data transaction commit failed.", ( typedb_source: DataCommitError )),
+        SchemaCommitFailed(21, "Schema transaction commit failed.", ( typedb_source: SchemaCommitError )),
+        UnrecognisedAnswerFormat(22, "Answer format '{format}' is not one of 'json' or 'arrow'.", format: String),
+        ArrowRequiresConceptRows(23, "The Arrow answer format only supports queries answering concept rows."),
+        ArrowEncodingFailed(24, "Failed to encode the answer as Arrow.", ( typedb_source: ArrowEncodeError )),
+        OperationNotPermitted(25, "The user is not permitted to execute the operation."),
+        QueryNotFound(26, "Query '{id}' is not running in the transaction.", id: String),
+        SchemaQueryCannotBeExplained(27, "Only pipeline queries can be explained, not schema queries."),
+        InvalidQueryParameter(
+            28,
+            "Query parameter '{name}' has invalid value '{value}'.",
+            name: &'static str, value: String
+        ),
+        ChangeStreamFailed(
+            29,
+            "Failed to read the changes committed to the database.",
+            ( typedb_source: ChangeStreamError )
+        ),
+        MissingListField(30, "The request body has no field '{field}' holding a list of strings.", field: &'static str),
+        BulkLoadRequiresWriteQueries(31, "Bulk loads only run pipelines that write data."),
+        BulkLoadFailed(
+            32,
+            "Bulk load failed. Batches ingested before the failure remain loaded.",
+            ( typedb_source: BulkLoadCommitError )
+        ),
+        AnswerStreamClosed(33, "The client stopped reading the answer stream."),
+        WriteQueryCannotBeKilled(
+            34,
+            "Query '{id}' writes data, so it cannot be killed alone. Kill its transaction instead.",
+            id: String
+        ),
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, io::Cursor, sync::Arc, time::Duration};
+
+    use arrow_array::{cast::AsArray, types::Int64Type};
+    use arrow_ipc::reader::StreamReader;
+    use arrow_schema::DataType;
+    use database::{
+        change_subscription::ChangeSubscription,
+        database_manager::DatabaseManager,
+        transaction::{TransactionRead, TransactionSchema},
+    };
+    use encoding::value::label::Label;
+    use executor::batch::FIXED_BATCH_ROWS_MAX;
+    use options::{QueryOptions, TransactionOptions};
+    use resource::constants::server::{
+        AUTHENTICATOR_PASSWORD_FIELD, AUTHENTICATOR_USERNAME_FIELD, DEFAULT_USER_NAME, DEFAULT_USER_PASSWORD,
+    };
+    use serde_json::{json, Value as JSON};
+    use test_utils::create_tmp_dir;
+    use tokio::{
+        io::AsyncReadExt,
+        net::{TcpListener, TcpStream},
+        task::spawn_blocking,
+    };
+    use tonic_types::StatusExt;
+    use typedb_protocol::transaction::Type;
+    use uuid::Uuid;
+
+    use super::{
+        answer_format, answer_query, bulk_load, close_transaction, commit_transaction, execute_query, http_chunk,
+        query_parameter, AnswerFormat, HttpAnswer, HttpService, HttpServiceError,
+    };
+    use crate::{
+        authenticator::Authenticator,
+        service::{http::HttpRequest, test_client::TestServer, transaction_service::Transaction},
+    };
+
+    // Joins the chunks of a chunked HTTP response, which must end with the terminating empty chunk
+    fn chunked_body(response: &[u8]) -> Vec<u8> {
+        let head_end = response.windows(4).position(|window| window == b"\r\n\r\n").unwrap() + 4;
+        let mut rest = &response[head_end..];
+        let mut body = Vec::new();
+        loop {
+            let line_end = rest.windows(2).position(|window| window == b"\r\n").unwrap();
+            let length = usize::from_str_radix(std::str::from_utf8(&rest[..line_end]).unwrap(), 16).unwrap();
+            rest = &rest[line_end + 2..];
+            if length == 0 {
+                return body;
+            }
+            body.extend_from_slice(&rest[..length]);
+            rest = &rest[length + 2..];
+        }
+    }
+
+    #[test]
+    fn query_parameters_are_found_by_name() {
+        assert_eq!(query_parameter("/v1/databases/db/changes?from=12", "from"), Some("12"));
+        assert_eq!(query_parameter("/v1/databases/db/changes?to=3&from=12", "from"), Some("12"));
+        assert_eq!(query_parameter("/v1/databases/db/changes?fromage=12", "from"), None);
+        assert_eq!(query_parameter("/v1/databases/db/changes", "from"), None);
+    }
+
+    #[test]
+    fn chunks_carry_their_length_in_hex() {
+        let chunk = http_chunk(&[json!({ "a": 1 }), json!({ "b": "0123456789" })]);
+        assert_eq!(String::from_utf8(chunk).unwrap(), "1b\r\n{\"a\":1}\n{\"b\":\"0123456789\"}\n\r\n");
+    }
+
+    #[tokio::test]
+    async fn changes_are_streamed_until_the_client_disconnects() {
+        let data_directory = create_tmp_dir();
+        let database_manager = DatabaseManager::new(&data_directory).unwrap();
+        database_manager.create_database("changes").unwrap();
+        let database = database_manager.database("changes").unwrap();
+        let subscription = ChangeSubscription::from_latest(database.clone());
+
+        let mut transaction = TransactionSchema::open(database, TransactionOptions::default()).unwrap();
+        let snapshot = Arc::get_mut(&mut transaction.snapshot).unwrap();
+        transaction.type_manager.create_entity_type(snapshot, &Label::build("person")).unwrap();
+        transaction.commit().unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        let streaming = tokio::spawn(HttpService::stream_changes(server, subscription));
+
+        let mut response = String::new();
+        let mut buffer = [0u8; 1024];
+        while !response.contains("schemaChanged") {
+            let read = client.read(&mut buffer).await.unwrap();
+            assert_ne!(read, 0, "stream ended early: {response}");
+            response.push_str(&String::from_utf8_lossy(&buffer[..read]));
+        }
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(response.contains("\"commitType\":\"schema\""));
+
+        drop(client);
+        tokio::time::timeout(Duration::from_secs(5), streaming).await.unwrap().unwrap().unwrap();
+    }
+
+    #[test]
+    fn bulk_load_runs_write_queries() {
+        let data_directory = create_tmp_dir();
+        let database_manager = DatabaseManager::new(&data_directory).unwrap();
+        database_manager.create_database("bulk").unwrap();
+        let database = database_manager.database("bulk").unwrap();
+        let mut transaction = TransactionSchema::open(database.clone(), TransactionOptions::default()).unwrap();
+        let snapshot = Arc::get_mut(&mut transaction.snapshot).unwrap();
+        transaction.type_manager.create_entity_type(snapshot, &Label::build("person")).unwrap();
+        transaction.commit().unwrap();
+
+        let queries = vec!["insert $p isa person;".to_owned(); 3];
+        let answer = bulk_load(database.clone(), &queries, QueryOptions::default()).unwrap();
+        assert_eq!(answer, json!({ "loadedQueries": 3 }));
+        let read_only = vec!["match $p isa person;".to_owned()];
+        let result = bulk_load(database.clone(), &read_only, QueryOptions::default());
+        assert!(matches!(result, Err(HttpServiceError::BulkLoadRequiresWriteQueries {})));
+
+        let transaction = TransactionRead::open(database.clone(), TransactionOptions::default()).unwrap();
+        let person = transaction.type_manager.get_entity_type(&*transaction.snapshot, &Label::build("person")).unwrap();
+        assert_eq!(transaction.thing_manager.get_entities_in(&*transaction.snapshot, person.unwrap()).count(), 3);
+    }
+
+    #[tokio::test]
+    async fn arrow_answers_are_streamed_in_batches_typed_before_the_query_runs() {
+        let data_directory = create_tmp_dir();
+        let database_manager = DatabaseManager::new(&data_directory).unwrap();
+        database_manager.create_database("arrow").unwrap();
+        let database = database_manager.database("arrow").unwrap();
+        let transaction =
+            Transaction::Schema(TransactionSchema::open(database.clone(), TransactionOptions::default()).unwrap());
+        let define = "define entity person, owns age; attribute age, value long;";
+        let (transaction, result) =
+            execute_query(transaction, define, QueryOptions::default(), AnswerFormat::Json, None);
+        result.unwrap();
+        commit_transaction(transaction).unwrap();
+        let row_count = 2 * FIXED_BATCH_ROWS_MAX as i64 + 1;
+        let inserts = (0..row_count).map(|age| format!("insert $p isa person, has age {age};")).collect::<Vec<_>>();
+        bulk_load(database.clone(), &inserts, QueryOptions::default()).unwrap();
+
+        let (format, batches) = answer_format(&json!({ "format": "arrow" })).unwrap();
+        let transaction = Transaction::Read(TransactionRead::open(database, TransactionOptions::default()).unwrap());
+        let query = spawn_blocking(move || {
+            let query = "match $p isa person, has age $a; let $next = $a + 1;";
+            let (transaction, result) = execute_query(transaction, query, QueryOptions::default(), format, None);
+            close_transaction(transaction);
+            result
+        });
+        let Ok(HttpAnswer::ArrowStream(arrow)) = answer_query(batches, query).await else {
+            panic!("expected the answer to be streamed once its first batch was full");
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        let streaming = tokio::spawn(HttpService::stream_arrow(server, arrow));
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), streaming).await.unwrap().unwrap().unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+        let reader = StreamReader::try_new(Cursor::new(chunked_body(&response)), None).unwrap();
+        let record_batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(record_batches.len(), 3);
+        let schema = record_batches[0].schema();
+        assert!(matches!(schema.field_with_name("p").unwrap().data_type(), DataType::Struct(_)));
+        assert!(matches!(schema.field_with_name("a").unwrap().data_type(), DataType::Struct(_)));
+        assert_eq!(schema.field_with_name("next").unwrap().data_type(), &DataType::Int64);
+        let mut next_ages = record_batches
+            .iter()
+            .flat_map(|batch| batch.column_by_name("next").unwrap().as_primitive::<Int64Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        next_ages.sort();
+        assert_eq!(next_ages, (1..=row_count).collect::<Vec<_>>());
+    }
+
+    async fn admin_request(service: &HttpService, method: &str, path: &str) -> Result<JSON, HttpServiceError> {
+        admin_request_with(service, method, path, Vec::new()).await
+    }
+
+    async fn admin_request_with(
+        service: &HttpService,
+        method: &str,
+        path: &str,
+        body: Vec<u8>,
+    ) -> Result<JSON, HttpServiceError> {
+        let headers = HashMap::from([
+            (AUTHENTICATOR_USERNAME_FIELD.to_owned(), DEFAULT_USER_NAME.to_owned()),
+            (AUTHENTICATOR_PASSWORD_FIELD.to_owned(), DEFAULT_USER_PASSWORD.to_owned()),
+        ]);
+        let request = HttpRequest { method: method.to_owned(), path: path.to_owned(), headers, body };
+        match service.handle(request).await? {
+            HttpAnswer::Json(answer) => Ok(answer),
+            other => panic!("expected a JSON answer, found {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn admins_list_and_kill_grpc_transactions() {
+        let server = TestServer::start().await;
+        server.database_manager.create_database("admin").unwrap();
+        let authenticator = Arc::new(Authenticator::new(server.user_manager.clone()));
+        let service = HttpService::new(
+            server.database_manager.clone(),
+            authenticator,
+            server.transaction_registry.clone(),
+            None,
+            None,
+        );
+        let mut transaction = server.open_transaction("admin", Type::Read).await;
+
+        let listed = admin_request(&service, "GET", "/v1/admin/transactions").await.unwrap();
+        let transactions = listed["transactions"].as_array().unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0]["database"], "admin");
+        assert_eq!(transactions[0]["user"], DEFAULT_USER_NAME);
+        assert_eq!(transactions[0]["transactionType"], "read");
+        let id = transactions[0]["id"].as_str().unwrap().to_owned();
+
+        let unknown = admin_request(&service, "POST", &format!("/v1/admin/transactions/{}/kill", Uuid::new_v4())).await;
+        assert!(matches!(unknown, Err(HttpServiceError::TransactionNotFound { .. })));
+        let malformed = admin_request(&service, "POST", "/v1/admin/transactions/not-an-id/kill").await;
+        assert!(matches!(malformed, Err(HttpServiceError::TransactionNotFound { .. })));
+
+        let killed = admin_request(&service, "POST", &format!("/v1/admin/transactions/{id}/kill")).await;
+        assert_eq!(killed.unwrap(), json!({}));
+        let Some(Err(status)) = transaction.next_for(Uuid::new_v4()).await else {
+            panic!("expected the killed transaction's stream to fail");
+        };
+        assert_eq!(status.get_error_details().error_info().unwrap().reason, "TSV17");
+        // the transaction is unregistered before its stream fails
+        let listed = admin_request(&service, "GET", "/v1/admin/transactions").await.unwrap();
+        assert_eq!(listed["transactions"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn admins_list_and_kill_http_transactions() {
+        let server = TestServer::start().await;
+        server.database_manager.create_database("admin_http").unwrap();
+        let authenticator = Arc::new(Authenticator::new(server.user_manager.clone()));
+        let service = HttpService::new(
+            server.database_manager.clone(),
+            authenticator,
+            server.transaction_registry.clone(),
+            None,
+            None,
+        );
+        let body = json!({ "database": "admin_http", "transactionType": "read" }).to_string().into_bytes();
+        let opened = admin_request_with(&service, "POST", "/v1/transactions/open", body).await.unwrap();
+        let id = opened["transactionId"].as_str().unwrap().to_owned();
+
+        let listed = admin_request(&service, "GET", "/v1/admin/transactions").await.unwrap();
+        let transactions = listed["transactions"].as_array().unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0]["id"], id.as_str());
+        assert_eq!(transactions[0]["database"], "admin_http");
+        assert_eq!(transactions[0]["user"], DEFAULT_USER_NAME);
+        assert_eq!(transactions[0]["transactionType"], "read");
+
+        let killed = admin_request(&service, "POST", &format!("/v1/admin/transactions/{id}/kill")).await;
+        assert_eq!(killed.unwrap(), json!({}));
+        // the kill is handled by a task of its own
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !server.transaction_registry.transactions().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        let body = json!({ "query": "match $x isa entity;" }).to_string().into_bytes();
+        let query = admin_request_with(&service, "POST", &format!("/v1/transactions/{id}/query"), body).await;
+        assert!(matches!(query, Err(HttpServiceError::TransactionNotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn closed_http_transactions_are_unregistered() {
+        let server = TestServer::start().await;
+        server.database_manager.create_database("admin_http_close").unwrap();
+        let authenticator = Arc::new(Authenticator::new(server.user_manager.clone()));
+        let service = HttpService::new(
+            server.database_manager.clone(),
+            authenticator,
+            server.transaction_registry.clone(),
+            None,
+            None,
+        );
+        let body = json!({ "database": "admin_http_close", "transactionType": "read" }).to_string().into_bytes();
+        let opened = admin_request_with(&service, "POST", "/v1/transactions/open", body).await.unwrap();
+        let id = opened["transactionId"].as_str().unwrap().to_owned();
+        assert_eq!(server.transaction_registry.transactions().len(), 1);
+
+        let closed = admin_request(&service, "POST", &format!("/v1/transactions/{id}/close")).await;
+        assert_eq!(closed.unwrap(), json!({}));
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !server.transaction_registry.transactions().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,268 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime};
+use concept::{
+    change_stream::{ChangeEvent, CommittedChanges},
+    thing::ThingAPI,
+};
+use encoding::{
+    graph::type_::vertex::TypeVertexEncoding,
+    value::{decimal_value::Decimal, duration_value::Duration},
+};
+use executor::profile::QueryProfileSummary;
+use serde_json::{json, Map, Value as JSON};
+use storage::isolation_manager::CommitType;
+use typedb_protocol::{
+    concept::Concept,
+    concept_document::{node, node::leaf::Leaf, Node},
+    row_entry::Entry,
+    value::{datetime_tz::Timezone, Value},
+    value_type::ValueType,
+};
+
+const DATETIME_FORMAT: &str = "%FT%T%.9f";
+
+/// Renders a row encoded for the network protocol as a JSON object keyed by column name. Empty entries become `null`.
+pub(crate) fn encode_row_json(row: &typedb_protocol::ConceptRow, columns: &[String]) -> JSON {
+    let entries = columns
+        .iter()
+        .zip(&row.row)
+        .map(|(column, entry)| (column.clone(), entry.entry.as_ref().map_or(JSON::Null, encode_entry_json)))
+        .collect::<Map<_, _>>();
+    JSON::Object(entries)
+}
+
+fn encode_entry_json(entry: &Entry) -> JSON {
+    match entry {
+        Entry::Empty(_) => JSON::Null,
+        Entry::Concept(concept) => encode_concept_json(concept),
+        Entry::Value(value) => encode_value_concept_json(value),
+        Entry::ConceptList(list) => JSON::Array(list.concepts.iter().map(encode_concept_json).collect()),
+        Entry::ValueList(list) => JSON::Array(list.values.iter().map(encode_value_concept_json).collect()),
+    }
+}
+
+/// Renders a fetched document encoded for the network protocol as plain JSON. Attributes and values become JSON
+/// values, and types become objects with their kind and label.
+pub(crate) fn encode_document_json(document: &typedb_protocol::ConceptDocument) -> JSON {
+    document.root.as_ref().map_or(JSON::Null, encode_node_json)
+}
+
+fn encode_node_json(node: &Node) -> JSON {
+    match &node.node {
+        None => JSON::Null,
+        Some(node::Node::List(list)) => JSON::Array(list.list.iter().map(encode_node_json).collect()),
+        Some(node::Node::Map(map)) => {
+            JSON::Object(map.map.iter().map(|(key, node)| (key.clone(), encode_node_json(node))).collect())
+        }
+        Some(node::Node::Leaf(leaf)) => match &leaf.leaf {
+            None | Some(Leaf::Empty(_)) => JSON::Null,
+            Some(Leaf::EntityType(entity_type)) => json!({ "kind": "entityType", "label": entity_type.label }),
+            Some(Leaf::RelationType(relation_type)) => json!({ "kind": "relationType", "label": relation_type.label }),
+            Some(Leaf::AttributeType(attribute_type)) => encode_attribute_type_json(attribute_type),
+            Some(Leaf::RoleType(role_type)) => json!({ "kind": "roleType", "label": role_type.label }),
+            Some(Leaf::Attribute(attribute)) => attribute.value.as_ref().map_or(JSON::Null, encode_value_json),
+            Some(Leaf::Value(value)) => encode_value_json(value),
+        },
+    }
+}
+
+fn encode_concept_json(concept: &typedb_protocol::Concept) -> JSON {
+    match &concept.concept {
+        None => JSON::Null,
+        Some(Concept::EntityType(entity_type)) => json!({ "kind": "entityType", "label": entity_type.label }),
+        Some(Concept::RelationType(relation_type)) => json!({ "kind": "relationType", "label": relation_type.label }),
+        Some(Concept::AttributeType(attribute_type)) => encode_attribute_type_json(attribute_type),
+        Some(Concept::RoleType(role_type)) => json!({ "kind": "roleType", "label": role_type.label }),
+        Some(Concept::Entity(entity)) => json!({
+            "kind": "entity",
+            "iid": encode_iid(&entity.iid),
+            "type": entity.entity_type.as_ref().map(|type_| json!({ "kind": "entityType", "label": type_.label })),
+        }),
+        Some(Concept::Relation(relation)) => json!({
+            "kind": "relation",
+            "iid": encode_iid(&relation.iid),
+            "type": relation.relation_type.as_ref().map(|type_| json!({ "kind": "relationType", "label": type_.label })),
+        }),
+        Some(Concept::Attribute(attribute)) => json!({
+            "kind": "attribute",
+            "iid": encode_iid(&attribute.iid),
+            "value": attribute.value.as_ref().map_or(JSON::Null, encode_value_json),
+            "valueType": attribute.value.as_ref().and_then(value_type_name),
+            "type": attribute.attribute_type.as_ref().map(encode_attribute_type_json),
+        }),
+        Some(Concept::Value(value)) => encode_value_concept_json(value),
+    }
+}
+
+fn encode_attribute_type_json(attribute_type: &typedb_protocol::AttributeType) -> JSON {
+    let value_type = attribute_type.value_type.as_ref().and_then(|value_type| value_type.value_type.as_ref());
+    json!({
+        "kind": "attributeType",
+        "label": attribute_type.label,
+        "valueType": value_type.map(|value_type| match value_type {
+            ValueType::Boolean(_) => "boolean".to_owned(),
+            ValueType::Long(_) => "long".to_owned(),
+            ValueType::Double(_) => "double".to_owned(),
+            ValueType::Decimal(_) => "decimal".to_owned(),
+            ValueType::Date(_) => "date".to_owned(),
+            ValueType::Datetime(_) => "datetime".to_owned(),
+            ValueType::DatetimeTz(_) => "datetime-tz".to_owned(),
+            ValueType::Duration(_) => "duration".to_owned(),
+            ValueType::String(_) => "string".to_owned(),
+            ValueType::Struct(struct_) => struct_.name.clone(),
+        }),
+    })
+}
+
+fn encode_value_concept_json(value: &typedb_protocol::Value) -> JSON {
+    json!({ "kind": "value", "value": encode_value_json(value), "valueType": value_type_name(value) })
+}
+
+fn value_type_name(value: &typedb_protocol::Value) -> Option<&'static str> {
+    let name = match value.value.as_ref()? {
+        Value::Boolean(_) => "boolean",
+        Value::Long(_) => "long",
+        Value::Double(_) => "double",
+        Value::Decimal(_) => "decimal",
+        Value::Date(_) => "date",
+        Value::Datetime(_) => "datetime",
+        Value::DatetimeTz(_) => "datetime-tz",
+        Value::Duration(_) => "duration",
+        Value::String(_) => "string",
+    };
+    Some(name)
+}
+
+/// Values that JSON has no type for are rendered as strings in their TypeQL literal syntax
+fn encode_value_json(value: &typedb_protocol::Value) -> JSON {
+    match &value.value {
+        None => JSON::Null,
+        Some(Value::Boolean(boolean)) => JSON::Bool(*boolean),
+        Some(Value::Long(long)) => JSON::from(*long),
+        Some(Value::Double(double)) => JSON::from(*double),
+        Some(Value::Decimal(decimal)) => JSON::String(Decimal::new(decimal.integer, decimal.fractional).to_string()),
+        Some(Value::Date(date)) => NaiveDate::from_num_days_from_ce_opt(date.num_days_since_ce)
+            .map_or(JSON::Null, |date| JSON::String(date.to_string())),
+        Some(Value::Datetime(datetime)) => decode_datetime(datetime)
+            .map_or(JSON::Null, |datetime| JSON::String(datetime.format(DATETIME_FORMAT).to_string())),
+        Some(Value::DatetimeTz(datetime_tz)) => {
+            let datetime = datetime_tz.datetime.as_ref().and_then(decode_datetime);
+            match (datetime, &datetime_tz.timezone) {
+                (Some(datetime), Some(Timezone::Named(name))) => match name.parse::<chrono_tz::Tz>() {
+                    Ok(tz) => JSON::String(format!(
+                        "{} {name}",
+                        datetime.and_utc().with_timezone(&tz).format(DATETIME_FORMAT)
+                    )),
+                    Err(_) => JSON::Null,
+                },
+                (Some(datetime), Some(Timezone::Offset(seconds))) => match FixedOffset::east_opt(*seconds) {
+                    Some(offset) => {
+                        JSON::String(datetime.and_utc().with_timezone(&offset).format("%FT%T%.9f%:z").to_string())
+                    }
+                    None => JSON::Null,
+                },
+                _ => JSON::Null,
+            }
+        }
+        Some(Value::Duration(duration)) => {
+            JSON::String(Duration { months: duration.months, days: duration.days, nanos: duration.nanos }.to_string())
+        }
+        Some(Value::String(string)) => JSON::String(string.clone()),
+    }
+}
+
+/// Renders a query profile as its stages, each with the rows, batches and time measured for its steps.
+pub(crate) fn encode_profile_json(profile: &QueryProfileSummary) -> JSON {
+    let stages = profile
+        .stages
+        .iter()
+        .map(|stage| {
+            let steps = stage
+                .steps
+                .iter()
+                .map(|step| {
+                    json!({
+                        "step": step.description,
+                        "estimatedRows": step.estimated_rows,
+                        "rows": step.rows,
+                        "batches": step.batches,
+                        "micros": step.micros,
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({ "id": stage.id, "stage": stage.description, "steps": steps })
+        })
+        .collect::<Vec<_>>();
+    json!({ "stages": stages })
+}
+
+/// Renders the changes of one commit as a line of a change stream. Concepts are identified by their IIDs, and role types
+/// by their type IDs, since the labels of deleted types may no longer be readable.
+pub(crate) fn encode_changes_json(changes: &CommittedChanges) -> JSON {
+    let events = changes
+        .events
+        .iter()
+        .map(|event| match event {
+            ChangeEvent::EntityCreated { entity } => {
+                json!({ "event": "entityCreated", "iid": encode_iid(&entity.iid()) })
+            }
+            ChangeEvent::EntityDeleted { entity } => {
+                json!({ "event": "entityDeleted", "iid": encode_iid(&entity.iid()) })
+            }
+            ChangeEvent::RelationCreated { relation } => {
+                json!({ "event": "relationCreated", "iid": encode_iid(&relation.iid()) })
+            }
+            ChangeEvent::RelationDeleted { relation } => {
+                json!({ "event": "relationDeleted", "iid": encode_iid(&relation.iid()) })
+            }
+            ChangeEvent::AttributeCreated { attribute } => {
+                json!({ "event": "attributeCreated", "iid": encode_iid(&attribute.iid()) })
+            }
+            ChangeEvent::AttributeDeleted { attribute } => {
+                json!({ "event": "attributeDeleted", "iid": encode_iid(&attribute.iid()) })
+            }
+            ChangeEvent::HasAdded { owner, attribute } => json!({
+                "event": "hasAdded", "owner": encode_iid(&owner.iid()), "attribute": encode_iid(&attribute.iid())
+            }),
+            ChangeEvent::HasRemoved { owner, attribute } => json!({
+                "event": "hasRemoved", "owner": encode_iid(&owner.iid()), "attribute": encode_iid(&attribute.iid())
+            }),
+            ChangeEvent::LinksAdded { relation, player, role_type } => json!({
+                "event": "linksAdded",
+                "relation": encode_iid(&relation.iid()),
+                "player": encode_iid(&player.iid()),
+                "roleTypeId": role_type.vertex().type_id_().as_u16(),
+            }),
+            ChangeEvent::LinksRemoved { relation, player, role_type } => json!({
+                "event": "linksRemoved",
+                "relation": encode_iid(&relation.iid()),
+                "player": encode_iid(&player.iid()),
+                "roleTypeId": role_type.vertex().type_id_().as_u16(),
+            }),
+            ChangeEvent::SchemaChanged => json!({ "event": "schemaChanged" }),
+            ChangeEvent::BulkLoaded => json!({ "event": "bulkLoaded" }),
+        })
+        .collect::<Vec<_>>();
+    let commit_type = match changes.commit_type {
+        CommitType::Data => "data",
+        CommitType::Schema => "schema",
+        CommitType::BulkLoad => "bulkLoad",
+    };
+    json!({ "sequenceNumber": changes.sequence_number.number(), "commitType": commit_type, "events": events })
+}
+
+fn decode_datetime(datetime: &typedb_protocol::value::Datetime) -> Option<NaiveDateTime> {
+    DateTime::from_timestamp(datetime.seconds, datetime.nanos).map(|datetime| datetime.naive_utc())
+}
+
+fn encode_iid(iid: &[u8]) -> String {
+    let mut string = String::with_capacity(2 + iid.len() * 2);
+    string.push_str("0x");
+    iid.iter().for_each(|byte| string.push_str(&format!("{byte:02x}")));
+    string
+}
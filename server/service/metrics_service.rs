@@ -0,0 +1,434 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{fmt::Write as FmtWrite, io, net::SocketAddr, sync::Arc};
+
+use database::database_manager::DatabaseManager;
+use resource::perf_counters::{
+    Counter, Histogram, ISOLATION_CONFLICT_DELETING_REQUIRED_KEY, ISOLATION_CONFLICT_EXCLUSIVE_LOCK,
+    ISOLATION_CONFLICT_READ_SET_WRITTEN, ISOLATION_CONFLICT_REQUIRE_DELETED_KEY, QUERY_CACHE_FLUSH, QUERY_CACHE_HITS,
+    QUERY_CACHE_MISSES, TRANSACTION_COMMITTED_SCHEMA, TRANSACTION_COMMITTED_WRITE, TRANSACTION_COMMIT_FAILED_SCHEMA,
+    TRANSACTION_COMMIT_FAILED_WRITE, TRANSACTION_COMMIT_LATENCY_SCHEMA, TRANSACTION_COMMIT_LATENCY_WRITE,
+    TRANSACTION_COMMIT_RETRIED_WRITE, TRANSACTION_OPENED_BULK_LOAD, TRANSACTION_OPENED_READ, TRANSACTION_OPENED_SCHEMA,
+    TRANSACTION_OPENED_WRITE, WAL_BYTES_WRITTEN, WAL_FSYNC_LATENCY,
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+};
+use tracing::{event, Level};
+
+use crate::service::http::{http_response, read_request, HttpRequest};
+
+const METRICS_PATH: &str = "/metrics";
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+const ROCKSDB_INT_PROPERTIES: [&str; 7] = [
+    "rocksdb.estimate-num-keys",
+    "rocksdb.estimate-live-data-size",
+    "rocksdb.total-sst-files-size",
+    "rocksdb.cur-size-all-mem-tables",
+    "rocksdb.block-cache-usage",
+    "rocksdb.num-running-compactions",
+    "rocksdb.estimate-pending-compaction-bytes",
+];
+
+/// Serves the server's metrics in the Prometheus text exposition format over plain HTTP, reading requests with the
+/// same HTTP stack as the HTTP API.
+#[derive(Debug)]
+pub(crate) struct MetricsService {
+    database_manager: Arc<DatabaseManager>,
+}
+
+impl MetricsService {
+    pub(crate) fn new(database_manager: Arc<DatabaseManager>) -> Self {
+        Self { database_manager }
+    }
+
+    pub(crate) async fn serve(self, address: SocketAddr) -> io::Result<()> {
+        self.serve_listener(TcpListener::bind(address).await?).await
+    }
+
+    async fn serve_listener(self, listener: TcpListener) -> io::Result<()> {
+        let service = Arc::new(self);
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let service = service.clone();
+            tokio::spawn(async move {
+                if let Err(error) = service.respond(stream).await {
+                    event!(Level::DEBUG, ?error, "Failed to respond to metrics request");
+                }
+            });
+        }
+    }
+
+    async fn respond(&self, mut stream: TcpStream) -> io::Result<()> {
+        // metrics requests have no body, so any body is refused as too large
+        let response = match read_request(&mut stream, 0).await? {
+            None => return Ok(()),
+            Some(Err(_)) => http_response("413 Payload Too Large", "text/plain", b"Request body not allowed.\n"),
+            Some(Ok(request)) => self.response(&request),
+        };
+        stream.write_all(&response).await?;
+        stream.shutdown().await
+    }
+
+    fn response(&self, request: &HttpRequest) -> Vec<u8> {
+        match (request.method.as_str(), request.route()) {
+            ("GET", METRICS_PATH) => http_response("200 OK", PROMETHEUS_CONTENT_TYPE, self.render().as_bytes()),
+            ("GET", _) => http_response("404 Not Found", "text/plain", b"Not found.\n"),
+            _ => http_response("405 Method Not Allowed", "text/plain", b"Method not allowed.\n"),
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+
+        write_counter_family(
+            &mut out,
+            "typedb_transactions_opened_total",
+            "Transactions opened, by transaction type.",
+            "type",
+            &[
+                ("read", &TRANSACTION_OPENED_READ),
+                ("write", &TRANSACTION_OPENED_WRITE),
+                ("schema", &TRANSACTION_OPENED_SCHEMA),
+                ("bulk_load", &TRANSACTION_OPENED_BULK_LOAD),
+            ],
+        );
+        write_counter_family(
+            &mut out,
+            "typedb_transactions_committed_total",
+            "Transactions committed successfully, by transaction type.",
+            "type",
+            &[("write", &TRANSACTION_COMMITTED_WRITE), ("schema", &TRANSACTION_COMMITTED_SCHEMA)],
+        );
+        write_counter_family(
+            &mut out,
+            "typedb_transaction_commits_failed_total",
+            "Transaction commits that failed, by transaction type.",
+            "type",
+            &[("write", &TRANSACTION_COMMIT_FAILED_WRITE), ("schema", &TRANSACTION_COMMIT_FAILED_SCHEMA)],
+        );
+        write_counter_family(
+            &mut out,
+            "typedb_transaction_commit_retries_total",
+            "Transaction commits retried on a fresh transaction after an isolation conflict, by transaction type.",
+            "type",
+            &[("write", &TRANSACTION_COMMIT_RETRIED_WRITE)],
+        );
+        write_histogram_family(
+            &mut out,
+            "typedb_transaction_commit_duration_seconds",
+            "Transaction commit latency, by transaction type.",
+            "type",
+            &[("write", &TRANSACTION_COMMIT_LATENCY_WRITE), ("schema", &TRANSACTION_COMMIT_LATENCY_SCHEMA)],
+        );
+        write_counter_family(
+            &mut out,
+            "typedb_isolation_conflicts_total",
+            "Commits rejected by isolation validation, by conflict kind.",
+            "kind",
+            &[
+                ("deleting_required_key", &ISOLATION_CONFLICT_DELETING_REQUIRED_KEY),
+                ("require_deleted_key", &ISOLATION_CONFLICT_REQUIRE_DELETED_KEY),
+                ("exclusive_lock", &ISOLATION_CONFLICT_EXCLUSIVE_LOCK),
+                ("read_set_written", &ISOLATION_CONFLICT_READ_SET_WRITTEN),
+            ],
+        );
+        write_counter_family(
+            &mut out,
+            "typedb_wal_written_bytes_total",
+            "Bytes written to the write-ahead log.",
+            "",
+            &[("", &WAL_BYTES_WRITTEN)],
+        );
+        write_histogram_family(
+            &mut out,
+            "typedb_wal_fsync_duration_seconds",
+            "Write-ahead log fsync latency.",
+            "",
+            &[("", &WAL_FSYNC_LATENCY)],
+        );
+        write_counter_family(
+            &mut out,
+            "typedb_query_cache_requests_total",
+            "Query plan cache lookups, by result.",
+            "result",
+            &[("hit", &QUERY_CACHE_HITS), ("miss", &QUERY_CACHE_MISSES)],
+        );
+        write_counter_family(
+            &mut out,
+            "typedb_query_cache_flushes_total",
+            "Query plan cache flushes.",
+            "",
+            &[("", &QUERY_CACHE_FLUSH)],
+        );
+
+        self.write_database_metrics(&mut out);
+        out
+    }
+
+    fn write_database_metrics(&self, out: &mut String) {
+        let databases = self
+            .database_manager
+            .database_names()
+            .into_iter()
+            .filter_map(|name| self.database_manager.database(&name))
+            .collect::<Vec<_>>();
+
+        write_header(
+            out,
+            "typedb_database_statistics_count",
+            "Thing statistics totals, by database and kind.",
+            "gauge",
+        );
+        for database in &databases {
+            let statistics = database.thing_statistics();
+            for (kind, value) in [
+                ("thing", statistics.total_thing_count),
+                ("entity", statistics.total_entity_count),
+                ("relation", statistics.total_relation_count),
+                ("attribute", statistics.total_attribute_count),
+                ("role", statistics.total_role_count),
+                ("has", statistics.total_has_count),
+            ] {
+                writeln!(
+                    out,
+                    "typedb_database_statistics_count{{database=\"{}\",kind=\"{kind}\"}} {value}",
+                    escape_label_value(database.name())
+                )
+                .unwrap();
+            }
+        }
+
+        write_header(
+            out,
+            "typedb_database_statistics_sequence_number",
+            "Sequence number the thing statistics are up to date with, by database.",
+            "gauge",
+        );
+        for database in &databases {
+            writeln!(
+                out,
+                "typedb_database_statistics_sequence_number{{database=\"{}\"}} {}",
+                escape_label_value(database.name()),
+                database.thing_statistics().sequence_number.number()
+            )
+            .unwrap();
+        }
+
+        write_header(
+            out,
+            "typedb_replication_lag",
+            "Sequence numbers a read replica is behind its primary, by database.",
+            "gauge",
+        );
+        for database in &databases {
+            if let Some(lag) = database.replication_lag() {
+                writeln!(out, "typedb_replication_lag{{database=\"{}\"}} {lag}", escape_label_value(database.name()))
+                    .unwrap();
+            }
+        }
+
+        for property in ROCKSDB_INT_PROPERTIES {
+            let metric_name = format!("typedb_{}", property.replace(['.', '-'], "_"));
+            write_header(
+                out,
+                &metric_name,
+                &format!("RocksDB property '{property}', by database and keyspace."),
+                "gauge",
+            );
+            for database in &databases {
+                match database.storage().keyspaces_property_int_value(property) {
+                    Ok(values) => {
+                        for (keyspace, value) in values {
+                            if let Some(value) = value {
+                                writeln!(
+                                    out,
+                                    "{metric_name}{{database=\"{}\",keyspace=\"{}\"}} {value}",
+                                    escape_label_value(database.name()),
+                                    escape_label_value(keyspace)
+                                )
+                                .unwrap();
+                            }
+                        }
+                    }
+                    Err(error) => event!(Level::DEBUG, ?error, "Failed to read RocksDB property '{}'", property),
+                }
+            }
+        }
+    }
+}
+
+fn write_header(out: &mut String, name: &str, help: &str, metric_type: &str) {
+    writeln!(out, "# HELP {name} {help}").unwrap();
+    writeln!(out, "# TYPE {name} {metric_type}").unwrap();
+}
+
+/// Label values may hold any text, so backslashes, double quotes and line feeds are escaped as the exposition format
+/// requires.
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn labels(label_name: &str, label_value: &str, extra: Option<(&str, String)>) -> String {
+    let mut pairs = Vec::new();
+    if !label_name.is_empty() {
+        pairs.push(format!("{label_name}=\"{}\"", escape_label_value(label_value)));
+    }
+    if let Some((name, value)) = extra {
+        pairs.push(format!("{name}=\"{}\"", escape_label_value(&value)));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn write_counter_family(out: &mut String, name: &str, help: &str, label_name: &str, counters: &[(&str, &Counter)]) {
+    write_header(out, name, help, "counter");
+    for (label_value, counter) in counters {
+        writeln!(out, "{name}{} {}", labels(label_name, label_value, None), counter.get()).unwrap();
+    }
+}
+
+fn write_histogram_family(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label_name: &str,
+    histograms: &[(&str, &Histogram)],
+) {
+    write_header(out, name, help, "histogram");
+    for (label_value, histogram) in histograms {
+        for (bound_micros, cumulative) in histogram.cumulative_buckets() {
+            let bound_seconds = bound_micros as f64 / 1_000_000.0;
+            let labels = labels(label_name, label_value, Some(("le", bound_seconds.to_string())));
+            writeln!(out, "{name}_bucket{labels} {cumulative}").unwrap();
+        }
+        let labels_inf = labels(label_name, label_value, Some(("le", "+Inf".to_owned())));
+        writeln!(out, "{name}_bucket{labels_inf} {}", histogram.count()).unwrap();
+        let plain_labels = labels(label_name, label_value, None);
+        writeln!(out, "{name}_sum{plain_labels} {}", histogram.sum_micros() as f64 / 1_000_000.0).unwrap();
+        writeln!(out, "{name}_count{plain_labels} {}", histogram.count()).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc, time::Duration};
+
+    use database::database_manager::DatabaseManager;
+    use resource::perf_counters::{Counter, Histogram};
+    use test_utils::create_tmp_dir;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    use super::{write_counter_family, write_histogram_family, MetricsService};
+    use crate::service::http::{HttpRequest, MAX_REQUEST_HEAD_BYTES};
+
+    #[test]
+    fn counter_family_is_rendered_with_labels() {
+        let read = Counter::new(true);
+        let write = Counter::new(true);
+        read.add(3);
+        write.increment();
+        let mut out = String::new();
+        write_counter_family(&mut out, "opened_total", "Opened.", "type", &[("read", &read), ("write", &write)]);
+        assert_eq!(
+            out,
+            "# HELP opened_total Opened.\n\
+             # TYPE opened_total counter\n\
+             opened_total{type=\"read\"} 3\n\
+             opened_total{type=\"write\"} 1\n"
+        );
+
+        let mut out = String::new();
+        write_counter_family(&mut out, "bytes_total", "Bytes.", "", &[("", &read)]);
+        assert!(out.ends_with("bytes_total 3\n"));
+    }
+
+    #[test]
+    fn histogram_family_is_rendered_cumulatively() {
+        let histogram = Histogram::new(true);
+        histogram.record(Duration::from_micros(50));
+        histogram.record(Duration::from_micros(200));
+        histogram.record(Duration::from_secs(5));
+        let mut out = String::new();
+        write_histogram_family(&mut out, "latency_seconds", "Latency.", "", &[("", &histogram)]);
+
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines[1], "# TYPE latency_seconds histogram");
+        assert_eq!(lines[2], "latency_seconds_bucket{le=\"0.0001\"} 1");
+        assert_eq!(lines[3], "latency_seconds_bucket{le=\"0.00025\"} 2");
+        assert!(lines.contains(&"latency_seconds_bucket{le=\"1\"} 2"));
+        assert!(lines.contains(&"latency_seconds_bucket{le=\"+Inf\"} 3"));
+        assert!(lines.contains(&"latency_seconds_sum 5.00025"));
+        assert!(lines.contains(&"latency_seconds_count 3"));
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        let counter = Counter::new(true);
+        let mut out = String::new();
+        write_counter_family(&mut out, "total", "Total.", "database", &[("a\\b\"c\nd", &counter)]);
+        assert!(out.ends_with("total{database=\"a\\\\b\\\"c\\nd\"} 0\n"), "{out}");
+    }
+
+    #[test]
+    fn requests_are_routed_by_method_and_path() {
+        let data_directory = create_tmp_dir();
+        let service = MetricsService::new(Arc::new(DatabaseManager::new(&data_directory).unwrap()));
+        let response = |method: &str, path: &str| {
+            let request = HttpRequest {
+                method: method.to_owned(),
+                path: path.to_owned(),
+                headers: HashMap::new(),
+                body: Vec::new(),
+            };
+            String::from_utf8(service.response(&request)).unwrap()
+        };
+
+        let metrics = response("GET", "/metrics");
+        assert!(metrics.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(metrics.contains("# TYPE typedb_transactions_opened_total counter"));
+        assert!(metrics.contains("# TYPE typedb_transaction_commit_duration_seconds histogram"));
+        assert!(response("GET", "/metrics?format=text").starts_with("HTTP/1.1 200 OK\r\n"));
+
+        assert!(response("GET", "/other").starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(response("POST", "/metrics").starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+
+    #[tokio::test]
+    async fn metrics_are_served_over_tcp() {
+        let data_directory = create_tmp_dir();
+        let service = MetricsService::new(Arc::new(DatabaseManager::new(&data_directory).unwrap()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(service.serve_listener(listener));
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n").await.unwrap();
+        stream.write_all(b"Host: localhost\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.contains(&format!("Content-Length: {}", body.len())));
+
+        // request heads beyond the limit are dropped without a response
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let oversized = format!("GET /metrics HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "a".repeat(MAX_REQUEST_HEAD_BYTES));
+        let _ = stream.write_all(oversized.as_bytes()).await;
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response).await;
+        assert!(response.is_empty());
+    }
+}
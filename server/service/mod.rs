@@ -4,12 +4,25 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+pub(crate) mod admin_service;
+mod arrow;
+pub(crate) mod change_stream_service;
 mod concept;
 mod document;
 mod error;
+mod grpc;
+mod http;
+pub(crate) mod http_service;
+mod json;
+pub(crate) mod metrics_service;
+mod query_batch;
 mod request_parser;
 mod response_builders;
 mod row;
+pub(crate) mod slow_query_log;
+#[cfg(test)]
+mod test_client;
+pub(crate) mod transaction_registry;
 pub(crate) mod transaction_service;
 pub(crate) mod typedb_service;
 
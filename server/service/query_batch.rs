@@ -0,0 +1,296 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use error::typedb_error;
+use resource::constants::server::{QUERY_BATCH_METADATA_FIELD, QUERY_PARAMETERS_METADATA_FIELD};
+use serde_json::Value as JSON;
+use tokio::sync::oneshot;
+
+// Until the protocol carries batches, they are marked by request metadata: consecutive query requests of one client
+// message marked with the same mode form a batch, executed in order through the transaction's query queue
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum QueryBatchMode {
+    StopOnError,
+    Continue,
+}
+
+impl QueryBatchMode {
+    const STOP_ON_ERROR: &'static str = "stop-on-error";
+    const CONTINUE: &'static str = "continue";
+
+    pub(crate) fn from_metadata(metadata: &HashMap<String, String>) -> Result<Option<Self>, QueryBatchError> {
+        match metadata.get(QUERY_BATCH_METADATA_FIELD).map(String::as_str) {
+            None => Ok(None),
+            Some(Self::STOP_ON_ERROR) => Ok(Some(Self::StopOnError)),
+            Some(Self::CONTINUE) => Ok(Some(Self::Continue)),
+            Some(mode) => Err(QueryBatchError::UnrecognisedBatchMode { mode: mode.to_owned() }),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct QueryBatch {
+    pub(crate) mode: QueryBatchMode,
+    // shared with the steps of the batch, since queries fail while they execute after the batch was formed
+    failed: Arc<AtomicBool>,
+    // closed once the query of the batch last stepped has finished executing
+    previous_finished: Option<oneshot::Receiver<()>>,
+}
+
+impl QueryBatch {
+    pub(crate) fn new(mode: QueryBatchMode) -> Self {
+        Self { mode, failed: Arc::new(AtomicBool::new(false)), previous_finished: None }
+    }
+
+    pub(crate) fn fail(&self) {
+        self.failed.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.mode == QueryBatchMode::StopOnError && self.failed.load(Ordering::SeqCst)
+    }
+
+    /// The place of the next query in the batch, carried by the query while it is queued and executed.
+    pub(crate) fn step(&mut self) -> QueryBatchStep {
+        let (finished, next_finished) = oneshot::channel();
+        let previous_finished = self.previous_finished.replace(next_finished);
+        QueryBatchStep { mode: self.mode, failed: self.failed.clone(), previous_finished, _finished: finished }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct QueryBatchStep {
+    mode: QueryBatchMode,
+    failed: Arc<AtomicBool>,
+    previous_finished: Option<oneshot::Receiver<()>>,
+    // never sent, only dropped with the step once its query has finished
+    _finished: oneshot::Sender<()>,
+}
+
+impl QueryBatchStep {
+    /// In stop-on-error mode, blocks until the previous query of the batch has finished executing, so that its failure
+    /// stops this query. Answers whether this query may execute.
+    pub(crate) fn wait_for_previous(&mut self) -> bool {
+        if let (QueryBatchMode::StopOnError, Some(previous_finished)) = (self.mode, self.previous_finished.take()) {
+            // the sender is dropped rather than sent, so the error is the expected outcome
+            let _ = previous_finished.blocking_recv();
+        }
+        !self.is_stopped()
+    }
+
+    pub(crate) fn fail(&self) {
+        self.failed.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.mode == QueryBatchMode::StopOnError && self.failed.load(Ordering::SeqCst)
+    }
+}
+
+/// Binds the parameters given as a JSON object in the request metadata, replacing each variable named by a parameter
+/// with the parameter's value as a TypeQL literal. Strings are escaped, so a value can never change the query's
+/// structure. Every parameter must be used by the query.
+pub(crate) fn bind_parameters<'a>(
+    query: &'a str,
+    metadata: &HashMap<String, String>,
+) -> Result<Cow<'a, str>, QueryBatchError> {
+    let Some(parameters) = metadata.get(QUERY_PARAMETERS_METADATA_FIELD) else { return Ok(Cow::Borrowed(query)) };
+    let parameters = match serde_json::from_str(parameters) {
+        Ok(JSON::Object(parameters)) => parameters,
+        Ok(_) => return Err(QueryBatchError::ParametersNotObject {}),
+        Err(source) => return Err(QueryBatchError::ParametersParseFailed { source: Arc::new(source) }),
+    };
+    let literals = parameters
+        .iter()
+        .map(|(name, value)| Ok((name.as_str(), typeql_literal(name, value)?)))
+        .collect::<Result<HashMap<_, _>, QueryBatchError>>()?;
+
+    let mut bound = String::with_capacity(query.len());
+    let mut used = HashSet::new();
+    let mut chars = query.char_indices().peekable();
+    while let Some((index, char)) = chars.next() {
+        match char {
+            '"' | '\'' => {
+                bound.push(char);
+                // strings are copied as written, including escaped quotes
+                while let Some((_, next)) = chars.next() {
+                    bound.push(next);
+                    match next {
+                        '\\' => bound.extend(chars.next().map(|(_, escaped)| escaped)),
+                        _ if next == char => break,
+                        _ => (),
+                    }
+                }
+            }
+            '#' => {
+                bound.push(char);
+                for (_, next) in chars.by_ref() {
+                    bound.push(next);
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '$' => {
+                let start = index + char.len_utf8();
+                let mut end = start;
+                while let Some((next_index, next)) = chars.next_if(|(_, next)| is_variable_char(*next)) {
+                    end = next_index + next.len_utf8();
+                }
+                let name = &query[start..end];
+                match literals.get(name) {
+                    Some(literal) => {
+                        used.insert(name);
+                        bound.push_str(literal);
+                    }
+                    None => bound.push_str(&query[index..end]),
+                }
+            }
+            _ => bound.push(char),
+        }
+    }
+
+    match literals.keys().find(|name| !used.contains(*name)) {
+        Some(name) => Err(QueryBatchError::UnusedParameter { name: name.to_string() }),
+        None => Ok(Cow::Owned(bound)),
+    }
+}
+
+fn is_variable_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '_' || char == '-'
+}
+
+fn typeql_literal(name: &str, value: &JSON) -> Result<String, QueryBatchError> {
+    match value {
+        JSON::Bool(bool) => Ok(bool.to_string()),
+        JSON::Number(number) => match (number.as_i64(), number.as_f64()) {
+            (Some(integer), _) => Ok(integer.to_string()),
+            (None, Some(double)) => {
+                // a double literal must have a decimal point, which the `Display` of whole numbers omits
+                let literal = double.to_string();
+                Ok(if literal.contains('.') { literal } else { format!("{literal}.0") })
+            }
+            (None, None) => Err(QueryBatchError::UnsupportedParameterValue { name: name.to_owned() }),
+        },
+        JSON::String(string) => Ok(string_literal(string)),
+        JSON::Null | JSON::Array(_) | JSON::Object(_) => {
+            Err(QueryBatchError::UnsupportedParameterValue { name: name.to_owned() })
+        }
+    }
+}
+
+// Quotes and backslashes are escaped so a string cannot end its literal early, and line breaks so it stays on one line
+fn string_literal(string: &str) -> String {
+    let mut literal = String::with_capacity(string.len() + 2);
+    literal.push('"');
+    for char in string.chars() {
+        match char {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            _ => literal.push(char),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+typedb_error!(
+    pub(crate) QueryBatchError(component = "Query batch", prefix = "QBT") {
+        UnrecognisedBatchMode(
+            1,
+            "Unrecognised query batch mode '{mode}'. It must be 'stop-on-error' or 'continue'.",
+            mode: String
+        ),
+        QuerySkipped(2, "The query was not executed, since an earlier query of its batch failed."),
+        ParametersParseFailed(3, "The query parameters are not valid JSON.", ( source: Arc<serde_json::Error> )),
+        ParametersNotObject(4, "The query parameters must be a JSON object, from variable names to values."),
+        UnsupportedParameterValue(5, "Parameter '{name}' must be a string, number or boolean.", name: String),
+        UnusedParameter(6, "Parameter '{name}' does not name a variable of the query.", name: String),
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use resource::constants::server::QUERY_PARAMETERS_METADATA_FIELD;
+
+    use super::{bind_parameters, QueryBatchError};
+
+    fn bind(query: &str, parameters: &str) -> Result<String, QueryBatchError> {
+        let metadata = HashMap::from([(QUERY_PARAMETERS_METADATA_FIELD.to_owned(), parameters.to_owned())]);
+        bind_parameters(query, &metadata).map(|bound| bound.into_owned())
+    }
+
+    #[test]
+    fn variables_in_strings_and_comments_are_not_bound() {
+        let query = "match $p has name $name; # or $name\n$q has name \"$name\", has nickname '$name';";
+        let bound = bind(query, r#"{"name": "Alice"}"#).unwrap();
+        assert_eq!(bound, "match $p has name \"Alice\"; # or $name\n$q has name \"$name\", has nickname '$name';");
+    }
+
+    #[test]
+    fn escaped_quotes_do_not_end_strings() {
+        let bound = bind(r#"match $p has name "say \"$name\""; $q has name $name;"#, r#"{"name": "Bob"}"#).unwrap();
+        assert_eq!(bound, r#"match $p has name "say \"$name\""; $q has name "Bob";"#);
+    }
+
+    #[test]
+    fn string_values_are_escaped() {
+        let parameters = r#"{"name": "a \"quoted\"; insert $x isa person; \\ and\na new line"}"#;
+        let bound = bind("match $p has name $name;", parameters).unwrap();
+        assert_eq!(bound, r#"match $p has name "a \"quoted\"; insert $x isa person; \\ and\na new line";"#);
+    }
+
+    #[test]
+    fn values_of_each_type_become_literals() {
+        let parameters = r#"{"long": 10, "double": 2.5, "whole": 3e0, "flag": true}"#;
+        let bound = bind("let $a = $long; let $b = $double; let $c = $whole; let $d = $flag;", parameters).unwrap();
+        assert_eq!(bound, "let $a = 10; let $b = 2.5; let $c = 3.0; let $d = true;");
+        let error = bind("let $a = $list;", r#"{"list": [1]}"#).unwrap_err();
+        assert!(matches!(error, QueryBatchError::UnsupportedParameterValue { name, .. } if name == "list"));
+    }
+
+    #[test]
+    fn a_name_prefixing_another_binds_only_its_own_variable() {
+        let bound = bind("let $total = $a + $ab + $a-b + $a_b;", r#"{"a": 1, "ab": 2}"#).unwrap();
+        assert_eq!(bound, "let $total = 1 + 2 + $a-b + $a_b;");
+    }
+
+    #[test]
+    fn variables_without_a_parameter_are_left_unbound() {
+        let bound = bind("match $p has age $age; $p has name $name;", r#"{"age": 30}"#).unwrap();
+        assert_eq!(bound, "match $p has age 30; $p has name $name;");
+    }
+
+    #[test]
+    fn parameters_must_be_used() {
+        let error = bind("match $p has age $age;", r#"{"age": 30, "name": "Alice"}"#).unwrap_err();
+        assert!(matches!(error, QueryBatchError::UnusedParameter { name, .. } if name == "name"));
+        // a parameter is not used by a variable named in a string
+        let error = bind(r#"match $p has name "$name";"#, r#"{"name": "Alice"}"#).unwrap_err();
+        assert!(matches!(error, QueryBatchError::UnusedParameter { name, .. } if name == "name"));
+    }
+
+    #[test]
+    fn parameters_must_be_a_json_object() {
+        assert!(matches!(bind("match $p;", "[1]"), Err(QueryBatchError::ParametersNotObject {})));
+        assert!(matches!(bind("match $p;", "{"), Err(QueryBatchError::ParametersParseFailed { .. })));
+        assert_eq!(bind_parameters("match $p;", &HashMap::new()).unwrap(), "match $p;");
+    }
+}
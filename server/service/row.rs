@@ -5,23 +5,25 @@
  */
 
 use answer::variable_value::VariableValue;
-use compiler::VariablePosition;
 use concept::{error::ConceptReadError, thing::thing_manager::ThingManager, type_::type_manager::TypeManager};
 use executor::row::MaybeOwnedRow;
 use storage::snapshot::ReadableSnapshot;
 
-use crate::service::concept::{encode_thing_concept, encode_type_concept, encode_value};
+use crate::service::{
+    concept::{encode_thing_concept, encode_type_concept, encode_value},
+    transaction_service::StreamQueryOutputDescriptor,
+};
 
 pub(crate) fn encode_row(
     row: MaybeOwnedRow<'_>,
-    columns: &[(String, VariablePosition)],
+    columns: &StreamQueryOutputDescriptor,
     snapshot: &impl ReadableSnapshot,
     type_manager: &TypeManager,
     thing_manager: &ThingManager,
 ) -> Result<typedb_protocol::ConceptRow, Box<ConceptReadError>> {
     // TODO: multiplicity?
     let mut encoded_row = Vec::with_capacity(columns.len());
-    for (_, position) in columns {
+    for (_, position, _) in columns {
         let variable_value = row.get(*position);
         let row_entry = encode_row_entry(variable_value, snapshot, type_manager, thing_manager)?;
         encoded_row.push(typedb_protocol::RowEntry { entry: Some(row_entry) });
@@ -29,6 +31,20 @@ pub(crate) fn encode_row(
     Ok(typedb_protocol::ConceptRow { row: encoded_row })
 }
 
+/// The final row of a profiled query, whose only entry is the profile in the trailing `profile` column
+pub(crate) fn encode_profile_row(columns: usize, profile: String) -> typedb_protocol::ConceptRow {
+    let mut row = vec![empty_row_entry(); columns];
+    let profile = typedb_protocol::Value { value: Some(typedb_protocol::value::Value::String(profile)) };
+    row.push(typedb_protocol::RowEntry { entry: Some(typedb_protocol::row_entry::Entry::Value(profile)) });
+    typedb_protocol::ConceptRow { row }
+}
+
+pub(crate) fn empty_row_entry() -> typedb_protocol::RowEntry {
+    typedb_protocol::RowEntry {
+        entry: Some(typedb_protocol::row_entry::Entry::Empty(typedb_protocol::row_entry::Empty {})),
+    }
+}
+
 pub(crate) fn encode_row_entry(
     variable_value: &VariableValue<'_>,
     snapshot: &impl ReadableSnapshot,
@@ -0,0 +1,214 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    fmt,
+    io::{self, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use executor::profile::QueryProfile;
+use logger::rotating_file::RotatingFileWriter;
+use resource::constants::server::SLOW_QUERY_LOG_FILE_NAME;
+use serde_json::{json, Value as JSON};
+use tracing::{event, Level};
+
+use crate::parameters::config::SlowQueryLogConfig;
+
+/// Records queries that exceed the configured threshold as JSON lines in a size-rotated file.
+/// Queries are only timed by default. Their step measurements are recorded when the log is configured to profile every
+/// query, or when the client asked for the query to be profiled.
+#[derive(Debug)]
+pub(crate) struct SlowQueryLog {
+    threshold: Duration,
+    profile: bool,
+    writer: Mutex<RotatingFileWriter>,
+}
+
+pub(crate) struct SlowQuery<'a> {
+    pub(crate) query: &'a dyn fmt::Display,
+    pub(crate) database: &'a str,
+    pub(crate) transaction_type: &'static str,
+    pub(crate) rows: u64,
+    pub(crate) elapsed: Duration,
+    pub(crate) profile: &'a QueryProfile,
+}
+
+/// Times a single query from the moment it is accepted, and reports it to the slow query log once it finishes.
+#[derive(Debug)]
+pub(crate) struct SlowQueryTimer {
+    log: Arc<SlowQueryLog>,
+    database: String,
+    transaction_type: &'static str,
+    start: Instant,
+}
+
+impl SlowQueryTimer {
+    pub(crate) fn start(log: Arc<SlowQueryLog>, database: &str, transaction_type: &'static str) -> Self {
+        Self { log, database: database.to_owned(), transaction_type, start: Instant::now() }
+    }
+
+    pub(crate) fn finish(self, query: &dyn fmt::Display, rows: u64, profile: &QueryProfile) {
+        self.log.record(SlowQuery {
+            query,
+            database: &self.database,
+            transaction_type: self.transaction_type,
+            rows,
+            elapsed: self.start.elapsed(),
+            profile,
+        })
+    }
+}
+
+impl SlowQueryLog {
+    pub(crate) fn open(config: &SlowQueryLogConfig, directory: &Path) -> io::Result<Self> {
+        let writer =
+            RotatingFileWriter::open(directory, SLOW_QUERY_LOG_FILE_NAME, config.max_file_size, config.max_files)?;
+        Ok(Self { threshold: config.threshold, profile: config.profile, writer: Mutex::new(writer) })
+    }
+
+    pub(crate) fn profiles_queries(&self) -> bool {
+        self.profile
+    }
+
+    pub(crate) fn is_slow(&self, elapsed: Duration) -> bool {
+        elapsed >= self.threshold
+    }
+
+    pub(crate) fn record(&self, slow_query: SlowQuery<'_>) {
+        if !self.is_slow(slow_query.elapsed) {
+            return;
+        }
+        let SlowQuery { query, database, transaction_type, rows, elapsed, profile } = slow_query;
+        let stages = profile.is_enabled().then(|| Self::profile_json(profile));
+        let line = json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "database": database,
+            "transaction_type": transaction_type,
+            "query": query.to_string(),
+            "rows": rows,
+            "elapsed_micros": elapsed.as_micros() as u64,
+            "profile": stages,
+        });
+
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(error) = writer.write_all(format!("{line}\n").as_bytes()) {
+            event!(Level::WARN, ?error, "Failed to write to the slow query log");
+        }
+    }
+
+    fn profile_json(profile: &QueryProfile) -> Vec<JSON> {
+        profile
+            .summary()
+            .stages
+            .into_iter()
+            .map(|stage| {
+                let steps = stage
+                    .steps
+                    .into_iter()
+                    .map(|step| {
+                        json!({
+                            "step": step.description.lines().next().unwrap_or_default(),
+                            "estimated_rows": step.estimated_rows,
+                            "rows": step.rows,
+                            "batches": step.batches,
+                            "micros": step.micros,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                json!({ "stage": stage.description, "steps": steps })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path, time::Duration};
+
+    use executor::profile::QueryProfile;
+    use serde_json::Value as JSON;
+    use test_utils::create_tmp_dir;
+
+    use super::{SlowQuery, SlowQueryLog, SLOW_QUERY_LOG_FILE_NAME};
+    use crate::parameters::config::SlowQueryLogConfig;
+
+    fn open_log(directory: &Path, threshold_millis: u64, profile: bool) -> SlowQueryLog {
+        let config = SlowQueryLogConfig::new(true, Some(threshold_millis), None, profile);
+        SlowQueryLog::open(&config, directory).unwrap()
+    }
+
+    fn record(log: &SlowQueryLog, query: &str, elapsed: Duration, profile: &QueryProfile) {
+        log.record(SlowQuery { query: &query, database: "test", transaction_type: "read", rows: 3, elapsed, profile });
+    }
+
+    fn read_lines(directory: &Path) -> Vec<JSON> {
+        let contents = fs::read_to_string(directory.join(SLOW_QUERY_LOG_FILE_NAME)).unwrap_or_default();
+        contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn only_queries_over_the_threshold_are_recorded() {
+        let directory = create_tmp_dir();
+        let log = open_log(&directory, 100, false);
+        let profile = QueryProfile::new(false);
+        record(&log, "match $x isa fast;", Duration::from_millis(99), &profile);
+        record(&log, "match $x isa slow;", Duration::from_millis(100), &profile);
+        record(&log, "match $x isa slower;", Duration::from_secs(5), &profile);
+
+        let lines = read_lines(&directory);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["query"], "match $x isa slow;");
+        assert_eq!(lines[0]["database"], "test");
+        assert_eq!(lines[0]["transaction_type"], "read");
+        assert_eq!(lines[0]["rows"], 3);
+        assert_eq!(lines[0]["elapsed_micros"], 100_000);
+        assert_eq!(lines[1]["query"], "match $x isa slower;");
+        assert!(lines[1]["timestamp"].is_string());
+    }
+
+    #[test]
+    fn unprofiled_queries_are_recorded_without_a_profile() {
+        let directory = create_tmp_dir();
+        let log = open_log(&directory, 0, false);
+        assert!(!log.profiles_queries());
+        record(&log, "match $x isa thing;", Duration::ZERO, &QueryProfile::new(false));
+
+        let lines = read_lines(&directory);
+        assert!(lines[0]["profile"].is_null());
+    }
+
+    #[test]
+    fn profiled_queries_are_recorded_with_their_stages() {
+        let directory = create_tmp_dir();
+        let log = open_log(&directory, 0, true);
+        assert!(log.profiles_queries());
+        let profile = QueryProfile::new(true);
+        profile.profile_stage(|| "Match".to_owned(), 7);
+        profile.profile_stage(|| "Limit".to_owned(), 3);
+        record(&log, "match $x isa thing; limit 1;", Duration::ZERO, &profile);
+
+        let lines = read_lines(&directory);
+        let stages = lines[0]["profile"].as_array().unwrap();
+        assert_eq!(stages.len(), 2);
+        assert_eq!(stages[0]["stage"], "Limit");
+        assert_eq!(stages[1]["stage"], "Match");
+        assert!(stages[1]["steps"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn multi_line_queries_stay_on_one_line() {
+        let directory = create_tmp_dir();
+        let log = open_log(&directory, 0, false);
+        record(&log, "match\n  $x isa thing;\n", Duration::ZERO, &QueryProfile::new(false));
+
+        let contents = fs::read_to_string(directory.join(SLOW_QUERY_LOG_FILE_NAME)).unwrap();
+        assert_eq!(contents.lines().count(), 1);
+        assert_eq!(read_lines(&directory)[0]["query"], "match\n  $x isa thing;\n");
+    }
+}
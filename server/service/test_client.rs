@@ -0,0 +1,240 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    sync::Arc,
+};
+
+use database::database_manager::DatabaseManager;
+use resource::constants::server::{AUTHENTICATOR_USERNAME_FIELD, DEFAULT_MAX_COMMIT_RETRY_ATTEMPTS, DEFAULT_USER_NAME};
+use system::initialise_system_database;
+use test_utils::{create_tmp_dir, TempDir};
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{metadata::MetadataMap, Status, Streaming};
+use typedb_protocol::{
+    query::{initial_res, res_part},
+    transaction::{self, req::Req, server::Server, stream_signal::res_part::State},
+    type_db_client::TypeDbClient,
+    type_db_server::TypeDbServer,
+};
+use user::user_manager::UserManager;
+use uuid::Uuid;
+
+use crate::service::{transaction_registry::TransactionRegistry, typedb_service::TypeDBService};
+
+/// A `TypeDBService` served on a local port, for tests that drive transactions through the gRPC protocol
+pub(crate) struct TestServer {
+    pub(crate) address: SocketAddr,
+    pub(crate) database_manager: Arc<DatabaseManager>,
+    pub(crate) user_manager: Arc<UserManager>,
+    pub(crate) transaction_registry: Arc<TransactionRegistry>,
+    _data_directory: TempDir,
+}
+
+impl TestServer {
+    pub(crate) async fn start() -> Self {
+        let data_directory = create_tmp_dir();
+        let database_manager = Arc::new(DatabaseManager::new(&data_directory).unwrap());
+        let user_manager = Arc::new(UserManager::new(initialise_system_database(&database_manager)));
+        let transaction_registry = Arc::new(TransactionRegistry::new());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let service = TypeDBService::new(
+            &address,
+            database_manager.clone(),
+            user_manager.clone(),
+            None,
+            transaction_registry.clone(),
+            None,
+            None,
+            DEFAULT_MAX_COMMIT_RETRY_ATTEMPTS,
+        );
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(TypeDbServer::new(service))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        Self { address, database_manager, user_manager, transaction_registry, _data_directory: data_directory }
+    }
+
+    pub(crate) async fn open_transaction(
+        &self,
+        database: &str,
+        transaction_type: transaction::Type,
+    ) -> TestTransaction {
+        self.open_transaction_with(database, transaction_type, &[]).await
+    }
+
+    pub(crate) async fn open_transaction_with(
+        &self,
+        database: &str,
+        transaction_type: transaction::Type,
+        metadata: &[(&str, &str)],
+    ) -> TestTransaction {
+        let mut client = TypeDbClient::connect(format!("http://{}", self.address)).await.unwrap();
+        let (requests, receiver) = mpsc::channel(16);
+        let mut request = tonic::Request::new(ReceiverStream::new(receiver));
+        request.metadata_mut().insert(AUTHENTICATOR_USERNAME_FIELD, DEFAULT_USER_NAME.parse().unwrap());
+        let responses = client.transaction(request).await.unwrap().into_inner();
+        let mut transaction = TestTransaction { requests, responses, pending: HashMap::new() };
+
+        let open = Req::OpenReq(transaction::open::Req {
+            r#type: transaction_type as i32,
+            database: database.to_owned(),
+            options: Some(Default::default()),
+            network_latency_millis: 0,
+        });
+        let req_id = transaction.send(vec![request_with(open, metadata)]).await.remove(0);
+        match transaction.next_for(req_id).await {
+            Some(Ok(Server::Res(res))) => assert!(matches!(res.res, Some(transaction::res::Res::OpenRes(_)))),
+            other => panic!("transaction failed to open: {other:?}"),
+        }
+        transaction
+    }
+}
+
+/// The answer of one query, or the error it failed with
+#[derive(Debug, Default)]
+pub(crate) struct TestAnswer {
+    pub(crate) columns: Vec<String>,
+    pub(crate) rows: Vec<typedb_protocol::ConceptRow>,
+    pub(crate) documents: Vec<typedb_protocol::ConceptDocument>,
+    pub(crate) error: Option<typedb_protocol::Error>,
+}
+
+impl TestAnswer {
+    pub(crate) fn error_code(&self) -> Option<&str> {
+        self.error.as_ref().map(|error| error.error_code.as_str())
+    }
+
+    /// The value of a column in a row, for rows answering values
+    pub(crate) fn value(&self, row: usize, column: &str) -> Option<&typedb_protocol::value::Value> {
+        let index = self.columns.iter().position(|name| name == column)?;
+        match self.rows.get(row)?.row.get(index)?.entry.as_ref()? {
+            typedb_protocol::row_entry::Entry::Value(value) => value.value.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) struct TestTransaction {
+    requests: mpsc::Sender<transaction::Client>,
+    responses: Streaming<transaction::Server>,
+    // responses read while waiting for those of another request
+    pending: HashMap<Uuid, VecDeque<Result<Server, Status>>>,
+}
+
+impl TestTransaction {
+    /// Send requests in one client message, answering their request IDs
+    pub(crate) async fn send(&self, reqs: Vec<transaction::Req>) -> Vec<Uuid> {
+        let req_ids = reqs.iter().map(|req| Uuid::from_slice(&req.req_id).unwrap()).collect();
+        self.requests.send(transaction::Client { reqs }).await.unwrap();
+        req_ids
+    }
+
+    pub(crate) async fn query(&mut self, query: &str) -> TestAnswer {
+        self.query_with(query, &[]).await
+    }
+
+    pub(crate) async fn query_with(&mut self, query: &str, metadata: &[(&str, &str)]) -> TestAnswer {
+        let req_id = self.send(vec![query_request(query, metadata)]).await.remove(0);
+        self.answer(req_id).await
+    }
+
+    /// Read the answer of a query sent earlier, continuing its stream as needed
+    pub(crate) async fn answer(&mut self, req_id: Uuid) -> TestAnswer {
+        let mut answer = TestAnswer::default();
+        match self.next_for(req_id).await {
+            Some(Ok(Server::Res(transaction::Res {
+                res: Some(transaction::res::Res::QueryInitialRes(res)), ..
+            }))) => match res.res.unwrap() {
+                initial_res::Res::Error(error) => {
+                    answer.error = Some(error);
+                    return answer;
+                }
+                initial_res::Res::Ok(ok) => match ok.ok.unwrap() {
+                    initial_res::ok::Ok::Done(_) => return answer,
+                    initial_res::ok::Ok::ConceptRowStream(stream) => answer.columns = stream.column_variable_names,
+                    initial_res::ok::Ok::ConceptDocumentStream(_) => (),
+                },
+            },
+            other => panic!("expected the initial response of query {req_id}, found {other:?}"),
+        }
+        loop {
+            let Some(Ok(Server::ResPart(part))) = self.next_for(req_id).await else {
+                panic!("query {req_id} stream ended without a done signal");
+            };
+            match part.res_part.unwrap() {
+                transaction::res_part::ResPart::QueryRes(res) => match res.res.unwrap() {
+                    res_part::Res::RowsRes(rows) => answer.rows.extend(rows.rows),
+                    res_part::Res::DocumentsRes(documents) => answer.documents.extend(documents.documents),
+                },
+                transaction::res_part::ResPart::StreamRes(signal) => match signal.state.unwrap() {
+                    State::Continue(_) => {
+                        self.send(vec![request_with_id(req_id, Req::StreamReq(Default::default()), &[])]).await;
+                    }
+                    State::Done(_) => return answer,
+                    State::Error(error) => {
+                        answer.error = Some(error);
+                        return answer;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Commit, answering the trailing metadata the server ends the transaction stream with
+    pub(crate) async fn commit(self, metadata: &[(&str, &str)]) -> Result<MetadataMap, Status> {
+        let Self { requests, mut responses, .. } = self;
+        requests
+            .send(transaction::Client { reqs: vec![request_with(Req::CommitReq(Default::default()), metadata)] })
+            .await
+            .unwrap();
+        while responses.message().await?.is_some() {}
+        Ok(responses.trailers().await?.unwrap_or_default())
+    }
+
+    /// The next response to a request, or `None` if the transaction stream ended
+    pub(crate) async fn next_for(&mut self, req_id: Uuid) -> Option<Result<Server, Status>> {
+        if let Some(response) = self.pending.get_mut(&req_id).and_then(VecDeque::pop_front) {
+            return Some(response);
+        }
+        loop {
+            let response = match self.responses.message().await {
+                Ok(Some(message)) => message.server.unwrap(),
+                Ok(None) => return None,
+                Err(status) => return Some(Err(status)),
+            };
+            let response_req_id = match &response {
+                Server::Res(res) => Uuid::from_slice(&res.req_id).unwrap(),
+                Server::ResPart(part) => Uuid::from_slice(&part.req_id).unwrap(),
+            };
+            if response_req_id == req_id {
+                return Some(Ok(response));
+            }
+            self.pending.entry(response_req_id).or_default().push_back(Ok(response));
+        }
+    }
+}
+
+pub(crate) fn query_request(query: &str, metadata: &[(&str, &str)]) -> transaction::Req {
+    request_with(Req::QueryReq(typedb_protocol::query::Req { query: query.to_owned(), ..Default::default() }), metadata)
+}
+
+pub(crate) fn request_with(req: Req, metadata: &[(&str, &str)]) -> transaction::Req {
+    request_with_id(Uuid::new_v4(), req, metadata)
+}
+
+pub(crate) fn request_with_id(req_id: Uuid, req: Req, metadata: &[(&str, &str)]) -> transaction::Req {
+    transaction::Req {
+        req_id: req_id.as_bytes().to_vec(),
+        metadata: metadata.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+        req: Some(req),
+    }
+}
@@ -0,0 +1,245 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use executor::InterruptType;
+use storage::{sequence_number::SequenceNumber, snapshot::ReadableSnapshot};
+use tokio::{
+    sync::{broadcast, Notify},
+    task::JoinHandle,
+};
+use uuid::Uuid;
+
+use crate::service::transaction_service::Transaction;
+
+/// The open transactions of every transaction service and the queries they are running, so operators can see what
+/// runs on the server and kill transactions or single queries.
+#[derive(Debug, Default)]
+pub(crate) struct TransactionRegistry {
+    transactions: Mutex<HashMap<Uuid, RegisteredTransaction>>,
+}
+
+#[derive(Debug)]
+struct RegisteredTransaction {
+    database: String,
+    user: String,
+    transaction_type: &'static str,
+    opened_at: SystemTime,
+    open_sequence_number: SequenceNumber,
+    // the transaction service closes the transaction when notified
+    kill: Arc<Notify>,
+    queries: HashMap<Uuid, RunningQuery>,
+}
+
+#[derive(Debug)]
+struct RunningQuery {
+    query: String,
+    started: Instant,
+    // write queries cannot be interrupted, since their writes to the transaction cannot be undone
+    interrupt: Option<broadcast::Sender<InterruptType>>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum QueryInterrupt {
+    Sent,
+    QueryNotFound,
+    QueryNotInterruptible,
+}
+
+#[derive(Debug)]
+pub(crate) struct TransactionInfo {
+    pub(crate) id: Uuid,
+    pub(crate) database: String,
+    pub(crate) user: String,
+    pub(crate) transaction_type: &'static str,
+    pub(crate) opened_at: SystemTime,
+    pub(crate) open_sequence_number: SequenceNumber,
+    pub(crate) queries: Vec<QueryInfo>,
+}
+
+#[derive(Debug)]
+pub(crate) struct QueryInfo {
+    pub(crate) id: Uuid,
+    pub(crate) query: String,
+    pub(crate) elapsed: Duration,
+}
+
+impl TransactionRegistry {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lists an open transaction until it is unregistered. The transaction's owner closes it when the returned signal
+    /// is notified.
+    pub(crate) fn register(&self, id: Uuid, user: String, transaction: &Transaction) -> Arc<Notify> {
+        let (database, transaction_type, open_sequence_number) = match transaction {
+            Transaction::Read(transaction) => {
+                (transaction.database.name(), "read", transaction.snapshot.open_sequence_number())
+            }
+            Transaction::Write(transaction) => {
+                (transaction.database.name(), "write", transaction.snapshot.open_sequence_number())
+            }
+            Transaction::Schema(transaction) => {
+                (transaction.database.name(), "schema", transaction.snapshot.open_sequence_number())
+            }
+        };
+        let kill = Arc::new(Notify::new());
+        let transaction = RegisteredTransaction {
+            database: database.to_owned(),
+            user,
+            transaction_type,
+            opened_at: SystemTime::now(),
+            open_sequence_number,
+            kill: kill.clone(),
+            queries: HashMap::new(),
+        };
+        self.transactions.lock().unwrap().insert(id, transaction);
+        kill
+    }
+
+    pub(crate) fn unregister(&self, id: Uuid) {
+        self.transactions.lock().unwrap().remove(&id);
+    }
+
+    /// Tracks a query until the returned guard is dropped. Its receiver interrupts the query alone when it is killed,
+    /// cancelled or runs for longer than its timeout.
+    pub(crate) fn start_query(
+        self: &Arc<Self>,
+        transaction_id: Uuid,
+        query_id: Uuid,
+        query: String,
+        timeout: Option<Duration>,
+    ) -> (RunningQueryGuard, broadcast::Receiver<InterruptType>) {
+        let (interrupt, receiver) = broadcast::channel(1);
+        let timer = timeout.map(|timeout| {
+            let interrupt = interrupt.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(timeout).await;
+                let _ = interrupt.send(InterruptType::QueryTimedOut);
+            })
+        });
+        self.insert_query(
+            transaction_id,
+            query_id,
+            RunningQuery { query, started: Instant::now(), interrupt: Some(interrupt) },
+        );
+        (RunningQueryGuard { registry: self.clone(), transaction_id, query_id, timer }, receiver)
+    }
+
+    /// Tracks a write query until the returned guard is dropped. Write queries are listed like any other, but cannot
+    /// be interrupted alone.
+    pub(crate) fn start_write_query(
+        self: &Arc<Self>,
+        transaction_id: Uuid,
+        query_id: Uuid,
+        query: String,
+    ) -> RunningQueryGuard {
+        self.insert_query(transaction_id, query_id, RunningQuery { query, started: Instant::now(), interrupt: None });
+        RunningQueryGuard { registry: self.clone(), transaction_id, query_id, timer: None }
+    }
+
+    fn insert_query(&self, transaction_id: Uuid, query_id: Uuid, query: RunningQuery) {
+        if let Some(transaction) = self.transactions.lock().unwrap().get_mut(&transaction_id) {
+            transaction.queries.insert(query_id, query);
+        }
+    }
+
+    pub(crate) fn transactions(&self) -> Vec<TransactionInfo> {
+        let transactions = self.transactions.lock().unwrap();
+        let mut infos = transactions
+            .iter()
+            .map(|(id, transaction)| {
+                let mut queries = transaction
+                    .queries
+                    .iter()
+                    .map(|(id, query)| QueryInfo {
+                        id: *id,
+                        query: query.query.clone(),
+                        elapsed: query.started.elapsed(),
+                    })
+                    .collect::<Vec<_>>();
+                queries.sort_by_key(|query| Reverse(query.elapsed));
+                TransactionInfo {
+                    id: *id,
+                    database: transaction.database.clone(),
+                    user: transaction.user.clone(),
+                    transaction_type: transaction.transaction_type,
+                    opened_at: transaction.opened_at,
+                    open_sequence_number: transaction.open_sequence_number,
+                    queries,
+                }
+            })
+            .collect::<Vec<_>>();
+        infos.sort_by_key(|info| info.opened_at);
+        infos
+    }
+
+    pub(crate) fn kill_transaction(&self, id: Uuid) -> bool {
+        match self.transactions.lock().unwrap().get(&id) {
+            Some(transaction) => {
+                transaction.kill.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn interrupt_query(
+        &self,
+        transaction_id: Uuid,
+        query_id: Uuid,
+        interrupt: InterruptType,
+    ) -> QueryInterrupt {
+        let transactions = self.transactions.lock().unwrap();
+        match transactions.get(&transaction_id).and_then(|transaction| transaction.queries.get(&query_id)) {
+            // the query may finish before it receives the signal, which is then ignored
+            Some(RunningQuery { interrupt: Some(sender), .. }) => {
+                let _ = sender.send(interrupt);
+                QueryInterrupt::Sent
+            }
+            Some(RunningQuery { interrupt: None, .. }) => QueryInterrupt::QueryNotInterruptible,
+            None => QueryInterrupt::QueryNotFound,
+        }
+    }
+
+    /// Interrupts every query the transaction is running that can be interrupted
+    pub(crate) fn interrupt_queries(&self, transaction_id: Uuid, interrupt: InterruptType) {
+        if let Some(transaction) = self.transactions.lock().unwrap().get(&transaction_id) {
+            for sender in transaction.queries.values().filter_map(|query| query.interrupt.as_ref()) {
+                let _ = sender.send(interrupt);
+            }
+        }
+    }
+
+    fn finish_query(&self, transaction_id: Uuid, query_id: Uuid) {
+        if let Some(transaction) = self.transactions.lock().unwrap().get_mut(&transaction_id) {
+            transaction.queries.remove(&query_id);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RunningQueryGuard {
+    registry: Arc<TransactionRegistry>,
+    transaction_id: Uuid,
+    query_id: Uuid,
+    timer: Option<JoinHandle<()>>,
+}
+
+impl Drop for RunningQueryGuard {
+    fn drop(&mut self) {
+        if let Some(timer) = &self.timer {
+            timer.abort();
+        }
+        self.registry.finish_query(self.transaction_id, self.query_id)
+    }
+}
@@ -6,15 +6,17 @@
 
 use std::{
     collections::{HashMap, VecDeque},
+    mem,
     ops::{
         ControlFlow,
         ControlFlow::{Break, Continue},
     },
     sync::Arc,
-    time::Instant,
+    thread,
+    time::{Duration, Instant},
 };
 
-use compiler::VariablePosition;
+use compiler::{executable::explain::PipelinePlan, VariablePosition};
 use concept::{thing::thing_manager::ThingManager, type_::type_manager::TypeManager};
 use database::{
     database_manager::DatabaseManager,
@@ -22,22 +24,33 @@ use database::{
         DataCommitError, SchemaCommitError, TransactionError, TransactionRead, TransactionSchema, TransactionWrite,
     },
 };
+use encoding::value::value_type::ValueType;
 use error::typedb_error;
 use executor::{
     batch::Batch,
     pipeline::{
-        pipeline::Pipeline,
-        stage::{ExecutionContext, ReadPipelineStage, StageIterator},
+        pipeline::{ColumnType, Pipeline},
+        stage::{ExecutionContext, ReadPipelineStage, StageAPI, StageIterator},
         PipelineExecutionError,
     },
+    profile::QueryProfile,
     ExecutionInterrupt, InterruptType,
 };
 use function::function_manager::FunctionManager;
 use itertools::Itertools;
 use lending_iterator::LendingIterator;
-use options::TransactionOptions;
+use options::{QueryOptions, SortSpillOptions, TransactionOptions};
 use query::{error::QueryError, query_manager::QueryManager};
-use resource::constants::server::{DEFAULT_PREFETCH_SIZE, DEFAULT_TRANSACTION_TIMEOUT_MILLIS};
+use rand::Rng;
+use resource::{
+    constants::server::{
+        COMMIT_ATTEMPTS_METADATA_FIELD, COMMIT_RETRY_ATTEMPTS_METADATA_FIELD, COMMIT_RETRY_BACKOFF_BASE_MILLIS,
+        COMMIT_RETRY_BACKOFF_MAX_MILLIS, DEFAULT_PREFETCH_SIZE, DEFAULT_TRANSACTION_TIMEOUT_MILLIS,
+        QUERY_CANCEL_METADATA_FIELD, QUERY_EXPLAIN_METADATA_FIELD, QUERY_EXPLAIN_PLAN_COLUMN, QUERY_PROFILE_COLUMN,
+        QUERY_PROFILE_METADATA_FIELD, QUERY_TIMEOUT_METADATA_FIELD,
+    },
+    perf_counters::TRANSACTION_COMMIT_RETRIED_WRITE,
+};
 use storage::{
     durability_client::WALClient,
     snapshot::{ReadableSnapshot, WritableSnapshot},
@@ -46,12 +59,13 @@ use tokio::{
     sync::{
         broadcast,
         mpsc::{channel, Receiver, Sender},
+        Notify,
     },
     task::{spawn_blocking, JoinHandle},
 };
 use tokio_stream::StreamExt;
-use tonic::{Status, Streaming};
-use tracing::{event, Level};
+use tonic::{Code, Status, Streaming};
+use tracing::{event, span, Instrument, Level, Span};
 use typedb_protocol::{
     query::Type::{Read, Write},
     transaction::{stream_signal::Req, Server},
@@ -64,8 +78,9 @@ use typeql::{
 use uuid::Uuid;
 
 use crate::service::{
-    document::encode_document,
+    document::{encode_document, encode_profile_document},
     error::{IntoGRPCStatus, IntoProtocolErrorMessage, ProtocolError},
+    query_batch::{bind_parameters, QueryBatch, QueryBatchError, QueryBatchMode, QueryBatchStep},
     response_builders::transaction::{
         query_initial_res_from_error, query_initial_res_from_query_res_ok, query_initial_res_ok_from_query_res_ok_ok,
         query_res_ok_concept_document_stream, query_res_ok_concept_row_stream, query_res_ok_done,
@@ -74,7 +89,9 @@ use crate::service::{
         transaction_server_res_part_stream_signal_error, transaction_server_res_parts_query_part,
         transaction_server_res_query_res,
     },
-    row::encode_row,
+    row::{empty_row_entry, encode_profile_row, encode_row},
+    slow_query_log::{SlowQueryLog, SlowQueryTimer},
+    transaction_registry::{QueryInterrupt, RunningQueryGuard, TransactionRegistry},
 };
 
 #[derive(Debug)]
@@ -97,6 +114,14 @@ macro_rules! with_readable_transaction {
 #[derive(Debug)]
 pub(crate) struct TransactionService {
     database_manager: Arc<DatabaseManager>,
+    slow_query_log: Option<Arc<SlowQueryLog>>,
+    transaction_registry: Arc<TransactionRegistry>,
+    query_memory_limit_bytes: Option<u64>,
+    query_sort_spill: Option<SortSpillOptions>,
+    max_commit_retry_attempts: u32,
+    transaction_id: Uuid,
+    user: String,
+    span: Span,
 
     request_stream: Streaming<typedb_protocol::transaction::Client>,
     response_sender: Sender<Result<typedb_protocol::transaction::Server, Status>>,
@@ -104,28 +129,24 @@ pub(crate) struct TransactionService {
     query_interrupt_receiver: ExecutionInterrupt,
 
     transaction_timeout_millis: Option<u64>,
+    // when the transaction times out, counted from when it was opened
+    transaction_deadline: Option<Instant>,
     schema_lock_acquire_timeout_millis: Option<u64>,
     prefetch_size: Option<u64>,
     network_latency_millis: Option<u64>,
 
     is_open: bool,
     transaction: Option<Transaction>,
-    request_queue: VecDeque<(Uuid, typeql::query::Pipeline)>,
+    transaction_type: Option<typedb_protocol::transaction::Type>,
+    // notified when the transaction is killed through the registry
+    kill: Option<Arc<Notify>>,
+    // the batch formed by the query requests of the current client message so far
+    query_batch: Option<QueryBatch>,
+    request_queue: VecDeque<(Uuid, typeql::query::Pipeline, QueryOptions, Option<QueryBatchStep>)>,
+    // write pipelines executed so far, replayed if the commit is retried
+    write_pipelines: Vec<(typeql::query::Pipeline, QueryOptions)>,
     responders: HashMap<Uuid, (JoinHandle<()>, QueryStreamTransmitter)>,
-    running_write_query:
-        Option<(Uuid, JoinHandle<(Transaction, Result<(StreamQueryOutputDescriptor, Batch), QueryError>)>)>,
-}
-
-macro_rules! unwrap_or_execute_and_return {
-    ($match_: expr, |$err:pat_param| $err_mapper: block) => {{
-        match $match_ {
-            Ok(inner) => inner,
-            Err($err) => {
-                $err_mapper
-                return;
-            }
-        }
-    }};
+    running_write_query: Option<(Uuid, JoinHandle<(Transaction, WriteQueryResult)>)>,
 }
 
 macro_rules! send_ok_message_else_return_break {
@@ -152,7 +173,8 @@ impl ImmediateQueryResponse {
     }
 }
 
-type StreamQueryOutputDescriptor = Vec<(String, VariablePosition)>;
+pub(crate) type StreamQueryOutputDescriptor = Vec<(String, VariablePosition, ColumnType)>;
+pub(crate) type WriteQueryResult = Result<(StreamQueryOutputDescriptor, Batch, Arc<QueryProfile>), QueryError>;
 
 enum StreamQueryResponse {
     // initial open response
@@ -167,7 +189,15 @@ enum StreamQueryResponse {
 
 impl StreamQueryResponse {
     fn init_ok_rows(columns: &StreamQueryOutputDescriptor, query_type: typedb_protocol::query::Type) -> Self {
-        let columns = columns.iter().map(|(name, _)| name.to_string()).collect();
+        let columns = columns.iter().map(|(name, _, _)| name.to_string()).collect();
+        let message = query_res_ok_concept_row_stream(columns, query_type);
+        Self::InitOk(query_initial_res_ok_from_query_res_ok_ok(message))
+    }
+
+    // A profiled query's row stream has a trailing `profile` column, which is only set in the final profile row
+    fn init_ok_profiled_rows(columns: &StreamQueryOutputDescriptor, query_type: typedb_protocol::query::Type) -> Self {
+        let columns =
+            columns.iter().map(|(name, _, _)| name.to_string()).chain([QUERY_PROFILE_COLUMN.to_owned()]).collect();
         let message = query_res_ok_concept_row_stream(columns, query_type);
         Self::InitOk(query_initial_res_ok_from_query_res_ok_ok(message))
     }
@@ -219,11 +249,27 @@ impl TransactionService {
         request_stream: Streaming<typedb_protocol::transaction::Client>,
         response_sender: Sender<Result<typedb_protocol::transaction::Server, Status>>,
         database_manager: Arc<DatabaseManager>,
+        slow_query_log: Option<Arc<SlowQueryLog>>,
+        transaction_registry: Arc<TransactionRegistry>,
+        query_memory_limit_bytes: Option<u64>,
+        query_sort_spill: Option<SortSpillOptions>,
+        max_commit_retry_attempts: u32,
+        transaction_id: Uuid,
+        user: String,
+        span: Span,
     ) -> Self {
         let (query_interrupt_sender, query_interrupt_receiver) = broadcast::channel(1);
 
         Self {
             database_manager,
+            slow_query_log,
+            transaction_registry,
+            query_memory_limit_bytes,
+            query_sort_spill,
+            max_commit_retry_attempts,
+            transaction_id,
+            user,
+            span,
 
             request_stream,
             response_sender,
@@ -231,13 +277,18 @@ impl TransactionService {
             query_interrupt_receiver: ExecutionInterrupt::new(query_interrupt_receiver),
 
             transaction_timeout_millis: None,
+            transaction_deadline: None,
             schema_lock_acquire_timeout_millis: None,
             prefetch_size: None,
             network_latency_millis: None,
 
             is_open: false,
             transaction: None,
+            transaction_type: None,
+            kill: None,
+            query_batch: None,
             request_queue: VecDeque::with_capacity(20),
+            write_pipelines: Vec::new(),
             responders: HashMap::new(),
             running_write_query: None,
         }
@@ -245,9 +296,19 @@ impl TransactionService {
 
     pub(crate) async fn listen(&mut self) {
         loop {
+            let kill = self.kill.clone();
+            let killed = async {
+                match kill {
+                    Some(kill) => kill.notified().await,
+                    None => std::future::pending().await,
+                }
+            };
             let result = if self.running_write_query.is_some() {
                 let (req_id, write_query_worker) = self.running_write_query.as_mut().unwrap();
                 tokio::select! { biased;
+                    _ = killed => {
+                        self.kill_transaction().await
+                    }
                     write_query_result = write_query_worker => {
                         let req_id = *req_id;
                         self.running_write_query = None;
@@ -265,8 +326,14 @@ impl TransactionService {
                     }
                 }
             } else {
-                let next = self.request_stream.next().await;
-                self.handle_next(next).await
+                tokio::select! { biased;
+                    _ = killed => {
+                        self.kill_transaction().await
+                    }
+                    next = self.request_stream.next() => {
+                        self.handle_next(next).await
+                    }
+                }
             };
 
             match result {
@@ -301,6 +368,8 @@ impl TransactionService {
                 Ok(Break(()))
             }
             Some(Ok(message)) => {
+                // batches never span client messages
+                self.query_batch = None;
                 for request in message.reqs {
                     let request_id = Uuid::from_slice(&request.req_id).unwrap();
                     let metadata = request.metadata;
@@ -312,13 +381,14 @@ impl TransactionService {
                             }
                             .into_status());
                         }
-                        Some(req) => match self.handle_request(request_id, req).await {
+                        Some(req) => match self.handle_request(request_id, req, &metadata).await {
                             Err(err) => return Err(err),
                             Ok(Break(())) => return Ok(Break(())),
                             Ok(Continue(())) => {}
                         },
                     }
                 }
+                self.query_batch = None;
                 Ok(Continue(()))
             }
         }
@@ -328,10 +398,15 @@ impl TransactionService {
         &mut self,
         request_id: Uuid,
         req: typedb_protocol::transaction::req::Req,
+        metadata: &HashMap<String, String>,
     ) -> Result<ControlFlow<(), ()>, Status> {
+        if !matches!(req, typedb_protocol::transaction::req::Req::QueryReq(_)) {
+            // any other request ends a batch
+            self.query_batch = None;
+        }
         match (self.is_open, req) {
             (false, typedb_protocol::transaction::req::Req::OpenReq(open_req)) => {
-                let result = self.handle_open(request_id, open_req).await;
+                let result = self.handle_open(request_id, open_req, metadata).await;
                 match &result {
                     Ok(ControlFlow::Continue(_)) => event!(Level::TRACE, "Transaction opened successfully."),
                     Ok(ControlFlow::Break(_)) => event!(Level::TRACE, "Transaction open aborted."),
@@ -343,7 +418,12 @@ impl TransactionService {
                 Err(ProtocolError::TransactionAlreadyOpen {}.into_status())
             }
             (true, typedb_protocol::transaction::req::Req::QueryReq(query_req)) => {
-                self.handle_query(request_id, query_req).await
+                self.handle_query(request_id, query_req, metadata).await
+            }
+            (true, typedb_protocol::transaction::req::Req::StreamReq(_))
+                if metadata.contains_key(QUERY_CANCEL_METADATA_FIELD) =>
+            {
+                Ok(self.handle_query_cancel(request_id).await)
             }
             (true, typedb_protocol::transaction::req::Req::StreamReq(stream_req)) => {
                 match self.handle_stream_continue(request_id, stream_req).await {
@@ -355,7 +435,12 @@ impl TransactionService {
             }
             (true, typedb_protocol::transaction::req::Req::CommitReq(commit_req)) => {
                 // Eagerly executed in main loop
-                self.handle_commit(commit_req).await?;
+                let attempts = self.handle_commit(commit_req, metadata).await?;
+                // The protocol has no commit response, so the attempts are answered in the status ending the stream
+                let status = with_commit_attempts(Status::new(Code::Ok, ""), attempts);
+                if let Err(err) = self.response_sender.send(Err(status)).await {
+                    event!(Level::TRACE, "Submit message failed: {:?}", err);
+                }
                 Ok(Break(()))
             }
             (true, typedb_protocol::transaction::req::Req::RollbackReq(rollback_req)) => {
@@ -396,6 +481,7 @@ impl TransactionService {
         &mut self,
         req_id: Uuid,
         open_req: typedb_protocol::transaction::open::Req,
+        metadata: &HashMap<String, String>,
     ) -> Result<ControlFlow<(), ()>, Status> {
         let receive_time = Instant::now();
         self.network_latency_millis = Some(open_req.network_latency_millis);
@@ -415,6 +501,14 @@ impl TransactionService {
                 options.transaction_timeout_millis.or(Some(DEFAULT_TRANSACTION_TIMEOUT_MILLIS));
         }
 
+        self.transaction_deadline =
+            self.transaction_timeout_millis.map(|millis| receive_time + Duration::from_millis(millis));
+
+        // The protocol's options have no field for commit retries, so they are read from the request metadata
+        if let Some(attempts) = self.commit_retry_attempts(metadata)? {
+            transaction_options.commit_retry_attempts = attempts;
+        }
+
         let transaction_type = typedb_protocol::transaction::Type::try_from(open_req.r#type)
             .map_err(|_| ProtocolError::UnrecognisedTransactionType { enum_variant: open_req.r#type }.into_status())?;
 
@@ -455,7 +549,9 @@ impl TransactionService {
                 Transaction::Schema(transaction)
             }
         };
+        self.kill = Some(self.transaction_registry.register(self.transaction_id, self.user.clone(), &transaction));
         self.transaction = Some(transaction);
+        self.transaction_type = Some(transaction_type);
         self.is_open = true;
 
         let processing_time_millis = Instant::now().duration_since(receive_time).as_millis() as u64;
@@ -467,7 +563,13 @@ impl TransactionService {
         }
     }
 
-    async fn handle_commit(&mut self, _commit_req: typedb_protocol::transaction::commit::Req) -> Result<(), Status> {
+    // Answers the number of attempts the commit took
+    async fn handle_commit(
+        &mut self,
+        _commit_req: typedb_protocol::transaction::commit::Req,
+        metadata: &HashMap<String, String>,
+    ) -> Result<u32, Status> {
+        let retry_attempts = self.commit_retry_attempts(metadata)?;
         // finish any running write query, interrupt running queries, clear all running/queued reads, finish all writes
         //   note: if any write query errors, the whole transaction errors
         // finish any active write query
@@ -488,19 +590,197 @@ impl TransactionService {
             Transaction::Read(_) => {
                 Err(TransactionServiceError::CannotCommitReadTransaction {}.into_error_message().into_status())
             }
-            Transaction::Write(transaction) => spawn_blocking(move || {
-                transaction.commit().map_err(|err| {
-                    TransactionServiceError::DataCommitFailed { typedb_source: err }.into_error_message().into_status()
-                })
-            })
-            .await
-            .unwrap(),
-            Transaction::Schema(transaction) => transaction.commit().map_err(|typedb_source| {
+            Transaction::Write(mut transaction) => {
+                if let Some(attempts) = retry_attempts {
+                    transaction.transaction_options.commit_retry_attempts = attempts;
+                }
+                let write_pipelines = mem::take(&mut self.write_pipelines);
+                let interrupt = self.query_interrupt_receiver.clone();
+                let commit =
+                    spawn_blocking(move || Self::commit_write_with_retry(transaction, write_pipelines, interrupt));
+                self.await_write_commit(commit).await
+            }
+            Transaction::Schema(transaction) => transaction.commit().map(|()| 1).map_err(|typedb_source| {
                 TransactionServiceError::SchemaCommitFailed { typedb_source }.into_error_message().into_status()
             }),
         }
     }
 
+    fn commit_retry_attempts(&self, metadata: &HashMap<String, String>) -> Result<Option<u32>, Status> {
+        let Some(value) = metadata.get(COMMIT_RETRY_ATTEMPTS_METADATA_FIELD) else {
+            return Ok(None);
+        };
+        let attempts = value.parse().map_err(|_| {
+            TransactionServiceError::InvalidCommitRetryAttempts { value: value.clone() }
+                .into_error_message()
+                .into_status()
+        })?;
+        if attempts > self.max_commit_retry_attempts {
+            return Err(TransactionServiceError::CommitRetryAttemptsAboveMaximum {
+                attempts,
+                maximum: self.max_commit_retry_attempts,
+            }
+            .into_error_message()
+            .into_status());
+        }
+        Ok(Some(attempts))
+    }
+
+    // While the commit runs, a kill, the transaction timing out, or the client closing the transaction or sending
+    // any other request interrupts it, so it stops retrying
+    async fn await_write_commit(&mut self, mut commit: JoinHandle<Result<u32, Status>>) -> Result<u32, Status> {
+        let kill = self.kill.clone();
+        let killed = async {
+            match kill {
+                Some(kill) => kill.notified().await,
+                None => std::future::pending().await,
+            }
+        };
+        let deadline = self.transaction_deadline;
+        let timed_out = async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                None => std::future::pending().await,
+            }
+        };
+        let interrupt = tokio::select! { biased;
+            result = &mut commit => return result.unwrap(),
+            _ = killed => InterruptType::KillRequest,
+            _ = timed_out => InterruptType::TransactionTimedOut,
+            _ = self.request_stream.next() => InterruptType::TransactionClosed,
+        };
+        event!(Level::DEBUG, %interrupt, "Interrupting write transaction commit.");
+        // sent once, so the commit's receiver never lags
+        let _ = self.query_interrupt_sender.send(interrupt);
+        commit.await.unwrap()
+    }
+
+    // Commit, and while the commit conflicts with a concurrent commit, replay the write pipelines on a fresh
+    // transaction and commit that instead, for as many attempts as the transaction options allow.
+    // Answers the number of attempts, which failures also carry in their status metadata.
+    fn commit_write_with_retry(
+        transaction: TransactionWrite<WALClient>,
+        write_pipelines: Vec<(typeql::query::Pipeline, QueryOptions)>,
+        mut interrupt: ExecutionInterrupt,
+    ) -> Result<u32, Status> {
+        let database = transaction.database.clone();
+        let transaction_options = transaction.transaction_options.clone();
+        let max_attempts = transaction_options.commit_retry_attempts.saturating_add(1);
+
+        let mut result = transaction.commit();
+        let mut attempt = 1;
+        while attempt < max_attempts && matches!(result, Err(DataCommitError::IsolationConflict { .. })) {
+            let error = result.as_ref().unwrap_err();
+            event!(Level::DEBUG, attempt, ?error, "Write transaction commit conflicted, retrying.");
+            thread::sleep(Self::commit_retry_backoff(attempt));
+            // the replay is given its interrupt first, so an interrupt is seen either here or by the replay
+            let replay_interrupt = interrupt.clone();
+            if let Some(interrupt) = interrupt.check() {
+                let status = TransactionServiceError::CommitRetryInterrupted { attempts: attempt, interrupt }
+                    .into_error_message()
+                    .into_status();
+                return Err(with_commit_attempts(status, attempt));
+            }
+            attempt += 1;
+            TRANSACTION_COMMIT_RETRIED_WRITE.increment();
+
+            let transaction =
+                TransactionWrite::open(database.clone(), transaction_options.clone()).map_err(|typedb_source| {
+                    let status =
+                        TransactionServiceError::TransactionFailed { typedb_source }.into_error_message().into_status();
+                    with_commit_attempts(status, attempt)
+                })?;
+            let replayed = Self::replay_write_pipelines(transaction, &write_pipelines, replay_interrupt);
+            let transaction = replayed.map_err(|typedb_source| {
+                let status = TransactionServiceError::CommitRetryReplayFailed { attempt, typedb_source }
+                    .into_error_message()
+                    .into_status();
+                with_commit_attempts(status, attempt)
+            })?;
+            result = transaction.commit();
+        }
+
+        match result {
+            Ok(()) => {
+                if attempt > 1 {
+                    event!(Level::DEBUG, attempts = attempt, "Write transaction committed after retrying.");
+                }
+                Ok(attempt)
+            }
+            Err(typedb_source) if attempt > 1 => {
+                let status = TransactionServiceError::DataCommitFailedAfterRetries { attempts: attempt, typedb_source }
+                    .into_error_message()
+                    .into_status();
+                Err(with_commit_attempts(status, attempt))
+            }
+            Err(typedb_source) => {
+                let status =
+                    TransactionServiceError::DataCommitFailed { typedb_source }.into_error_message().into_status();
+                Err(with_commit_attempts(status, attempt))
+            }
+        }
+    }
+
+    // Full jitter: wait a random time up to an exponentially growing, capped ceiling
+    fn commit_retry_backoff(attempt: u32) -> Duration {
+        let ceiling = (COMMIT_RETRY_BACKOFF_BASE_MILLIS << attempt.min(16)).min(COMMIT_RETRY_BACKOFF_MAX_MILLIS);
+        Duration::from_millis(rand::thread_rng().gen_range(0..=ceiling))
+    }
+
+    fn replay_write_pipelines(
+        transaction: TransactionWrite<WALClient>,
+        write_pipelines: &[(typeql::query::Pipeline, QueryOptions)],
+        interrupt: ExecutionInterrupt,
+    ) -> Result<TransactionWrite<WALClient>, QueryError> {
+        let TransactionWrite {
+            snapshot,
+            type_manager,
+            thing_manager,
+            function_manager,
+            query_manager,
+            database,
+            transaction_options,
+        } = transaction;
+        let mut snapshot = Arc::into_inner(snapshot).expect("Cannot unwrap Arc<Snapshot>, still in use.");
+        let mut error = None;
+        for (pipeline, query_options) in write_pipelines {
+            let (returned_snapshot, result) = Self::execute_write_query_in(
+                snapshot,
+                &type_manager,
+                thing_manager.clone(),
+                &function_manager,
+                &query_manager,
+                pipeline,
+                query_options.clone(),
+                false,
+                None,
+                interrupt.clone(),
+            );
+            snapshot = returned_snapshot;
+            if let Err(err) = result {
+                error = Some(err);
+                break;
+            }
+        }
+
+        let transaction = TransactionWrite::from(
+            Arc::new(snapshot),
+            type_manager,
+            thing_manager,
+            function_manager,
+            query_manager,
+            database,
+            transaction_options,
+        );
+        match error {
+            None => Ok(transaction),
+            Some(err) => {
+                transaction.close();
+                Err(err)
+            }
+        }
+    }
+
     async fn handle_rollback(
         &mut self,
         _rollback_req: typedb_protocol::transaction::rollback::Req,
@@ -525,6 +805,7 @@ impl TransactionService {
             Transaction::Write(mut transaction) => transaction.rollback(),
             Transaction::Schema(mut transaction) => transaction.rollback(),
         };
+        self.write_pipelines.clear();
         Ok(Continue(()))
     }
 
@@ -533,10 +814,14 @@ impl TransactionService {
     }
 
     async fn do_close(&mut self) {
-        self.interrupt_and_close_responders(InterruptType::TransactionClosed).await;
-        let _ = self.cancel_queued_read_queries(InterruptType::TransactionClosed).await;
-        let _ = self.finish_running_write_query_no_transmit(InterruptType::TransactionClosed).await;
-        let _ = self.cancel_queued_write_queries(InterruptType::TransactionClosed).await;
+        self.close_with(InterruptType::TransactionClosed).await
+    }
+
+    async fn close_with(&mut self, interrupt: InterruptType) {
+        self.interrupt_and_close_responders(interrupt).await;
+        let _ = self.cancel_queued_read_queries(interrupt).await;
+        let _ = self.finish_running_write_query_no_transmit(interrupt).await;
+        let _ = self.cancel_queued_write_queries(interrupt).await;
 
         match self.transaction.take() {
             None => (),
@@ -544,15 +829,27 @@ impl TransactionService {
             Some(Transaction::Write(transaction)) => transaction.close(),
             Some(Transaction::Schema(transaction)) => transaction.close(),
         };
+        self.transaction_registry.unregister(self.transaction_id);
+    }
+
+    // Interrupt the queries and close the transaction, failing the stream so the client learns why it ended
+    async fn kill_transaction(&mut self) -> Result<ControlFlow<(), ()>, Status> {
+        event!(Level::INFO, "Killing transaction on request.");
+        self.close_with(InterruptType::KillRequest).await;
+        Err(TransactionServiceError::TransactionKilled {}.into_error_message().into_status())
     }
 
     async fn interrupt_and_close_responders(&mut self, interrupt: InterruptType) {
         self.query_interrupt_sender.send(interrupt).unwrap();
-        for (_, (worker, mut transmitter)) in self.responders.drain() {
-            // WARNING: we cannot await the worker to finish first - it's a blocking task that could catch the interrupt
-            // or be waiting for the queue to unblock as the transmitter task is done. So, we should first drain some answers from the transmitter
-            // then wait for the worker to catch the interrupt signal
+        let mut responders: Vec<_> = self.responders.drain().map(|(_, responder)| responder).collect();
+        // WARNING: we cannot await the worker to finish first - it's a blocking task that could catch the interrupt
+        // or be waiting for the queue to unblock as the transmitter task is done. So, we should first drain some answers from the transmitter
+        // then wait for the worker to catch the interrupt signal. Every transmitter is drained before any worker is
+        // awaited, since a worker of a stop-on-error batch waits for the worker of the query before it.
+        for (_, transmitter) in &mut responders {
             transmitter.check_finished_else_queue_continue().await;
+        }
+        for (worker, mut transmitter) in responders {
             if let Err(err) = worker.await {
                 event!(Level::DEBUG, "Awaiting query worker returned error: {:?}", err);
             }
@@ -562,9 +859,9 @@ impl TransactionService {
 
     async fn cancel_queued_read_queries(&mut self, interrupt: InterruptType) -> ControlFlow<(), ()> {
         let mut write_queries = VecDeque::with_capacity(self.request_queue.len());
-        for (req_id, pipeline) in self.request_queue.drain(0..self.request_queue.len()) {
-            if Self::is_write_pipeline(&pipeline) {
-                write_queries.push_back((req_id, pipeline));
+        for (req_id, pipeline, query_options, batch_step) in self.request_queue.drain(0..self.request_queue.len()) {
+            if Self::is_write_query(&pipeline, &query_options) {
+                write_queries.push_back((req_id, pipeline, query_options, batch_step));
             }
             Self::respond_query_response(
                 &self.response_sender,
@@ -607,14 +904,10 @@ impl TransactionService {
         }
     }
 
-    fn transmit_write_results(
-        &mut self,
-        req_id: Uuid,
-        result: Result<(StreamQueryOutputDescriptor, Batch), QueryError>,
-    ) -> Result<(), Status> {
+    fn transmit_write_results(&mut self, req_id: Uuid, result: WriteQueryResult) -> Result<(), Status> {
         match result {
-            Ok((output_descriptor, batch)) => {
-                self.activate_write_transmitter(req_id, output_descriptor, batch);
+            Ok((output_descriptor, batch, profile)) => {
+                self.activate_write_transmitter(req_id, output_descriptor, batch, profile);
                 Ok(())
             }
             Err(err) => {
@@ -626,8 +919,8 @@ impl TransactionService {
 
     async fn cancel_queued_write_queries(&mut self, interrupt: InterruptType) -> ControlFlow<(), ()> {
         let mut read_queries = VecDeque::with_capacity(self.request_queue.len());
-        for (req_id, pipeline) in self.request_queue.drain(0..self.request_queue.len()) {
-            if Self::is_write_pipeline(&pipeline) {
+        for (req_id, pipeline, query_options, batch_step) in self.request_queue.drain(0..self.request_queue.len()) {
+            if Self::is_write_query(&pipeline, &query_options) {
                 Self::respond_query_response(
                     &self.response_sender,
                     req_id,
@@ -637,7 +930,7 @@ impl TransactionService {
                 )
                 .await?;
             } else {
-                read_queries.push_back((req_id, pipeline));
+                read_queries.push_back((req_id, pipeline, query_options, batch_step));
             }
         }
         self.request_queue = read_queries;
@@ -647,12 +940,12 @@ impl TransactionService {
     async fn finish_queued_write_queries(&mut self, interrupt: InterruptType) -> Result<(), Status> {
         self.finish_running_write_query_no_transmit(interrupt).await?;
         let requests: Vec<_> = self.request_queue.drain(0..self.request_queue.len()).collect();
-        for (req_id, pipeline) in requests.into_iter() {
-            if Self::is_write_pipeline(&pipeline) {
-                self.run_write_query(req_id, pipeline).await;
+        for (req_id, pipeline, query_options, batch_step) in requests.into_iter() {
+            if Self::is_write_query(&pipeline, &query_options) {
+                self.run_write_query(req_id, pipeline, query_options, batch_step).await;
                 self.finish_running_write_query_no_transmit(interrupt).await?;
             } else {
-                self.request_queue.push_back((req_id, pipeline));
+                self.request_queue.push_back((req_id, pipeline, query_options, batch_step));
             }
         }
         Ok(())
@@ -662,12 +955,12 @@ impl TransactionService {
         debug_assert!(self.running_write_query.is_none());
 
         // unblock requests until the first write request, which we begin executing if it exists
-        while let Some((req_id, query_pipeline)) = self.request_queue.pop_front() {
-            if Self::is_write_pipeline(&query_pipeline) {
-                self.run_write_query(req_id, query_pipeline).await;
+        while let Some((req_id, query_pipeline, query_options, batch_step)) = self.request_queue.pop_front() {
+            if Self::is_write_query(&query_pipeline, &query_options) {
+                self.run_write_query(req_id, query_pipeline, query_options, batch_step).await;
                 return;
             } else {
-                self.run_and_activate_read_transmitter(req_id, query_pipeline);
+                self.run_and_activate_read_transmitter(req_id, query_pipeline, query_options, batch_step);
             }
         }
     }
@@ -676,45 +969,84 @@ impl TransactionService {
         &mut self,
         req_id: Uuid,
         query_req: typedb_protocol::query::Req,
+        metadata: &HashMap<String, String>,
     ) -> Result<ControlFlow<(), ()>, Status> {
-        let _query_options = &query_req.options; // TODO: pass query options
-        let parsed = match parse_query(&query_req.query) {
+        let batch_mode = match QueryBatchMode::from_metadata(metadata) {
+            Ok(batch_mode) => batch_mode,
+            Err(err) => return Ok(self.respond_query_error(req_id, err).await),
+        };
+        let continues_batch =
+            matches!((&self.query_batch, batch_mode), (Some(batch), Some(mode)) if batch.mode == mode);
+        if !continues_batch {
+            self.query_batch = batch_mode.map(QueryBatch::new);
+        }
+        if self.query_batch.as_ref().is_some_and(QueryBatch::is_stopped) {
+            return Ok(self.respond_query_error(req_id, QueryBatchError::QuerySkipped {}).await);
+        }
+        let query_options = match self.query_options_from_request(&query_req, metadata) {
+            Ok(query_options) => query_options,
+            Err(err) => return Ok(self.respond_query_error(req_id, err).await),
+        };
+
+        let query = match bind_parameters(&query_req.query, metadata) {
+            Ok(query) => query,
+            Err(err) => return Ok(self.respond_query_error(req_id, err).await),
+        };
+        let parsed = match parse_query(&query) {
             Ok(parsed) => parsed,
             Err(err) => {
-                let response = ImmediateQueryResponse::non_fatal_err(TransactionServiceError::QueryParseFailed {
-                    typedb_source: err,
-                });
-                return Ok(Self::respond_query_response(&self.response_sender, req_id, response).await);
+                let error = TransactionServiceError::QueryParseFailed { typedb_source: err };
+                return Ok(self.respond_query_error(req_id, error).await);
             }
         };
+        if self.query_batch.is_some() && !query_options.explain {
+            // a batch query must fail before the queries after it are queued, so an invalid one can stop its batch
+            if let Some(error) = self.query_transaction_type_error(&parsed) {
+                return Ok(self.respond_query_error(req_id, error).await);
+            }
+        }
         match parsed {
+            Query::Schema(_) if query_options.explain => {
+                Ok(self.respond_query_error(req_id, TransactionServiceError::SchemaQueryCannotBeExplained {}).await)
+            }
             Query::Schema(schema_query) => {
                 self.interrupt_and_close_responders(InterruptType::SchemaQueryExecution).await;
                 self.cancel_queued_read_queries(InterruptType::SchemaQueryExecution).await;
                 self.finish_queued_write_queries(InterruptType::SchemaQueryExecution).await?;
+                // the read queries before it may have failed while executing
+                if self.query_batch.as_ref().is_some_and(QueryBatch::is_stopped) {
+                    return Ok(self.respond_query_error(req_id, QueryBatchError::QuerySkipped {}).await);
+                }
 
                 // schema queries are handled immediately so there is a query response or a fatal Status
-                let response = self.handle_query_schema(schema_query).await?;
+                let query_span = self.query_span(req_id);
+                let response = self.handle_query_schema(schema_query).instrument(query_span).await?;
                 Ok(Self::respond_query_response(&self.response_sender, req_id, response).await)
             }
+            Query::Pipeline(pipeline)
+                if query_options.timeout_millis.is_some() && Self::is_write_query(&pipeline, &query_options) =>
+            {
+                Ok(self.respond_query_error(req_id, TransactionServiceError::WriteQueryCannotTimeOut {}).await)
+            }
             Query::Pipeline(pipeline) => {
+                let batch_step = self.query_batch.as_mut().map(QueryBatch::step);
                 #[allow(clippy::collapsible_else_if)]
-                if Self::is_write_pipeline(&pipeline) {
+                if Self::is_write_query(&pipeline, &query_options) {
                     if !self.request_queue.is_empty() || self.running_write_query.is_some() {
-                        self.request_queue.push_back((req_id, pipeline));
+                        self.request_queue.push_back((req_id, pipeline, query_options, batch_step));
                         // queued queries are not handled yet so there will be no query response yet
                         Ok(Continue(()))
                     } else {
-                        self.run_write_query(req_id, pipeline).await;
+                        self.run_write_query(req_id, pipeline, query_options, batch_step).await;
                         Ok(Continue(()))
                     }
                 } else {
                     if !self.request_queue.is_empty() || self.running_write_query.is_some() {
-                        self.request_queue.push_back((req_id, pipeline));
+                        self.request_queue.push_back((req_id, pipeline, query_options, batch_step));
                         // queued queries are not handled yet so there will be no query response yet
                         Ok(Continue(()))
                     } else {
-                        self.run_and_activate_read_transmitter(req_id, pipeline);
+                        self.run_and_activate_read_transmitter(req_id, pipeline, query_options, batch_step);
                         // running read queries have no response on the main loop and will respond asynchronously
                         Ok(Continue(()))
                     }
@@ -723,6 +1055,140 @@ impl TransactionService {
         }
     }
 
+    // Respond with a query error, failing the current batch. Read queries that fail while executing fail their batch
+    // through their batch step instead, and write query errors fail the transaction anyway.
+    async fn respond_query_error(&mut self, req_id: Uuid, error: impl IntoProtocolErrorMessage) -> ControlFlow<(), ()> {
+        if let Some(query_batch) = &self.query_batch {
+            query_batch.fail();
+        }
+        Self::respond_query_response(&self.response_sender, req_id, ImmediateQueryResponse::non_fatal_err(error)).await
+    }
+
+    fn query_transaction_type_error(&self, query: &Query) -> Option<TransactionServiceError> {
+        match (self.transaction_type?, query) {
+            (typedb_protocol::transaction::Type::Schema, _) => None,
+            (_, Query::Schema(_)) => Some(TransactionServiceError::SchemaQueryRequiresSchemaTransaction {}),
+            (typedb_protocol::transaction::Type::Read, Query::Pipeline(pipeline))
+                if Self::is_write_pipeline(pipeline) =>
+            {
+                Some(TransactionServiceError::WriteQueryRequiresSchemaOrWriteTransaction {})
+            }
+            (_, Query::Pipeline(_)) => None,
+        }
+    }
+
+    fn query_options_from_request(
+        &self,
+        _query_req: &typedb_protocol::query::Req,
+        metadata: &HashMap<String, String>,
+    ) -> Result<QueryOptions, TransactionServiceError> {
+        let timeout_millis = metadata
+            .get(QUERY_TIMEOUT_METADATA_FIELD)
+            .map(|timeout| {
+                timeout.parse().map_err(|_| TransactionServiceError::InvalidQueryTimeout { value: timeout.clone() })
+            })
+            .transpose()?;
+        // The protocol has no field for query profiles, so a profiled query answers its profile after its answers
+        let profile = Self::metadata_flag(metadata, QUERY_PROFILE_METADATA_FIELD)?.unwrap_or(false);
+        let explain = Self::metadata_flag(metadata, QUERY_EXPLAIN_METADATA_FIELD)?.unwrap_or(false);
+        Ok(QueryOptions {
+            timeout_millis,
+            memory_limit_bytes: self.query_memory_limit_bytes,
+            sort_spill: self.query_sort_spill.clone(),
+            profile,
+            explain,
+            ..QueryOptions::default()
+        })
+    }
+
+    fn metadata_flag(
+        metadata: &HashMap<String, String>,
+        field: &'static str,
+    ) -> Result<Option<bool>, TransactionServiceError> {
+        metadata
+            .get(field)
+            .map(|value| match value.as_str() {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(TransactionServiceError::InvalidMetadataFlag { field, value: value.clone() }),
+            })
+            .transpose()
+    }
+
+    // Tracks the query in the registry until the returned guard is dropped by its worker. The query's timeout counts
+    // from here, so the time it waited in the queue is not included.
+    fn start_query(
+        &self,
+        req_id: Uuid,
+        pipeline: &typeql::query::Pipeline,
+        query_options: &QueryOptions,
+    ) -> (RunningQueryGuard, broadcast::Receiver<InterruptType>) {
+        self.transaction_registry.start_query(
+            self.transaction_id,
+            req_id,
+            pipeline.to_string(),
+            query_options.timeout_millis.map(Duration::from_millis),
+        )
+    }
+
+    // Cancels a queued or executing query, leaving the transaction open. Cancelled read queries end their answer stream
+    // with the cancellation error. Write queries can only be cancelled while queued: one already executing would leave
+    // its writes partially applied, so it runs to the end and answers as usual. Queries that already finished
+    // executing are not affected.
+    async fn handle_query_cancel(&mut self, req_id: Uuid) -> ControlFlow<(), ()> {
+        match self.request_queue.iter().position(|(queued_req_id, _, _, _)| *queued_req_id == req_id) {
+            Some(position) => {
+                self.request_queue.remove(position);
+                let response = ImmediateQueryResponse::non_fatal_err(TransactionServiceError::QueryCancelled {});
+                Self::respond_query_response(&self.response_sender, req_id, response).await
+            }
+            None => {
+                let interrupt = InterruptType::QueryCancelled;
+                if self.transaction_registry.interrupt_query(self.transaction_id, req_id, interrupt)
+                    == QueryInterrupt::QueryNotInterruptible
+                {
+                    event!(Level::DEBUG, request_id = %req_id, "Executing write query cannot be cancelled.");
+                }
+                Continue(())
+            }
+        }
+    }
+
+    fn query_interrupted_error(interrupt: InterruptType) -> TransactionServiceError {
+        match interrupt {
+            InterruptType::QueryCancelled => TransactionServiceError::QueryCancelled {},
+            InterruptType::QueryTimedOut => TransactionServiceError::QueryTimedOut {},
+            interrupt => TransactionServiceError::QueryInterrupted { interrupt },
+        }
+    }
+
+    // Worker threads do not inherit the current span, so each query carries its own span parented by the transaction
+    fn query_span(&self, req_id: Uuid) -> Span {
+        span!(parent: &self.span, Level::INFO, "query", request_id = %req_id)
+    }
+
+    // A slow query log configured to profile needs the profile of every query, but profiles are only logged when
+    // requested
+    fn execution_query_options(&self, query_options: &QueryOptions) -> QueryOptions {
+        let mut execution_options = query_options.clone();
+        execution_options.profile |= self.slow_query_log.as_ref().is_some_and(|log| log.profiles_queries());
+        execution_options
+    }
+
+    fn is_profile_logged(query_options: &QueryOptions) -> bool {
+        query_options.profile || tracing::enabled!(Level::TRACE)
+    }
+
+    fn start_slow_query_timer(&self) -> Option<SlowQueryTimer> {
+        let slow_query_log = self.slow_query_log.as_ref()?;
+        let (database, transaction_type) = match self.transaction.as_ref()? {
+            Transaction::Read(transaction) => (transaction.database.name(), "read"),
+            Transaction::Write(transaction) => (transaction.database.name(), "write"),
+            Transaction::Schema(transaction) => (transaction.database.name(), "schema"),
+        };
+        Some(SlowQueryTimer::start(slow_query_log.clone(), database, transaction_type))
+    }
+
     async fn handle_query_schema(&mut self, query: SchemaQuery) -> Result<ImmediateQueryResponse, Status> {
         if let Some(Transaction::Schema(schema_transaction)) = self.transaction.take() {
             let TransactionSchema {
@@ -773,16 +1239,38 @@ impl TransactionService {
         }
     }
 
-    async fn run_write_query(&mut self, req_id: Uuid, pipeline: typeql::query::Pipeline) {
+    async fn run_write_query(
+        &mut self,
+        req_id: Uuid,
+        pipeline: typeql::query::Pipeline,
+        query_options: QueryOptions,
+        batch_step: Option<QueryBatchStep>,
+    ) {
         debug_assert!(self.running_write_query.is_none());
         self.interrupt_and_close_responders(InterruptType::WriteQueryExecution).await;
-        let handle = match self.spawn_blocking_execute_write_query(pipeline) {
+        // the read queries before it have finished, and may have failed while executing
+        if batch_step.as_ref().is_some_and(QueryBatchStep::is_stopped) {
+            let response = ImmediateQueryResponse::non_fatal_err(QueryBatchError::QuerySkipped {});
+            Self::respond_query_response(&self.response_sender, req_id, response).await;
+            return;
+        }
+        // kept even while retries are off, since the commit request may turn them on
+        if let Some(Transaction::Write(_)) = &self.transaction {
+            self.write_pipelines.push((pipeline.clone(), query_options.clone()));
+        }
+        let query_span = self.query_span(req_id);
+        let running_query =
+            self.transaction_registry.start_write_query(self.transaction_id, req_id, pipeline.to_string());
+        let handle = match self.spawn_blocking_execute_write_query(pipeline, query_options, running_query, query_span) {
             Ok(handle) => {
                 // running write queries have no valid response yet (until they finish) and will respond asynchronously
                 handle
             }
             Err(err) => {
                 // non-fatal errors we will respond immediately
+                if let Some(batch_step) = &batch_step {
+                    batch_step.fail();
+                }
                 Self::respond_query_response(&self.response_sender, req_id, ImmediateQueryResponse::non_fatal_err(err))
                     .await;
                 return;
@@ -796,10 +1284,11 @@ impl TransactionService {
         req_id: Uuid,
         output_descriptor: StreamQueryOutputDescriptor,
         batch: Batch,
+        profile: Arc<QueryProfile>,
     ) {
         let (sender, receiver) = channel(self.prefetch_size.unwrap() as usize);
         let interrupt = self.query_interrupt_receiver.clone();
-        let batch_reader = self.write_query_batch_reader(output_descriptor, batch, sender, interrupt);
+        let batch_reader = self.write_query_batch_reader(output_descriptor, batch, profile, sender, interrupt);
         let stream_transmitter = QueryStreamTransmitter::start_new(
             self.response_sender.clone(),
             receiver,
@@ -810,9 +1299,18 @@ impl TransactionService {
         self.responders.insert(req_id, (batch_reader, stream_transmitter));
     }
 
-    fn run_and_activate_read_transmitter(&mut self, req_id: Uuid, pipeline: typeql::query::Pipeline) {
+    fn run_and_activate_read_transmitter(
+        &mut self,
+        req_id: Uuid,
+        pipeline: typeql::query::Pipeline,
+        query_options: QueryOptions,
+        batch_step: Option<QueryBatchStep>,
+    ) {
         let (sender, receiver) = channel(self.prefetch_size.unwrap() as usize);
-        let worker_handle = self.blocking_read_query_worker(pipeline, sender);
+        let running_query = self.start_query(req_id, &pipeline, &query_options);
+        let query_span = self.query_span(req_id);
+        let worker_handle =
+            self.blocking_read_query_worker(pipeline, query_options, batch_step, sender, running_query, query_span);
         let stream_transmitter = QueryStreamTransmitter::start_new(
             self.response_sender.clone(),
             receiver,
@@ -826,15 +1324,22 @@ impl TransactionService {
     fn spawn_blocking_execute_write_query(
         &mut self,
         pipeline: typeql::query::Pipeline,
-    ) -> Result<
-        JoinHandle<(Transaction, Result<(StreamQueryOutputDescriptor, Batch), QueryError>)>,
-        TransactionServiceError,
-    > {
+        query_options: QueryOptions,
+        running_query: RunningQueryGuard,
+        query_span: Span,
+    ) -> Result<JoinHandle<(Transaction, WriteQueryResult)>, TransactionServiceError> {
         debug_assert!(self.running_write_query.is_none());
         debug_assert!(self.transaction.is_some());
+        // only the whole transaction interrupts a write query, since its partial writes could not be undone
         let interrupt = self.query_interrupt_receiver.clone();
+        let slow_query_timer = self.start_slow_query_timer();
+        let log_profile = Self::is_profile_logged(&query_options);
+        let answer_profile = query_options.profile;
+        let query_options = self.execution_query_options(&query_options);
         match self.transaction.take() {
             Some(Transaction::Schema(schema_transaction)) => Ok(spawn_blocking(move || {
+                let _query_span_guard = query_span.enter();
+                let _running_query = running_query;
                 let TransactionSchema {
                     snapshot,
                     type_manager,
@@ -852,8 +1357,12 @@ impl TransactionService {
                     &function_manager,
                     &query_manager,
                     &pipeline,
+                    query_options,
+                    log_profile,
+                    slow_query_timer,
                     interrupt,
                 );
+                let result = Self::with_answered_profile(result, answer_profile);
 
                 let transaction = Transaction::Schema(TransactionSchema::from(
                     snapshot,
@@ -867,6 +1376,8 @@ impl TransactionService {
                 (transaction, result)
             })),
             Some(Transaction::Write(write_transaction)) => Ok(spawn_blocking(move || {
+                let _query_span_guard = query_span.enter();
+                let _running_query = running_query;
                 let TransactionWrite {
                     snapshot,
                     type_manager,
@@ -884,8 +1395,12 @@ impl TransactionService {
                     &function_manager,
                     &query_manager,
                     &pipeline,
+                    query_options,
+                    log_profile,
+                    slow_query_timer,
                     interrupt,
                 );
+                let result = Self::with_answered_profile(result, answer_profile);
 
                 let transaction = Transaction::Write(TransactionWrite::from(
                     Arc::new(snapshot),
@@ -906,23 +1421,37 @@ impl TransactionService {
         }
     }
 
-    fn execute_write_query_in<Snapshot: WritableSnapshot + 'static>(
+    // Only a profile requested by the client is answered, not one collected for the slow query log
+    fn with_answered_profile(result: WriteQueryResult, answer_profile: bool) -> WriteQueryResult {
+        result.map(|(descriptor, batch, profile)| match answer_profile {
+            true => (descriptor, batch, profile),
+            false => (descriptor, batch, Arc::new(QueryProfile::new(false))),
+        })
+    }
+
+    pub(crate) fn execute_write_query_in<Snapshot: WritableSnapshot + 'static>(
         snapshot: Snapshot,
         type_manager: &TypeManager,
         thing_manager: Arc<ThingManager>,
         function_manager: &FunctionManager,
         query_manager: &QueryManager,
         pipeline: &typeql::query::Pipeline,
+        query_options: QueryOptions,
+        log_profile: bool,
+        slow_query_timer: Option<SlowQueryTimer>,
         interrupt: ExecutionInterrupt,
-    ) -> (Snapshot, Result<(StreamQueryOutputDescriptor, Batch), QueryError>) {
-        let result =
-            query_manager.prepare_write_pipeline(snapshot, type_manager, thing_manager, function_manager, pipeline);
+    ) -> (Snapshot, WriteQueryResult) {
+        let query = pipeline;
+        let result = query_manager.prepare_write_pipeline(
+            snapshot,
+            type_manager,
+            thing_manager,
+            function_manager,
+            pipeline,
+            query_options,
+        );
         let (query_output_descriptor, pipeline) = match result {
-            Ok(pipeline) => {
-                let named_outputs = pipeline.rows_positions().unwrap();
-                let named_outputs: StreamQueryOutputDescriptor = named_outputs.clone().into_iter().sorted().collect();
-                (named_outputs, pipeline)
-            }
+            Ok(pipeline) => (output_descriptor(&pipeline), pipeline),
             Err((snapshot, err)) => return (snapshot, Err(err)),
         };
 
@@ -937,14 +1466,19 @@ impl TransactionService {
         };
 
         let result = match iterator.collect_owned() {
-            Ok(batch) => (Arc::into_inner(snapshot).unwrap(), Ok((query_output_descriptor, batch))),
+            Ok(batch) => {
+                (Arc::into_inner(snapshot).unwrap(), Ok((query_output_descriptor, batch, query_profile.clone())))
+            }
             Err(err) => {
                 (Arc::into_inner(snapshot).unwrap(), Err(QueryError::WritePipelineExecution { typedb_source: err }))
             }
         };
-        if query_profile.is_enabled() {
+        if query_profile.is_enabled() && log_profile {
             event!(Level::INFO, "Write query completed.\n{}", query_profile);
         }
+        if let (Some(slow_query_timer), Ok((_, batch, _))) = (slow_query_timer, &result.1) {
+            slow_query_timer.finish(query, batch.len() as u64, &query_profile);
+        }
         result
     }
 
@@ -953,6 +1487,7 @@ impl TransactionService {
         &self,
         output_descriptor: StreamQueryOutputDescriptor,
         batch: Batch,
+        profile: Arc<QueryProfile>,
         sender: Sender<StreamQueryResponse>,
         mut interrupt: ExecutionInterrupt,
     ) -> JoinHandle<()> {
@@ -962,8 +1497,12 @@ impl TransactionService {
             let thing_manager = transaction.thing_manager.clone();
             tokio::spawn(async move {
                 let mut as_lending_iter = batch.into_iterator();
-                Self::submit_response_async(&sender, StreamQueryResponse::init_ok_rows(&output_descriptor, Write))
-                    .await;
+                let answer_profile = profile.is_enabled();
+                let initial_response = match answer_profile {
+                    true => StreamQueryResponse::init_ok_profiled_rows(&output_descriptor, Write),
+                    false => StreamQueryResponse::init_ok_rows(&output_descriptor, Write),
+                };
+                Self::submit_response_async(&sender, initial_response).await;
 
                 while let Some(row) = as_lending_iter.next() {
                     if let Some(interrupt) = interrupt.check() {
@@ -978,7 +1517,10 @@ impl TransactionService {
                     let encoded_row =
                         encode_row(row, &output_descriptor, snapshot.as_ref(), &type_manager, &thing_manager);
                     match encoded_row {
-                        Ok(encoded_row) => {
+                        Ok(mut encoded_row) => {
+                            if answer_profile {
+                                encoded_row.row.push(empty_row_entry());
+                            }
                             Self::submit_response_async(&sender, StreamQueryResponse::next_row(encoded_row)).await;
                         }
                         Err(err) => {
@@ -991,6 +1533,10 @@ impl TransactionService {
                         }
                     }
                 }
+                if answer_profile {
+                    let profile_row = encode_profile_row(output_descriptor.len(), profile.to_string());
+                    Self::submit_response_async(&sender, StreamQueryResponse::next_row(profile_row)).await;
+                }
                 Self::submit_response_async(&sender, StreamQueryResponse::done_ok()).await
             })
         })
@@ -999,12 +1545,20 @@ impl TransactionService {
     fn blocking_read_query_worker(
         &self,
         pipeline: typeql::query::Pipeline,
+        query_options: QueryOptions,
+        mut batch_step: Option<QueryBatchStep>,
         sender: Sender<StreamQueryResponse>,
+        (running_query, query_signal): (RunningQueryGuard, broadcast::Receiver<InterruptType>),
+        query_span: Span,
     ) -> JoinHandle<()> {
         debug_assert!(
             self.request_queue.is_empty() && self.running_write_query.is_none() && self.transaction.is_some()
         );
-        let interrupt = self.query_interrupt_receiver.clone();
+        let interrupt = self.query_interrupt_receiver.clone().with_query_signal(query_signal);
+        let slow_query_timer = self.start_slow_query_timer();
+        let log_profile = Self::is_profile_logged(&query_options);
+        let answer_profile = query_options.profile;
+        let query_options = self.execution_query_options(&query_options);
         with_readable_transaction!(self.transaction.as_ref().unwrap(), |transaction| {
             let snapshot = transaction.snapshot.clone();
             let type_manager = transaction.type_manager.clone();
@@ -1012,23 +1566,61 @@ impl TransactionService {
             let function_manager = transaction.function_manager.clone();
             let query_manager = transaction.query_manager.clone();
             spawn_blocking(move || {
-                let pipeline = Self::prepare_read_query_in(
-                    snapshot.clone(),
-                    &type_manager,
-                    thing_manager.clone(),
-                    &function_manager,
-                    &query_manager,
-                    &pipeline,
-                );
-
-                let pipeline = unwrap_or_execute_and_return!(pipeline, |err| {
-                    Self::submit_response_sync(&sender, StreamQueryResponse::done_err(err));
-                });
-                Self::respond_read_query_sync(pipeline, interrupt, &sender, snapshot, &type_manager, thing_manager);
+                let _query_span_guard = query_span.enter();
+                let _running_query = running_query;
+                if batch_step.as_mut().is_some_and(|batch_step| !batch_step.wait_for_previous()) {
+                    Self::submit_response_sync(
+                        &sender,
+                        StreamQueryResponse::done_err(QueryBatchError::QuerySkipped {}),
+                    );
+                    return;
+                }
+                let failed = if query_options.explain {
+                    let plan = query_manager.explain_pipeline(
+                        snapshot.as_ref(),
+                        &type_manager,
+                        &thing_manager,
+                        &function_manager,
+                        &pipeline,
+                    );
+                    Self::respond_explained_query_sync(&sender, plan)
+                } else {
+                    let prepared_pipeline = Self::prepare_read_query_in(
+                        snapshot.clone(),
+                        &type_manager,
+                        thing_manager.clone(),
+                        &function_manager,
+                        &query_manager,
+                        &pipeline,
+                        query_options,
+                    );
+                    match prepared_pipeline {
+                        Ok(prepared_pipeline) => Self::respond_read_query_sync(
+                            prepared_pipeline,
+                            interrupt,
+                            &sender,
+                            snapshot,
+                            &type_manager,
+                            thing_manager,
+                            &pipeline,
+                            log_profile,
+                            answer_profile,
+                            slow_query_timer,
+                        ),
+                        Err(err) => {
+                            Self::submit_response_sync(&sender, StreamQueryResponse::done_err(err));
+                            true
+                        }
+                    }
+                };
+                if let (true, Some(batch_step)) = (failed, &batch_step) {
+                    batch_step.fail();
+                }
             })
         })
     }
 
+    // Answers whether the query failed with an error, rather than finishing or being interrupted
     fn respond_read_query_sync<Snapshot: ReadableSnapshot>(
         pipeline: Pipeline<Snapshot, ReadPipelineStage<Snapshot>>,
         mut interrupt: ExecutionInterrupt,
@@ -1036,36 +1628,43 @@ impl TransactionService {
         snapshot: Arc<Snapshot>,
         type_manager: &TypeManager,
         thing_manager: Arc<ThingManager>,
-    ) {
+        query: &typeql::query::Pipeline,
+        log_profile: bool,
+        answer_profile: bool,
+        slow_query_timer: Option<SlowQueryTimer>,
+    ) -> bool {
+        let mut answers: u64 = 0;
         let query_profile = if pipeline.has_fetch() {
             let initial_response = StreamQueryResponse::init_ok_documents(Read);
             Self::submit_response_sync(sender, initial_response);
-            let (iterator, context) =
-                unwrap_or_execute_and_return!(pipeline.into_documents_iterator(interrupt.clone()), |(err, _)| {
-                    Self::submit_response_sync(
-                        sender,
-                        StreamQueryResponse::done_err(QueryError::ReadPipelineExecution { typedb_source: err }),
-                    );
-                });
+            let (iterator, context) = match pipeline.into_documents_iterator(interrupt.clone()) {
+                Ok(iterator) => iterator,
+                Err((err, _)) => {
+                    let error = QueryError::ReadPipelineExecution { typedb_source: err };
+                    return Self::submit_execution_error_sync(sender, &mut interrupt, error);
+                }
+            };
 
             let parameters = context.parameters;
             for next in iterator {
                 if let Some(interrupt) = interrupt.check() {
                     Self::submit_response_sync(
                         sender,
-                        StreamQueryResponse::done_err(TransactionServiceError::QueryInterrupted { interrupt }),
+                        StreamQueryResponse::done_err(Self::query_interrupted_error(interrupt)),
                     );
-                    return;
+                    return false;
                 }
 
-                let document = unwrap_or_execute_and_return!(next, |err| {
-                    Self::submit_response_sync(sender, StreamQueryResponse::done_err(err));
-                });
+                let document = match next {
+                    Ok(document) => document,
+                    Err(err) => return Self::submit_execution_error_sync(sender, &mut interrupt, err),
+                };
 
                 let encoded_document =
                     encode_document(document, snapshot.as_ref(), type_manager, &thing_manager, &parameters);
                 match encoded_document {
                     Ok(encoded_document) => {
+                        answers += 1;
                         Self::submit_response_sync(sender, StreamQueryResponse::next_document(encoded_document))
                     }
                     Err(err) => {
@@ -1073,67 +1672,145 @@ impl TransactionService {
                             sender,
                             StreamQueryResponse::done_err(PipelineExecutionError::ConceptRead { source: err }),
                         );
-                        return;
+                        return true;
                     }
                 }
             }
+            if answer_profile {
+                let profile_document = encode_profile_document(context.profile.to_string());
+                Self::submit_response_sync(sender, StreamQueryResponse::next_document(profile_document));
+            }
             context.profile
         } else {
-            let named_outputs = pipeline.rows_positions().unwrap();
-            let descriptor: StreamQueryOutputDescriptor = named_outputs.clone().into_iter().sorted().collect();
-            let initial_response = StreamQueryResponse::init_ok_rows(&descriptor, Read);
+            let descriptor = output_descriptor(&pipeline);
+            let initial_response = match answer_profile {
+                true => StreamQueryResponse::init_ok_profiled_rows(&descriptor, Read),
+                false => StreamQueryResponse::init_ok_rows(&descriptor, Read),
+            };
             Self::submit_response_sync(sender, initial_response);
 
-            let (mut iterator, context) =
-                unwrap_or_execute_and_return!(pipeline.into_rows_iterator(interrupt.clone()), |(err, _)| {
-                    Self::submit_response_sync(
-                        sender,
-                        StreamQueryResponse::done_err(QueryError::ReadPipelineExecution { typedb_source: err }),
-                    );
-                });
+            let (mut iterator, context) = match pipeline.into_rows_iterator(interrupt.clone()) {
+                Ok(iterator) => iterator,
+                Err((err, _)) => {
+                    let error = QueryError::ReadPipelineExecution { typedb_source: err };
+                    return Self::submit_execution_error_sync(sender, &mut interrupt, error);
+                }
+            };
 
             while let Some(next) = iterator.next() {
                 if let Some(interrupt) = interrupt.check() {
                     Self::submit_response_sync(
                         sender,
-                        StreamQueryResponse::done_err(TransactionServiceError::QueryInterrupted { interrupt }),
+                        StreamQueryResponse::done_err(Self::query_interrupted_error(interrupt)),
                     );
-                    return;
+                    return false;
                 }
 
-                let row = unwrap_or_execute_and_return!(next, |err| {
-                    Self::submit_response_sync(sender, StreamQueryResponse::done_err(err));
-                });
+                let row = match next {
+                    Ok(row) => row,
+                    Err(err) => return Self::submit_execution_error_sync(sender, &mut interrupt, err),
+                };
 
                 let encoded_row = encode_row(row, &descriptor, snapshot.as_ref(), type_manager, &thing_manager);
                 match encoded_row {
-                    Ok(encoded_row) => Self::submit_response_sync(sender, StreamQueryResponse::next_row(encoded_row)),
+                    Ok(mut encoded_row) => {
+                        if answer_profile {
+                            encoded_row.row.push(empty_row_entry());
+                        }
+                        answers += 1;
+                        Self::submit_response_sync(sender, StreamQueryResponse::next_row(encoded_row))
+                    }
                     Err(err) => {
                         Self::submit_response_sync(
                             sender,
                             StreamQueryResponse::done_err(PipelineExecutionError::ConceptRead { source: err }),
                         );
-                        return;
+                        return true;
                     }
                 }
             }
+            if answer_profile {
+                let profile_row = encode_profile_row(descriptor.len(), context.profile.to_string());
+                Self::submit_response_sync(sender, StreamQueryResponse::next_row(profile_row));
+            }
             context.profile
         };
-        if query_profile.is_enabled() {
+        if query_profile.is_enabled() && log_profile {
             event!(Level::INFO, "Read query done (including network request time).\n{}", query_profile);
         }
-        Self::submit_response_sync(sender, StreamQueryResponse::done_ok())
+        if let Some(slow_query_timer) = slow_query_timer {
+            slow_query_timer.finish(query, answers, &query_profile);
+        }
+        Self::submit_response_sync(sender, StreamQueryResponse::done_ok());
+        false
     }
 
-    fn prepare_read_query_in<Snapshot: ReadableSnapshot + 'static>(
+    // An explained query answers its plan as the single row of a `plan` column, answering whether it failed
+    fn respond_explained_query_sync(
+        sender: &Sender<StreamQueryResponse>,
+        plan: Result<PipelinePlan, QueryError>,
+    ) -> bool {
+        let plan = match plan {
+            Ok(plan) => plan,
+            Err(err) => {
+                Self::submit_response_sync(sender, StreamQueryResponse::done_err(err));
+                return true;
+            }
+        };
+        let descriptor = vec![(
+            QUERY_EXPLAIN_PLAN_COLUMN.to_owned(),
+            VariablePosition::new(0),
+            ColumnType::Value(ValueType::String),
+        )];
+        Self::submit_response_sync(sender, StreamQueryResponse::init_ok_rows(&descriptor, Read));
+        let plan = typedb_protocol::Value { value: Some(typedb_protocol::value::Value::String(plan.to_string())) };
+        let row = typedb_protocol::ConceptRow {
+            row: vec![typedb_protocol::RowEntry { entry: Some(typedb_protocol::row_entry::Entry::Value(plan)) }],
+        };
+        Self::submit_response_sync(sender, StreamQueryResponse::next_row(row));
+        Self::submit_response_sync(sender, StreamQueryResponse::done_ok());
+        false
+    }
+
+    pub(crate) fn prepare_read_query_in<Snapshot: ReadableSnapshot + 'static>(
         snapshot: Arc<Snapshot>,
         type_manager: &TypeManager,
         thing_manager: Arc<ThingManager>,
         function_manager: &FunctionManager,
         query_manager: &QueryManager,
         pipeline: &typeql::query::Pipeline,
+        query_options: QueryOptions,
     ) -> Result<Pipeline<Snapshot, ReadPipelineStage<Snapshot>>, QueryError> {
-        query_manager.prepare_read_pipeline(snapshot, type_manager, thing_manager, function_manager, pipeline)
+        query_manager.prepare_read_pipeline(
+            snapshot,
+            type_manager,
+            thing_manager,
+            function_manager,
+            pipeline,
+            query_options,
+        )
+    }
+
+    // A query interrupted inside the executor fails with an execution error, so its interrupt is checked again to report
+    // a cancellation or timeout with its own error. Answers whether the query failed rather than being interrupted.
+    fn submit_execution_error_sync(
+        sender: &Sender<StreamQueryResponse>,
+        interrupt: &mut ExecutionInterrupt,
+        error: impl IntoProtocolErrorMessage,
+    ) -> bool {
+        match interrupt.check() {
+            Some(interrupt @ (InterruptType::QueryCancelled | InterruptType::QueryTimedOut)) => {
+                Self::submit_response_sync(
+                    sender,
+                    StreamQueryResponse::done_err(Self::query_interrupted_error(interrupt)),
+                );
+                false
+            }
+            interrupt => {
+                Self::submit_response_sync(sender, StreamQueryResponse::done_err(error));
+                interrupt.is_none()
+            }
+        }
     }
 
     fn submit_response_sync(sender: &Sender<StreamQueryResponse>, response: StreamQueryResponse) {
@@ -1166,7 +1843,12 @@ impl TransactionService {
         }
     }
 
-    fn is_write_pipeline(pipeline: &typeql::query::Pipeline) -> bool {
+    // Explained write pipelines are only compiled, so they are answered like read queries
+    fn is_write_query(pipeline: &typeql::query::Pipeline, query_options: &QueryOptions) -> bool {
+        Self::is_write_pipeline(pipeline) && !query_options.explain
+    }
+
+    pub(crate) fn is_write_pipeline(pipeline: &typeql::query::Pipeline) -> bool {
         for stage in &pipeline.stages {
             match stage {
                 Stage::Insert(_) | Stage::Put(_) | Stage::Delete(_) | Stage::Update(_) => return true,
@@ -1447,6 +2129,28 @@ impl<T, E> ResultControlFlowExt<T, E> for ControlFlow<Result<T, E>> {
     }
 }
 
+/// The named outputs of a rows pipeline, ordered by name, with the column types known before it runs
+pub(crate) fn output_descriptor<Snapshot: ReadableSnapshot + 'static, Nonterminals: StageAPI<Snapshot>>(
+    pipeline: &Pipeline<Snapshot, Nonterminals>,
+) -> StreamQueryOutputDescriptor {
+    let column_types = pipeline.rows_column_types().unwrap();
+    pipeline
+        .rows_positions()
+        .unwrap()
+        .iter()
+        .map(|(name, &position)| {
+            (name.clone(), position, column_types.get(name).cloned().unwrap_or(ColumnType::Unknown))
+        })
+        .sorted_by(|(name, _, _), (other_name, _, _)| name.cmp(other_name))
+        .collect()
+}
+
+// Commits answer how many attempts they took as status metadata, as the protocol's commit has no response
+fn with_commit_attempts(mut status: Status, attempts: u32) -> Status {
+    status.metadata_mut().insert(COMMIT_ATTEMPTS_METADATA_FIELD, attempts.into());
+    status
+}
+
 typedb_error!(
     pub(crate) TransactionServiceError(component = "Transaction service", prefix = "TSV") {
         DatabaseNotFound(1, "Database '{name}' not found.", name: String),
@@ -1470,5 +2174,293 @@ typedb_error!(
             query_request_id: Uuid
         ),
         ServiceClosingFailedQueueCleanup(14, "The operation failed since the service is closing."),
+        CommitRetryReplayFailed(
+            15,
+            "Replaying the transaction's write queries for commit attempt {attempt} failed.",
+            attempt: u32,
+            ( typedb_source: QueryError )
+        ),
+        DataCommitFailedAfterRetries(
+            16,
+            "Data transaction commit failed after {attempts} attempts.",
+            attempts: u32,
+            ( typedb_source: DataCommitError )
+        ),
+        TransactionKilled(17, "The transaction was killed on request."),
+        QueryCancelled(18, "The query was cancelled on request."),
+        QueryTimedOut(19, "The query was cancelled, since it executed for longer than its timeout."),
+        InvalidQueryTimeout(
+            20,
+            "Invalid query timeout '{value}'. It must be a whole number of milliseconds.",
+            value: String
+        ),
+        InvalidMetadataFlag(
+            21,
+            "Invalid value '{value}' for the '{field}' metadata field. It must be 'true' or 'false'.",
+            field: &'static str,
+            value: String
+        ),
+        SchemaQueryCannotBeExplained(22, "Only pipeline queries can be explained, not schema queries."),
+        InvalidCommitRetryAttempts(
+            23,
+            "Invalid commit retry attempts '{value}'. It must be a whole number.",
+            value: String
+        ),
+        CommitRetryAttemptsAboveMaximum(
+            24,
+            "Requested {attempts} commit retry attempts, but the server allows at most {maximum}.",
+            attempts: u32,
+            maximum: u32
+        ),
+        CommitRetryInterrupted(
+            25,
+            "Data transaction commit stopped retrying after {attempts} attempts, due to a {interrupt}.",
+            attempts: u32,
+            interrupt: InterruptType
+        ),
+        WriteQueryCannotTimeOut(
+            26,
+            "Write queries cannot have a timeout, since stopping one part way would leave its writes partially applied."
+        ),
     }
 );
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use resource::constants::server::{
+        COMMIT_ATTEMPTS_METADATA_FIELD, COMMIT_RETRY_ATTEMPTS_METADATA_FIELD, DEFAULT_MAX_COMMIT_RETRY_ATTEMPTS,
+        QUERY_BATCH_METADATA_FIELD, QUERY_CANCEL_METADATA_FIELD, QUERY_PROFILE_COLUMN, QUERY_PROFILE_METADATA_FIELD,
+        QUERY_TIMEOUT_METADATA_FIELD,
+    };
+    use tonic::{metadata::MetadataMap, Code};
+    use typedb_protocol::{
+        transaction::{req::Req, Type},
+        value::Value,
+    };
+    use uuid::Uuid;
+
+    use crate::service::test_client::{query_request, request_with_id, TestAnswer, TestServer, TestTransaction};
+
+    fn commit_attempts(metadata: &MetadataMap) -> Option<&str> {
+        metadata.get(COMMIT_ATTEMPTS_METADATA_FIELD).map(|value| value.to_str().unwrap())
+    }
+
+    // Opens a write transaction that gives Alice an age, then replaces Alice with a new person of the same name in a
+    // concurrent transaction that commits first, so committing the first transaction conflicts
+    async fn conflicting_write(server: &TestServer, database: &str, open_metadata: &[(&str, &str)]) -> TestTransaction {
+        server.database_manager.create_database(database).unwrap();
+        let mut transaction = server.open_transaction(database, Type::Schema).await;
+        let define =
+            "define entity person, owns name, owns age; attribute name, value string; attribute age, value long;";
+        assert_eq!(transaction.query(define).await.error, None);
+        transaction.commit(&[]).await.unwrap();
+        let mut transaction = server.open_transaction(database, Type::Write).await;
+        assert_eq!(transaction.query(r#"insert $p isa person, has name "Alice";"#).await.error, None);
+        transaction.commit(&[]).await.unwrap();
+
+        let mut conflicting = server.open_transaction_with(database, Type::Write, open_metadata).await;
+        let answer = conflicting.query(r#"match $p isa person, has name "Alice"; insert $p has age 30;"#).await;
+        assert_eq!(answer.error, None);
+        let mut replacing = server.open_transaction(database, Type::Write).await;
+        assert_eq!(replacing.query(r#"match $p isa person, has name "Alice"; delete $p;"#).await.error, None);
+        assert_eq!(replacing.query(r#"insert $p isa person, has name "Alice";"#).await.error, None);
+        replacing.commit(&[]).await.unwrap();
+        conflicting
+    }
+
+    #[tokio::test]
+    async fn conflicting_commit_is_retried_until_it_succeeds() {
+        let server = TestServer::start().await;
+        let transaction = conflicting_write(&server, "retry", &[(COMMIT_RETRY_ATTEMPTS_METADATA_FIELD, "3")]).await;
+        // the replayed query gives the new Alice the age, and the second attempt commits
+        let trailers = transaction.commit(&[]).await.unwrap();
+        assert_eq!(commit_attempts(&trailers), Some("2"));
+
+        let mut transaction = server.open_transaction("retry", Type::Read).await;
+        assert_eq!(transaction.query("match $p isa person;").await.rows.len(), 1);
+        let answer = transaction.query(r#"match $p isa person, has name "Alice", has age 30;"#).await;
+        assert_eq!(answer.rows.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn commit_retries_above_the_server_maximum_are_rejected() {
+        let server = TestServer::start().await;
+        let transaction = conflicting_write(&server, "retry_maximum", &[]).await;
+        let attempts = (DEFAULT_MAX_COMMIT_RETRY_ATTEMPTS + 1).to_string();
+        let status = transaction.commit(&[(COMMIT_RETRY_ATTEMPTS_METADATA_FIELD, &attempts)]).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(commit_attempts(status.metadata()), None);
+    }
+
+    #[tokio::test]
+    async fn commit_retries_can_be_requested_on_commit() {
+        let server = TestServer::start().await;
+        let transaction = conflicting_write(&server, "retry_on_commit", &[]).await;
+        let trailers = transaction.commit(&[(COMMIT_RETRY_ATTEMPTS_METADATA_FIELD, "1")]).await.unwrap();
+        assert_eq!(commit_attempts(&trailers), Some("2"));
+    }
+
+    #[tokio::test]
+    async fn conflicting_commit_without_retries_fails_after_one_attempt() {
+        let server = TestServer::start().await;
+        let transaction = conflicting_write(&server, "no_retry", &[]).await;
+        let status = transaction.commit(&[]).await.unwrap_err();
+        assert_eq!(commit_attempts(status.metadata()), Some("1"));
+    }
+
+    #[tokio::test]
+    async fn profiled_query_answers_its_profile_in_a_final_row() {
+        let server = TestServer::start().await;
+        server.database_manager.create_database("profile").unwrap();
+        let mut transaction = server.open_transaction("profile", Type::Schema).await;
+        assert_eq!(transaction.query("define entity person;").await.error, None);
+        transaction.commit(&[]).await.unwrap();
+        let mut transaction = server.open_transaction("profile", Type::Write).await;
+        assert_eq!(transaction.query("insert $p isa person;").await.error, None);
+        transaction.commit(&[]).await.unwrap();
+
+        let mut transaction = server.open_transaction("profile", Type::Read).await;
+        let answer = transaction.query_with("match $p isa person;", &[(QUERY_PROFILE_METADATA_FIELD, "true")]).await;
+        assert_eq!(answer.error, None);
+        assert_eq!(answer.columns, vec!["p".to_owned(), QUERY_PROFILE_COLUMN.to_owned()]);
+        assert_eq!(answer.rows.len(), 2);
+        assert_eq!(answer.value(0, QUERY_PROFILE_COLUMN), None);
+        let Some(Value::String(profile)) = answer.value(1, QUERY_PROFILE_COLUMN) else {
+            panic!("expected a profile in the final row, found {:?}", answer.rows[1]);
+        };
+        assert!(profile.starts_with("Query profile[measurements_enabled=true]"), "{profile}");
+
+        let answer = transaction.query("match $p isa person;").await;
+        assert_eq!(answer.columns, vec!["p".to_owned()]);
+        assert_eq!(answer.rows.len(), 1);
+    }
+
+    // Sends a batch whose second query fails while it executes, since the age it adds one to overflows
+    async fn batch_failing_while_executing(server: &TestServer, database: &str, mode: &str) -> Vec<TestAnswer> {
+        server.database_manager.create_database(database).unwrap();
+        let mut transaction = server.open_transaction(database, Type::Schema).await;
+        assert_eq!(transaction.query("define entity person, owns age; attribute age, value long;").await.error, None);
+        transaction.commit(&[]).await.unwrap();
+        let mut transaction = server.open_transaction(database, Type::Write).await;
+        let answer = transaction.query(&format!("insert $p isa person, has age {};", i64::MAX)).await;
+        assert_eq!(answer.error, None);
+        transaction.commit(&[]).await.unwrap();
+
+        let mut transaction = server.open_transaction(database, Type::Read).await;
+        let metadata = [(QUERY_BATCH_METADATA_FIELD, mode)];
+        let queries = ["match $p isa person;", "match $p has age $a; let $b = $a + 1;", "match $p has age $a;"];
+        let req_ids = transaction.send(queries.iter().map(|query| query_request(query, &metadata)).collect()).await;
+        let mut answers = Vec::new();
+        for req_id in req_ids {
+            answers.push(transaction.answer(req_id).await);
+        }
+        answers
+    }
+
+    #[tokio::test]
+    async fn read_failing_while_executing_stops_a_stop_on_error_batch() {
+        let server = TestServer::start().await;
+        let answers = batch_failing_while_executing(&server, "batch_stop", "stop-on-error").await;
+        assert_eq!(answers[0].error, None);
+        assert_eq!(answers[0].rows.len(), 1);
+        assert!(answers[1].error.is_some());
+        assert_ne!(answers[1].error_code(), Some("QBT2"));
+        assert_eq!(answers[2].error_code(), Some("QBT2"));
+        assert!(answers[2].rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn read_failing_while_executing_does_not_stop_a_continue_batch() {
+        let server = TestServer::start().await;
+        let answers = batch_failing_while_executing(&server, "batch_continue", "continue").await;
+        assert_eq!(answers[0].error, None);
+        assert!(answers[1].error.is_some());
+        assert_eq!(answers[2].error, None);
+        assert_eq!(answers[2].rows.len(), 1);
+    }
+
+    const PEOPLE: usize = 50;
+    // Answers every pair of people, far more rows than fit in the answer stream before the client continues it, so
+    // the query keeps running until its stream is read
+    const PAIRS_QUERY: &str = "match $a isa person; $b isa person;";
+
+    async fn people(server: &TestServer, database: &str) -> TestTransaction {
+        server.database_manager.create_database(database).unwrap();
+        let mut transaction = server.open_transaction(database, Type::Schema).await;
+        assert_eq!(transaction.query("define entity person;").await.error, None);
+        transaction.commit(&[]).await.unwrap();
+        let mut transaction = server.open_transaction(database, Type::Write).await;
+        let insert = (0..PEOPLE).map(|i| format!("$p{i} isa person;")).collect::<Vec<_>>().join(" ");
+        assert_eq!(transaction.query(&format!("insert {insert}")).await.error, None);
+        transaction.commit(&[]).await.unwrap();
+        server.open_transaction(database, Type::Read).await
+    }
+
+    #[tokio::test]
+    async fn query_is_interrupted_at_its_timeout() {
+        let server = TestServer::start().await;
+        let mut transaction = people(&server, "timeout").await;
+        let timeout = [(QUERY_TIMEOUT_METADATA_FIELD, "100")];
+        let req_id = transaction.send(vec![query_request(PAIRS_QUERY, &timeout)]).await.remove(0);
+        // the query cannot finish before its stream is read
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let answer = transaction.answer(req_id).await;
+        assert_eq!(answer.error_code(), Some("TSV19"));
+        assert!(answer.rows.len() < PEOPLE * PEOPLE);
+
+        let answer = transaction.query_with(PAIRS_QUERY, &[(QUERY_TIMEOUT_METADATA_FIELD, "60000")]).await;
+        assert_eq!(answer.error, None);
+        assert_eq!(answer.rows.len(), PEOPLE * PEOPLE);
+    }
+
+    #[tokio::test]
+    async fn cancelled_query_stops_and_leaves_the_transaction_usable() {
+        let server = TestServer::start().await;
+        let mut transaction = people(&server, "cancel").await;
+        let req_id = transaction.send(vec![query_request(PAIRS_QUERY, &[])]).await.remove(0);
+        let cancel = request_with_id(req_id, Req::StreamReq(Default::default()), &[(QUERY_CANCEL_METADATA_FIELD, "")]);
+        transaction.send(vec![cancel]).await;
+        let answer = transaction.answer(req_id).await;
+        assert_eq!(answer.error_code(), Some("TSV18"));
+        assert!(answer.rows.len() < PEOPLE * PEOPLE);
+
+        let answer = transaction.query("match $p isa person;").await;
+        assert_eq!(answer.error, None);
+        assert_eq!(answer.rows.len(), PEOPLE);
+    }
+
+    #[tokio::test]
+    async fn write_query_with_a_timeout_is_rejected_and_leaves_the_transaction_usable() {
+        let server = TestServer::start().await;
+        people(&server, "write_timeout").await;
+        let mut transaction = server.open_transaction("write_timeout", Type::Write).await;
+        let answer = transaction.query_with("insert $p isa person;", &[(QUERY_TIMEOUT_METADATA_FIELD, "60000")]).await;
+        assert_eq!(answer.error_code(), Some("TSV26"));
+
+        assert_eq!(transaction.query("insert $p isa person;").await.error, None);
+        assert_eq!(transaction.query("match $p isa person;").await.rows.len(), PEOPLE + 1);
+        transaction.commit(&[]).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancelled_write_query_runs_to_the_end_and_leaves_the_transaction_usable() {
+        let server = TestServer::start().await;
+        people(&server, "write_cancel").await;
+        let mut transaction = server.open_transaction("write_cancel", Type::Write).await;
+        let query = query_request("match $p isa person; insert $q isa person;", &[]);
+        let req_id = Uuid::from_slice(&query.req_id).unwrap();
+        let cancel = request_with_id(req_id, Req::StreamReq(Default::default()), &[(QUERY_CANCEL_METADATA_FIELD, "")]);
+        // the write query starts executing as soon as it is received, before the cancel request
+        transaction.send(vec![query, cancel]).await;
+        let answer = transaction.answer(req_id).await;
+        assert_eq!(answer.error, None);
+        assert_eq!(answer.rows.len(), PEOPLE);
+
+        let answer = transaction.query("match $p isa person;").await;
+        assert_eq!(answer.error, None);
+        assert_eq!(answer.rows.len(), 2 * PEOPLE);
+        transaction.commit(&[]).await.unwrap();
+    }
+}
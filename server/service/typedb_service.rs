@@ -8,12 +8,13 @@ use std::{fmt::format, net::SocketAddr, pin::Pin, sync::Arc, time::Instant};
 
 use database::database_manager::DatabaseManager;
 use error::typedb_error;
-use resource::constants::server::{AUTHENTICATOR_USERNAME_FIELD, DEFAULT_USER_NAME};
+use options::SortSpillOptions;
+use resource::constants::server::{AUTHENTICATOR_USERNAME_FIELD, CONNECTION_ID_METADATA_FIELD, DEFAULT_USER_NAME};
 use system::concepts::{Credential, PasswordHash, User};
 use tokio::sync::mpsc::channel;
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::{metadata::MetadataMap, Request, Response, Status, Streaming};
-use tracing::{event, Level};
+use tracing::{event, span, Instrument, Level};
 use typedb_protocol::{
     self,
     server_manager::all::{Req, Res},
@@ -38,6 +39,8 @@ use crate::service::{
             user_create_res, user_update_res, users_all_res, users_contains_res, users_delete_res, users_get_res,
         },
     },
+    slow_query_log::SlowQueryLog,
+    transaction_registry::TransactionRegistry,
     transaction_service::TransactionService,
     ConnectionID,
 };
@@ -47,15 +50,34 @@ pub(crate) struct TypeDBService {
     address: SocketAddr,
     database_manager: Arc<DatabaseManager>,
     user_manager: Arc<UserManager>,
+    slow_query_log: Option<Arc<SlowQueryLog>>,
+    transaction_registry: Arc<TransactionRegistry>,
+    query_memory_limit_bytes: Option<u64>,
+    query_sort_spill: Option<SortSpillOptions>,
+    max_commit_retry_attempts: u32,
 }
 
 impl TypeDBService {
-    pub(crate) fn new(address: &SocketAddr, database_manager: DatabaseManager, user_manager: Arc<UserManager>) -> Self {
-        Self { address: *address, database_manager: Arc::new(database_manager), user_manager }
-    }
-
-    pub(crate) fn database_manager(&self) -> &DatabaseManager {
-        &self.database_manager
+    pub(crate) fn new(
+        address: &SocketAddr,
+        database_manager: Arc<DatabaseManager>,
+        user_manager: Arc<UserManager>,
+        slow_query_log: Option<Arc<SlowQueryLog>>,
+        transaction_registry: Arc<TransactionRegistry>,
+        query_memory_limit_bytes: Option<u64>,
+        query_sort_spill: Option<SortSpillOptions>,
+        max_commit_retry_attempts: u32,
+    ) -> Self {
+        Self {
+            address: *address,
+            database_manager,
+            user_manager,
+            slow_query_log,
+            transaction_registry,
+            query_memory_limit_bytes,
+            query_sort_spill,
+            max_commit_retry_attempts,
+        }
     }
 
     fn generate_connection_id(&self) -> ConnectionID {
@@ -70,6 +92,7 @@ impl typedb_protocol::type_db_server::TypeDb for TypeDBService {
         request: Request<typedb_protocol::connection::open::Req>,
     ) -> Result<Response<typedb_protocol::connection::open::Res>, Status> {
         let receive_time = Instant::now();
+        let remote_address = request.remote_addr();
         let message = request.into_inner();
         if message.version != typedb_protocol::Version::Version as i32 {
             let err = ProtocolError::IncompatibleProtocolVersion {
@@ -88,8 +111,15 @@ impl typedb_protocol::type_db_server::TypeDb for TypeDBService {
                 &message.driver_version
             );
             // generate a connection ID per 'connection_open' to be able to trace different connections by the same user
+            let connection_id = self.generate_connection_id();
+            event!(
+                Level::DEBUG,
+                connection_id = %Uuid::from_bytes(connection_id),
+                remote_address = ?remote_address,
+                "Opened connection"
+            );
             Ok(Response::new(connection_open_res(
-                self.generate_connection_id(),
+                connection_id,
                 receive_time,
                 database_all_res(&self.address, self.database_manager.database_names()),
             )))
@@ -263,16 +293,40 @@ impl typedb_protocol::type_db_server::TypeDb for TypeDBService {
         &self,
         request: Request<Streaming<Client>>,
     ) -> Result<Response<Self::transactionStream>, Status> {
+        // Drivers pass back the connection ID answered by 'connection_open', tying the transaction's logs to its
+        // connection. The transaction ID is the one listed and killed through the transaction registry.
+        let connection_id = extract_connection_id(request.metadata())
+            .map_or_else(|| "unknown".to_owned(), |connection_id| Uuid::from_bytes(connection_id).to_string());
+        let transaction_id = Uuid::new_v4();
+        let span = span!(Level::INFO, "transaction", connection_id = %connection_id, transaction_id = %transaction_id);
+        let user = extract_username_field(request.metadata());
         let request_stream = request.into_inner();
         let (response_sender, response_receiver) = channel(10);
-        let mut service = TransactionService::new(request_stream, response_sender, self.database_manager.clone());
-        tokio::spawn(async move { service.listen().await });
+        let mut service = TransactionService::new(
+            request_stream,
+            response_sender,
+            self.database_manager.clone(),
+            self.slow_query_log.clone(),
+            self.transaction_registry.clone(),
+            self.query_memory_limit_bytes,
+            self.query_sort_spill.clone(),
+            self.max_commit_retry_attempts,
+            transaction_id,
+            user,
+            span.clone(),
+        );
+        tokio::spawn(async move { service.listen().await }.instrument(span));
         let stream: ReceiverStream<Result<Server, Status>> = ReceiverStream::new(response_receiver);
         Ok(Response::new(Box::pin(stream)))
     }
 }
 
-fn extract_username_field(metadata: &MetadataMap) -> String {
+fn extract_connection_id(metadata: &MetadataMap) -> Option<ConnectionID> {
+    let connection_id = metadata.get(CONNECTION_ID_METADATA_FIELD)?.to_str().ok()?;
+    Uuid::parse_str(connection_id).ok().map(Uuid::into_bytes)
+}
+
+pub(crate) fn extract_username_field(metadata: &MetadataMap) -> String {
     metadata
         .get(AUTHENTICATOR_USERNAME_FIELD)
         .map(|u| u.to_str())
@@ -4,25 +4,45 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use std::{error::Error, fmt, fs, io, path::PathBuf, sync::Arc};
+use std::{
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+};
 
-use database::{database_manager::DatabaseManager, DatabaseOpenError};
-use resource::constants::server::GRPC_CONNECTION_KEEPALIVE;
+use database::{
+    database_manager::DatabaseManager,
+    replication::{ReplicationFollower, ReplicationServer},
+    DatabaseOpenError,
+};
+use options::SortSpillOptions;
+use resource::constants::{executor::SORT_SPILL_DIRECTORY_NAME, server::GRPC_CONNECTION_KEEPALIVE};
 use system::initialise_system_database;
 use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+use tracing::{event, Level};
 use user::{initialise_default_user, user_manager::UserManager};
 
 use crate::{
     authenticator::Authenticator,
     parameters::config::{Config, EncryptionConfig},
-    service::typedb_service::TypeDBService,
+    service::{
+        admin_service::AdminService, change_stream_service::ChangeStreamService, http_service::HttpService,
+        metrics_service::MetricsService, slow_query_log::SlowQueryLog, transaction_registry::TransactionRegistry,
+        typedb_service::TypeDBService,
+    },
 };
 
 #[derive(Debug)]
 pub struct Server {
     data_directory: PathBuf,
+    database_manager: Arc<DatabaseManager>,
     user_manager: Arc<UserManager>,
+    transaction_registry: Arc<TransactionRegistry>,
+    sort_spill: SortSpillOptions,
     typedb_service: Option<TypeDBService>,
+    replication_follower: Option<ReplicationFollower>,
     config: Config,
 }
 
@@ -34,31 +54,95 @@ impl Server {
         } else if !storage_directory.is_dir() {
             return Err(ServerOpenError::NotADirectory { path: storage_directory.to_owned() });
         }
-        let database_manager = DatabaseManager::new(storage_directory)
-            .map_err(|err| ServerOpenError::DatabaseOpenError { source: err })?;
+        executor::memory::set_server_memory_limit(config.server.query_memory.server_limit_bytes);
+        let sort_spill = Self::open_sort_spill(&config)?;
+        let database_manager = Arc::new(
+            DatabaseManager::new(storage_directory)
+                .map_err(|err| ServerOpenError::DatabaseOpenError { source: err })?,
+        );
         let system_db = initialise_system_database(&database_manager);
         let user_manager = Arc::new(UserManager::new(system_db));
         initialise_default_user(&user_manager);
-        let typedb_service = TypeDBService::new(&config.server.address, database_manager, user_manager.clone());
+        let replication_follower = config
+            .server
+            .replication
+            .primary
+            .map(|primary| ReplicationFollower::new(database_manager.clone(), primary));
+        let slow_query_log = Self::open_slow_query_log(&config)?.map(Arc::new);
+        let transaction_registry = Arc::new(TransactionRegistry::new());
+        let typedb_service = TypeDBService::new(
+            &config.server.address,
+            database_manager.clone(),
+            user_manager.clone(),
+            slow_query_log,
+            transaction_registry.clone(),
+            config.server.query_memory.query_limit_bytes,
+            Some(sort_spill.clone()),
+            config.server.transaction.max_commit_retry_attempts,
+        );
         Ok(Self {
             data_directory: storage_directory.to_owned(),
+            database_manager,
             user_manager,
+            transaction_registry,
+            sort_spill,
             typedb_service: Some(typedb_service),
+            replication_follower,
             config,
         })
     }
 
     pub fn database_manager(&self) -> &DatabaseManager {
-        self.typedb_service.as_ref().unwrap().database_manager()
+        &self.database_manager
     }
 
     pub async fn serve(mut self) -> Result<(), tonic::transport::Error> {
+        if self.config.server.metrics.enabled {
+            let metrics_address = self.config.server.metrics.address;
+            let metrics_service = MetricsService::new(self.database_manager.clone());
+            tokio::spawn(async move {
+                if let Err(error) = metrics_service.serve(metrics_address).await {
+                    event!(Level::ERROR, ?error, "Metrics endpoint at '{}' stopped", metrics_address);
+                }
+            });
+        }
+        let authenticator = Arc::new(Authenticator::new(self.user_manager.clone()));
+        if self.config.server.http.enabled {
+            let http_address = self.config.server.http.address;
+            let http_service = HttpService::new(
+                self.database_manager.clone(),
+                authenticator.clone(),
+                self.transaction_registry.clone(),
+                self.config.server.query_memory.query_limit_bytes,
+                Some(self.sort_spill.clone()),
+            );
+            tokio::spawn(async move {
+                if let Err(error) = http_service.serve(http_address).await {
+                    event!(Level::ERROR, ?error, "HTTP endpoint at '{}' stopped", http_address);
+                }
+            });
+        }
+        if self.config.server.replication.enabled {
+            let replication_address = self.config.server.replication.address;
+            let replication_server = ReplicationServer::new(self.database_manager.clone());
+            thread::spawn(move || {
+                if let Err(error) = replication_server.serve(replication_address) {
+                    event!(Level::ERROR, ?error, "Replication endpoint at '{}' stopped", replication_address);
+                }
+            });
+        }
+        if let Some(replication_follower) = self.replication_follower.take() {
+            thread::spawn(move || replication_follower.run());
+        }
         let service = typedb_protocol::type_db_server::TypeDbServer::new(self.typedb_service.take().unwrap());
+        let admin_service = AdminService::new(self.transaction_registry.clone());
+        let change_stream_service = ChangeStreamService::new(self.database_manager.clone());
         println!("Ready!");
-        let authenticator = Arc::new(Authenticator::new(self.user_manager.clone()));
         Self::create_tonic_server(&self.config.server.encryption)
             .layer(tonic::service::interceptor(move |req| authenticator.authenticate(req)))
             .add_service(service)
+            .add_service(admin_service)
+            .add_service(change_stream_service)
             .serve(self.config.server.address)
             .await
     }
@@ -78,6 +162,45 @@ impl Server {
         tonic_server.http2_keepalive_interval(Some(GRPC_CONNECTION_KEEPALIVE))
     }
 
+    fn open_slow_query_log(config: &Config) -> Result<Option<SlowQueryLog>, ServerOpenError> {
+        let slow_query_log_config = &config.server.slow_query_log;
+        if !slow_query_log_config.enabled {
+            return Ok(None);
+        }
+        let directory = slow_query_log_config.directory.clone().unwrap_or_else(|| {
+            let data_directory = &config.storage.data;
+            data_directory.parent().unwrap_or(data_directory).join("logs")
+        });
+        SlowQueryLog::open(slow_query_log_config, &directory)
+            .map(Some)
+            .map_err(|source| ServerOpenError::CouldNotOpenSlowQueryLog { path: directory, source })
+    }
+
+    // The server owns the directory sorts spill to, and empties it of the runs left by sorts it did not finish before
+    fn open_sort_spill(config: &Config) -> Result<SortSpillOptions, ServerOpenError> {
+        let query_memory_config = &config.server.query_memory;
+        let directory = query_memory_config
+            .sort_spill_directory
+            .clone()
+            .unwrap_or_else(|| config.storage.data.join(SORT_SPILL_DIRECTORY_NAME));
+        Self::empty_directory(&directory)
+            .map_err(|source| ServerOpenError::CouldNotOpenSortSpillDirectory { path: directory.clone(), source })?;
+        Ok(SortSpillOptions { directory, run_bytes: query_memory_config.sort_spill_bytes })
+    }
+
+    fn empty_directory(directory: &Path) -> io::Result<()> {
+        fs::create_dir_all(directory)?;
+        for entry in fs::read_dir(directory)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                fs::remove_dir_all(entry.path())?;
+            } else {
+                fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+
     fn create_storage_directory(storage_directory: &PathBuf) -> Result<(), ServerOpenError> {
         fs::create_dir_all(storage_directory).map_err(|error| ServerOpenError::CouldNotCreateDataDirectory {
             path: storage_directory.to_owned(),
@@ -91,6 +214,8 @@ impl Server {
 pub enum ServerOpenError {
     NotADirectory { path: PathBuf },
     CouldNotCreateDataDirectory { path: PathBuf, source: io::Error },
+    CouldNotOpenSlowQueryLog { path: PathBuf, source: io::Error },
+    CouldNotOpenSortSpillDirectory { path: PathBuf, source: io::Error },
     DatabaseOpenError { source: DatabaseOpenError },
 }
 
@@ -7,7 +7,11 @@
 use std::{
     borrow::Cow,
     io::{self, Read, Write},
-    sync::{mpsc, Arc},
+    iter,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc,
+    },
 };
 
 use durability::{wal::WAL, DurabilityRecordType, DurabilityService, DurabilityServiceError, RawRecord};
@@ -237,6 +241,87 @@ impl DurabilityClient for WALClient {
     }
 }
 
+/// A durability client that discards every record, for storage that is not meant to outlive the process. Sequence
+/// numbers are still handed out in order, but nothing can be read back.
+#[derive(Debug)]
+pub struct NoDurabilityClient {
+    next_sequence_number: AtomicU64,
+}
+
+impl NoDurabilityClient {
+    pub fn new() -> Self {
+        Self { next_sequence_number: AtomicU64::new(SequenceNumber::MIN.next().number()) }
+    }
+}
+
+impl Default for NoDurabilityClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DurabilityClient for NoDurabilityClient {
+    fn register_record_type<Record: DurabilityRecord>(&mut self) {}
+
+    fn current(&self) -> SequenceNumber {
+        SequenceNumber::from(self.next_sequence_number.load(Ordering::Relaxed))
+    }
+
+    fn previous(&self) -> SequenceNumber {
+        SequenceNumber::from(self.next_sequence_number.load(Ordering::Relaxed) - 1)
+    }
+
+    fn sequenced_write<Record>(&self, _record: &Record) -> Result<SequenceNumber, DurabilityClientError>
+    where
+        Record: SequencedDurabilityRecord,
+    {
+        Ok(SequenceNumber::from(self.next_sequence_number.fetch_add(1, Ordering::Relaxed)))
+    }
+
+    fn unsequenced_write<Record>(&self, _record: &Record) -> Result<(), DurabilityClientError>
+    where
+        Record: UnsequencedDurabilityRecord,
+    {
+        Ok(())
+    }
+
+    fn request_sync(&self) -> mpsc::Receiver<()> {
+        let (sender, receiver) = mpsc::channel();
+        sender.send(()).unwrap();
+        receiver
+    }
+
+    fn iter_from(
+        &self,
+        _sequence_number: SequenceNumber,
+    ) -> Result<impl Iterator<Item = Result<RawRecord<'static>, DurabilityClientError>>, DurabilityClientError> {
+        Ok(iter::empty())
+    }
+
+    fn iter_type_from<Record: DurabilityRecord>(
+        &self,
+        _sequence_number: SequenceNumber,
+    ) -> Result<impl Iterator<Item = Result<(SequenceNumber, Record), DurabilityClientError>>, DurabilityClientError>
+    {
+        Ok(iter::empty())
+    }
+
+    fn find_last_unsequenced_type<Record: UnsequencedDurabilityRecord>(
+        &self,
+    ) -> Result<Option<Record>, DurabilityClientError> {
+        Ok(None)
+    }
+
+    fn delete_durability(self) -> Result<(), DurabilityClientError> {
+        Ok(())
+    }
+
+    fn reset(&mut self) -> Result<(), DurabilityClientError> {
+        self.next_sequence_number.store(SequenceNumber::MIN.next().number(), Ordering::SeqCst);
+        Ok(())
+    }
+}
+
 typedb_error!(
     pub DurabilityClientError(component = "Durability client", prefix = "DUC") {
         SerializeError(1, "Durability client failed to serialise/deserialise durability record", ( source: Arc<bincode::Error> )),
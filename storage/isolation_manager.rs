@@ -19,10 +19,11 @@ use std::{
     },
 };
 
+use bytes::{byte_array::ByteArray, util::HexBytesFormatter};
 use durability::DurabilityRecordType;
 use logger::result::ResultExt;
 use primitive::maybe_owns::MaybeOwns;
-use resource::constants::storage::TIMELINE_WINDOW_SIZE;
+use resource::constants::{snapshot::BUFFER_KEY_INLINE, storage::TIMELINE_WINDOW_SIZE};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -30,6 +31,7 @@ use crate::{
         DurabilityClient, DurabilityClientError, DurabilityRecord, SequencedDurabilityRecord,
         UnsequencedDurabilityRecord,
     },
+    key_value::StorageKeyArray,
     sequence_number::SequenceNumber,
     snapshot::{buffer::OperationsBuffer, lock::LockType, write::Write},
     write_batches::WriteBatches,
@@ -166,10 +168,12 @@ impl IsolationManager {
             commit_record.open_sequence_number.next(),
             stop_sequence_number,
         )? {
-            if let Ok((_, commit_status)) = commit_status_result {
+            if let Ok((predecessor_sequence_number, commit_status)) = commit_status_result {
                 let commit_dependency = match commit_status {
                     CommitStatus::Aborted => CommitDependency::Independent,
-                    CommitStatus::Applied(predecessor_record) => commit_record.compute_dependency(&predecessor_record),
+                    CommitStatus::Applied(predecessor_record) => {
+                        commit_record.compute_dependency(&predecessor_record, predecessor_sequence_number)
+                    }
                     CommitStatus::Pending(_) => {
                         unreachable!("Evicted records cannot be pending")
                     }
@@ -272,18 +276,20 @@ fn resolve_concurrent(
     }
     let commit_dependency = match predecessor_window.get_status(predecessor_sequence_number) {
         CommitStatus::Empty => unreachable!("A concurrent status should never be empty at commit time"),
-        CommitStatus::Pending(predecessor_record) => match commit_record.compute_dependency(&predecessor_record) {
-            CommitDependency::Independent => CommitDependency::Independent,
-            result => {
-                if predecessor_window.await_pending_status_commits(predecessor_sequence_number) {
-                    result
-                } else {
-                    CommitDependency::Independent
+        CommitStatus::Pending(predecessor_record) => {
+            match commit_record.compute_dependency(&predecessor_record, predecessor_sequence_number) {
+                CommitDependency::Independent => CommitDependency::Independent,
+                result => {
+                    if predecessor_window.await_pending_status_commits(predecessor_sequence_number) {
+                        result
+                    } else {
+                        CommitDependency::Independent
+                    }
                 }
             }
-        },
+        }
         CommitStatus::Validated(predecessor_record) | CommitStatus::Applied(predecessor_record) => {
-            commit_record.compute_dependency(&predecessor_record)
+            commit_record.compute_dependency(&predecessor_record, predecessor_sequence_number)
         }
         CommitStatus::Aborted => CommitDependency::Independent,
     };
@@ -321,19 +327,54 @@ enum CommitDependency {
     Conflict(IsolationConflict),
 }
 
+/// A conflict with the concurrent commit `predecessor`, on the `key` both commits touched.
+/// Lock keys are not stored per keyspace, so exclusive locks only carry the raw lock bytes.
 #[derive(Debug, Clone)]
 pub enum IsolationConflict {
-    DeletingRequiredKey,
-    RequireDeletedKey,
-    ExclusiveLock,
+    DeletingRequiredKey { key: StorageKeyArray<BUFFER_KEY_INLINE>, predecessor: SequenceNumber },
+    RequireDeletedKey { key: StorageKeyArray<BUFFER_KEY_INLINE>, predecessor: SequenceNumber },
+    ExclusiveLock { lock: ByteArray<BUFFER_KEY_INLINE>, predecessor: SequenceNumber },
+    ReadSetWritten { key: StorageKeyArray<BUFFER_KEY_INLINE>, predecessor: SequenceNumber },
+}
+
+impl IsolationConflict {
+    /// The sequence number of the concurrent commit that won the conflict
+    pub fn predecessor(&self) -> SequenceNumber {
+        match self {
+            IsolationConflict::DeletingRequiredKey { predecessor, .. }
+            | IsolationConflict::RequireDeletedKey { predecessor, .. }
+            | IsolationConflict::ExclusiveLock { predecessor, .. }
+            | IsolationConflict::ReadSetWritten { predecessor, .. } => *predecessor,
+        }
+    }
 }
 
 impl fmt::Display for IsolationConflict {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            IsolationConflict::DeletingRequiredKey => write!(f, "Transaction data a concurrent commit requires."),
-            IsolationConflict::RequireDeletedKey => write!(f, "Transaction uses data a concurrent commit deletes."),
-            IsolationConflict::ExclusiveLock => write!(f, "Transaction uses a lock held by a concurrent commit."),
+            IsolationConflict::DeletingRequiredKey { key, predecessor } => write!(
+                f,
+                "Transaction deletes data concurrent commit '{predecessor}' requires (keyspace {}, key {}).",
+                key.keyspace_id(),
+                HexBytesFormatter::borrowed(key.bytes())
+            ),
+            IsolationConflict::RequireDeletedKey { key, predecessor } => write!(
+                f,
+                "Transaction uses data concurrent commit '{predecessor}' deletes (keyspace {}, key {}).",
+                key.keyspace_id(),
+                HexBytesFormatter::borrowed(key.bytes())
+            ),
+            IsolationConflict::ExclusiveLock { lock, predecessor } => write!(
+                f,
+                "Transaction uses a lock held by concurrent commit '{predecessor}' (lock {}).",
+                HexBytesFormatter::borrowed(lock)
+            ),
+            IsolationConflict::ReadSetWritten { key, predecessor } => write!(
+                f,
+                "Transaction read data that concurrent commit '{predecessor}' wrote (keyspace {}, key {}).",
+                key.keyspace_id(),
+                HexBytesFormatter::borrowed(key.bytes())
+            ),
         }
     }
 }
@@ -663,10 +704,13 @@ pub struct CommitRecord {
 pub enum CommitType {
     Data,
     Schema,
+    /// Marks data ingested into the keyspaces directly. The record carries no operations, so the data can only be
+    /// recovered from a checkpoint taken after the ingestion, and cannot be replicated from the WAL.
+    BulkLoad,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-pub(crate) struct StatusRecord {
+pub struct StatusRecord {
     pub(crate) commit_record_sequence_number: SequenceNumber,
     pub(crate) was_committed: bool,
 }
@@ -709,7 +753,11 @@ impl CommitRecord {
         bincode::deserialize_from(reader).unwrap_or_log()
     }
 
-    fn compute_dependency(&self, predecessor: &CommitRecord) -> CommitDependency {
+    fn compute_dependency(
+        &self,
+        predecessor: &CommitRecord,
+        predecessor_sequence_number: SequenceNumber,
+    ) -> CommitDependency {
         // TODO: this can be optimised by some kind of bit-wise AND of two bloom filter-like data
         // structures first, since we assume few clashes this should mostly succeed
         // TODO: can be optimised with an intersection of two sorted iterators instead of iterate + gets
@@ -740,7 +788,10 @@ impl CommitRecord {
                 }
                 if matches!(write, Write::Delete) && matches!(predecessor_locks.get(key), Some(LockType::Unmodifiable))
                 {
-                    return CommitDependency::Conflict(IsolationConflict::DeletingRequiredKey);
+                    return CommitDependency::Conflict(IsolationConflict::DeletingRequiredKey {
+                        key: StorageKeyArray::new_raw(write_buffer.keyspace_id, key.clone()),
+                        predecessor: predecessor_sequence_number,
+                    });
                 }
             }
 
@@ -749,7 +800,10 @@ impl CommitRecord {
             for (key, lock) in locks.iter() {
                 if matches!(lock, LockType::Unmodifiable) {
                     if let Some(Write::Delete) = predecessor_writes.get(key) {
-                        return CommitDependency::Conflict(IsolationConflict::RequireDeletedKey);
+                        return CommitDependency::Conflict(IsolationConflict::RequireDeletedKey {
+                            key: StorageKeyArray::new_raw(write_buffer.keyspace_id, key.clone()),
+                            predecessor: predecessor_sequence_number,
+                        });
                     }
                 }
             }
@@ -757,10 +811,21 @@ impl CommitRecord {
 
         for (key, lock) in locks.iter() {
             if matches!(lock, LockType::Exclusive) && matches!(predecessor_locks.get(key), Some(LockType::Exclusive)) {
-                return CommitDependency::Conflict(IsolationConflict::ExclusiveLock);
+                return CommitDependency::Conflict(IsolationConflict::ExclusiveLock {
+                    lock: key.clone(),
+                    predecessor: predecessor_sequence_number,
+                });
             }
         }
 
+        // serializable snapshots additionally may not have read anything the predecessor wrote
+        if let Some(key) = self.operations().reads().and_then(|reads| reads.find_written(predecessor.operations())) {
+            return CommitDependency::Conflict(IsolationConflict::ReadSetWritten {
+                key,
+                predecessor: predecessor_sequence_number,
+            });
+        }
+
         if puts_to_update.is_empty() {
             CommitDependency::Independent
         } else {
@@ -800,11 +865,11 @@ impl StatusRecord {
         StatusRecord { commit_record_sequence_number: sequence_number, was_committed: committed }
     }
 
-    pub(crate) fn was_committed(&self) -> bool {
+    pub fn was_committed(&self) -> bool {
         self.was_committed
     }
 
-    pub(crate) fn commit_record_sequence_number(&self) -> SequenceNumber {
+    pub fn commit_record_sequence_number(&self) -> SequenceNumber {
         self.commit_record_sequence_number
     }
 
@@ -0,0 +1,201 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    ops::Bound,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use lending_iterator::{LendingIterator, Seekable};
+use rocksdb::{WriteBatch, WriteBatchIterator};
+
+use super::{
+    keyspace::{KVStore, KeyspaceCheckpointError, KeyspaceDeleteError, KeyspaceError, KeyspaceId},
+    raw_iterator::RawIterator,
+    IteratorPool,
+};
+
+type OrderedMap = BTreeMap<Box<[u8]>, Box<[u8]>>;
+
+/// A keyspace held in an ordered map in memory, which is lost when dropped.
+/// Iterators share the map as it was when they were created, so a write only copies the map while one is open.
+pub(crate) struct InMemoryStore {
+    name: &'static str,
+    map: RwLock<Arc<OrderedMap>>,
+}
+
+impl InMemoryStore {
+    pub(super) fn new(name: &'static str) -> Self {
+        Self { name, map: RwLock::new(Arc::new(BTreeMap::new())) }
+    }
+}
+
+impl KVStore for InMemoryStore {
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<(), KeyspaceError> {
+        Arc::make_mut(&mut self.map.write().unwrap()).insert(key.into(), value.into());
+        Ok(())
+    }
+
+    fn get_mapped(&self, key: &[u8], mapper: &mut dyn FnMut(&[u8])) -> Result<(), KeyspaceError> {
+        if let Some(value) = self.map.read().unwrap().get(key) {
+            mapper(value);
+        }
+        Ok(())
+    }
+
+    fn get_prev_mapped(&self, key: &[u8], mapper: &mut dyn FnMut(&[u8], &[u8])) {
+        let map = self.map.read().unwrap();
+        let mut range = map.range::<[u8], _>((Bound::Unbounded, Bound::Included(key)));
+        if let Some((key, value)) = range.next_back() {
+            mapper(key, value);
+        }
+    }
+
+    fn write(&self, write_batch: WriteBatch) -> Result<(), KeyspaceError> {
+        write_batch.iterate(&mut BatchApplier { map: Arc::make_mut(&mut self.map.write().unwrap()) });
+        Ok(())
+    }
+
+    /// Sorted writes cost the same as any others in memory, so they are simply inserted.
+    fn ingest<'a>(
+        &self,
+        _file: Option<&Path>,
+        sorted: &mut dyn Iterator<Item = (&'a [u8], &'a [u8])>,
+    ) -> Result<(), KeyspaceError> {
+        let mut guard = self.map.write().unwrap();
+        let map = Arc::make_mut(&mut guard);
+        sorted.for_each(|(key, value)| {
+            map.insert(key.into(), value.into());
+        });
+        Ok(())
+    }
+
+    /// Shares the current map with the iterator, taking the lock just once and copying no entries
+    fn iterate_from(
+        &self,
+        _keyspace_id: KeyspaceId,
+        _iterpool: &IteratorPool,
+        start: &[u8],
+        end: Bound<&[u8]>,
+    ) -> RawIterator {
+        let map = self.map.read().unwrap().clone();
+        let end = match end {
+            Bound::Included(end) => Bound::Included(end.into()),
+            Bound::Excluded(end) => Bound::Excluded(end.into()),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        RawIterator::InMemory(InMemoryIterator::new(map, start.into(), end))
+    }
+
+    fn property_int_value(&self, _property: &str) -> Result<Option<u64>, KeyspaceError> {
+        Ok(None)
+    }
+
+    fn checkpoint(&self, _checkpoint_dir: &Path) -> Result<(), KeyspaceCheckpointError> {
+        Err(KeyspaceCheckpointError::InMemory { name: self.name })
+    }
+
+    fn path(&self) -> Option<&Path> {
+        None
+    }
+
+    fn delete(self: Box<Self>) -> Result<(), KeyspaceDeleteError> {
+        Ok(())
+    }
+
+    fn reset(&mut self) -> Result<(), KeyspaceError> {
+        *self.map.write().unwrap() = Arc::new(BTreeMap::new());
+        Ok(())
+    }
+}
+
+struct BatchApplier<'a> {
+    map: &'a mut OrderedMap,
+}
+
+impl WriteBatchIterator for BatchApplier<'_> {
+    fn put(&mut self, key: Box<[u8]>, value: Box<[u8]>) {
+        self.map.insert(key, value);
+    }
+
+    fn delete(&mut self, key: Box<[u8]>) {
+        self.map.remove(&key);
+    }
+}
+
+/// Iterates the map as it was when the iterator was created, so it holds no lock and sees none of the writes made
+/// after it was created. Each step re-seeks the map from the key of the current entry.
+pub(super) struct InMemoryIterator {
+    map: Arc<OrderedMap>,
+    start: Box<[u8]>,
+    end: Bound<Box<[u8]>>,
+    position: Option<Box<[u8]>>,
+    // NOTE: when `yielded` is set, the entry at `position` has been returned to the user and must be advanced past
+    yielded: bool,
+}
+
+impl InMemoryIterator {
+    fn new(map: Arc<OrderedMap>, start: Box<[u8]>, end: Bound<Box<[u8]>>) -> Self {
+        let mut iterator = Self { map, start, end, position: None, yielded: false };
+        iterator.position = iterator.first_from(Bound::Included(&*iterator.start));
+        iterator
+    }
+
+    fn first_from(&self, lower: Bound<&[u8]>) -> Option<Box<[u8]>> {
+        let (key, _) = self.map.range::<[u8], _>((lower, Bound::Unbounded)).next()?;
+        let in_range = match &self.end {
+            Bound::Included(end) => key <= end,
+            Bound::Excluded(end) => key < end,
+            Bound::Unbounded => true,
+        };
+        in_range.then(|| key.clone())
+    }
+
+    fn advance(&mut self) {
+        self.yielded = false;
+        self.position = match self.position.take() {
+            Some(current) => self.first_from(Bound::Excluded(&*current)),
+            None => None,
+        };
+    }
+
+    pub(super) fn peek_key(&mut self) -> Option<&[u8]> {
+        if self.yielded {
+            self.advance();
+        }
+        self.position.as_deref()
+    }
+}
+
+impl LendingIterator for InMemoryIterator {
+    type Item<'a>
+        = (&'a [u8], &'a [u8])
+    where
+        Self: 'a;
+
+    fn next(&mut self) -> Option<Self::Item<'_>> {
+        if self.yielded {
+            self.advance();
+        }
+        self.yielded = true;
+        let (key, value) = self.map.get_key_value(self.position.as_deref()?)?;
+        Some((&**key, &**value))
+    }
+}
+
+impl Seekable<[u8]> for InMemoryIterator {
+    fn seek(&mut self, key: &[u8]) {
+        self.yielded = false;
+        self.position = self.first_from(Bound::Included(key.max(&*self.start)));
+    }
+
+    fn compare_key(&self, item: &Self::Item<'_>, key: &[u8]) -> Ordering {
+        item.0.cmp(key)
+    }
+}
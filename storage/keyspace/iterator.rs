@@ -4,18 +4,18 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use std::cmp::Ordering;
+use std::{cmp::Ordering, ops::Bound};
 
 use bytes::{byte_array::ByteArray, Bytes};
 use lending_iterator::{LendingIterator, Seekable};
 
 use crate::{
     key_range::{KeyRange, RangeEnd, RangeStart},
-    keyspace::{raw_iterator, raw_iterator::DBIterator, IteratorPool, Keyspace, KeyspaceError},
+    keyspace::{raw_iterator, raw_iterator::RawIterator, IteratorPool, Keyspace, KeyspaceError},
 };
 
 pub struct KeyspaceRangeIterator {
-    iterator: DBIterator,
+    iterator: RawIterator,
     continue_condition: ContinueCondition,
     keyspace_name: &'static str,
 }
@@ -27,6 +27,23 @@ enum ContinueCondition {
     Always,
 }
 
+impl ContinueCondition {
+    /// The first key past the range, if any key is
+    fn end_bound(&self) -> Bound<ByteArray<48>> {
+        match self {
+            Self::ExactPrefix(prefix) | Self::EndPrefixInclusive(prefix) => {
+                let mut end = prefix.clone();
+                match end.increment() {
+                    Ok(()) => Bound::Excluded(end),
+                    Err(_) => Bound::Unbounded,
+                }
+            }
+            Self::EndPrefixExclusive(end) => Bound::Excluded(end.clone()),
+            Self::Always => Bound::Unbounded,
+        }
+    }
+}
+
 impl KeyspaceRangeIterator {
     pub(crate) fn new<'a, const INLINE_BYTES: usize>(
         keyspace: &'a Keyspace,
@@ -46,11 +63,6 @@ impl KeyspaceRangeIterator {
             }
         };
 
-        let mut iterator = raw_iterator::DBIterator::new_from(iterpool.get_iterator(keyspace), start_prefix.as_ref());
-        if matches!(range.start(), RangeStart::ExcludeFirstWithPrefix(_)) {
-            Self::may_skip_start(&mut iterator, range.start().get_value());
-        }
-
         let continue_condition = match range.end() {
             RangeEnd::WithinStartAsPrefix => {
                 ContinueCondition::ExactPrefix(ByteArray::from(&**range.start().get_value()))
@@ -59,13 +71,17 @@ impl KeyspaceRangeIterator {
             RangeEnd::EndPrefixExclusive(end) => ContinueCondition::EndPrefixExclusive(ByteArray::from(&**end)),
             RangeEnd::Unbounded => ContinueCondition::Always,
         };
+
+        let end = continue_condition.end_bound();
+        let mut iterator = keyspace.raw_iterator_from(iterpool, start_prefix.as_ref(), end.as_ref().map(|end| &**end));
+        if matches!(range.start(), RangeStart::ExcludeFirstWithPrefix(_)) {
+            Self::may_skip_start(&mut iterator, range.start().get_value());
+        }
         KeyspaceRangeIterator { iterator, continue_condition, keyspace_name: keyspace.name() }
     }
 
-    fn may_skip_start(iterator: &mut DBIterator, excluded_value: &[u8]) {
-        if iterator.peek().is_some_and(|result| result.as_ref().is_ok_and(|(key, _)| *key == excluded_value)) {
-            iterator.next();
-        }
+    fn may_skip_start(iterator: &mut RawIterator, excluded_value: &[u8]) {
+        iterator.skip_key(excluded_value);
     }
 
     fn accept_value(condition: &ContinueCondition, value: &<Self as LendingIterator>::Item<'_>) -> bool {
@@ -7,6 +7,7 @@
 use std::{
     error::Error,
     fmt, fs, io,
+    ops::Bound,
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -14,11 +15,14 @@ use std::{
 use bytes::{util::MB, Bytes};
 use itertools::Itertools;
 use resource::constants::storage::ROCKSDB_CACHE_SIZE_MB;
-use rocksdb::{checkpoint::Checkpoint, IteratorMode, Options, ReadOptions, WriteBatch, WriteOptions, DB};
+use rocksdb::{Options, WriteBatch};
 use serde::{Deserialize, Serialize};
 
-use super::{iterator, IteratorPool};
-use crate::{key_range::KeyRange, write_batches::WriteBatches};
+use super::{in_memory::InMemoryStore, iterator, raw_iterator::RawIterator, rocks::RocksDBStore, IteratorPool};
+use crate::{
+    key_range::KeyRange,
+    write_batches::{SortedWrites, WriteBatches},
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct KeyspaceId(pub u8);
@@ -62,12 +66,23 @@ impl Keyspaces {
         let path = storage_dir.as_ref();
 
         let cache = rocksdb::Cache::new_lru_cache((ROCKSDB_CACHE_SIZE_MB * MB) as usize);
+        Self::open_each::<KS>(|keyspace| Keyspace::open(path, keyspace, &keyspace.rocks_configuration(&cache)))
+    }
+
+    /// Open keyspaces that only live in memory, and are lost when dropped
+    pub(crate) fn open_in_memory<KS: KeyspaceSet>() -> Result<Self, KeyspaceOpenError> {
+        Self::open_each::<KS>(|keyspace| Ok(Keyspace::open_in_memory(keyspace)))
+    }
+
+    fn open_each<KS: KeyspaceSet>(
+        mut open_keyspace: impl FnMut(KS) -> Result<Keyspace, KeyspaceOpenError>,
+    ) -> Result<Self, KeyspaceOpenError> {
         let mut keyspaces = Keyspaces::new();
         for keyspace in KS::iter() {
             keyspaces
                 .validate_new_keyspace(keyspace)
                 .map_err(|error| KeyspaceOpenError::Validation { source: error })?;
-            keyspaces.keyspaces.push(Keyspace::open(path, keyspace, &keyspace.rocks_configuration(&cache))?);
+            keyspaces.keyspaces.push(open_keyspace(keyspace)?);
             keyspaces.index[keyspace.id().0 as usize] = Some(KeyspaceId(keyspaces.keyspaces.len() as u8 - 1));
         }
         Ok(keyspaces)
@@ -100,6 +115,10 @@ impl Keyspaces {
         Ok(())
     }
 
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Keyspace> {
+        self.keyspaces.iter()
+    }
+
     pub(crate) fn get(&self, keyspace_id: KeyspaceId) -> &Keyspace {
         let keyspace_index = self.index[keyspace_id.0 as usize].unwrap();
         &self.keyspaces[keyspace_index.0 as usize]
@@ -113,6 +132,37 @@ impl Keyspaces {
         Ok(())
     }
 
+    /// Ingest each keyspace's writes as one sorted file in `ingest_dir`, or directly if there is none. If any
+    /// keyspace fails, the keyspaces already ingested are cleared of these writes again, so a failed ingestion never
+    /// becomes partially visible.
+    pub(crate) fn ingest(
+        &self,
+        sorted_writes: &SortedWrites<'_>,
+        ingest_dir: Option<&Path>,
+    ) -> Result<(), KeyspaceError> {
+        let mut ingested = Vec::new();
+        for (index, writes) in sorted_writes.iter() {
+            debug_assert!(index < KEYSPACE_MAXIMUM_COUNT);
+            let keyspace = self.get(KeyspaceId(index as u8));
+            let file = ingest_dir.map(|dir| dir.join(format!("{}.sst", keyspace.name())));
+            match keyspace.ingest(file.as_deref(), writes.iter().map(|(key, value)| (key.bytes(), *value))) {
+                Ok(()) => ingested.push((keyspace, writes)),
+                Err(error) => {
+                    if let Some(file) = &file {
+                        let _ = fs::remove_file(file);
+                    }
+                    for (keyspace, writes) in ingested {
+                        let mut write_batch = WriteBatch::default();
+                        writes.iter().for_each(|(key, _)| write_batch.delete(key.bytes()));
+                        keyspace.write(write_batch)?;
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn checkpoint(&self, current_checkpoint_dir: &Path) -> Result<(), KeyspaceCheckpointError> {
         for keyspace in &self.keyspaces {
             keyspace.checkpoint(current_checkpoint_dir)?;
@@ -163,14 +213,52 @@ impl fmt::Display for KeyspaceValidationError {
 
 impl Error for KeyspaceValidationError {}
 
+/// A non-durable ordered key-value store backing a keyspace
+pub(super) trait KVStore: Send + Sync {
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<(), KeyspaceError>;
+
+    /// Pass the value stored under `key`, if there is one, to `mapper`
+    fn get_mapped(&self, key: &[u8], mapper: &mut dyn FnMut(&[u8])) -> Result<(), KeyspaceError>;
+
+    /// Pass the last entry at or before `key`, if there is one, to `mapper`
+    fn get_prev_mapped(&self, key: &[u8], mapper: &mut dyn FnMut(&[u8], &[u8]));
+
+    fn write(&self, write_batch: WriteBatch) -> Result<(), KeyspaceError>;
+
+    /// Add the key-value pairs, which must be in strictly increasing key order, bypassing the regular write path
+    /// where the store supports it. A store may stage them in `file` to do so.
+    fn ingest<'a>(
+        &self,
+        file: Option<&Path>,
+        sorted: &mut dyn Iterator<Item = (&'a [u8], &'a [u8])>,
+    ) -> Result<(), KeyspaceError>;
+
+    /// Iterate from the first key at or after `start`. Keys at or past `end` may be left out.
+    fn iterate_from(
+        &self,
+        keyspace_id: KeyspaceId,
+        iterpool: &IteratorPool,
+        start: &[u8],
+        end: Bound<&[u8]>,
+    ) -> RawIterator;
+
+    fn property_int_value(&self, property: &str) -> Result<Option<u64>, KeyspaceError>;
+
+    fn checkpoint(&self, checkpoint_dir: &Path) -> Result<(), KeyspaceCheckpointError>;
+
+    /// The directory the store keeps its files in, unless it is in memory
+    fn path(&self) -> Option<&Path>;
+
+    fn delete(self: Box<Self>) -> Result<(), KeyspaceDeleteError>;
+
+    fn reset(&mut self) -> Result<(), KeyspaceError>;
+}
+
 /// A non-durable key-value store that supports put, get, delete, iterate and checkpointing.
 pub(crate) struct Keyspace {
-    path: PathBuf,
     name: &'static str,
     id: KeyspaceId,
-    pub(super) kv_storage: DB,
-    read_options: ReadOptions,
-    write_options: WriteOptions,
+    store: Box<dyn KVStore>,
 }
 
 impl Keyspace {
@@ -179,25 +267,13 @@ impl Keyspace {
         keyspace: impl KeyspaceSet,
         options: &Options,
     ) -> Result<Keyspace, KeyspaceOpenError> {
-        use KeyspaceOpenError::SpeeDB;
-        let name = keyspace.name();
-        let path = storage_path.join(name);
-        let kv_storage = DB::open(options, &path).map_err(|error| SpeeDB { name, source: error })?;
-        Ok(Self::new(path, keyspace, kv_storage))
+        let store = RocksDBStore::open(storage_path, keyspace.name(), options)?;
+        Ok(Self { name: keyspace.name(), id: keyspace.id(), store: Box::new(store) })
     }
 
-    fn new(path: PathBuf, keyspace: impl KeyspaceSet, kv_storage: DB) -> Self {
-        // initial read options, should be customised to this storage's properties
-        let read_options = ReadOptions::default();
-        let mut write_options = WriteOptions::default();
-        write_options.disable_wal(true);
-        Self { path, name: keyspace.name(), id: keyspace.id(), kv_storage, read_options, write_options }
-    }
-
-    pub(super) fn new_read_options(&self) -> ReadOptions {
-        let mut options = ReadOptions::default();
-        options.set_total_order_seek(false);
-        options
+    pub(crate) fn open_in_memory(keyspace: impl KeyspaceSet) -> Keyspace {
+        let store = InMemoryStore::new(keyspace.name());
+        Self { name: keyspace.name(), id: keyspace.id(), store: Box::new(store) }
     }
 
     pub(crate) fn id(&self) -> KeyspaceId {
@@ -209,28 +285,25 @@ impl Keyspace {
     }
 
     pub(crate) fn put(&self, key: &[u8], value: &[u8]) -> Result<(), KeyspaceError> {
-        self.kv_storage
-            .put_opt(key, value, &self.write_options)
-            .map_err(|error| KeyspaceError::Put { name: self.name, source: error })
+        self.store.put(key, value)
     }
 
     pub(crate) fn get<M, V>(&self, key: &[u8], mut mapper: M) -> Result<Option<V>, KeyspaceError>
     where
         M: FnMut(&[u8]) -> V,
     {
-        self.kv_storage
-            .get_pinned_opt(key, &self.read_options)
-            .map(|option| option.map(|value| mapper(value.as_ref())))
-            .map_err(|error| KeyspaceError::Get { name: self.name, source: error })
+        let mut mapped = None;
+        self.store.get_mapped(key, &mut |value| mapped = Some(mapper(value)))?;
+        Ok(mapped)
     }
 
     pub(crate) fn get_prev<M, T>(&self, key: &[u8], mut mapper: M) -> Option<T>
     where
         M: FnMut(&[u8], &[u8]) -> T,
     {
-        let mut iterator = self.kv_storage.raw_iterator_opt(self.new_read_options());
-        iterator.seek_for_prev(key);
-        iterator.item().map(|(k, v)| mapper(k, v))
+        let mut mapped = None;
+        self.store.get_prev_mapped(key, &mut |key, value| mapped = Some(mapper(key, value)));
+        mapped
     }
 
     // TODO: we should benchmark using iterator pools, which would require changing prefix/range on read options
@@ -242,47 +315,45 @@ impl Keyspace {
         iterator::KeyspaceRangeIterator::new(self, iterpool, range)
     }
 
-    pub(crate) fn write(&self, write_batch: WriteBatch) -> Result<(), KeyspaceError> {
-        self.kv_storage
-            .write_opt(write_batch, &self.write_options)
-            .map_err(|error| KeyspaceError::BatchWrite { name: self.name, source: error })
+    pub(super) fn raw_iterator_from(&self, iterpool: &IteratorPool, start: &[u8], end: Bound<&[u8]>) -> RawIterator {
+        self.store.iterate_from(self.id, iterpool, start, end)
     }
 
-    pub(crate) fn checkpoint(&self, checkpoint_dir: &Path) -> Result<(), KeyspaceCheckpointError> {
-        use KeyspaceCheckpointError::{CheckpointExists, CreateSpeeDBCheckpoint};
+    pub(crate) fn write(&self, write_batch: WriteBatch) -> Result<(), KeyspaceError> {
+        self.store.write(write_batch)
+    }
 
-        let checkpoint_dir = checkpoint_dir.join(self.name);
-        if checkpoint_dir.exists() {
-            return Err(CheckpointExists { name: self.name, dir: checkpoint_dir });
-        }
+    pub(crate) fn ingest<'a>(
+        &self,
+        file: Option<&Path>,
+        sorted: impl IntoIterator<Item = (&'a [u8], &'a [u8])>,
+    ) -> Result<(), KeyspaceError> {
+        self.store.ingest(file, &mut sorted.into_iter())
+    }
 
-        Checkpoint::new(&self.kv_storage)
-            .and_then(|checkpoint| checkpoint.create_checkpoint(&checkpoint_dir))
-            .map_err(|error| CreateSpeeDBCheckpoint { name: self.name, source: error })?;
+    pub(crate) fn property_int_value(&self, property: &str) -> Result<Option<u64>, KeyspaceError> {
+        self.store.property_int_value(property)
+    }
 
-        Ok(())
+    pub(crate) fn checkpoint(&self, checkpoint_dir: &Path) -> Result<(), KeyspaceCheckpointError> {
+        self.store.checkpoint(checkpoint_dir)
     }
 
     pub(crate) fn delete(self) -> Result<(), KeyspaceDeleteError> {
-        drop(self.kv_storage);
-        fs::remove_dir_all(self.path.clone())
-            .map_err(|error| KeyspaceDeleteError::DirectoryRemove { name: self.name, source: Arc::new(error) })?;
-        Ok(())
+        self.store.delete()
     }
 
     pub(crate) fn reset(&mut self) -> Result<(), KeyspaceError> {
-        let iterator = self.kv_storage.iterator(IteratorMode::Start);
-        for entry in iterator {
-            let (key, _) = entry.map_err(|err| KeyspaceError::Iterate { name: self.name, source: err })?;
-            self.kv_storage.delete(key).map_err(|err| KeyspaceError::Iterate { name: self.name, source: err })?;
-        }
-        Ok(())
+        self.store.reset()
     }
 }
 
 impl fmt::Debug for Keyspace {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Keyspace[name={}, path={:?}, id={}]", self.name, self.path, self.id)
+        match self.store.path() {
+            Some(path) => write!(f, "Keyspace[name={}, path={:?}, id={}]", self.name, path, self.id),
+            None => write!(f, "Keyspace[name={}, in-memory, id={}]", self.name, self.id),
+        }
     }
 }
 
@@ -311,6 +382,7 @@ impl Error for KeyspaceOpenError {
 pub enum KeyspaceCheckpointError {
     CheckpointExists { name: &'static str, dir: PathBuf },
     CreateSpeeDBCheckpoint { name: &'static str, source: rocksdb::Error },
+    InMemory { name: &'static str },
 }
 
 impl fmt::Display for KeyspaceCheckpointError {
@@ -324,6 +396,7 @@ impl Error for KeyspaceCheckpointError {
         match self {
             Self::CheckpointExists { .. } => None,
             Self::CreateSpeeDBCheckpoint { source, .. } => Some(source),
+            Self::InMemory { .. } => None,
         }
     }
 }
@@ -354,6 +427,8 @@ pub enum KeyspaceError {
     BatchWrite { name: &'static str, source: rocksdb::Error },
     Iterate { name: &'static str, source: rocksdb::Error },
     DeleteRange { name: &'static str, source: rocksdb::Error },
+    Property { name: &'static str, source: rocksdb::Error },
+    Ingest { name: &'static str, source: rocksdb::Error },
 }
 
 impl fmt::Display for KeyspaceError {
@@ -370,6 +445,8 @@ impl Error for KeyspaceError {
             Self::BatchWrite { source, .. } => Some(source),
             Self::Iterate { source, .. } => Some(source),
             KeyspaceError::DeleteRange { source, .. } => Some(source),
+            Self::Property { source, .. } => Some(source),
+            Self::Ingest { source, .. } => Some(source),
         }
     }
 }
@@ -8,11 +8,14 @@ pub(crate) use keyspace::{Keyspace, KeyspaceCheckpointError, KeyspaceError, Keys
 pub use keyspace::{KeyspaceDeleteError, KeyspaceId, KeyspaceOpenError, KeyspaceSet, KeyspaceValidationError};
 use rocksdb::{DBRawIterator, DB};
 
+use self::rocks::RocksDBStore;
 use crate::snapshot::pool::{PoolRecycleGuard, Poolable, SinglePool};
 
+mod in_memory;
 pub mod iterator;
 mod keyspace;
 mod raw_iterator;
+mod rocks;
 
 impl Poolable for DBRawIterator<'static> {}
 
@@ -26,10 +29,10 @@ impl IteratorPool {
         Self { pools_per_keyspace }
     }
 
-    fn get_iterator(&self, keyspace: &Keyspace) -> PoolRecycleGuard<DBRawIterator<'static>> {
-        self.pools_per_keyspace[keyspace.id().0 as usize].get_or_create(|| {
-            let kv_storage: &'static DB = unsafe { std::mem::transmute(&keyspace.kv_storage) };
-            kv_storage.raw_iterator_opt(keyspace.new_read_options()) // It is safe to read later RocksDB snapshots since our MVCC will
+    fn get_iterator(&self, keyspace_id: KeyspaceId, store: &RocksDBStore) -> PoolRecycleGuard<DBRawIterator<'static>> {
+        self.pools_per_keyspace[keyspace_id.0 as usize].get_or_create(|| {
+            let kv_storage: &'static DB = unsafe { std::mem::transmute(&store.kv_storage) };
+            kv_storage.raw_iterator_opt(store.new_read_options()) // It is safe to read later RocksDB snapshots since our MVCC will
         })
     }
 }
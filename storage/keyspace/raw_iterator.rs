@@ -9,6 +9,7 @@ use std::{cmp::Ordering, mem::transmute};
 use lending_iterator::{LendingIterator, Seekable};
 use rocksdb::DBRawIterator;
 
+use super::in_memory::InMemoryIterator;
 use crate::snapshot::pool::PoolRecycleGuard;
 
 type KeyValue<'a> = Result<(&'a [u8], &'a [u8]), rocksdb::Error>;
@@ -86,6 +87,54 @@ impl Seekable<[u8]> for DBIterator {
     }
 }
 
+/// An iterator over the raw keys of a keyspace, whichever store backs it
+pub(super) enum RawIterator {
+    RocksDB(DBIterator),
+    InMemory(InMemoryIterator),
+}
+
+impl RawIterator {
+    /// Skip the next item if it has exactly this key
+    pub(super) fn skip_key(&mut self, key: &[u8]) {
+        let at_key = match self {
+            Self::RocksDB(iterator) => {
+                iterator.peek().is_some_and(|result| result.as_ref().is_ok_and(|(peeked, _)| *peeked == key))
+            }
+            Self::InMemory(iterator) => iterator.peek_key() == Some(key),
+        };
+        if at_key {
+            self.next();
+        }
+    }
+}
+
+impl LendingIterator for RawIterator {
+    type Item<'a>
+        = Result<(&'a [u8], &'a [u8]), rocksdb::Error>
+    where
+        Self: 'a;
+
+    fn next(&mut self) -> Option<Self::Item<'_>> {
+        match self {
+            Self::RocksDB(iterator) => iterator.next(),
+            Self::InMemory(iterator) => iterator.next().map(Ok),
+        }
+    }
+}
+
+impl Seekable<[u8]> for RawIterator {
+    fn seek(&mut self, key: &[u8]) {
+        match self {
+            Self::RocksDB(iterator) => iterator.seek(key),
+            Self::InMemory(iterator) => iterator.seek(key),
+        }
+    }
+
+    fn compare_key(&self, item: &Self::Item<'_>, key: &[u8]) -> Ordering {
+        compare_key(item, key)
+    }
+}
+
 pub(super) fn compare_key<E>(item: &Result<(&[u8], &[u8]), E>, key: &[u8]) -> Ordering {
     if let Ok(item) = item {
         let (peek, _) = item;
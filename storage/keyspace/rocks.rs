@@ -0,0 +1,167 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    fs,
+    ops::Bound,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rocksdb::{
+    checkpoint::Checkpoint, IngestExternalFileOptions, IteratorMode, Options, ReadOptions, SstFileWriter, WriteBatch,
+    WriteOptions, DB,
+};
+
+use super::{
+    keyspace::{KVStore, KeyspaceCheckpointError, KeyspaceDeleteError, KeyspaceError, KeyspaceId, KeyspaceOpenError},
+    raw_iterator::{DBIterator, RawIterator},
+    IteratorPool,
+};
+
+/// A keyspace stored on disk in its own RocksDB instance
+pub(crate) struct RocksDBStore {
+    path: PathBuf,
+    name: &'static str,
+    pub(super) kv_storage: DB,
+    options: Options,
+    read_options: ReadOptions,
+    write_options: WriteOptions,
+}
+
+impl RocksDBStore {
+    pub(super) fn open(storage_path: &Path, name: &'static str, options: &Options) -> Result<Self, KeyspaceOpenError> {
+        use KeyspaceOpenError::SpeeDB;
+        let path = storage_path.join(name);
+        let kv_storage = DB::open(options, &path).map_err(|error| SpeeDB { name, source: error })?;
+        Ok(Self::new(path, name, kv_storage, options.clone()))
+    }
+
+    fn new(path: PathBuf, name: &'static str, kv_storage: DB, options: Options) -> Self {
+        // initial read options, should be customised to this storage's properties
+        let read_options = ReadOptions::default();
+        let mut write_options = WriteOptions::default();
+        write_options.disable_wal(true);
+        Self { path, name, kv_storage, options, read_options, write_options }
+    }
+
+    pub(super) fn new_read_options(&self) -> ReadOptions {
+        let mut options = ReadOptions::default();
+        options.set_total_order_seek(false);
+        options
+    }
+}
+
+impl KVStore for RocksDBStore {
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<(), KeyspaceError> {
+        self.kv_storage
+            .put_opt(key, value, &self.write_options)
+            .map_err(|error| KeyspaceError::Put { name: self.name, source: error })
+    }
+
+    fn get_mapped(&self, key: &[u8], mapper: &mut dyn FnMut(&[u8])) -> Result<(), KeyspaceError> {
+        let value = self
+            .kv_storage
+            .get_pinned_opt(key, &self.read_options)
+            .map_err(|error| KeyspaceError::Get { name: self.name, source: error })?;
+        if let Some(value) = value {
+            mapper(value.as_ref());
+        }
+        Ok(())
+    }
+
+    fn get_prev_mapped(&self, key: &[u8], mapper: &mut dyn FnMut(&[u8], &[u8])) {
+        let mut iterator = self.kv_storage.raw_iterator_opt(self.new_read_options());
+        iterator.seek_for_prev(key);
+        if let Some((key, value)) = iterator.item() {
+            mapper(key, value);
+        }
+    }
+
+    fn write(&self, write_batch: WriteBatch) -> Result<(), KeyspaceError> {
+        self.kv_storage
+            .write_opt(write_batch, &self.write_options)
+            .map_err(|error| KeyspaceError::BatchWrite { name: self.name, source: error })
+    }
+
+    /// Write the key-value pairs to a new sorted file and move it into this keyspace. This bypasses the memtable
+    /// entirely. Without a file to stage them in, they are written as one batch instead.
+    fn ingest<'a>(
+        &self,
+        file: Option<&Path>,
+        sorted: &mut dyn Iterator<Item = (&'a [u8], &'a [u8])>,
+    ) -> Result<(), KeyspaceError> {
+        let Some(file) = file else {
+            let mut write_batch = WriteBatch::default();
+            sorted.for_each(|(key, value)| write_batch.put(key, value));
+            return self.write(write_batch);
+        };
+        let to_error = |error| KeyspaceError::Ingest { name: self.name, source: error };
+        let mut writer = SstFileWriter::create(&self.options);
+        writer.open(file).map_err(to_error)?;
+        for (key, value) in sorted {
+            writer.put(key, value).map_err(to_error)?;
+        }
+        writer.finish().map_err(to_error)?;
+
+        let mut ingest_options = IngestExternalFileOptions::default();
+        ingest_options.set_move_files(true);
+        self.kv_storage.ingest_external_file_opts(&ingest_options, vec![file]).map_err(to_error)
+    }
+
+    /// Iterators are pooled per keyspace and see every later write, so the end of the range is not used
+    fn iterate_from(
+        &self,
+        keyspace_id: KeyspaceId,
+        iterpool: &IteratorPool,
+        start: &[u8],
+        _end: Bound<&[u8]>,
+    ) -> RawIterator {
+        RawIterator::RocksDB(DBIterator::new_from(iterpool.get_iterator(keyspace_id, self), start))
+    }
+
+    fn property_int_value(&self, property: &str) -> Result<Option<u64>, KeyspaceError> {
+        self.kv_storage
+            .property_int_value(property)
+            .map_err(|error| KeyspaceError::Property { name: self.name, source: error })
+    }
+
+    fn checkpoint(&self, checkpoint_dir: &Path) -> Result<(), KeyspaceCheckpointError> {
+        use KeyspaceCheckpointError::{CheckpointExists, CreateSpeeDBCheckpoint};
+
+        let checkpoint_dir = checkpoint_dir.join(self.name);
+        if checkpoint_dir.exists() {
+            return Err(CheckpointExists { name: self.name, dir: checkpoint_dir });
+        }
+
+        Checkpoint::new(&self.kv_storage)
+            .and_then(|checkpoint| checkpoint.create_checkpoint(&checkpoint_dir))
+            .map_err(|error| CreateSpeeDBCheckpoint { name: self.name, source: error })?;
+
+        Ok(())
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+
+    fn delete(self: Box<Self>) -> Result<(), KeyspaceDeleteError> {
+        let Self { path, name, kv_storage, .. } = *self;
+        drop(kv_storage);
+        fs::remove_dir_all(path)
+            .map_err(|error| KeyspaceDeleteError::DirectoryRemove { name, source: Arc::new(error) })?;
+        Ok(())
+    }
+
+    fn reset(&mut self) -> Result<(), KeyspaceError> {
+        let iterator = self.kv_storage.iterator(IteratorMode::Start);
+        for entry in iterator {
+            let (key, _) = entry.map_err(|err| KeyspaceError::Iterate { name: self.name, source: err })?;
+            self.kv_storage.delete(key).map_err(|err| KeyspaceError::Iterate { name: self.name, source: err })?;
+        }
+        Ok(())
+    }
+}
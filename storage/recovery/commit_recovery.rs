@@ -12,7 +12,7 @@ use tracing::{event, Level};
 
 use crate::{
     durability_client::{DurabilityClient, DurabilityClientError, DurabilityRecord},
-    isolation_manager::{CommitRecord, IsolationManager, StatusRecord, ValidatedCommit},
+    isolation_manager::{CommitRecord, CommitType, IsolationManager, StatusRecord, ValidatedCommit},
     keyspace::{KeyspaceError, Keyspaces},
     sequence_number::SequenceNumber,
     write_batches::WriteBatches,
@@ -23,10 +23,22 @@ use crate::{
 pub fn load_commit_data_from(
     start: SequenceNumber,
     durability_client: &impl DurabilityClient,
+) -> Result<BTreeMap<SequenceNumber, RecoveryCommitStatus>, StorageRecoveryError> {
+    load_commit_data_between(start, SequenceNumber::MAX, durability_client)
+}
+
+/// Load commit data from the start up to and including the end.
+/// Stops reading once past the end and every loaded commit has its status, so a bounded window does not read the WAL tail.
+/// Commits whose status has not been written by the end of the WAL are returned as pending.
+pub fn load_commit_data_between(
+    start: SequenceNumber,
+    end: SequenceNumber,
+    durability_client: &impl DurabilityClient,
 ) -> Result<BTreeMap<SequenceNumber, RecoveryCommitStatus>, StorageRecoveryError> {
     use StorageRecoveryError::{DurabilityClientRead, DurabilityRecordDeserialize, DurabilityRecordsMissing};
 
     let mut recovered_commits = BTreeMap::new();
+    let mut pending_count = 0;
 
     let records =
         durability_client.iter_from(start).map_err(|error| DurabilityClientRead { typedb_source: error })?.peekable();
@@ -44,20 +56,33 @@ pub fn load_commit_data_from(
             }
             first_record = false;
         }
+        if sequence_number > end && pending_count == 0 {
+            break;
+        }
 
         match record_type {
             CommitRecord::RECORD_TYPE => {
+                if sequence_number > end {
+                    continue;
+                }
                 let commit_record = CommitRecord::deserialise_from(&mut &*bytes)
                     .map_err(|error| DurabilityRecordDeserialize { source: Arc::new(error) })?;
                 recovered_commits.insert(sequence_number, RecoveryCommitStatus::Pending(commit_record));
+                pending_count += 1;
             }
             StatusRecord::RECORD_TYPE => {
                 let StatusRecord { commit_record_sequence_number, was_committed } =
                     StatusRecord::deserialise_from(&mut &*bytes)
                         .map_err(|error| DurabilityRecordDeserialize { source: Arc::new(error) })?;
-                if commit_record_sequence_number < start {
+                if commit_record_sequence_number < start || commit_record_sequence_number > end {
                     continue;
                 }
+                if matches!(
+                    recovered_commits.get(&commit_record_sequence_number),
+                    Some(RecoveryCommitStatus::Pending(_))
+                ) {
+                    pending_count -= 1;
+                }
                 if was_committed {
                     let record = recovered_commits.remove(&commit_record_sequence_number).unwrap();
                     let RecoveryCommitStatus::Pending(record) = record else {
@@ -92,6 +117,14 @@ pub(crate) fn apply_recovered(
 
     let mut pending_writes = Vec::new();
     for (commit_sequence_number, commit) in recovered_commits {
+        if let RecoveryCommitStatus::Validated(record) | RecoveryCommitStatus::Pending(record) = &commit {
+            if matches!(record.commit_type(), CommitType::BulkLoad) {
+                event!(
+                    Level::WARN,
+                    "Data bulk loaded at {commit_sequence_number} was not checkpointed and cannot be recovered from the WAL."
+                );
+            }
+        }
         match commit {
             RecoveryCommitStatus::Validated(commit_record) => {
                 pending_writes.push(WriteBatches::from_operations(commit_sequence_number, commit_record.operations()));
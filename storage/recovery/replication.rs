@@ -0,0 +1,158 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{collections::BTreeMap, error::Error, sync::Arc};
+
+use durability::RawRecord;
+use error::typedb_error;
+use tracing::{event, Level};
+
+use crate::{
+    durability_client::{DurabilityClient, DurabilityClientError, DurabilityRecord},
+    isolation_manager::{CommitRecord, CommitType, StatusRecord},
+    keyspace::KeyspaceError,
+    sequence_number::SequenceNumber,
+    write_batches::WriteBatches,
+    MVCCStorage,
+};
+
+/// Applies WAL records shipped from a primary to a follower storage, which never commits on its own.
+///
+/// Every commit and status record is appended to the follower's own WAL in the primary's order, so the two logs
+/// assign identical sequence numbers and the follower recovers from its own WAL exactly like a primary would.
+/// Commit records are held back until their status arrives, then written to the keyspaces like recovered commits.
+#[derive(Debug, Default)]
+pub struct ReplicationApplier {
+    pending: BTreeMap<SequenceNumber, CommitRecord>,
+}
+
+impl ReplicationApplier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The sequence number the primary should ship records from to continue this follower without gaps.
+    /// Records the follower already holds may be shipped again and are skipped.
+    pub fn resume_from<D: DurabilityClient>(&self, storage: &MVCCStorage<D>) -> SequenceNumber {
+        // Status records are stamped with the sequence number of the latest commit record when they are written,
+        // so the status of a pending commit can only be found at or after that commit.
+        match self.pending.first_key_value() {
+            Some((&sequence_number, _)) => sequence_number,
+            None => storage.durability().previous().max(SequenceNumber::MIN.next()),
+        }
+    }
+
+    pub fn pending_commits(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether applying this record makes a schema commit visible. Callers use this to exclude schema readers.
+    pub fn resolves_schema_commit(&self, record: &RawRecord<'_>) -> bool {
+        record.record_type == StatusRecord::RECORD_TYPE
+            && StatusRecord::deserialise_from(&mut &*record.bytes).is_ok_and(|status| {
+                status.was_committed()
+                    && self
+                        .pending
+                        .get(&status.commit_record_sequence_number())
+                        .is_some_and(|commit| matches!(commit.commit_type(), CommitType::Schema))
+            })
+    }
+
+    /// Apply one record, in the order the primary wrote it. Returns the sequence number of a commit that became
+    /// visible on the follower as a result, if any.
+    pub fn apply<D: DurabilityClient>(
+        &mut self,
+        storage: &MVCCStorage<D>,
+        record: RawRecord<'_>,
+    ) -> Result<Option<SequenceNumber>, StorageReplicationError> {
+        use StorageReplicationError::{DurabilityRecordDeserialize, DurabilityWrite, Internal, KeyspaceWrite};
+
+        match record.record_type {
+            CommitRecord::RECORD_TYPE => {
+                let expected = storage.durability().current();
+                if record.sequence_number < expected {
+                    // already replicated before a reconnect
+                    return Ok(None);
+                } else if record.sequence_number > expected {
+                    return Err(StorageReplicationError::SequenceGap {
+                        expected_sequence_number: expected,
+                        found_sequence_number: record.sequence_number,
+                    });
+                }
+                let commit_record = CommitRecord::deserialise_from(&mut &*record.bytes)
+                    .map_err(|error| DurabilityRecordDeserialize { source: Arc::new(error) })?;
+                if matches!(commit_record.commit_type(), CommitType::BulkLoad) {
+                    // the ingested data is not in the WAL, so following past it would silently diverge
+                    return Err(StorageReplicationError::BulkLoadNotReplicated {
+                        sequence_number: record.sequence_number,
+                    });
+                }
+                let written = storage
+                    .durability()
+                    .sequenced_write(&commit_record)
+                    .map_err(|error| DurabilityWrite { typedb_source: error })?;
+                debug_assert_eq!(written, record.sequence_number);
+                self.pending.insert(record.sequence_number, commit_record);
+                Ok(None)
+            }
+            StatusRecord::RECORD_TYPE => {
+                let status = StatusRecord::deserialise_from(&mut &*record.bytes)
+                    .map_err(|error| DurabilityRecordDeserialize { source: Arc::new(error) })?;
+                let commit_sequence_number = status.commit_record_sequence_number();
+                let Some(commit_record) = self.pending.remove(&commit_sequence_number) else {
+                    // resolved before a reconnect, or during recovery of this follower
+                    return Ok(None);
+                };
+                MVCCStorage::persist_commit_status(
+                    status.was_committed(),
+                    commit_sequence_number,
+                    storage.durability(),
+                )
+                .map_err(|error| DurabilityWrite { typedb_source: error })?;
+
+                if status.was_committed() {
+                    storage.durability().request_sync().recv().unwrap(); // persist the WAL before the keyspaces
+                    let write_batches =
+                        WriteBatches::from_operations(commit_sequence_number, commit_record.operations());
+                    storage.keyspaces.write(write_batches).map_err(|error| KeyspaceWrite { source: error })?;
+                    storage.isolation_manager.load_validated(commit_sequence_number, commit_record);
+                    storage
+                        .isolation_manager
+                        .applied(commit_sequence_number)
+                        .map_err(|error| Internal { source: Arc::new(error) })?;
+                    event!(Level::TRACE, "Applied replicated commit {}", commit_sequence_number);
+                    Ok(Some(commit_sequence_number))
+                } else {
+                    storage.isolation_manager.load_aborted(commit_sequence_number);
+                    Ok(None)
+                }
+            }
+            _not_storage_record => {
+                // unsequenced records of other components, such as statistics, are maintained by the follower itself
+                Ok(None)
+            }
+        }
+    }
+}
+
+typedb_error!(
+    pub StorageReplicationError(component = "Storage replication", prefix = "RPL") {
+        DurabilityRecordDeserialize(1, "Failed to deserialise replicated WAL record.", ( source: Arc<bincode::Error> )),
+        DurabilityWrite(2, "Failed to write replicated record to the local WAL.", ( typedb_source: DurabilityClientError )),
+        SequenceGap(
+            3,
+            "Replicated WAL records are not contiguous - expected commit record '{expected_sequence_number}', but received '{found_sequence_number}'.",
+            expected_sequence_number: SequenceNumber, found_sequence_number: SequenceNumber
+        ),
+        KeyspaceWrite(4, "Error writing replicated commit to keyspace.", ( source: KeyspaceError )),
+        Internal(5, "Internal error applying replicated commit.", ( source: Arc<dyn Error + Send + Sync + 'static> )),
+        BulkLoadNotReplicated(
+            6,
+            "The primary bulk loaded data at '{sequence_number}', which is not in its WAL and cannot be replicated. Recreate the follower from a checkpoint of the primary.",
+            sequence_number: SequenceNumber
+        ),
+    }
+);
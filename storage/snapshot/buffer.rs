@@ -5,16 +5,25 @@
  */
 
 use std::{
-    borrow::Borrow,
+    borrow::{Borrow, Cow},
     cmp::Ordering,
-    collections::{BTreeMap, Bound},
-    fmt,
+    collections::{BTreeMap, BTreeSet, Bound},
+    fmt, fs, io,
     iter::{IntoIterator, Peekable},
-    sync::{atomic::AtomicBool, Arc},
+    mem,
+    path::PathBuf,
+    sync::{
+        atomic::{self, AtomicBool, AtomicU64},
+        Arc,
+    },
+    vec,
 };
 
 use bytes::{byte_array::ByteArray, util::increment, Bytes};
-use resource::constants::snapshot::{BUFFER_KEY_INLINE, BUFFER_VALUE_INLINE};
+use logger::result::ResultExt;
+use resource::constants::snapshot::{
+    BUFFER_KEY_INLINE, BUFFER_VALUE_INLINE, DEFAULT_WRITE_BUFFER_LIMIT_BYTES, DEFAULT_WRITE_BUFFER_SPILL_BYTES,
+};
 use serde::{
     de::{self, MapAccess, SeqAccess, Visitor},
     ser::SerializeStruct,
@@ -23,25 +32,164 @@ use serde::{
 
 use crate::{
     key_range::{KeyRange, RangeEnd, RangeStart},
-    key_value::StorageKeyArray,
+    key_value::{StorageKey, StorageKeyArray, StorageKeyReference},
     keyspace::{KeyspaceId, KEYSPACE_MAXIMUM_COUNT},
-    snapshot::{lock::LockType, write::Write},
+    snapshot::{
+        lock::LockType,
+        read_set::ReadSet,
+        spill::{RunCursor, SpilledRun},
+        write::Write,
+        SnapshotError,
+    },
 };
 
+static SPILL_DIRECTORY_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Sizes at which the writes buffered by a snapshot are spilled to disk, and beyond which it refuses further writes.
+/// A commit loads its spilled writes back into memory, so the limit also bounds the memory a commit takes.
+#[derive(Debug, Clone, Copy)]
+pub struct WriteBufferLimits {
+    pub spill_bytes: u64,
+    pub limit_bytes: u64,
+}
+
+impl Default for WriteBufferLimits {
+    fn default() -> Self {
+        Self { spill_bytes: DEFAULT_WRITE_BUFFER_SPILL_BYTES, limit_bytes: DEFAULT_WRITE_BUFFER_LIMIT_BYTES }
+    }
+}
+
 #[derive(Debug)]
 pub struct OperationsBuffer {
     write_buffers: [WriteBuffer; KEYSPACE_MAXIMUM_COUNT],
     locks: BTreeMap<ByteArray<BUFFER_KEY_INLINE>, LockType>,
+    limits: WriteBufferLimits,
+    spill_directory: Option<PathBuf>,
+    spilled_runs: usize,
+    write_error: Option<SnapshotError>,
+    reads: Option<ReadSet>,
 }
 
 impl OperationsBuffer {
     pub(crate) fn new() -> OperationsBuffer {
+        Self::from_parts(std::array::from_fn(|i| WriteBuffer::new(KeyspaceId(i as u8))), BTreeMap::new())
+    }
+
+    fn from_parts(
+        write_buffers: [WriteBuffer; KEYSPACE_MAXIMUM_COUNT],
+        locks: BTreeMap<ByteArray<BUFFER_KEY_INLINE>, LockType>,
+    ) -> OperationsBuffer {
         OperationsBuffer {
-            write_buffers: std::array::from_fn(|i| WriteBuffer::new(KeyspaceId(i as u8))),
-            locks: BTreeMap::new(),
+            write_buffers,
+            locks,
+            limits: WriteBufferLimits::default(),
+            spill_directory: None,
+            spilled_runs: 0,
+            write_error: None,
+            reads: None,
+        }
+    }
+
+    /// Record the keys and ranges read from storage, so the commit can be validated as serializable
+    pub(crate) fn track_reads(&mut self) {
+        self.reads = Some(ReadSet::default());
+    }
+
+    pub(crate) fn reads(&self) -> Option<&ReadSet> {
+        self.reads.as_ref()
+    }
+
+    pub(crate) fn record_read(&self, key: StorageKeyReference<'_>) {
+        if let Some(reads) = &self.reads {
+            reads.record_key(key);
+        }
+    }
+
+    pub(crate) fn record_read_range<const PS: usize>(&self, range: &KeyRange<StorageKey<'_, PS>>) {
+        if let Some(reads) = &self.reads {
+            let keyspace_id = range.start().get_value().keyspace_id();
+            reads.record_range(keyspace_id, range.clone().map(|k| k.as_bytes(), |fixed| fixed));
+        }
+    }
+
+    /// Allow spilling writes to a new directory under `spill_root`, which is only created once writes are spilled
+    pub(crate) fn enable_spill(&mut self, spill_root: PathBuf) {
+        let id = SPILL_DIRECTORY_ID.fetch_add(1, atomic::Ordering::Relaxed);
+        self.spill_directory = Some(spill_root.join(id.to_string()));
+    }
+
+    /// Move the buffered operations out, leaving an empty buffer with the same limits that spills to a new directory
+    pub(crate) fn take(&mut self) -> OperationsBuffer {
+        let mut emptied = OperationsBuffer::new();
+        emptied.limits = self.limits;
+        if let Some(spill_root) = self.spill_directory.as_ref().and_then(|directory| directory.parent()) {
+            emptied.enable_spill(spill_root.to_owned());
+        }
+        std::mem::replace(self, emptied)
+    }
+
+    pub(crate) fn limits(&self) -> WriteBufferLimits {
+        self.limits
+    }
+
+    pub(crate) fn set_limits(&mut self, limits: WriteBufferLimits) {
+        self.limits = limits;
+    }
+
+    /// Total size of the buffered writes, in memory and spilled to disk
+    pub(crate) fn size_bytes(&self) -> u64 {
+        self.write_buffers.iter().map(|buffer| buffer.memory_bytes + buffer.spilled_bytes()).sum()
+    }
+
+    /// The error that made this buffer refuse further writes, which the snapshot then fails to commit with
+    pub(crate) fn write_error(&self) -> Option<&SnapshotError> {
+        self.write_error.as_ref()
+    }
+
+    /// Check the buffer after a write: fail once the writes exceed the limit, and otherwise spill the in-memory
+    /// writes of every keyspace as sorted runs if together they exceed the spill size.
+    /// Once failed, the buffer refuses further writes, so a failing transaction stops growing its memory or disk use.
+    pub(crate) fn enforce_limits(&mut self) {
+        if self.write_error.is_some() {
+            return;
+        }
+        let size = self.size_bytes();
+        if size > self.limits.limit_bytes {
+            self.write_error = Some(SnapshotError::WriteBufferLimitExceeded { size, limit: self.limits.limit_bytes });
+            return;
+        }
+        let Some(spill_directory) = &self.spill_directory else {
+            return;
+        };
+        let memory_bytes: u64 = self.write_buffers.iter().map(|buffer| buffer.memory_bytes).sum();
+        if memory_bytes < self.limits.spill_bytes {
+            return;
+        }
+
+        let spilled = fs::create_dir_all(spill_directory).and_then(|_| {
+            for buffer in self.write_buffers.iter_mut().filter(|buffer| !buffer.writes.is_empty()) {
+                let path = spill_directory.join(format!("{}-{}.run", buffer.keyspace_id, self.spilled_runs));
+                self.spilled_runs += 1;
+                buffer.spill(path)?;
+            }
+            Ok(())
+        });
+        if let Err(error) = spilled {
+            self.write_error = Some(SnapshotError::WriteBufferSpill { source: Arc::new(error) });
         }
     }
 
+    /// Merge all spilled writes back into memory, as required to commit
+    pub(crate) fn merge_spilled(&mut self) -> io::Result<()> {
+        for buffer in self.write_buffers.iter_mut() {
+            buffer.merge_spilled()?;
+        }
+        if let Some(spill_directory) = self.spill_directory.take() {
+            let _ = fs::remove_dir_all(spill_directory);
+        }
+        Ok(())
+    }
+
     pub(crate) fn is_writes_empty(&self) -> bool {
         self.write_buffers.iter().all(|buffer| buffer.is_empty())
     }
@@ -70,7 +218,7 @@ impl OperationsBuffer {
         self.locks.remove(key);
     }
 
-    pub(crate) fn locks(&self) -> &BTreeMap<ByteArray<BUFFER_KEY_INLINE>, LockType> {
+    pub fn locks(&self) -> &BTreeMap<ByteArray<BUFFER_KEY_INLINE>, LockType> {
         &self.locks
     }
 
@@ -88,12 +236,22 @@ impl OperationsBuffer {
 
     pub fn clear(&mut self) {
         self.locks.clear();
+        self.write_error = None;
         for buffer in self.write_buffers.iter_mut() {
             buffer.clear();
         }
     }
 }
 
+impl Drop for OperationsBuffer {
+    fn drop(&mut self) {
+        if let Some(spill_directory) = &self.spill_directory {
+            self.write_buffers.iter_mut().for_each(|buffer| buffer.spilled.clear());
+            let _ = fs::remove_dir_all(spill_directory);
+        }
+    }
+}
+
 impl<'a> IntoIterator for &'a OperationsBuffer {
     type Item = &'a WriteBuffer;
     type IntoIter = <&'a [WriteBuffer] as IntoIterator>::IntoIter;
@@ -110,23 +268,104 @@ impl<'a> IntoIterator for &'a OperationsBuffer {
 //       3) We would benefit hugely from a table where writes are never moved, so we can freely
 //          take references to existing writes without having to Clone them out every time... This
 //          might lead us to a RocksDB-like Buffer+Index structure
+//
+// Writes may be spilled to disk as sorted runs, oldest first, in which case the in-memory writes are the newest.
+// Writes removed from memory are recorded in `removed` while any run exists, since runs may hold older versions.
 #[derive(Debug)]
 pub struct WriteBuffer {
     pub(crate) keyspace_id: KeyspaceId,
     writes: BTreeMap<ByteArray<BUFFER_KEY_INLINE>, Write>,
+    memory_bytes: u64,
+    spilled: Vec<Arc<SpilledRun>>,
+    removed: BTreeSet<ByteArray<BUFFER_KEY_INLINE>>,
 }
 
 impl WriteBuffer {
     pub(crate) fn new(keyspace_id: KeyspaceId) -> WriteBuffer {
-        WriteBuffer { keyspace_id, writes: BTreeMap::new() }
+        Self::from_writes(keyspace_id, BTreeMap::new())
+    }
+
+    fn from_writes(keyspace_id: KeyspaceId, writes: BTreeMap<ByteArray<BUFFER_KEY_INLINE>, Write>) -> WriteBuffer {
+        let memory_bytes = writes.iter().map(|(key, write)| Self::write_size(key, write)).sum();
+        WriteBuffer { keyspace_id, writes, memory_bytes, spilled: Vec::new(), removed: BTreeSet::new() }
     }
 
     pub(crate) fn is_empty(&self) -> bool {
-        self.writes.is_empty()
+        self.writes.is_empty() && self.spilled.is_empty()
+    }
+
+    fn write_size(key: &[u8], write: &Write) -> u64 {
+        let value_size = match write {
+            Write::Insert { value } | Write::Put { value, .. } => value.len(),
+            Write::Delete => 0,
+        };
+        (key.len() + value_size + mem::size_of::<Write>()) as u64
+    }
+
+    fn write(&mut self, key: ByteArray<BUFFER_KEY_INLINE>, write: Write) {
+        self.memory_bytes += Self::write_size(&key, &write);
+        if !self.removed.is_empty() {
+            self.removed.remove(&key);
+        }
+        if let Some(replaced) = self.writes.get(&key) {
+            self.memory_bytes -= Self::write_size(&key, replaced);
+        }
+        self.writes.insert(key, write);
+    }
+
+    fn remove(&mut self, key: ByteArray<BUFFER_KEY_INLINE>) -> Option<Write> {
+        let in_memory = self.writes.remove(&key);
+        if let Some(write) = &in_memory {
+            self.memory_bytes -= Self::write_size(&key, write);
+        }
+        let removed = in_memory.or_else(|| self.get_spilled(&key));
+        if !self.spilled.is_empty() {
+            self.removed.insert(key);
+        }
+        removed
+    }
+
+    fn get_spilled(&self, key: &[u8]) -> Option<Write> {
+        if self.removed.contains(key) {
+            return None;
+        }
+        self.spilled.iter().rev().find_map(|run| run.get(key).unwrap_or_log())
+    }
+
+    fn spilled_bytes(&self) -> u64 {
+        self.spilled.iter().map(|run| run.size()).sum()
+    }
+
+    fn spill(&mut self, path: PathBuf) -> io::Result<()> {
+        let run = SpilledRun::write(path, &self.writes)?;
+        self.spilled.push(Arc::new(run));
+        self.writes.clear();
+        self.memory_bytes = 0;
+        Ok(())
+    }
+
+    // Each run is read one block at a time and the in-memory writes are moved, so the merge holds little beyond
+    // the merged writes themselves
+    fn merge_spilled(&mut self) -> io::Result<()> {
+        if self.spilled.is_empty() {
+            return Ok(());
+        }
+        let runs = mem::take(&mut self.spilled)
+            .into_iter()
+            .map(|run| run.cursor(Bound::Unbounded))
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut merge =
+            MergedWrites::new(runs, mem::take(&mut self.writes).into_iter(), mem::take(&mut self.removed).into_iter());
+        let mut merged = BTreeMap::new();
+        while let Some((key, write)) = merge.next()? {
+            merged.insert(key, write);
+        }
+        *self = Self::from_writes(self.keyspace_id, merged);
+        Ok(())
     }
 
     pub(crate) fn insert(&mut self, key: ByteArray<BUFFER_KEY_INLINE>, value: ByteArray<BUFFER_VALUE_INLINE>) {
-        self.writes.insert(key, Write::Insert { value });
+        self.write(key, Write::Insert { value });
     }
 
     pub(crate) fn uninsert(
@@ -134,7 +373,7 @@ impl WriteBuffer {
         key: ByteArray<BUFFER_KEY_INLINE>,
         expected_value: ByteArray<BUFFER_VALUE_INLINE>,
     ) {
-        match self.writes.remove(&key) {
+        match self.remove(key) {
             Some(Write::Insert { value, .. }) => {
                 if value != expected_value {
                     panic!("Unexpected value `{:?}` when trying to uninsert; expected `{:?}`", value, expected_value)
@@ -146,16 +385,15 @@ impl WriteBuffer {
     }
 
     pub(crate) fn put(&mut self, key: ByteArray<BUFFER_KEY_INLINE>, value: ByteArray<BUFFER_VALUE_INLINE>) {
-        self.writes
-            .insert(key, Write::Put { value, reinsert: Arc::new(AtomicBool::new(false)), known_to_exist: false });
+        self.write(key, Write::Put { value, reinsert: Arc::new(AtomicBool::new(false)), known_to_exist: false });
     }
 
     pub(crate) fn put_existing(&mut self, key: ByteArray<BUFFER_KEY_INLINE>, value: ByteArray<BUFFER_VALUE_INLINE>) {
-        self.writes.insert(key, Write::Put { value, reinsert: Arc::new(AtomicBool::new(false)), known_to_exist: true });
+        self.write(key, Write::Put { value, reinsert: Arc::new(AtomicBool::new(false)), known_to_exist: true });
     }
 
     pub(crate) fn unput(&mut self, key: ByteArray<BUFFER_KEY_INLINE>, expected_value: ByteArray<BUFFER_VALUE_INLINE>) {
-        match self.writes.remove(&key) {
+        match self.remove(key) {
             Some(Write::Put { value, .. }) => {
                 if value != expected_value {
                     panic!("Unexpected value `{:?}` when trying to unput; expected `{:?}`", value, expected_value)
@@ -170,15 +408,19 @@ impl WriteBuffer {
         // note: If this snapshot has Inserted the key, we don't know if it's a preexisting key
         // with a different value for overwrite or a brand new key so we always have to write a
         // delete marker instead of removing an element from the map in some cases
-        self.writes.insert(key, Write::Delete);
+        self.write(key, Write::Delete);
     }
 
     pub(crate) fn contains(&self, key: &ByteArray<BUFFER_KEY_INLINE>) -> bool {
-        self.writes.contains_key(key)
+        self.get(key).is_some()
     }
 
-    pub(crate) fn get(&self, key: &[u8]) -> Option<&Write> {
-        self.writes.get(key)
+    pub(crate) fn get(&self, key: &[u8]) -> Option<Cow<'_, Write>> {
+        match self.writes.get(key) {
+            Some(write) => Some(Cow::Borrowed(write)),
+            None if self.spilled.is_empty() => None,
+            None => self.get_spilled(key).map(Cow::Owned),
+        }
     }
 
     pub(crate) fn iterate_range<const INLINE: usize>(&self, range: KeyRange<Bytes<'_, INLINE>>) -> BufferRangeIterator {
@@ -192,12 +434,17 @@ impl WriteBuffer {
         let start_as_bound = Self::range_start_as_bound(range_start);
         let start_bytes = start_as_bound.as_ref().map(|bytes| bytes.as_ref());
         // TODO: we shouldn't have to copy now that we use single-writer semantics
-        BufferRangeIterator::new(
-            self.writes
-                .range::<[u8], _>((start_bytes, end))
-                .map(|(key, val)| (StorageKeyArray::new_raw(self.keyspace_id, key.clone()), val.clone()))
-                .collect::<Vec<_>>(),
-        )
+        let writes = self
+            .writes
+            .range::<[u8], _>((start_bytes, end))
+            .map(|(key, val)| (key.clone(), val.clone()))
+            .collect::<Vec<_>>();
+        if self.spilled.is_empty() {
+            return BufferRangeIterator::new(self.keyspace_id, writes);
+        }
+        let removed = self.removed.range::<[u8], _>((start_bytes, end)).cloned().collect::<Vec<_>>();
+        let merge = MergedWrites::new(self.run_cursors(start_bytes), writes.into_iter(), removed.into_iter());
+        BufferRangeIterator::new_merged(self.keyspace_id, merge, end.map(ByteArray::copy))
     }
 
     // TODO: if the iterate_range becomes zero-copy, then we can eliminate this method
@@ -211,10 +458,27 @@ impl WriteBuffer {
         };
         let start_as_bound = Self::range_start_as_bound(range_start);
         let start_bytes = start_as_bound.as_ref().map(|bytes| bytes.as_ref());
+        if !self.spilled.is_empty() {
+            let writes = self.writes.range::<[u8], _>((start_bytes, end)).map(|(key, val)| (key.clone(), val.clone()));
+            let removed = self.removed.range::<[u8], _>((start_bytes, end)).cloned();
+            let mut merge = MergedWrites::new(self.run_cursors(start_bytes), writes, removed);
+            while let Some((key, write)) = merge.next().unwrap_or_log() {
+                if !is_before_end(&key, end) {
+                    return false;
+                } else if !write.is_delete() {
+                    return true;
+                }
+            }
+            return false;
+        }
         self.writes.range::<[u8], _>((start_bytes, end)).any(|(_, write)| !write.is_delete())
     }
 
-    fn range_start_as_bound<const INLINE: usize>(
+    fn run_cursors(&self, start: Bound<&[u8]>) -> Vec<RunCursor> {
+        self.spilled.iter().map(|run| run.clone().cursor(start).unwrap_or_log()).collect()
+    }
+
+    pub(crate) fn range_start_as_bound<const INLINE: usize>(
         range_start: RangeStart<Bytes<'_, INLINE>>,
     ) -> Bound<Bytes<'_, INLINE>> {
         match range_start {
@@ -228,7 +492,7 @@ impl WriteBuffer {
         }
     }
 
-    fn compute_exclusive_end<const INLINE: usize>(
+    pub(crate) fn compute_exclusive_end<const INLINE: usize>(
         start: &RangeStart<Bytes<'_, INLINE>>,
         end: &RangeEnd<Bytes<'_, INLINE>>,
     ) -> ByteArray<INLINE> {
@@ -249,6 +513,7 @@ impl WriteBuffer {
     }
 
     pub(crate) fn writes(&self) -> &BTreeMap<ByteArray<BUFFER_KEY_INLINE>, Write> {
+        debug_assert!(self.spilled.is_empty(), "Spilled writes must be merged before accessing all writes");
         &self.writes
     }
 
@@ -256,31 +521,114 @@ impl WriteBuffer {
         &mut self.writes
     }
 
-    pub fn get_write(&self, key: &[u8]) -> Option<&Write> {
-        self.writes.get(key)
+    pub fn get_write(&self, key: &[u8]) -> Option<Cow<'_, Write>> {
+        self.get(key)
     }
 
     pub fn clear(&mut self) {
-        self.writes.clear()
+        self.writes.clear();
+        self.memory_bytes = 0;
+        self.spilled.clear();
+        self.removed.clear();
     }
 }
 
+/// Merges the runs spilled from a buffer, oldest first, with the newer writes still in memory, reading each run one
+/// block at a time. Where several sources hold a key, the newest write wins. Keys removed from memory after the runs
+/// were spilled are in `removed`, and hide the older writes the runs hold for them.
+struct MergedWrites<W: Iterator, R: Iterator> {
+    runs: Vec<RunCursor>,
+    writes: Peekable<W>,
+    removed: Peekable<R>,
+}
+
+impl<W, R> MergedWrites<W, R>
+where
+    W: Iterator<Item = (ByteArray<BUFFER_KEY_INLINE>, Write)>,
+    R: Iterator<Item = ByteArray<BUFFER_KEY_INLINE>>,
+{
+    fn new(runs: Vec<RunCursor>, writes: W, removed: R) -> Self {
+        Self { runs, writes: writes.peekable(), removed: removed.peekable() }
+    }
+
+    // A transaction only spills a few runs per keyspace, so finding the smallest key by scanning every run is cheap
+    fn next(&mut self) -> io::Result<Option<(ByteArray<BUFFER_KEY_INLINE>, Write)>> {
+        loop {
+            let mut newest_run = None;
+            for (index, run) in self.runs.iter().enumerate().rev() {
+                let Some((key, _)) = run.head() else { continue };
+                let smaller = match newest_run.and_then(|newest: usize| self.runs[newest].head()) {
+                    Some((newest_key, _)) => key < newest_key,
+                    None => true,
+                };
+                if smaller {
+                    newest_run = Some(index);
+                }
+            }
+            let run_key = newest_run.and_then(|index| self.runs[index].head()).map(|(key, _)| key.clone());
+
+            let from_memory = match (self.writes.peek(), &run_key) {
+                (None, None) => return Ok(None),
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (Some((key, _)), Some(run_key)) => key <= run_key,
+            };
+            if from_memory {
+                let (key, write) = self.writes.next().unwrap();
+                self.skip_runs_at(&key)?;
+                return Ok(Some((key, write)));
+            }
+
+            let run_key = run_key.unwrap();
+            let (key, write) = self.runs[newest_run.unwrap()].advance()?.unwrap();
+            self.skip_runs_at(&run_key)?;
+            while self.removed.next_if(|removed| removed < &key).is_some() {}
+            if self.removed.next_if(|removed| removed == &key).is_none() {
+                return Ok(Some((key, write)));
+            }
+        }
+    }
+
+    fn skip_runs_at(&mut self, key: &[u8]) -> io::Result<()> {
+        for run in &mut self.runs {
+            if run.head().is_some_and(|(run_key, _)| &**run_key == key) {
+                run.advance()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+type MergedRange =
+    MergedWrites<vec::IntoIter<(ByteArray<BUFFER_KEY_INLINE>, Write)>, vec::IntoIter<ByteArray<BUFFER_KEY_INLINE>>>;
+
 // TODO: this iterator takes a 'snapshot' of the time it was opened at - we could have it read without clones and have it 'live' if the buffers are immutable
 pub struct BufferRangeIterator {
-    inner: Peekable<<Vec<(StorageKeyArray<BUFFER_KEY_INLINE>, Write)> as IntoIterator>::IntoIter>,
+    keyspace_id: KeyspaceId,
+    writes: Peekable<vec::IntoIter<(ByteArray<BUFFER_KEY_INLINE>, Write)>>,
+    merged: Option<(MergedRange, Bound<ByteArray<BUFFER_KEY_INLINE>>)>,
+    peeked: Option<(StorageKeyArray<BUFFER_KEY_INLINE>, Write)>,
 }
 
 impl BufferRangeIterator {
-    fn new(range: Vec<(StorageKeyArray<BUFFER_KEY_INLINE>, Write)>) -> Self {
-        Self { inner: range.into_iter().peekable() }
+    fn new(keyspace_id: KeyspaceId, writes: Vec<(ByteArray<BUFFER_KEY_INLINE>, Write)>) -> Self {
+        Self { keyspace_id, writes: writes.into_iter().peekable(), merged: None, peeked: None }
+    }
+
+    /// Iterate over writes spilled to disk, merged with those in memory, reading the runs as the iterator advances
+    fn new_merged(keyspace_id: KeyspaceId, merged: MergedRange, end: Bound<ByteArray<BUFFER_KEY_INLINE>>) -> Self {
+        Self { keyspace_id, writes: Vec::new().into_iter().peekable(), merged: Some((merged, end)), peeked: None }
     }
 
     pub fn new_empty() -> Self {
-        Self { inner: Vec::new().into_iter().peekable() }
+        Self::new(KeyspaceId(0), Vec::new())
     }
 
     pub fn peek(&mut self) -> Option<&(StorageKeyArray<BUFFER_KEY_INLINE>, Write)> {
-        self.inner.peek()
+        if self.peeked.is_none() {
+            self.peeked = self.next_write();
+        }
+        self.peeked.as_ref()
     }
 
     // TODO: This is a 'dumb' seek, in that it simply consumes values until the criteria is no longer matched
@@ -296,12 +644,35 @@ impl BufferRangeIterator {
             }
         }
     }
+
+    fn next_write(&mut self) -> Option<(StorageKeyArray<BUFFER_KEY_INLINE>, Write)> {
+        let (key, write) = match &mut self.merged {
+            None => self.writes.next()?,
+            Some((merged, end)) => {
+                let (key, write) = merged.next().unwrap_or_log()?;
+                if !is_before_end(&key, end.as_ref().map(|end| &**end)) {
+                    self.merged = None;
+                    return None;
+                }
+                (key, write)
+            }
+        };
+        Some((StorageKeyArray::new_raw(self.keyspace_id, key), write))
+    }
 }
 
 impl Iterator for BufferRangeIterator {
     type Item = (StorageKeyArray<BUFFER_KEY_INLINE>, Write);
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+        self.peeked.take().or_else(|| self.next_write())
+    }
+}
+
+fn is_before_end(key: &[u8], end: Bound<&[u8]>) -> bool {
+    match end {
+        Bound::Included(end) => key <= end,
+        Bound::Excluded(end) => key < end,
+        Bound::Unbounded => true,
     }
 }
 
@@ -373,7 +744,7 @@ impl<'de> Deserialize<'de> for OperationsBuffer {
                 let write_buffers = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
                 let locks: BTreeMap<ByteArray<BUFFER_KEY_INLINE>, LockType> =
                     seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
-                Ok(OperationsBuffer { write_buffers, locks })
+                Ok(OperationsBuffer::from_parts(write_buffers, locks))
             }
         }
 
@@ -386,6 +757,7 @@ impl Serialize for WriteBuffer {
     where
         S: Serializer,
     {
+        debug_assert!(self.spilled.is_empty(), "Spilled writes must be merged before serialising");
         let mut state = serializer.serialize_struct("KeyspaceBuffer", 2)?;
         state.serialize_field("KeyspaceId", &self.keyspace_id)?;
         state.serialize_field("Buffer", &self.writes)?;
@@ -449,7 +821,7 @@ impl<'de> Deserialize<'de> for WriteBuffer {
                 let keyspace_id = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
                 let buffer: BTreeMap<ByteArray<BUFFER_KEY_INLINE>, Write> =
                     seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
-                Ok(WriteBuffer { keyspace_id, writes: buffer })
+                Ok(WriteBuffer::from_writes(keyspace_id, buffer))
             }
 
             fn visit_map<V>(self, mut map: V) -> Result<WriteBuffer, V::Error>
@@ -478,7 +850,7 @@ impl<'de> Deserialize<'de> for WriteBuffer {
                 let keyspace_id = keyspace_id.ok_or_else(|| de::Error::invalid_length(1, &self))?;
                 let buffer: BTreeMap<ByteArray<BUFFER_KEY_INLINE>, Write> =
                     buffer.ok_or_else(|| de::Error::invalid_length(1, &self))?;
-                Ok(WriteBuffer { keyspace_id, writes: buffer })
+                Ok(WriteBuffer::from_writes(keyspace_id, buffer))
             }
         }
 
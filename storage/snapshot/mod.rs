@@ -13,5 +13,7 @@ pub mod buffer;
 pub mod iterator;
 pub mod lock;
 pub(crate) mod pool;
+mod read_set;
 mod snapshot;
+pub(crate) mod spill;
 pub mod write;
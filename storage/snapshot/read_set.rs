@@ -0,0 +1,70 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    collections::{BTreeSet, Bound},
+    sync::Mutex,
+};
+
+use bytes::{byte_array::ByteArray, Bytes};
+use resource::constants::snapshot::BUFFER_KEY_INLINE;
+
+use crate::{
+    key_range::{KeyRange, RangeEnd},
+    key_value::{StorageKeyArray, StorageKeyReference},
+    keyspace::KeyspaceId,
+    snapshot::buffer::{OperationsBuffer, WriteBuffer},
+};
+
+type ReadRange = (KeyspaceId, Bound<ByteArray<BUFFER_KEY_INLINE>>, Bound<ByteArray<BUFFER_KEY_INLINE>>);
+
+/// The keys and key ranges a snapshot read from storage, recorded for serializable isolation: the snapshot may only
+/// commit if no concurrent commit wrote into any of them. Reads are recorded through shared references, since
+/// snapshots are read concurrently by query workers.
+#[derive(Debug, Default)]
+pub(crate) struct ReadSet {
+    keys: Mutex<BTreeSet<StorageKeyArray<BUFFER_KEY_INLINE>>>,
+    ranges: Mutex<Vec<ReadRange>>,
+}
+
+impl ReadSet {
+    pub(crate) fn record_key(&self, key: StorageKeyReference<'_>) {
+        self.keys.lock().unwrap().insert(StorageKeyArray::from(key));
+    }
+
+    pub(crate) fn record_range<const INLINE: usize>(
+        &self,
+        keyspace_id: KeyspaceId,
+        range: KeyRange<Bytes<'_, INLINE>>,
+    ) {
+        let (range_start, range_end, _) = range.into_raw();
+        let end = match range_end {
+            RangeEnd::Unbounded => Bound::Unbounded,
+            _ => Bound::Excluded(ByteArray::copy(&WriteBuffer::compute_exclusive_end(&range_start, &range_end))),
+        };
+        let start = WriteBuffer::range_start_as_bound(range_start).map(|bytes| ByteArray::copy(&bytes));
+        self.ranges.lock().unwrap().push((keyspace_id, start, end));
+    }
+
+    /// Returns a key written by `operations` that this snapshot read, if any
+    pub(crate) fn find_written(&self, operations: &OperationsBuffer) -> Option<StorageKeyArray<BUFFER_KEY_INLINE>> {
+        let keys = self.keys.lock().unwrap();
+        if let Some(key) =
+            keys.iter().find(|key| operations.writes_in(key.keyspace_id()).writes().contains_key(key.bytes()))
+        {
+            return Some(key.clone());
+        }
+
+        let ranges = self.ranges.lock().unwrap();
+        ranges.iter().find_map(|(keyspace_id, start, end)| {
+            let start = start.as_ref().map(|bytes| &**bytes);
+            let end = end.as_ref().map(|bytes| &**bytes);
+            let writes = operations.writes_in(*keyspace_id).writes();
+            let (key, _) = writes.range::<[u8], _>((start, end)).next()?;
+            Some(StorageKeyArray::new_raw(*keyspace_id, key.clone()))
+        })
+    }
+}
@@ -4,7 +4,7 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use std::{any::type_name, error::Error, fmt, iter::empty, sync::Arc};
+use std::{any::type_name, borrow::Cow, error::Error, fmt, io, iter::empty, sync::Arc};
 
 use bytes::byte_array::ByteArray;
 use error::typedb_error;
@@ -20,7 +20,7 @@ use crate::{
     keyspace::IteratorPool,
     sequence_number::SequenceNumber,
     snapshot::{
-        buffer::{BufferRangeIterator, OperationsBuffer},
+        buffer::{BufferRangeIterator, OperationsBuffer, WriteBufferLimits},
         iterator::SnapshotRangeIterator,
         lock::LockType,
         write::Write,
@@ -67,7 +67,7 @@ pub trait ReadableSnapshot {
     fn any_in_range<const PS: usize>(&self, range: &KeyRange<StorageKey<'_, PS>>, buffered_only: bool) -> bool;
 
     // --- we are slightly breaking the abstraction and Rust model by mimicking polymorphism for the following methods ---
-    fn get_write(&self, key: StorageKeyReference<'_>) -> Option<&Write>;
+    fn get_write(&self, key: StorageKeyReference<'_>) -> Option<Cow<'_, Write>>;
 
     fn iterate_writes(&self) -> impl Iterator<Item = (StorageKeyArray<BUFFER_KEY_INLINE>, Write)> + '_;
 
@@ -89,9 +89,13 @@ pub trait WritableSnapshot: ReadableSnapshot {
     }
 
     fn insert_val(&mut self, key: StorageKeyArray<BUFFER_KEY_INLINE>, value: ByteArray<BUFFER_VALUE_INLINE>) {
+        if self.operations().write_error().is_some() {
+            return;
+        }
         let keyspace_id = key.keyspace_id();
         let byte_array = key.into_byte_array();
         self.operations_mut().writes_in_mut(keyspace_id).insert(byte_array, value);
+        self.operations_mut().enforce_limits();
     }
 
     /// Insert a key with a new version
@@ -100,6 +104,10 @@ pub trait WritableSnapshot: ReadableSnapshot {
     }
 
     fn uninsert_val(&mut self, key: StorageKeyArray<BUFFER_KEY_INLINE>, value: ByteArray<BUFFER_VALUE_INLINE>) {
+        // the insert may have been refused
+        if self.operations().write_error().is_some() {
+            return;
+        }
         let keyspace_id = key.keyspace_id();
         let byte_array = key.into_byte_array();
         self.operations_mut().writes_in_mut(keyspace_id).uninsert(byte_array, value);
@@ -112,9 +120,13 @@ pub trait WritableSnapshot: ReadableSnapshot {
     }
 
     fn put_val(&mut self, key: StorageKeyArray<BUFFER_KEY_INLINE>, value: ByteArray<BUFFER_VALUE_INLINE>) {
+        if self.operations().write_error().is_some() {
+            return;
+        }
         let keyspace_id = key.keyspace_id();
         let byte_array = key.into_byte_array();
         self.operations_mut().writes_in_mut(keyspace_id).put(byte_array, value);
+        self.operations_mut().enforce_limits();
     }
 
     fn unput(&mut self, key: StorageKeyArray<BUFFER_KEY_INLINE>) {
@@ -122,6 +134,10 @@ pub trait WritableSnapshot: ReadableSnapshot {
     }
 
     fn unput_val(&mut self, key: StorageKeyArray<BUFFER_KEY_INLINE>, value: ByteArray<BUFFER_VALUE_INLINE>) {
+        // the put may have been refused
+        if self.operations().write_error().is_some() {
+            return;
+        }
         let keyspace_id = key.keyspace_id();
         let byte_array = key.into_byte_array();
         self.operations_mut().writes_in_mut(keyspace_id).unput(byte_array, value);
@@ -129,9 +145,13 @@ pub trait WritableSnapshot: ReadableSnapshot {
 
     /// Insert a delete marker for the key with a new version
     fn delete(&mut self, key: StorageKeyArray<BUFFER_KEY_INLINE>) {
+        if self.operations().write_error().is_some() {
+            return;
+        }
         let keyspace_id = key.keyspace_id();
         let byte_array = key.into_byte_array();
         self.operations_mut().writes_in_mut(keyspace_id).delete(byte_array);
+        self.operations_mut().enforce_limits();
     }
 
     /// Get a Value, and mark it as a required key
@@ -140,10 +160,14 @@ pub trait WritableSnapshot: ReadableSnapshot {
         key: StorageKey<'_, BUFFER_KEY_INLINE>,
     ) -> Result<ByteArray<BUFFER_VALUE_INLINE>, SnapshotGetError> {
         let keyspace_id = key.keyspace_id();
-        let writes = self.operations().writes_in(keyspace_id);
-        match writes.get(key.bytes()) {
-            Some(Write::Insert { value, .. }) | Some(Write::Put { value, .. }) => Ok(ByteArray::copy(value)),
-            Some(Write::Delete) => {
+        let buffered = match self.operations().writes_in(keyspace_id).get(key.bytes()).as_deref() {
+            Some(Write::Insert { value, .. }) | Some(Write::Put { value, .. }) => Some(Some(ByteArray::copy(value))),
+            Some(Write::Delete) => Some(None),
+            None => None,
+        };
+        match buffered {
+            Some(Some(value)) => Ok(value),
+            Some(None) => {
                 Err(SnapshotGetError::ExpectedRequiredKeyToExist { key: StorageKey::Array(key.into_owned_array()) })
             }
             None => {
@@ -171,6 +195,20 @@ pub trait WritableSnapshot: ReadableSnapshot {
         self.operations_mut().lock_add(key, LockType::Exclusive)
     }
 
+    /// Set the size at which buffered writes are spilled to disk, and the size beyond which the snapshot refuses writes
+    fn set_write_buffer_limits(&mut self, limits: WriteBufferLimits) {
+        self.operations_mut().set_limits(limits)
+    }
+
+    /// Fails once the write buffer has refused writes, because it exceeded its limit or failed to spill to disk.
+    /// Refused writes are dropped, so writers check this after each write they make and stop.
+    fn check_write_buffer(&self) -> Result<(), SnapshotError> {
+        match self.operations().write_error() {
+            Some(error) => Err(error.clone()),
+            None => Ok(()),
+        }
+    }
+
     fn clear(&mut self) {
         self.operations_mut().clear()
     }
@@ -239,7 +277,7 @@ impl<D> ReadableSnapshot for ReadSnapshot<D> {
             && self.storage.iterate_range(self.iterator_pool(), range, self.open_sequence_number).next().is_some()
     }
 
-    fn get_write(&self, _: StorageKeyReference<'_>) -> Option<&Write> {
+    fn get_write(&self, _: StorageKeyReference<'_>) -> Option<Cow<'_, Write>> {
         None
     }
 
@@ -277,12 +315,17 @@ impl<D: fmt::Debug> fmt::Debug for WriteSnapshot<D> {
 impl<D> WriteSnapshot<D> {
     pub(crate) fn new(storage: Arc<MVCCStorage<D>>, open_sequence_number: SequenceNumber) -> Self {
         storage.isolation_manager.opened_for_read(open_sequence_number);
-        WriteSnapshot {
-            storage,
-            operations: OperationsBuffer::new(),
-            open_sequence_number,
-            iterator_pool: IteratorPool::new(),
+        let mut operations = OperationsBuffer::new();
+        if let Some(spill_path) = storage.spill_path() {
+            operations.enable_spill(spill_path);
         }
+        WriteSnapshot { storage, operations, open_sequence_number, iterator_pool: IteratorPool::new() }
+    }
+
+    /// Track the keys and ranges this snapshot reads from storage, and fail the commit if a concurrent commit wrote
+    /// into any of them
+    pub fn set_serializable(&mut self) {
+        self.operations.track_reads();
     }
 
     pub fn new_with_operations(
@@ -305,13 +348,15 @@ impl<D> ReadableSnapshot for WriteSnapshot<D> {
         key: StorageKeyReference<'_>,
     ) -> Result<Option<ByteArray<INLINE_BYTES>>, SnapshotGetError> {
         let writes = self.operations().writes_in(key.keyspace_id());
-        match writes.get(key.bytes()) {
+        match writes.get(key.bytes()).as_deref() {
             Some(Write::Insert { value, .. }) | Some(Write::Put { value, .. }) => Ok(Some(ByteArray::copy(value))),
             Some(Write::Delete) => Ok(None),
-            None => self
-                .storage
-                .get(self.iterator_pool(), key, self.open_sequence_number)
-                .map_err(|error| SnapshotGetError::MVCCRead { source: error }),
+            None => {
+                self.operations.record_read(key);
+                self.storage
+                    .get(self.iterator_pool(), key, self.open_sequence_number)
+                    .map_err(|error| SnapshotGetError::MVCCRead { source: error })
+            }
         }
     }
 
@@ -321,12 +366,14 @@ impl<D> ReadableSnapshot for WriteSnapshot<D> {
         key: StorageKeyReference<'_>,
     ) -> Result<Option<ByteArray<INLINE_BYTES>>, SnapshotGetError> {
         let writes = self.operations().writes_in(key.keyspace_id());
-        match writes.get(key.bytes()) {
+        match writes.get(key.bytes()).as_deref() {
             Some(Write::Insert { value, .. }) | Some(Write::Put { value, .. }) => Ok(Some(ByteArray::copy(value))),
-            Some(Write::Delete) | None => self
-                .storage
-                .get(self.iterator_pool(), key, self.open_sequence_number)
-                .map_err(|error| SnapshotGetError::MVCCRead { source: error }),
+            Some(Write::Delete) | None => {
+                self.operations.record_read(key);
+                self.storage
+                    .get(self.iterator_pool(), key, self.open_sequence_number)
+                    .map_err(|error| SnapshotGetError::MVCCRead { source: error })
+            }
         }
     }
 
@@ -335,6 +382,7 @@ impl<D> ReadableSnapshot for WriteSnapshot<D> {
             .operations
             .writes_in(range.start().get_value().keyspace_id())
             .iterate_range(range.clone().map(|k| k.as_bytes(), |fixed| fixed));
+        self.operations.record_read_range(range);
         let storage_iterator = self.storage.iterate_range(self.iterator_pool(), range, self.open_sequence_number);
         SnapshotRangeIterator::new(storage_iterator, Some(buffered_iterator))
     }
@@ -347,7 +395,7 @@ impl<D> ReadableSnapshot for WriteSnapshot<D> {
         buffered || (!buffered_only && self.iterate_range(range).next().is_some())
     }
 
-    fn get_write(&self, key: StorageKeyReference<'_>) -> Option<&Write> {
+    fn get_write(&self, key: StorageKeyReference<'_>) -> Option<Cow<'_, Write>> {
         self.operations().writes_in(key.keyspace_id()).get_write(key.bytes())
     }
 
@@ -367,6 +415,7 @@ impl<D> ReadableSnapshot for WriteSnapshot<D> {
     }
 
     fn iterate_storage_range<const PS: usize>(&self, range: &KeyRange<StorageKey<'_, PS>>) -> SnapshotRangeIterator {
+        self.operations.record_read_range(range);
         let mvcc_iterator = self.storage.iterate_range(self.iterator_pool(), range, self.open_sequence_number);
         SnapshotRangeIterator::new(mvcc_iterator, None)
     }
@@ -391,10 +440,11 @@ impl<D> WritableSnapshot for WriteSnapshot<D> {
 }
 
 impl<D: DurabilityClient> CommittableSnapshot<D> for WriteSnapshot<D> {
-    fn commit(self) -> Result<Option<SequenceNumber>, SnapshotError> {
+    fn commit(mut self) -> Result<Option<SequenceNumber>, SnapshotError> {
         if self.operations.is_writes_empty() && self.operations.locks_empty() {
             Ok(None)
         } else {
+            prepare_spilled_commit(&mut self.operations)?;
             match self.storage.clone().snapshot_commit(self) {
                 Ok(sequence_number) => Ok(Some(sequence_number)),
                 Err(error) => Err(SnapshotError::Commit { typedb_source: error }),
@@ -423,12 +473,11 @@ impl<D: fmt::Debug> fmt::Debug for SchemaSnapshot<D> {
 impl<D> SchemaSnapshot<D> {
     pub(crate) fn new(storage: Arc<MVCCStorage<D>>, open_sequence_number: SequenceNumber) -> Self {
         storage.isolation_manager.opened_for_read(open_sequence_number);
-        SchemaSnapshot {
-            storage,
-            operations: OperationsBuffer::new(),
-            open_sequence_number,
-            iterator_pool: IteratorPool::new(),
+        let mut operations = OperationsBuffer::new();
+        if let Some(spill_path) = storage.spill_path() {
+            operations.enable_spill(spill_path);
         }
+        SchemaSnapshot { storage, operations, open_sequence_number, iterator_pool: IteratorPool::new() }
     }
 
     pub fn new_with_operations(
@@ -451,7 +500,7 @@ impl<D> ReadableSnapshot for SchemaSnapshot<D> {
         key: StorageKeyReference<'_>,
     ) -> Result<Option<ByteArray<INLINE_BYTES>>, SnapshotGetError> {
         let writes = self.operations().writes_in(key.keyspace_id());
-        match writes.get(key.bytes()) {
+        match writes.get(key.bytes()).as_deref() {
             Some(Write::Insert { value, .. }) | Some(Write::Put { value, .. }) => Ok(Some(ByteArray::copy(value))),
             Some(Write::Delete) => Ok(None),
             None => self
@@ -467,7 +516,7 @@ impl<D> ReadableSnapshot for SchemaSnapshot<D> {
         key: StorageKeyReference<'_>,
     ) -> Result<Option<ByteArray<INLINE_BYTES>>, SnapshotGetError> {
         let writes = self.operations().writes_in(key.keyspace_id());
-        match writes.get(key.bytes()) {
+        match writes.get(key.bytes()).as_deref() {
             Some(Write::Insert { value, .. }) | Some(Write::Put { value, .. }) => Ok(Some(ByteArray::copy(value))),
             Some(Write::Delete) | None => self
                 .storage
@@ -493,7 +542,7 @@ impl<D> ReadableSnapshot for SchemaSnapshot<D> {
         buffered || (!buffered_only && self.iterate_range(range).next().is_some())
     }
 
-    fn get_write(&self, key: StorageKeyReference<'_>) -> Option<&Write> {
+    fn get_write(&self, key: StorageKeyReference<'_>) -> Option<Cow<'_, Write>> {
         self.operations().writes_in(key.keyspace_id()).get_write(key.bytes())
     }
 
@@ -538,10 +587,11 @@ impl<D> WritableSnapshot for SchemaSnapshot<D> {
 
 impl<D: DurabilityClient> CommittableSnapshot<D> for SchemaSnapshot<D> {
     // TODO: extract these two methods into separate trait
-    fn commit(self) -> Result<Option<SequenceNumber>, SnapshotError> {
+    fn commit(mut self) -> Result<Option<SequenceNumber>, SnapshotError> {
         if self.operations.is_writes_empty() && self.operations.locks_empty() {
             Ok(None)
         } else {
+            prepare_spilled_commit(&mut self.operations)?;
             match self.storage.clone().snapshot_commit(self) {
                 Ok(sequence_number) => Ok(Some(sequence_number)),
                 Err(error) => Err(SnapshotError::Commit { typedb_source: error }),
@@ -554,9 +604,61 @@ impl<D: DurabilityClient> CommittableSnapshot<D> for SchemaSnapshot<D> {
     }
 }
 
+impl<D: DurabilityClient> SchemaSnapshot<D> {
+    /// Whether the buffered writes have reached the size at which they are spilled to disk
+    pub fn is_write_buffer_full(&self) -> bool {
+        self.operations.size_bytes() >= self.operations.limits().spill_bytes
+    }
+
+    /// Commit by ingesting the writes into the keyspaces as sorted files, without logging them to the WAL or
+    /// validating them against concurrent commits. Only safe while no other snapshot can commit, and the writes are
+    /// only recoverable once the storage is checkpointed. The snapshot then reads from the ingested commit, so it sees
+    /// the ingested writes and buffers later ones for the next ingestion. Returns the ingested commit, whose
+    /// operations are needed by components that otherwise read commits from the WAL.
+    pub fn commit_ingest(&mut self) -> Result<Option<(SequenceNumber, CommitRecord)>, SnapshotError> {
+        if self.operations.is_writes_empty() {
+            return Ok(None);
+        }
+        prepare_spilled_commit(&mut self.operations)?;
+        let ingested = SchemaSnapshot {
+            operations: self.operations.take(),
+            open_sequence_number: self.open_sequence_number,
+            iterator_pool: IteratorPool::new(),
+            storage: self.storage.clone(),
+        };
+        let (sequence_number, commit_record) = self
+            .storage
+            .clone()
+            .snapshot_ingest(ingested)
+            .map_err(|error| SnapshotError::Commit { typedb_source: error })?;
+
+        self.storage.isolation_manager.opened_for_read(sequence_number);
+        self.storage.closed_snapshot_write(self.open_sequence_number);
+        self.open_sequence_number = sequence_number;
+        // pooled iterators read the keyspaces as they were before the ingestion
+        self.iterator_pool = IteratorPool::new();
+        Ok(Some((sequence_number, commit_record)))
+    }
+}
+
+/// Merge spilled writes back into memory, since the commit record and its isolation validation need every write.
+/// The memory this takes is bounded by the write buffer refusing writes beyond the configured limit.
+fn prepare_spilled_commit(operations: &mut OperationsBuffer) -> Result<(), SnapshotError> {
+    if let Some(error) = operations.write_error() {
+        return Err(error.clone());
+    }
+    operations.merge_spilled().map_err(|error| SnapshotError::WriteBufferSpill { source: Arc::new(error) })
+}
+
 typedb_error!(
     pub SnapshotError(component = "Snapshot error", prefix = "SST") {
         Commit(1, "Snapshot commit failed due to storage commit error.", (typedb_source : StorageCommitError )),
+        WriteBufferLimitExceeded(
+            2,
+            "Snapshot buffered {size} bytes of writes, which exceeds the transaction limit of {limit} bytes.",
+            size: u64, limit: u64
+        ),
+        WriteBufferSpill(3, "Failed to spill writes to disk, or to read them back.", ( source: Arc<io::Error> )),
     }
 );
 
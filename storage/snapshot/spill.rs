@@ -0,0 +1,172 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    collections::{BTreeMap, Bound},
+    fs,
+    fs::File,
+    io,
+    io::{BufWriter, Read, Seek, SeekFrom, Write as IOWrite},
+    mem::size_of,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    vec,
+};
+
+use bytes::byte_array::ByteArray;
+use resource::constants::snapshot::{BUFFER_KEY_INLINE, SPILLED_RUN_INDEX_INTERVAL};
+
+use crate::snapshot::write::Write;
+
+type RecordLength = u32;
+
+/// The writes of one keyspace buffer, spilled to a file in key order once the in-memory buffers grew too large.
+/// Every `SPILLED_RUN_INDEX_INTERVAL`th key is indexed in memory, splitting the file into blocks that are each read
+/// with a single read through the file handle the run keeps open.
+/// The file is deleted when the run is dropped.
+#[derive(Debug)]
+pub(crate) struct SpilledRun {
+    path: PathBuf,
+    file: Mutex<File>,
+    index: Vec<(ByteArray<BUFFER_KEY_INLINE>, u64)>,
+    size: u64,
+}
+
+impl SpilledRun {
+    pub(crate) fn write(path: PathBuf, writes: &BTreeMap<ByteArray<BUFFER_KEY_INLINE>, Write>) -> io::Result<Self> {
+        let mut index = Vec::with_capacity(writes.len() / SPILLED_RUN_INDEX_INTERVAL + 1);
+        let written = Self::write_records(&path, writes, &mut index);
+        match written {
+            Ok((file, size)) => Ok(Self { path, file: Mutex::new(file), index, size }),
+            Err(error) => {
+                let _ = fs::remove_file(&path);
+                Err(error)
+            }
+        }
+    }
+
+    fn write_records(
+        path: &PathBuf,
+        writes: &BTreeMap<ByteArray<BUFFER_KEY_INLINE>, Write>,
+        index: &mut Vec<(ByteArray<BUFFER_KEY_INLINE>, u64)>,
+    ) -> io::Result<(File, u64)> {
+        let file = File::options().read(true).write(true).create_new(true).open(path)?;
+        let mut writer = BufWriter::new(file);
+        let mut offset = 0;
+        for (position, (key, write)) in writes.iter().enumerate() {
+            if position % SPILLED_RUN_INDEX_INTERVAL == 0 {
+                index.push((key.clone(), offset));
+            }
+            let record =
+                bincode::serialize(&(key, write)).map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+            writer.write_all(&(record.len() as RecordLength).to_le_bytes())?;
+            writer.write_all(&record)?;
+            offset += (size_of::<RecordLength>() + record.len()) as u64;
+        }
+        let file = writer.into_inner().map_err(|error| error.into_error())?;
+        Ok((file, offset))
+    }
+
+    /// Bytes used on disk
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub(crate) fn get(&self, key: &[u8]) -> io::Result<Option<Write>> {
+        let block = self.index.partition_point(|(first_key, _)| &**first_key <= key);
+        if block == 0 {
+            return Ok(None);
+        }
+        let records = self.read_block(block - 1)?;
+        match records.binary_search_by(|(record_key, _)| (**record_key).cmp(key)) {
+            Ok(position) => Ok(Some(records.into_iter().nth(position).unwrap().1)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// A cursor over the records of the run in key order, starting from the first record within `start`
+    pub(crate) fn cursor(self: Arc<Self>, start: Bound<&[u8]>) -> io::Result<RunCursor> {
+        let block = match start {
+            Bound::Included(key) | Bound::Excluded(key) => {
+                self.index.partition_point(|(first_key, _)| &**first_key <= key).saturating_sub(1)
+            }
+            Bound::Unbounded => 0,
+        };
+        let mut cursor = RunCursor { run: self, next_block: block, records: Vec::new().into_iter(), head: None };
+        cursor.advance()?;
+        while let Some((key, _)) = &cursor.head {
+            let before_start = match start {
+                Bound::Included(start) => &**key < start,
+                Bound::Excluded(start) => &**key <= start,
+                Bound::Unbounded => false,
+            };
+            if !before_start {
+                break;
+            }
+            cursor.advance()?;
+        }
+        Ok(cursor)
+    }
+
+    fn read_block(&self, block: usize) -> io::Result<Vec<(ByteArray<BUFFER_KEY_INLINE>, Write)>> {
+        let start = self.index[block].1;
+        let end = self.index.get(block + 1).map(|(_, offset)| *offset).unwrap_or(self.size);
+        let mut bytes = vec![0; (end - start) as usize];
+        {
+            let mut file = self.file.lock().unwrap();
+            file.seek(SeekFrom::Start(start))?;
+            file.read_exact(&mut bytes)?;
+        }
+
+        let mut records = Vec::with_capacity(SPILLED_RUN_INDEX_INTERVAL);
+        let mut remaining = bytes.as_slice();
+        while !remaining.is_empty() {
+            let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "Spilled run record is truncated.");
+            let (length, rest) =
+                remaining.split_first_chunk::<{ size_of::<RecordLength>() }>().ok_or_else(truncated)?;
+            let length = RecordLength::from_le_bytes(*length) as usize;
+            if rest.len() < length {
+                return Err(truncated());
+            }
+            let (record, rest) = rest.split_at(length);
+            records
+                .push(bincode::deserialize(record).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?);
+            remaining = rest;
+        }
+        Ok(records)
+    }
+}
+
+impl Drop for SpilledRun {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Reads a run one block at a time, so iterating it only holds one block of records in memory
+pub(crate) struct RunCursor {
+    run: Arc<SpilledRun>,
+    next_block: usize,
+    records: vec::IntoIter<(ByteArray<BUFFER_KEY_INLINE>, Write)>,
+    head: Option<(ByteArray<BUFFER_KEY_INLINE>, Write)>,
+}
+
+impl RunCursor {
+    pub(crate) fn head(&self) -> Option<&(ByteArray<BUFFER_KEY_INLINE>, Write)> {
+        self.head.as_ref()
+    }
+
+    /// Answer the current record, and move to the next one
+    pub(crate) fn advance(&mut self) -> io::Result<Option<(ByteArray<BUFFER_KEY_INLINE>, Write)>> {
+        let mut next = self.records.next();
+        while next.is_none() && self.next_block < self.run.index.len() {
+            self.records = self.run.read_block(self.next_block)?.into_iter();
+            self.next_block += 1;
+            next = self.records.next();
+        }
+        Ok(std::mem::replace(&mut self.head, next))
+    }
+}
@@ -24,12 +24,18 @@ use iterator::MVCCReadError;
 use keyspace::KeyspaceDeleteError;
 use lending_iterator::LendingIterator;
 use logger::{error, result::ResultExt};
-use resource::constants::{snapshot::BUFFER_VALUE_INLINE, storage::WATERMARK_WAIT_INTERVAL_MICROSECONDS};
+use resource::{
+    constants::{snapshot::BUFFER_VALUE_INLINE, storage::WATERMARK_WAIT_INTERVAL_MICROSECONDS},
+    perf_counters::{
+        ISOLATION_CONFLICT_DELETING_REQUIRED_KEY, ISOLATION_CONFLICT_EXCLUSIVE_LOCK,
+        ISOLATION_CONFLICT_READ_SET_WRITTEN, ISOLATION_CONFLICT_REQUIRE_DELETED_KEY,
+    },
+};
 
 use crate::{
     durability_client::{DurabilityClient, DurabilityClientError},
     error::{MVCCStorageError, MVCCStorageErrorKind},
-    isolation_manager::{CommitRecord, IsolationManager, StatusRecord, ValidatedCommit},
+    isolation_manager::{CommitRecord, CommitType, IsolationManager, StatusRecord, ValidatedCommit},
     iterator::MVCCRangeIterator,
     key_range::KeyRange,
     key_value::{StorageKey, StorageKeyReference},
@@ -42,7 +48,10 @@ use crate::{
         commit_recovery::{apply_recovered, load_commit_data_from, StorageRecoveryError},
     },
     sequence_number::SequenceNumber,
-    snapshot::{write::Write, CommittableSnapshot, ReadSnapshot, SchemaSnapshot, WriteSnapshot},
+    snapshot::{
+        buffer::OperationsBuffer, write::Write, CommittableSnapshot, ReadSnapshot, SchemaSnapshot, WriteSnapshot,
+    },
+    write_batches::SortedWrites,
 };
 
 pub mod durability_client;
@@ -60,7 +69,7 @@ mod write_batches;
 #[derive(Debug)]
 pub struct MVCCStorage<Durability> {
     name: Arc<String>,
-    path: PathBuf,
+    path: Option<PathBuf>,
     keyspaces: Keyspaces,
     durability_client: Durability,
     isolation_manager: IsolationManager,
@@ -68,6 +77,8 @@ pub struct MVCCStorage<Durability> {
 
 impl<Durability> MVCCStorage<Durability> {
     pub const STORAGE_DIR_NAME: &'static str = "storage";
+    const INGEST_DIR_NAME: &'static str = "ingest";
+    const SPILL_DIR_NAME: &'static str = "spill";
 
     pub fn create<KS: KeyspaceSet>(
         name: impl AsRef<str>,
@@ -92,7 +103,30 @@ impl<Durability> MVCCStorage<Durability> {
         let isolation_manager = IsolationManager::new(durability_client.current());
         Ok(Self {
             name: Arc::new(name.as_ref().to_owned()),
-            path: storage_dir,
+            path: Some(storage_dir),
+            durability_client,
+            keyspaces,
+            isolation_manager,
+        })
+    }
+
+    /// Create a storage whose keyspaces only live in memory. Nothing is written to disk, and large transactions are
+    /// never spilled, so the storage is lost when dropped.
+    pub fn create_in_memory<KS: KeyspaceSet>(
+        name: impl AsRef<str>,
+        mut durability_client: Durability,
+    ) -> Result<Self, StorageOpenError>
+    where
+        Durability: DurabilityClient,
+    {
+        Self::register_durability_record_types(&mut durability_client);
+        let keyspaces = Keyspaces::open_in_memory::<KS>()
+            .map_err(|err| StorageOpenError::KeyspaceOpen { name: name.as_ref().to_owned(), source: err })?;
+
+        let isolation_manager = IsolationManager::new(durability_client.current());
+        Ok(Self {
+            name: Arc::new(name.as_ref().to_owned()),
+            path: None,
             durability_client,
             keyspaces,
             isolation_manager,
@@ -142,8 +176,17 @@ impl<Durability> MVCCStorage<Durability> {
                 .map_err(|error| RecoverFromCheckpoint { name: name.to_owned(), typedb_source: error })?,
         };
 
+        // writes spilled by transactions that never committed
+        let _ = fs::remove_dir_all(storage_dir.join(Self::SPILL_DIR_NAME));
+
         let isolation_manager = IsolationManager::new(next_sequence_number);
-        Ok(Self { name: Arc::new(name.to_owned()), path: storage_dir, durability_client, keyspaces, isolation_manager })
+        Ok(Self {
+            name: Arc::new(name.to_owned()),
+            path: Some(storage_dir),
+            durability_client,
+            keyspaces,
+            isolation_manager,
+        })
     }
 
     fn register_durability_record_types(durability_client: &mut impl DurabilityClient) {
@@ -155,8 +198,13 @@ impl<Durability> MVCCStorage<Durability> {
         self.name.clone()
     }
 
-    pub fn path(&self) -> &PathBuf {
-        &self.path
+    /// The storage directory, unless the storage is in memory
+    pub fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+    pub(crate) fn spill_path(&self) -> Option<PathBuf> {
+        self.path.as_ref().map(|path| path.join(Self::SPILL_DIR_NAME))
     }
 
     pub fn durability(&self) -> &Durability {
@@ -250,6 +298,14 @@ impl<Durability> MVCCStorage<Durability> {
                 Ok(commit_sequence_number)
             }
             Ok(ValidatedCommit::Conflict(conflict)) => {
+                match conflict {
+                    IsolationConflict::DeletingRequiredKey { .. } => {
+                        ISOLATION_CONFLICT_DELETING_REQUIRED_KEY.increment()
+                    }
+                    IsolationConflict::RequireDeletedKey { .. } => ISOLATION_CONFLICT_REQUIRE_DELETED_KEY.increment(),
+                    IsolationConflict::ExclusiveLock { .. } => ISOLATION_CONFLICT_EXCLUSIVE_LOCK.increment(),
+                    IsolationConflict::ReadSetWritten { .. } => ISOLATION_CONFLICT_READ_SET_WRITTEN.increment(),
+                }
                 sync_notifier.recv().unwrap();
                 Self::persist_commit_status(false, commit_sequence_number, &self.durability_client)
                     .map_err(|error| Durability { name: self.name.clone(), typedb_source: error })?;
@@ -262,6 +318,56 @@ impl<Durability> MVCCStorage<Durability> {
         }
     }
 
+    fn snapshot_ingest(
+        &self,
+        snapshot: SchemaSnapshot<Durability>,
+    ) -> Result<(SequenceNumber, CommitRecord), StorageCommitError>
+    where
+        Durability: DurabilityClient,
+    {
+        use StorageCommitError::{Durability, Internal, Keyspace, MVCCRead, IO};
+
+        self.set_initial_put_status(&snapshot).map_err(|error| MVCCRead { name: self.name.clone(), source: error })?;
+        let commit_record = snapshot.into_commit_record();
+
+        // The WAL only records that a bulk load happened at this sequence number, not its operations.
+        // Recovery therefore relies on a checkpoint taken after the ingestion.
+        let marker_record =
+            CommitRecord::new(OperationsBuffer::new(), commit_record.open_sequence_number(), CommitType::BulkLoad);
+        let commit_sequence_number = self
+            .durability_client
+            .sequenced_write(&marker_record)
+            .map_err(|error| Durability { name: self.name.clone(), typedb_source: error })?;
+        self.durability_client.request_sync().recv().unwrap();
+
+        let ingest_dir = self.path.as_ref().map(|path| path.join(Self::INGEST_DIR_NAME));
+        let ingested = ingest_dir
+            .as_ref()
+            .map_or(Ok(()), fs::create_dir_all)
+            .map_err(|error| IO { name: self.name.clone(), source: Arc::new(error) })
+            .and_then(|_| {
+                let sorted_writes = SortedWrites::from_operations(commit_sequence_number, commit_record.operations());
+                self.keyspaces
+                    .ingest(&sorted_writes, ingest_dir.as_deref())
+                    .map_err(|error| Keyspace { name: self.name.clone(), source: Arc::new(error) })
+            });
+
+        if let Err(error) = ingested {
+            self.isolation_manager.load_aborted(commit_sequence_number);
+            Self::persist_commit_status(false, commit_sequence_number, &self.durability_client)
+                .map_err(|error| Durability { name: self.name.clone(), typedb_source: error })?;
+            return Err(error);
+        }
+
+        self.isolation_manager.load_validated(commit_sequence_number, marker_record);
+        self.isolation_manager
+            .applied(commit_sequence_number)
+            .map_err(|error| Internal { name: self.name.clone(), source: Arc::new(error) })?;
+        Self::persist_commit_status(true, commit_sequence_number, &self.durability_client)
+            .map_err(|error| Durability { name: self.name.clone(), typedb_source: error })?;
+        Ok((commit_sequence_number, commit_record))
+    }
+
     fn set_initial_put_status(&self, snapshot: &impl CommittableSnapshot<Durability>) -> Result<(), MVCCReadError>
     where
         Durability: DurabilityClient,
@@ -326,8 +432,8 @@ impl<Durability> MVCCStorage<Durability> {
             .delete_durability()
             .map_err(|err| DurabilityDelete { name: self.name.clone(), typedb_source: err })?;
 
-        if self.path.exists() {
-            std::fs::remove_dir_all(&self.path).map_err(|error| {
+        if let Some(path) = self.path.as_ref().filter(|path| path.exists()) {
+            std::fs::remove_dir_all(path).map_err(|error| {
                 error!("Failed to delete storage {}, received error: {}", self.name, error);
                 DirectoryDelete { name: self.name.clone(), source: Arc::new(error) }
             })?;
@@ -379,6 +485,13 @@ impl<Durability> MVCCStorage<Durability> {
         MVCCRangeIterator::new(self, iterpool, range, open_sequence_number)
     }
 
+    pub fn keyspaces_property_int_value(
+        &self,
+        property: &str,
+    ) -> Result<Vec<(&'static str, Option<u64>)>, KeyspaceError> {
+        self.keyspaces.iter().map(|keyspace| Ok((keyspace.name(), keyspace.property_int_value(property)?))).collect()
+    }
+
     pub fn snapshot_watermark(&self) -> SequenceNumber {
         self.isolation_manager.watermark()
     }
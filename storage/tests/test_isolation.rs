@@ -109,6 +109,32 @@ fn g0_update_conflicts_fail() {
     );
 }
 
+#[test]
+fn conflict_reports_key_and_predecessor() {
+    init_logging();
+    let storage_path = create_tmp_dir();
+    let storage = setup_storage(&storage_path);
+
+    let mut snapshot_1 = storage.clone().open_snapshot_write();
+    let mut snapshot_2 = storage.clone().open_snapshot_write();
+
+    let key_1 = StorageKey::Reference(StorageKeyReference::new(Keyspace, &KEY_1));
+    snapshot_1.get_required(key_1.clone()).unwrap();
+    snapshot_2.delete(key_1.clone().into_owned_array());
+    let predecessor = snapshot_2.commit().unwrap().unwrap();
+
+    let result = snapshot_1.commit();
+    let Err(SnapshotError::Commit { typedb_source: StorageCommitError::Isolation { conflict, .. }, .. }) = result
+    else {
+        panic!("{result:?}")
+    };
+    assert_eq!(conflict.predecessor(), predecessor);
+    match conflict {
+        IsolationConflict::RequireDeletedKey { key, .. } => assert_eq!(key.bytes(), &KEY_1),
+        conflict => panic!("{conflict:?}"),
+    }
+}
+
 #[ignore] // TODO: This currently fails because of the behaviour flagged in typedb#7033
 #[test]
 fn g0_dirty_writes() {
@@ -397,6 +423,76 @@ fn g2_predicate_anti_dependency_cycles() {
     fails_without_serializability!(result_1.is_err() || result_2.is_err());
 }
 
+#[test]
+fn serializable_write_skew_fails() {
+    // t1 reads x and y, sets x; t2 reads x and y, sets y. Under serializable isolation, t2 must fail.
+    init_logging();
+    let key_1 = StorageKeyArray::new(Keyspace, ByteArray::copy(&KEY_1));
+    let key_2 = StorageKeyArray::new(Keyspace, ByteArray::copy(&KEY_2));
+    let value_1 = ByteArray::inline([11], 1);
+    let storage_path = create_tmp_dir();
+    let storage = setup_storage(&storage_path);
+
+    let mut snapshot_1 = storage.clone().open_snapshot_write();
+    let mut snapshot_2 = storage.clone().open_snapshot_write();
+    snapshot_1.set_serializable();
+    snapshot_2.set_serializable();
+
+    for snapshot in [&snapshot_1, &snapshot_2] {
+        snapshot.get::<128>(StorageKeyReference::from(&key_1)).unwrap().unwrap();
+        snapshot.get::<128>(StorageKeyReference::from(&key_2)).unwrap().unwrap();
+    }
+    snapshot_1.put_val(key_1.to_owned(), ByteArray::copy(&value_1));
+    snapshot_2.put_val(key_2.to_owned(), ByteArray::copy(&value_1));
+
+    let predecessor = snapshot_1.commit().unwrap().unwrap();
+    let result = snapshot_2.commit();
+    let Err(SnapshotError::Commit { typedb_source: StorageCommitError::Isolation { conflict, .. }, .. }) = result
+    else {
+        panic!("{result:?}")
+    };
+    assert_eq!(conflict.predecessor(), predecessor);
+    match conflict {
+        IsolationConflict::ReadSetWritten { key, .. } => assert_eq!(key.bytes(), &KEY_1),
+        conflict => panic!("{conflict:?}"),
+    }
+}
+
+#[test]
+fn serializable_predicate_write_fails() {
+    // t1 and t2 both scan a range and insert into it. Under serializable isolation, t2 must fail.
+    init_logging();
+    let key_3 = StorageKeyArray::new(Keyspace, ByteArray::copy(&KEY_3));
+    let key_4 = StorageKeyArray::new(Keyspace, ByteArray::copy(&[0x0, 0x0, 0x0, 0x4]));
+    let key_prefix = StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, [0x0]));
+    let prefix = KeyRange::new_within(StorageKey::Array(key_prefix), false);
+    let value = ByteArray::inline([30], 1);
+    let storage_path = create_tmp_dir();
+    let storage = setup_storage(&storage_path);
+
+    let mut snapshot_1 = storage.clone().open_snapshot_write();
+    let mut snapshot_2 = storage.clone().open_snapshot_write();
+    snapshot_1.set_serializable();
+    snapshot_2.set_serializable();
+
+    assert_eq!(snapshot_1.iterate_range(&prefix).count(), snapshot_2.iterate_range(&prefix).count());
+    snapshot_1.put_val(key_3.to_owned(), ByteArray::copy(&value));
+    snapshot_2.put_val(key_4.to_owned(), ByteArray::copy(&value));
+
+    assert!(snapshot_1.commit().is_ok());
+    let result = snapshot_2.commit();
+    assert!(
+        matches!(
+            result,
+            Err(SnapshotError::Commit {
+                typedb_source: StorageCommitError::Isolation { conflict: IsolationConflict::ReadSetWritten { .. }, .. },
+                ..
+            })
+        ),
+        "{result:?}"
+    );
+}
+
 #[test]
 fn g2_antidependency_cycles_fekete() {
     // From "A Read-Only Transaction Anomaly Under Snapshot Isolation" (Fekete et al.)
@@ -641,7 +737,10 @@ fn isolation_manager_reads_evicted_from_disk() {
             matches!(
                 snapshot_conflicts_result,
                 Err(SnapshotError::Commit {
-                    typedb_source: StorageCommitError::Isolation { conflict: IsolationConflict::RequireDeletedKey, .. },
+                    typedb_source: StorageCommitError::Isolation {
+                        conflict: IsolationConflict::RequireDeletedKey { .. },
+                        ..
+                    },
                     ..
                 })
             ),
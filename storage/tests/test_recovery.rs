@@ -104,7 +104,7 @@ fn no_wal_and_checkpoint_illegal() {
         snapshot.put(key_world.clone());
         snapshot.commit().unwrap();
 
-        (checkpoint_storage(&storage), storage.path().parent().unwrap().to_owned())
+        (checkpoint_storage(&storage), storage.path().unwrap().parent().unwrap().to_owned())
     };
 
     // delete wal
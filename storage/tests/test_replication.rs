@@ -0,0 +1,120 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use durability::wal::WAL;
+use resource::constants::snapshot::BUFFER_KEY_INLINE;
+use storage::{
+    durability_client::{DurabilityClient, WALClient},
+    key_value::{StorageKeyArray, StorageKeyReference},
+    recovery::replication::{ReplicationApplier, StorageReplicationError},
+    sequence_number::SequenceNumber,
+    snapshot::{CommittableSnapshot, ReadableSnapshot, WritableSnapshot},
+    MVCCStorage,
+};
+use test_utils::{create_tmp_dir, init_logging};
+use test_utils_storage::{create_storage, load_storage, test_keyspace_set};
+
+fn ship(
+    primary: &MVCCStorage<WALClient>,
+    follower: &MVCCStorage<WALClient>,
+    applier: &mut ReplicationApplier,
+    start: Option<SequenceNumber>,
+) -> Result<(), StorageReplicationError> {
+    let start = start.unwrap_or_else(|| applier.resume_from(follower));
+    for record in primary.durability().iter_from(start).unwrap() {
+        applier.apply(follower, record.unwrap())?;
+    }
+    Ok(())
+}
+
+#[test]
+fn follower_applies_primary_commits() {
+    test_keyspace_set! { Keyspace => 0: "keyspace" }
+
+    init_logging();
+    let key_hello = StorageKeyArray::<BUFFER_KEY_INLINE>::from((TestKeyspaceSet::Keyspace, b"hello"));
+    let key_world = StorageKeyArray::<BUFFER_KEY_INLINE>::from((TestKeyspaceSet::Keyspace, b"world"));
+
+    let primary_path = create_tmp_dir();
+    let follower_path = create_tmp_dir();
+    let primary = create_storage::<TestKeyspaceSet>(&primary_path).unwrap();
+    let follower = create_storage::<TestKeyspaceSet>(&follower_path).unwrap();
+    let mut applier = ReplicationApplier::new();
+
+    let mut snapshot = primary.clone().open_snapshot_write();
+    snapshot.put(key_hello.clone());
+    snapshot.commit().unwrap();
+
+    ship(&primary, &follower, &mut applier, None).unwrap();
+    assert_eq!(primary.snapshot_watermark(), follower.snapshot_watermark());
+    assert_eq!(applier.pending_commits(), 0);
+
+    let mut snapshot = primary.clone().open_snapshot_write();
+    snapshot.put(key_world.clone());
+    snapshot.commit().unwrap();
+
+    // resuming re-ships the last commit already held by the follower, which must be skipped
+    ship(&primary, &follower, &mut applier, None).unwrap();
+    assert_eq!(primary.snapshot_watermark(), follower.snapshot_watermark());
+    assert_eq!(primary.durability().current(), follower.durability().current());
+
+    let snapshot = follower.clone().open_snapshot_read();
+    assert!(snapshot.get_mapped(StorageKeyReference::from(&key_hello), |_| true).unwrap().is_some());
+    assert!(snapshot.get_mapped(StorageKeyReference::from(&key_world), |_| true).unwrap().is_some());
+}
+
+#[test]
+fn follower_recovers_from_own_wal() {
+    test_keyspace_set! { Keyspace => 0: "keyspace" }
+
+    init_logging();
+    let key_hello = StorageKeyArray::<BUFFER_KEY_INLINE>::from((TestKeyspaceSet::Keyspace, b"hello"));
+
+    let primary_path = create_tmp_dir();
+    let follower_path = create_tmp_dir();
+    let primary = create_storage::<TestKeyspaceSet>(&primary_path).unwrap();
+    let watermark = {
+        let follower = create_storage::<TestKeyspaceSet>(&follower_path).unwrap();
+        let mut applier = ReplicationApplier::new();
+
+        let mut snapshot = primary.clone().open_snapshot_write();
+        snapshot.put(key_hello.clone());
+        snapshot.commit().unwrap();
+
+        ship(&primary, &follower, &mut applier, None).unwrap();
+        follower.snapshot_watermark()
+    };
+
+    let follower = load_storage::<TestKeyspaceSet>(&follower_path, WAL::load(&follower_path).unwrap(), None).unwrap();
+    assert_eq!(watermark, follower.snapshot_watermark());
+    let snapshot = follower.clone().open_snapshot_read();
+    assert!(snapshot.get_mapped(StorageKeyReference::from(&key_hello), |_| true).unwrap().is_some());
+}
+
+#[test]
+fn follower_rejects_gap() {
+    test_keyspace_set! { Keyspace => 0: "keyspace" }
+
+    init_logging();
+    let key_hello = StorageKeyArray::<BUFFER_KEY_INLINE>::from((TestKeyspaceSet::Keyspace, b"hello"));
+    let key_world = StorageKeyArray::<BUFFER_KEY_INLINE>::from((TestKeyspaceSet::Keyspace, b"world"));
+
+    let primary_path = create_tmp_dir();
+    let follower_path = create_tmp_dir();
+    let primary = create_storage::<TestKeyspaceSet>(&primary_path).unwrap();
+    let follower = create_storage::<TestKeyspaceSet>(&follower_path).unwrap();
+    let mut applier = ReplicationApplier::new();
+
+    let mut snapshot = primary.clone().open_snapshot_write();
+    snapshot.put(key_hello.clone());
+    let first_commit = snapshot.commit().unwrap().unwrap();
+    let mut snapshot = primary.clone().open_snapshot_write();
+    snapshot.put(key_world.clone());
+    snapshot.commit().unwrap();
+
+    let result = ship(&primary, &follower, &mut applier, Some(first_commit.next()));
+    assert!(matches!(result, Err(StorageReplicationError::SequenceGap { .. })), "{result:?}");
+}
@@ -13,10 +13,10 @@ use resource::constants::snapshot::{BUFFER_KEY_INLINE, BUFFER_VALUE_INLINE};
 use storage::{
     key_range::KeyRange,
     key_value::{StorageKey, StorageKeyArray},
-    snapshot::{CommittableSnapshot, ReadableSnapshot, WritableSnapshot},
+    snapshot::{buffer::WriteBufferLimits, CommittableSnapshot, ReadableSnapshot, SnapshotError, WritableSnapshot},
 };
 use test_utils::{create_tmp_dir, init_logging};
-use test_utils_storage::{create_storage, test_keyspace_set};
+use test_utils_storage::{create_in_memory_storage, create_storage, test_keyspace_set};
 
 use self::TestKeyspaceSet::Keyspace;
 
@@ -152,6 +152,41 @@ fn snapshot_read_through() {
     snapshot.close_resources();
 }
 
+#[test]
+fn in_memory_snapshot_read_through() {
+    init_logging();
+    let storage = create_in_memory_storage::<TestKeyspaceSet>().unwrap();
+    assert!(storage.path().is_none());
+
+    let key_1 = StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, [0x0, 0x0, 0x1]));
+    let key_2 = StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, [0x1, 0x0, 0x10]));
+    let key_3 = StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, [0x1, 0x0, 0xff]));
+    let key_4 = StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, [0x2, 0x0, 0xff]));
+    let value_1 = ByteArray::copy(&[0, 0, 0, 1]);
+
+    let mut snapshot = storage.clone().open_snapshot_write();
+    snapshot.put_val(key_1.clone(), value_1.clone());
+    snapshot.put(key_2.clone());
+    snapshot.put(key_3.clone());
+    snapshot.put(key_4.clone());
+    snapshot.commit().unwrap_or_log();
+
+    let mut snapshot = storage.clone().open_snapshot_write();
+    assert_eq!(snapshot.get(StorageKey::Array(key_1).as_reference()).unwrap(), Some(value_1));
+    snapshot.delete(key_3.clone());
+    snapshot.commit().unwrap_or_log();
+
+    let snapshot = storage.open_snapshot_read();
+    let key_prefix = StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, [0x1]));
+    let key_values: Vec<(StorageKeyArray<BUFFER_KEY_INLINE>, ByteArray<BUFFER_VALUE_INLINE>)> = snapshot
+        .iterate_range(&KeyRange::new_within(StorageKey::Array(key_prefix), false))
+        .collect_cloned_vec(|k, v| (StorageKeyArray::from(k), ByteArray::from(v)))
+        .unwrap();
+    assert_eq!(key_values, vec![(key_2, ByteArray::empty())]);
+    assert!(snapshot.get::<48>(StorageKey::Array(key_3).as_reference()).unwrap().is_none());
+    snapshot.close_resources();
+}
+
 #[test]
 fn snapshot_read_buffered_delete_of_persisted_key() {
     init_logging();
@@ -218,3 +253,133 @@ fn snapshot_delete_reinserted() {
     assert_eq!(snapshot_2.get::<BUFFER_KEY_INLINE>(StorageKey::Array(key_1).as_reference()).unwrap(), None);
     snapshot_2.close_resources();
 }
+
+#[test]
+fn snapshot_spilled_writes_read_and_commit() {
+    init_logging();
+    let storage_path = create_tmp_dir();
+    let storage = create_storage::<TestKeyspaceSet>(&storage_path).unwrap();
+    let key = |i: u16| StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, i.to_be_bytes()));
+
+    let mut snapshot = storage.clone().open_snapshot_write();
+    snapshot.set_write_buffer_limits(WriteBufferLimits { spill_bytes: 1024, limit_bytes: u64::MAX });
+    for i in 0..1000 {
+        snapshot.put_val(key(i), ByteArray::copy(&i.to_be_bytes()));
+    }
+    snapshot.delete(key(10));
+    snapshot.unput_val(key(20), ByteArray::copy(&20u16.to_be_bytes()));
+    snapshot.put_val(key(30), ByteArray::copy(&[0xff]));
+
+    assert_eq!(snapshot.get::<48>(StorageKey::Array(key(5)).as_reference()).unwrap(), Some(ByteArray::copy(&[0, 5])));
+    assert_eq!(snapshot.get::<48>(StorageKey::Array(key(10)).as_reference()).unwrap(), None);
+    assert_eq!(snapshot.get::<48>(StorageKey::Array(key(20)).as_reference()).unwrap(), None);
+    assert_eq!(snapshot.get::<48>(StorageKey::Array(key(30)).as_reference()).unwrap(), Some(ByteArray::copy(&[0xff])));
+
+    let key_prefix = StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, [0x0]));
+    let items: Vec<StorageKeyArray<BUFFER_KEY_INLINE>> = snapshot
+        .iterate_range(&KeyRange::new_within(StorageKey::Array(key_prefix), false))
+        .collect_cloned_vec(|k, _| StorageKeyArray::from(k))
+        .unwrap();
+    assert_eq!(items, (0..256).filter(|i| *i != 10 && *i != 20).map(key).collect::<Vec<_>>());
+    snapshot.commit().unwrap_or_log();
+
+    let snapshot = storage.clone().open_snapshot_read();
+    let count: usize = (0..4u8)
+        .map(|prefix| {
+            let key_prefix = StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, [prefix]));
+            snapshot
+                .iterate_range(&KeyRange::new_within(StorageKey::Array(key_prefix), false))
+                .collect_cloned_vec(|k, _| StorageKeyArray::<BUFFER_KEY_INLINE>::from(k))
+                .unwrap()
+                .len()
+        })
+        .sum();
+    assert_eq!(count, 998);
+    assert_eq!(snapshot.get::<48>(StorageKey::Array(key(30)).as_reference()).unwrap(), Some(ByteArray::copy(&[0xff])));
+    snapshot.close_resources();
+}
+
+#[test]
+fn snapshot_write_buffer_limit_fails_commit() {
+    init_logging();
+    let storage_path = create_tmp_dir();
+    let storage = create_storage::<TestKeyspaceSet>(&storage_path).unwrap();
+
+    let mut snapshot = storage.open_snapshot_write();
+    snapshot.set_write_buffer_limits(WriteBufferLimits { spill_bytes: 1024, limit_bytes: 4096 });
+    for i in 0..1000u16 {
+        snapshot.put(StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, i.to_be_bytes())));
+    }
+    let result = snapshot.check_write_buffer();
+    assert!(matches!(result, Err(SnapshotError::WriteBufferLimitExceeded { .. })), "{result:?}");
+    // the limit is enforced as the writes are made, so the buffer stops growing
+    let last_key = StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, 999u16.to_be_bytes()));
+    assert_eq!(snapshot.get::<48>(StorageKey::Array(last_key).as_reference()).unwrap(), None);
+    let result = snapshot.commit();
+    assert!(matches!(result, Err(SnapshotError::WriteBufferLimitExceeded { .. })), "{result:?}");
+}
+
+#[test]
+fn snapshot_failed_spill_refuses_writes() {
+    init_logging();
+    let storage_path = create_tmp_dir();
+    let storage = create_storage::<TestKeyspaceSet>(&storage_path).unwrap();
+    // a file in place of the spill directory makes every spill fail
+    std::fs::write(storage.path().unwrap().join("spill"), []).unwrap();
+
+    let mut snapshot = storage.open_snapshot_write();
+    snapshot.set_write_buffer_limits(WriteBufferLimits { spill_bytes: 1024, limit_bytes: u64::MAX });
+    for i in 0..1000u16 {
+        snapshot.put(StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, i.to_be_bytes())));
+    }
+    let result = snapshot.check_write_buffer();
+    assert!(matches!(result, Err(SnapshotError::WriteBufferSpill { .. })), "{result:?}");
+    let result = snapshot.commit();
+    assert!(matches!(result, Err(SnapshotError::WriteBufferSpill { .. })), "{result:?}");
+}
+
+#[test]
+fn snapshot_spilled_runs_merge_newest_writes() {
+    init_logging();
+    let storage_path = create_tmp_dir();
+    let storage = create_storage::<TestKeyspaceSet>(&storage_path).unwrap();
+    let key = |i: u16| StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, i.to_be_bytes()));
+    let value = |round: u8| ByteArray::<BUFFER_VALUE_INLINE>::copy(&[round]);
+
+    // each round rewrites every key, spilling several runs that all hold a version of each key
+    let mut snapshot = storage.clone().open_snapshot_write();
+    snapshot.set_write_buffer_limits(WriteBufferLimits { spill_bytes: 4096, limit_bytes: u64::MAX });
+    for round in 0..4 {
+        for i in 0..200 {
+            snapshot.put_val(key(i), value(round));
+        }
+    }
+    for i in (0..200).step_by(3) {
+        snapshot.delete(key(i));
+    }
+    snapshot.unput_val(key(100), value(3));
+    snapshot.put_val(key(101), value(9));
+
+    let expected: Vec<(StorageKeyArray<BUFFER_KEY_INLINE>, ByteArray<BUFFER_VALUE_INLINE>)> = (0..200)
+        .filter(|i| i % 3 != 0 && *i != 100)
+        .map(|i| (key(i), if i == 101 { value(9) } else { value(3) }))
+        .collect();
+    let key_prefix = StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, [0x0]));
+    let items: Vec<(StorageKeyArray<BUFFER_KEY_INLINE>, ByteArray<BUFFER_VALUE_INLINE>)> = snapshot
+        .iterate_range(&KeyRange::new_within(StorageKey::Array(key_prefix), false))
+        .collect_cloned_vec(|k, v| (StorageKeyArray::from(k), ByteArray::copy(v)))
+        .unwrap();
+    assert_eq!(items, expected);
+    assert!(snapshot.any_in_range(&KeyRange::new_within(StorageKey::Array(key(101)), false), true));
+    assert!(!snapshot.any_in_range(&KeyRange::new_within(StorageKey::Array(key(99)), false), true));
+    snapshot.commit().unwrap_or_log();
+
+    let snapshot = storage.clone().open_snapshot_read();
+    let key_prefix = StorageKeyArray::<BUFFER_KEY_INLINE>::from((Keyspace, [0x0]));
+    let items: Vec<(StorageKeyArray<BUFFER_KEY_INLINE>, ByteArray<BUFFER_VALUE_INLINE>)> = snapshot
+        .iterate_range(&KeyRange::new_within(StorageKey::Array(key_prefix), false))
+        .collect_cloned_vec(|k, v| (StorageKeyArray::from(k), ByteArray::copy(v)))
+        .unwrap();
+    assert_eq!(items, expected);
+    snapshot.close_resources();
+}
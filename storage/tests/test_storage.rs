@@ -15,10 +15,13 @@ use storage::{
     key_range::{KeyRange, RangeStart},
     key_value::{StorageKey, StorageKeyArray, StorageKeyReference},
     keyspace::{IteratorPool, KeyspaceOpenError, KeyspaceValidationError},
+    recovery::checkpoint::Checkpoint,
     StorageOpenError,
 };
 use test_utils::{create_tmp_dir, init_logging};
-use test_utils_storage::{checkpoint_storage, create_storage, load_storage, test_keyspace_set};
+use test_utils_storage::{
+    checkpoint_storage, create_in_memory_storage, create_storage, load_storage, test_keyspace_set,
+};
 
 #[test]
 fn create_delete() {
@@ -205,3 +208,77 @@ fn get_put_iterate() {
         ]
     );
 }
+
+#[test]
+fn in_memory_get_put_iterate() {
+    test_keyspace_set! { Keyspace => 0: "keyspace" }
+
+    init_logging();
+    let storage = create_in_memory_storage::<TestKeyspaceSet>().unwrap();
+
+    let keys = [[0x0, 0x0, 0x1], [0x1, 0x0, 0x10], [0x1, 0x0, 0xff], [0x2, 0x0, 0xff]]
+        .into_iter()
+        .map(|bytes| StorageKeyArray::<BUFFER_VALUE_INLINE>::from((TestKeyspaceSet::Keyspace, bytes)))
+        .collect_vec();
+    for key in &keys {
+        storage.put_raw(StorageKeyReference::from(key), &empty_value());
+    }
+
+    let value: Option<ByteArray<48>> = storage.get_raw_mapped(StorageKeyReference::from(&keys[1]), ByteArray::copy);
+    assert_eq!(value, Some(ByteArray::empty()));
+
+    let start = StorageKeyArray::<BUFFER_VALUE_INLINE>::from((TestKeyspaceSet::Keyspace, [0x1, 0x0, 0x10]));
+    let items = storage
+        .iterate_keyspace_range(
+            &IteratorPool::new(),
+            KeyRange::new_unbounded(RangeStart::ExcludeFirstWithPrefix(StorageKey::<BUFFER_VALUE_INLINE>::Reference(
+                StorageKeyReference::from(&start),
+            ))),
+        )
+        .map_static::<ByteArray<BUFFER_VALUE_INLINE>, _>(|res| ByteArray::copy(res.unwrap().0))
+        .collect::<Vec<_>>();
+    assert_eq!(items, keys[2..].iter().cloned().map(StorageKeyArray::into_byte_array).collect_vec());
+
+    let checkpoint = Checkpoint::new(&create_tmp_dir()).unwrap();
+    assert!(storage.checkpoint(&checkpoint).is_err());
+}
+
+#[test]
+fn in_memory_iterator_reads_a_snapshot_of_its_range() {
+    test_keyspace_set! { Keyspace => 0: "keyspace" }
+
+    init_logging();
+    let storage = create_in_memory_storage::<TestKeyspaceSet>().unwrap();
+    let key = |bytes: [u8; 3]| StorageKeyArray::<BUFFER_VALUE_INLINE>::from((TestKeyspaceSet::Keyspace, bytes));
+    for bytes in [[0x0, 0x0, 0x1], [0x1, 0x0, 0x10], [0x1, 0x0, 0xff], [0x1, 0xff, 0xff], [0x2, 0x0, 0xff]] {
+        storage.put_raw(StorageKeyReference::from(&key(bytes)), &empty_value());
+    }
+
+    let iterator_pool = IteratorPool::new();
+    let prefix = StorageKeyArray::<BUFFER_VALUE_INLINE>::from((TestKeyspaceSet::Keyspace, [0x1]));
+    let mut iterator = storage.iterate_keyspace_range(
+        &iterator_pool,
+        KeyRange::new_within(StorageKey::<BUFFER_VALUE_INLINE>::Reference(StorageKeyReference::from(&prefix)), false),
+    );
+    assert_eq!(iterator.next().unwrap().unwrap().0, &*key([0x1, 0x0, 0x10]).into_byte_array());
+
+    // writes made after the iterator was created are not seen, whether ahead of the iterator or not
+    storage.put_raw(StorageKeyReference::from(&key([0x1, 0x0, 0x20])), &empty_value());
+    storage.put_raw(StorageKeyReference::from(&key([0x1, 0x0, 0x01])), &empty_value());
+    let items = iterator
+        .map_static::<ByteArray<BUFFER_VALUE_INLINE>, _>(|res| ByteArray::copy(res.unwrap().0))
+        .collect::<Vec<_>>();
+    assert_eq!(items, [key([0x1, 0x0, 0xff]).into_byte_array(), key([0x1, 0xff, 0xff]).into_byte_array()]);
+
+    let items = storage
+        .iterate_keyspace_range(
+            &iterator_pool,
+            KeyRange::new_within(
+                StorageKey::<BUFFER_VALUE_INLINE>::Reference(StorageKeyReference::from(&prefix)),
+                false,
+            ),
+        )
+        .map_static::<ByteArray<BUFFER_VALUE_INLINE>, _>(|res| ByteArray::copy(res.unwrap().0))
+        .collect::<Vec<_>>();
+    assert_eq!(items.len(), 5);
+}
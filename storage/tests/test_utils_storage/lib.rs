@@ -8,8 +8,10 @@ use std::{path::Path, sync::Arc};
 
 use durability::wal::WAL;
 use storage::{
-    durability_client::WALClient, keyspace::KeyspaceSet, recovery::checkpoint::Checkpoint, MVCCStorage,
-    StorageOpenError,
+    durability_client::{NoDurabilityClient, WALClient},
+    keyspace::KeyspaceSet,
+    recovery::checkpoint::Checkpoint,
+    MVCCStorage, StorageOpenError,
 };
 
 pub mod mock_snapshot;
@@ -37,8 +39,13 @@ pub fn create_storage<KS: KeyspaceSet>(path: &Path) -> Result<Arc<MVCCStorage<WA
     Ok(Arc::new(storage))
 }
 
+pub fn create_in_memory_storage<KS: KeyspaceSet>() -> Result<Arc<MVCCStorage<NoDurabilityClient>>, StorageOpenError> {
+    let storage = MVCCStorage::create_in_memory::<KS>("storage", NoDurabilityClient::new())?;
+    Ok(Arc::new(storage))
+}
+
 pub fn checkpoint_storage(storage: &MVCCStorage<WALClient>) -> Checkpoint {
-    let checkpoint = Checkpoint::new(storage.path().parent().unwrap()).unwrap();
+    let checkpoint = Checkpoint::new(storage.path().unwrap().parent().unwrap()).unwrap();
     storage.checkpoint(&checkpoint).unwrap();
     checkpoint.finish().unwrap();
     checkpoint
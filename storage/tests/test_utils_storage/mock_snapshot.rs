@@ -4,7 +4,7 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use std::iter::empty;
+use std::{borrow::Cow, iter::empty};
 
 use bytes::byte_array::ByteArray;
 use storage::{
@@ -58,7 +58,7 @@ impl ReadableSnapshot for MockSnapshot {
         false
     }
 
-    fn get_write(&self, key: StorageKeyReference<'_>) -> Option<&Write> {
+    fn get_write(&self, key: StorageKeyReference<'_>) -> Option<Cow<'_, Write>> {
         None
     }
 
@@ -52,6 +52,45 @@ impl WriteBatches {
     }
 }
 
+/// The writes of one commit as MVCC key-value pairs per keyspace, in the strictly increasing key order required to
+/// ingest them as sorted files.
+pub(crate) struct SortedWrites<'a> {
+    writes: [Vec<(MVCCKey<'static>, &'a [u8])>; KEYSPACE_MAXIMUM_COUNT],
+}
+
+impl<'a> SortedWrites<'a> {
+    pub(crate) fn from_operations(seq: SequenceNumber, operations: &'a OperationsBuffer) -> Self {
+        let mut writes: [Vec<_>; KEYSPACE_MAXIMUM_COUNT] = std::array::from_fn(|_| Vec::new());
+        for (index, buffer) in operations.write_buffers().enumerate() {
+            let keyspace_writes = &mut writes[index];
+            for (key, write) in buffer.writes() {
+                match write {
+                    Write::Insert { value } => {
+                        keyspace_writes.push((MVCCKey::build(key, seq, StorageOperation::Insert), &**value))
+                    }
+                    Write::Put { value, reinsert, .. } => {
+                        if reinsert.load(Ordering::SeqCst) {
+                            keyspace_writes.push((MVCCKey::build(key, seq, StorageOperation::Insert), &**value))
+                        }
+                    }
+                    Write::Delete => keyspace_writes.push((MVCCKey::build(key, seq, StorageOperation::Delete), &[])),
+                }
+            }
+            // buffered keys are ordered, but a key followed by its MVCC suffix may sort after a longer key it prefixes
+            keyspace_writes.sort_unstable_by(|(key, _), (other_key, _)| key.bytes().cmp(other_key.bytes()));
+        }
+        Self { writes }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (usize, &[(MVCCKey<'static>, &'a [u8])])> {
+        self.writes
+            .iter()
+            .enumerate()
+            .filter(|(_, writes)| !writes.is_empty())
+            .map(|(index, writes)| (index, &**writes))
+    }
+}
+
 impl IntoIterator for WriteBatches {
     type Item = (usize, WriteBatch);
     type IntoIter = iter::FilterMap<
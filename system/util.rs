@@ -122,6 +122,7 @@ pub mod query_util {
         ExecutionInterrupt,
     };
     use function::function_manager::FunctionManager;
+    use options::QueryOptions;
     use query::query_manager::QueryManager;
     use storage::{durability_client::WALClient, snapshot::WriteSnapshot};
     use typeql::query::Pipeline;
@@ -141,6 +142,7 @@ pub mod query_util {
                 tx.thing_manager.clone(),
                 &tx.function_manager,
                 pipeline,
+                QueryOptions::default(),
             )
             .unwrap();
 
@@ -171,7 +173,14 @@ pub mod query_util {
         Arc<WriteSnapshot<WALClient>>,
     ) {
         let prepared_pipeline = query_manager
-            .prepare_write_pipeline(snapshot, type_manager, thing_manager, function_manager, pipeline)
+            .prepare_write_pipeline(
+                snapshot,
+                type_manager,
+                thing_manager,
+                function_manager,
+                pipeline,
+                QueryOptions::default(),
+            )
             .unwrap();
 
         let named_outputs = prepared_pipeline.rows_positions().unwrap().clone();
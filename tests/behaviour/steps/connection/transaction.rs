@@ -131,7 +131,9 @@ pub async fn transaction_commits(context: &mut Context, may_error: params::MayEr
                     DataCommitError::ConceptWriteErrorsFirst { typedb_source } => {
                         may_error.check_concept_write_without_read_errors::<()>(&Err(typedb_source));
                     }
-                    DataCommitError::SnapshotInUse { .. } | DataCommitError::SnapshotError { .. } => {
+                    DataCommitError::SnapshotInUse { .. }
+                    | DataCommitError::SnapshotError { .. }
+                    | DataCommitError::IsolationConflict { .. } => {
                         panic!("Unexpected write commit error: {:?}", error);
                     }
                 }
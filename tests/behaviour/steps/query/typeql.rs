@@ -25,6 +25,7 @@ use executor::{
 use itertools::Itertools;
 use lending_iterator::LendingIterator;
 use macro_rules_attribute::apply;
+use options::QueryOptions;
 use query::error::QueryError;
 use test_utils::assert_matches;
 
@@ -63,6 +64,7 @@ fn execute_read_query(context: &Context, query: typeql::Query) -> Result<QueryAn
             tx.thing_manager.clone(),
             &tx.function_manager,
             &query.into_pipeline(),
+            QueryOptions::default(),
         )?;
         if pipeline.has_fetch() {
             match pipeline.into_documents_iterator(ExecutionInterrupt::new_uninterruptible()) {
@@ -113,6 +115,7 @@ fn execute_write_query(
             thing_manager.clone(),
             &function_manager,
             &query.into_pipeline(),
+            QueryOptions::default(),
         );
 
         match pipeline_result {
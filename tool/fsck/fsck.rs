@@ -0,0 +1,74 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+#![deny(unused_must_use)]
+#![deny(elided_lifetimes_in_paths)]
+
+use std::{path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+use database::Database;
+use storage::durability_client::WALClient;
+
+/// Check a database directory for inconsistencies between its data and the indexes derived from it.
+///
+/// The database must not be open in a running server. Opening the database recovers its write-ahead log as server
+/// startup does, but no data is changed unless `--repair` is given.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct FsckArgs {
+    /// Database directory to check, such as `server/data/<database>`
+    database: PathBuf,
+
+    /// Rewrite derivable index entries found to be missing, wrong or stale
+    #[arg(long, default_value_t = false)]
+    repair: bool,
+}
+
+fn main() -> ExitCode {
+    let args = FsckArgs::parse();
+
+    let database = match Database::<WALClient>::open(&args.database) {
+        Ok(database) => database,
+        Err(error) => {
+            eprintln!("Could not open database at '{}': {error:?}", args.database.display());
+            return ExitCode::from(2);
+        }
+    };
+    let report = match database.check_integrity(args.repair) {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!("Integrity check failed: {error:?}");
+            return ExitCode::from(2);
+        }
+    };
+
+    for problem in &report.problems {
+        println!("{problem}");
+    }
+    if !report.statistics_checked {
+        println!("Statistics were not checked, since they are not as of the latest commit.");
+    }
+    if report.is_consistent() {
+        println!("Database '{}' is consistent.", database.name());
+        return ExitCode::SUCCESS;
+    }
+    let repairable = report.repairable_count();
+    if args.repair {
+        println!(
+            "Found {} problems in database '{}', of which {repairable} were repaired.",
+            report.problems.len(),
+            database.name()
+        );
+    } else {
+        println!(
+            "Found {} problems in database '{}', of which {repairable} can be repaired with --repair.",
+            report.problems.len(),
+            database.name()
+        );
+    }
+    ExitCode::FAILURE
+}
@@ -0,0 +1,107 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+#![deny(unused_must_use)]
+#![deny(elided_lifetimes_in_paths)]
+
+use std::{path::PathBuf, process::ExitCode, sync::Arc};
+
+use clap::Parser;
+use database::Database;
+use import::{ImportOptions, Importer, Mapping};
+use resource::constants::import::{DEFAULT_IMPORT_BATCH_SIZE, DEFAULT_IMPORT_WORKERS};
+use storage::durability_client::WALClient;
+
+/// Import CSV and newline-delimited JSON files into a database, as described by a JSON mapping file.
+///
+/// The database must not be open in a running server. Rows failing validation are rejected and reported, and the
+/// rest are committed. An interrupted import resumes where it stopped when given the same progress file.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct ImportArgs {
+    /// Database directory to import into, such as `server/data/<database>`
+    database: PathBuf,
+
+    /// Mapping of the source files to the types of the schema
+    #[arg(long)]
+    mapping: PathBuf,
+
+    /// Rows committed together in one write transaction
+    #[arg(long, default_value_t = DEFAULT_IMPORT_BATCH_SIZE)]
+    batch_size: usize,
+
+    /// Write transactions committing batches in parallel
+    #[arg(long, default_value_t = DEFAULT_IMPORT_WORKERS)]
+    workers: usize,
+
+    /// Ingest batches through a bulk-load transaction instead of committing write transactions. Faster for large loads,
+    /// but the database only recovers ingested batches from the checkpoint taken after each one.
+    #[arg(long)]
+    bulk_load: bool,
+
+    /// Record committed batches in this file, skipping those it already records
+    #[arg(long)]
+    progress: Option<PathBuf>,
+
+    /// Append rejected rows to this file as JSON lines, instead of printing them
+    #[arg(long)]
+    rejected: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let args = ImportArgs::parse();
+
+    let mapping = match Mapping::from_file(&args.mapping) {
+        Ok(mapping) => mapping,
+        Err(error) => {
+            eprintln!("{error:?}");
+            return ExitCode::from(2);
+        }
+    };
+    let database = match Database::<WALClient>::open(&args.database) {
+        Ok(database) => Arc::new(database),
+        Err(error) => {
+            eprintln!("Could not open database at '{}': {error:?}", args.database.display());
+            return ExitCode::from(2);
+        }
+    };
+
+    let options = ImportOptions {
+        batch_size: args.batch_size,
+        workers: args.workers,
+        bulk_load: args.bulk_load,
+        progress_file: args.progress,
+        rejected_file: args.rejected.clone(),
+        ..ImportOptions::default()
+    };
+    let report = match Importer::new(database.clone(), options).import(&mapping) {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!("Import failed: {error:?}");
+            return ExitCode::from(2);
+        }
+    };
+
+    if args.rejected.is_none() {
+        for row in &report.rejected {
+            println!("{}:{}: {:?}", row.file, row.line, row.error);
+        }
+    }
+    if report.skipped_rows > 0 {
+        println!("Skipped {} rows committed by an earlier import.", report.skipped_rows);
+    }
+    println!(
+        "Imported {} rows into database '{}', rejecting {}.",
+        report.imported_rows,
+        database.name(),
+        report.rejected.len()
+    );
+    if report.rejected.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,294 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+#![deny(unused_must_use)]
+#![deny(elided_lifetimes_in_paths)]
+
+use std::{ops::ControlFlow, path::PathBuf, process::ExitCode};
+
+use clap::{Parser, Subcommand};
+use concept::{inspect::InspectedKey, thing::statistics::Statistics};
+use database::Database;
+use durability::{
+    wal::{WALFileInfo, WALReader},
+    DurabilityRecordType, RawRecord,
+};
+use encoding::{
+    layout::prefix::{Prefix, PrefixID},
+    value::label::Label,
+    EncodingKeyspace,
+};
+use resource::constants::snapshot::BUFFER_KEY_INLINE;
+use storage::{
+    durability_client::{DurabilityRecord, WALClient},
+    isolation_manager::{CommitRecord, StatusRecord},
+    key_value::StorageKeyArray,
+    keyspace::KeyspaceSet,
+    snapshot::{lock::LockType, write::Write},
+};
+
+/// Inspect the write-ahead log and keyspaces of a database directory, decoding their records and keys.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct InspectArgs {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the files and records of the write-ahead log. The log is only read, so this may be used while the
+    /// database is open in a running server unless `--decode` is given.
+    Wal {
+        /// Database directory, such as `server/data/<database>`
+        database: PathBuf,
+
+        /// Print the operations of commit records and the contents of status and statistics records
+        #[arg(long, default_value_t = false)]
+        records: bool,
+
+        /// Describe the keys written by commit records in terms of the database's latest schema and data, rather than
+        /// by their bytes. The database must not be open in a running server.
+        #[arg(long, default_value_t = false)]
+        decode: bool,
+
+        /// Skip records before this sequence number
+        #[arg(long)]
+        from: Option<u64>,
+
+        /// Print at most this many records
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+
+    /// Dump the keys of the keyspaces as of the latest commit, decoded into vertices, edges, properties and indexes.
+    /// The database must not be open in a running server.
+    Keyspace {
+        /// Database directory, such as `server/data/<database>`
+        database: PathBuf,
+
+        /// Only dump keys with this prefix, such as `VertexEntity` or `EdgeHas`. May be given more than once.
+        #[arg(long = "prefix", value_parser = parse_prefix)]
+        prefixes: Vec<Prefix>,
+
+        /// Only dump keys about the type with this label, such as `person` or `friendship:friend`
+        #[arg(long = "type")]
+        type_label: Option<String>,
+
+        /// Print at most this many keys
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+}
+
+fn main() -> ExitCode {
+    let args = InspectArgs::parse();
+
+    let result = match args.command {
+        Command::Wal { database, records, decode, from, limit } => inspect_wal(database, records, decode, from, limit),
+        Command::Keyspace { database, prefixes, type_label, limit } => {
+            inspect_keyspaces(database, prefixes, type_label, limit)
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn inspect_wal(
+    directory: PathBuf,
+    print_records: bool,
+    decode: bool,
+    from: Option<u64>,
+    limit: Option<usize>,
+) -> Result<(), String> {
+    let reader = WALReader::open(&directory)
+        .map_err(|error| format!("Could not read WAL of '{}': {error:?}", directory.display()))?;
+    let database = if decode { Some(open_database(&directory)?) } else { None };
+
+    let mut remaining = limit.unwrap_or(usize::MAX);
+    let files: Vec<WALFileInfo> = reader.files().collect();
+    for (index, file) in files.iter().enumerate() {
+        let file_end = files.get(index + 1).map(|next| next.start.number());
+        if from.is_some_and(|from| file_end.is_some_and(|end| end <= from)) {
+            continue;
+        }
+        println!(
+            "file {} starting at sequence number {} ({} bytes)",
+            file.path.display(),
+            file.start.number(),
+            file.len
+        );
+        let records = reader
+            .file_records(index)
+            .map_err(|error| format!("Could not read WAL file '{}': {error:?}", file.path.display()))?;
+        for record in records {
+            if remaining == 0 {
+                return Ok(());
+            }
+            let record = record.map_err(|error| format!("Could not read WAL record: {error:?}"))?;
+            if from.is_some_and(|from| record.sequence_number.number() < from) {
+                continue;
+            }
+            remaining -= 1;
+            println!(
+                "  {} {} ({} bytes)",
+                record.sequence_number.number(),
+                record_name(record.record_type),
+                record.bytes.len()
+            );
+            if print_records {
+                print_record(&record, database.as_ref());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn record_name(record_type: DurabilityRecordType) -> String {
+    match record_type {
+        CommitRecord::RECORD_TYPE => CommitRecord::RECORD_NAME.to_owned(),
+        StatusRecord::RECORD_TYPE => StatusRecord::RECORD_NAME.to_owned(),
+        Statistics::RECORD_TYPE => Statistics::RECORD_NAME.to_owned(),
+        _ => format!("unknown_record_{record_type}"),
+    }
+}
+
+fn print_record(record: &RawRecord<'_>, database: Option<&Database<WALClient>>) {
+    let mut bytes: &[u8] = &record.bytes;
+    match record.record_type {
+        CommitRecord::RECORD_TYPE => match <CommitRecord as DurabilityRecord>::deserialise_from(&mut bytes) {
+            Ok(commit) => {
+                println!(
+                    "    {:?} commit opened at sequence number {}",
+                    commit.commit_type(),
+                    commit.open_sequence_number().number()
+                );
+                for (key, write) in commit.operations().iterate_writes() {
+                    let (operation, value) = match &write {
+                        Write::Insert { value } => ("insert", Some(&**value)),
+                        Write::Put { value, .. } => ("put", Some(&**value)),
+                        Write::Delete => ("delete", None),
+                    };
+                    println!("    {operation} {}", describe_key(database, &key, value.unwrap_or_default()));
+                }
+                for (lock, lock_type) in commit.operations().locks() {
+                    let lock_type = match lock_type {
+                        LockType::Unmodifiable => "unmodifiable",
+                        LockType::Exclusive => "exclusive",
+                    };
+                    println!("    {lock_type} lock {}", hex(lock));
+                }
+            }
+            Err(error) => println!("    could not decode commit record: {error}"),
+        },
+        StatusRecord::RECORD_TYPE => match <StatusRecord as DurabilityRecord>::deserialise_from(&mut bytes) {
+            Ok(status) => println!(
+                "    commit record {} was {}",
+                status.commit_record_sequence_number().number(),
+                if status.was_committed() { "committed" } else { "aborted" }
+            ),
+            Err(error) => println!("    could not decode status record: {error}"),
+        },
+        Statistics::RECORD_TYPE => match Statistics::deserialise_from(&mut bytes) {
+            Ok(statistics) => println!(
+                "    statistics as of sequence number {}: {} entities, {} relations, {} attributes, {} role players, \
+                {} has edges",
+                statistics.sequence_number.number(),
+                statistics.total_entity_count,
+                statistics.total_relation_count,
+                statistics.total_attribute_count,
+                statistics.total_role_count,
+                statistics.total_has_count
+            ),
+            Err(error) => println!("    could not decode statistics record: {error}"),
+        },
+        _ => println!("    {}", hex(&record.bytes)),
+    }
+}
+
+fn describe_key(
+    database: Option<&Database<WALClient>>,
+    key: &StorageKeyArray<BUFFER_KEY_INLINE>,
+    value: &[u8],
+) -> String {
+    let keyspace = keyspace_name(key.keyspace_id().0);
+    match database {
+        Some(database) => format!("[{keyspace}] {}", format_inspected(database.inspect_key(key, value))),
+        None => {
+            let prefix = key.bytes().first().and_then(|&byte| Prefix::try_from_prefix_id(PrefixID::new(byte)));
+            let prefix = prefix.map_or_else(|| "unknown prefix".to_owned(), |prefix| format!("{prefix:?}"));
+            if value.is_empty() {
+                format!("[{keyspace}] {prefix} {}", hex(key.bytes()))
+            } else {
+                format!("[{keyspace}] {prefix} {} = {}", hex(key.bytes()), hex(value))
+            }
+        }
+    }
+}
+
+fn inspect_keyspaces(
+    directory: PathBuf,
+    prefixes: Vec<Prefix>,
+    type_label: Option<String>,
+    limit: Option<usize>,
+) -> Result<(), String> {
+    let database = open_database(&directory)?;
+    let prefixes = if prefixes.is_empty() { Prefix::ALL.to_vec() } else { prefixes };
+    let type_label = type_label.map(|label| Label::parse_from(&label));
+
+    let mut remaining = limit.unwrap_or(usize::MAX);
+    database
+        .inspect_keyspaces(&prefixes, type_label.as_ref(), |keyspace, inspected| {
+            if remaining == 0 {
+                return ControlFlow::Break(());
+            }
+            remaining -= 1;
+            println!("[{}] {}", keyspace.name(), format_inspected(inspected));
+            ControlFlow::Continue(())
+        })
+        .map_err(|error| format!("Could not inspect keyspaces: {error:?}"))
+}
+
+fn open_database(directory: &PathBuf) -> Result<Database<WALClient>, String> {
+    // opening a missing database would create it
+    if !directory.is_dir() {
+        return Err(format!("No database found at '{}'", directory.display()));
+    }
+    Database::<WALClient>::open(directory)
+        .map_err(|error| format!("Could not open database at '{}': {error:?}", directory.display()))
+}
+
+fn format_inspected(inspected: InspectedKey) -> String {
+    match inspected.value {
+        Some(value) => format!("{} = {value}", inspected.description),
+        None => inspected.description,
+    }
+}
+
+fn keyspace_name(keyspace_id: u8) -> String {
+    EncodingKeyspace::iter()
+        .find(|keyspace| keyspace.id().0 == keyspace_id)
+        .map_or_else(|| format!("keyspace {keyspace_id}"), |keyspace| keyspace.name().to_owned())
+}
+
+fn parse_prefix(name: &str) -> Result<Prefix, String> {
+    Prefix::ALL.iter().find(|prefix| format!("{prefix:?}") == name).copied().ok_or_else(|| {
+        let names: Vec<String> = Prefix::ALL.iter().map(|prefix| format!("{prefix:?}")).collect();
+        format!("unknown prefix '{name}', expected one of: {}", names.join(", "))
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut string = String::with_capacity(2 + bytes.len() * 2);
+    string.push_str("0x");
+    bytes.iter().for_each(|byte| string.push_str(&format!("{byte:02x}")));
+    string
+}
@@ -28,4 +28,8 @@ impl PermissionManager {
     pub fn exec_user_delete_allowed(accessor: &str, subject: &str) -> bool {
         accessor == DEFAULT_USER_NAME || accessor == subject
     }
+
+    pub fn exec_transactions_manage_permitted(accessor: &str) -> bool {
+        accessor == DEFAULT_USER_NAME
+    }
 }